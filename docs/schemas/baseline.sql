@@ -0,0 +1,211 @@
+-- Baseline schema: core.*, webhook_events and google_ads_leads as of migration 018
+--
+-- Migrations 001-008 are placeholders (applied to production before the
+-- migrations folder existed) and 009-018 migrate data out of the legacy
+-- core.entity_* tables, so migrations/ alone cannot build the schema the
+-- code queries. This file is that schema after migration 018.
+--
+-- It is a RECONSTRUCTION, not a dump: it was written by hand from the
+-- production schema report (docs/database/DATABASE_SCHEMA_REPORT_FINAL.md)
+-- and the webhook design (docs/integrations/WEBHOOK_IMPLEMENTATION.md). The
+-- .sqlx metadata is only as right as this file, so a difference from
+-- production (a column type, a NOT NULL) is not caught at build time. Check
+-- it against production (`pg_dump --schema-only -t 'core.*' -t webhook_events
+-- -t google_ads_leads`) before relying on it, and whenever one of these
+-- tables is changed outside migrations/.
+--
+-- Load it into an empty database, then apply migrations 019 onwards:
+-- scripts/sqlx-prepare.sh does both and regenerates the .sqlx metadata.
+
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+CREATE SCHEMA IF NOT EXISTS core;
+
+-- ============ Parties ============
+
+CREATE TABLE core.parties (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    party_type TEXT NOT NULL,
+    cpf_cnpj TEXT,
+    full_name TEXT NOT NULL,
+    normalized_name TEXT,
+    sex CHAR(1),
+    birth_date DATE,
+    mother_name TEXT,
+    father_name TEXT,
+    rg TEXT,
+    fantasy_name TEXT,
+    normalized_fantasy_name TEXT,
+    opening_date DATE,
+    registration_status_date DATE,
+    company_type TEXT,
+    company_size TEXT,
+    enriched BOOLEAN DEFAULT false,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE INDEX idx_parties_cpf_cnpj ON core.parties (cpf_cnpj) WHERE cpf_cnpj IS NOT NULL;
+CREATE INDEX idx_parties_normalized_name ON core.parties (normalized_name);
+CREATE INDEX idx_parties_party_type ON core.parties (party_type);
+
+CREATE TABLE core.people (
+    party_id UUID PRIMARY KEY REFERENCES core.parties(id),
+    full_name TEXT,
+    mothers_name TEXT,
+    birth_date DATE,
+    sex TEXT,
+    marital_status TEXT,
+    document_cpf TEXT,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE core.companies (
+    party_id UUID PRIMARY KEY REFERENCES core.parties(id),
+    legal_name TEXT,
+    trade_name TEXT,
+    cnpj TEXT,
+    company_size TEXT,
+    industry TEXT,
+    foundation_date DATE,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now()
+);
+
+-- ============ Contacts ============
+
+CREATE TYPE core.contact_type_enum AS ENUM ('email', 'phone', 'whatsapp');
+
+CREATE TABLE core.party_contacts (
+    contact_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    party_id UUID NOT NULL REFERENCES core.parties(id),
+    contact_type core.contact_type_enum NOT NULL,
+    value TEXT NOT NULL,
+    is_primary BOOLEAN DEFAULT false,
+    is_verified BOOLEAN DEFAULT false,
+    is_whatsapp BOOLEAN DEFAULT false,
+    source TEXT,
+    confidence NUMERIC(3,2) CHECK (confidence >= 0 AND confidence <= 1),
+    valid_from TIMESTAMPTZ DEFAULT now(),
+    valid_to TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now(),
+    CONSTRAINT uq_party_contact_unique UNIQUE (party_id, contact_type, value)
+);
+
+CREATE INDEX idx_party_contacts_party ON core.party_contacts (party_id);
+CREATE INDEX idx_party_contacts_value ON core.party_contacts (value);
+
+-- ============ Addresses (party_addresses: migration 009) ============
+
+CREATE TABLE core.addresses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    street TEXT,
+    number TEXT,
+    complement TEXT,
+    neighborhood TEXT,
+    city TEXT,
+    state TEXT,
+    zip_code TEXT,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    formatted_address TEXT,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE core.party_addresses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    party_id UUID NOT NULL REFERENCES core.parties(id) ON DELETE CASCADE,
+    address_id UUID NOT NULL REFERENCES core.addresses(id) ON DELETE CASCADE,
+    address_type TEXT CHECK (address_type IN ('residential', 'commercial', 'billing', 'family_member', 'other')),
+    is_primary BOOLEAN DEFAULT false,
+    is_current BOOLEAN DEFAULT true,
+    verified BOOLEAN DEFAULT false,
+    confidence_score NUMERIC(3,2) DEFAULT 0.80 CHECK (confidence_score >= 0 AND confidence_score <= 1),
+    metadata JSONB DEFAULT '{}',
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_party_addresses_party ON core.party_addresses (party_id);
+CREATE INDEX idx_party_addresses_address ON core.party_addresses (address_id);
+CREATE UNIQUE INDEX idx_party_addresses_primary ON core.party_addresses (party_id) WHERE is_primary = true;
+CREATE UNIQUE INDEX uq_party_address_link ON core.party_addresses (party_id, address_id);
+
+-- ============ Enrichment snapshots (one per party) ============
+
+CREATE TABLE core.party_enrichments (
+    enrichment_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    party_id UUID NOT NULL UNIQUE REFERENCES core.parties(id),
+    provider TEXT,
+    raw_payload JSONB,
+    normalized_data JSONB,
+    quality_score NUMERIC(3,2),
+    enriched_at TIMESTAMPTZ DEFAULT now(),
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+
+-- ============ Relationships and transactions (party ids: migration 011) ============
+
+CREATE TABLE core.party_relationships (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_party_id UUID NOT NULL,
+    target_party_id UUID NOT NULL,
+    relationship_type TEXT NOT NULL,
+    confidence NUMERIC(3,2) CHECK (confidence >= 0 AND confidence <= 1),
+    start_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    end_date DATE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_party_relationships_source ON core.party_relationships (source_party_id);
+CREATE INDEX idx_party_relationships_target ON core.party_relationships (target_party_id);
+
+CREATE TABLE core.property_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    buyer_party_id UUID REFERENCES core.parties(id) ON DELETE SET NULL,
+    seller_party_id UUID REFERENCES core.parties(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+
+-- ============ Inbound leads (migrations 002 and 003) ============
+
+CREATE TABLE webhook_events (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    hook_action TEXT,
+    payload_raw JSONB NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    processed_at TIMESTAMPTZ,
+    status TEXT NOT NULL DEFAULT 'received',
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at_ts TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX ux_webhook_events_lead_updated ON webhook_events (lead_id, updated_at);
+CREATE INDEX ix_webhook_events_status ON webhook_events (status) WHERE status IN ('received', 'processing');
+
+CREATE TABLE google_ads_leads (
+    id BIGSERIAL PRIMARY KEY,
+    google_lead_id TEXT NOT NULL UNIQUE,
+    c2s_lead_id TEXT NOT NULL,
+    form_id BIGINT,
+    campaign_id BIGINT,
+    gcl_id TEXT,
+    payload_raw JSONB NOT NULL,
+    enrichment_status TEXT,
+    cpf VARCHAR(11),
+    description_length INTEGER,
+    c2s_latency_ms INTEGER,
+    c2s_created_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMIT;
@@ -0,0 +1,24 @@
+-- Migration 019: WhatsApp inbound lead tracking
+-- Date: 2025-11-24
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS whatsapp_leads (
+    id BIGSERIAL PRIMARY KEY,
+    wa_message_id TEXT NOT NULL UNIQUE,
+    wa_id TEXT NOT NULL,
+    profile_name TEXT,
+    phone_number_id TEXT,
+    message_type TEXT NOT NULL,
+    message_text TEXT,
+    phone_normalized TEXT,
+    c2s_lead_id TEXT,
+    enrichment_status TEXT NOT NULL,
+    c2s_latency_ms INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_whatsapp_leads_wa_id_created
+    ON whatsapp_leads (wa_id, created_at DESC);
+
+COMMIT;
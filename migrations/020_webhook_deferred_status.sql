@@ -0,0 +1,14 @@
+-- Migration 020: Deferred webhook events (degraded mode)
+-- Date: 2025-11-25
+--
+-- Webhook enrichments are marked 'deferred' (instead of 'failed') while the
+-- Work API or Diretrix circuit breaker is open, and re-queued once providers
+-- recover. Partial index keeps the retry scan cheap.
+
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS ix_webhook_events_deferred
+    ON webhook_events (received_at)
+    WHERE status = 'deferred';
+
+COMMIT;
@@ -0,0 +1,57 @@
+-- Migration 021: Supporting indexes for customer lookups
+-- Date: 2025-11-26
+--
+-- CustomerService looks parties up by CPF, contact value and name. Without
+-- these indexes the contact and name lookups scan core.party_contacts and
+-- core.parties (1.5M+ rows).
+--
+-- normalized_name is rewritten to the same canonical form produced by
+-- services::normalize_name (uppercase, unaccented, single-spaced) so that
+-- find_by_name can search it through the trigram index.
+--
+-- Plain CREATE INDEX (not CONCURRENTLY) because migrations run inside a
+-- transaction; run during a low-traffic window.
+
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Contact lookups: WHERE value = $1 AND contact_type = ...
+CREATE INDEX IF NOT EXISTS idx_party_contacts_value_type
+    ON core.party_contacts (value, contact_type);
+
+-- One party per CPF/CNPJ (find_by_cpf, storage upsert). Fails with a clear
+-- message if historical duplicates still exist; merge those first.
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM core.parties
+        WHERE cpf_cnpj IS NOT NULL
+        GROUP BY cpf_cnpj
+        HAVING COUNT(*) > 1
+    ) THEN
+        RAISE EXCEPTION 'core.parties has duplicate cpf_cnpj values; merge them before applying migration 021';
+    END IF;
+END $$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_parties_cpf_cnpj
+    ON core.parties (cpf_cnpj)
+    WHERE cpf_cnpj IS NOT NULL;
+
+-- Canonical names (keep in sync with services::normalize_name)
+UPDATE core.parties
+SET normalized_name = regexp_replace(
+        btrim(upper(translate(
+            full_name,
+            'áàâãäéèêëíìîïóòôõöúùûüçñÁÀÂÃÄÉÈÊËÍÌÎÏÓÒÔÕÖÚÙÛÜÇÑ',
+            'AAAAAEEEEIIIIOOOOOUUUUCNAAAAAEEEEIIIIOOOOOUUUUCN'
+        ))),
+        '\s+', ' ', 'g'
+    )
+WHERE full_name IS NOT NULL;
+
+-- Substring name search: WHERE normalized_name LIKE '%...%'
+CREATE INDEX IF NOT EXISTS idx_parties_normalized_name_trgm
+    ON core.parties USING gin (normalized_name gin_trgm_ops);
+
+COMMIT;
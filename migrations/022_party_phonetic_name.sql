@@ -0,0 +1,18 @@
+-- Migration 022: Phonetic name keys
+-- Date: 2025-11-26
+--
+-- Sound-alike key of full_name (phonetic::phonetic_key), so name lookups can
+-- fall back to variants like "Luis"/"Luís"/"Luiz". Computed in Rust: new and
+-- updated parties get it from EnrichmentStorage, existing rows are filled by
+-- `cargo run --bin backfill_phonetic_names`.
+
+BEGIN;
+
+ALTER TABLE core.parties
+    ADD COLUMN IF NOT EXISTS phonetic_name TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_parties_phonetic_name
+    ON core.parties (phonetic_name)
+    WHERE phonetic_name IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,17 @@
+-- Migration 023: Company CNAE
+-- Date: 2025-11-27
+--
+-- CNPJ documents are now stored as 'company' parties with a core.companies
+-- row (EnrichmentStorage::store_enriched_company). `industry` keeps the CNAE
+-- description; the code itself gets its own column so it can be filtered on.
+
+BEGIN;
+
+ALTER TABLE core.companies
+    ADD COLUMN IF NOT EXISTS cnae TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_companies_cnae
+    ON core.companies (cnae)
+    WHERE cnae IS NOT NULL;
+
+COMMIT;
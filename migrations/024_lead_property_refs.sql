@@ -0,0 +1,21 @@
+-- Migration 024: Lead property references
+-- Date: 2025-11-27
+--
+-- Listing codes mentioned by a C2S lead, from `product.prop_ref` or free text
+-- (descriptions, messages). Filled by property_refs::store_property_refs.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS lead_property_refs (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    ref_code TEXT NOT NULL,
+    source TEXT NOT NULL CHECK (source IN ('prop_ref', 'text')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (lead_id, ref_code)
+);
+
+CREATE INDEX IF NOT EXISTS idx_lead_property_refs_ref_code
+    ON lead_property_refs (ref_code);
+
+COMMIT;
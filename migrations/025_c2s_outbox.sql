@@ -0,0 +1,35 @@
+-- Migration 025: C2S message outbox
+-- Date: 2025-11-28
+--
+-- Enrichment messages that cannot be sent right away (quiet hours) are queued
+-- here with `send_after` and delivered by the outbox dispatcher. `created_at`
+-- vs `send_after` / `sent_at` shows how long a message was held, and
+-- `delay_reason` why. Partial index keeps the dispatcher scan cheap.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    tenant TEXT,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sending', 'sent', 'failed')),
+    send_after TIMESTAMPTZ NOT NULL,
+    delay_reason TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- Set when the dispatcher claims the row ('sending')
+    locked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS ix_c2s_outbox_pending
+    ON c2s_outbox (send_after)
+    WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS ix_c2s_outbox_lead_id
+    ON c2s_outbox (lead_id);
+
+COMMIT;
@@ -0,0 +1,18 @@
+-- Migration 026: Last enrichment message per C2S lead
+-- Date: 2025-11-28
+--
+-- Body and SHA-256 hash of the last enrichment message sent (or queued) for
+-- each lead. A re-enrichment shortly after (duplicate webhook with a new
+-- updated_at) is coalesced into an "updated data" message with only the
+-- changed lines, see lead_messages::coalesce.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_last_messages (
+    lead_id TEXT PRIMARY KEY,
+    body_hash TEXT NOT NULL,
+    body TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,15 @@
+-- Migration 027: Hash of the last message sent to each C2S lead
+-- Date: 2025-11-28
+--
+-- Any message byte-identical to the last one delivered to the same lead is
+-- skipped (lead_messages::send_deduplicated), whatever triggered it.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_message_hashes (
+    lead_id TEXT PRIMARY KEY,
+    body_hash TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,26 @@
+-- Migration 028: Sent-message log
+-- Date: 2025-11-28
+--
+-- One row per message to a C2S lead: delivered, rejected by C2S, or skipped
+-- as a duplicate. Keeps the body hash (not the body) to prove what was sent
+-- and when, and what triggered it (enrichment workflow, outbox, endpoints).
+-- Exposed per lead at GET /api/v1/admin/leads/:lead_id/messages.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS sent_messages (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    body_hash TEXT NOT NULL,
+    body_length INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('sent', 'failed', 'skipped')),
+    status_code INTEGER,
+    error_message TEXT,
+    triggered_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_sent_messages_lead_id
+    ON sent_messages (lead_id, created_at DESC);
+
+COMMIT;
@@ -0,0 +1,18 @@
+-- Migration 029: C2S message templates
+-- Date: 2025-11-28
+--
+-- Editable texts of the C2S messages (dossier headers, follow-up reminder,
+-- update header). A row overrides the built-in default of the same name;
+-- `version` is bumped on every edit so workers reload it within seconds.
+-- Managed through GET/PUT /api/v1/admin/templates.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS message_templates (
+    name TEXT PRIMARY KEY,
+    body TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
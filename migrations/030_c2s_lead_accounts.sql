@@ -0,0 +1,16 @@
+-- Migration 030: C2S account of each lead
+-- Date: 2025-11-28
+--
+-- Leads created in (or received from) an additional C2S account configured
+-- in C2S_ACCOUNTS. Messages to these leads must go through the same account;
+-- leads without a row belong to the default account (C2S_BASE_URL).
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_lead_accounts (
+    lead_id TEXT PRIMARY KEY,
+    account TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,28 @@
+-- Migration 031: Shadow provider comparisons
+-- Date: 2025-11-28
+--
+-- For a sample of leads (SHADOW_SAMPLE_RATE) the candidate provider at
+-- SHADOW_PROVIDER_URL is called alongside Work API. Each row holds the fields
+-- where both providers disagree; shadow data never reaches sellers.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS shadow_comparisons (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    cpf TEXT NOT NULL,
+    -- Shadow call failed (error set, nothing compared)
+    shadow_error TEXT,
+    primary_ms INTEGER NOT NULL,
+    shadow_ms INTEGER NOT NULL,
+    fields_compared INTEGER NOT NULL DEFAULT 0,
+    -- [{"field": ..., "primary": ..., "shadow": ...}]
+    mismatches JSONB NOT NULL DEFAULT '[]'::jsonb,
+    shadow_payload JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_shadow_comparisons_created_at
+    ON shadow_comparisons (created_at DESC);
+
+COMMIT;
@@ -0,0 +1,24 @@
+-- Migration 032: Debug payload capture
+-- Date: 2025-11-28
+--
+-- Full request/response of each enrichment workflow step for sampled leads
+-- (DEBUG_SAMPLE_RATE) and allowlisted CPFs (DEBUG_CPF_ALLOWLIST). Rows hold
+-- provider payloads with personal data and are purged after expires_at.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS debug_payloads (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    cpf TEXT,
+    step TEXT NOT NULL,
+    request JSONB NOT NULL,
+    response JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_debug_payloads_lead_id ON debug_payloads (lead_id);
+CREATE INDEX IF NOT EXISTS idx_debug_payloads_expires_at ON debug_payloads (expires_at);
+
+COMMIT;
@@ -0,0 +1,22 @@
+-- Migration 033: Consents
+-- Date: 2025-11-28
+--
+-- Consent per subject (CPF, email or E.164 phone) and purpose, captured from
+-- lead forms or the admin API. Tenants in CONSENT_REQUIRED_TENANTS are only
+-- enriched when an active 'enrichment' consent exists for the lead.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS consents (
+    id BIGSERIAL PRIMARY KEY,
+    subject_type TEXT NOT NULL CHECK (subject_type IN ('cpf', 'email', 'phone')),
+    subject TEXT NOT NULL,
+    purpose TEXT NOT NULL,
+    source TEXT,
+    granted_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (subject_type, subject, purpose)
+);
+
+COMMIT;
@@ -0,0 +1,16 @@
+-- Migration 034: Anonymization of stale leads
+-- Date: 2025-11-28
+--
+-- Marks rows whose personal identifiers were replaced by salted hashes
+-- (ANONYMIZE_AFTER_DAYS). Hashes are longer than a CPF, so the Google Ads
+-- cpf column is widened to TEXT.
+
+BEGIN;
+
+ALTER TABLE webhook_events ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ;
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ;
+ALTER TABLE whatsapp_leads ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ;
+
+ALTER TABLE google_ads_leads ALTER COLUMN cpf TYPE TEXT;
+
+COMMIT;
@@ -0,0 +1,14 @@
+-- Migration 035: Webhook payload pruning
+-- Date: 2025-11-28
+--
+-- Raw payloads past WEBHOOK_PAYLOAD_RETENTION_DAYS are removed from
+-- webhook_events; the row keeps its metadata, when it was pruned and, if the
+-- payload was archived to object storage first, the archive key.
+
+BEGIN;
+
+ALTER TABLE webhook_events ALTER COLUMN payload_raw DROP NOT NULL;
+ALTER TABLE webhook_events ADD COLUMN IF NOT EXISTS payload_archive_key TEXT;
+ALTER TABLE webhook_events ADD COLUMN IF NOT EXISTS payload_pruned_at TIMESTAMPTZ;
+
+COMMIT;
@@ -0,0 +1,21 @@
+-- Migration 036: Google Ads form configuration
+-- Date: 2025-11-28
+--
+-- Per lead form settings read by the Google Ads webhook: field mapping
+-- (custom question → standard column id), target seller, C2S product and
+-- a description header template. Forms without a row use the defaults.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS google_ads_forms (
+    form_id BIGINT PRIMARY KEY,
+    name TEXT,
+    field_mapping JSONB NOT NULL DEFAULT '{}'::jsonb,
+    seller_id TEXT,
+    product TEXT,
+    description_template TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
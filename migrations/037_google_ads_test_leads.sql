@@ -0,0 +1,12 @@
+-- Migration 037: Google Ads test leads
+-- Date: 2025-11-28
+--
+-- Test deliveries (is_test=true, sent from the Google Ads UI) are stored for
+-- traceability but never create a C2S lead.
+
+BEGIN;
+
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS is_test BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE google_ads_leads ALTER COLUMN c2s_lead_id DROP NOT NULL;
+
+COMMIT;
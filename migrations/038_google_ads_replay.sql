@@ -0,0 +1,15 @@
+-- Migration 038: Google Ads lead replay
+-- Date: 2025-11-28
+--
+-- Admin replays re-run a stored lead from payload_raw. replay_started_at
+-- guards against concurrent replays; the replaced C2S lead is kept in
+-- previous_c2s_lead_id so it can be cleaned up in C2S.
+
+BEGIN;
+
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS replay_started_at TIMESTAMPTZ;
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS replayed_at TIMESTAMPTZ;
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS replay_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE google_ads_leads ADD COLUMN IF NOT EXISTS previous_c2s_lead_id TEXT;
+
+COMMIT;
@@ -0,0 +1,33 @@
+-- Migration 039: Deferred enrichment of leads without CPF
+-- Date: 2025-11-28
+--
+-- Leads for which Diretrix finds no CPF are queued here instead of being
+-- dropped, and retried with a growing delay (contact data often becomes
+-- resolvable days later). Contact data is cleared once the row reaches a
+-- final state ('enriched' or 'expired').
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS pending_enrichments (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL UNIQUE,
+    tenant TEXT,
+    customer_name TEXT,
+    phone TEXT,
+    email TEXT,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'resolving', 'enriched', 'expired')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    last_error TEXT,
+    -- Set when the retry job claims the row ('resolving')
+    locked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    enriched_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS ix_pending_enrichments_due
+    ON pending_enrichments (next_attempt_at)
+    WHERE status = 'pending';
+
+COMMIT;
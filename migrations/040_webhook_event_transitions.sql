@@ -0,0 +1,51 @@
+-- Migration 040: Webhook event status history
+-- Date: 2025-11-28
+--
+-- `webhook_events.status` only holds the latest state of an enrichment job.
+-- A trigger records every status change here (received → processing →
+-- completed / failed / deferred, and deferred → received on retry), so the
+-- lead timeline (`GET /api/v1/leads/:lead_id/timeline`) can show how a job
+-- progressed.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS webhook_event_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    lead_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    -- NULL for the insert ('received')
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    error_message TEXT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_webhook_event_transitions_lead_id
+    ON webhook_event_transitions (lead_id, changed_at);
+
+CREATE OR REPLACE FUNCTION record_webhook_event_transition()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO webhook_event_transitions
+            (lead_id, updated_at, from_status, to_status, error_message)
+        VALUES (
+            NEW.lead_id,
+            NEW.updated_at,
+            CASE WHEN TG_OP = 'UPDATE' THEN OLD.status END,
+            NEW.status,
+            NEW.error_message
+        );
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_webhook_event_transitions ON webhook_events;
+
+CREATE TRIGGER trg_webhook_event_transitions
+    AFTER INSERT OR UPDATE OF status ON webhook_events
+    FOR EACH ROW
+    EXECUTE FUNCTION record_webhook_event_transition();
+
+COMMIT;
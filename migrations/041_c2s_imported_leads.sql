@@ -0,0 +1,22 @@
+-- Migration 041: Contacts of leads created by batch imports
+-- Date: 2025-11-28
+--
+-- `POST /api/v1/c2s/leads/batch` creates C2S leads from imported lists.
+-- Each created lead records one row per contact (phone and/or email), keyed
+-- by the SHA-256 of the normalized contact, so re-importing the same list or
+-- overlapping lists does not create the same lead twice. Only hashes are
+-- stored, no contact data.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_imported_leads (
+    contact_hash TEXT PRIMARY KEY,
+    c2s_lead_id TEXT NOT NULL,
+    account TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_c2s_imported_leads_lead_id
+    ON c2s_imported_leads (c2s_lead_id);
+
+COMMIT;
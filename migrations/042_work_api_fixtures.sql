@@ -0,0 +1,18 @@
+-- Migration 042: Work API fixture recorder
+-- Date: 2025-11-28
+--
+-- Anonymized Work API responses of a sample of CPFs
+-- (WORK_API_FIXTURE_SAMPLE_RATE), one per distinct payload shape, used to
+-- grow the parser test corpus. Payloads hold no personal data: strings are
+-- masked and the CPF replaced before insertion.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS work_api_fixtures (
+    id BIGSERIAL PRIMARY KEY,
+    shape_hash TEXT NOT NULL UNIQUE,
+    payload JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
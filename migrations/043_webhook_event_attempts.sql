@@ -0,0 +1,19 @@
+-- Migration 043: Webhook event attempts and poisoned events
+-- Date: 2025-11-28
+--
+-- Every enrichment run of a webhook event increments `attempts`. An event
+-- whose runs keep failing in a retryable way (deferred while a provider is
+-- down, or a crashed enrichment job) is marked 'poisoned' once it reaches
+-- WEBHOOK_MAX_ATTEMPTS: it is no longer retried, and `error_message` keeps
+-- the error chain of its last run.
+
+BEGIN;
+
+ALTER TABLE webhook_events
+    ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS ix_webhook_events_poisoned
+    ON webhook_events (received_at)
+    WHERE status = 'poisoned';
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- Migration 044: Persistent job queue
+-- Date: 2025-11-28
+--
+-- Background work that must survive a restart (webhook enrichments) is
+-- stored here instead of an in-process channel. Workers of every instance
+-- claim due 'queued' jobs with FOR UPDATE SKIP LOCKED; a job left 'running'
+-- by a crashed worker is claimed again once its lock is stale. Failed runs
+-- are retried after `run_after` until `max_attempts`, then marked 'failed'.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    -- Set when a worker claims the job ('running')
+    locked_at TIMESTAMPTZ,
+    locked_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS ix_jobs_due
+    ON jobs (kind, run_after)
+    WHERE status = 'queued';
+
+CREATE INDEX IF NOT EXISTS ix_jobs_running
+    ON jobs (kind, locked_at)
+    WHERE status = 'running';
+
+COMMIT;
@@ -0,0 +1,13 @@
+-- Migration 045: Job progress and result
+-- Date: 2025-11-28
+--
+-- Enrichments requested with `?async=true` run as jobs polled through
+-- GET /api/v1/jobs/:id: the worker records the step it reached in
+-- `progress` and the enrichment response in `result`.
+
+BEGIN;
+
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS progress TEXT;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS result JSONB;
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- Migration 046: Contact and address history
+-- Date: 2025-11-28
+--
+-- Enrichments now record when a party's contact or address was first seen
+-- (valid_from), last returned by a provider (last_confirmed_at) and dropped
+-- from the provider's answer (valid_to). Existing rows are backfilled from
+-- their creation / update timestamps.
+
+BEGIN;
+
+ALTER TABLE core.party_contacts ADD COLUMN IF NOT EXISTS last_confirmed_at TIMESTAMPTZ;
+
+UPDATE core.party_contacts
+SET valid_from = COALESCE(valid_from, created_at),
+    last_confirmed_at = COALESCE(updated_at, created_at)
+WHERE last_confirmed_at IS NULL;
+
+ALTER TABLE core.party_contacts ALTER COLUMN valid_from SET DEFAULT now();
+ALTER TABLE core.party_contacts ALTER COLUMN last_confirmed_at SET DEFAULT now();
+
+ALTER TABLE core.party_addresses ADD COLUMN IF NOT EXISTS valid_from TIMESTAMPTZ;
+ALTER TABLE core.party_addresses ADD COLUMN IF NOT EXISTS valid_to TIMESTAMPTZ;
+ALTER TABLE core.party_addresses ADD COLUMN IF NOT EXISTS last_confirmed_at TIMESTAMPTZ;
+
+UPDATE core.party_addresses
+SET valid_from = COALESCE(valid_from, created_at),
+    last_confirmed_at = COALESCE(updated_at, created_at)
+WHERE last_confirmed_at IS NULL;
+
+ALTER TABLE core.party_addresses ALTER COLUMN valid_from SET DEFAULT now();
+ALTER TABLE core.party_addresses ALTER COLUMN last_confirmed_at SET DEFAULT now();
+
+COMMENT ON COLUMN core.party_addresses.valid_from IS 'First time the address was seen for the party';
+COMMENT ON COLUMN core.party_addresses.valid_to IS 'When a provider stopped returning the address';
+COMMENT ON COLUMN core.party_addresses.last_confirmed_at IS 'Last time a provider returned the address';
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- Migration 047: API keys
+-- Date: 2025-11-28
+--
+-- Clients of the API authenticate with an X-Api-Key header. Only the SHA-256
+-- of a key is stored; the plaintext is returned once, when the key is
+-- created. key_prefix (the first characters of the key) identifies a key in
+-- listings and logs. Revoked keys are kept for auditing.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL
+        CHECK (scopes <@ ARRAY['enrich', 'read', 'admin']::text[]),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+COMMIT;
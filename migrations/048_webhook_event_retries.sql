@@ -0,0 +1,19 @@
+-- Migration 048: Manual retries of webhook events
+-- Date: 2025-11-28
+--
+-- Failed and poisoned webhook events can be re-run from
+-- POST /api/v1/admin/webhooks/retry instead of by hand in SQL. A retry moves
+-- the event back to 'received' with a fresh `attempts` budget; `retry_count`
+-- and `last_retried_at` record the manual retries of the event.
+
+BEGIN;
+
+ALTER TABLE webhook_events
+    ADD COLUMN IF NOT EXISTS retry_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_retried_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS ix_webhook_events_failed
+    ON webhook_events (received_at)
+    WHERE status = 'failed';
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- Migration 049: Contacts of created C2S leads
+-- Date: 2025-11-28
+--
+-- Leads created by the Google Ads flow record one row per contact (phone
+-- and/or email), keyed like c2s_imported_leads by the SHA-256 of the
+-- normalized contact. Before creating a lead, tenants with duplicate
+-- detection (LEAD_DEDUP_TENANTS) look here for a recent lead of the same
+-- contact, and append a message to it instead. Only hashes are stored.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS c2s_lead_contacts (
+    contact_hash TEXT NOT NULL,
+    c2s_lead_id TEXT NOT NULL,
+    account TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (contact_hash, c2s_lead_id)
+);
+
+CREATE INDEX IF NOT EXISTS ix_c2s_lead_contacts_recent
+    ON c2s_lead_contacts (contact_hash, created_at DESC);
+
+COMMIT;
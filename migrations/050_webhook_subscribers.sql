@@ -0,0 +1,29 @@
+-- Migration 050: Outbound webhook subscribers
+-- Date: 2025-11-28
+--
+-- External systems register a callback URL (admin endpoints
+-- /api/v1/admin/subscribers) instead of polling for enrichment results.
+-- Each subscriber has its own signing secret, returned once at
+-- registration, and receives a signed POST per enrichment completed or
+-- failed, optionally only for one tenant (lead source). Deliveries run as
+-- 'subscriber_notification' jobs. Disabled subscribers are kept for auditing.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS webhook_subscribers (
+    id BIGSERIAL PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    tenant TEXT,
+    events TEXT[] NOT NULL
+        CHECK (events <@ ARRAY['enrichment.completed', 'enrichment.failed']::text[]),
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    disabled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS ix_webhook_subscribers_active
+    ON webhook_subscribers (id)
+    WHERE disabled_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- Migration 051: Audit log of sensitive admin reads
+-- Date: 2025-11-28
+--
+-- Admin endpoints returning personal data that is otherwise only reachable
+-- in the database (raw provider payloads) record who read what, and when.
+-- actor is the API key ('api_key:<id> (<name>)') or 'admin_token'; details
+-- holds the request options (e.g. masked fields), never the data returned.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    action TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_admin_audit_log_subject
+    ON admin_audit_log (subject, created_at DESC);
+
+COMMIT;
@@ -0,0 +1,36 @@
+-- Migration 052: Provenance of merged parties
+-- Date: 2025-11-28
+--
+-- Near-duplicate parties (same CPF stored with and without formatting,
+-- overlapping contacts) are consolidated into a surviving party by
+-- EnrichmentStorage::merge_parties (POST /api/v1/admin/parties/merge). The
+-- merged party row is deleted once its contacts, addresses, relationships and
+-- enrichment snapshot are moved; this table keeps what it was: the party row
+-- as it stood, the enrichment snapshot dropped in favor of a newer one, and
+-- the number of rows moved. No foreign keys: either party may later be merged
+-- again or removed.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS core.party_merges (
+    id BIGSERIAL PRIMARY KEY,
+    survivor_id UUID NOT NULL,
+    merged_id UUID NOT NULL,
+    merged_document TEXT,
+    merged_party JSONB NOT NULL,
+    discarded_enrichment JSONB,
+    moved JSONB NOT NULL DEFAULT '{}'::jsonb,
+    reason TEXT,
+    actor TEXT NOT NULL,
+    merged_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_party_merges_survivor
+    ON core.party_merges (survivor_id, merged_at DESC);
+
+CREATE INDEX IF NOT EXISTS ix_party_merges_merged
+    ON core.party_merges (merged_id);
+
+COMMENT ON TABLE core.party_merges IS 'Parties merged into another one, with the merged row as it was';
+
+COMMIT;
@@ -0,0 +1,51 @@
+-- Migration 053: UF (state) of each party
+-- Date: 2025-11-28
+--
+-- Regional campaigns and regional partners work on the leads of one or more
+-- states. The UF is derived from the party's best current address (primary
+-- first, then the most confident and most recently confirmed; the DDD-inferred
+-- address only when nothing better is known) and kept up to date by
+-- residency::refresh_party_uf whenever addresses are stored or parties merged.
+-- Parties without an address in a known UF keep it NULL. Filtered by
+-- `GET /api/v1/customers?uf=SP,RJ`.
+
+BEGIN;
+
+ALTER TABLE core.parties
+    ADD COLUMN IF NOT EXISTS uf CHAR(2);
+
+ALTER TABLE core.parties DROP CONSTRAINT IF EXISTS chk_parties_uf;
+ALTER TABLE core.parties ADD CONSTRAINT chk_parties_uf CHECK (
+    uf IS NULL OR uf IN (
+        'AC', 'AL', 'AP', 'AM', 'BA', 'CE', 'DF', 'ES', 'GO', 'MA', 'MT', 'MS', 'MG', 'PA',
+        'PB', 'PR', 'PE', 'PI', 'RJ', 'RN', 'RS', 'RO', 'RR', 'SC', 'SP', 'SE', 'TO'
+    )
+);
+
+WITH ranked AS (
+    SELECT DISTINCT ON (pa.party_id) pa.party_id, UPPER(TRIM(a.state)) AS uf
+    FROM core.party_addresses pa
+    JOIN core.addresses a ON a.id = pa.address_id
+    WHERE pa.is_current
+      AND UPPER(TRIM(a.state)) IN (
+        'AC', 'AL', 'AP', 'AM', 'BA', 'CE', 'DF', 'ES', 'GO', 'MA', 'MT', 'MS', 'MG', 'PA',
+        'PB', 'PR', 'PE', 'PI', 'RJ', 'RN', 'RS', 'RO', 'RR', 'SC', 'SP', 'SE', 'TO'
+      )
+    ORDER BY pa.party_id,
+             pa.is_primary DESC NULLS LAST,
+             pa.confidence_score DESC NULLS LAST,
+             COALESCE(pa.last_confirmed_at, pa.created_at) DESC NULLS LAST
+)
+UPDATE core.parties p
+SET uf = ranked.uf
+FROM ranked
+WHERE p.id = ranked.party_id
+  AND p.uf IS DISTINCT FROM ranked.uf;
+
+CREATE INDEX IF NOT EXISTS idx_parties_uf
+    ON core.parties (uf, id)
+    WHERE uf IS NOT NULL;
+
+COMMENT ON COLUMN core.parties.uf IS 'State of the best current address (residency tagging)';
+
+COMMIT;
@@ -0,0 +1,33 @@
+-- Migration 054: Region-restricted API keys
+-- Date: 2025-11-28
+--
+-- Regional partners may only read the parties of their states (core.parties.uf,
+-- migration 053). `ufs` lists the states a key is restricted to; NULL keeps
+-- the key unrestricted. A restricted key may only call the customer endpoints,
+-- where its UFs are intersected with the `?uf=` filter (see api_keys).
+--
+-- The admin dashboard filters its recent leads by UF through the lead id
+-- recorded in the enrichment snapshot, hence the expression index.
+
+BEGIN;
+
+ALTER TABLE api_keys
+    ADD COLUMN IF NOT EXISTS ufs TEXT[];
+
+ALTER TABLE api_keys DROP CONSTRAINT IF EXISTS chk_api_keys_ufs;
+ALTER TABLE api_keys ADD CONSTRAINT chk_api_keys_ufs CHECK (
+    ufs IS NULL OR (
+        cardinality(ufs) > 0
+        AND ufs <@ ARRAY[
+            'AC', 'AL', 'AP', 'AM', 'BA', 'CE', 'DF', 'ES', 'GO', 'MA', 'MT', 'MS', 'MG', 'PA',
+            'PB', 'PR', 'PE', 'PI', 'RJ', 'RN', 'RS', 'RO', 'RR', 'SC', 'SP', 'SE', 'TO'
+        ]::text[]
+    )
+);
+
+CREATE INDEX IF NOT EXISTS idx_party_enrichments_lead_id
+    ON core.party_enrichments ((raw_payload->>'lead_id'));
+
+COMMENT ON COLUMN api_keys.ufs IS 'States (UF) the key is restricted to; NULL for every state';
+
+COMMIT;
@@ -0,0 +1,36 @@
+-- Migration 055: Fold c2s_message_hashes into c2s_last_messages
+-- Date: 2025-11-28
+--
+-- c2s_last_messages (migration 026) and c2s_message_hashes (migration 027)
+-- are both keyed by lead_id. last_sent_hash now holds the hash of the last
+-- message actually delivered to the lead, whatever triggered it: an
+-- identical message is skipped (lead_messages::send_deduplicated).
+--
+-- A lead may have a delivered hash before any enrichment message is
+-- recorded, so body and body_hash become nullable (NULL until then).
+
+BEGIN;
+
+ALTER TABLE c2s_last_messages
+    ADD COLUMN IF NOT EXISTS last_sent_hash TEXT;
+
+ALTER TABLE c2s_last_messages
+    ALTER COLUMN body_hash DROP NOT NULL,
+    ALTER COLUMN body DROP NOT NULL;
+
+DO $$
+BEGIN
+    IF to_regclass('public.c2s_message_hashes') IS NOT NULL THEN
+        INSERT INTO c2s_last_messages (lead_id, sent_at, last_sent_hash)
+        SELECT lead_id, sent_at, body_hash FROM c2s_message_hashes
+        ON CONFLICT (lead_id) DO UPDATE
+        SET last_sent_hash = EXCLUDED.last_sent_hash;
+    END IF;
+END $$;
+
+DROP TABLE IF EXISTS c2s_message_hashes;
+
+COMMENT ON COLUMN c2s_last_messages.last_sent_hash IS
+    'SHA-256 of the last message delivered to the lead (send_deduplicated)';
+
+COMMIT;
@@ -0,0 +1,16 @@
+-- Migration 056: API key that queued each job
+-- Date: 2025-11-28
+--
+-- Job ids are sequential, so GET /api/v1/jobs/:id only returns a job to the
+-- key that queued it (admin keys and the admin token see every job). Jobs
+-- queued without a key (internal work, rollout without API_KEYS_REQUIRED)
+-- have no owner.
+
+BEGIN;
+
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS api_key_id BIGINT REFERENCES api_keys(id);
+
+COMMENT ON COLUMN jobs.api_key_id IS 'API key that queued the job; NULL when queued without one';
+
+COMMIT;
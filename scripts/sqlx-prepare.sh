@@ -0,0 +1,48 @@
+#!/bin/bash
+#
+# Regenerate the offline sqlx metadata (.sqlx/) against a scratch database
+#
+# Builds the schema the code queries from docs/schemas/baseline.sql (the
+# schema as of migration 018, reconstructed by hand: see its header) plus
+# migrations 019 onwards, then compiles the workspace with SQLX_OFFLINE_DIR
+# so every query!/query_as!/query_scalar! writes its metadata.
+#
+# Usage: DATABASE_URL=postgres://user@localhost/c2s_sqlx scripts/sqlx-prepare.sh
+# The database is dropped and recreated: never point it at a real one.
+
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+if [ -z "${DATABASE_URL:-}" ]; then
+    echo "DATABASE_URL must point to a scratch database" >&2
+    exit 1
+fi
+
+BASE_URL="${DATABASE_URL%%\?*}"
+QUERY=""
+if [[ "$DATABASE_URL" == *\?* ]]; then
+    QUERY="?${DATABASE_URL#*\?}"
+fi
+DB_NAME="${BASE_URL##*/}"
+ADMIN_URL="${BASE_URL%/*}/postgres$QUERY"
+
+psql "$ADMIN_URL" -q -v ON_ERROR_STOP=1 \
+    -c "DROP DATABASE IF EXISTS \"$DB_NAME\"" \
+    -c "CREATE DATABASE \"$DB_NAME\""
+
+psql "$DATABASE_URL" -q -v ON_ERROR_STOP=1 -f docs/schemas/baseline.sql
+for migration in migrations/*.sql; do
+    number=$(basename "$migration" | cut -d_ -f1)
+    if [ "$((10#$number))" -ge 19 ]; then
+        psql "$DATABASE_URL" -q -v ON_ERROR_STOP=1 -f "$migration" > /dev/null
+    fi
+done
+
+rm -f .sqlx/query-*.json
+mkdir -p .sqlx
+# Touch the crate roots so cargo re-expands the macros
+touch src/lib.rs src/main.rs
+SQLX_OFFLINE=false SQLX_OFFLINE_DIR="$PWD/.sqlx" cargo check --workspace --all-targets
+
+echo "Wrote $(ls .sqlx | wc -l) query files to .sqlx/"
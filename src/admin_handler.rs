@@ -0,0 +1,1100 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+
+use crate::{
+    api_keys::{self, ApiKey, ApiKeyCreate, AuthenticatedKey},
+    audit, c2s_accounts,
+    c2s_sellers::{self, C2SSeller},
+    caches::{CacheEntry, CacheName, CacheStats},
+    circuit_breaker::{provider_breaker_snapshots, BreakerSnapshot},
+    config::Config,
+    consent::{self, Consent, SubjectKind},
+    db::TimedQuery,
+    db_storage::PartyMerge,
+    debug_payloads::{self, DebugPayload},
+    errors::{self, AppError, ErrorContext, ResultExt},
+    google_ads_forms::{self, GoogleAdsForm, GoogleAdsFormUpdate},
+    google_ads_handler::{self, GoogleAdsWebhookResponse},
+    handlers::AppState,
+    jobs,
+    lead_messages::{self, SentMessage},
+    outbox::{self, OutboxEntry},
+    pending_enrichments::{self, PendingEnrichment},
+    privacy,
+    provider_stats::{self, ProviderLatency},
+    raw_payloads::{self, PayloadMask},
+    shadow::{self, ShadowSummary},
+    subscribers::{self, Subscriber, SubscriberCreate},
+    templates::{self, EffectiveTemplate, StoredTemplate},
+    timeline::{self, TimelineEvent},
+    webhook_handler::{self, WebhookRetryOutcome, WebhookRetryRequest},
+    work_api_fixtures::{self, WorkApiFixture},
+};
+
+/// Query parameters for the dashboard endpoints
+#[derive(Debug, Deserialize)]
+pub struct DashboardQuery {
+    /// Number of recent leads to return (default 50, max 200)
+    pub limit: Option<i64>,
+    /// Comma-separated UFs of the recent leads (`residency`); all when absent
+    #[serde(default)]
+    pub uf: Option<String>,
+}
+
+/// Query parameters for the outbox view
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    /// pending (default), sending, sent, failed
+    pub status: Option<String>,
+    /// Number of messages to return (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for the deferred enrichment queue view
+#[derive(Debug, Deserialize)]
+pub struct PendingEnrichmentQuery {
+    /// pending (default), resolving, enriched, expired
+    pub status: Option<String>,
+    /// Number of leads to return (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for the shadow provider summary
+#[derive(Debug, Deserialize)]
+pub struct ShadowQuery {
+    /// Window in days (default 7, max 90)
+    pub days: Option<i32>,
+}
+
+/// Identifier of a consent subject (exactly one of cpf, email, phone)
+#[derive(Debug, Deserialize)]
+pub struct ConsentSubject {
+    pub cpf: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+impl ConsentSubject {
+    fn resolve(&self) -> Result<(SubjectKind, &str), AppError> {
+        let given: Vec<(SubjectKind, &str)> = [
+            self.cpf.as_deref().map(|v| (SubjectKind::Cpf, v)),
+            self.email.as_deref().map(|v| (SubjectKind::Email, v)),
+            self.phone.as_deref().map(|v| (SubjectKind::Phone, v)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        match given.as_slice() {
+            [subject] => Ok(*subject),
+            _ => Err(AppError::BadRequest(
+                "Exactly one of cpf, email or phone is required".to_string(),
+            )),
+        }
+    }
+}
+
+/// Body of a consent grant or revocation
+#[derive(Debug, Deserialize)]
+pub struct ConsentUpdate {
+    #[serde(flatten)]
+    pub subject: ConsentSubject,
+    /// Defaults to "enrichment"
+    pub purpose: Option<String>,
+    pub granted: bool,
+    /// Where the consent was collected (default "admin")
+    pub source: Option<String>,
+}
+
+/// Query parameters for the data subject export
+#[derive(Debug, Deserialize)]
+pub struct PrivacyExportQuery {
+    pub cpf: String,
+}
+
+/// Query parameters of the raw payload endpoint
+#[derive(Debug, Deserialize)]
+pub struct RawPayloadQuery {
+    /// `*` or comma-separated keys whose values are masked
+    #[serde(default)]
+    pub mask: Option<String>,
+}
+
+/// Body of the party merge endpoint
+#[derive(Debug, Deserialize)]
+pub struct PartyMergeRequest {
+    /// Party kept, receiving the rows of the others
+    pub survivor_id: uuid::Uuid,
+    /// Parties merged into it, then deleted
+    pub merged_ids: Vec<uuid::Uuid>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Query parameters for a Google Ads lead replay
+#[derive(Debug, Deserialize)]
+pub struct ReplayQuery {
+    /// Create a new C2S lead even if one was already created (default false)
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Query parameters for the C2S seller directory
+#[derive(Debug, Deserialize)]
+pub struct SellerQuery {
+    /// C2S account (default `default`)
+    pub account: Option<String>,
+    /// Bypass the cache and reload from C2S (default false)
+    #[serde(default)]
+    pub refresh: bool,
+    /// Only active sellers (default false)
+    #[serde(default)]
+    pub active_only: bool,
+}
+
+/// Body of a template edit
+#[derive(Debug, Deserialize)]
+pub struct TemplateUpdate {
+    pub body: String,
+}
+
+/// A recently received lead, from any inbound channel
+#[derive(Debug, Serialize, FromRow)]
+pub struct RecentLead {
+    /// Inbound channel: c2s_webhook, google_ads, whatsapp
+    pub source: String,
+    pub lead_id: Option<String>,
+    /// Channel-specific ID (Google lead ID, WhatsApp message ID)
+    pub external_id: Option<String>,
+    pub status: String,
+    pub error_message: Option<String>,
+    /// UF of the enriched party, once the lead is enriched
+    pub uf: Option<String>,
+    /// `error_message` split into its context chain
+    #[sqlx(skip)]
+    pub error_chain: Vec<String>,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Webhook processing backlog
+#[derive(Debug, Serialize)]
+pub struct QueueDepth {
+    pub received: i64,
+    pub processing: i64,
+    /// Waiting for a provider circuit breaker to close
+    pub deferred: i64,
+    pub failed_last_24h: i64,
+    /// Gave up after WEBHOOK_MAX_ATTEMPTS runs, not retried
+    pub poisoned: i64,
+    /// Leads currently held by the in-process dedup cache
+    pub in_flight_leads: u64,
+    /// Webhook enrichment jobs (persistent queue, all instances)
+    pub jobs: jobs::JobCounts,
+}
+
+/// Full dashboard payload
+#[derive(Debug, Serialize)]
+pub struct DashboardResponse {
+    pub generated_at: DateTime<Utc>,
+    pub queue: QueueDepth,
+    pub breakers: Vec<BreakerSnapshot>,
+    pub providers: Vec<ProviderLatency>,
+    pub recent_leads: Vec<RecentLead>,
+}
+
+/// Validate the `X-Admin-Token` header against `ADMIN_API_TOKEN`
+pub(crate) fn require_admin(config: &Config, headers: &HeaderMap) -> Result<(), AppError> {
+    let expected = config.admin_api_token.as_deref().ok_or_else(|| {
+        AppError::Unauthorized("Admin endpoints disabled (ADMIN_API_TOKEN not set)".to_string())
+    })?;
+
+    let provided = headers
+        .get("x-admin-token")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing X-Admin-Token header".to_string()))?;
+
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// GET /api/v1/admin/dashboard
+///
+/// Everything an internal status page needs in one payload: recent leads with
+/// status, webhook queue depth, circuit breaker states and provider latencies.
+pub async fn dashboard(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<DashboardQuery>,
+) -> Result<Json<DashboardResponse>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let ufs = crate::residency::parse_uf_filter(query.uf.as_deref())?;
+    let queue = fetch_queue_depth(&state)
+        .await
+        .context(ErrorContext::new("admin.dashboard"))?;
+    let recent_leads = fetch_recent_leads(&state.db, limit, &ufs)
+        .await
+        .context(ErrorContext::new("admin.dashboard").id("limit", limit))?;
+
+    Ok(Json(DashboardResponse {
+        generated_at: Utc::now(),
+        queue,
+        breakers: provider_breaker_snapshots(),
+        providers: provider_stats::snapshot(),
+        recent_leads,
+    }))
+}
+
+/// GET /api/v1/admin/dashboard/providers
+///
+/// Breaker states and provider latencies only (no DB access, cheap to poll).
+pub async fn dashboard_providers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    Ok(Json(json!({
+        "generated_at": Utc::now(),
+        "breakers": provider_breaker_snapshots(),
+        "providers": provider_stats::snapshot(),
+    })))
+}
+
+/// GET /api/v1/admin/outbox
+///
+/// Scheduled C2S messages (quiet hours, follow-ups, throttled batches) with
+/// their delivery time and how long they are held.
+pub async fn outbox_messages(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<OutboxQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let status = query.status.as_deref().unwrap_or("pending");
+    if !matches!(status, "pending" | "sending" | "sent" | "failed") {
+        return Err(AppError::BadRequest(format!(
+            "Invalid outbox status: {}",
+            status
+        )));
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let messages: Vec<OutboxEntry> = outbox::list_messages(&state.db, status, limit)
+        .await
+        .context(ErrorContext::db("c2s_outbox.list").id("status", status))?;
+
+    Ok(Json(json!({
+        "generated_at": Utc::now(),
+        "status": status,
+        "count": messages.len(),
+        "messages": messages,
+    })))
+}
+
+/// GET /api/v1/admin/pending-enrichments
+///
+/// Leads queued because Diretrix found no CPF, with their retry schedule.
+pub async fn pending_enrichments(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PendingEnrichmentQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let status = query.status.as_deref().unwrap_or("pending");
+    if !matches!(status, "pending" | "resolving" | "enriched" | "expired") {
+        return Err(AppError::BadRequest(format!(
+            "Invalid pending enrichment status: {}",
+            status
+        )));
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let leads: Vec<PendingEnrichment> = pending_enrichments::list_pending(&state.db, status, limit)
+        .await
+        .context(ErrorContext::db("pending_enrichments.list").id("status", status))?;
+
+    Ok(Json(json!({
+        "generated_at": Utc::now(),
+        "status": status,
+        "count": leads.len(),
+        "leads": leads,
+    })))
+}
+
+/// GET /api/v1/admin/leads/:lead_id/messages
+///
+/// Every message sent (or attempted, or skipped as a duplicate) to a C2S
+/// lead, with body hash, C2S status code and trigger.
+pub async fn lead_message_log(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(lead_id): Path<String>,
+    Query(query): Query<DashboardQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let messages: Vec<SentMessage> = lead_messages::list_sent_messages(&state.db, &lead_id, limit)
+        .await
+        .context(ErrorContext::db("lead_messages.list").id("lead_id", &lead_id))?;
+
+    Ok(Json(json!({
+        "lead_id": lead_id,
+        "count": messages.len(),
+        "messages": messages,
+    })))
+}
+
+/// GET /api/v1/admin/templates
+///
+/// Message templates as used by this process (stored override or default),
+/// with their variables and version.
+pub async fn list_templates(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let templates: Vec<EffectiveTemplate> = templates::effective();
+    Ok(Json(json!({ "templates": templates })))
+}
+
+/// PUT /api/v1/admin/templates/:name
+///
+/// Store a new template body (version bumped). Other workers pick it up
+/// within a few seconds.
+pub async fn update_template(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(update): Json<TemplateUpdate>,
+) -> Result<Json<StoredTemplate>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let stored = templates::save(&state.db, &name, &update.body)
+        .await
+        .context(ErrorContext::db("message_templates.save").id("template", &name))?;
+    tracing::info!(
+        "Message template {} updated to version {}",
+        stored.name,
+        stored.version
+    );
+    Ok(Json(stored))
+}
+
+/// GET /api/v1/admin/google-ads/forms
+///
+/// Configured Google Ads lead forms (field mapping, seller, product, header).
+pub async fn list_google_ads_forms(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let forms: Vec<GoogleAdsForm> = google_ads_forms::list(&state.db)
+        .await
+        .context(ErrorContext::db("google_ads_forms.list"))?;
+    Ok(Json(json!({ "forms": forms })))
+}
+
+/// PUT /api/v1/admin/google-ads/forms/:form_id
+///
+/// Create or replace a form's configuration; used by the next lead of the form.
+pub async fn update_google_ads_form(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(form_id): Path<i64>,
+    Json(update): Json<GoogleAdsFormUpdate>,
+) -> Result<Json<GoogleAdsForm>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let form = google_ads_forms::save(&state.db, form_id, &update)
+        .await
+        .context(ErrorContext::db("google_ads_forms.save").id("form_id", form_id))?;
+    tracing::info!("Google Ads form {} configuration updated", form_id);
+    Ok(Json(form))
+}
+
+/// POST /api/v1/admin/google-ads/:google_lead_id/replay?force=
+///
+/// Re-run the processing of a stored lead (e.g. after fixing a form mapping).
+/// Refused with 409 when the lead already has a C2S lead, unless `force=true`.
+pub async fn replay_google_ads_lead(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(google_lead_id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Json<GoogleAdsWebhookResponse>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let response = google_ads_handler::replay_google_ads_lead(&state, &google_lead_id, query.force)
+        .await
+        .context(
+            ErrorContext::new("google_ads.replay")
+                .id("google_lead_id", &google_lead_id)
+                .id("force", query.force),
+        )?;
+    Ok(Json(response))
+}
+
+/// GET /api/v1/admin/caches
+///
+/// Entry count, weighted size, capacity and TTL of every in-process cache,
+/// plus the effective Work API TTL and its lookups per key category.
+pub async fn cache_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let caches: Vec<CacheStats> = state.caches.stats().await;
+    Ok(Json(json!({
+        "generated_at": Utc::now(),
+        "caches": caches,
+        "work_api_ttl": crate::cache_ttl::current(),
+        "work_api_lookups": crate::cache_ttl::category_stats(),
+    })))
+}
+
+/// GET /api/v1/admin/caches/:name
+///
+/// A sample of the entries held by one cache (this process only).
+pub async fn cache_entries(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(name): Path<CacheName>,
+    Query(query): Query<DashboardQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500) as usize;
+    let entries: Vec<CacheEntry> = state.caches.snapshot(name, limit);
+    Ok(Json(json!({
+        "name": name,
+        "size": state.caches.size(name),
+        "weighted_size": state.caches.weighted_size(name),
+        "count": entries.len(),
+        "entries": entries,
+    })))
+}
+
+/// GET /api/v1/admin/shadow
+///
+/// How the shadow provider compares with Work API: error rate, latency and
+/// mismatch count per field over the last days.
+pub async fn shadow_summary(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ShadowQuery>,
+) -> Result<Json<ShadowSummary>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let days = query.days.unwrap_or(7).clamp(1, 90);
+    let summary = shadow::summary(&state.db, days)
+        .await
+        .context(ErrorContext::db("shadow_comparisons.summary").id("days", days))?;
+    Ok(Json(summary))
+}
+
+/// GET /api/v1/admin/leads/:lead_id/debug
+///
+/// Captured request/response of every workflow step for a sampled or
+/// allowlisted lead (empty when the lead was not captured or expired).
+pub async fn lead_debug_payloads(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(lead_id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let payloads: Vec<DebugPayload> = debug_payloads::list_for_lead(&state.db, &lead_id)
+        .await
+        .context(ErrorContext::db("debug_payloads.list").id("lead_id", &lead_id))?;
+    Ok(Json(json!({
+        "lead_id": lead_id,
+        "count": payloads.len(),
+        "payloads": payloads,
+    })))
+}
+
+/// GET /api/v1/c2s/sellers?account=&refresh=&active_only=
+///
+/// Live sellers (id, name, team) of a C2S account, for routing rules, form
+/// configuration and the admin UI. Served from a 15 min cache unless
+/// `refresh=true`. `default_seller_known` tells whether
+/// `C2S_DEFAULT_SELLER_ID` is one of them.
+pub async fn c2s_sellers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<SellerQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let account = query
+        .account
+        .as_deref()
+        .unwrap_or(c2s_accounts::DEFAULT_ACCOUNT);
+    if account != c2s_accounts::DEFAULT_ACCOUNT && !state.config.c2s_accounts.contains_key(account)
+    {
+        return Err(AppError::BadRequest(format!(
+            "Unknown C2S account: {}",
+            account
+        )));
+    }
+
+    let directory = c2s_sellers::directory(&state, account, query.refresh)
+        .await
+        .context(
+            ErrorContext::new("c2s.list_sellers")
+                .provider("c2s")
+                .id("account", account),
+        )?;
+    let sellers: Vec<&C2SSeller> = directory
+        .iter()
+        .filter(|s| s.active || !query.active_only)
+        .collect();
+    let default_seller_known = state
+        .config
+        .c2s_default_seller_id
+        .as_deref()
+        .map(|id| directory.iter().any(|s| s.id == id));
+
+    Ok(Json(json!({
+        "account": account,
+        "count": sellers.len(),
+        "default_seller_known": default_seller_known,
+        "sellers": sellers,
+    })))
+}
+
+/// GET /api/v1/leads/:lead_id/timeline
+///
+/// Webhook receipts, job status changes, provider calls, message sends and
+/// storage writes of a lead in chronological order, for support
+/// investigations. Returns 404 when nothing is known about the lead.
+pub async fn lead_timeline(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(lead_id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let events: Vec<TimelineEvent> = timeline::lead_timeline(&state.db, &lead_id)
+        .await
+        .context(ErrorContext::db("timeline.lead").id("lead_id", &lead_id))?;
+    if events.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No events recorded for lead_id: {}",
+            lead_id
+        )));
+    }
+
+    Ok(Json(json!({
+        "lead_id": lead_id,
+        "first_event_at": events.first().map(|e| e.at),
+        "last_event_at": events.last().map(|e| e.at),
+        "count": events.len(),
+        "events": events,
+    })))
+}
+
+/// GET /api/v1/admin/work-api-fixtures
+///
+/// Anonymized Work API responses recorded by the fixture recorder, newest
+/// first, ready to be saved under `tests/fixtures/work_api/`.
+pub async fn work_api_fixtures(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<DashboardQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let fixtures: Vec<WorkApiFixture> = work_api_fixtures::list(&state.db, limit)
+        .await
+        .context(ErrorContext::db("work_api_fixtures.list"))?;
+    Ok(Json(json!({
+        "count": fixtures.len(),
+        "fixtures": fixtures,
+    })))
+}
+
+/// GET /api/v1/admin/consents?cpf=|email=|phone=
+///
+/// Consents recorded for an identifier, revoked ones included. A key
+/// restricted to some states only gets the subjects of its states.
+pub async fn list_consents(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    key: Option<Extension<AuthenticatedKey>>,
+    Query(query): Query<ConsentSubject>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let (kind, value) = query.resolve()?;
+    if !crate::residency::subject_visible_to(&state.db, kind, value, key.as_deref()).await? {
+        return Err(AppError::NotFound(format!(
+            "No consents recorded for this {}",
+            kind.as_str()
+        )));
+    }
+    let consents: Vec<Consent> = consent::list_for_subject(&state.db, kind, value)
+        .await
+        .context(ErrorContext::db("consents.list").id("subject_type", kind.as_str()))?;
+    Ok(Json(json!({
+        "subject_type": kind,
+        "consents": consents,
+    })))
+}
+
+/// POST /api/v1/admin/consents
+///
+/// Grant or revoke a consent collected outside lead forms (phone call,
+/// signed document, data subject request).
+pub async fn update_consent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(update): Json<ConsentUpdate>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let (kind, value) = update.subject.resolve()?;
+    let purpose = update
+        .purpose
+        .as_deref()
+        .unwrap_or(consent::PURPOSE_ENRICHMENT);
+    let consent_context = || {
+        ErrorContext::db(if update.granted {
+            "consents.grant"
+        } else {
+            "consents.revoke"
+        })
+        .id("subject_type", kind.as_str())
+        .id("purpose", purpose)
+    };
+    let changed = if update.granted {
+        let source = update.source.as_deref().unwrap_or("admin");
+        consent::grant(&state.db, kind, value, purpose, source)
+            .await
+            .context(consent_context())?;
+        true
+    } else {
+        consent::revoke(&state.db, kind, value, purpose)
+            .await
+            .context(consent_context())?
+    };
+    tracing::info!(
+        "Consent {} {} for {} ({})",
+        purpose,
+        if update.granted { "granted" } else { "revoked" },
+        kind.as_str(),
+        if changed {
+            "updated"
+        } else {
+            "no active consent"
+        }
+    );
+    Ok(Json(json!({
+        "subject_type": kind,
+        "purpose": purpose,
+        "granted": update.granted,
+        "changed": changed,
+    })))
+}
+
+/// GET /api/v1/privacy/export?cpf=
+///
+/// Everything stored about a person, to answer LGPD access requests.
+/// Admin scope: the response holds the person's full personal data. A key
+/// restricted to some states only exports the people of its states.
+pub async fn privacy_export(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    key: Option<Extension<AuthenticatedKey>>,
+    Query(query): Query<PrivacyExportQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let visible = crate::residency::subject_visible_to(
+        &state.db,
+        SubjectKind::Cpf,
+        &query.cpf,
+        key.as_deref(),
+    )
+    .await?;
+    if !visible {
+        return Err(AppError::NotFound(
+            "No data stored for this CPF".to_string(),
+        ));
+    }
+    let export = privacy::export(&state.db, &query.cpf)
+        .await
+        .context(ErrorContext::new("privacy.export"))?;
+    tracing::info!(
+        "Data subject export generated ({} lead(s))",
+        export["leads"]["lead_ids"]
+            .as_array()
+            .map_or(0, |ids| ids.len())
+    );
+    Ok(Json(export))
+}
+
+/// GET /api/v1/admin/parties/:id/raw-payload?mask=
+///
+/// Provider payload stored for a party, to debug mappings without database
+/// access. Values can be masked (`?mask=*` or `?mask=nome,telefones`). Every
+/// read is recorded in the audit log first.
+pub async fn party_raw_payload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    key: Option<Extension<AuthenticatedKey>>,
+    Path(party_id): Path<uuid::Uuid>,
+    Query(query): Query<RawPayloadQuery>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let mask = PayloadMask::parse(query.mask.as_deref());
+    let actor = audit::actor(key.as_ref().map(|Extension(key)| key));
+    audit::record(
+        &state.db,
+        audit::READ_RAW_PAYLOAD,
+        &party_id.to_string(),
+        &actor,
+        &json!({ "mask": mask.describe() }),
+    )
+    .await?;
+
+    let stored = raw_payloads::find(&state.db, party_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("No enrichment payload for party {}", party_id))
+        })?;
+    tracing::info!(
+        "Raw payload of party {} read by {} (mask: {})",
+        party_id,
+        actor,
+        mask.describe()
+    );
+
+    let raw_payload = raw_payloads::mask_payload(
+        &stored.raw_payload,
+        &mask,
+        stored.document.as_deref().unwrap_or_default(),
+    );
+    Ok(Json(json!({
+        "party_id": stored.party_id,
+        "party_type": stored.party_type,
+        "provider": stored.provider,
+        "enriched_at": stored.enriched_at,
+        "quality_score": stored.quality_score,
+        "masked": mask.describe(),
+        "raw_payload": raw_payload,
+    })))
+}
+
+/// POST /api/v1/admin/parties/merge
+///
+/// Merge near-duplicate parties into `survivor_id`: contacts, addresses,
+/// relationships and the newer enrichment snapshot move to it, and each
+/// merged party is recorded in `core.party_merges` before being deleted.
+/// 409 when the parties have different types or documents.
+pub async fn merge_parties(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    key: Option<Extension<AuthenticatedKey>>,
+    Json(request): Json<PartyMergeRequest>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let actor = audit::actor(key.as_ref().map(|Extension(key)| key));
+    let merges: Vec<PartyMerge> = state
+        .services
+        .storage
+        .merge_parties(
+            request.survivor_id,
+            &request.merged_ids,
+            request.reason.as_deref(),
+            &actor,
+        )
+        .await?;
+    Ok(Json(json!({
+        "survivor_id": request.survivor_id,
+        "count": merges.len(),
+        "merges": merges,
+    })))
+}
+
+/// GET /api/v1/admin/api-keys
+///
+/// Every API key with its scopes and last use (never the key itself).
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let keys: Vec<ApiKey> = api_keys::list(&state.db).await?;
+    Ok(Json(json!({
+        "count": keys.len(),
+        "api_keys": keys,
+    })))
+}
+
+/// POST /api/v1/admin/api-keys
+///
+/// Create a key with `{"name", "scopes"}`. The key is in the response only:
+/// it is stored hashed and cannot be shown again.
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ApiKeyCreate>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let (api_key, key) = api_keys::create(&state.db, &request).await?;
+    tracing::info!(
+        "API key {} ({}) created with scopes {:?}",
+        api_key.id,
+        api_key.name,
+        api_key.scopes
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "api_key": api_key,
+            "key": key,
+        })),
+    ))
+}
+
+/// DELETE /api/v1/admin/api-keys/:id
+///
+/// Revoke a key. Other instances may accept it for up to a minute (cache).
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    if !api_keys::revoke(&state, id).await? {
+        return Err(AppError::NotFound(format!(
+            "No active API key with id {}",
+            id
+        )));
+    }
+    tracing::info!("API key {} revoked", id);
+    Ok(Json(json!({
+        "id": id,
+        "revoked": true,
+    })))
+}
+
+/// GET /api/v1/admin/subscribers
+///
+/// Every webhook subscriber with its events and tenant (never the secret).
+pub async fn list_subscribers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let subscribers: Vec<Subscriber> = subscribers::list(&state.db).await?;
+    Ok(Json(json!({
+        "count": subscribers.len(),
+        "subscribers": subscribers,
+    })))
+}
+
+/// POST /api/v1/admin/subscribers
+///
+/// Register a callback URL with `{"url", "events", "tenant", "description"}`.
+/// The signing secret is in the response only.
+pub async fn create_subscriber(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SubscriberCreate>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let (subscriber, secret) = subscribers::create(&state.db, &request).await?;
+    tracing::info!(
+        "Webhook subscriber {} registered for {:?} ({})",
+        subscriber.id,
+        subscriber.events,
+        subscriber.url
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "subscriber": subscriber,
+            "secret": secret,
+        })),
+    ))
+}
+
+/// DELETE /api/v1/admin/subscribers/:id
+///
+/// Stop notifying a subscriber. Deliveries already queued are dropped.
+pub async fn disable_subscriber(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    if !subscribers::disable(&state.db, id).await? {
+        return Err(AppError::NotFound(format!(
+            "No active subscriber with id {}",
+            id
+        )));
+    }
+    tracing::info!("Webhook subscriber {} disabled", id);
+    Ok(Json(json!({
+        "id": id,
+        "disabled": true,
+    })))
+}
+
+/// POST /api/v1/admin/webhooks/retry
+///
+/// Re-run failed webhook events: `{"id": 42}` for one event (failed or
+/// poisoned), or `{"status", "from", "to", "limit"}` for the oldest events
+/// of a status received in a date range. Retries are counted on the event.
+pub async fn retry_webhook_events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<WebhookRetryRequest>,
+) -> Result<Json<WebhookRetryOutcome>, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    let outcome = webhook_handler::retry_webhook_events(&state, &request).await?;
+    tracing::info!(
+        "Webhook retry (id={:?}, status={:?}): {} event(s) queued, {} invalid",
+        request.id,
+        request.status,
+        outcome.queued,
+        outcome.invalid
+    );
+    Ok(Json(outcome))
+}
+
+async fn fetch_queue_depth(state: &AppState) -> Result<QueueDepth, AppError> {
+    let (received, processing, deferred, failed_last_24h, poisoned): (i64, i64, i64, i64, i64) =
+        sqlx::query_as(
+            r#"
+        SELECT
+            COUNT(*) FILTER (WHERE status = 'received'),
+            COUNT(*) FILTER (WHERE status = 'processing'),
+            COUNT(*) FILTER (WHERE status = 'deferred'),
+            COUNT(*) FILTER (WHERE status = 'failed' AND received_at > NOW() - INTERVAL '24 hours'),
+            COUNT(*) FILTER (WHERE status = 'poisoned')
+        FROM webhook_events
+        WHERE status IN ('received', 'processing', 'deferred', 'failed', 'poisoned')
+        "#,
+        )
+        .fetch_one(&state.db)
+        .timed("admin.queue_depth")
+        .await
+        .context("Failed to fetch webhook queue depth")?;
+
+    Ok(QueueDepth {
+        received,
+        processing,
+        deferred,
+        failed_last_24h,
+        poisoned,
+        in_flight_leads: state.caches.size(CacheName::ProcessingLeads),
+        jobs: jobs::counts(&state.db, jobs::WEBHOOK_ENRICHMENT).await?,
+    })
+}
+
+/// Latest leads of every channel, only those of `ufs` when not empty
+///
+/// The UF is the one of the party enriched for the lead, found through the
+/// lead id of its enrichment snapshot (indexed, migration 054).
+async fn fetch_recent_leads(
+    db: &PgPool,
+    limit: i64,
+    ufs: &[&str],
+) -> Result<Vec<RecentLead>, AppError> {
+    let mut leads = sqlx::query_as::<_, RecentLead>(
+        r#"
+        SELECT leads.*, party.uf FROM (
+            SELECT 'c2s_webhook' AS source, lead_id, NULL::text AS external_id,
+                   status, error_message, received_at, processed_at
+            FROM webhook_events
+            UNION ALL
+            SELECT 'google_ads', c2s_lead_id, google_lead_id,
+                   enrichment_status, NULL, created_at, c2s_created_at
+            FROM google_ads_leads
+            UNION ALL
+            SELECT 'whatsapp', c2s_lead_id, wa_message_id,
+                   enrichment_status, NULL, created_at, NULL
+            FROM whatsapp_leads
+        ) leads
+        LEFT JOIN LATERAL (
+            SELECT p.uf::text AS uf
+            FROM core.party_enrichments pe
+            JOIN core.parties p ON p.id = pe.party_id
+            WHERE pe.raw_payload->>'lead_id' = leads.lead_id
+            LIMIT 1
+        ) party ON true
+        WHERE cardinality($2::text[]) = 0 OR party.uf = ANY($2)
+        ORDER BY received_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .bind(ufs)
+    .fetch_all(db)
+    .timed("admin.recent_leads")
+    .await
+    .context("Failed to fetch recent leads")?;
+
+    for lead in &mut leads {
+        lead.error_chain = lead
+            .error_message
+            .as_deref()
+            .map(errors::split_chain)
+            .unwrap_or_default();
+    }
+    Ok(leads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+
+    #[test]
+    fn test_replay_query_defaults_to_no_force() {
+        let query: ReplayQuery = serde_json::from_value(json!({})).unwrap();
+        assert!(!query.force);
+        let query: ReplayQuery = serde_json::from_value(json!({ "force": true })).unwrap();
+        assert!(query.force);
+    }
+}
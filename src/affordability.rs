@@ -0,0 +1,150 @@
+//! Affordability of the inquired property for an enriched lead
+//!
+//! Compares the listing price (`listings::Listing`) with the lead's annual
+//! income. Declared income (`DadosEconomicos.renda`) is used when present;
+//! otherwise the income floor of the purchasing power segment is used as an
+//! estimate.
+
+use crate::enrichment::WealthSegment;
+use crate::money::Brl;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Monthly income assumed per segment when `renda` is missing (segment floor)
+fn segment_monthly_income(segment: WealthSegment) -> Option<f64> {
+    match segment {
+        WealthSegment::MuitoAlto => Some(15_000.0),
+        WealthSegment::Alto => Some(7_000.0),
+        WealthSegment::Medio => Some(3_000.0),
+        WealthSegment::Baixo => Some(1_500.0),
+        WealthSegment::Desconhecido => None,
+    }
+}
+
+/// Price-to-annual-income bands
+///
+/// Typical financing (20% down, installment up to 30% of income over 30
+/// years) reaches roughly 4-5x the annual income.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AffordabilityBand {
+    /// Up to 3x annual income
+    Confortavel,
+    /// Up to 5x: financeable
+    Viavel,
+    /// Up to 8x: needs savings or other income
+    Esticado,
+    /// Above 8x
+    Improvavel,
+}
+
+impl AffordabilityBand {
+    fn from_ratio(ratio: f64) -> Self {
+        match ratio {
+            r if r <= 3.0 => Self::Confortavel,
+            r if r <= 5.0 => Self::Viavel,
+            r if r <= 8.0 => Self::Esticado,
+            _ => Self::Improvavel,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Confortavel => "confortável",
+            Self::Viavel => "viável",
+            Self::Esticado => "esticado",
+            Self::Improvavel => "improvável",
+        }
+    }
+}
+
+/// Affordability indicator of a lead for one property
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Affordability {
+    /// Property price divided by annual income
+    pub price_to_income: f64,
+    pub band: AffordabilityBand,
+    /// Income estimated from the purchasing power segment (no declared `renda`)
+    pub estimated: bool,
+}
+
+impl Affordability {
+    /// Compute the indicator, `None` without a price or any income information
+    pub fn assess(work_data: &Value, price: f64) -> Option<Self> {
+        if price <= 0.0 {
+            return None;
+        }
+
+        let declared = work_data
+            .get("DadosEconomicos")
+            .and_then(|d| d.get("renda"))
+            .and_then(|v| v.as_str())
+            .and_then(Brl::parse)
+            .map(|renda| renda.to_f64())
+            .filter(|r| *r > 0.0);
+        let (monthly_income, estimated) = match declared {
+            Some(renda) => (renda, false),
+            None => (
+                segment_monthly_income(WealthSegment::from_work_data(work_data))?,
+                true,
+            ),
+        };
+
+        let price_to_income = price / (monthly_income * 12.0);
+        Some(Self {
+            price_to_income,
+            band: AffordabilityBand::from_ratio(price_to_income),
+            estimated,
+        })
+    }
+
+    /// Message line, e.g. "Preço/Renda anual: 4.2x (viável)"
+    pub fn format_line(&self) -> String {
+        format!(
+            "Preço/Renda anual: {:.1}x ({}{})\n",
+            self.price_to_income,
+            self.band.label(),
+            if self.estimated {
+                ", renda estimada"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_assess_with_declared_income() {
+        let data = json!({"DadosEconomicos": {"renda": "20000,00"}});
+
+        let result = Affordability::assess(&data, 1_000_000.0).expect("affordability");
+        assert!((result.price_to_income - 4.1667).abs() < 1e-3);
+        assert_eq!(result.band, AffordabilityBand::Viavel);
+        assert!(!result.estimated);
+        assert_eq!(result.format_line(), "Preço/Renda anual: 4.2x (viável)\n");
+    }
+
+    #[test]
+    fn test_assess_falls_back_to_purchasing_power() {
+        let data = json!({"DadosEconomicos": {
+            "poderAquisitivo": {"poderAquisitivoDescricao": "MEDIO"}
+        }});
+
+        let result = Affordability::assess(&data, 900_000.0).expect("affordability");
+        assert_eq!(result.band, AffordabilityBand::Improvavel);
+        assert!(result.estimated);
+        assert!(result.format_line().contains("renda estimada"));
+    }
+
+    #[test]
+    fn test_assess_without_income_or_price() {
+        assert!(Affordability::assess(&json!({}), 500_000.0).is_none());
+        let data = json!({"DadosEconomicos": {"renda": "5000"}});
+        assert!(Affordability::assess(&data, 0.0).is_none());
+    }
+}
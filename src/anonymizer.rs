@@ -0,0 +1,424 @@
+//! Anonymization of stale leads
+//!
+//! Leads with no activity (webhook, form, WhatsApp message or C2S message)
+//! for `ANONYMIZE_AFTER_DAYS` have their personal identifiers replaced by
+//! salted hashes (`pseudonymize`): the CPF of Google Ads leads, the WhatsApp
+//! sender, and every name, contact and free-text field of the stored webhook
+//! and form payloads (`scrub_payload`). Structure, statuses, sources, campaign
+//! ids and timestamps are kept, and equal identifiers hash equally, so
+//! counts, funnels and distinct-contact analytics still work. Messages sent
+//! to C2S (outbox and last message) are replaced by a marker. Processed rows
+//! get `anonymized_at` (migration 034).
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::leader::Leadership;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefix of anonymized values (already anonymized values are left as is)
+const ANON_PREFIX: &str = "anon:";
+
+/// Replacement of anonymized message bodies
+const ANONYMIZED_BODY: &str = "[anonymized]";
+
+/// Rows anonymized per query
+const BATCH_SIZE: i64 = 500;
+
+/// Payload keys whose whole value is personal data
+const PII_KEYS: &[&str] = &[
+    "customer",
+    "contacts",
+    "profile",
+    "profile_name",
+    "full_name",
+    "email",
+    "phone",
+    "mobile",
+    "cpf",
+    "document",
+    "wa_id",
+    "from",
+    "sender",
+    "message",
+    "body",
+    "text",
+    "description",
+    "string_value",
+];
+
+/// Keys that are never personal even under a personal key
+const KEPT_KEYS: &[&str] = &[
+    "column_id",
+    "column_name",
+    "type",
+    "created_at",
+    "updated_at",
+];
+
+/// Lead ids with activity since $1, in any table
+const ACTIVE_LEADS: &str = r#"
+    active AS (
+        SELECT lead_id FROM webhook_events WHERE received_at >= $1
+        UNION SELECT c2s_lead_id FROM google_ads_leads WHERE created_at >= $1
+        UNION SELECT c2s_lead_id FROM whatsapp_leads WHERE created_at >= $1
+        UNION SELECT lead_id FROM sent_messages WHERE created_at >= $1
+        UNION SELECT lead_id FROM c2s_outbox WHERE created_at >= $1 OR status IN ('pending', 'sending')
+        UNION SELECT lead_id FROM pending_enrichments WHERE status IN ('pending', 'resolving')
+    )
+"#;
+
+/// Irreversible, stable replacement of an identifier
+pub fn pseudonymize(salt: &str, value: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}", salt, value.trim()).as_bytes());
+    format!("{}{}", ANON_PREFIX, hex::encode(&digest[..16]))
+}
+
+/// Replace the personal values of a stored payload, keeping its structure
+///
+/// Object keys, booleans, numbers and the values of non-personal keys
+/// (statuses, sources, products, ids) are kept.
+pub fn scrub_payload(payload: &mut Value, salt: &str) {
+    scrub(payload, salt, false);
+}
+
+fn scrub(value: &mut Value, salt: &str, personal: bool) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if KEPT_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                scrub(child, salt, personal || PII_KEYS.contains(&key.as_str()));
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                scrub(item, salt, personal);
+            }
+        }
+        Value::String(s) if personal && !s.is_empty() && !s.starts_with(ANON_PREFIX) => {
+            *s = pseudonymize(salt, s);
+        }
+        _ => {}
+    }
+}
+
+/// Rows anonymized by one run
+#[derive(Debug, Default, Serialize)]
+pub struct AnonymizationReport {
+    pub webhook_events: u64,
+    pub google_ads_leads: u64,
+    pub whatsapp_leads: u64,
+    pub outbox_messages: u64,
+    pub last_messages: u64,
+}
+
+impl AnonymizationReport {
+    pub fn total(&self) -> u64 {
+        self.webhook_events
+            + self.google_ads_leads
+            + self.whatsapp_leads
+            + self.outbox_messages
+            + self.last_messages
+    }
+}
+
+/// Anonymize every lead without activity since `cutoff`
+pub async fn anonymize_stale(
+    pool: &PgPool,
+    salt: &str,
+    cutoff: DateTime<Utc>,
+) -> Result<AnonymizationReport, AppError> {
+    let mut report = AnonymizationReport::default();
+    loop {
+        let n = anonymize_webhook_events(pool, salt, cutoff).await?;
+        report.webhook_events += n;
+        if n < BATCH_SIZE as u64 {
+            break;
+        }
+    }
+    loop {
+        let n = anonymize_google_ads_leads(pool, salt, cutoff).await?;
+        report.google_ads_leads += n;
+        if n < BATCH_SIZE as u64 {
+            break;
+        }
+    }
+    loop {
+        let n = anonymize_whatsapp_leads(pool, salt, cutoff).await?;
+        report.whatsapp_leads += n;
+        if n < BATCH_SIZE as u64 {
+            break;
+        }
+    }
+    report.outbox_messages = anonymize_outbox(pool, cutoff).await?;
+    report.last_messages = anonymize_last_messages(pool, cutoff).await?;
+    Ok(report)
+}
+
+async fn anonymize_webhook_events(
+    pool: &PgPool,
+    salt: &str,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let rows: Vec<(String, DateTime<Utc>, Value)> = sqlx::query_as(&format!(
+        r#"
+        WITH {ACTIVE_LEADS}
+        SELECT w.lead_id, w.updated_at, w.payload_raw FROM webhook_events w
+        WHERE w.anonymized_at IS NULL
+          AND w.payload_raw IS NOT NULL
+          AND w.received_at < $1
+          AND w.status IN ('completed', 'failed')
+          AND NOT EXISTS (SELECT 1 FROM active a WHERE a.lead_id = w.lead_id)
+        LIMIT $2
+        "#
+    ))
+    .bind(cutoff)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .timed("webhook_events.select_stale")
+    .await
+    .context("Failed to select stale webhook events")?;
+
+    let count = rows.len() as u64;
+    for (lead_id, updated_at, mut payload) in rows {
+        scrub_payload(&mut payload, salt);
+        sqlx::query(
+            r#"
+            UPDATE webhook_events SET payload_raw = $3, anonymized_at = NOW()
+            WHERE lead_id = $1 AND updated_at = $2
+            "#,
+        )
+        .bind(&lead_id)
+        .bind(updated_at)
+        .bind(payload)
+        .execute(pool)
+        .timed("webhook_events.anonymize")
+        .await
+        .context(format!(
+            "Failed to anonymize webhook event for lead_id: {}",
+            lead_id
+        ))?;
+    }
+    Ok(count)
+}
+
+async fn anonymize_google_ads_leads(
+    pool: &PgPool,
+    salt: &str,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let rows: Vec<(String, Option<String>, Value)> = sqlx::query_as(&format!(
+        r#"
+        WITH {ACTIVE_LEADS}
+        SELECT g.google_lead_id, g.cpf, g.payload_raw FROM google_ads_leads g
+        WHERE g.anonymized_at IS NULL
+          AND g.created_at < $1
+          AND NOT EXISTS (SELECT 1 FROM active a WHERE a.lead_id = g.c2s_lead_id)
+        LIMIT $2
+        "#
+    ))
+    .bind(cutoff)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .timed("google_ads_leads.select_stale")
+    .await
+    .context("Failed to select stale Google Ads leads")?;
+
+    let count = rows.len() as u64;
+    for (google_lead_id, cpf, mut payload) in rows {
+        scrub_payload(&mut payload, salt);
+        sqlx::query(
+            r#"
+            UPDATE google_ads_leads SET cpf = $2, payload_raw = $3, anonymized_at = NOW()
+            WHERE google_lead_id = $1
+            "#,
+        )
+        .bind(&google_lead_id)
+        .bind(cpf.map(|cpf| pseudonymize(salt, &cpf)))
+        .bind(payload)
+        .execute(pool)
+        .timed("google_ads_leads.anonymize")
+        .await
+        .context(format!(
+            "Failed to anonymize Google Ads lead: {}",
+            google_lead_id
+        ))?;
+    }
+    Ok(count)
+}
+
+async fn anonymize_whatsapp_leads(
+    pool: &PgPool,
+    salt: &str,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    type Row = (i64, String, Option<String>, Option<String>, Option<String>);
+    let rows: Vec<Row> = sqlx::query_as(&format!(
+        r#"
+        WITH {ACTIVE_LEADS}
+        SELECT w.id, w.wa_id, w.profile_name, w.message_text, w.phone_normalized
+        FROM whatsapp_leads w
+        WHERE w.anonymized_at IS NULL
+          AND w.created_at < $1
+          AND NOT EXISTS (SELECT 1 FROM active a WHERE a.lead_id = w.c2s_lead_id)
+        LIMIT $2
+        "#
+    ))
+    .bind(cutoff)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .timed("whatsapp_leads.select_stale")
+    .await
+    .context("Failed to select stale WhatsApp leads")?;
+
+    let count = rows.len() as u64;
+    let hash = |value: Option<String>| value.map(|v| pseudonymize(salt, &v));
+    for (id, wa_id, profile_name, message_text, phone) in rows {
+        sqlx::query(
+            r#"
+            UPDATE whatsapp_leads
+            SET wa_id = $2, profile_name = $3, message_text = $4, phone_normalized = $5,
+                anonymized_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(pseudonymize(salt, &wa_id))
+        .bind(hash(profile_name))
+        .bind(hash(message_text))
+        .bind(hash(phone))
+        .execute(pool)
+        .timed("whatsapp_leads.anonymize")
+        .await
+        .context(format!("Failed to anonymize WhatsApp lead: {}", id))?;
+    }
+    Ok(count)
+}
+
+async fn anonymize_outbox(pool: &PgPool, cutoff: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query(&format!(
+        r#"
+        WITH {ACTIVE_LEADS}
+        UPDATE c2s_outbox o SET body = $2
+        WHERE o.status IN ('sent', 'failed')
+          AND o.created_at < $1
+          AND o.body <> $2
+          AND NOT EXISTS (SELECT 1 FROM active a WHERE a.lead_id = o.lead_id)
+        "#
+    ))
+    .bind(cutoff)
+    .bind(ANONYMIZED_BODY)
+    .execute(pool)
+    .timed("c2s_outbox.anonymize")
+    .await
+    .context("Failed to anonymize C2S outbox messages")?;
+    Ok(result.rows_affected())
+}
+
+async fn anonymize_last_messages(pool: &PgPool, cutoff: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query(&format!(
+        r#"
+        WITH {ACTIVE_LEADS}
+        UPDATE c2s_last_messages m SET body = $2
+        WHERE m.sent_at < $1
+          AND m.body <> $2
+          AND NOT EXISTS (SELECT 1 FROM active a WHERE a.lead_id = m.lead_id)
+        "#
+    ))
+    .bind(cutoff)
+    .bind(ANONYMIZED_BODY)
+    .execute(pool)
+    .timed("c2s_last_messages.anonymize")
+    .await
+    .context("Failed to anonymize last C2S messages")?;
+    Ok(result.rows_affected())
+}
+
+/// Background task anonymizing stale leads every 6 hours
+///
+/// Runs on the instance holding the "lead_anonymizer" leadership, only when
+/// `ANONYMIZE_AFTER_DAYS` is set.
+pub fn spawn_anonymizer(state: Arc<AppState>) {
+    let (Some(days), Some(salt)) = (
+        state.config.anonymize_after_days,
+        state.config.anonymization_salt.clone(),
+    ) else {
+        return;
+    };
+    tokio::spawn(async move {
+        let leader = Leadership::new("lead_anonymizer");
+        let mut interval = tokio::time::interval(Duration::from_secs(6 * 3600));
+        loop {
+            interval.tick().await;
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+            let cutoff = Utc::now() - chrono::Duration::days(days);
+            match anonymize_stale(&state.db, &salt, cutoff).await {
+                Ok(report) if report.total() == 0 => {}
+                Ok(report) => tracing::info!("Anonymized stale leads: {:?}", report),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pseudonymize_is_stable_and_salted() {
+        let a = pseudonymize("salt", "12345678901");
+        assert_eq!(a, pseudonymize("salt", " 12345678901 "));
+        assert_ne!(a, pseudonymize("other", "12345678901"));
+        assert!(a.starts_with(ANON_PREFIX));
+        assert!(!a.contains("12345678901"));
+    }
+
+    #[test]
+    fn test_scrub_payload_keeps_structure() {
+        let mut payload = json!({
+            "id": "lead-1",
+            "attributes": {
+                "customer": {"name": "Ana", "email": "ana@example.com", "phone": "11987654321"},
+                "lead_source": {"id": 7},
+                "lead_status": {"alias": "novo"},
+                "messages": [{"message": "Oi, meu CPF é 123", "created_at": "2025-01-01"}]
+            },
+            "user_column_data": [
+                {"column_id": "EMAIL", "column_name": "E-mail", "string_value": "ana@example.com"}
+            ],
+            "is_test": false
+        });
+        scrub_payload(&mut payload, "salt");
+
+        assert_eq!(payload["id"], "lead-1");
+        assert_eq!(payload["attributes"]["lead_source"]["id"], 7);
+        assert_eq!(payload["attributes"]["lead_status"]["alias"], "novo");
+        assert_eq!(payload["user_column_data"][0]["column_id"], "EMAIL");
+        assert_eq!(payload["is_test"], false);
+        assert_eq!(
+            payload["attributes"]["messages"][0]["created_at"],
+            "2025-01-01"
+        );
+        let email = pseudonymize("salt", "ana@example.com");
+        assert_eq!(payload["attributes"]["customer"]["email"], email);
+        assert_eq!(payload["user_column_data"][0]["string_value"], email);
+        assert!(!payload.to_string().contains("Ana"));
+
+        // Idempotent
+        let once = payload.clone();
+        scrub_payload(&mut payload, "salt");
+        assert_eq!(payload, once);
+    }
+}
@@ -10,3 +10,35 @@ pub mod webhook_handler {
 pub mod google_ads_handler {
     pub use crate::google_ads_handler::*;
 }
+
+pub mod whatsapp_handler {
+    pub use crate::whatsapp_handler::*;
+}
+
+pub mod admin_handler {
+    pub use crate::admin_handler::*;
+}
+
+pub mod etag {
+    pub use crate::etag::*;
+}
+
+pub mod cors {
+    pub use crate::cors::*;
+}
+
+pub mod enrichment_jobs {
+    pub use crate::enrichment_jobs::*;
+}
+
+pub mod batch_jobs {
+    pub use crate::batch_jobs::*;
+}
+
+pub mod batch_enrich {
+    pub use crate::batch_enrich::*;
+}
+
+pub mod api_keys {
+    pub use crate::api_keys::*;
+}
@@ -0,0 +1,444 @@
+//! API keys
+//!
+//! Clients authenticate with an `X-Api-Key` header. Keys are created and
+//! revoked through the admin endpoints (`/api/v1/admin/api-keys`) and stored
+//! as their SHA-256 in `api_keys` (migration 047); the plaintext is shown
+//! once, at creation. Each key carries scopes:
+//!
+//! - `read`: GET endpoints (customers, Work API modules, job status),
+//! - `enrich`: the other non-admin endpoints (enrichment, leads, batches),
+//! - `admin`: `/api/v1/admin/*` and `/api/v1/privacy/*`, and everything else.
+//!
+//! A key may also be restricted to some states (`ufs`, migration 054), for
+//! regional partners: such a key can only call the customer endpoints, the
+//! privacy export and the consent listing (`region_scoped`), which list and
+//! return the parties of its states only (`residency`).
+//!
+//! Admin endpoints still check `X-Admin-Token` themselves; a valid admin token
+//! stands in for an `admin` key. Webhooks (authenticated by their provider),
+//! `/docs`, `/health` and `/metrics` need no key. Without
+//! `API_KEYS_REQUIRED=true`, requests without a key are let through (rollout),
+//! but a key that is sent must be valid and carry the scope. A missing or
+//! unknown key is a 401; a valid key without the scope, or outside its
+//! states, is a 403 (`AppError::Forbidden`).
+//!
+//! Authenticated keys are cached for 60s (`Caches::api_keys`): a revocation
+//! is immediate on the instance that handled it and takes up to a minute
+//! elsewhere, and `last_used_at` is refreshed on cache misses only.
+
+use crate::admin_handler::require_admin;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use crate::handlers::AppState;
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+
+/// Prefix of every generated key (recognizable in leaked-secret scans)
+pub const KEY_PREFIX: &str = "mbk_";
+
+/// Random bytes of a key (hex-encoded after the prefix)
+const KEY_BYTES: usize = 24;
+
+/// Characters of the key kept in `key_prefix` to identify it
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// What a key may call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Enrich,
+    Read,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Enrich => "enrich",
+            Scope::Read => "read",
+            Scope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "enrich" => Some(Scope::Enrich),
+            "read" => Some(Scope::Read),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// A stored key, as listed by the admin endpoint (never the key itself)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    /// States the key is restricted to (None: every state)
+    pub ufs: Option<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Key of the current request, available as a request extension
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedKey {
+    pub id: i64,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// States the key is restricted to (None: every state)
+    pub ufs: Option<Vec<String>>,
+}
+
+impl AuthenticatedKey {
+    /// Whether the key may call endpoints of `scope` (`admin` allows all)
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+/// Body of `POST /api/v1/admin/api-keys`
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyCreate {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Restrict the key to these states (regional partners)
+    #[serde(default)]
+    pub ufs: Option<Vec<String>>,
+}
+
+/// New random key: `mbk_` followed by 48 hex characters
+pub fn generate_key() -> String {
+    let mut bytes = [0u8; KEY_BYTES];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("failed to generate API key");
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+/// Stored form of a key (hex SHA-256)
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Scope needed to call `path`, `None` for endpoints without API keys
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    if path.starts_with("/api/v1/webhooks/") || path == "/docs" || path.starts_with("/api-docs/") {
+        return None;
+    }
+    if path.starts_with("/api/v1/admin/") || path.starts_with("/api/v1/privacy/") {
+        return Some(Scope::Admin);
+    }
+    if method == Method::GET || method == Method::HEAD {
+        Some(Scope::Read)
+    } else {
+        Some(Scope::Enrich)
+    }
+}
+
+/// Whether `path` serves parties filtered by the UFs of a restricted key
+///
+/// The customer endpoints, plus the privacy export and consent listing,
+/// which answer for one data subject and check its state (`residency`).
+pub fn region_scoped(method: &Method, path: &str) -> bool {
+    path == "/api/v1/customers"
+        || path.starts_with("/api/v1/customers/")
+        || path == "/api/v1/privacy/export"
+        || (path == "/api/v1/admin/consents" && method == Method::GET)
+}
+
+/// Normalized UFs of a new key (None: unrestricted)
+fn key_ufs(ufs: Option<&[String]>) -> Result<Option<Vec<&'static str>>, AppError> {
+    let Some(ufs) = ufs else {
+        return Ok(None);
+    };
+    let mut normalized = ufs
+        .iter()
+        .map(|uf| {
+            crate::residency::normalize_uf(uf)
+                .ok_or_else(|| AppError::BadRequest(format!("Invalid UF: {}", uf.trim())))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    normalized.sort_unstable();
+    normalized.dedup();
+    if normalized.is_empty() {
+        return Err(AppError::BadRequest(
+            "ufs must list at least one UF (omit it for an unrestricted key)".to_string(),
+        ));
+    }
+    Ok(Some(normalized))
+}
+
+/// Create a key, returning it with its plaintext (shown only this once)
+pub async fn create(pool: &PgPool, request: &ApiKeyCreate) -> Result<(ApiKey, String), AppError> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("API key name is required".to_string()));
+    }
+    let mut scopes: Vec<&str> = request.scopes.iter().map(|s| s.as_str()).collect();
+    scopes.sort_unstable();
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one scope (enrich, read, admin) is required".to_string(),
+        ));
+    }
+    let ufs = key_ufs(request.ufs.as_deref())?;
+
+    let key = generate_key();
+    let api_key: ApiKey = sqlx::query_as(
+        r#"
+        INSERT INTO api_keys (name, key_prefix, key_hash, scopes, ufs)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, name, key_prefix, scopes, ufs, created_at, last_used_at, revoked_at
+        "#,
+    )
+    .bind(name)
+    .bind(&key[..DISPLAY_PREFIX_LEN])
+    .bind(hash_key(&key))
+    .bind(&scopes)
+    .bind(&ufs)
+    .fetch_one(pool)
+    .timed("api_keys.create")
+    .await
+    .context(ErrorContext::db("api_keys.create").id("name", name))?;
+    Ok((api_key, key))
+}
+
+/// Every key, newest first (revoked ones included)
+pub async fn list(pool: &PgPool) -> Result<Vec<ApiKey>, AppError> {
+    sqlx::query_as(
+        r#"
+        SELECT id, name, key_prefix, scopes, ufs, created_at, last_used_at, revoked_at
+        FROM api_keys
+        ORDER BY created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .timed("api_keys.list")
+    .await
+    .context(ErrorContext::db("api_keys.list"))
+}
+
+/// Revoke a key and drop it from this instance's cache
+///
+/// Returns whether an active key was revoked.
+pub async fn revoke(state: &AppState, id: i64) -> Result<bool, AppError> {
+    let key_hash: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE api_keys SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING key_hash
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .timed("api_keys.revoke")
+    .await
+    .context(ErrorContext::db("api_keys.revoke").id("id", id))?;
+
+    match key_hash {
+        Some(key_hash) => {
+            state.caches.api_keys().invalidate(&key_hash).await;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Active key matching `key`, from the cache or the database
+pub async fn authenticate(
+    state: &AppState,
+    key: &str,
+) -> Result<Option<AuthenticatedKey>, AppError> {
+    let key_hash = hash_key(key);
+    if let Some(cached) = state.caches.api_keys().get(&key_hash).await {
+        return Ok(cached);
+    }
+
+    let row: Option<KeyRow> = sqlx::query_as(AUTHENTICATE_SQL)
+        .bind(&key_hash)
+        .fetch_optional(&state.db)
+        .timed("api_keys.authenticate")
+        .await
+        .context(ErrorContext::db("api_keys.authenticate"))?;
+    let authenticated = row.map(|row| AuthenticatedKey {
+        id: row.id,
+        name: row.name,
+        scopes: row.scopes.iter().filter_map(|s| Scope::parse(s)).collect(),
+        ufs: row.ufs,
+    });
+    state
+        .caches
+        .api_keys()
+        .insert(key_hash, authenticated.clone())
+        .await;
+    Ok(authenticated)
+}
+
+/// Row returned by `AUTHENTICATE_SQL`
+#[derive(FromRow)]
+struct KeyRow {
+    id: i64,
+    name: String,
+    scopes: Vec<String>,
+    ufs: Option<Vec<String>>,
+}
+
+pub(crate) const AUTHENTICATE_SQL: &str = r#"
+    UPDATE api_keys SET last_used_at = NOW()
+    WHERE key_hash = $1 AND revoked_at IS NULL
+    RETURNING id, name, scopes, ufs
+"#;
+
+/// Middleware checking `X-Api-Key` against the scope of the route
+pub async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(scope) = required_scope(request.method(), request.uri().path()) else {
+        return Ok(next.run(request).await);
+    };
+
+    let provided = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let Some(key) = provided else {
+        let admin_token =
+            scope == Scope::Admin && require_admin(&state.config, request.headers()).is_ok();
+        if state.config.api_keys_required && !admin_token {
+            return Err(AppError::Unauthorized(
+                "Missing X-Api-Key header".to_string(),
+            ));
+        }
+        return Ok(next.run(request).await);
+    };
+
+    let authenticated = authenticate(&state, key)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid API key".to_string()))?;
+    if !authenticated.allows(scope) {
+        tracing::warn!(
+            "API key {} ({}) denied {} {}: missing {} scope",
+            authenticated.id,
+            authenticated.name,
+            request.method(),
+            request.uri().path(),
+            scope.as_str()
+        );
+        return Err(AppError::Forbidden(format!(
+            "API key lacks the {} scope",
+            scope.as_str()
+        )));
+    }
+    if authenticated.ufs.is_some() && !region_scoped(request.method(), request.uri().path()) {
+        tracing::warn!(
+            "API key {} ({}) denied {} {}: restricted to {:?}",
+            authenticated.id,
+            authenticated.name,
+            request.method(),
+            request.uri().path(),
+            authenticated.ufs
+        );
+        return Err(AppError::Forbidden(
+            "API key is restricted to the customers of its states".to_string(),
+        ));
+    }
+    request.extensions_mut().insert(authenticated);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys() {
+        let key = generate_key();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_eq!(key.len(), KEY_PREFIX.len() + 2 * KEY_BYTES);
+        assert_ne!(key, generate_key());
+
+        let hash = hash_key(&key);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_key(&key));
+        assert_ne!(hash, hash_key(&generate_key()));
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(
+            required_scope(&Method::GET, "/api/v1/customers/42"),
+            Some(Scope::Read)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/api/v1/enrich/batch"),
+            Some(Scope::Enrich)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/v1/admin/api-keys"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/v1/privacy/export"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(required_scope(&Method::POST, "/api/v1/webhooks/c2s"), None);
+        assert_eq!(required_scope(&Method::GET, "/docs"), None);
+    }
+
+    #[test]
+    fn test_admin_scope_allows_everything() {
+        let key = |scopes: Vec<Scope>| AuthenticatedKey {
+            id: 1,
+            name: "partner".to_string(),
+            scopes,
+            ufs: None,
+        };
+        assert!(key(vec![Scope::Admin]).allows(Scope::Enrich));
+        assert!(key(vec![Scope::Read]).allows(Scope::Read));
+        assert!(!key(vec![Scope::Read]).allows(Scope::Enrich));
+        assert!(!key(vec![Scope::Enrich, Scope::Read]).allows(Scope::Admin));
+    }
+
+    #[test]
+    fn test_key_ufs() {
+        assert_eq!(key_ufs(None).unwrap(), None);
+        let ufs = vec!["sp".to_string(), " RJ".to_string(), "SP".to_string()];
+        assert_eq!(key_ufs(Some(&ufs)).unwrap(), Some(vec!["RJ", "SP"]));
+        assert!(key_ufs(Some(&[])).is_err());
+        assert!(key_ufs(Some(&["XX".to_string()])).is_err());
+    }
+
+    #[test]
+    fn test_region_scoped() {
+        assert!(region_scoped(&Method::GET, "/api/v1/customers"));
+        assert!(region_scoped(
+            &Method::GET,
+            "/api/v1/customers/42/contacts/history"
+        ));
+        assert!(region_scoped(&Method::GET, "/api/v1/privacy/export"));
+        assert!(region_scoped(&Method::GET, "/api/v1/admin/consents"));
+        assert!(!region_scoped(&Method::POST, "/api/v1/admin/consents"));
+        assert!(!region_scoped(&Method::GET, "/api/v1/contributor/customer"));
+        assert!(!region_scoped(&Method::GET, "/api/v1/work/modules/all"));
+    }
+}
@@ -0,0 +1,40 @@
+//! Services shared by every request
+//!
+//! Handlers used to assemble `EnrichmentService`, `CustomerService` and
+//! `EnrichmentStorage` from the pool, clients and caches of `AppState` on each
+//! request, every call site wiring (or forgetting) the caches. They are now
+//! built once at startup, caches attached, and shared through
+//! `AppState::services`. Clients of tenants with their own provider contract
+//! are still built per lead (see `tenant_credentials`).
+
+use crate::caches::Caches;
+use crate::clients::WorkApi;
+use crate::db_storage::EnrichmentStorage;
+use crate::services::{CustomerService, EnrichmentService};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Service container built at startup
+pub struct Services {
+    /// Local data plus Work API, with the Work API and customer lookup caches
+    pub enrichment: EnrichmentService,
+    /// Local customer lookups, with the customer lookup cache
+    pub customers: CustomerService,
+    /// Writes of enriched parties, invalidating the lookup and contact caches
+    pub storage: EnrichmentStorage,
+}
+
+impl Services {
+    pub fn new(pool: &PgPool, work_api: Arc<dyn WorkApi>, caches: &Caches) -> Self {
+        Self {
+            enrichment: EnrichmentService::new(work_api, pool.clone())
+                .with_work_api_cache(caches.work_api().clone())
+                .with_lookup_cache(caches.customer_lookup().clone()),
+            customers: CustomerService::new(pool.clone())
+                .with_lookup_cache(caches.customer_lookup().clone()),
+            storage: EnrichmentStorage::new(pool.clone())
+                .with_lookup_cache(caches.customer_lookup().clone())
+                .with_contact_cache(caches.contact_to_cpf().clone()),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Audit trail of sensitive admin reads
+//!
+//! Endpoints exposing data otherwise only reachable in the database record
+//! each access in `admin_audit_log` (migration 051) before answering: the
+//! action, its subject (e.g. a party id), the caller and the request options.
+//! The data returned is never logged. A failed write fails the request, so
+//! nothing is read without a trace.
+
+use crate::api_keys::AuthenticatedKey;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// Raw provider payload of a party (`GET /api/v1/admin/parties/:id/raw-payload`)
+pub const READ_RAW_PAYLOAD: &str = "read_raw_payload";
+
+/// Caller as recorded in the log: its API key, else the admin token
+pub fn actor(key: Option<&AuthenticatedKey>) -> String {
+    match key {
+        Some(key) => format!("api_key:{} ({})", key.id, key.name),
+        None => "admin_token".to_string(),
+    }
+}
+
+/// Record an access, returning its log id
+pub async fn record(
+    pool: &PgPool,
+    action: &'static str,
+    subject: &str,
+    actor: &str,
+    details: &Value,
+) -> Result<i64, AppError> {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO admin_audit_log (action, subject, actor, details)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(action)
+    .bind(subject)
+    .bind(actor)
+    .bind(details)
+    .fetch_one(pool)
+    .timed("admin_audit_log.insert")
+    .await
+    .context(
+        ErrorContext::db("admin_audit_log.insert")
+            .id("action", action)
+            .id("subject", subject),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_keys::Scope;
+
+    #[test]
+    fn test_actor() {
+        let key = AuthenticatedKey {
+            id: 7,
+            name: "data-team".to_string(),
+            scopes: vec![Scope::Admin],
+            ufs: None,
+        };
+        assert_eq!(actor(Some(&key)), "api_key:7 (data-team)");
+        assert_eq!(actor(None), "admin_token");
+    }
+}
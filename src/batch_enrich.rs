@@ -0,0 +1,322 @@
+//! Batch customer enrichment
+//!
+//! `POST /api/v1/enrich/batch` enriches up to `MAX_BATCH_SIZE` customers
+//! given by CPF, phone and/or email (the Make.com replacement pushes 50–200
+//! leads at once) and answers with a result per item instead of failing the
+//! whole batch. Each item is:
+//!
+//! - validated (a valid CPF, or a valid phone or email),
+//! - resolved to a CPF: given, found in the local database, or looked up in
+//!   Diretrix (scored against the item's name when it has one),
+//! - enriched like `POST /api/v1/enrich` (local data, Work API, cache when
+//!   the Work API is down).
+//!
+//! Items run concurrently, `WORK_API_MAX_CONCURRENCY` at a time, and every
+//! Work API call still waits on the shared limiter (`rate_limit`). With a
+//! `callback_url`, the batch runs in the background (see `batch_jobs`).
+
+use crate::admin_handler::require_admin;
+use crate::api_keys::AuthenticatedKey;
+use crate::batch_jobs::{self, BatchRequest};
+use crate::enrichment::{check_cpf, find_cpf_via_diretrix, is_valid_email, validate_br_phone};
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use crate::models::{CustomerQueryParams, UnifiedCustomerResponse};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Maximum items per batch request
+pub const MAX_BATCH_SIZE: usize = 200;
+
+/// One customer to enrich
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchEnrichItem {
+    /// Used to score Diretrix candidates, never to search
+    pub name: Option<String>,
+    pub cpf: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Request body of `POST /api/v1/enrich/batch`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchEnrichRequest {
+    pub items: Vec<BatchEnrichItem>,
+    /// Run in the background and POST the summary here when done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+/// Outcome of one item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Enriched,
+    NotFound,
+    Invalid,
+    Failed,
+}
+
+/// Result of one item, in request order
+#[derive(Debug, Serialize)]
+pub struct BatchEnrichResult {
+    pub index: usize,
+    pub status: ItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpf: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer: Option<UnifiedCustomerResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An item with its identifiers validated and normalized
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidItem {
+    pub name: Option<String>,
+    pub cpf: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Validate an item: a given CPF must be valid, and one usable identifier is required
+///
+/// An invalid phone or email next to a usable identifier is dropped.
+pub fn validate_item(item: &BatchEnrichItem) -> Result<ValidItem, String> {
+    let present = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    let cpf = match present(&item.cpf) {
+        Some(cpf) => {
+            check_cpf(&cpf).map_err(|rejection| format!("Invalid CPF ({})", rejection.as_str()))?;
+            Some(cpf.chars().filter(char::is_ascii_digit).collect())
+        }
+        None => None,
+    };
+    let phone = present(&item.phone).and_then(|phone| {
+        let (is_valid, normalized) = validate_br_phone(&phone);
+        is_valid.then_some(normalized)
+    });
+    let email = present(&item.email)
+        .filter(|email| is_valid_email(email))
+        .map(|email| email.to_lowercase());
+
+    if cpf.is_none() && phone.is_none() && email.is_none() {
+        return Err("A valid CPF, phone or email is required".to_string());
+    }
+    Ok(ValidItem {
+        name: present(&item.name),
+        cpf,
+        phone,
+        email,
+    })
+}
+
+/// POST /api/v1/enrich/batch
+///
+/// Enriches the items concurrently (X-Admin-Token required). Always answers
+/// 200 with per-item results; counts by status are in `summary`. With a
+/// `callback_url`, answers 202 with the job running the batch.
+pub async fn enrich_batch(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
+    headers: HeaderMap,
+    Json(mut request): Json<BatchEnrichRequest>,
+) -> Result<Response, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    if request.items.is_empty() {
+        return Err(AppError::BadRequest("No items in batch".to_string()));
+    }
+    if request.items.len() > MAX_BATCH_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "Batch too large: {} items (max {})",
+            request.items.len(),
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    if let Some(callback_url) = request.callback_url.take() {
+        return batch_jobs::accept(
+            &state,
+            key.map(|Extension(key)| key.id),
+            BatchRequest::Enrichment(request),
+            callback_url,
+        )
+        .await;
+    }
+    Ok(Json(enrich_items(state, request.items).await).into_response())
+}
+
+/// Enrich the items of a batch, returning the endpoint's response
+///
+/// Takes the state and items by value: each item future owns what it uses,
+/// so the whole future stays `Send` (handlers and spawned workers need it).
+pub async fn enrich_items(state: Arc<AppState>, items: Vec<BatchEnrichItem>) -> serde_json::Value {
+    let concurrency = crate::rate_limit::work_api_limiter().concurrency();
+    tracing::info!(
+        "Batch enrichment: {} item(s), {} at a time",
+        items.len(),
+        concurrency
+    );
+    let results: Vec<BatchEnrichResult> = stream::iter(items.into_iter().enumerate())
+        .map(|(index, item)| {
+            let state = state.clone();
+            async move { enrich_one(&state, index, &item).await }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let count = |status: ItemStatus| results.iter().filter(|r| r.status == status).count();
+    let summary = serde_json::json!({
+        "total": results.len(),
+        "enriched": count(ItemStatus::Enriched),
+        "not_found": count(ItemStatus::NotFound),
+        "invalid": count(ItemStatus::Invalid),
+        "failed": count(ItemStatus::Failed),
+        "degraded": results
+            .iter()
+            .filter(|r| r.customer.as_ref().is_some_and(|c| c.metadata.degraded))
+            .count(),
+    });
+    tracing::info!("Batch enrichment done: {}", summary);
+
+    serde_json::json!({
+        "summary": summary,
+        "results": results,
+    })
+}
+
+/// Resolve and enrich one item
+async fn enrich_one(state: &AppState, index: usize, item: &BatchEnrichItem) -> BatchEnrichResult {
+    let result = |status, cpf, customer, error| BatchEnrichResult {
+        index,
+        status,
+        cpf,
+        customer,
+        error,
+    };
+
+    let item = match validate_item(item) {
+        Ok(item) => item,
+        Err(reason) => return result(ItemStatus::Invalid, None, None, Some(reason)),
+    };
+
+    let cpf = match resolve_cpf(state, &item).await {
+        Ok(Some(cpf)) => cpf,
+        Ok(None) => {
+            return result(
+                ItemStatus::NotFound,
+                None,
+                None,
+                Some("No CPF found for the contacts".to_string()),
+            )
+        }
+        Err(e) => return result(ItemStatus::Failed, None, None, Some(e.to_string())),
+    };
+
+    let params = CustomerQueryParams {
+        name: None,
+        phone: None,
+        email: None,
+        cpf: Some(cpf.clone()),
+    };
+    match state
+        .services
+        .enrichment
+        .get_customer_unified(&params)
+        .await
+    {
+        Ok(customer) => result(ItemStatus::Enriched, Some(cpf), Some(customer), None),
+        Err(e @ AppError::NotFound(_)) => {
+            result(ItemStatus::NotFound, Some(cpf), None, Some(e.to_string()))
+        }
+        Err(e) => result(ItemStatus::Failed, Some(cpf), None, Some(e.to_string())),
+    }
+}
+
+/// CPF of an item: given, from a known party, or from Diretrix
+async fn resolve_cpf(state: &AppState, item: &ValidItem) -> Result<Option<String>, AppError> {
+    if let Some(cpf) = &item.cpf {
+        return Ok(Some(cpf.clone()));
+    }
+
+    let known = state
+        .services
+        .customers
+        .find_customer(&CustomerQueryParams {
+            name: None,
+            phone: item.phone.clone(),
+            email: item.email.clone(),
+            cpf: None,
+        })
+        .await?;
+    if let Some((party, _)) = known {
+        return Ok(Some(party.cpf_cnpj));
+    }
+
+    let lookup = find_cpf_via_diretrix(
+        item.name.as_deref(),
+        item.phone.as_deref(),
+        item.email.as_deref(),
+        state.diretrix.as_ref(),
+        state.config.identity_match_threshold,
+    )
+    .await?;
+    Ok(lookup.cpfs.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(cpf: Option<&str>, phone: Option<&str>, email: Option<&str>) -> BatchEnrichItem {
+        BatchEnrichItem {
+            name: None,
+            cpf: cpf.map(str::to_string),
+            phone: phone.map(str::to_string),
+            email: email.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_valid_cpf_is_normalized() {
+        let valid = validate_item(&item(Some("529.982.247-25"), None, None)).unwrap();
+        assert_eq!(valid.cpf.as_deref(), Some("52998224725"));
+    }
+
+    #[test]
+    fn test_invalid_identifiers() {
+        assert!(validate_item(&item(None, None, None)).is_err());
+        assert!(validate_item(&item(Some("  "), Some(""), None)).is_err());
+        let err = validate_item(&item(Some("52998224700"), None, Some("a@b.com"))).unwrap_err();
+        assert!(err.contains("checksum"));
+    }
+
+    #[test]
+    fn test_unusable_contact_is_dropped() {
+        let valid = validate_item(&item(None, Some("123"), Some("Joao@Example.com"))).unwrap();
+        assert_eq!(valid.phone, None);
+        assert_eq!(valid.email.as_deref(), Some("joao@example.com"));
+    }
+
+    #[test]
+    fn test_handler_mounts_on_router() {
+        // Only compiles while the handler future is `Send`
+        let _router: axum::Router<Arc<AppState>> =
+            axum::Router::new().route("/api/v1/enrich/batch", axum::routing::post(enrich_batch));
+    }
+}
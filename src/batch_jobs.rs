@@ -0,0 +1,487 @@
+//! Batch requests run in the background, with a completion callback
+//!
+//! `POST /api/v1/c2s/leads/batch` (CSV imports) and `POST /api/v1/enrich/batch`
+//! accept an optional `callback_url`. With one, the batch is stored as a
+//! `batch` job (see `jobs`) and the endpoint answers 202 with the job id
+//! instead of blocking for minutes. A background worker per instance runs
+//! the batch, stores the usual response as the job's result, then POSTs the
+//! callback with the summary and a link to the results (`GET /api/v1/jobs/:id`,
+//! under `PUBLIC_BASE_URL`), so callers do not have to poll.
+//!
+//! The callback URL is POSTed to from inside our network: it must resolve to
+//! public addresses only (`check_callback_url`, checked again before each
+//! delivery) and redirects are not followed.
+//!
+//! Callbacks are signed when `BATCH_CALLBACK_SECRET` is set: `X-Signature`
+//! holds `sha256=<hex HMAC-SHA256 of the body>`. A failed delivery is retried
+//! `CALLBACK_ATTEMPTS` times; the outcome is recorded as the job's progress
+//! and the results stay available for polling either way.
+
+use crate::batch_enrich::{self, BatchEnrichRequest};
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use crate::jobs::{self, BATCH};
+use crate::lead_import::{self, BatchLeadRequest};
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use url::{Host, Url};
+
+/// Runs of a batch whose worker was lost before it is marked 'failed'
+///
+/// Re-running is safe: imports skip leads created by the first run.
+const MAX_ATTEMPTS: i32 = 2;
+
+/// Batches run at once per instance
+const WORKERS: usize = 1;
+
+/// Idle workers look for queued batches at this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between sweeps of abandoned and old completed batches
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Callback deliveries before giving up
+const CALLBACK_ATTEMPTS: u32 = 3;
+
+/// Timeout of one callback delivery
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A batch request, as received by its endpoint
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "batch", rename_all = "snake_case")]
+pub enum BatchRequest {
+    LeadImport(BatchLeadRequest),
+    Enrichment(BatchEnrichRequest),
+}
+
+impl BatchRequest {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchRequest::LeadImport(_) => "lead_import",
+            BatchRequest::Enrichment(_) => "enrichment",
+        }
+    }
+}
+
+/// Payload of a `batch` job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub request: BatchRequest,
+    pub callback_url: String,
+}
+
+/// Path of a job's status and results
+pub fn status_path(job_id: i64) -> String {
+    format!("/api/v1/jobs/{}", job_id)
+}
+
+/// Queue a batch for an API key and answer 202 with its job
+///
+/// The callback URL must be an absolute http(s) URL to a public host (see
+/// `check_callback_url`).
+pub async fn accept(
+    state: &AppState,
+    api_key_id: Option<i64>,
+    request: BatchRequest,
+    callback_url: String,
+) -> Result<Response, AppError> {
+    let callback_url = check_callback_url(&callback_url).await?;
+    let batch = request.name();
+    let payload = serde_json::to_value(BatchJob {
+        request,
+        callback_url,
+    })
+    .map_err(|e| AppError::InternalError(format!("Failed to serialize job: {}", e)))?;
+    let job_id = jobs::enqueue(&state.db, BATCH, &payload, MAX_ATTEMPTS, api_key_id).await?;
+
+    let status_url = status_path(job_id);
+    tracing::info!("Batch {} queued as job #{}", batch, job_id);
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, status_url.clone())],
+        Json(json!({
+            "job_id": job_id,
+            "batch": batch,
+            "status": "queued",
+            "status_url": status_url,
+        })),
+    )
+        .into_response())
+}
+
+/// Normalized callback URL, if it is an absolute http(s) URL to a public host
+///
+/// Callback and subscriber URLs are POSTed to from inside our network, so
+/// `localhost`, `*.internal` and `*.local` names are refused, and every
+/// address the host resolves to must be public (`is_public_ip`).
+pub async fn check_callback_url(raw: &str) -> Result<String, AppError> {
+    let url = parse_callback_url(raw)?;
+    ensure_public_host(&url).await?;
+    Ok(url.to_string())
+}
+
+/// Parse a callback URL: absolute http(s) with a host, not an internal name
+fn parse_callback_url(raw: &str) -> Result<Url, AppError> {
+    let url = Url::parse(raw.trim())
+        .map_err(|e| AppError::BadRequest(format!("Invalid callback_url: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(AppError::BadRequest(
+            "callback_url must be an http(s) URL".to_string(),
+        ));
+    }
+    if let Some(Host::Domain(name)) = url.host() {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        let internal = [".localhost", ".internal", ".local"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix));
+        if internal || name == "localhost" {
+            return Err(AppError::BadRequest(format!(
+                "callback_url host {} is internal",
+                name
+            )));
+        }
+    }
+    Ok(url)
+}
+
+/// Fail unless every address of the URL's host is public
+///
+/// Also checked before each delivery, as DNS may have changed since.
+pub async fn ensure_public_host(url: &Url) -> Result<(), AppError> {
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(name)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            tokio::net::lookup_host((name, port))
+                .await
+                .map_err(|e| {
+                    AppError::BadRequest(format!("callback_url host {} not found: {}", name, e))
+                })?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    if addresses.is_empty() || !addresses.iter().all(|ip| is_public_ip(*ip)) {
+        return Err(AppError::BadRequest(format!(
+            "callback_url must point to a public address ({} resolves to {:?})",
+            url.host_str().unwrap_or_default(),
+            addresses
+        )));
+    }
+    Ok(())
+}
+
+/// Whether an address is routable on the internet
+///
+/// Refuses loopback, private (RFC 1918, IPv6 unique local), link-local (cloud
+/// metadata at 169.254.169.254), shared (100.64.0.0/10), unspecified,
+/// broadcast, multicast and documentation ranges.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of the body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    format!("sha256={}", hex::encode(hmac::sign(&key, body).as_ref()))
+}
+
+/// Spawn the workers running background batches
+pub fn spawn_workers(state: Arc<AppState>) {
+    // No redirects: a public callback could otherwise bounce into our network
+    let client = reqwest::Client::builder()
+        .timeout(CALLBACK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default();
+
+    for index in 0..WORKERS {
+        let state = state.clone();
+        let client = client.clone();
+        let worker = jobs::worker_id(index);
+        tokio::spawn(async move {
+            loop {
+                match jobs::claim(&state.db, BATCH, &worker).await {
+                    Ok(Some(job)) => run_job(state.clone(), client.clone(), job).await,
+                    Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Err(e) => {
+                        tracing::error!("Failed to claim a batch job: {}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match jobs::sweep(&state.db, BATCH).await {
+                Ok(0) => {}
+                Ok(failed) => {
+                    tracing::warn!("{} batch job(s) failed: worker lost on every run", failed)
+                }
+                Err(e) => tracing::warn!("Failed to sweep batch jobs: {}", e),
+            }
+        }
+    });
+    tracing::info!("✓ {} batch worker(s) started", WORKERS);
+}
+
+/// Run one claimed batch, record its result and call back
+async fn run_job(state: Arc<AppState>, client: reqwest::Client, job: jobs::Job) {
+    let batch = match serde_json::from_value::<BatchJob>(job.payload.clone()) {
+        Ok(batch) => batch,
+        Err(e) => {
+            // Without a payload there is no callback URL to report to
+            tracing::error!("Batch job #{} has an invalid payload: {}", job.id, e);
+            let error = format!("Invalid job payload: {}", e);
+            if let Err(e) = jobs::fail(&state.db, &job, &error).await {
+                tracing::error!("Failed to mark batch job #{} as failed: {}", job.id, e);
+            }
+            return;
+        }
+    };
+
+    let name = batch.request.name();
+    tracing::info!(
+        "Running batch job #{} ({}, run {}/{})",
+        job.id,
+        name,
+        job.attempts,
+        job.max_attempts
+    );
+    let run = async {
+        match batch.request {
+            BatchRequest::LeadImport(request) => {
+                lead_import::import_leads(state.clone(), request).await
+            }
+            BatchRequest::Enrichment(request) => {
+                batch_enrich::enrich_items(state.clone(), request.items).await
+            }
+        }
+    };
+    let response = jobs::with_heartbeat(&state.db, &job, run).await;
+
+    let results_url = format!(
+        "{}{}",
+        state.config.public_base_url.as_deref().unwrap_or_default(),
+        status_path(job.id)
+    );
+    let callback = match jobs::complete(&state.db, &job, Some(&response)).await {
+        // Claimed again by another worker, which reports its own run
+        Ok(false) => return,
+        Ok(true) => json!({
+            "job_id": job.id,
+            "batch": name,
+            "status": "completed",
+            "summary": response["summary"],
+            "results_url": results_url,
+        }),
+        Err(e) => {
+            // The results are lost: tell the caller rather than leave it waiting
+            tracing::error!("Failed to store results of batch job #{}: {}", job.id, e);
+            if let Err(e) = jobs::fail(&state.db, &job, &e.chain_message()).await {
+                tracing::error!("Failed to mark batch job #{} as failed: {}", job.id, e);
+            }
+            json!({
+                "job_id": job.id,
+                "batch": name,
+                "status": "failed",
+                "summary": response["summary"],
+                "error": "Results could not be stored",
+            })
+        }
+    };
+
+    let progress = match deliver_callback(&state, &client, &batch.callback_url, &callback).await {
+        Ok(()) => "callback_delivered",
+        Err(e) => {
+            tracing::warn!("Callback of batch job #{} not delivered: {}", job.id, e);
+            "callback_failed"
+        }
+    };
+    if let Err(e) = jobs::set_progress(&state.db, job.id, progress).await {
+        tracing::warn!("Failed to record callback of batch job #{}: {}", job.id, e);
+    }
+}
+
+/// POST the callback body, retrying failed deliveries
+async fn deliver_callback(
+    state: &AppState,
+    client: &reqwest::Client,
+    url: &str,
+    body: &Value,
+) -> Result<(), AppError> {
+    let body = serde_json::to_vec(body)
+        .map_err(|e| AppError::InternalError(format!("Failed to serialize callback: {}", e)))?;
+    let parsed = Url::parse(url)
+        .map_err(|e| AppError::InternalError(format!("Stored callback_url invalid: {}", e)))?;
+    ensure_public_host(&parsed).await?;
+
+    let mut last_error = String::new();
+    for attempt in 1..=CALLBACK_ATTEMPTS {
+        let mut request = client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(secret) = &state.config.batch_callback_secret {
+            request = request.header("X-Signature", sign(secret, &body));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => last_error = format!("HTTP {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < CALLBACK_ATTEMPTS {
+            tracing::debug!(
+                "Callback to {} failed ({}), attempt {}/{}",
+                url,
+                last_error,
+                attempt,
+                CALLBACK_ATTEMPTS
+            );
+            tokio::time::sleep(Duration::from_secs(5 * 3u64.pow(attempt - 1))).await;
+        }
+    }
+
+    Err(AppError::ExternalApiError(format!(
+        "Callback to {} failed after {} attempts: {}",
+        url, CALLBACK_ATTEMPTS, last_error
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch_enrich::BatchEnrichItem;
+
+    #[test]
+    fn test_run_job_future_is_spawnable() {
+        // Only compiles while the job future is `Send + 'static`
+        fn assert_spawnable<F: std::future::Future + Send + 'static>(_: F) {}
+        fn check(state: Arc<AppState>, client: reqwest::Client, job: jobs::Job) {
+            assert_spawnable(run_job(state, client, job));
+        }
+        let _ = check;
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = serde_json::to_value(BatchJob {
+            request: BatchRequest::Enrichment(BatchEnrichRequest {
+                items: vec![BatchEnrichItem {
+                    cpf: Some("52998224725".to_string()),
+                    ..Default::default()
+                }],
+                callback_url: None,
+            }),
+            callback_url: "https://hooks.example.com/done".to_string(),
+        })
+        .unwrap();
+        assert_eq!(payload["request"]["batch"], "enrichment");
+        assert_eq!(payload["request"]["items"][0]["cpf"], "52998224725");
+
+        let batch: BatchJob = serde_json::from_value(payload).unwrap();
+        assert_eq!(batch.request.name(), "enrichment");
+    }
+
+    #[test]
+    fn test_callback_url_must_be_http() {
+        assert_eq!(
+            parse_callback_url(" https://hooks.example.com/done ")
+                .unwrap()
+                .as_str(),
+            "https://hooks.example.com/done"
+        );
+        assert!(parse_callback_url("ftp://hooks.example.com").is_err());
+        assert!(parse_callback_url("/relative").is_err());
+    }
+
+    #[test]
+    fn test_internal_names_are_refused() {
+        assert!(parse_callback_url("http://localhost:8080/hook").is_err());
+        assert!(parse_callback_url("http://db.internal/hook").is_err());
+        assert!(parse_callback_url("http://Redis.Internal./hook").is_err());
+        assert!(parse_callback_url("http://printer.local/hook").is_err());
+        assert!(parse_callback_url("https://internal.example.com/hook").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_callback_url_must_be_public() {
+        assert_eq!(
+            check_callback_url("https://8.8.8.8/done").await.unwrap(),
+            "https://8.8.8.8/done"
+        );
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://10.0.0.5/hook",
+            "http://172.16.3.4/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fdaa::3]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(check_callback_url(url).await.is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_public_ips() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(is_public_ip(ip("8.8.8.8")));
+        assert!(is_public_ip(ip("2001:4860:4860::8888")));
+        assert!(!is_public_ip(ip("100.64.0.1")));
+        assert!(!is_public_ip(ip("0.0.0.0")));
+        assert!(!is_public_ip(ip("fe80::1")));
+        assert!(!is_public_ip(ip("224.0.0.1")));
+    }
+
+    #[test]
+    fn test_signature_verifies() {
+        let body = br#"{"job_id":7}"#;
+        let signature = sign("secret", body);
+        let hex_mac = signature.strip_prefix("sha256=").unwrap();
+
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        assert!(hmac::verify(&key, body, &hex::decode(hex_mac).unwrap()).is_ok());
+        assert_ne!(signature, sign("other", body));
+    }
+}
@@ -0,0 +1,61 @@
+use dotenvy::dotenv;
+use rust_c2s_api::phonetic::phonetic_key;
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use uuid::Uuid;
+
+/// Parties updated per round trip
+const BATCH_SIZE: i64 = 1000;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await?;
+
+    tracing::info!("Backfilling core.parties.phonetic_name (migration 022)...");
+
+    let mut total = 0u64;
+    loop {
+        let batch: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, full_name FROM core.parties
+             WHERE phonetic_name IS NULL AND full_name IS NOT NULL
+             LIMIT $1",
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(&pool)
+        .await?;
+
+        if batch.is_empty() {
+            break;
+        }
+
+        // Names without letters get an empty key so they are not selected again
+        let (ids, keys): (Vec<Uuid>, Vec<String>) = batch
+            .iter()
+            .map(|(id, name)| (*id, phonetic_key(name)))
+            .unzip();
+
+        let result = sqlx::query(
+            "UPDATE core.parties p
+             SET phonetic_name = k.key
+             FROM UNNEST($1::uuid[], $2::text[]) AS k(id, key)
+             WHERE p.id = k.id",
+        )
+        .bind(&ids)
+        .bind(&keys)
+        .execute(&pool)
+        .await?;
+
+        total += result.rows_affected();
+        tracing::info!("Updated {} parties so far", total);
+    }
+
+    tracing::info!("Backfill complete. {} parties updated.", total);
+    Ok(())
+}
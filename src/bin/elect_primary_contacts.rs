@@ -0,0 +1,60 @@
+use dotenvy::dotenv;
+use rust_c2s_api::primary_contact::elect_primary_contacts;
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use uuid::Uuid;
+
+/// Parties re-elected per round trip
+const BATCH_SIZE: i64 = 500;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await?;
+
+    tracing::info!("Re-electing primary contacts of every party...");
+
+    let mut parties = 0usize;
+    let mut changed = 0u64;
+    let mut after = Uuid::nil();
+    loop {
+        let batch: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT party_id FROM core.party_contacts
+             WHERE party_id > $1
+             ORDER BY party_id
+             LIMIT $2",
+        )
+        .bind(after)
+        .bind(BATCH_SIZE)
+        .fetch_all(&pool)
+        .await?;
+
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = *last;
+
+        changed += elect_primary_contacts(&pool, &batch)
+            .await
+            .map_err(|e| e.chain_message())?;
+        parties += batch.len();
+        tracing::info!(
+            "{} parties processed, {} contacts changed so far",
+            parties,
+            changed
+        );
+    }
+
+    tracing::info!(
+        "Election complete. {} parties, {} contacts changed.",
+        parties,
+        changed
+    );
+    Ok(())
+}
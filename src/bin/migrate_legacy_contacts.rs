@@ -0,0 +1,233 @@
+//! Consolidate the legacy contact link tables into `core.party_contacts`
+//!
+//! The old JSON importer (`docs/database/examples/import_json_to_db.rs`)
+//! stored contacts in `app.emails` / `app.phones`, linked to parties through
+//! `core.party_emails` / `core.party_phones`, while the enrichment path reads
+//! and writes `core.party_contacts`. Like `migrate_legacy_entities`, this
+//! copies the legacy rows over:
+//!
+//! - emails are trimmed and lowercased, phones reduced to their national
+//!   digits (DDD + number); invalid values are skipped and counted,
+//! - rows carry their provenance in `source` (`legacy:app.emails`,
+//!   `legacy:app.phones`) and no `last_confirmed_at`, since no provider
+//!   confirmed them,
+//! - a contact the party already has is left as is,
+//! - primary contacts of the touched parties are re-elected.
+//!
+//! Safe to re-run. Link tables that no longer exist (migrations 013 / 018)
+//! are skipped. `--dry-run` only counts what would be copied.
+
+use dotenvy::dotenv;
+use rust_c2s_api::enrichment::is_valid_email;
+use rust_c2s_api::primary_contact::elect_primary_contacts;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+/// Parties migrated per round trip
+const BATCH_SIZE: i64 = 500;
+
+/// A legacy link table and the contacts it points to
+struct LegacySource {
+    kind: &'static str,
+    link_table: &'static str,
+    value_table: &'static str,
+    /// Parties linked, after `$1`, at most `$2`
+    parties_sql: &'static str,
+    /// Raw values of the parties in `$1`
+    values_sql: &'static str,
+    normalize: fn(&str) -> Option<String>,
+    provenance: &'static str,
+}
+
+const SOURCES: &[LegacySource] = &[
+    LegacySource {
+        kind: "email",
+        link_table: "core.party_emails",
+        value_table: "app.emails",
+        parties_sql: "SELECT DISTINCT pe.party_id FROM core.party_emails pe
+             JOIN core.parties p ON p.id = pe.party_id
+             WHERE pe.party_id > $1
+             ORDER BY pe.party_id
+             LIMIT $2",
+        values_sql: "SELECT pe.party_id, e.email FROM core.party_emails pe
+             JOIN app.emails e ON e.id = pe.email_id
+             WHERE pe.party_id = ANY($1) AND e.email IS NOT NULL",
+        normalize: normalize_email,
+        provenance: "legacy:app.emails",
+    },
+    LegacySource {
+        kind: "phone",
+        link_table: "core.party_phones",
+        value_table: "app.phones",
+        parties_sql: "SELECT DISTINCT pp.party_id FROM core.party_phones pp
+             JOIN core.parties p ON p.id = pp.party_id
+             WHERE pp.party_id > $1
+             ORDER BY pp.party_id
+             LIMIT $2",
+        values_sql: "SELECT pp.party_id, ph.number FROM core.party_phones pp
+             JOIN app.phones ph ON ph.id = pp.phone_id
+             WHERE pp.party_id = ANY($1) AND ph.number IS NOT NULL",
+        normalize: normalize_phone,
+        provenance: "legacy:app.phones",
+    },
+];
+
+/// Counts of one legacy source
+#[derive(Debug, Default)]
+struct Report {
+    parties: usize,
+    read: usize,
+    invalid: usize,
+    inserted: u64,
+    primaries_changed: u64,
+}
+
+/// Email as stored in `party_contacts`, `None` when not a plausible address
+fn normalize_email(raw: &str) -> Option<String> {
+    let email = raw.trim().to_lowercase();
+    is_valid_email(&email).then_some(email)
+}
+
+/// National digits of a Brazilian phone (DDD + number), country code dropped
+fn normalize_phone(raw: &str) -> Option<String> {
+    let digits: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+    let national = match digits.len() {
+        12 | 13 if digits.starts_with("55") => &digits[2..],
+        _ => digits.as_str(),
+    };
+    let national = national.trim_start_matches('0');
+    (10..=11)
+        .contains(&national.len())
+        .then(|| national.to_string())
+}
+
+async fn table_exists(pool: &PgPool, table: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(table)
+        .fetch_one(pool)
+        .await
+}
+
+async fn migrate_source(
+    pool: &PgPool,
+    source: &LegacySource,
+    dry_run: bool,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::default();
+    let mut after = Uuid::nil();
+    loop {
+        let parties: Vec<Uuid> = sqlx::query_scalar(source.parties_sql)
+            .bind(after)
+            .bind(BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+        let Some(last) = parties.last() else {
+            break;
+        };
+        after = *last;
+        report.parties += parties.len();
+
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(source.values_sql)
+            .bind(&parties)
+            .fetch_all(pool)
+            .await?;
+        report.read += rows.len();
+
+        let mut party_ids = Vec::with_capacity(rows.len());
+        let mut values = Vec::with_capacity(rows.len());
+        for (party_id, raw) in rows {
+            match (source.normalize)(&raw) {
+                Some(value) => {
+                    party_ids.push(party_id);
+                    values.push(value);
+                }
+                None => {
+                    tracing::debug!("Skipping invalid {} of party {}", source.kind, party_id);
+                    report.invalid += 1;
+                }
+            }
+        }
+
+        if dry_run || values.is_empty() {
+            continue;
+        }
+        let result = sqlx::query(
+            r#"
+            INSERT INTO core.party_contacts (
+                contact_id, party_id, contact_type, value,
+                is_primary, is_verified, is_whatsapp, source,
+                confidence, valid_from, valid_to, last_confirmed_at, created_at, updated_at
+            )
+            SELECT gen_random_uuid(), c.party_id, $3::text::core.contact_type_enum, c.value,
+                   false, false, false, $4, NULL, now(), NULL, NULL, now(), now()
+            FROM UNNEST($1::uuid[], $2::text[]) AS c(party_id, value)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&party_ids)
+        .bind(&values)
+        .bind(source.kind)
+        .bind(source.provenance)
+        .execute(pool)
+        .await?;
+        report.inserted += result.rows_affected();
+
+        report.primaries_changed += elect_primary_contacts(pool, &parties)
+            .await
+            .map_err(|e| e.chain_message())?;
+        tracing::info!(
+            "{}: {} parties processed, {} contacts inserted so far",
+            source.link_table,
+            report.parties,
+            report.inserted
+        );
+    }
+    Ok(report)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let dry_run = env::args().any(|arg| arg == "--dry-run");
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await?;
+
+    tracing::info!(
+        "Consolidating legacy contact links into core.party_contacts{}...",
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    for source in SOURCES {
+        if !table_exists(&pool, source.link_table).await?
+            || !table_exists(&pool, source.value_table).await?
+        {
+            tracing::info!(
+                "{} / {} not found, nothing to migrate",
+                source.link_table,
+                source.value_table
+            );
+            continue;
+        }
+
+        let report = migrate_source(&pool, source, dry_run).await?;
+        tracing::info!(
+            "{} complete. Parties: {}, {}s read: {}, invalid: {}, inserted: {}, primaries changed: {}",
+            source.link_table,
+            report.parties,
+            source.kind,
+            report.read,
+            report.invalid,
+            report.inserted,
+            report.primaries_changed
+        );
+    }
+
+    Ok(())
+}
@@ -283,6 +283,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Migrated: {}", migrated_count);
     tracing::info!("Skipped (already exists): {}", skipped_count);
     tracing::info!("Errors: {}", error_count);
+    tracing::info!(
+        "Contacts linked through app.emails / app.phones are consolidated by migrate_legacy_contacts"
+    );
 
     Ok(())
 }
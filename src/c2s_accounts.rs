@@ -0,0 +1,259 @@
+//! Multiple C2S accounts
+//!
+//! Some lead sources belong to a second C2S company. Extra accounts are
+//! configured in `C2S_ACCOUNTS` (the `C2S_BASE_URL`/`C2S_TOKEN` pair is the
+//! `default` account) and `C2S_ACCOUNT_RULES` selects the account by lead
+//! source, Google Ads campaign or form:
+//!
+//! ```json
+//! C2S_ACCOUNTS={"locacao": {"base_url": "https://api.contact2sale.com", "token": "..."}}
+//! C2S_ACCOUNT_RULES=[{"account": "locacao", "sources": ["WhatsApp"]},
+//!                    {"account": "locacao", "campaigns": ["21593847"]}]
+//! ```
+//!
+//! Leads created in (or received from) a non-default account are recorded in
+//! `c2s_lead_accounts` (migration 030) so every later message to the lead is
+//! sent through the same account.
+
+use crate::clients::C2sApi;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::services::C2SService;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Account configured by `C2S_BASE_URL` / `C2S_TOKEN`
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Credentials of an additional C2S account
+#[derive(Debug, Clone, Deserialize)]
+pub struct C2sAccount {
+    pub base_url: String,
+    pub token: String,
+}
+
+/// Rule selecting the account of a new lead (first match wins)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccountRule {
+    pub account: String,
+
+    /// Lead source names (case-insensitive, empty = any)
+    #[serde(default)]
+    pub sources: Vec<String>,
+
+    /// Google Ads campaign IDs (empty = any)
+    #[serde(default)]
+    pub campaigns: Vec<String>,
+
+    /// Google Ads form IDs (empty = any)
+    #[serde(default)]
+    pub forms: Vec<String>,
+}
+
+/// Where a lead comes from, for account selection
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeadOrigin<'a> {
+    pub source: Option<&'a str>,
+    pub campaign_id: Option<&'a str>,
+    pub form_id: Option<&'a str>,
+}
+
+impl AccountRule {
+    pub fn matches(&self, origin: &LeadOrigin<'_>) -> bool {
+        let source_ok = self.sources.is_empty()
+            || origin
+                .source
+                .is_some_and(|s| self.sources.iter().any(|r| r.eq_ignore_ascii_case(s)));
+        let campaign_ok = self.campaigns.is_empty()
+            || origin
+                .campaign_id
+                .is_some_and(|c| self.campaigns.iter().any(|r| r == c));
+        let form_ok = self.forms.is_empty()
+            || origin
+                .form_id
+                .is_some_and(|f| self.forms.iter().any(|r| r == f));
+        source_ok && campaign_ok && form_ok
+    }
+}
+
+/// Parse the additional accounts from JSON (empty string = none)
+pub fn parse_accounts(json: &str) -> anyhow::Result<HashMap<String, C2sAccount>> {
+    if json.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    let accounts: HashMap<String, C2sAccount> = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid C2S_ACCOUNTS JSON: {}", e))?;
+    for (name, account) in &accounts {
+        if name == DEFAULT_ACCOUNT {
+            anyhow::bail!("C2S_ACCOUNTS: '{}' is reserved for C2S_BASE_URL", name);
+        }
+        if !account.base_url.starts_with("http://") && !account.base_url.starts_with("https://") {
+            anyhow::bail!(
+                "C2S_ACCOUNTS: base_url of '{}' must be an http(s) URL",
+                name
+            );
+        }
+        if account.token.trim().is_empty() {
+            anyhow::bail!("C2S_ACCOUNTS: token of '{}' cannot be empty", name);
+        }
+    }
+    Ok(accounts)
+}
+
+/// Parse the account rules from JSON, checking they name configured accounts
+pub fn parse_rules(
+    json: &str,
+    accounts: &HashMap<String, C2sAccount>,
+) -> anyhow::Result<Vec<AccountRule>> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let rules: Vec<AccountRule> = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid C2S_ACCOUNT_RULES JSON: {}", e))?;
+    if let Some(rule) = rules
+        .iter()
+        .find(|r| r.account != DEFAULT_ACCOUNT && !accounts.contains_key(&r.account))
+    {
+        anyhow::bail!(
+            "C2S_ACCOUNT_RULES: unknown account '{}' (not in C2S_ACCOUNTS)",
+            rule.account
+        );
+    }
+    Ok(rules)
+}
+
+/// Account of a new lead (`default` when no rule matches)
+pub fn select_account<'a>(rules: &'a [AccountRule], origin: &LeadOrigin<'_>) -> &'a str {
+    rules
+        .iter()
+        .find(|rule| rule.matches(origin))
+        .map(|rule| rule.account.as_str())
+        .unwrap_or(DEFAULT_ACCOUNT)
+}
+
+/// C2S client for an account (unknown names fall back to the default account, `AppState::c2s`)
+pub fn service_for(state: &AppState, account: &str) -> Arc<dyn C2sApi> {
+    match state.config.c2s_accounts.get(account) {
+        Some(c2s) => Arc::new(C2SService::with_credentials(
+            c2s.base_url.clone(),
+            c2s.token.clone(),
+        )),
+        None => state.c2s.clone(),
+    }
+}
+
+/// Record the account a lead lives in (non-default accounts only, first one wins)
+pub async fn record_lead_account(
+    pool: &PgPool,
+    lead_id: &str,
+    account: &str,
+) -> Result<(), AppError> {
+    if account == DEFAULT_ACCOUNT {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO c2s_lead_accounts (lead_id, account)
+        VALUES ($1, $2)
+        ON CONFLICT (lead_id) DO NOTHING
+        "#,
+    )
+    .bind(lead_id)
+    .bind(account)
+    .execute(pool)
+    .timed("c2s_lead_accounts.insert")
+    .await
+    .context(format!(
+        "Failed to record C2S account of lead_id: {}",
+        lead_id
+    ))?;
+    Ok(())
+}
+
+/// Account recorded for a lead, `default` when none
+pub async fn lead_account(state: &AppState, lead_id: &str) -> Result<String, AppError> {
+    if state.config.c2s_accounts.is_empty() {
+        return Ok(DEFAULT_ACCOUNT.to_string());
+    }
+    let account: Option<(String,)> =
+        sqlx::query_as("SELECT account FROM c2s_lead_accounts WHERE lead_id = $1")
+            .bind(lead_id)
+            .fetch_optional(&state.db)
+            .timed("c2s_lead_accounts.select")
+            .await
+            .context(format!(
+                "Failed to load C2S account of lead_id: {}",
+                lead_id
+            ))?;
+    Ok(account
+        .map(|(a,)| a)
+        .unwrap_or_else(|| DEFAULT_ACCOUNT.to_string()))
+}
+
+/// Send a message through the account the lead lives in, returns the HTTP status
+pub async fn send_message(state: &AppState, lead_id: &str, body: &str) -> Result<u16, AppError> {
+    let account = lead_account(state, lead_id).await?;
+    if account != DEFAULT_ACCOUNT {
+        tracing::info!(
+            "Sending message to lead {} via C2S account {}",
+            lead_id,
+            account
+        );
+    }
+    service_for(state, &account)
+        .send_message(lead_id, body)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts() -> HashMap<String, C2sAccount> {
+        parse_accounts(r#"{"locacao": {"base_url": "https://c2s.example.com", "token": "t"}}"#)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_accounts_and_rules() {
+        let accounts = accounts();
+        assert_eq!(accounts["locacao"].base_url, "https://c2s.example.com");
+        assert!(parse_accounts(r#"{"default": {"base_url": "https://x", "token": "t"}}"#).is_err());
+        assert!(parse_accounts(r#"{"x": {"base_url": "ftp://x", "token": "t"}}"#).is_err());
+
+        assert!(parse_rules(
+            r#"[{"account": "locacao", "sources": ["WhatsApp"]}]"#,
+            &accounts
+        )
+        .is_ok());
+        assert!(parse_rules(r#"[{"account": "vendas"}]"#, &accounts).is_err());
+    }
+
+    #[test]
+    fn test_select_account() {
+        let rules = parse_rules(
+            r#"[{"account": "locacao", "sources": ["whatsapp"]},
+                {"account": "locacao", "sources": ["Google Ads"], "campaigns": ["42"]}]"#,
+            &accounts(),
+        )
+        .unwrap();
+
+        let whatsapp = LeadOrigin {
+            source: Some("WhatsApp"),
+            ..Default::default()
+        };
+        assert_eq!(select_account(&rules, &whatsapp), "locacao");
+
+        let campaign = |id| LeadOrigin {
+            source: Some("Google Ads"),
+            campaign_id: Some(id),
+            form_id: Some("7"),
+        };
+        assert_eq!(select_account(&rules, &campaign("42")), "locacao");
+        assert_eq!(select_account(&rules, &campaign("43")), DEFAULT_ACCOUNT);
+        assert_eq!(select_account(&[], &whatsapp), DEFAULT_ACCOUNT);
+    }
+}
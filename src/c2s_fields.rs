@@ -0,0 +1,142 @@
+//! Enrichment facts written to C2S lead custom fields
+//!
+//! The chat message is not filterable inside C2S. When `C2S_CUSTOM_FIELDS`
+//! is set, key facts of the enriched lead are also written to custom fields
+//! of the C2S lead, using the field names configured per fact:
+//!
+//! ```json
+//! {"income_band": "faixa_renda", "credit_score": "score_credito",
+//!  "verified_phone": "telefone_verificado", "wealth_level": "patrimonio"}
+//! ```
+
+use crate::enrichment::{validate_br_phone, EnrichedLeadSummary};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Enrichment fact that can be mapped to a C2S custom field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrichmentFact {
+    /// Monthly income band (e.g. "10k-20k")
+    IncomeBand,
+    /// Credit score (scoreCSBA)
+    CreditScore,
+    /// Lead phone in E.164 when it is a valid BR number
+    VerifiedPhone,
+    /// Wealth segment tag (e.g. "patrimonio-alto")
+    WealthLevel,
+    /// State (UF)
+    Uf,
+    /// Price-to-income of the inquired property
+    PriceToIncome,
+}
+
+/// Fact -> C2S custom field name
+pub type FieldMapping = BTreeMap<EnrichmentFact, String>;
+
+/// Parse the field mapping from JSON (empty string = disabled)
+pub fn parse_mapping(json: &str) -> anyhow::Result<FieldMapping> {
+    if json.trim().is_empty() {
+        return Ok(FieldMapping::new());
+    }
+    let mapping: FieldMapping = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid C2S_CUSTOM_FIELDS JSON: {}", e))?;
+    if let Some((fact, _)) = mapping.iter().find(|(_, field)| field.trim().is_empty()) {
+        anyhow::bail!("C2S_CUSTOM_FIELDS: empty field name for {:?}", fact);
+    }
+    Ok(mapping)
+}
+
+/// Monthly income band used for filtering in C2S
+pub fn income_band(monthly_income: f64) -> &'static str {
+    match monthly_income {
+        i if i < 5_000.0 => "ate-5k",
+        i if i < 10_000.0 => "5k-10k",
+        i if i < 20_000.0 => "10k-20k",
+        i if i < 50_000.0 => "20k-50k",
+        _ => "acima-50k",
+    }
+}
+
+/// Custom field values for an enriched lead
+///
+/// Facts without a value (no income, invalid phone...) are left out so an
+/// existing value in C2S is not overwritten with an empty one.
+pub fn custom_field_values(
+    mapping: &FieldMapping,
+    summary: &EnrichedLeadSummary,
+) -> Map<String, Value> {
+    let mut fields = Map::new();
+    for (fact, field) in mapping {
+        let value = match fact {
+            EnrichmentFact::IncomeBand => summary.income.map(|i| json!(income_band(i))),
+            EnrichmentFact::CreditScore => summary.credit_score.map(|s| json!(s)),
+            EnrichmentFact::VerifiedPhone => summary
+                .phone
+                .as_deref()
+                .map(validate_br_phone)
+                .filter(|(valid, _)| *valid)
+                .map(|(_, normalized)| json!(normalized)),
+            EnrichmentFact::WealthLevel => Some(json!(summary.wealth.as_tag())),
+            EnrichmentFact::Uf => summary.uf.as_ref().map(|uf| json!(uf)),
+            EnrichmentFact::PriceToIncome => summary
+                .affordability
+                .as_ref()
+                .map(|a| json!((a.price_to_income * 10.0).round() / 10.0)),
+        };
+        if let Some(value) = value {
+            fields.insert(field.clone(), value);
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrichment::WealthSegment;
+
+    fn summary() -> EnrichedLeadSummary {
+        EnrichedLeadSummary {
+            lead_id: "lead-1".to_string(),
+            name: "Maria".to_string(),
+            phone: Some("11987654321".to_string()),
+            email: None,
+            cpf: Some("12345678901".to_string()),
+            income: Some(12_000.0),
+            credit_score: Some(780),
+            wealth: WealthSegment::Alto,
+            affordability: None,
+            uf: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_mapping() {
+        let mapping =
+            parse_mapping(r#"{"income_band":"faixa_renda","wealth_level":"patrimonio"}"#).unwrap();
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(mapping[&EnrichmentFact::IncomeBand], "faixa_renda");
+
+        assert!(parse_mapping("").unwrap().is_empty());
+        assert!(parse_mapping(r#"{"unknown_fact":"x"}"#).is_err());
+        assert!(parse_mapping(r#"{"uf":" "}"#).is_err());
+    }
+
+    #[test]
+    fn test_custom_field_values_skip_missing_facts() {
+        let mapping = parse_mapping(
+            r#"{"income_band":"faixa_renda","credit_score":"score","verified_phone":"telefone",
+                "wealth_level":"patrimonio","uf":"estado"}"#,
+        )
+        .unwrap();
+        let fields = custom_field_values(&mapping, &summary());
+
+        assert_eq!(fields["faixa_renda"], json!("10k-20k"));
+        assert_eq!(fields["score"], json!(780));
+        assert_eq!(fields["telefone"], json!("+5511987654321"));
+        assert_eq!(fields["patrimonio"], json!("patrimonio-alto"));
+        assert!(!fields.contains_key("estado"));
+    }
+}
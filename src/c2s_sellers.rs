@@ -0,0 +1,151 @@
+//! Directory of C2S sellers and teams
+//!
+//! Routing rules, Google Ads forms and `C2S_DEFAULT_SELLER_ID` reference
+//! sellers by their C2S id. The directory lists the live sellers of an
+//! account (`C2sApi::list_sellers`), cached per account for
+//! `SELLERS_TTL_SECS` (`Caches::c2s_sellers`), and is exposed through
+//! `GET /api/v1/c2s/sellers` so configuration can be checked against it.
+
+use crate::c2s_accounts;
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// How long a directory is served from the cache
+pub const SELLERS_TTL_SECS: u64 = 900;
+
+/// A C2S seller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct C2SSeller {
+    pub id: String,
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    /// Team (C2S "equipe") the seller belongs to
+    pub team: Option<String>,
+    pub active: bool,
+}
+
+/// Sellers of a JSON:API page (`data: [{id, attributes}]`)
+///
+/// Entries without an id are skipped; `active` defaults to true when C2S
+/// does not send it.
+pub fn parse_sellers(page: &Value) -> Vec<C2SSeller> {
+    let Some(entries) = page.get("data").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let id = match entry.get("id")? {
+                Value::String(id) => id.clone(),
+                Value::Number(id) => id.to_string(),
+                _ => return None,
+            };
+            let attributes = entry.get("attributes").unwrap_or(&Value::Null);
+            let text = |key: &str| {
+                attributes
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            };
+            // Team as a plain name or a nested object
+            let team = match attributes.get("team") {
+                Some(Value::Object(team)) => {
+                    team.get("name").and_then(Value::as_str).map(str::to_string)
+                }
+                Some(Value::String(team)) => Some(team.clone()),
+                _ => None,
+            };
+            Some(C2SSeller {
+                name: text("name").unwrap_or_else(|| id.clone()),
+                id,
+                email: text("email"),
+                phone: text("phone"),
+                team,
+                active: attributes
+                    .get("active")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true),
+            })
+        })
+        .collect()
+}
+
+/// Sellers of an account, from the cache unless `refresh` is set
+pub async fn directory(
+    state: &AppState,
+    account: &str,
+    refresh: bool,
+) -> Result<Arc<Vec<C2SSeller>>, AppError> {
+    let cache = state.caches.c2s_sellers();
+    if !refresh {
+        let cached = cache.get(account).await;
+        crate::metrics::record_cache_lookup("c2s_sellers", cached.is_some());
+        if let Some(sellers) = cached {
+            return Ok(sellers);
+        }
+    }
+
+    let sellers = Arc::new(
+        c2s_accounts::service_for(state, account)
+            .list_sellers()
+            .await?,
+    );
+    tracing::info!(
+        "C2S seller directory of account '{}': {} seller(s)",
+        account,
+        sellers.len()
+    );
+    cache.insert(account.to_string(), sellers.clone()).await;
+    Ok(sellers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_sellers() {
+        let page = json!({
+            "data": [
+                {
+                    "id": "s-1",
+                    "type": "seller",
+                    "attributes": {
+                        "name": "Maria Corretora",
+                        "email": "maria@mbras.com.br",
+                        "phone": "",
+                        "team": {"id": "t-1", "name": "Alto Padrão"},
+                        "active": false
+                    }
+                },
+                {"id": 42, "attributes": {"name": " João ", "team": "Litoral"}},
+                {"type": "seller", "attributes": {"name": "Sem id"}}
+            ]
+        });
+
+        let sellers = parse_sellers(&page);
+        assert_eq!(sellers.len(), 2);
+        assert_eq!(sellers[0].id, "s-1");
+        assert_eq!(sellers[0].email.as_deref(), Some("maria@mbras.com.br"));
+        assert_eq!(sellers[0].phone, None);
+        assert_eq!(sellers[0].team.as_deref(), Some("Alto Padrão"));
+        assert!(!sellers[0].active);
+        assert_eq!(sellers[1].id, "42");
+        assert_eq!(sellers[1].name, "João");
+        assert_eq!(sellers[1].team.as_deref(), Some("Litoral"));
+        assert!(sellers[1].active);
+    }
+
+    #[test]
+    fn test_parse_sellers_without_data() {
+        assert!(parse_sellers(&json!({"errors": []})).is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+//! Adaptive TTL of the Work API response cache
+//!
+//! Responses are fresh for `WORK_API_CACHE_TTL_SECS` (1h) while the Work API
+//! is healthy. When it degrades, cached responses stay usable longer, up to
+//! `WORK_API_CACHE_MAX_TTL_SECS` (24h): serving older data beats serving
+//! errors. The TTL is at its bound while the Work API breaker is open or
+//! half-open, and otherwise grows linearly with the error rate of the last
+//! calls between `ELEVATED_ERROR_RATE` and `DEGRADED_ERROR_RATE`.
+//!
+//! moka keeps entries for the bound; the effective TTL is applied on read from
+//! the entry age (`ValidatedCacheEntry::created_at`), so it also covers
+//! entries cached earlier and shrinks back as soon as the provider recovers.
+//! Lookups are counted per key category (`all`, `module`, `cep`); hit rates
+//! and the effective TTL are exported at `/metrics` and in
+//! `GET /api/v1/admin/caches`.
+
+use crate::cache_validator::ValidatedCacheEntry;
+use crate::circuit_breaker::{BreakerState, Provider};
+use crate::config::Config;
+use moka::future::Cache;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+/// TTL while the Work API is healthy, unless configured
+pub const DEFAULT_TTL_SECS: u64 = 3600;
+
+/// Bound of the TTL while the Work API is degraded, unless configured
+pub const DEFAULT_MAX_TTL_SECS: u64 = 86400;
+
+/// Recent Work API calls the error rate is computed on
+const ERROR_WINDOW: usize = 50;
+
+/// Fewer recent calls than this say nothing about the provider health
+const MIN_CALLS: usize = 10;
+
+/// Error rate from which the TTL starts to lengthen
+const ELEVATED_ERROR_RATE: f64 = 0.1;
+
+/// Error rate at which the TTL reaches its bound
+const DEGRADED_ERROR_RATE: f64 = 0.5;
+
+static POLICY: OnceLock<TtlPolicy> = OnceLock::new();
+
+/// Lookups by key category and result ("hit", "expired" or "miss")
+static LOOKUPS: LazyLock<Mutex<BTreeMap<&'static str, CategoryLookups>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Bounds of the Work API cache TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlPolicy {
+    pub base_secs: u64,
+    pub max_secs: u64,
+}
+
+impl Default for TtlPolicy {
+    fn default() -> Self {
+        Self {
+            base_secs: DEFAULT_TTL_SECS,
+            max_secs: DEFAULT_MAX_TTL_SECS,
+        }
+    }
+}
+
+/// Why the effective TTL has its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtlReason {
+    Healthy,
+    ElevatedErrors,
+    BreakerOpen,
+    BreakerHalfOpen,
+}
+
+impl TtlReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TtlReason::Healthy => "healthy",
+            TtlReason::ElevatedErrors => "elevated_errors",
+            TtlReason::BreakerOpen => "breaker_open",
+            TtlReason::BreakerHalfOpen => "breaker_half_open",
+        }
+    }
+}
+
+/// TTL applied to Work API cache reads at a point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveTtl {
+    pub ttl_secs: u64,
+    pub base_secs: u64,
+    pub max_secs: u64,
+    pub reason: TtlReason,
+    /// Error rate of the last Work API calls (None with too few calls)
+    pub error_rate: Option<f64>,
+}
+
+impl TtlPolicy {
+    /// Effective TTL given the breaker state and the recent (calls, error rate)
+    pub fn effective(&self, breaker: BreakerState, recent: Option<(usize, f64)>) -> EffectiveTtl {
+        let error_rate = recent
+            .filter(|(calls, _)| *calls >= MIN_CALLS)
+            .map(|(_, rate)| rate);
+
+        let (ttl_secs, reason) = match breaker {
+            BreakerState::Open => (self.max_secs, TtlReason::BreakerOpen),
+            BreakerState::HalfOpen => (self.max_secs, TtlReason::BreakerHalfOpen),
+            BreakerState::Closed => match error_rate {
+                Some(rate) if rate >= ELEVATED_ERROR_RATE => {
+                    let degradation = ((rate - ELEVATED_ERROR_RATE)
+                        / (DEGRADED_ERROR_RATE - ELEVATED_ERROR_RATE))
+                        .min(1.0);
+                    let extra = self.max_secs.saturating_sub(self.base_secs) as f64 * degradation;
+                    (
+                        self.base_secs + extra.round() as u64,
+                        TtlReason::ElevatedErrors,
+                    )
+                }
+                _ => (self.base_secs, TtlReason::Healthy),
+            },
+        };
+
+        EffectiveTtl {
+            ttl_secs,
+            base_secs: self.base_secs,
+            max_secs: self.max_secs,
+            reason,
+            error_rate,
+        }
+    }
+}
+
+/// Configure the process-wide TTL bounds (called once at startup, before `Caches::new`)
+pub fn init(config: &Config) {
+    let policy = TtlPolicy {
+        base_secs: config.work_api_cache_ttl_secs,
+        max_secs: config.work_api_cache_max_ttl_secs,
+    };
+    if POLICY.set(policy).is_err() {
+        tracing::warn!("Work API cache TTL policy already initialized");
+    }
+}
+
+/// Process-wide TTL bounds (defaults when `init` was not called)
+pub fn policy() -> TtlPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Effective TTL from the current Work API breaker state and error rate
+pub fn current() -> EffectiveTtl {
+    let provider = Provider::WorkApi;
+    policy().effective(
+        provider.breaker().state(),
+        crate::provider_stats::recent_error_rate(provider.name(), ERROR_WINDOW),
+    )
+}
+
+/// Category of a Work API cache key ("all:{cpf}" → "all")
+pub fn category(key: &str) -> &'static str {
+    match key.split(':').next() {
+        Some("all") => "all",
+        Some("module") => "module",
+        Some("cep") => "cep",
+        _ => "other",
+    }
+}
+
+/// Cached Work API response still fresh under the effective TTL
+///
+/// Older entries count as expired but stay cached: they become fresh again
+/// if the provider degrades before the bound.
+pub async fn get(cache: &Cache<String, String>, key: &str) -> Option<ValidatedCacheEntry> {
+    lookup(cache, key, current().ttl_secs).await
+}
+
+/// Any valid cached response up to the bound, for degraded responses once a
+/// Work API call has failed
+pub async fn get_stale(cache: &Cache<String, String>, key: &str) -> Option<ValidatedCacheEntry> {
+    lookup(cache, key, policy().max_secs).await
+}
+
+async fn lookup(
+    cache: &Cache<String, String>,
+    key: &str,
+    ttl_secs: u64,
+) -> Option<ValidatedCacheEntry> {
+    let entry = match cache.get(key).await {
+        Some(cached) => ValidatedCacheEntry::deserialize_validated(&cached),
+        None => None,
+    };
+    let result = match &entry {
+        None => "miss",
+        Some(entry) if entry.age_secs() as u64 > ttl_secs => "expired",
+        Some(_) => "hit",
+    };
+
+    record_lookup(category(key), result);
+    crate::metrics::record_cache_lookup("work_api", result == "hit");
+    entry.filter(|_| result == "hit")
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CategoryLookups {
+    hits: u64,
+    expired: u64,
+    misses: u64,
+}
+
+/// Lookups of one key category since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryStats {
+    pub category: &'static str,
+    pub hits: u64,
+    /// Entries found but older than the effective TTL
+    pub expired: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+fn record_lookup(category: &'static str, result: &'static str) {
+    let mut lookups = LOOKUPS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = lookups.entry(category).or_default();
+    match result {
+        "hit" => stats.hits += 1,
+        "expired" => stats.expired += 1,
+        _ => stats.misses += 1,
+    }
+}
+
+/// Lookup counts and hit rate per key category, sorted by category
+pub fn category_stats() -> Vec<CategoryStats> {
+    let lookups = LOOKUPS.lock().unwrap_or_else(|e| e.into_inner());
+    lookups
+        .iter()
+        .map(|(category, stats)| {
+            let total = stats.hits + stats.expired + stats.misses;
+            CategoryStats {
+                category,
+                hits: stats.hits,
+                expired: stats.expired,
+                misses: stats.misses,
+                hit_rate: if total == 0 {
+                    0.0
+                } else {
+                    stats.hits as f64 / total as f64
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_POLICY: TtlPolicy = TtlPolicy {
+        base_secs: 3600,
+        max_secs: 86400,
+    };
+
+    #[test]
+    fn test_healthy_provider_keeps_base_ttl() {
+        let ttl = TEST_POLICY.effective(BreakerState::Closed, Some((50, 0.02)));
+        assert_eq!(ttl.ttl_secs, 3600);
+        assert_eq!(ttl.reason, TtlReason::Healthy);
+        assert_eq!(ttl.error_rate, Some(0.02));
+
+        // Too few calls to judge, even if they all failed
+        let ttl = TEST_POLICY.effective(BreakerState::Closed, Some((3, 1.0)));
+        assert_eq!(ttl.ttl_secs, 3600);
+        assert_eq!(ttl.error_rate, None);
+        assert_eq!(
+            TEST_POLICY.effective(BreakerState::Closed, None).reason,
+            TtlReason::Healthy
+        );
+    }
+
+    #[test]
+    fn test_error_rate_lengthens_ttl_up_to_bound() {
+        // Halfway between the elevated and degraded rates
+        let ttl = TEST_POLICY.effective(BreakerState::Closed, Some((50, 0.3)));
+        assert_eq!(ttl.ttl_secs, 3600 + (86400 - 3600) / 2);
+        assert_eq!(ttl.reason, TtlReason::ElevatedErrors);
+
+        let ttl = TEST_POLICY.effective(BreakerState::Closed, Some((50, 0.9)));
+        assert_eq!(ttl.ttl_secs, 86400);
+    }
+
+    #[test]
+    fn test_breaker_not_closed_uses_bound() {
+        let ttl = TEST_POLICY.effective(BreakerState::Open, Some((50, 0.0)));
+        assert_eq!(ttl.ttl_secs, 86400);
+        assert_eq!(ttl.reason, TtlReason::BreakerOpen);
+
+        let ttl = TEST_POLICY.effective(BreakerState::HalfOpen, None);
+        assert_eq!(ttl.ttl_secs, 86400);
+        assert_eq!(ttl.reason, TtlReason::BreakerHalfOpen);
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(category("all:12345678909"), "all");
+        assert_eq!(category("module:protesto:12345678909"), "module");
+        assert_eq!(category("cep:01310100"), "cep");
+        assert_eq!(category("12345678909"), "other");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_counts_per_category() {
+        let cache: Cache<String, String> = Cache::new(10);
+        let key = "cep:01310100";
+        assert!(lookup(&cache, key, 3600).await.is_none());
+
+        cache
+            .insert(
+                key.to_string(),
+                ValidatedCacheEntry::new("{}".to_string()).serialize(),
+            )
+            .await;
+        let entry = lookup(&cache, key, 3600).await.expect("fresh entry");
+        assert_eq!(entry.data, "{}");
+
+        let stats = category_stats();
+        let cep = stats.iter().find(|s| s.category == "cep").unwrap();
+        assert!(cep.hits >= 1);
+        assert!(cep.misses >= 1);
+        assert!(cep.hit_rate > 0.0 && cep.hit_rate < 1.0);
+    }
+}
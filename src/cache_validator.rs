@@ -1,61 +1,125 @@
-use hex;
-use sha2::{Digest, Sha256};
-
-/// Validates cached data integrity using SHA-256 checksums
-///
-/// This module provides protection against cache poisoning by:
-/// 1. Generating a checksum when data is cached
-/// 2. Validating the checksum when data is retrieved
-/// 3. Rejecting corrupted or tampered data
-///
-/// # Security Model
-///
-/// - Uses SHA-256 for cryptographic hash generation
-/// - Stores checksum alongside cached data
-/// - Validates on retrieval to detect tampering
-/// - Falls back to fresh fetch if validation fails
+//! Validates cached data integrity using HMAC-SHA256 signatures
+//!
+//! This module provides protection against cache poisoning by:
+//! 1. Signing the data, schema version and creation time when data is cached
+//! 2. Verifying the signature when data is retrieved
+//! 3. Rejecting corrupted, tampered or cross-version data
+//!
+//! # Security Model
+//!
+//! - HMAC-SHA256 keyed with a server secret (`CACHE_SIGNING_SECRET`, or a
+//!   random per-process key when unset), so a forged entry needs the secret
+//!   and not just a recomputed hash
+//! - Entries written with another schema version (including the former
+//!   unsigned `checksum` entries) are rejected
+//! - `created_at` is covered by the signature, so the cache age is trustworthy
+//! - Falls back to fresh fetch if validation fails
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use std::sync::OnceLock;
+
+/// Layout version of cached entries, bumped when the cached data changes shape
+pub const CACHE_SCHEMA_VERSION: u32 = 2;
+
+static SIGNING_KEY: OnceLock<hmac::Key> = OnceLock::new();
+
+/// Configure the process-wide signing key (called once at startup)
+pub fn init_signing_key(config: &Config) {
+    let Some(secret) = config.cache_signing_secret.as_deref() else {
+        tracing::info!("CACHE_SIGNING_SECRET not set, cache entries signed with a per-process key");
+        return;
+    };
+    if SIGNING_KEY
+        .set(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()))
+        .is_err()
+    {
+        tracing::warn!("Cache signing key already initialized");
+    }
+}
+
+/// Process-wide signing key (random when `init_signing_key` set none)
+fn signing_key() -> &'static hmac::Key {
+    SIGNING_KEY.get_or_init(|| {
+        let rng = ring::rand::SystemRandom::new();
+        hmac::Key::generate(hmac::HMAC_SHA256, &rng).expect("failed to generate cache signing key")
+    })
+}
 
 /// Wrapper for cached data with integrity validation
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ValidatedCacheEntry {
     /// The actual cached data (JSON string)
     pub data: String,
-    /// SHA-256 checksum of the data (hex encoded)
-    pub checksum: String,
+    /// `CACHE_SCHEMA_VERSION` of the writer
+    pub schema_version: u32,
+    /// When the entry was created
+    pub created_at: DateTime<Utc>,
+    /// HMAC-SHA256 of version, creation time and data (hex encoded)
+    pub signature: String,
 }
 
 impl ValidatedCacheEntry {
-    /// Creates a new validated cache entry with computed checksum
+    /// Creates a new signed cache entry
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// let entry = ValidatedCacheEntry::new(r#"{"name": "John"}"#.to_string());
     /// cache.insert(key, entry.serialize()).await;
     /// ```
     pub fn new(data: String) -> Self {
-        let checksum = Self::compute_checksum(&data);
-        Self { data, checksum }
+        Self::signed_with(signing_key(), data)
     }
 
-    /// Computes SHA-256 checksum of the data
-    fn compute_checksum(data: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        hex::encode(hasher.finalize())
+    fn signed_with(key: &hmac::Key, data: String) -> Self {
+        let created_at = Utc::now();
+        let signature = Self::sign(key, CACHE_SCHEMA_VERSION, &created_at, &data);
+        Self {
+            data,
+            schema_version: CACHE_SCHEMA_VERSION,
+            created_at,
+            signature,
+        }
+    }
+
+    /// HMAC over `version|created_at|data`
+    fn sign(key: &hmac::Key, version: u32, created_at: &DateTime<Utc>, data: &str) -> String {
+        hex::encode(hmac::sign(key, &Self::signed_bytes(version, created_at, data)).as_ref())
+    }
+
+    fn signed_bytes(version: u32, created_at: &DateTime<Utc>, data: &str) -> Vec<u8> {
+        format!("{}|{}|{}", version, created_at.to_rfc3339(), data).into_bytes()
     }
 
     /// Validates the integrity of the cached data
     ///
-    /// Returns true if the checksum matches, false if tampered
+    /// Returns true if the entry has the current schema version and a valid
+    /// signature, false if tampered or written by another version
     pub fn is_valid(&self) -> bool {
-        let computed = Self::compute_checksum(&self.data);
-        computed == self.checksum
+        self.is_valid_with(signing_key())
+    }
+
+    fn is_valid_with(&self, key: &hmac::Key) -> bool {
+        if self.schema_version != CACHE_SCHEMA_VERSION {
+            return false;
+        }
+        let Ok(signature) = hex::decode(&self.signature) else {
+            return false;
+        };
+        let signed = Self::signed_bytes(self.schema_version, &self.created_at, &self.data);
+        hmac::verify(key, &signed, &signature).is_ok()
+    }
+
+    /// Seconds since the entry was cached
+    pub fn age_secs(&self) -> i64 {
+        (Utc::now() - self.created_at).num_seconds().max(0)
     }
 
     /// Serializes the entry for storage in cache
     ///
-    /// Returns JSON string with both data and checksum
+    /// Returns JSON string with data, metadata and signature
     pub fn serialize(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
     }
@@ -66,7 +130,7 @@ impl ValidatedCacheEntry {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// if let Some(cached) = cache.get(&key).await {
     ///     if let Some(valid_data) = ValidatedCacheEntry::deserialize_and_validate(&cached) {
     ///         // Use valid_data safely
@@ -76,15 +140,24 @@ impl ValidatedCacheEntry {
     /// }
     /// ```
     pub fn deserialize_and_validate(serialized: &str) -> Option<String> {
-        let entry: ValidatedCacheEntry = serde_json::from_str(serialized).ok()?;
+        Self::deserialize_validated(serialized).map(|entry| entry.data)
+    }
+
+    /// Deserializes and validates a cache entry, keeping its metadata
+    pub fn deserialize_validated(serialized: &str) -> Option<ValidatedCacheEntry> {
+        let Ok(entry) = serde_json::from_str::<ValidatedCacheEntry>(serialized) else {
+            // Unparseable or written before entries were signed
+            tracing::warn!("Cache validation failed: unreadable entry, refetching");
+            return None;
+        };
 
         if entry.is_valid() {
-            Some(entry.data)
+            Some(entry)
         } else {
-            // Checksum mismatch - cache poisoned
             tracing::warn!(
-                "Cache validation failed: checksum mismatch. Expected: {}, Data length: {}",
-                entry.checksum,
+                "Cache validation failed: bad signature or schema version {} (current {}), data length: {}",
+                entry.schema_version,
+                CACHE_SCHEMA_VERSION,
                 entry.data.len()
             );
             None
@@ -103,6 +176,8 @@ mod tests {
 
         assert!(entry.is_valid());
         assert_eq!(entry.data, data);
+        assert_eq!(entry.schema_version, CACHE_SCHEMA_VERSION);
+        assert_eq!(entry.age_secs(), 0);
     }
 
     #[test]
@@ -143,11 +218,40 @@ mod tests {
     }
 
     #[test]
-    fn test_checksum_consistency() {
-        let data = "test data".to_string();
-        let entry1 = ValidatedCacheEntry::new(data.clone());
-        let entry2 = ValidatedCacheEntry::new(data);
+    fn test_backdated_entry_rejected() {
+        let mut entry = ValidatedCacheEntry::new("data".to_string());
+        entry.created_at -= chrono::Duration::hours(1);
+
+        assert!(!entry.is_valid());
+    }
+
+    #[test]
+    fn test_other_schema_version_rejected() {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        let mut entry = ValidatedCacheEntry::signed_with(&key, "data".to_string());
+        entry.schema_version = CACHE_SCHEMA_VERSION - 1;
+        entry.signature =
+            ValidatedCacheEntry::sign(&key, entry.schema_version, &entry.created_at, &entry.data);
+
+        assert!(!entry.is_valid_with(&key));
+    }
+
+    #[test]
+    fn test_recomputed_hash_without_secret_rejected() {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        let forger = hmac::Key::new(hmac::HMAC_SHA256, b"guess");
+        let forged = ValidatedCacheEntry::signed_with(&forger, "forged".to_string());
+
+        assert!(!forged.is_valid_with(&key));
+    }
+
+    #[test]
+    fn test_unsigned_legacy_entry_rejected() {
+        let legacy = serde_json::json!({
+            "data": "{}",
+            "checksum": "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a",
+        });
 
-        assert_eq!(entry1.checksum, entry2.checksum);
+        assert!(ValidatedCacheEntry::deserialize_validated(&legacy.to_string()).is_none());
     }
 }
@@ -0,0 +1,269 @@
+//! In-process caches shared through `AppState`
+//!
+//! The caches are built here with their TTL and capacity, and handlers reach
+//! them through the typed accessors. Introspection for the admin endpoint
+//! (entry count, weighted size, entry snapshots) goes through `Caches` too, so
+//! nothing outside this module depends on moka internals.
+
+use crate::api_keys::AuthenticatedKey;
+use crate::c2s_sellers::{C2SSeller, SELLERS_TTL_SECS};
+use crate::enrichment::ExistingEnrichment;
+use moka::future::Cache;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Identifies one of the shared caches (path segment of the admin endpoint)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheName {
+    RecentCpf,
+    ProcessingLeads,
+    ContactToCpf,
+    WorkApi,
+    CustomerLookup,
+    C2sSellers,
+    ApiKeys,
+}
+
+/// Size of a cache at a point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: CacheName,
+    pub entries: u64,
+    pub weighted_size: u64,
+    pub max_capacity: Option<u64>,
+    pub ttl_secs: Option<u64>,
+}
+
+/// One cache entry as shown by the admin endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    pub key: String,
+    /// Short description of the value (Work API payloads are not dumped)
+    pub value: String,
+}
+
+#[derive(Clone)]
+pub struct Caches {
+    /// CPF → last processing timestamp (5 min TTL), global CPF deduplication
+    recent_cpf: Cache<String, i64>,
+    /// lead_id → processing start timestamp (5 min TTL), concurrent lead deduplication
+    processing_leads: Cache<String, i64>,
+    /// "phone:{digits}" / "email:{email}" → existing enrichment (24h TTL), `None` means checked and not found
+    /// Invalidated by `EnrichmentStorage` when contacts or parties are written
+    contact_to_cpf: Cache<String, Option<ExistingEnrichment>>,
+    /// "all:{cpf}" / "module:{module}:{cpf}" / "cep:{cep}" → Work API JSON response
+    /// Kept for the TTL bound (24h), read through `cache_ttl` which applies the effective TTL
+    work_api: Cache<String, String>,
+    /// "cpf:{cpf}" / "email:{email}" / "phone:{digits}" → party_id (60s TTL)
+    /// Invalidated by `EnrichmentStorage` when those identifiers are written
+    customer_lookup: Cache<String, Uuid>,
+    /// C2S account → seller directory (15 min TTL), refreshed on demand
+    c2s_sellers: Cache<String, Arc<Vec<C2SSeller>>>,
+    /// API key hash → active key (60s TTL), `None` means unknown or revoked
+    /// Invalidated by `api_keys::revoke` on the instance that revoked the key
+    api_keys: Cache<String, Option<AuthenticatedKey>>,
+}
+
+impl Default for Caches {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Caches {
+    pub fn new() -> Self {
+        Self {
+            recent_cpf: Cache::builder()
+                .time_to_live(Duration::from_secs(300))
+                .max_capacity(10_000)
+                .build(),
+            processing_leads: Cache::builder()
+                .time_to_live(Duration::from_secs(300))
+                .max_capacity(10_000)
+                .build(),
+            contact_to_cpf: Cache::builder()
+                .time_to_live(Duration::from_secs(86400))
+                .max_capacity(50_000)
+                .build(),
+            work_api: Cache::builder()
+                .time_to_live(Duration::from_secs(crate::cache_ttl::policy().max_secs))
+                .max_capacity(100_000)
+                .build(),
+            customer_lookup: Cache::builder()
+                .time_to_live(Duration::from_secs(60))
+                .max_capacity(10_000)
+                .build(),
+            c2s_sellers: Cache::builder()
+                .time_to_live(Duration::from_secs(SELLERS_TTL_SECS))
+                .max_capacity(100)
+                .build(),
+            api_keys: Cache::builder()
+                .time_to_live(Duration::from_secs(60))
+                .max_capacity(10_000)
+                .build(),
+        }
+    }
+
+    pub fn recent_cpf(&self) -> &Cache<String, i64> {
+        &self.recent_cpf
+    }
+
+    pub fn processing_leads(&self) -> &Cache<String, i64> {
+        &self.processing_leads
+    }
+
+    pub fn contact_to_cpf(&self) -> &Cache<String, Option<ExistingEnrichment>> {
+        &self.contact_to_cpf
+    }
+
+    pub fn work_api(&self) -> &Cache<String, String> {
+        &self.work_api
+    }
+
+    pub fn customer_lookup(&self) -> &Cache<String, Uuid> {
+        &self.customer_lookup
+    }
+
+    pub fn c2s_sellers(&self) -> &Cache<String, Arc<Vec<C2SSeller>>> {
+        &self.c2s_sellers
+    }
+
+    pub fn api_keys(&self) -> &Cache<String, Option<AuthenticatedKey>> {
+        &self.api_keys
+    }
+
+    /// Approximate number of entries (pending evictions may still be counted)
+    pub fn size(&self, name: CacheName) -> u64 {
+        match name {
+            CacheName::RecentCpf => self.recent_cpf.entry_count(),
+            CacheName::ProcessingLeads => self.processing_leads.entry_count(),
+            CacheName::ContactToCpf => self.contact_to_cpf.entry_count(),
+            CacheName::WorkApi => self.work_api.entry_count(),
+            CacheName::CustomerLookup => self.customer_lookup.entry_count(),
+            CacheName::C2sSellers => self.c2s_sellers.entry_count(),
+            CacheName::ApiKeys => self.api_keys.entry_count(),
+        }
+    }
+
+    /// Approximate weighted size (equals `size` without a weigher)
+    pub fn weighted_size(&self, name: CacheName) -> u64 {
+        match name {
+            CacheName::RecentCpf => self.recent_cpf.weighted_size(),
+            CacheName::ProcessingLeads => self.processing_leads.weighted_size(),
+            CacheName::ContactToCpf => self.contact_to_cpf.weighted_size(),
+            CacheName::WorkApi => self.work_api.weighted_size(),
+            CacheName::CustomerLookup => self.customer_lookup.weighted_size(),
+            CacheName::C2sSellers => self.c2s_sellers.weighted_size(),
+            CacheName::ApiKeys => self.api_keys.weighted_size(),
+        }
+    }
+
+    /// Sizes of every cache, after applying pending evictions
+    pub async fn stats(&self) -> Vec<CacheStats> {
+        vec![
+            stats_of(CacheName::RecentCpf, &self.recent_cpf).await,
+            stats_of(CacheName::ProcessingLeads, &self.processing_leads).await,
+            stats_of(CacheName::ContactToCpf, &self.contact_to_cpf).await,
+            stats_of(CacheName::WorkApi, &self.work_api).await,
+            stats_of(CacheName::CustomerLookup, &self.customer_lookup).await,
+            stats_of(CacheName::C2sSellers, &self.c2s_sellers).await,
+            stats_of(CacheName::ApiKeys, &self.api_keys).await,
+        ]
+    }
+
+    /// Up to `limit` entries of a cache (iteration order is unspecified)
+    pub fn snapshot(&self, name: CacheName, limit: usize) -> Vec<CacheEntry> {
+        match name {
+            CacheName::RecentCpf => snapshot_of(&self.recent_cpf, limit, |ts| ts.to_string()),
+            CacheName::ProcessingLeads => {
+                snapshot_of(&self.processing_leads, limit, |ts| ts.to_string())
+            }
+            CacheName::ContactToCpf => {
+                snapshot_of(&self.contact_to_cpf, limit, |found| match found {
+                    Some(existing) => format!("party {}", existing.party_id),
+                    None => "not found".to_string(),
+                })
+            }
+            CacheName::WorkApi => snapshot_of(&self.work_api, limit, |body| {
+                format!("{} bytes", body.len())
+            }),
+            CacheName::CustomerLookup => snapshot_of(&self.customer_lookup, limit, |party_id| {
+                party_id.to_string()
+            }),
+            CacheName::C2sSellers => snapshot_of(&self.c2s_sellers, limit, |sellers| {
+                format!("{} sellers", sellers.len())
+            }),
+            CacheName::ApiKeys => snapshot_of(&self.api_keys, limit, |key| match key {
+                Some(key) => format!("key {} ({})", key.id, key.name),
+                None => "invalid".to_string(),
+            }),
+        }
+    }
+}
+
+async fn stats_of<V>(name: CacheName, cache: &Cache<String, V>) -> CacheStats
+where
+    V: Clone + Send + Sync + 'static,
+{
+    cache.run_pending_tasks().await;
+    let policy = cache.policy();
+    CacheStats {
+        name,
+        entries: cache.entry_count(),
+        weighted_size: cache.weighted_size(),
+        max_capacity: policy.max_capacity(),
+        ttl_secs: policy.time_to_live().map(|ttl| ttl.as_secs()),
+    }
+}
+
+fn snapshot_of<V>(
+    cache: &Cache<String, V>,
+    limit: usize,
+    describe: impl Fn(&V) -> String,
+) -> Vec<CacheEntry>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    cache
+        .iter()
+        .take(limit)
+        .map(|(key, value)| CacheEntry {
+            key: key.to_string(),
+            value: describe(&value),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats_and_snapshot() {
+        let caches = Caches::new();
+        caches
+            .work_api()
+            .insert("all:123".to_string(), "{}".to_string())
+            .await;
+        caches
+            .processing_leads()
+            .insert("lead-1".to_string(), 42)
+            .await;
+
+        let stats = caches.stats().await;
+        let work_api = stats.iter().find(|s| s.name == CacheName::WorkApi).unwrap();
+        assert_eq!(work_api.entries, 1);
+        assert_eq!(work_api.max_capacity, Some(100_000));
+        assert_eq!(work_api.ttl_secs, Some(86400));
+        assert_eq!(caches.size(CacheName::ProcessingLeads), 1);
+        assert_eq!(caches.size(CacheName::RecentCpf), 0);
+
+        let entries = caches.snapshot(CacheName::WorkApi, 10);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "all:123");
+        assert_eq!(entries[0].value, "2 bytes");
+    }
+}
@@ -1,5 +1,9 @@
+use crate::errors::AppError;
 use failsafe::{backoff, failure_policy, Config};
-use std::time::Duration;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 /// Creates a circuit breaker for database operations to prevent cascading failures
 ///
@@ -14,7 +18,7 @@ use std::time::Duration;
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// let circuit_breaker = create_db_circuit_breaker();
 ///
 /// let result = circuit_breaker.call(async {
@@ -32,6 +36,215 @@ pub fn create_db_circuit_breaker() -> impl failsafe::CircuitBreaker {
     Config::new().failure_policy(failure_policy).build()
 }
 
+/// External providers protected by a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    WorkApi,
+    Diretrix,
+    C2s,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 3] = [Provider::WorkApi, Provider::Diretrix, Provider::C2s];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::WorkApi => "work_api",
+            Provider::Diretrix => "diretrix",
+            Provider::C2s => "c2s",
+        }
+    }
+
+    /// Process-wide breaker for this provider
+    pub fn breaker(&self) -> &'static ProviderBreaker {
+        static WORK_API: LazyLock<ProviderBreaker> = LazyLock::new(ProviderBreaker::default);
+        static DIRETRIX: LazyLock<ProviderBreaker> = LazyLock::new(ProviderBreaker::default);
+        static C2S: LazyLock<ProviderBreaker> = LazyLock::new(ProviderBreaker::default);
+
+        match self {
+            Provider::WorkApi => &WORK_API,
+            Provider::Diretrix => &DIRETRIX,
+            Provider::C2s => &C2S,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct BreakerInner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    open_duration: Duration,
+}
+
+/// Circuit breaker for an external provider with observable state
+///
+/// Same policy as the DB breaker: opens after 5 consecutive failures, then
+/// waits (exponential, 10s up to 60s) before letting a trial call through.
+#[derive(Debug)]
+pub struct ProviderBreaker {
+    failure_threshold: u32,
+    min_open: Duration,
+    max_open: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+impl Default for ProviderBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(10), Duration::from_secs(60))
+    }
+}
+
+/// Point-in-time view of a provider breaker (admin dashboard)
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerSnapshot {
+    pub provider: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub retry_in_secs: Option<u64>,
+}
+
+impl ProviderBreaker {
+    pub fn new(failure_threshold: u32, min_open: Duration, max_open: Duration) -> Self {
+        Self {
+            failure_threshold,
+            min_open,
+            max_open,
+            inner: Mutex::new(BreakerInner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn state(&self) -> BreakerState {
+        let inner = self.lock();
+        match inner.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if opened_at.elapsed() < inner.open_duration => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a call may go through (closed or half-open)
+    pub fn is_call_permitted(&self) -> bool {
+        self.state() != BreakerState::Open
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.open_duration = Duration::ZERO;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+
+        if let Some(opened_at) = inner.opened_at {
+            // Failed trial call while half-open: back off further
+            if opened_at.elapsed() >= inner.open_duration {
+                inner.open_duration = (inner.open_duration * 2).min(self.max_open);
+                inner.opened_at = Some(Instant::now());
+            }
+        } else if inner.consecutive_failures >= self.failure_threshold {
+            inner.open_duration = self.min_open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn snapshot(&self, provider: &str) -> BreakerSnapshot {
+        let state = self.state();
+        let inner = self.lock();
+        let retry_in_secs = match (state, inner.opened_at) {
+            (BreakerState::Open, Some(opened_at)) => Some(
+                inner
+                    .open_duration
+                    .saturating_sub(opened_at.elapsed())
+                    .as_secs(),
+            ),
+            _ => None,
+        };
+
+        BreakerSnapshot {
+            provider: provider.to_string(),
+            state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_in_secs,
+        }
+    }
+}
+
+/// Snapshots of all provider breakers
+pub fn provider_breaker_snapshots() -> Vec<BreakerSnapshot> {
+    Provider::ALL
+        .iter()
+        .map(|p| p.breaker().snapshot(p.name()))
+        .collect()
+}
+
+/// Run a provider call through its circuit breaker, recording latency
+///
+/// Fails fast with `ProviderUnavailable` while the breaker is open, and with
+/// `DeadlineExceeded` when the request deadline runs out before the call ends.
+pub async fn guarded<T, F>(provider: Provider, call: F) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>>,
+{
+    let breaker = provider.breaker();
+    if !breaker.is_call_permitted() {
+        tracing::warn!(
+            "Circuit breaker open for {}, skipping call",
+            provider.name()
+        );
+        crate::metrics::record_provider_call(provider.name(), "circuit_open", Duration::ZERO);
+        return Err(AppError::ProviderUnavailable(format!(
+            "{} circuit breaker open",
+            provider.name()
+        )));
+    }
+
+    // Only as long as the request deadline allows (see `deadline`)
+    let budget = crate::deadline::remaining(provider.name())?;
+    let start = Instant::now();
+    let result = match budget {
+        Some(budget) => match tokio::time::timeout(budget, call).await {
+            Ok(result) => result,
+            Err(_) => {
+                // The caller's budget, not a provider failure: the breaker is left alone
+                tracing::warn!(
+                    "{} call cancelled after {}ms: request deadline reached",
+                    provider.name(),
+                    budget.as_millis()
+                );
+                crate::metrics::record_provider_call(provider.name(), "deadline", start.elapsed());
+                return Err(crate::deadline::exceeded(provider.name()));
+            }
+        },
+        None => call.await,
+    };
+    let latency = start.elapsed();
+    crate::provider_stats::record_call(provider.name(), latency, result.is_ok());
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    crate::metrics::record_provider_call(provider.name(), outcome, latency);
+
+    match &result {
+        Ok(_) => breaker.record_success(),
+        Err(_) => breaker.record_failure(),
+    }
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,4 +280,36 @@ mod tests {
 
         assert_eq!(result.unwrap(), 42);
     }
+
+    #[test]
+    fn test_provider_breaker_opens_and_recovers() {
+        let breaker = ProviderBreaker::new(3, Duration::from_millis(20), Duration::from_millis(80));
+
+        for _ in 0..3 {
+            assert!(breaker.is_call_permitted());
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.is_call_permitted());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.snapshot("test").consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_provider_breaker_failed_trial_reopens() {
+        let breaker = ProviderBreaker::new(1, Duration::from_millis(20), Duration::from_millis(80));
+
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.snapshot("test").retry_in_secs.is_some());
+    }
 }
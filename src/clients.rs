@@ -0,0 +1,413 @@
+//! Provider client abstractions
+//!
+//! Handlers and the enrichment workflow talk to Work API, Diretrix and C2S
+//! through these traits (held as trait objects in `AppState`), so they can be
+//! unit-tested with in-memory mocks. The real clients live in `services`;
+//! their base URLs come from `Config` (`WORK_API_BASE_URL`, ...), so tests
+//! can also point them at wiremock servers (see `testsupport`). An alternate
+//! enrichment provider is plugged in by implementing `WorkApi`.
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde_json::Value;
+
+use crate::c2s_sellers::C2SSeller;
+use crate::errors::AppError;
+use crate::lead_dedup::C2sLeadMatch;
+use crate::models::{CompanyModules, WorkApiCompleteResponse};
+use crate::services::{
+    C2SLeadResponse, C2SService, DiretrixPersonData, DiretrixPersonSearch, DiretrixService,
+    WorkApiService,
+};
+
+/// Work API (CPF/CNPJ enrichment)
+#[async_trait]
+pub trait WorkApi: Send + Sync {
+    /// All modules of a CPF (`modulo=cpf`)
+    async fn fetch_all_modules(&self, documento: &str)
+        -> Result<WorkApiCompleteResponse, AppError>;
+
+    /// A single module, `None` when the Work API has no data for it
+    async fn fetch_module(&self, module: &str, consulta: &str) -> Result<Option<Value>, AppError>;
+
+    /// Company modules (sócios, faturamento presumido, situação cadastral) of a CNPJ
+    async fn fetch_company_modules(&self, cnpj: &str) -> Result<CompanyModules, AppError>;
+
+    /// Modules of several documents (CPF or CNPJ), results in input order
+    ///
+    /// The Work API has no bulk endpoint: documents are fetched concurrently,
+    /// up to `WORK_API_MAX_CONCURRENCY` at a time, each call waiting on the
+    /// shared Work API limiter (`rate_limit::work_api_limiter`).
+    async fn fetch_documents(
+        &self,
+        documentos: &[String],
+    ) -> Vec<Result<DocumentModules, AppError>> {
+        let concurrency = crate::rate_limit::work_api_limiter().concurrency();
+        stream::iter(documentos.to_vec())
+            .map(|documento: String| async move {
+                if crate::enrichment::is_cnpj(&documento) {
+                    self.fetch_company_modules(&documento)
+                        .await
+                        .map(DocumentModules::Company)
+                } else {
+                    self.fetch_all_modules(&documento)
+                        .await
+                        .map(DocumentModules::Person)
+                }
+            })
+            .buffered(concurrency)
+            .collect()
+            .await
+    }
+}
+
+/// Work API data of one document
+#[derive(Debug)]
+pub enum DocumentModules {
+    /// All modules of a CPF
+    Person(WorkApiCompleteResponse),
+    /// Company modules of a CNPJ
+    Company(CompanyModules),
+}
+
+/// Diretrix (contact → CPF lookups, person data)
+#[async_trait]
+pub trait DiretrixApi: Send + Sync {
+    async fn search_by_phone(&self, phone: &str) -> Result<Vec<DiretrixPersonSearch>, AppError>;
+
+    async fn search_by_email(&self, email: &str) -> Result<Vec<DiretrixPersonSearch>, AppError>;
+
+    async fn get_person_by_cpf(&self, cpf: &str) -> Result<DiretrixPersonData, AppError>;
+}
+
+/// C2S integration API (one client per account)
+#[async_trait]
+pub trait C2sApi: Send + Sync {
+    async fn fetch_lead(&self, lead_id: &str) -> Result<C2SLeadResponse, AppError>;
+
+    /// Post a message on a lead, returns the HTTP status (201)
+    async fn send_message(&self, lead_id: &str, body: &str) -> Result<u16, AppError>;
+
+    /// Product (ad group name) of a Google Ads lead, via the gateway
+    async fn resolve_lead_source(&self, google_lead_id: &str) -> Result<Option<String>, AppError>;
+
+    /// Create a lead, returns its C2S id
+    #[allow(clippy::too_many_arguments)]
+    async fn create_lead(
+        &self,
+        customer_name: &str,
+        phone: Option<&str>,
+        email: Option<&str>,
+        description: &str,
+        source: Option<&str>,
+        product: Option<&str>,
+        seller_id: Option<&str>,
+    ) -> Result<String, AppError>;
+
+    /// Set custom fields of a lead
+    async fn update_custom_fields(
+        &self,
+        lead_id: &str,
+        fields: &serde_json::Map<String, Value>,
+    ) -> Result<(), AppError>;
+
+    /// Sellers (and their teams) of the account
+    async fn list_sellers(&self) -> Result<Vec<C2SSeller>, AppError>;
+
+    /// Leads of the account with this phone or email, newest first
+    async fn find_leads(
+        &self,
+        phone: Option<&str>,
+        email: Option<&str>,
+    ) -> Result<Vec<C2sLeadMatch>, AppError>;
+}
+
+#[async_trait]
+impl WorkApi for WorkApiService {
+    async fn fetch_all_modules(
+        &self,
+        documento: &str,
+    ) -> Result<WorkApiCompleteResponse, AppError> {
+        WorkApiService::fetch_all_modules(self, documento).await
+    }
+
+    async fn fetch_module(&self, module: &str, consulta: &str) -> Result<Option<Value>, AppError> {
+        WorkApiService::fetch_module(self, module, consulta).await
+    }
+
+    async fn fetch_company_modules(&self, cnpj: &str) -> Result<CompanyModules, AppError> {
+        WorkApiService::fetch_company_modules(self, cnpj).await
+    }
+}
+
+#[async_trait]
+impl DiretrixApi for DiretrixService {
+    async fn search_by_phone(&self, phone: &str) -> Result<Vec<DiretrixPersonSearch>, AppError> {
+        DiretrixService::search_by_phone(self, phone).await
+    }
+
+    async fn search_by_email(&self, email: &str) -> Result<Vec<DiretrixPersonSearch>, AppError> {
+        DiretrixService::search_by_email(self, email).await
+    }
+
+    async fn get_person_by_cpf(&self, cpf: &str) -> Result<DiretrixPersonData, AppError> {
+        DiretrixService::get_person_by_cpf(self, cpf).await
+    }
+}
+
+#[async_trait]
+impl C2sApi for C2SService {
+    async fn fetch_lead(&self, lead_id: &str) -> Result<C2SLeadResponse, AppError> {
+        C2SService::fetch_lead(self, lead_id).await
+    }
+
+    async fn send_message(&self, lead_id: &str, body: &str) -> Result<u16, AppError> {
+        C2SService::send_message(self, lead_id, body).await
+    }
+
+    async fn resolve_lead_source(&self, google_lead_id: &str) -> Result<Option<String>, AppError> {
+        C2SService::resolve_lead_source(self, google_lead_id).await
+    }
+
+    async fn create_lead(
+        &self,
+        customer_name: &str,
+        phone: Option<&str>,
+        email: Option<&str>,
+        description: &str,
+        source: Option<&str>,
+        product: Option<&str>,
+        seller_id: Option<&str>,
+    ) -> Result<String, AppError> {
+        C2SService::create_lead(
+            self,
+            customer_name,
+            phone,
+            email,
+            description,
+            source,
+            product,
+            seller_id,
+        )
+        .await
+    }
+
+    async fn update_custom_fields(
+        &self,
+        lead_id: &str,
+        fields: &serde_json::Map<String, Value>,
+    ) -> Result<(), AppError> {
+        C2SService::update_custom_fields(self, lead_id, fields).await
+    }
+
+    async fn list_sellers(&self) -> Result<Vec<C2SSeller>, AppError> {
+        C2SService::list_sellers(self).await
+    }
+
+    async fn find_leads(
+        &self,
+        phone: Option<&str>,
+        email: Option<&str>,
+    ) -> Result<Vec<C2sLeadMatch>, AppError> {
+        C2SService::find_leads(self, phone, email).await
+    }
+}
+
+/// Stand-in for a C2S client that failed to initialize (`C2S_STARTUP_POLICY=degraded`)
+///
+/// Every call fails with `ProviderUnavailable`, so webhook enrichments are
+/// deferred and API callers get a 503 instead of a crash.
+pub struct UnavailableC2s {
+    reason: String,
+}
+
+impl UnavailableC2s {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+
+    fn error(&self) -> AppError {
+        AppError::ProviderUnavailable(format!("C2S client not initialized: {}", self.reason))
+    }
+}
+
+#[async_trait]
+impl C2sApi for UnavailableC2s {
+    async fn fetch_lead(&self, _lead_id: &str) -> Result<C2SLeadResponse, AppError> {
+        Err(self.error())
+    }
+
+    async fn send_message(&self, _lead_id: &str, _body: &str) -> Result<u16, AppError> {
+        Err(self.error())
+    }
+
+    async fn resolve_lead_source(&self, _google_lead_id: &str) -> Result<Option<String>, AppError> {
+        Err(self.error())
+    }
+
+    async fn create_lead(
+        &self,
+        _customer_name: &str,
+        _phone: Option<&str>,
+        _email: Option<&str>,
+        _description: &str,
+        _source: Option<&str>,
+        _product: Option<&str>,
+        _seller_id: Option<&str>,
+    ) -> Result<String, AppError> {
+        Err(self.error())
+    }
+
+    async fn update_custom_fields(
+        &self,
+        _lead_id: &str,
+        _fields: &serde_json::Map<String, Value>,
+    ) -> Result<(), AppError> {
+        Err(self.error())
+    }
+
+    async fn list_sellers(&self) -> Result<Vec<C2SSeller>, AppError> {
+        Err(self.error())
+    }
+
+    async fn find_leads(
+        &self,
+        _phone: Option<&str>,
+        _email: Option<&str>,
+    ) -> Result<Vec<C2sLeadMatch>, AppError> {
+        Err(self.error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// In-memory Diretrix answering a fixed phone
+    struct MockDiretrix;
+
+    #[async_trait]
+    impl DiretrixApi for MockDiretrix {
+        async fn search_by_phone(
+            &self,
+            phone: &str,
+        ) -> Result<Vec<DiretrixPersonSearch>, AppError> {
+            Ok(if phone.ends_with("11987654321") {
+                vec![DiretrixPersonSearch {
+                    nome: "João da Silva".to_string(),
+                    cpf: "52998224725".to_string(),
+                }]
+            } else {
+                vec![]
+            })
+        }
+
+        async fn search_by_email(
+            &self,
+            _email: &str,
+        ) -> Result<Vec<DiretrixPersonSearch>, AppError> {
+            Ok(vec![])
+        }
+
+        async fn get_person_by_cpf(&self, cpf: &str) -> Result<DiretrixPersonData, AppError> {
+            Err(AppError::NotFound(cpf.to_string()))
+        }
+    }
+
+    /// In-memory C2S recording sent messages
+    #[derive(Default)]
+    struct MockC2s {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl C2sApi for MockC2s {
+        async fn fetch_lead(&self, lead_id: &str) -> Result<C2SLeadResponse, AppError> {
+            Err(AppError::NotFound(lead_id.to_string()))
+        }
+
+        async fn send_message(&self, lead_id: &str, body: &str) -> Result<u16, AppError> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((lead_id.to_string(), body.to_string()));
+            Ok(201)
+        }
+
+        async fn resolve_lead_source(&self, _: &str) -> Result<Option<String>, AppError> {
+            Ok(None)
+        }
+
+        async fn create_lead(
+            &self,
+            _customer_name: &str,
+            _phone: Option<&str>,
+            _email: Option<&str>,
+            _description: &str,
+            _source: Option<&str>,
+            _product: Option<&str>,
+            _seller_id: Option<&str>,
+        ) -> Result<String, AppError> {
+            Ok("mock-lead".to_string())
+        }
+
+        async fn update_custom_fields(
+            &self,
+            _lead_id: &str,
+            _fields: &serde_json::Map<String, Value>,
+        ) -> Result<(), AppError> {
+            Ok(())
+        }
+
+        async fn list_sellers(&self) -> Result<Vec<C2SSeller>, AppError> {
+            Ok(vec![])
+        }
+
+        async fn find_leads(
+            &self,
+            _phone: Option<&str>,
+            _email: Option<&str>,
+        ) -> Result<Vec<C2sLeadMatch>, AppError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workflow_runs_on_mocks() {
+        let lookup = crate::enrichment::find_cpf_via_diretrix(
+            Some("João da Silva"),
+            Some("11987654321"),
+            None,
+            &MockDiretrix,
+            0.6,
+        )
+        .await
+        .unwrap();
+        assert_eq!(lookup.cpfs, vec!["52998224725".to_string()]);
+
+        let c2s = MockC2s::default();
+        let api: &dyn C2sApi = &c2s;
+        let status = api.send_message("lead-1", "Olá").await.unwrap();
+        assert_eq!(status, 201);
+        assert_eq!(
+            *c2s.sent.lock().unwrap(),
+            vec![("lead-1".to_string(), "Olá".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_c2s_defers_calls() {
+        let api: &dyn C2sApi = &UnavailableC2s::new("builder error");
+        let err = api.send_message("lead-1", "Olá").await.unwrap_err();
+        assert!(
+            matches!(err, AppError::ProviderUnavailable(ref msg) if msg.contains("builder error"))
+        );
+        assert!(matches!(
+            api.fetch_lead("lead-1").await,
+            Err(AppError::ProviderUnavailable(_))
+        ));
+    }
+}
@@ -4,25 +4,160 @@ use url::Url;
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub database_url: String,
+    pub slow_query_ms: u64,        // Log DB queries slower than this (ms)
+    pub sql_self_test: bool,       // Prepare critical SQL statements on boot (see schema_check)
+    pub dedup_backend: String,     // "memory" (default) or "redis", see dedup
+    pub redis_url: Option<String>, // Required with DEDUP_BACKEND=redis
     pub port: u16,
     pub c2s_token: String,
     pub c2s_base_url: String,
     pub webhook_secret: Option<String>, // Optional webhook secret for C2S webhooks
+    pub admin_api_token: Option<String>, // Token for /api/v1/admin endpoints (disabled if unset)
+    pub api_keys_required: bool,        // Reject requests without X-Api-Key (see api_keys)
+    #[serde(skip)]
+    pub cors: crate::cors::CorsPolicy, // Cross-origin allowlist (none when CORS_ALLOWED_ORIGINS is unset)
     pub worker_api_key: String,
+    pub work_api_base_url: String,
     pub diretrix_base_url: String,
     pub diretrix_user: String,
     pub diretrix_pass: String,
+    pub identity_match_threshold: f32, // Min score (0-1) to auto-link a lead to a Diretrix candidate
+
+    // Shadow provider compared against Work API on a sample of leads (optional, see shadow)
+    pub shadow_provider_url: Option<String>,
+    pub shadow_provider_token: Option<String>,
+    pub shadow_sample_rate: f64, // Fraction (0-1) of leads sent to the shadow provider
+
+    // Full workflow payload capture for debugging (see debug_payloads)
+    pub debug_sample_rate: f64, // Fraction (0-1) of leads captured
+    pub debug_cpf_allowlist: Vec<String>, // CPFs always captured
+    pub debug_payload_ttl_hours: i64, // Captured payloads expire after this
+
+    // Anonymized Work API responses recorded as test fixtures (see work_api_fixtures)
+    pub work_api_fixture_sample_rate: f64, // Fraction (0-1) of CPFs recorded
+    pub work_api_fixture_dir: Option<String>, // Also written here as JSON files
+    // Shared Work API limits (see rate_limit::ProviderLimiter)
+    pub work_api_rate_per_minute: u32, // Work API calls started per minute
+    pub work_api_max_concurrency: usize, // Work API calls in flight at once
+    pub work_api_max_wait_secs: u64,   // Max wait for a Work API slot
+    // Work API response cache TTL (see cache_ttl)
+    pub work_api_cache_ttl_secs: u64, // While the Work API is healthy
+    pub work_api_cache_max_ttl_secs: u64, // Bound while it is degraded
+    // Limits of each Diretrix account (see rate_limit::diretrix_limiter)
+    pub diretrix_rate_per_minute: u32, // Diretrix calls started per minute
+    pub diretrix_max_concurrency: usize, // Diretrix calls in flight at once
+    pub diretrix_max_wait_secs: u64,   // Max wait for a Diretrix slot
+
+    // Tenants whose leads need a recorded consent before enrichment (see consent)
+    pub consent_required_tenants: Vec<String>,
+
+    // Tenants whose leads also get Diretrix person data merged into the Work API data
+    pub diretrix_person_tenants: Vec<String>,
+
+    // C2S accounts whose new leads are appended to an open lead of the same contact (see lead_dedup)
+    pub lead_dedup_tenants: Vec<String>,
+    pub lead_dedup_window_days: i64, // Age of local leads still matched
+
+    // Own Work API / Diretrix credentials per tenant (JSON, see tenant_credentials)
+    pub tenant_provider_credentials: crate::tenant_credentials::TenantCredentialMap,
+
+    // Enrichment stages per tenant (JSON, see pipeline; default: all stages)
+    pub tenant_pipeline_stages: crate::pipeline::TenantStages,
+
+    // Anonymization of leads without activity (see anonymizer, disabled when unset)
+    pub anonymize_after_days: Option<i64>,
+    pub anonymization_salt: Option<String>, // Secret mixed into identifier hashes
+
+    // HMAC key of cached provider data (see cache_validator, random per process when unset)
+    pub cache_signing_secret: Option<String>,
+
+    // Webhook payload pruning (see retention, disabled when unset)
+    pub webhook_payload_retention_days: Option<i64>,
+    pub webhook_archive_url: Option<String>, // Object storage prefix, payloads PUT under it before pruning
+    pub webhook_archive_token: Option<String>,
+
+    // C2S webhook batches (see webhook_queue)
+    pub webhook_max_events: usize, // Events processed per request, the rest rejected
+    pub webhook_workers: usize,    // Background enrichments running at once (per instance)
+    pub webhook_queue_capacity: usize, // Queued jobs (all instances) before events are rejected
+    pub webhook_max_attempts: i32, // Runs of a failing event (or its job) before giving up
+
+    // Async C2S enrichments (see enrichment_jobs)
+    pub enrich_job_workers: usize, // Workers running them (per instance)
+
+    // Callbacks of background batches (see batch_jobs, unsigned when unset)
+    pub batch_callback_secret: Option<String>, // HMAC key of the X-Signature header
+    pub public_base_url: Option<String>, // Public https://host prefixing callback results links
 
     // Google Ads integration (optional - only required if using Google Ads webhooks)
     pub google_ads_webhook_key: Option<String>, // Webhook verification key
     pub c2s_default_seller_id: Option<String>,  // Default seller for new leads
     pub c2s_description_max_length: usize,      // Max description length
+    pub c2s_send_rate_per_minute: u32,          // Global C2S send_message rate
+    pub c2s_send_rate_per_lead_per_minute: u32, // Per-lead C2S send_message rate
+    pub c2s_send_max_wait_secs: u64,            // Max wait for a send slot
+    pub c2s_coalesce_window_minutes: i64,       // Re-enrichment coalescing window (0 = off)
+    pub c2s_status_notes: bool, // Post a status note to the lead after webhook enrichment
+    pub c2s_startup_policy: String, // "fail_fast" (default) or "degraded" when the C2S client fails to build
+    // Additional C2S accounts and the rules selecting them (JSON, see c2s_accounts)
+    pub c2s_accounts: std::collections::HashMap<String, crate::c2s_accounts::C2sAccount>,
+    pub c2s_account_rules: Vec<crate::c2s_accounts::AccountRule>,
+    // Enrichment facts written to C2S lead custom fields (JSON, see c2s_fields)
+    pub c2s_custom_fields: crate::c2s_fields::FieldMapping,
+
+    // WhatsApp Business (Meta Cloud API) webhook (optional)
+    pub whatsapp_verify_token: Option<String>, // Subscription verification token
+    pub whatsapp_app_secret: Option<String>,   // App secret for X-Hub-Signature-256
+
+    // RD Station Marketing integration (optional - conversions are skipped when unset)
+    pub rdstation_api_key: Option<String>,
+    pub rdstation_base_url: String,
+
+    // MBRAS listings API (optional - property details in the C2S message when set)
+    pub listings_api_url: Option<String>,
+    pub listings_api_key: Option<String>,
+
+    // Salesforce lead sync (feature flag - disabled unless SALESFORCE_ENABLED=true)
+    pub salesforce_enabled: bool,
+    pub salesforce_login_url: String,
+    pub salesforce_client_id: Option<String>,
+    pub salesforce_client_secret: Option<String>,
+    pub salesforce_api_version: String,
+
+    // Google Sheets append (optional - requires spreadsheet ID and service account)
+    pub google_sheets_spreadsheet_id: Option<String>,
+    pub google_sheets_range: String,
+    pub google_service_account_json: Option<String>,
+
+    // Lead routing rules (JSON array, see routing::RoutingRule)
+    pub routing_rules: Vec<crate::routing::RoutingRule>,
+
+    // Quiet hours for C2S messages (America/Sao_Paulo, see quiet_hours::QuietHours)
+    #[serde(skip)]
+    pub quiet_hours: crate::quiet_hours::QuietHours,
+    // Follow-up reminder to the seller N hours after the enrichment message (optional)
+    pub follow_up_nudge_hours: Option<i64>,
+
+    // SMS notifications (optional - provider: "twilio" or "zenvia")
+    pub sms_provider: Option<String>,
+    pub sms_from: Option<String>,
+    pub twilio_account_sid: Option<String>,
+    pub twilio_auth_token: Option<String>,
+    pub zenvia_api_token: Option<String>,
 }
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
 
+        let c2s_accounts = crate::c2s_accounts::parse_accounts(
+            &std::env::var("C2S_ACCOUNTS").unwrap_or_default(),
+        )?;
+        let c2s_account_rules = crate::c2s_accounts::parse_rules(
+            &std::env::var("C2S_ACCOUNT_RULES").unwrap_or_default(),
+            &c2s_accounts,
+        )?;
+
         let config = Self {
             database_url: std::env::var("DB_URL")
                 .or_else(|_| std::env::var("DATABASE_URL"))
@@ -50,6 +185,21 @@ impl Config {
 
                 port
             },
+            sql_self_test: std::env::var("SQL_SELF_TEST")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            dedup_backend: std::env::var("DEDUP_BACKEND")
+                .map(|v| v.trim().to_lowercase())
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "memory".to_string()),
+            redis_url: std::env::var("REDIS_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            slow_query_ms: std::env::var("SLOW_QUERY_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(500),
             c2s_token: std::env::var("C2S_TOKEN")
                 .map_err(|_| anyhow::anyhow!("C2S_TOKEN environment variable required"))
                 .and_then(|token| {
@@ -61,6 +211,17 @@ impl Config {
             webhook_secret: std::env::var("WEBHOOK_SECRET")
                 .ok()
                 .filter(|s| !s.trim().is_empty()),
+            admin_api_token: std::env::var("ADMIN_API_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            api_keys_required: std::env::var("API_KEYS_REQUIRED")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            cors: crate::cors::parse_policy(
+                &std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default(),
+                &std::env::var("CORS_ALLOWED_METHODS").unwrap_or_default(),
+                &std::env::var("CORS_ALLOWED_HEADERS").unwrap_or_default(),
+            )?,
             c2s_base_url: std::env::var("C2S_BASE_URL")
                 .map_err(|_| anyhow::anyhow!("C2S_BASE_URL environment variable required"))
                 .and_then(|url| {
@@ -83,6 +244,10 @@ impl Config {
                     }
                     Ok(key)
                 })?,
+            work_api_base_url: std::env::var("WORK_API_BASE_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| crate::services::WORK_API_BASE_URL.to_string()),
             diretrix_base_url: std::env::var("DIRETRIX_BASE_URL")
                 .map_err(|_| anyhow::anyhow!("DIRETRIX_BASE_URL environment variable required"))
                 .and_then(|url| {
@@ -110,6 +275,182 @@ impl Config {
                     }
                     Ok(pass)
                 })?,
+            identity_match_threshold: {
+                let threshold: f32 = std::env::var("IDENTITY_MATCH_THRESHOLD")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0.6);
+
+                if !(0.0..=1.0).contains(&threshold) {
+                    anyhow::bail!("IDENTITY_MATCH_THRESHOLD must be between 0 and 1");
+                }
+
+                threshold
+            },
+            shadow_provider_url: std::env::var("SHADOW_PROVIDER_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            shadow_provider_token: std::env::var("SHADOW_PROVIDER_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            shadow_sample_rate: {
+                let rate: f64 = std::env::var("SHADOW_SAMPLE_RATE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0.0);
+
+                if !(0.0..=1.0).contains(&rate) {
+                    anyhow::bail!("SHADOW_SAMPLE_RATE must be between 0 and 1");
+                }
+
+                rate
+            },
+            debug_sample_rate: {
+                let rate: f64 = std::env::var("DEBUG_SAMPLE_RATE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0.0);
+
+                if !(0.0..=1.0).contains(&rate) {
+                    anyhow::bail!("DEBUG_SAMPLE_RATE must be between 0 and 1");
+                }
+
+                rate
+            },
+            debug_cpf_allowlist: crate::debug_payloads::parse_allowlist(
+                &std::env::var("DEBUG_CPF_ALLOWLIST").unwrap_or_default(),
+            ),
+            debug_payload_ttl_hours: std::env::var("DEBUG_PAYLOAD_TTL_HOURS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|h: &i64| *h > 0)
+                .unwrap_or(72),
+            work_api_fixture_sample_rate: {
+                let rate: f64 = std::env::var("WORK_API_FIXTURE_SAMPLE_RATE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0.0);
+
+                if !(0.0..=1.0).contains(&rate) {
+                    anyhow::bail!("WORK_API_FIXTURE_SAMPLE_RATE must be between 0 and 1");
+                }
+
+                rate
+            },
+            work_api_fixture_dir: std::env::var("WORK_API_FIXTURE_DIR")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            work_api_rate_per_minute: std::env::var("WORK_API_RATE_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|r: &u32| *r > 0)
+                .unwrap_or(30),
+            work_api_max_concurrency: std::env::var("WORK_API_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(3),
+            work_api_max_wait_secs: std::env::var("WORK_API_MAX_WAIT_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(60),
+            work_api_cache_ttl_secs: std::env::var("WORK_API_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .unwrap_or(crate::cache_ttl::DEFAULT_TTL_SECS),
+            work_api_cache_max_ttl_secs: std::env::var("WORK_API_CACHE_MAX_TTL_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .unwrap_or(crate::cache_ttl::DEFAULT_MAX_TTL_SECS),
+            diretrix_rate_per_minute: std::env::var("DIRETRIX_RATE_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|r: &u32| *r > 0)
+                .unwrap_or(60),
+            diretrix_max_concurrency: std::env::var("DIRETRIX_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(4),
+            diretrix_max_wait_secs: std::env::var("DIRETRIX_MAX_WAIT_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(30),
+            consent_required_tenants: crate::consent::parse_tenants(
+                &std::env::var("CONSENT_REQUIRED_TENANTS").unwrap_or_default(),
+            ),
+            diretrix_person_tenants: crate::consent::parse_tenants(
+                &std::env::var("DIRETRIX_PERSON_TENANTS").unwrap_or_default(),
+            ),
+            lead_dedup_tenants: crate::consent::parse_tenants(
+                &std::env::var("LEAD_DEDUP_TENANTS").unwrap_or_default(),
+            ),
+            lead_dedup_window_days: std::env::var("LEAD_DEDUP_WINDOW_DAYS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &i64| *n > 0)
+                .unwrap_or(30),
+            tenant_provider_credentials: crate::tenant_credentials::parse_credentials(
+                &std::env::var("TENANT_PROVIDER_CREDENTIALS").unwrap_or_default(),
+            )?,
+            tenant_pipeline_stages: crate::pipeline::parse_tenant_stages(
+                &std::env::var("TENANT_PIPELINE_STAGES").unwrap_or_default(),
+            )?,
+            anonymize_after_days: std::env::var("ANONYMIZE_AFTER_DAYS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|d: &i64| *d > 0),
+            anonymization_salt: std::env::var("ANONYMIZATION_SALT")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            cache_signing_secret: std::env::var("CACHE_SIGNING_SECRET")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            webhook_payload_retention_days: std::env::var("WEBHOOK_PAYLOAD_RETENTION_DAYS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|d: &i64| *d > 0),
+            webhook_archive_url: std::env::var("WEBHOOK_ARCHIVE_URL")
+                .ok()
+                .map(|s| s.trim().trim_end_matches('/').to_string())
+                .filter(|s| !s.is_empty()),
+            webhook_archive_token: std::env::var("WEBHOOK_ARCHIVE_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            webhook_max_events: std::env::var("WEBHOOK_MAX_EVENTS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(100),
+            webhook_workers: std::env::var("WEBHOOK_WORKERS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(8),
+            webhook_queue_capacity: std::env::var("WEBHOOK_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(1000),
+            webhook_max_attempts: std::env::var("WEBHOOK_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &i32| *n > 0)
+                .unwrap_or(5),
+            enrich_job_workers: std::env::var("ENRICH_JOB_WORKERS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(2),
+            batch_callback_secret: std::env::var("BATCH_CALLBACK_SECRET")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            public_base_url: std::env::var("PUBLIC_BASE_URL")
+                .ok()
+                .map(|s| s.trim().trim_end_matches('/').to_string())
+                .filter(|s| !s.is_empty()),
             google_ads_webhook_key: std::env::var("GOOGLE_ADS_WEBHOOK_KEY")
                 .ok()
                 .filter(|s| !s.trim().is_empty()),
@@ -128,7 +469,169 @@ impl Config {
 
                 max_len
             },
+            c2s_send_rate_per_minute: std::env::var("C2S_SEND_RATE_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|r: &u32| *r > 0)
+                .unwrap_or(60),
+            c2s_send_rate_per_lead_per_minute: std::env::var("C2S_SEND_RATE_PER_LEAD_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|r: &u32| *r > 0)
+                .unwrap_or(5),
+            c2s_send_max_wait_secs: std::env::var("C2S_SEND_MAX_WAIT_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(30),
+            c2s_coalesce_window_minutes: std::env::var("C2S_COALESCE_WINDOW_MINUTES")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|m: &i64| *m >= 0)
+                .unwrap_or(60),
+            c2s_status_notes: std::env::var("C2S_STATUS_NOTES")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            c2s_startup_policy: std::env::var("C2S_STARTUP_POLICY")
+                .map(|v| v.trim().to_lowercase())
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "fail_fast".to_string()),
+            whatsapp_verify_token: std::env::var("WHATSAPP_VERIFY_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            whatsapp_app_secret: std::env::var("WHATSAPP_APP_SECRET")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            rdstation_api_key: std::env::var("RDSTATION_API_KEY")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            rdstation_base_url: std::env::var("RDSTATION_BASE_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "https://api.rd.services".to_string()),
+            listings_api_url: std::env::var("LISTINGS_API_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            listings_api_key: std::env::var("LISTINGS_API_KEY")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            salesforce_enabled: std::env::var("SALESFORCE_ENABLED")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            salesforce_login_url: std::env::var("SALESFORCE_LOGIN_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "https://login.salesforce.com".to_string()),
+            salesforce_client_id: std::env::var("SALESFORCE_CLIENT_ID")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            salesforce_client_secret: std::env::var("SALESFORCE_CLIENT_SECRET")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            salesforce_api_version: std::env::var("SALESFORCE_API_VERSION")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "v59.0".to_string()),
+            google_sheets_spreadsheet_id: std::env::var("GOOGLE_SHEETS_SPREADSHEET_ID")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            google_sheets_range: std::env::var("GOOGLE_SHEETS_RANGE")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "Leads!A:I".to_string()),
+            google_service_account_json: std::env::var("GOOGLE_SERVICE_ACCOUNT_JSON")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            routing_rules: crate::routing::parse_rules(
+                &std::env::var("ROUTING_RULES").unwrap_or_default(),
+            )?,
+            c2s_accounts,
+            c2s_account_rules,
+            c2s_custom_fields: crate::c2s_fields::parse_mapping(
+                &std::env::var("C2S_CUSTOM_FIELDS").unwrap_or_default(),
+            )?,
+            quiet_hours: crate::quiet_hours::QuietHours::parse(
+                &std::env::var("QUIET_HOURS").unwrap_or_default(),
+                &std::env::var("QUIET_HOURS_BY_TENANT").unwrap_or_default(),
+            )?,
+            follow_up_nudge_hours: std::env::var("FOLLOW_UP_NUDGE_HOURS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|h: &i64| *h > 0),
+            sms_provider: std::env::var("SMS_PROVIDER")
+                .ok()
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty()),
+            sms_from: std::env::var("SMS_FROM")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            twilio_account_sid: std::env::var("TWILIO_ACCOUNT_SID")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            twilio_auth_token: std::env::var("TWILIO_AUTH_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            zenvia_api_token: std::env::var("ZENVIA_API_TOKEN")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+
+        if config.work_api_cache_max_ttl_secs < config.work_api_cache_ttl_secs {
+            anyhow::bail!(
+                "WORK_API_CACHE_MAX_TTL_SECS ({}) must not be below WORK_API_CACHE_TTL_SECS ({})",
+                config.work_api_cache_max_ttl_secs,
+                config.work_api_cache_ttl_secs
+            );
+        }
+
+        match config.sms_provider.as_deref() {
+            None | Some("twilio") | Some("zenvia") => {}
+            Some(other) => {
+                anyhow::bail!("SMS_PROVIDER must be 'twilio' or 'zenvia', got '{}'", other)
+            }
+        }
+        let sms_missing = match config.sms_provider.as_deref() {
+            Some("twilio") => {
+                config.twilio_account_sid.is_none()
+                    || config.twilio_auth_token.is_none()
+                    || config.sms_from.is_none()
+            }
+            Some("zenvia") => config.zenvia_api_token.is_none() || config.sms_from.is_none(),
+            _ => false,
         };
+        if sms_missing {
+            anyhow::bail!(
+                "SMS_FROM and provider credentials (TWILIO_ACCOUNT_SID/TWILIO_AUTH_TOKEN or ZENVIA_API_TOKEN) are required when SMS_PROVIDER is set"
+            );
+        }
+
+        if config.salesforce_enabled
+            && (config.salesforce_client_id.is_none() || config.salesforce_client_secret.is_none())
+        {
+            anyhow::bail!(
+                "SALESFORCE_CLIENT_ID and SALESFORCE_CLIENT_SECRET are required when SALESFORCE_ENABLED=true"
+            );
+        }
+
+        match config.dedup_backend.as_str() {
+            "memory" => {}
+            "redis" if config.redis_url.is_none() => {
+                anyhow::bail!("REDIS_URL is required when DEDUP_BACKEND=redis");
+            }
+            "redis" => {}
+            other => anyhow::bail!("DEDUP_BACKEND must be memory or redis, got: {}", other),
+        }
+
+        if !matches!(config.c2s_startup_policy.as_str(), "fail_fast" | "degraded") {
+            anyhow::bail!(
+                "C2S_STARTUP_POLICY must be fail_fast or degraded, got: {}",
+                config.c2s_startup_policy
+            );
+        }
+
+        if config.anonymize_after_days.is_some() && config.anonymization_salt.is_none() {
+            anyhow::bail!("ANONYMIZATION_SALT is required when ANONYMIZE_AFTER_DAYS is set");
+        }
 
         // Log successful configuration load (without sensitive values)
         tracing::info!("Configuration loaded successfully");
@@ -158,6 +661,19 @@ impl Config {
                 "No webhook secret configured - C2S webhooks will not validate authentication"
             );
         }
+        if config.admin_api_token.is_none() {
+            tracing::info!("ADMIN_API_TOKEN not set - admin endpoints disabled");
+        }
+        if config.api_keys_required {
+            tracing::info!("API keys required (X-Api-Key)");
+        } else {
+            tracing::warn!("API_KEYS_REQUIRED not set - requests without X-Api-Key are allowed");
+        }
+        if config.cors.allows_any_origin() {
+            tracing::warn!("CORS_ALLOWED_ORIGINS=* - any origin can call the API from a browser");
+        } else {
+            tracing::info!("CORS allowed origins: {}", config.cors.describe_origins());
+        }
         tracing::debug!("Diretrix Base URL: {}", config.diretrix_base_url);
         tracing::debug!("Server Port: {}", config.port);
 
@@ -180,6 +696,223 @@ impl Config {
             "C2S description max length: {} chars",
             config.c2s_description_max_length
         );
+        tracing::info!(
+            "Work API limits: {}/min, {} concurrent (max wait {}s)",
+            config.work_api_rate_per_minute,
+            config.work_api_max_concurrency,
+            config.work_api_max_wait_secs
+        );
+        tracing::info!(
+            "Work API cache TTL: {}s, up to {}s while the Work API is degraded",
+            config.work_api_cache_ttl_secs,
+            config.work_api_cache_max_ttl_secs
+        );
+        tracing::info!(
+            "Diretrix limits per account: {}/min, {} concurrent (max wait {}s)",
+            config.diretrix_rate_per_minute,
+            config.diretrix_max_concurrency,
+            config.diretrix_max_wait_secs
+        );
+        tracing::info!(
+            "C2S send rate: {}/min global, {}/min per lead (max wait {}s)",
+            config.c2s_send_rate_per_minute,
+            config.c2s_send_rate_per_lead_per_minute,
+            config.c2s_send_max_wait_secs
+        );
+        tracing::info!(
+            "C2S message coalescing window: {} min",
+            config.c2s_coalesce_window_minutes
+        );
+        if config.c2s_status_notes {
+            tracing::info!("C2S status notes enabled for webhook enrichments");
+        }
+        tracing::info!("C2S startup policy: {}", config.c2s_startup_policy);
+        tracing::info!("Slow query threshold: {}ms", config.slow_query_ms);
+        tracing::info!("Lead/CPF deduplication backend: {}", config.dedup_backend);
+        if config.sql_self_test {
+            tracing::info!("SQL self-test enabled on startup");
+        }
+        tracing::info!(
+            "Identity match threshold: {:.2}",
+            config.identity_match_threshold
+        );
+
+        if let Some(url) = config.shadow_provider_url.as_deref() {
+            tracing::info!(
+                "Shadow provider comparison enabled: {} ({:.0}% of leads)",
+                url,
+                config.shadow_sample_rate * 100.0
+            );
+        }
+
+        if config.debug_sample_rate > 0.0 || !config.debug_cpf_allowlist.is_empty() {
+            tracing::info!(
+                "Debug payload capture enabled ({:.1}% of leads, {} allowlisted CPF(s), {}h retention)",
+                config.debug_sample_rate * 100.0,
+                config.debug_cpf_allowlist.len(),
+                config.debug_payload_ttl_hours
+            );
+        }
+
+        if config.work_api_fixture_sample_rate > 0.0 {
+            tracing::info!(
+                "Work API fixture recording enabled ({:.1}% of CPFs{})",
+                config.work_api_fixture_sample_rate * 100.0,
+                config
+                    .work_api_fixture_dir
+                    .as_deref()
+                    .map(|dir| format!(", also written to {}", dir))
+                    .unwrap_or_default()
+            );
+        }
+
+        if !config.consent_required_tenants.is_empty() {
+            tracing::info!(
+                "Enrichment consent required for tenants: {}",
+                config.consent_required_tenants.join(", ")
+            );
+        }
+
+        if !config.diretrix_person_tenants.is_empty() {
+            tracing::info!(
+                "Diretrix person enrichment enabled for tenants: {}",
+                config.diretrix_person_tenants.join(", ")
+            );
+        }
+
+        if !config.lead_dedup_tenants.is_empty() {
+            tracing::info!(
+                "Duplicate lead detection enabled for tenants: {} ({} day window)",
+                config.lead_dedup_tenants.join(", "),
+                config.lead_dedup_window_days
+            );
+        }
+
+        for (tenant, credentials) in &config.tenant_provider_credentials {
+            tracing::info!(
+                "Tenant '{}' uses its own provider credentials: {}",
+                tenant,
+                credentials.providers().join(", ")
+            );
+        }
+
+        for (tenant, stages) in &config.tenant_pipeline_stages {
+            tracing::info!(
+                "Enrichment pipeline of tenant '{}': {}",
+                tenant,
+                stages
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" → ")
+            );
+        }
+
+        if let Some(days) = config.anonymize_after_days {
+            tracing::info!("Leads anonymized after {} days without activity", days);
+        }
+
+        tracing::info!(
+            "C2S webhooks: up to {} events per request, {} worker(s), queue of {}, poisoned after {} attempts",
+            config.webhook_max_events,
+            config.webhook_workers,
+            config.webhook_queue_capacity,
+            config.webhook_max_attempts
+        );
+        tracing::info!(
+            "Async C2S enrichments: {} worker(s)",
+            config.enrich_job_workers
+        );
+        tracing::info!(
+            "Batch callbacks: {}",
+            if config.batch_callback_secret.is_some() {
+                "signed"
+            } else {
+                "unsigned (BATCH_CALLBACK_SECRET not set)"
+            }
+        );
+
+        if let Some(days) = config.webhook_payload_retention_days {
+            tracing::info!(
+                "Webhook payloads pruned after {} days ({})",
+                days,
+                if config.webhook_archive_url.is_some() {
+                    "archived first"
+                } else {
+                    "not archived"
+                }
+            );
+        }
+
+        if config.whatsapp_app_secret.is_none() {
+            tracing::warn!(
+                "WHATSAPP_APP_SECRET not configured - WhatsApp webhooks will be rejected"
+            );
+        }
+
+        if config.rdstation_api_key.is_some() {
+            tracing::info!(
+                "RD Station integration enabled: {}",
+                config.rdstation_base_url
+            );
+        } else {
+            tracing::debug!("RDSTATION_API_KEY not set - RD Station conversions disabled");
+        }
+        match (&config.listings_api_url, &config.listings_api_key) {
+            (Some(url), Some(_)) => tracing::info!("Listings API enabled: {}", url),
+            (Some(_), None) => tracing::warn!(
+                "LISTINGS_API_URL set without LISTINGS_API_KEY - property details disabled"
+            ),
+            _ => {}
+        }
+        match (
+            &config.google_sheets_spreadsheet_id,
+            &config.google_service_account_json,
+        ) {
+            (Some(_), Some(_)) => tracing::info!(
+                "Google Sheets append enabled (range: {})",
+                config.google_sheets_range
+            ),
+            (Some(_), None) => tracing::warn!(
+                "GOOGLE_SHEETS_SPREADSHEET_ID set without GOOGLE_SERVICE_ACCOUNT_JSON - Sheets append disabled"
+            ),
+            _ => {}
+        }
+        if !config.routing_rules.is_empty() {
+            tracing::info!("Loaded {} lead routing rule(s)", config.routing_rules.len());
+        }
+        if !config.c2s_accounts.is_empty() {
+            tracing::info!(
+                "Additional C2S accounts: {} ({} routing rule(s))",
+                config.c2s_accounts.len(),
+                config.c2s_account_rules.len()
+            );
+        }
+        if !config.c2s_custom_fields.is_empty() {
+            tracing::info!(
+                "C2S custom fields enabled for {} fact(s)",
+                config.c2s_custom_fields.len()
+            );
+        }
+        if config.quiet_hours.is_enabled() {
+            tracing::info!(
+                "Quiet hours enabled (default: {:?}, {} tenant override(s))",
+                config.quiet_hours.default,
+                config.quiet_hours.tenants.len()
+            );
+        }
+        if let Some(hours) = config.follow_up_nudge_hours {
+            tracing::info!("Follow-up nudges enabled ({}h after enrichment)", hours);
+        }
+        if let Some(ref provider) = config.sms_provider {
+            tracing::info!("SMS notifications enabled via {}", provider);
+        }
+        if config.salesforce_enabled {
+            tracing::info!(
+                "Salesforce lead sync enabled: {}",
+                config.salesforce_login_url
+            );
+        }
 
         Ok(config)
     }
@@ -0,0 +1,256 @@
+//! Consent tracking and enforcement
+//!
+//! Consents are recorded per subject (CPF, email or phone) and purpose in the
+//! `consents` table (migration 033), from lead forms (Google Ads consent
+//! question) or through the admin API. Tenants listed in
+//! `CONSENT_REQUIRED_TENANTS` only have their leads sent to data providers
+//! when an active `enrichment` consent exists for one of the lead's
+//! identifiers; otherwise the workflow refuses with `AppError::ConsentRequired`
+//! (HTTP 451, `"code": "consent_required"`).
+
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+
+/// Purpose checked before calling data providers
+pub const PURPOSE_ENRICHMENT: &str = "enrichment";
+
+/// Kind of identifier a consent is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubjectKind {
+    Cpf,
+    Email,
+    Phone,
+}
+
+impl SubjectKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubjectKind::Cpf => "cpf",
+            SubjectKind::Email => "email",
+            SubjectKind::Phone => "phone",
+        }
+    }
+
+    /// Canonical form of an identifier (None when empty or invalid)
+    pub fn normalize(self, value: &str) -> Option<String> {
+        let normalized = match self {
+            SubjectKind::Cpf => value.chars().filter(|c| c.is_ascii_digit()).collect(),
+            SubjectKind::Email => value.trim().to_lowercase(),
+            SubjectKind::Phone => match crate::enrichment::validate_br_phone(value) {
+                (true, e164) => e164,
+                (false, _) => value.chars().filter(|c| c.is_ascii_digit()).collect(),
+            },
+        };
+        (!normalized.is_empty()).then_some(normalized)
+    }
+}
+
+/// Parse the comma-separated tenants requiring consent (lowercased)
+pub fn parse_tenants(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Whether leads of a tenant need consent before enrichment
+pub fn requires_consent(config: &Config, tenant: Option<&str>) -> bool {
+    tenant.is_some_and(|t| {
+        let tenant = t.trim().to_lowercase();
+        config.consent_required_tenants.contains(&tenant)
+    })
+}
+
+/// Whether an answer to a consent question means "yes"
+pub fn is_affirmative(answer: &str) -> bool {
+    matches!(
+        answer.trim().to_lowercase().as_str(),
+        "sim" | "s" | "yes" | "y" | "true" | "1" | "aceito" | "concordo" | "autorizo"
+    )
+}
+
+/// A recorded consent
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Consent {
+    pub subject_type: String,
+    pub subject: String,
+    pub purpose: String,
+    pub source: Option<String>,
+    pub granted_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Record (or renew) a consent; a previous revocation is cleared
+pub async fn grant(
+    pool: &PgPool,
+    kind: SubjectKind,
+    value: &str,
+    purpose: &str,
+    source: &str,
+) -> Result<(), AppError> {
+    let Some(subject) = kind.normalize(value) else {
+        return Err(AppError::BadRequest(format!(
+            "Invalid {} for consent",
+            kind.as_str()
+        )));
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO consents (subject_type, subject, purpose, source, granted_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (subject_type, subject, purpose) DO UPDATE
+        SET source = EXCLUDED.source, granted_at = EXCLUDED.granted_at, revoked_at = NULL
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(&subject)
+    .bind(purpose)
+    .bind(source)
+    .execute(pool)
+    .timed("consents.grant")
+    .await
+    .context(format!("Failed to record {} consent", purpose))?;
+    Ok(())
+}
+
+/// Revoke an active consent, returns whether one was found
+pub async fn revoke(
+    pool: &PgPool,
+    kind: SubjectKind,
+    value: &str,
+    purpose: &str,
+) -> Result<bool, AppError> {
+    let Some(subject) = kind.normalize(value) else {
+        return Ok(false);
+    };
+    let result = sqlx::query(
+        r#"
+        UPDATE consents SET revoked_at = NOW()
+        WHERE subject_type = $1 AND subject = $2 AND purpose = $3 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(&subject)
+    .bind(purpose)
+    .execute(pool)
+    .timed("consents.revoke")
+    .await
+    .context(format!("Failed to revoke {} consent", purpose))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Consents (active and revoked) recorded for an identifier
+pub async fn list_for_subject(
+    pool: &PgPool,
+    kind: SubjectKind,
+    value: &str,
+) -> Result<Vec<Consent>, AppError> {
+    let Some(subject) = kind.normalize(value) else {
+        return Ok(Vec::new());
+    };
+    let consents = sqlx::query_as::<_, Consent>(
+        r#"
+        SELECT subject_type, subject, purpose, source, granted_at, revoked_at
+        FROM consents
+        WHERE subject_type = $1 AND subject = $2
+        ORDER BY purpose
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(&subject)
+    .fetch_all(pool)
+    .timed("consents.list")
+    .await
+    .context("Failed to load consents")?;
+    Ok(consents)
+}
+
+/// Whether any of the identifiers has an active consent for the purpose
+pub async fn has_consent(
+    pool: &PgPool,
+    subjects: &[(SubjectKind, &str)],
+    purpose: &str,
+) -> Result<bool, AppError> {
+    let (kinds, values): (Vec<&str>, Vec<String>) = subjects
+        .iter()
+        .filter_map(|(kind, value)| Some((kind.as_str(), kind.normalize(value)?)))
+        .unzip();
+    if kinds.is_empty() {
+        return Ok(false);
+    }
+    let found: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM consents c
+            JOIN UNNEST($1::text[], $2::text[]) AS s(subject_type, subject)
+              ON c.subject_type = s.subject_type AND c.subject = s.subject
+            WHERE c.purpose = $3 AND c.revoked_at IS NULL
+        )
+        "#,
+    )
+    .bind(&kinds)
+    .bind(&values)
+    .bind(purpose)
+    .fetch_one(pool)
+    .timed("consents.check")
+    .await
+    .context("Failed to check consent")?;
+    Ok(found)
+}
+
+/// Refuse enrichment of a lead of a consent-required tenant without consent
+pub async fn ensure_enrichment_consent(
+    pool: &PgPool,
+    config: &Config,
+    tenant: Option<&str>,
+    subjects: &[(SubjectKind, &str)],
+) -> Result<(), AppError> {
+    if !requires_consent(config, tenant) {
+        return Ok(());
+    }
+    if has_consent(pool, subjects, PURPOSE_ENRICHMENT).await? {
+        return Ok(());
+    }
+    Err(AppError::ConsentRequired(format!(
+        "No enrichment consent recorded for this lead (tenant {})",
+        tenant.unwrap_or_default()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_subjects() {
+        assert_eq!(
+            SubjectKind::Cpf.normalize("123.456.789-01").as_deref(),
+            Some("12345678901")
+        );
+        assert_eq!(
+            SubjectKind::Email.normalize(" Ana@Example.com ").as_deref(),
+            Some("ana@example.com")
+        );
+        assert_eq!(
+            SubjectKind::Phone.normalize("(11) 98765-4321").as_deref(),
+            Some("+5511987654321")
+        );
+        assert_eq!(SubjectKind::Cpf.normalize("--"), None);
+    }
+
+    #[test]
+    fn test_affirmative_answers_and_tenants() {
+        assert!(is_affirmative("Sim"));
+        assert!(is_affirmative(" aceito "));
+        assert!(!is_affirmative("Não"));
+        assert_eq!(
+            parse_tenants(" Locação, ,VENDAS"),
+            vec!["locação", "vendas"]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Cross-provider consistency checks (Diretrix vs Work API)
+//!
+//! Both providers return identity data for a CPF. When they disagree on the
+//! name, birth date or mother's name, one of them is stale or the CPF was
+//! linked to the wrong person. Disagreements are recorded in the enrichment
+//! payload (`data_quality_issues`) and flagged in the C2S message.
+
+use crate::services::{normalize_name, DiretrixPersonData, DiretrixPersonSearch};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Payload key holding the issues found for an enrichment
+pub const ISSUES_KEY: &str = "data_quality_issues";
+
+/// Placeholder providers use for unknown values
+const NO_INFO: &str = "SEM INFORMACAO";
+
+/// Identity fields compared across providers
+#[derive(Debug, Clone, Default)]
+pub struct PersonFields {
+    pub name: Option<String>,
+    pub birth_date: Option<String>,
+    pub mother_name: Option<String>,
+}
+
+impl PersonFields {
+    /// Fields from Work API `DadosBasicos`
+    pub fn from_work_data(work_data: &Value) -> Self {
+        let field = |key: &str| {
+            work_data
+                .get("DadosBasicos")
+                .and_then(|d| d.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            name: field("nome"),
+            birth_date: field("dataNascimento"),
+            mother_name: field("nomeMae"),
+        }
+    }
+}
+
+impl From<&DiretrixPersonData> for PersonFields {
+    fn from(person: &DiretrixPersonData) -> Self {
+        Self {
+            name: Some(person.nome.clone()),
+            birth_date: person.data_nascimento.clone(),
+            mother_name: person.mae.clone(),
+        }
+    }
+}
+
+/// Search results only carry the name
+impl From<&DiretrixPersonSearch> for PersonFields {
+    fn from(person: &DiretrixPersonSearch) -> Self {
+        Self {
+            name: Some(person.nome.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// One field on which Diretrix and Work API disagree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataQualityIssue {
+    /// `nome`, `data_nascimento` or `nome_mae`
+    pub field: String,
+    pub diretrix: String,
+    pub work_api: String,
+}
+
+impl DataQualityIssue {
+    fn label(&self) -> &str {
+        match self.field.as_str() {
+            "nome" => "Nome",
+            "data_nascimento" => "Data Nascimento",
+            "nome_mae" => "Mãe",
+            other => other,
+        }
+    }
+}
+
+/// Compare the fields both providers returned (missing values are not issues)
+pub fn compare(diretrix: &PersonFields, work_api: &PersonFields) -> Vec<DataQualityIssue> {
+    [
+        (
+            "nome",
+            &diretrix.name,
+            &work_api.name,
+            same_name as fn(&str, &str) -> bool,
+        ),
+        (
+            "data_nascimento",
+            &diretrix.birth_date,
+            &work_api.birth_date,
+            same_date,
+        ),
+        (
+            "nome_mae",
+            &diretrix.mother_name,
+            &work_api.mother_name,
+            same_name,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, a, b, same)| {
+        let (a, b) = (known(a)?, known(b)?);
+        (!same(a, b)).then(|| DataQualityIssue {
+            field: field.to_string(),
+            diretrix: a.to_string(),
+            work_api: b.to_string(),
+        })
+    })
+    .collect()
+}
+
+/// Store issues in an enrichment payload (no-op when there are none)
+pub fn record_issues(payload: &mut Value, issues: &[DataQualityIssue]) {
+    if !issues.is_empty() && payload.is_object() {
+        payload[ISSUES_KEY] = json!(issues);
+    }
+}
+
+/// Issues previously recorded in an enrichment payload
+pub fn recorded_issues(payload: &Value) -> Vec<DataQualityIssue> {
+    payload
+        .get(ISSUES_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Message section listing the disagreements
+pub fn format_issues(issues: &[DataQualityIssue]) -> String {
+    let mut section = String::from("\n⚠️ DIVERGÊNCIAS ENTRE FONTES\n");
+    for issue in issues {
+        section.push_str(&format!(
+            "{}: Work API \"{}\" × Diretrix \"{}\"\n",
+            issue.label(),
+            issue.work_api,
+            issue.diretrix
+        ));
+    }
+    section
+}
+
+fn known(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && normalize_name(v) != NO_INFO)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    normalize_name(a) == normalize_name(b)
+}
+
+/// Dates are compared by value; providers use `dd/mm/yyyy` or ISO formats
+fn same_date(a: &str, b: &str) -> bool {
+    match (parse_date(a), parse_date(b)) {
+        (Some(a), Some(b)) => a == b,
+        // Unparseable on either side: nothing reliable to compare
+        _ => true,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%d/%m/%Y")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn work_data(nome: &str, nascimento: &str, mae: &str) -> Value {
+        json!({"DadosBasicos": {"nome": nome, "dataNascimento": nascimento, "nomeMae": mae}})
+    }
+
+    #[test]
+    fn test_consistent_providers_have_no_issues() {
+        let diretrix = PersonFields {
+            name: Some("José da Silva".to_string()),
+            birth_date: Some("1980-03-15T00:00:00".to_string()),
+            mother_name: Some("Maria da Silva".to_string()),
+        };
+        let work = PersonFields::from_work_data(&work_data(
+            "JOSE DA SILVA",
+            "15/03/1980",
+            "MARIA DA SILVA",
+        ));
+
+        assert!(compare(&diretrix, &work).is_empty());
+    }
+
+    #[test]
+    fn test_disagreements_are_reported() {
+        let diretrix = PersonFields {
+            name: Some("JOSE DA SILVA".to_string()),
+            birth_date: Some("16/03/1980".to_string()),
+            mother_name: Some("SEM INFORMAÇÃO".to_string()),
+        };
+        let work = PersonFields::from_work_data(&work_data(
+            "JOSE DA SILVA SANTOS",
+            "15/03/1980",
+            "ANA SANTOS",
+        ));
+
+        let issues = compare(&diretrix, &work);
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(fields, ["nome", "data_nascimento"]);
+
+        let mut payload = work_data("JOSE DA SILVA SANTOS", "15/03/1980", "ANA SANTOS");
+        record_issues(&mut payload, &issues);
+        assert_eq!(recorded_issues(&payload), issues);
+        assert!(format_issues(&issues).contains("Data Nascimento: Work API \"15/03/1980\""));
+    }
+}
@@ -0,0 +1,124 @@
+//! Chronological contact history of a party
+//!
+//! Sellers regularly ask whether the phone of a lead is recent. Every
+//! enrichment storing a party now dates its contacts and addresses
+//! (migration 046):
+//!
+//! - `valid_from`: first time the value was seen for the party
+//! - `last_confirmed_at`: last time a provider returned it
+//! - `valid_to`: when the provider stopped returning it (cleared if it comes back)
+//!
+//! `party_contact_history` lists them oldest first, with the age of each
+//! value. Served by `GET /api/v1/customers/:id/contacts/history`.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// One contact or address of a party
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ContactHistoryEntry {
+    /// `phone`, `whatsapp`, `email` or `address`
+    pub kind: String,
+    pub value: String,
+    pub source: Option<String>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_confirmed_at: Option<DateTime<Utc>>,
+    /// Set once a provider stopped returning the value
+    pub valid_to: Option<DateTime<Utc>>,
+    /// Whether the value is still returned
+    #[sqlx(skip)]
+    pub current: bool,
+    /// Days since the value was first seen
+    #[sqlx(skip)]
+    pub age_days: i64,
+}
+
+/// Contacts and addresses of a party, oldest first
+pub async fn party_contact_history(
+    pool: &PgPool,
+    party_id: Uuid,
+) -> Result<Vec<ContactHistoryEntry>, AppError> {
+    let entries = sqlx::query_as::<_, ContactHistoryEntry>(CONTACT_HISTORY_SQL)
+        .bind(party_id)
+        .fetch_all(pool)
+        .timed("contact_history.load")
+        .await
+        .context(ErrorContext::db("contact_history.load").id("party_id", party_id))?;
+
+    Ok(with_age(entries, Utc::now()))
+}
+
+/// Sort entries by first sighting and fill `current` and `age_days` at `now`
+pub fn with_age(
+    mut entries: Vec<ContactHistoryEntry>,
+    now: DateTime<Utc>,
+) -> Vec<ContactHistoryEntry> {
+    entries.sort_by_key(|e| e.first_seen_at);
+    for entry in &mut entries {
+        entry.current = entry.valid_to.is_none();
+        entry.age_days = (now - entry.first_seen_at).num_days().max(0);
+    }
+    entries
+}
+
+pub(crate) const CONTACT_HISTORY_SQL: &str = r#"
+    SELECT contact_type::text AS kind, value, source,
+           COALESCE(valid_from, created_at) AS first_seen_at,
+           last_confirmed_at, valid_to
+    FROM core.party_contacts
+    WHERE party_id = $1
+    UNION ALL
+    SELECT 'address',
+           COALESCE(a.formatted_address,
+                    concat_ws(', ', a.street, a.number, a.neighborhood, a.city, a.state, a.zip_code)),
+           pa.metadata->>'source',
+           COALESCE(pa.valid_from, pa.created_at, now()),
+           pa.last_confirmed_at, pa.valid_to
+    FROM core.party_addresses pa
+    JOIN core.addresses a ON a.id = pa.address_id
+    WHERE pa.party_id = $1
+    ORDER BY first_seen_at
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &str, first_seen_days_ago: i64, retired: bool) -> ContactHistoryEntry {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        ContactHistoryEntry {
+            kind: kind.to_string(),
+            value: format!("{}-value", kind),
+            source: None,
+            first_seen_at: now - chrono::Duration::days(first_seen_days_ago),
+            last_confirmed_at: None,
+            valid_to: retired.then_some(now),
+            current: false,
+            age_days: 0,
+        }
+    }
+
+    #[test]
+    fn test_history_is_chronological_with_age() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let entries = with_age(
+            vec![
+                entry("phone", 3, false),
+                entry("email", 400, true),
+                entry("address", 30, false),
+            ],
+            now,
+        );
+
+        let kinds: Vec<&str> = entries.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["email", "address", "phone"]);
+        let ages: Vec<i64> = entries.iter().map(|e| e.age_days).collect();
+        assert_eq!(ages, vec![400, 30, 3]);
+        assert!(!entries[0].current);
+        assert!(entries[2].current);
+    }
+}
@@ -1,12 +1,56 @@
 // Domain-layer modules and shared errors/models
+pub mod affordability {
+    pub use crate::affordability::*;
+}
+
+pub mod consent {
+    pub use crate::consent::*;
+}
+
+pub mod consistency {
+    pub use crate::consistency::*;
+}
+
+pub mod ddd {
+    pub use crate::ddd::*;
+}
+
 pub mod enrichment {
     pub use crate::enrichment::*;
 }
 
+pub mod identity {
+    pub use crate::identity::*;
+}
+
 pub mod models {
     pub use crate::models::*;
 }
 
+pub mod money {
+    pub use crate::money::*;
+}
+
+pub mod partners {
+    pub use crate::partners::*;
+}
+
+pub mod phonetic {
+    pub use crate::phonetic::*;
+}
+
+pub mod property_refs {
+    pub use crate::property_refs::*;
+}
+
+pub mod quiet_hours {
+    pub use crate::quiet_hours::*;
+}
+
+pub mod routing {
+    pub use crate::routing::*;
+}
+
 pub mod services {
     pub use crate::services::*;
 }
@@ -14,3 +58,7 @@ pub mod services {
 pub mod errors {
     pub use crate::errors::*;
 }
+
+pub mod templates {
+    pub use crate::templates::*;
+}
@@ -0,0 +1,182 @@
+//! CORS policy of the API
+//!
+//! The admin tooling calls the API from the browser with `X-Admin-Token`, so
+//! cross-origin access is limited to the origins listed in
+//! `CORS_ALLOWED_ORIGINS` (comma-separated, `*` for any origin, e.g. in a
+//! development environment). Unset, no origin is allowed and browsers only
+//! reach the API from its own origin (Swagger UI at `/docs`). Methods and
+//! request headers default to what the API uses and can be overridden with
+//! `CORS_ALLOWED_METHODS` and `CORS_ALLOWED_HEADERS`.
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+/// Methods allowed when `CORS_ALLOWED_METHODS` is unset
+const DEFAULT_METHODS: &str = "GET,POST,PUT,DELETE,OPTIONS";
+
+/// Request headers allowed when `CORS_ALLOWED_HEADERS` is unset
+const DEFAULT_HEADERS: &str =
+    "content-type,authorization,x-admin-token,x-api-key,if-none-match,x-request-deadline";
+
+/// Response headers readable by browser scripts
+const EXPOSED_HEADERS: [&str; 2] = ["etag", "x-degraded"];
+
+/// One allowlist: everything, or the listed values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Allowed<T> {
+    Any,
+    List(Vec<T>),
+}
+
+/// Allowed cross-origin requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    pub origins: Allowed<HeaderValue>,
+    pub methods: Allowed<Method>,
+    pub headers: Allowed<HeaderName>,
+}
+
+impl Default for CorsPolicy {
+    /// No cross-origin access
+    fn default() -> Self {
+        parse_policy("", "", "").expect("default CORS policy is valid")
+    }
+}
+
+impl CorsPolicy {
+    pub fn allows_any_origin(&self) -> bool {
+        self.origins == Allowed::Any
+    }
+
+    /// Origins for the startup log
+    pub fn describe_origins(&self) -> String {
+        match &self.origins {
+            Allowed::Any => "*".to_string(),
+            Allowed::List(origins) if origins.is_empty() => "none".to_string(),
+            Allowed::List(origins) => origins
+                .iter()
+                .filter_map(|o| o.to_str().ok())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let origins = match &self.origins {
+            Allowed::Any => AllowOrigin::any(),
+            Allowed::List(origins) => AllowOrigin::list(origins.clone()),
+        };
+        let methods = match &self.methods {
+            Allowed::Any => AllowMethods::any(),
+            Allowed::List(methods) => AllowMethods::list(methods.clone()),
+        };
+        let headers = match &self.headers {
+            Allowed::Any => AllowHeaders::any(),
+            Allowed::List(headers) => AllowHeaders::list(headers.clone()),
+        };
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+    }
+}
+
+/// Parse `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS` and `CORS_ALLOWED_HEADERS`
+pub fn parse_policy(origins: &str, methods: &str, headers: &str) -> anyhow::Result<CorsPolicy> {
+    Ok(CorsPolicy {
+        origins: parse_list(origins, "", "CORS_ALLOWED_ORIGINS", parse_origin)?,
+        methods: parse_list(methods, DEFAULT_METHODS, "CORS_ALLOWED_METHODS", |m| {
+            Method::from_bytes(m.to_uppercase().as_bytes()).map_err(|e| e.to_string())
+        })?,
+        headers: parse_list(headers, DEFAULT_HEADERS, "CORS_ALLOWED_HEADERS", |h| {
+            HeaderName::from_bytes(h.to_lowercase().as_bytes()).map_err(|e| e.to_string())
+        })?,
+    })
+}
+
+fn parse_list<T>(
+    raw: &str,
+    default: &str,
+    variable: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> anyhow::Result<Allowed<T>> {
+    let raw = if raw.trim().is_empty() { default } else { raw };
+    let values: Vec<&str> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+    if values.contains(&"*") {
+        return Ok(Allowed::Any);
+    }
+    values
+        .into_iter()
+        .map(|v| {
+            parse(v).map_err(|e| anyhow::anyhow!("{}: invalid value '{}': {}", variable, v, e))
+        })
+        .collect::<anyhow::Result<_>>()
+        .map(Allowed::List)
+}
+
+/// `scheme://host[:port]`, as browsers send it in the `Origin` header
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let url = url::Url::parse(origin).map_err(|e| e.to_string())?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err("expected http(s)://host[:port]".to_string());
+    }
+    if url.path() != "/" || origin.ends_with('/') || url.query().is_some() {
+        return Err("an origin has no path (remove the trailing slash)".to_string());
+    }
+    HeaderValue::from_str(origin).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_denies_cross_origin() {
+        let policy = CorsPolicy::default();
+        assert_eq!(policy.origins, Allowed::List(vec![]));
+        assert_eq!(policy.describe_origins(), "none");
+        let Allowed::List(methods) = &policy.methods else {
+            panic!("default methods are a list");
+        };
+        assert!(methods.contains(&Method::PUT));
+        let Allowed::List(headers) = &policy.headers else {
+            panic!("default headers are a list");
+        };
+        assert!(headers.contains(&HeaderName::from_static("x-admin-token")));
+        assert!(headers.contains(&HeaderName::from_static("x-api-key")));
+        assert!(headers.contains(&HeaderName::from_static(crate::deadline::DEADLINE_HEADER)));
+    }
+
+    #[test]
+    fn test_parse_allowlist() {
+        let policy = parse_policy(
+            "https://admin.example.com, http://localhost:3000",
+            "get,post",
+            "*",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.describe_origins(),
+            "https://admin.example.com, http://localhost:3000"
+        );
+        assert_eq!(
+            policy.methods,
+            Allowed::List(vec![Method::GET, Method::POST])
+        );
+        assert_eq!(policy.headers, Allowed::Any);
+        assert!(parse_policy("*", "", "").unwrap().allows_any_origin());
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        assert!(parse_policy("https://admin.example.com/", "", "").is_err());
+        assert!(parse_policy("admin.example.com", "", "").is_err());
+        assert!(parse_policy("", "GE T", "").is_err());
+        assert!(parse_policy("", "", "x admin").is_err());
+    }
+}
@@ -6,3 +6,47 @@ pub mod db {
 pub mod db_storage {
     pub use crate::db_storage::*;
 }
+
+pub mod contact_history {
+    pub use crate::contact_history::*;
+}
+
+pub mod primary_contact {
+    pub use crate::primary_contact::*;
+}
+
+pub mod lead_messages {
+    pub use crate::lead_messages::*;
+}
+
+pub mod outbox {
+    pub use crate::outbox::*;
+}
+
+pub mod leader {
+    pub use crate::leader::*;
+}
+
+pub mod anonymizer {
+    pub use crate::anonymizer::*;
+}
+
+pub mod retention {
+    pub use crate::retention::*;
+}
+
+pub mod caches {
+    pub use crate::caches::*;
+}
+
+pub mod debug_payloads {
+    pub use crate::debug_payloads::*;
+}
+
+pub mod privacy {
+    pub use crate::privacy::*;
+}
+
+pub mod jobs {
+    pub use crate::jobs::*;
+}
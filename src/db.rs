@@ -1,16 +1,32 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions, PgPool,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default slow-query threshold
+const DEFAULT_SLOW_QUERY_MS: u64 = 500;
+
+/// Queries slower than this are logged (set from `SLOW_QUERY_MS` at startup)
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_SLOW_QUERY_MS);
 
 /// Database connection pool with resilience features
 ///
 /// Features:
 /// - Connection pooling (max 10 connections)
 /// - Automatic reconnection on connection failure
+/// - Slow statements logged by sqlx with their SQL text only (bind
+///   parameters are never logged); see also `TimedQuery`
 ///
 /// Circuit Breaker Usage:
 /// For critical operations, wrap database calls with the circuit breaker
 /// from `crate::circuit_breaker::create_db_circuit_breaker()`:
 ///
-/// ```rust
+/// ```rust,ignore
 /// use crate::circuit_breaker::create_db_circuit_breaker;
 ///
 /// let cb = create_db_circuit_breaker();
@@ -29,9 +45,24 @@ pub struct Database {
 
 impl Database {
     pub async fn new(database_url: &str) -> anyhow::Result<Self> {
+        Self::with_slow_query_threshold(database_url, Duration::from_millis(DEFAULT_SLOW_QUERY_MS))
+            .await
+    }
+
+    /// Connect, logging queries slower than `slow_query_threshold`
+    pub async fn with_slow_query_threshold(
+        database_url: &str,
+        slow_query_threshold: Duration,
+    ) -> anyhow::Result<Self> {
+        SLOW_QUERY_THRESHOLD_MS.store(slow_query_threshold.as_millis() as u64, Ordering::Relaxed);
+
+        let connect_options = PgConnectOptions::from_str(database_url)?
+            .log_statements(log::LevelFilter::Trace)
+            .log_slow_statements(log::LevelFilter::Warn, slow_query_threshold);
+
         let pool = PgPoolOptions::new()
             .max_connections(10)
-            .connect(database_url)
+            .connect_with(connect_options)
             .await?;
 
         // Run migrations if needed
@@ -40,3 +71,45 @@ impl Database {
         Ok(Self { pool })
     }
 }
+
+type TimedFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, sqlx::Error>> + Send + 'a>>;
+
+/// Extension trait timing sqlx queries per named operation
+///
+/// Records latency and errors (exported via `/metrics`) and warns when the
+/// query exceeds the slow-query threshold. Only the operation name is logged.
+///
+/// ```rust,ignore
+/// let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(...)")
+///     .bind(lead_id)
+///     .fetch_one(db)
+///     .timed("webhook_events.exists")
+///     .await?;
+/// ```
+pub trait TimedQuery<'a, T>: Future<Output = Result<T, sqlx::Error>> + Send + Sized + 'a {
+    fn timed(self, operation: &'static str) -> TimedFuture<'a, T> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = self.await;
+            let elapsed = start.elapsed();
+
+            crate::metrics::record_query(operation, elapsed, result.is_ok());
+            let threshold = SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed);
+            if elapsed.as_millis() as u64 >= threshold {
+                tracing::warn!(
+                    "Slow query: {} took {}ms (threshold {}ms)",
+                    operation,
+                    elapsed.as_millis(),
+                    threshold
+                );
+            }
+
+            result
+        })
+    }
+}
+
+impl<'a, T, F> TimedQuery<'a, T> for F where
+    F: Future<Output = Result<T, sqlx::Error>> + Send + Sized + 'a
+{
+}
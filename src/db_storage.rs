@@ -1,19 +1,116 @@
+use crate::db::TimedQuery;
+use crate::ddd::DddRegion;
+use crate::enrichment::{is_cnpj, ExistingEnrichment};
 use crate::errors::{AppError, ResultExt};
 use crate::models::WorkApiCompleteResponse;
+use crate::partners::{extract_partners, CompanyPartner};
+use crate::phonetic::phonetic_key;
+use crate::services::{cpf_lookup_key, email_lookup_key, normalize_name, phone_lookup_key};
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use serde::Serialize;
 use serde_json::json;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use std::str::FromStr;
 use uuid::Uuid;
 
 /// Database storage service for enriched person data
 pub struct EnrichmentStorage {
     pool: PgPool,
+    lookup_cache: Option<Cache<String, Uuid>>,
+    contact_cache: Option<Cache<String, Option<ExistingEnrichment>>>,
 }
 
 impl EnrichmentStorage {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            lookup_cache: None,
+            contact_cache: None,
+        }
+    }
+
+    /// Invalidate customer lookup cache entries for identifiers written by this storage
+    pub fn with_lookup_cache(mut self, cache: Cache<String, Uuid>) -> Self {
+        self.lookup_cache = Some(cache);
+        self
+    }
+
+    /// Invalidate contact → CPF cache entries for contacts written by this storage
+    pub fn with_contact_cache(mut self, cache: Cache<String, Option<ExistingEnrichment>>) -> Self {
+        self.contact_cache = Some(cache);
+        self
+    }
+
+    async fn invalidate_lookup(&self, key: String) {
+        if let Some(cache) = self.lookup_cache.as_ref() {
+            cache.invalidate(&key).await;
+        }
+        if let Some(cache) = self.contact_cache.as_ref() {
+            cache.invalidate(&key).await;
+        }
+    }
+
+    /// Invalidate the cached lookups of every contact of a party, in the background
+    ///
+    /// Called whenever a party is (re)written: an upsert on an existing CPF
+    /// merges the new data into that party, and contacts linked to it before
+    /// (or its old enrichment snapshot) must not be served from the 24h
+    /// contact cache after the correction.
+    pub fn invalidate_party_contacts(&self, party_id: Uuid) {
+        if self.lookup_cache.is_none() && self.contact_cache.is_none() {
+            return;
+        }
+        let pool = self.pool.clone();
+        let lookup_cache = self.lookup_cache.clone();
+        let contact_cache = self.contact_cache.clone();
+        tokio::spawn(async move {
+            let contacts: Vec<(String, String)> = match sqlx::query!(
+                r#"
+                SELECT contact_type::text AS "contact_type!", value
+                FROM core.party_contacts
+                WHERE party_id = $1
+                "#,
+                party_id
+            )
+            .fetch_all(&pool)
+            .timed("party_contacts.list_for_invalidation")
+            .await
+            {
+                Ok(rows) => rows
+                    .into_iter()
+                    .map(|row| (row.contact_type, row.value))
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to list contacts of party {} for cache invalidation: {}",
+                        party_id,
+                        e
+                    );
+                    return;
+                }
+            };
+
+            for (contact_type, value) in &contacts {
+                let key = if contact_type == "email" {
+                    email_lookup_key(value)
+                } else {
+                    phone_lookup_key(value)
+                };
+                if let Some(cache) = lookup_cache.as_ref() {
+                    cache.invalidate(&key).await;
+                }
+                if let Some(cache) = contact_cache.as_ref() {
+                    cache.invalidate(&key).await;
+                }
+            }
+            tracing::debug!(
+                "Invalidated cached lookups of {} contact(s) of party {}",
+                contacts.len(),
+                party_id
+            );
+        });
     }
 
     /// Store or update enriched person data from Work API
@@ -29,6 +126,9 @@ impl EnrichmentStorage {
     }
 
     /// Store enriched person data with optional lead_id for C2S tracking
+    ///
+    /// All writes share one transaction: any failure (contact, address,
+    /// snapshot) leaves the party as it was before the call.
     pub async fn store_enriched_person_with_lead(
         &self,
         cpf: &str,
@@ -122,8 +222,9 @@ impl EnrichmentStorage {
         });
 
         // Build financial metadata
-        // Create canonical name (uppercase, normalized)
-        let canonical_name = nome.to_uppercase();
+        // Create canonical name (uppercase, unaccented; searched by find_by_name)
+        let canonical_name = normalize_name(nome);
+        let phonetic_name = phonetic_key(nome);
 
         // Build payload for enrichment (attach lead_id if present) and normalized data (addresses)
         let mut enrichment_payload = work_data.clone();
@@ -135,17 +236,26 @@ impl EnrichmentStorage {
             normalized_data["addresses"] = serde_json::Value::Array(enderecos.to_vec());
         }
 
+        // Steps 1-4 run in one transaction: a failure rolls back the party,
+        // so no half-written party (no contacts, no snapshot) is left behind
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context(format!("Failed to start transaction for CPF: {}", cpf))?;
+
         // Step 1: Upsert party
-        let party_id = match sqlx::query_as::<_, (Uuid,)>(
+        let party_id = match sqlx::query_scalar!(
             "SELECT id FROM core.parties WHERE cpf_cnpj = $1 LIMIT 1",
+            cpf
         )
-        .bind(cpf)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *tx)
+        .timed("parties.find_by_cpf")
         .await
         .context(format!("Failed to check existing party for CPF: {}", cpf))?
         {
             Some(existing) => {
-                sqlx::query(
+                sqlx::query!(
                     r#"
                     UPDATE core.parties
                     SET party_type = COALESCE(party_type, $2),
@@ -158,56 +268,61 @@ impl EnrichmentStorage {
                         opening_date = COALESCE(opening_date, $8),
                         company_type = COALESCE(company_type, $9),
                         company_size = COALESCE(company_size, $10),
+                        phonetic_name = COALESCE(phonetic_name, $11),
                         updated_at = now()
                     WHERE id = $1
                     "#,
+                    existing,
+                    "person",
+                    nome,
+                    canonical_name,
+                    data_nasc,
+                    Some(sexo.to_string()),
+                    nome_mae,
+                    None::<chrono::NaiveDate>,
+                    None::<String>,
+                    None::<String>,
+                    phonetic_name
                 )
-                .bind(existing.0)
-                .bind("person")
-                .bind(nome)
-                .bind(&canonical_name)
-                .bind(data_nasc)
-                .bind(Some(sexo.to_string()))
-                .bind(nome_mae)
-                .bind(None::<chrono::NaiveDate>)
-                .bind(None::<String>)
-                .bind(None::<String>)
-                .execute(&self.pool)
+                .execute(&mut *tx)
+                .timed("parties.update")
                 .await
                 .context(format!("Failed to update existing party for CPF: {}", cpf))?;
-                existing.0
+                existing
             }
             None => {
-                let inserted: (Uuid,) = sqlx::query_as(
+                let inserted: Uuid = sqlx::query_scalar!(
                     r#"
                     INSERT INTO core.parties (
                         id, party_type, cpf_cnpj, full_name, normalized_name, enriched,
                         birth_date, sex, mother_name, opening_date, company_type, company_size,
-                        created_at, updated_at
+                        phonetic_name, created_at, updated_at
                     )
-                    VALUES (gen_random_uuid(), $1, $2, $3, $4, true, $5, $6, $7, $8, $9, $10, now(), now())
+                    VALUES (gen_random_uuid(), $1, $2, $3, $4, true, $5, $6, $7, $8, $9, $10, $11, now(), now())
                     RETURNING id
                     "#,
+                    "person",
+                    cpf,
+                    nome,
+                    canonical_name,
+                    data_nasc,
+                    Some(sexo.to_string()),
+                    nome_mae,
+                    None::<chrono::NaiveDate>,
+                    None::<String>,
+                    None::<String>,
+                    phonetic_name
                 )
-                .bind("person")
-                .bind(cpf)
-                .bind(nome)
-                .bind(&canonical_name)
-                .bind(data_nasc)
-                .bind(Some(sexo.to_string()))
-                .bind(nome_mae)
-                .bind(None::<chrono::NaiveDate>)
-                .bind(None::<String>)
-                .bind(None::<String>)
-                .fetch_one(&self.pool)
+                .fetch_one(&mut *tx)
+                .timed("parties.insert")
                 .await
                 .context(format!("Failed to insert new party for CPF: {}", cpf))?;
-                inserted.0
+                inserted
             }
         };
 
         // Step 2: Upsert people
-        sqlx::query(
+        sqlx::query!(
             r#"
             INSERT INTO core.people (
                 party_id, full_name, mothers_name, birth_date, sex,
@@ -223,15 +338,16 @@ impl EnrichmentStorage {
                 document_cpf = COALESCE(EXCLUDED.document_cpf, core.people.document_cpf),
                 updated_at = EXCLUDED.updated_at
             "#,
+            party_id,
+            nome,
+            nome_mae,
+            data_nasc,
+            Some(sexo.to_string()),
+            estado_civil,
+            cpf
         )
-        .bind(party_id)
-        .bind(nome)
-        .bind(nome_mae)
-        .bind(data_nasc)
-        .bind(Some(sexo.to_string()))
-        .bind(estado_civil)
-        .bind(cpf)
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .timed("people.upsert")
         .await
         .context(format!(
             "Failed to insert person record for party_id: {}",
@@ -239,19 +355,27 @@ impl EnrichmentStorage {
         ))?;
 
         // Step 3: Store contacts
+        let mut stale_keys = vec![cpf_lookup_key(cpf)];
         if let Some(emails) = work_data.get("emails").and_then(|e| e.as_array()) {
-            self.store_party_emails(party_id, emails)
-                .await
-                .context(format!("Failed to store emails for party_id: {}", party_id))?;
+            stale_keys.extend(
+                self.store_party_emails(&mut tx, party_id, emails)
+                    .await
+                    .context(format!("Failed to store emails for party_id: {}", party_id))?,
+            );
         }
         if let Some(telefones) = work_data.get("telefones").and_then(|t| t.as_array()) {
-            self.store_party_phones(party_id, telefones)
-                .await
-                .context(format!("Failed to store phones for party_id: {}", party_id))?;
+            stale_keys.extend(
+                self.store_party_phones(&mut tx, party_id, telefones)
+                    .await
+                    .context(format!("Failed to store phones for party_id: {}", party_id))?,
+            );
         }
+        crate::primary_contact::elect_primary_contacts(&mut *tx, &[party_id]).await?;
         if let Some(enderecos) = work_data.get("enderecos").and_then(|e| e.as_array()) {
-            self.store_party_addresses(party_id, enderecos).await?;
+            self.store_party_addresses(&mut tx, party_id, enderecos)
+                .await?;
         }
+        crate::residency::refresh_party_uf(&mut *tx, &[party_id]).await?;
 
         // Step 4: Store enrichment snapshot
         let quality_score = risk_score
@@ -259,42 +383,408 @@ impl EnrichmentStorage {
             .and_then(|bd| bd.to_string().parse::<f64>().ok())
             .unwrap_or(0.5);
 
-        sqlx::query(
+        self.store_enrichment_snapshot(&mut tx, party_id, &enrichment_payload, quality_score)
+            .await?;
+
+        tx.commit()
+            .await
+            .context(format!("Failed to commit enriched data for CPF: {}", cpf))?;
+
+        // Only evicted once committed, so a concurrent lookup cannot re-cache the old rows
+        for key in stale_keys {
+            self.invalidate_lookup(key).await;
+        }
+        self.invalidate_party_contacts(party_id);
+
+        tracing::info!(
+            "Successfully stored enriched data for CPF: {} (party_id: {})",
+            cpf,
+            party_id
+        );
+
+        Ok(party_id)
+    }
+
+    /// Store or update enriched company data (document is a CNPJ)
+    ///
+    /// Parallel to `store_enriched_person_with_lead`: upserts a `company` party,
+    /// its `core.companies` row (legal name, CNAE, size), contacts, addresses and
+    /// the enrichment snapshot. Fields are read from `DadosEmpresa` (Work API
+    /// `modulo=cnpj`) or `SituacaoCadastral` (`WorkApiService::fetch_company_modules`),
+    /// falling back to the payload root.
+    pub async fn store_enriched_company(
+        &self,
+        cnpj: &str,
+        company_data: &serde_json::Value,
+        lead_id: Option<&str>,
+    ) -> Result<Uuid, AppError> {
+        let field = |key: &str| {
+            ["DadosEmpresa", "SituacaoCadastral"]
+                .iter()
+                .find_map(|section| company_data.get(section).and_then(|d| d.get(key)))
+                .or_else(|| company_data.get(key))
+        };
+        let text = |key: &str| {
+            field(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let legal_name = text("razaoSocial").unwrap_or("");
+        let trade_name = text("nomeFantasia");
+        let company_size = text("porte");
+        let legal_nature = text("naturezaJuridica");
+        let opening_date = text("dataAbertura").and_then(|d| parse_br_date(d).ok());
+        let status_date = text("dataSituacaoCadastral").and_then(|d| parse_br_date(d).ok());
+        let (cnae, industry) = parse_cnae(field("cnaePrincipal"));
+
+        let mut enrichment_payload = company_data.clone();
+        if let Some(lid) = lead_id {
+            enrichment_payload["lead_id"] = json!(lid);
+        }
+
+        // Step 1: Upsert party (single statement, cpf_cnpj is unique)
+        let party_id: Uuid = sqlx::query_scalar!(
+            r#"
+            INSERT INTO core.parties (
+                id, party_type, cpf_cnpj, full_name, normalized_name, phonetic_name,
+                fantasy_name, normalized_fantasy_name, opening_date, registration_status_date,
+                company_type, company_size, enriched, created_at, updated_at
+            )
+            VALUES (gen_random_uuid(), 'company', $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, true, now(), now())
+            ON CONFLICT (cpf_cnpj) WHERE cpf_cnpj IS NOT NULL DO UPDATE
+            SET full_name = COALESCE(NULLIF(core.parties.full_name, ''), EXCLUDED.full_name),
+                normalized_name = COALESCE(core.parties.normalized_name, EXCLUDED.normalized_name),
+                phonetic_name = COALESCE(core.parties.phonetic_name, EXCLUDED.phonetic_name),
+                fantasy_name = COALESCE(EXCLUDED.fantasy_name, core.parties.fantasy_name),
+                normalized_fantasy_name = COALESCE(EXCLUDED.normalized_fantasy_name, core.parties.normalized_fantasy_name),
+                opening_date = COALESCE(core.parties.opening_date, EXCLUDED.opening_date),
+                registration_status_date = COALESCE(EXCLUDED.registration_status_date, core.parties.registration_status_date),
+                company_type = COALESCE(EXCLUDED.company_type, core.parties.company_type),
+                company_size = COALESCE(EXCLUDED.company_size, core.parties.company_size),
+                enriched = true,
+                updated_at = now()
+            RETURNING id
+            "#,
+            cnpj,
+            legal_name,
+            normalize_name(legal_name),
+            phonetic_key(legal_name),
+            trade_name,
+            trade_name.map(normalize_name),
+            opening_date,
+            status_date,
+            legal_nature,
+            company_size
+        )
+        .fetch_one(&self.pool)
+        .timed("parties.upsert_company")
+        .await
+        .context(format!("Failed to upsert company party for CNPJ: {}", cnpj))?;
+
+        // Step 2: Upsert companies
+        sqlx::query!(
+            r#"
+            INSERT INTO core.companies (
+                party_id, legal_name, trade_name, cnpj, company_size, industry, cnae,
+                foundation_date, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now(), now())
+            ON CONFLICT (party_id) DO UPDATE
+            SET legal_name = EXCLUDED.legal_name,
+                trade_name = COALESCE(EXCLUDED.trade_name, core.companies.trade_name),
+                cnpj = EXCLUDED.cnpj,
+                company_size = COALESCE(EXCLUDED.company_size, core.companies.company_size),
+                industry = COALESCE(EXCLUDED.industry, core.companies.industry),
+                cnae = COALESCE(EXCLUDED.cnae, core.companies.cnae),
+                foundation_date = COALESCE(EXCLUDED.foundation_date, core.companies.foundation_date),
+                updated_at = EXCLUDED.updated_at
+            "#,
+            party_id,
+            legal_name,
+            trade_name,
+            cnpj,
+            company_size,
+            industry.as_deref(),
+            cnae.as_deref(),
+            opening_date
+        )
+        .execute(&self.pool)
+        .timed("companies.upsert")
+        .await
+        .context(format!(
+            "Failed to insert company record for party_id: {}",
+            party_id
+        ))?;
+
+        // Step 3: Store contacts (same layout as the CPF module)
+        let mut conn = self.pool.acquire().await.context(format!(
+            "Failed to acquire connection for party_id: {}",
+            party_id
+        ))?;
+        let mut stale_keys = Vec::new();
+        if let Some(emails) = field("emails").and_then(|e| e.as_array()) {
+            stale_keys.extend(
+                self.store_party_emails(&mut conn, party_id, emails)
+                    .await
+                    .context(format!("Failed to store emails for party_id: {}", party_id))?,
+            );
+        }
+        if let Some(telefones) = field("telefones").and_then(|t| t.as_array()) {
+            stale_keys.extend(
+                self.store_party_phones(&mut conn, party_id, telefones)
+                    .await
+                    .context(format!("Failed to store phones for party_id: {}", party_id))?,
+            );
+        }
+        crate::primary_contact::elect_primary_contacts(&mut *conn, &[party_id]).await?;
+        if let Some(enderecos) = field("enderecos").and_then(|e| e.as_array()) {
+            self.store_party_addresses(&mut conn, party_id, enderecos)
+                .await?;
+        }
+        crate::residency::refresh_party_uf(&mut *conn, &[party_id]).await?;
+
+        // Step 4: Link partners (QSA); failures do not lose the company data
+        let partners = extract_partners(company_data);
+        if !partners.is_empty() {
+            match self.store_company_partners(party_id, &partners).await {
+                Ok(linked) => tracing::info!(
+                    "Linked {}/{} partners to company party_id: {}",
+                    linked,
+                    partners.len(),
+                    party_id
+                ),
+                Err(e) => tracing::warn!(
+                    "Failed to link partners to company party_id {}: {}",
+                    party_id,
+                    e
+                ),
+            }
+        }
+
+        // Step 5: Store enrichment snapshot (no credit score for companies)
+        self.store_enrichment_snapshot(&mut conn, party_id, &enrichment_payload, 0.5)
+            .await?;
+        for key in stale_keys {
+            self.invalidate_lookup(key).await;
+        }
+        self.invalidate_party_contacts(party_id);
+
+        tracing::info!(
+            "Successfully stored enriched data for CNPJ: {} (party_id: {})",
+            cnpj,
+            party_id
+        );
+
+        Ok(party_id)
+    }
+
+    /// Link partners to a company party (`ownership` relationships)
+    ///
+    /// Partners with a full CPF are located or created as person parties.
+    /// Without a CPF (masked in the QSA) the partner is only linked when
+    /// exactly one person party has the same normalized name. Returns how many
+    /// partners were linked.
+    pub async fn store_company_partners(
+        &self,
+        company_party_id: Uuid,
+        partners: &[CompanyPartner],
+    ) -> Result<usize, AppError> {
+        let mut linked = 0;
+        for partner in partners {
+            let person_id = match &partner.cpf {
+                Some(cpf) => Some(self.upsert_partner_person(cpf, &partner.name).await?),
+                None => self.find_person_by_exact_name(&partner.name).await?,
+            };
+            let Some(person_id) = person_id else {
+                tracing::debug!(
+                    "Partner {} has no CPF and no unique name match, not linked",
+                    partner.name
+                );
+                continue;
+            };
+
+            sqlx::query!(
+                r#"
+                INSERT INTO core.party_relationships (
+                    source_party_id, target_party_id, relationship_type, confidence,
+                    start_date, created_at, updated_at
+                )
+                SELECT $1, $2, 'ownership', $3::float8, COALESCE($4, CURRENT_DATE), now(), now()
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM core.party_relationships
+                    WHERE source_party_id = $1
+                      AND target_party_id = $2
+                      AND relationship_type = 'ownership'
+                      AND end_date IS NULL
+                )
+                "#,
+                person_id,
+                company_party_id,
+                // Registry data: verified with a CPF, secondary when matched by name
+                if partner.cpf.is_some() { 0.95 } else { 0.7 },
+                partner.since
+            )
+            .execute(&self.pool)
+            .timed("party_relationships.insert_partner")
+            .await
+            .context(format!(
+                "Failed to link partner to company party_id: {}",
+                company_party_id
+            ))?;
+            linked += 1;
+        }
+
+        Ok(linked)
+    }
+
+    /// Person party for a partner CPF, created (not enriched) if missing
+    async fn upsert_partner_person(&self, cpf: &str, name: &str) -> Result<Uuid, AppError> {
+        let party_id: Uuid = sqlx::query_scalar!(
+            r#"
+            INSERT INTO core.parties (
+                id, party_type, cpf_cnpj, full_name, normalized_name, phonetic_name,
+                enriched, created_at, updated_at
+            )
+            VALUES (gen_random_uuid(), 'person', $1, $2, $3, $4, false, now(), now())
+            ON CONFLICT (cpf_cnpj) WHERE cpf_cnpj IS NOT NULL DO UPDATE
+            SET full_name = COALESCE(NULLIF(core.parties.full_name, ''), EXCLUDED.full_name)
+            RETURNING id
+            "#,
+            cpf,
+            name,
+            normalize_name(name),
+            phonetic_key(name)
+        )
+        .fetch_one(&self.pool)
+        .timed("parties.upsert_partner")
+        .await
+        .context(format!("Failed to upsert partner party for CPF: {}", cpf))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO core.people (party_id, full_name, document_cpf, created_at, updated_at)
+            VALUES ($1, $2, $3, now(), now())
+            ON CONFLICT (party_id) DO NOTHING
+            "#,
+            party_id,
+            name,
+            cpf
+        )
+        .execute(&self.pool)
+        .timed("people.insert_partner")
+        .await
+        .context(format!(
+            "Failed to insert partner person for party_id: {}",
+            party_id
+        ))?;
+
+        Ok(party_id)
+    }
+
+    /// The only person party with this normalized name, if exactly one exists
+    async fn find_person_by_exact_name(&self, name: &str) -> Result<Option<Uuid>, AppError> {
+        let matches: Vec<Uuid> = sqlx::query_scalar!(
+            "SELECT id FROM core.parties
+             WHERE normalized_name = $1 AND party_type = 'person'
+             LIMIT 2",
+            normalize_name(name)
+        )
+        .fetch_all(&self.pool)
+        .timed("parties.find_by_exact_name")
+        .await
+        .context("Failed to look up partner by name")?;
+
+        Ok(match matches.as_slice() {
+            [id] => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Work API payloads stored for a lead (`raw_payload.lead_id`), in storage order
+    ///
+    /// The first one is the phone match, as in the enrichment workflow.
+    pub async fn find_lead_enrichments(
+        &self,
+        lead_id: &str,
+    ) -> Result<Vec<serde_json::Value>, AppError> {
+        let payloads = sqlx::query_scalar!(
+            r#"
+            SELECT raw_payload AS "raw_payload!" FROM core.party_enrichments
+            WHERE raw_payload->>'lead_id' = $1
+            ORDER BY enriched_at
+            LIMIT 2
+            "#,
+            lead_id
+        )
+        .fetch_all(&self.pool)
+        .timed("party_enrichments.find_by_lead")
+        .await
+        .context(format!(
+            "Failed to load enrichments for lead_id: {}",
+            lead_id
+        ))?;
+
+        Ok(payloads)
+    }
+
+    /// Store a person or company depending on the document (CPF or CNPJ)
+    pub async fn store_enriched_party(
+        &self,
+        document: &str,
+        data: &serde_json::Value,
+        lead_id: Option<&str>,
+    ) -> Result<Uuid, AppError> {
+        if is_cnpj(document) {
+            self.store_enriched_company(document, data, lead_id).await
+        } else {
+            self.store_enriched_person_with_lead(document, data, lead_id)
+                .await
+        }
+    }
+
+    /// Upsert the raw enrichment snapshot of a party (one per party)
+    async fn store_enrichment_snapshot(
+        &self,
+        conn: &mut PgConnection,
+        party_id: Uuid,
+        payload: &serde_json::Value,
+        quality_score: f64,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
             r#"
             INSERT INTO core.party_enrichments (
                 enrichment_id, party_id, provider, raw_payload, normalized_data,
                 quality_score, enriched_at, created_at
             )
-            VALUES (gen_random_uuid(), $1, 'work_api', $2, '{}'::jsonb, $3, now(), now())
+            VALUES (gen_random_uuid(), $1, 'work_api', $2, '{}'::jsonb, $3::float8, now(), now())
             ON CONFLICT (party_id) DO UPDATE
             SET provider = EXCLUDED.provider,
                 raw_payload = EXCLUDED.raw_payload,
                 quality_score = GREATEST(core.party_enrichments.quality_score, EXCLUDED.quality_score),
                 enriched_at = EXCLUDED.enriched_at
             "#,
+            party_id,
+            payload,
+            quality_score
         )
-        .bind(party_id)
-        .bind(&enrichment_payload)
-        .bind(quality_score)
-        .execute(&self.pool)
+        .execute(&mut *conn)
+        .timed("party_enrichments.upsert")
         .await
         .context(format!("Failed to store party enrichment for party_id: {}", party_id))?;
 
-        tracing::info!(
-            "Successfully stored enriched data for CPF: {} (party_id: {})",
-            cpf,
-            party_id
-        );
-
-        Ok(party_id)
+        Ok(())
     }
 
     /// Store addresses for a party (creates address rows as needed)
     async fn store_party_addresses(
         &self,
+        conn: &mut PgConnection,
         party_id: Uuid,
         enderecos: &[serde_json::Value],
     ) -> Result<(), AppError> {
+        let mut confirmed = Vec::new();
         for (idx, endereco) in enderecos.iter().enumerate() {
             let street = endereco.get("logradouro").and_then(|v| v.as_str());
             let number = endereco.get("numero").and_then(|v| v.as_str());
@@ -317,37 +807,68 @@ impl EnrichmentStorage {
             let latitude = endereco.get("latitude").and_then(|v| v.as_f64());
             let longitude = endereco.get("longitude").and_then(|v| v.as_f64());
 
-            let address_id: Uuid = sqlx::query_scalar(
+            // The same address returned again confirms the stored one
+            let known: Option<Uuid> = sqlx::query_scalar!(
                 r#"
-                INSERT INTO core.addresses (
-                    id, street, number, neighborhood, city, state, zip_code,
-                    complement, latitude, longitude, formatted_address,
-                    created_at, updated_at
-                )
-                VALUES (
-                    gen_random_uuid(), $1, $2, $3, $4, $5, $6,
-                    $7, $8, $9, $10, now(), now()
-                )
-                RETURNING id
+                SELECT a.id
+                FROM core.party_addresses pa
+                JOIN core.addresses a ON a.id = pa.address_id
+                WHERE pa.party_id = $1
+                  AND a.zip_code IS NOT DISTINCT FROM $2
+                  AND lower(a.street) IS NOT DISTINCT FROM lower($3)
+                  AND a.number IS NOT DISTINCT FROM $4
+                ORDER BY pa.created_at
+                LIMIT 1
                 "#,
+                party_id,
+                normalized_zip.as_deref(),
+                street,
+                number
             )
-            .bind(street)
-            .bind(number)
-            .bind(neighborhood)
-            .bind(city)
-            .bind(state)
-            .bind(normalized_zip)
-            .bind(complement)
-            .bind(latitude)
-            .bind(longitude)
-            .bind(formatted)
-            .fetch_one(&self.pool)
+            .fetch_optional(&mut *conn)
+            .timed("party_addresses.find_same")
             .await
             .context(format!(
-                "Failed to insert address for party_id: {}",
+                "Failed to look up known addresses for party_id: {}",
                 party_id
             ))?;
 
+            let address_id: Uuid = match known {
+                Some(address_id) => address_id,
+                None => sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO core.addresses (
+                        id, street, number, neighborhood, city, state, zip_code,
+                        complement, latitude, longitude, formatted_address,
+                        created_at, updated_at
+                    )
+                    VALUES (
+                        gen_random_uuid(), $1, $2, $3, $4, $5, $6,
+                        $7, $8, $9, $10, now(), now()
+                    )
+                    RETURNING id
+                    "#,
+                    street,
+                    number,
+                    neighborhood,
+                    city,
+                    state,
+                    normalized_zip,
+                    complement,
+                    latitude,
+                    longitude,
+                    formatted
+                )
+                .fetch_one(&mut *conn)
+                .timed("addresses.insert")
+                .await
+                .context(format!(
+                    "Failed to insert address for party_id: {}",
+                    party_id
+                ))?,
+            };
+            confirmed.push(address_id);
+
             let address_type = match endereco
                 .get("tipo")
                 .and_then(|v| v.as_str())
@@ -368,52 +889,163 @@ impl EnrichmentStorage {
                 metadata["legacy_type"] = json!(tp);
             }
 
-            let confidence = if idx == 0 { 0.90 } else { 0.75 };
+            // Entries merged from a secondary source carry their own confidence
+            let confidence = endereco
+                .get("confianca")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(if idx == 0 { 0.90 } else { 0.75 });
             let is_primary = idx == 0;
 
-            let _ = sqlx::query(
+            sqlx::query!(
                 r#"
                 INSERT INTO core.party_addresses (
                     id, party_id, address_id, address_type, is_primary, is_current,
-                    verified, confidence_score, metadata, created_at, updated_at
+                    verified, confidence_score, metadata, valid_from, last_confirmed_at,
+                    created_at, updated_at
                 )
                 VALUES (
                     gen_random_uuid(), $1, $2, $3, $4, true,
-                    false, $5, $6, now(), now()
+                    false, $5::float8, $6, now(), now(), now(), now()
                 )
                 ON CONFLICT (party_id, address_id) DO UPDATE
                 SET confidence_score = GREATEST(core.party_addresses.confidence_score, EXCLUDED.confidence_score),
                     is_primary = core.party_addresses.is_primary OR EXCLUDED.is_primary,
                     metadata = core.party_addresses.metadata || EXCLUDED.metadata,
+                    is_current = true,
+                    valid_to = NULL,
+                    last_confirmed_at = now(),
                     updated_at = now()
                 "#,
+                party_id,
+                address_id,
+                address_type,
+                is_primary,
+                confidence,
+                metadata
             )
-            .bind(party_id)
-            .bind(address_id)
-            .bind(address_type)
-            .bind(is_primary)
-            .bind(confidence)
-            .bind(metadata)
-            .execute(&self.pool)
-            .await;
+            .execute(&mut *conn)
+            .timed("party_addresses.upsert")
+            .await
+            .context(format!(
+                "Failed to link address to party_id: {}",
+                party_id
+            ))?;
+        }
+
+        // Addresses no longer returned are kept as history, not current
+        if !confirmed.is_empty() {
+            sqlx::query!(
+                r#"
+                UPDATE core.party_addresses
+                SET valid_to = now(), is_current = false
+                WHERE party_id = $1 AND valid_to IS NULL AND address_id <> ALL($2)
+                "#,
+                party_id,
+                &confirmed
+            )
+            .execute(&mut *conn)
+            .timed("party_addresses.retire_unconfirmed")
+            .await
+            .context(format!(
+                "Failed to close unconfirmed addresses of party_id: {}",
+                party_id
+            ))?;
         }
 
         Ok(())
     }
 
+    /// Store the region inferred from the lead phone DDD as a low-confidence address
+    ///
+    /// Only used when the party has no current address; returns whether the
+    /// address was stored.
+    pub async fn store_ddd_address(
+        &self,
+        party_id: Uuid,
+        region: &DddRegion,
+    ) -> Result<bool, AppError> {
+        let has_address = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM core.party_addresses WHERE party_id = $1 AND is_current
+            ) AS "exists!"
+            "#,
+            party_id
+        )
+        .fetch_one(&self.pool)
+        .timed("party_addresses.exists")
+        .await
+        .context(format!(
+            "Failed to check addresses for party_id: {}",
+            party_id
+        ))?;
+        if has_address {
+            return Ok(false);
+        }
+
+        let address_id: Uuid = sqlx::query_scalar!(
+            r#"
+            INSERT INTO core.addresses (id, city, state, formatted_address, created_at, updated_at)
+            VALUES (gen_random_uuid(), $1, $2, $3, now(), now())
+            RETURNING id
+            "#,
+            region.city,
+            region.uf,
+            format!("{} - {} (DDD {})", region.city, region.uf, region.ddd)
+        )
+        .fetch_one(&self.pool)
+        .timed("addresses.insert")
+        .await
+        .context(format!(
+            "Failed to insert DDD address for party_id: {}",
+            party_id
+        ))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO core.party_addresses (
+                id, party_id, address_id, address_type, is_primary, is_current,
+                verified, confidence_score, metadata, created_at, updated_at
+            )
+            VALUES (gen_random_uuid(), $1, $2, 'other', false, true, false, 0.30, $3, now(), now())
+            ON CONFLICT (party_id, address_id) DO NOTHING
+            "#,
+            party_id,
+            address_id,
+            json!({"source": "ddd_inference", "ddd": region.ddd})
+        )
+        .execute(&self.pool)
+        .timed("party_addresses.upsert")
+        .await
+        .context(format!(
+            "Failed to link DDD address to party_id: {}",
+            party_id
+        ))?;
+        crate::residency::refresh_party_uf(&self.pool, &[party_id]).await?;
+
+        Ok(true)
+    }
+
     /// Store emails for a party
+    ///
+    /// Returns the lookup cache keys of the stored emails, to be invalidated
+    /// by the caller once the write is committed.
     async fn store_party_emails(
         &self,
+        conn: &mut PgConnection,
         party_id: Uuid,
         emails: &[serde_json::Value],
-    ) -> Result<(), AppError> {
+    ) -> Result<Vec<String>, AppError> {
+        let mut confirmed = Vec::new();
+        let mut stale_keys = Vec::new();
         for (idx, email_obj) in emails.iter().enumerate() {
             let email = email_obj.get("email").and_then(|e| e.as_str());
             let prioridade = email_obj.get("prioridade").and_then(|p| p.as_str());
             let qualidade = email_obj.get("qualidade").and_then(|q| q.as_str());
 
             if let Some(email_addr) = email {
-                let is_primary = idx == 0; // First email is primary
+                // Provisional: re-elected once all contacts are stored (primary_contact)
+                let is_primary = idx == 0;
                 let is_verified = qualidade == Some("BOM");
 
                 let mut metadata = json!({});
@@ -430,40 +1062,61 @@ impl EnrichmentStorage {
                     metadata["blacklist"] = json!(blacklist);
                 }
 
-                let _ = sqlx::query(
+                sqlx::query!(
                     r#"
                     INSERT INTO core.party_contacts (
                         contact_id, party_id, contact_type, value,
                         is_primary, is_verified, is_whatsapp, source,
-                        confidence, valid_from, valid_to, created_at, updated_at
+                        confidence, valid_from, valid_to, last_confirmed_at, created_at, updated_at
                     )
-                    VALUES (gen_random_uuid(), $1, 'email'::core.contact_type_enum, $2, $3, $4, false, $5, $6, now(), NULL, now(), now())
-                    ON CONFLICT ON CONSTRAINT uq_party_contact_unique DO NOTHING
+                    VALUES (gen_random_uuid(), $1, 'email'::core.contact_type_enum, $2, $3, $4, false, $5, $6::float8, now(), NULL, now(), now(), now())
+                    ON CONFLICT ON CONSTRAINT uq_party_contact_unique DO UPDATE
+                    SET last_confirmed_at = now(), valid_to = NULL
                     "#,
+                    party_id,
+                    email_addr.to_lowercase(),
+                    is_primary,
+                    is_verified,
+                    email_obj
+                        .get("fonte")
+                        .and_then(|v| v.as_str())
+                        .or(metadata.get("prioridade").and_then(|v| v.as_str())),
+                    email_obj
+                        .get("confianca")
+                        .and_then(|v| v.as_f64())
+                        .or_else(|| {
+                            metadata
+                                .get("qualidade")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| s.parse::<f64>().ok())
+                        })
                 )
-                .bind(party_id)
-                .bind(email_addr.to_lowercase())
-                .bind(is_primary)
-                .bind(is_verified)
-                .bind(metadata.get("prioridade").and_then(|v| v.as_str()))
-                .bind(metadata
-                    .get("qualidade")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse::<f64>().ok()))
-                .execute(&self.pool)
-                .await;
+                .execute(&mut *conn)
+                .timed("party_contacts.insert_email")
+                .await
+                .context(format!("Failed to store email for party_id: {}", party_id))?;
+
+                stale_keys.push(email_lookup_key(email_addr));
+                confirmed.push(email_addr.to_lowercase());
             }
         }
 
-        Ok(())
+        Self::retire_unconfirmed_contacts(conn, party_id, &["email"], &confirmed).await?;
+        Ok(stale_keys)
     }
 
     /// Store phones for a party
+    ///
+    /// Returns the lookup cache keys of the stored phones, to be invalidated
+    /// by the caller once the write is committed.
     async fn store_party_phones(
         &self,
+        conn: &mut PgConnection,
         party_id: Uuid,
         telefones: &[serde_json::Value],
-    ) -> Result<(), AppError> {
+    ) -> Result<Vec<String>, AppError> {
+        let mut confirmed = Vec::new();
+        let mut stale_keys = Vec::new();
         for (idx, phone_obj) in telefones.iter().enumerate() {
             let telefone = phone_obj.get("telefone").and_then(|t| t.as_str());
             let _tipo = phone_obj.get("tipo").and_then(|t| t.as_str());
@@ -472,36 +1125,84 @@ impl EnrichmentStorage {
             let status = phone_obj.get("status").and_then(|s| s.as_str());
 
             if let Some(phone) = telefone {
+                // Provisional: re-elected once all contacts are stored (primary_contact)
                 let is_primary = idx == 0;
                 let is_whatsapp = whatsapp == Some("SIM");
                 let normalized: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
 
-                let _ = sqlx::query(
+                sqlx::query!(
                     r#"
                     INSERT INTO core.party_contacts (
                         contact_id, party_id, contact_type, value,
                         is_primary, is_verified, is_whatsapp, source,
-                        confidence, valid_from, valid_to, created_at, updated_at
+                        confidence, valid_from, valid_to, last_confirmed_at, created_at, updated_at
                     )
                     VALUES (
                         gen_random_uuid(), $1,
                         CASE WHEN $3 THEN 'whatsapp'::core.contact_type_enum ELSE 'phone'::core.contact_type_enum END,
-                        $2, $4, true, $3, $5, $6, now(), NULL, now(), now()
+                        $2, $4, true, $3, $5, $6::float8, now(), NULL, now(), now(), now()
                     )
-                    ON CONFLICT ON CONSTRAINT uq_party_contact_unique DO NOTHING
+                    ON CONFLICT ON CONSTRAINT uq_party_contact_unique DO UPDATE
+                    SET last_confirmed_at = now(), valid_to = NULL
                     "#,
+                    party_id,
+                    normalized,
+                    is_whatsapp,
+                    is_primary,
+                    phone_obj.get("fonte").and_then(|v| v.as_str()).or(operadora),
+                    phone_obj
+                        .get("confianca")
+                        .and_then(|v| v.as_f64())
+                        .or_else(|| status.and_then(|s| s.parse::<f64>().ok()))
                 )
-                .bind(party_id)
-                .bind(&normalized)
-                .bind(is_whatsapp)
-                .bind(is_primary)
-                .bind(operadora)
-                .bind(status.and_then(|s| s.parse::<f64>().ok()))
-                .execute(&self.pool)
-                .await;
+                .execute(&mut *conn)
+                .timed("party_contacts.insert_phone")
+                .await
+                .context(format!("Failed to store phone for party_id: {}", party_id))?;
+
+                stale_keys.push(phone_lookup_key(&normalized));
+                confirmed.push(normalized);
             }
         }
 
+        Self::retire_unconfirmed_contacts(conn, party_id, &["phone", "whatsapp"], &confirmed)
+            .await?;
+        Ok(stale_keys)
+    }
+
+    /// Close (`valid_to`) contacts of the given types the provider no longer returns
+    ///
+    /// Skipped when nothing was confirmed: a response without the section
+    /// says nothing about the contacts already stored.
+    async fn retire_unconfirmed_contacts(
+        conn: &mut PgConnection,
+        party_id: Uuid,
+        contact_types: &[&str],
+        confirmed: &[String],
+    ) -> Result<(), AppError> {
+        if confirmed.is_empty() {
+            return Ok(());
+        }
+        sqlx::query!(
+            r#"
+            UPDATE core.party_contacts
+            SET valid_to = now()
+            WHERE party_id = $1
+              AND contact_type::text = ANY($2)
+              AND valid_to IS NULL
+              AND value <> ALL($3)
+            "#,
+            party_id,
+            contact_types as &[&str],
+            confirmed
+        )
+        .execute(&mut *conn)
+        .timed("party_contacts.retire_unconfirmed")
+        .await
+        .context(format!(
+            "Failed to close unconfirmed contacts of party_id: {}",
+            party_id
+        ))?;
         Ok(())
     }
 
@@ -516,29 +1217,697 @@ impl EnrichmentStorage {
         let normalized_phone =
             phone.map(|p| p.chars().filter(|c| c.is_ascii_digit()).collect::<String>());
 
-        let result = sqlx::query_scalar(
+        let result = sqlx::query_scalar!(
             r#"
-            SELECT p.cpf_cnpj
+            SELECT p.cpf_cnpj AS "cpf_cnpj!"
             FROM core.party_contacts pc
             JOIN core.parties p ON pc.party_id = p.id
-            WHERE (pc.value = $1 AND pc.contact_type IN ('phone', 'whatsapp'))
-               OR (pc.value = $2 AND pc.contact_type = 'email')
+            WHERE ((pc.value = $1 AND pc.contact_type IN ('phone', 'whatsapp'))
+               OR (pc.value = $2 AND pc.contact_type = 'email'))
             AND p.cpf_cnpj IS NOT NULL
+            AND p.party_type = 'person'
             ORDER BY p.updated_at DESC
             LIMIT 1
             "#,
+            normalized_phone,
+            email
         )
-        .bind(normalized_phone)
-        .bind(email)
         .fetch_optional(&self.pool)
+        .timed("party_contacts.lookup_cpf")
         .await
         .context("Failed to lookup CPF from phone/email contact")?;
 
         Ok(result)
     }
+
+    /// Merge near-duplicate parties into `survivor_id`
+    ///
+    /// Runs in one transaction. For each merged party: blank fields of the
+    /// survivor (party, person / company record) are filled from it; contacts
+    /// and addresses the survivor already has are combined (earliest
+    /// `valid_from`, latest confirmation, highest confidence), the others
+    /// moved; the newer enrichment snapshot is kept; relationships and
+    /// property transactions are repointed. The merged party is then deleted
+    /// and recorded in `core.party_merges` (migration 052) as it was. The
+    /// survivor ends with a digits-only document and re-elected primary
+    /// contacts.
+    pub async fn merge_parties(
+        &self,
+        survivor_id: Uuid,
+        merged_ids: &[Uuid],
+        reason: Option<&str>,
+        actor: &str,
+    ) -> Result<Vec<PartyMerge>, AppError> {
+        if merged_ids.is_empty() {
+            return Err(AppError::BadRequest(
+                "merged_ids must list at least one party".to_string(),
+            ));
+        }
+        let mut all_ids = vec![survivor_id];
+        all_ids.extend_from_slice(merged_ids);
+        let mut distinct = all_ids.clone();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() != all_ids.len() {
+            return Err(AppError::BadRequest(
+                "survivor_id and merged_ids must be distinct parties".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await.context(format!(
+            "Failed to start transaction merging into party_id: {}",
+            survivor_id
+        ))?;
+
+        // Locked in id order, so concurrent merges cannot deadlock
+        let rows: Vec<(Uuid, Option<String>, Option<String>, serde_json::Value)> = sqlx::query!(
+            r#"
+            SELECT p.id, p.party_type AS "party_type?", p.cpf_cnpj, to_jsonb(p) AS "party!"
+            FROM core.parties p
+            WHERE p.id = ANY($1)
+            ORDER BY p.id
+            FOR UPDATE
+            "#,
+            &all_ids
+        )
+        .fetch_all(&mut *tx)
+        .timed("parties.lock_for_merge")
+        .await
+        .context(format!(
+            "Failed to lock parties merged into party_id: {}",
+            survivor_id
+        ))?
+        .into_iter()
+        .map(|row| (row.id, row.party_type, row.cpf_cnpj, row.party))
+        .collect();
+
+        let find = |id: Uuid| {
+            rows.iter()
+                .find(|row| row.0 == id)
+                .ok_or_else(|| AppError::NotFound(format!("Party {} not found", id)))
+        };
+        let survivor = find(survivor_id)?;
+        let survivor_identity = PartyIdentity {
+            party_type: survivor.1.as_deref(),
+            document: survivor.2.as_deref(),
+        };
+        let mut stale_keys = Vec::new();
+        let mut document = survivor.2.clone();
+        let mut merges = Vec::with_capacity(merged_ids.len());
+
+        for merged_id in merged_ids {
+            let merged = find(*merged_id)?;
+            check_mergeable(
+                &survivor_identity,
+                &PartyIdentity {
+                    party_type: merged.1.as_deref(),
+                    document: merged.2.as_deref(),
+                },
+            )?;
+            if let Some(doc) = merged.2.as_deref() {
+                stale_keys.push(cpf_lookup_key(doc));
+                document = document.or_else(|| Some(doc.to_string()));
+            }
+
+            let (moved, discarded_enrichment) =
+                Self::merge_party_rows(&mut tx, survivor_id, *merged_id).await?;
+
+            let merge_id: i64 = sqlx::query_scalar!(
+                r#"
+                INSERT INTO core.party_merges (
+                    survivor_id, merged_id, merged_document, merged_party,
+                    discarded_enrichment, moved, reason, actor
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id
+                "#,
+                survivor_id,
+                merged_id,
+                merged.2.as_deref(),
+                merged.3,
+                discarded_enrichment,
+                json!(moved),
+                reason,
+                actor
+            )
+            .fetch_one(&mut *tx)
+            .timed("party_merges.insert")
+            .await
+            .context(format!("Failed to record merge of party_id: {}", merged_id))?;
+
+            merges.push(PartyMerge {
+                merge_id,
+                survivor_id,
+                merged_id: *merged_id,
+                merged_document: merged.2.clone(),
+                moved,
+            });
+        }
+
+        // Merged parties are gone: the document can take its canonical form
+        let document = document.as_deref().map(|doc| {
+            doc.chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect::<String>()
+        });
+        sqlx::query!(
+            r#"
+            UPDATE core.parties
+            SET cpf_cnpj = COALESCE(NULLIF($2, ''), cpf_cnpj),
+                updated_at = now()
+            WHERE id = $1
+            "#,
+            survivor_id,
+            document.as_deref()
+        )
+        .execute(&mut *tx)
+        .timed("parties.normalize_document")
+        .await
+        .context(format!(
+            "Failed to normalize document of party_id: {}",
+            survivor_id
+        ))?;
+        crate::primary_contact::elect_primary_contacts(&mut *tx, &[survivor_id]).await?;
+        crate::residency::refresh_party_uf(&mut *tx, &[survivor_id]).await?;
+
+        tx.commit().await.context(format!(
+            "Failed to commit merge into party_id: {}",
+            survivor_id
+        ))?;
+
+        if let Some(doc) = survivor.2.as_deref() {
+            stale_keys.push(cpf_lookup_key(doc));
+        }
+        if let Some(doc) = document.as_deref() {
+            stale_keys.push(cpf_lookup_key(doc));
+        }
+        for key in stale_keys {
+            self.invalidate_lookup(key).await;
+        }
+        self.invalidate_party_contacts(survivor_id);
+
+        tracing::info!(
+            "Merged {} party(ies) into party_id: {} ({})",
+            merges.len(),
+            survivor_id,
+            actor
+        );
+        Ok(merges)
+    }
+
+    /// Move the rows of one merged party to the survivor and delete it
+    ///
+    /// Returns the rows moved and the enrichment snapshot dropped, if any.
+    async fn merge_party_rows(
+        conn: &mut PgConnection,
+        survivor_id: Uuid,
+        merged_id: Uuid,
+    ) -> Result<(MergeCounts, Option<serde_json::Value>), AppError> {
+        let mut moved = MergeCounts::default();
+
+        sqlx::query!(
+            r#"
+            UPDATE core.parties s
+            SET full_name = COALESCE(s.full_name, m.full_name),
+                normalized_name = COALESCE(s.normalized_name, m.normalized_name),
+                phonetic_name = COALESCE(s.phonetic_name, m.phonetic_name),
+                birth_date = COALESCE(s.birth_date, m.birth_date),
+                sex = COALESCE(s.sex, m.sex),
+                mother_name = COALESCE(s.mother_name, m.mother_name),
+                opening_date = COALESCE(s.opening_date, m.opening_date),
+                company_type = COALESCE(s.company_type, m.company_type),
+                company_size = COALESCE(s.company_size, m.company_size),
+                enriched = COALESCE(s.enriched, false) OR COALESCE(m.enriched, false),
+                updated_at = now()
+            FROM core.parties m
+            WHERE s.id = $1 AND m.id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("parties.merge_fields")
+        .await
+        .context(format!("Failed to merge fields of party_id: {}", merged_id))?;
+
+        // Person / company record: fill the survivor's, or take it over
+        sqlx::query!(
+            r#"
+            UPDATE core.people s
+            SET mothers_name = COALESCE(s.mothers_name, m.mothers_name),
+                birth_date = COALESCE(s.birth_date, m.birth_date),
+                sex = COALESCE(s.sex, m.sex),
+                marital_status = COALESCE(s.marital_status, m.marital_status),
+                document_cpf = COALESCE(s.document_cpf, m.document_cpf),
+                updated_at = now()
+            FROM core.people m
+            WHERE s.party_id = $1 AND m.party_id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("people.merge_fields")
+        .await
+        .context(format!("Failed to merge person of party_id: {}", merged_id))?;
+        sqlx::query!(
+            r#"
+            UPDATE core.companies s
+            SET trade_name = COALESCE(s.trade_name, m.trade_name),
+                company_size = COALESCE(s.company_size, m.company_size),
+                industry = COALESCE(s.industry, m.industry),
+                cnae = COALESCE(s.cnae, m.cnae),
+                foundation_date = COALESCE(s.foundation_date, m.foundation_date),
+                updated_at = now()
+            FROM core.companies m
+            WHERE s.party_id = $1 AND m.party_id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("companies.merge_fields")
+        .await
+        .context(format!(
+            "Failed to merge company of party_id: {}",
+            merged_id
+        ))?;
+        sqlx::query!(
+            "UPDATE core.people SET party_id = $1
+             WHERE party_id = $2
+               AND NOT EXISTS (SELECT 1 FROM core.people WHERE party_id = $1)",
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("people.move")
+        .await
+        .context(format!("Failed to move person of party_id: {}", merged_id))?;
+        sqlx::query!(
+            "UPDATE core.companies SET party_id = $1
+             WHERE party_id = $2
+               AND NOT EXISTS (SELECT 1 FROM core.companies WHERE party_id = $1)",
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("companies.move")
+        .await
+        .context(format!("Failed to move company of party_id: {}", merged_id))?;
+
+        // Contacts the survivor already has are combined, the others moved
+        moved.contacts_combined = sqlx::query!(
+            r#"
+            UPDATE core.party_contacts s
+            SET is_verified = s.is_verified OR m.is_verified,
+                is_whatsapp = s.is_whatsapp OR m.is_whatsapp,
+                confidence = GREATEST(s.confidence, m.confidence),
+                valid_from = LEAST(s.valid_from, m.valid_from),
+                valid_to = CASE WHEN s.valid_to IS NULL OR m.valid_to IS NULL THEN NULL
+                                ELSE GREATEST(s.valid_to, m.valid_to) END,
+                last_confirmed_at = GREATEST(s.last_confirmed_at, m.last_confirmed_at),
+                updated_at = now()
+            FROM core.party_contacts m
+            WHERE s.party_id = $1 AND m.party_id = $2
+              AND s.contact_type = m.contact_type AND s.value = m.value
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_contacts.merge_combine")
+        .await
+        .context(format!(
+            "Failed to combine contacts of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+        sqlx::query!(
+            r#"
+            DELETE FROM core.party_contacts m
+            USING core.party_contacts s
+            WHERE m.party_id = $2 AND s.party_id = $1
+              AND s.contact_type = m.contact_type AND s.value = m.value
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_contacts.merge_delete_combined")
+        .await
+        .context(format!(
+            "Failed to drop combined contacts of party_id: {}",
+            merged_id
+        ))?;
+        // Primaries are re-elected once every party is merged
+        moved.contacts_moved = sqlx::query!(
+            "UPDATE core.party_contacts SET party_id = $1, is_primary = false, updated_at = now()
+             WHERE party_id = $2",
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_contacts.merge_move")
+        .await
+        .context(format!(
+            "Failed to move contacts of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+
+        // Same for addresses, keyed by the shared core.addresses row
+        moved.addresses_combined = sqlx::query!(
+            r#"
+            UPDATE core.party_addresses s
+            SET confidence_score = GREATEST(s.confidence_score, m.confidence_score),
+                metadata = m.metadata || s.metadata,
+                is_current = s.is_current OR m.is_current,
+                valid_from = LEAST(s.valid_from, m.valid_from),
+                valid_to = CASE WHEN s.valid_to IS NULL OR m.valid_to IS NULL THEN NULL
+                                ELSE GREATEST(s.valid_to, m.valid_to) END,
+                last_confirmed_at = GREATEST(s.last_confirmed_at, m.last_confirmed_at),
+                updated_at = now()
+            FROM core.party_addresses m
+            WHERE s.party_id = $1 AND m.party_id = $2 AND s.address_id = m.address_id
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_addresses.merge_combine")
+        .await
+        .context(format!(
+            "Failed to combine addresses of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+        sqlx::query!(
+            r#"
+            DELETE FROM core.party_addresses m
+            USING core.party_addresses s
+            WHERE m.party_id = $2 AND s.party_id = $1 AND s.address_id = m.address_id
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_addresses.merge_delete_combined")
+        .await
+        .context(format!(
+            "Failed to drop combined addresses of party_id: {}",
+            merged_id
+        ))?;
+        moved.addresses_moved = sqlx::query!(
+            r#"
+            UPDATE core.party_addresses m
+            SET party_id = $1,
+                is_primary = m.is_primary AND NOT EXISTS (
+                    SELECT 1 FROM core.party_addresses s WHERE s.party_id = $1 AND s.is_primary
+                ),
+                updated_at = now()
+            WHERE m.party_id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_addresses.merge_move")
+        .await
+        .context(format!(
+            "Failed to move addresses of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+
+        // One snapshot per party: the newer one is kept, the other recorded
+        let party_ids = [survivor_id, merged_id];
+        let snapshots: Vec<(Uuid, Option<DateTime<Utc>>, serde_json::Value)> = sqlx::query!(
+            r#"
+            SELECT e.party_id, e.enriched_at, to_jsonb(e) AS "snapshot!"
+            FROM core.party_enrichments e
+            WHERE e.party_id = ANY($1)
+            "#,
+            &party_ids[..]
+        )
+        .fetch_all(&mut *conn)
+        .timed("party_enrichments.find_for_merge")
+        .await
+        .context(format!(
+            "Failed to load snapshots of party_id: {}",
+            merged_id
+        ))?
+        .into_iter()
+        .map(|row| (row.party_id, row.enriched_at, row.snapshot))
+        .collect();
+        let survivor_snapshot = snapshots.iter().find(|s| s.0 == survivor_id);
+        let merged_snapshot = snapshots.iter().find(|s| s.0 == merged_id);
+        let discarded = match (survivor_snapshot, merged_snapshot) {
+            (_, None) => None,
+            (Some(kept), Some(dropped)) if !newer_snapshot(dropped.1, kept.1) => {
+                Some((merged_id, dropped.2.clone()))
+            }
+            (kept, Some(_)) => {
+                moved.enrichment_replaced = true;
+                kept.map(|kept| (survivor_id, kept.2.clone()))
+            }
+        };
+        if let Some((party_id, _)) = &discarded {
+            sqlx::query!(
+                "DELETE FROM core.party_enrichments WHERE party_id = $1",
+                party_id
+            )
+            .execute(&mut *conn)
+            .timed("party_enrichments.merge_delete")
+            .await
+            .context(format!("Failed to drop snapshot of party_id: {}", party_id))?;
+        }
+        if moved.enrichment_replaced {
+            sqlx::query!(
+                "UPDATE core.party_enrichments SET party_id = $1 WHERE party_id = $2",
+                survivor_id,
+                merged_id
+            )
+            .execute(&mut *conn)
+            .timed("party_enrichments.merge_move")
+            .await
+            .context(format!(
+                "Failed to move snapshot of party_id: {}",
+                merged_id
+            ))?;
+        }
+
+        moved.relationships_moved = sqlx::query!(
+            r#"
+            UPDATE core.party_relationships
+            SET source_party_id = CASE WHEN source_party_id = $2 THEN $1 ELSE source_party_id END,
+                target_party_id = CASE WHEN target_party_id = $2 THEN $1 ELSE target_party_id END,
+                updated_at = now()
+            WHERE source_party_id = $2 OR target_party_id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("party_relationships.merge_move")
+        .await
+        .context(format!(
+            "Failed to move relationships of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+        // A relationship between the two parties would now point to itself
+        sqlx::query!(
+            "DELETE FROM core.party_relationships WHERE source_party_id = $1 AND target_party_id = $1",
+            survivor_id
+        )
+        .execute(&mut *conn)
+        .timed("party_relationships.merge_delete_self")
+        .await
+        .context(format!(
+            "Failed to drop self relationships of party_id: {}",
+            survivor_id
+        ))?;
+
+        moved.transactions_moved = sqlx::query!(
+            r#"
+            UPDATE core.property_transactions
+            SET buyer_party_id = CASE WHEN buyer_party_id = $2 THEN $1 ELSE buyer_party_id END,
+                seller_party_id = CASE WHEN seller_party_id = $2 THEN $1 ELSE seller_party_id END
+            WHERE buyer_party_id = $2 OR seller_party_id = $2
+            "#,
+            survivor_id,
+            merged_id
+        )
+        .execute(&mut *conn)
+        .timed("property_transactions.merge_move")
+        .await
+        .context(format!(
+            "Failed to move transactions of party_id: {}",
+            merged_id
+        ))?
+        .rows_affected();
+
+        // Anything not moved above (a person record the survivor already had) goes with it
+        sqlx::query!("DELETE FROM core.people WHERE party_id = $1", merged_id)
+            .execute(&mut *conn)
+            .timed("people.merge_delete")
+            .await
+            .context(format!("Failed to drop person of party_id: {}", merged_id))?;
+        sqlx::query!("DELETE FROM core.companies WHERE party_id = $1", merged_id)
+            .execute(&mut *conn)
+            .timed("companies.merge_delete")
+            .await
+            .context(format!("Failed to drop company of party_id: {}", merged_id))?;
+        sqlx::query!("DELETE FROM core.parties WHERE id = $1", merged_id)
+            .execute(&mut *conn)
+            .timed("parties.merge_delete")
+            .await
+            .context(format!("Failed to delete merged party_id: {}", merged_id))?;
+
+        Ok((moved, discarded.map(|(_, snapshot)| snapshot)))
+    }
+}
+
+/// Rows moved from a merged party to the survivor
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeCounts {
+    pub contacts_moved: u64,
+    /// Contacts the survivor already had, combined with the merged party's
+    pub contacts_combined: u64,
+    pub addresses_moved: u64,
+    pub addresses_combined: u64,
+    /// Whether the merged party's snapshot (newer) replaced the survivor's
+    pub enrichment_replaced: bool,
+    pub relationships_moved: u64,
+    pub transactions_moved: u64,
+}
+
+/// One party merged into another (a `core.party_merges` row)
+#[derive(Debug, Clone, Serialize)]
+pub struct PartyMerge {
+    pub merge_id: i64,
+    pub survivor_id: Uuid,
+    pub merged_id: Uuid,
+    pub merged_document: Option<String>,
+    pub moved: MergeCounts,
+}
+
+/// What decides whether two parties can be the same
+struct PartyIdentity<'a> {
+    party_type: Option<&'a str>,
+    document: Option<&'a str>,
+}
+
+/// Parties of different types or with different documents (once
+/// formatting is ignored) are never merged
+fn check_mergeable(survivor: &PartyIdentity, merged: &PartyIdentity) -> Result<(), AppError> {
+    if let (Some(a), Some(b)) = (survivor.party_type, merged.party_type) {
+        if a != b {
+            return Err(AppError::Conflict(format!(
+                "Cannot merge a {} party into a {} party",
+                b, a
+            )));
+        }
+    }
+    let digits = |doc: &str| {
+        doc.chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect::<String>()
+    };
+    if let (Some(a), Some(b)) = (survivor.document, merged.document) {
+        if digits(a) != digits(b) {
+            return Err(AppError::Conflict(
+                "Cannot merge parties with different documents".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a snapshot enriched at `candidate` replaces one enriched at `current`
+fn newer_snapshot(candidate: Option<DateTime<Utc>>, current: Option<DateTime<Utc>>) -> bool {
+    match (candidate, current) {
+        (Some(candidate), Some(current)) => candidate > current,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Split the main CNAE into (code, description)
+///
+/// Accepts `{"codigo": "6810-2/01", "descricao": "..."}` or the flat
+/// `"6810-2/01 - Compra e venda de imóveis próprios"` form.
+fn parse_cnae(value: Option<&serde_json::Value>) -> (Option<String>, Option<String>) {
+    let clean = |v: Option<&str>| {
+        v.map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    match value {
+        Some(serde_json::Value::Object(obj)) => (
+            clean(obj.get("codigo").and_then(|v| v.as_str())),
+            clean(obj.get("descricao").and_then(|v| v.as_str())),
+        ),
+        Some(serde_json::Value::String(text)) => match text.split_once(" - ") {
+            Some((code, description)) => (clean(Some(code)), clean(Some(description))),
+            None => (clean(Some(text)), None),
+        },
+        _ => (None, None),
+    }
 }
 
 /// Parse Brazilian date format (DD/MM/YYYY) to chrono::NaiveDate
 fn parse_br_date(date_str: &str) -> Result<chrono::NaiveDate, chrono::ParseError> {
     chrono::NaiveDate::parse_from_str(date_str, "%d/%m/%Y")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity<'a>(party_type: &'a str, document: Option<&'a str>) -> PartyIdentity<'a> {
+        PartyIdentity {
+            party_type: Some(party_type),
+            document,
+        }
+    }
+
+    #[test]
+    fn test_check_mergeable_ignores_document_formatting() {
+        assert!(check_mergeable(
+            &identity("person", Some("12345678909")),
+            &identity("person", Some("123.456.789-09")),
+        )
+        .is_ok());
+        assert!(check_mergeable(
+            &identity("person", Some("12345678909")),
+            &identity("person", None),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_check_mergeable_rejects_different_parties() {
+        assert!(matches!(
+            check_mergeable(
+                &identity("person", Some("12345678909")),
+                &identity("person", Some("98765432100")),
+            ),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(matches!(
+            check_mergeable(&identity("person", None), &identity("company", None)),
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn test_newer_snapshot() {
+        let older = Utc::now() - chrono::Duration::days(1);
+        let newer = Utc::now();
+        assert!(newer_snapshot(Some(newer), Some(older)));
+        assert!(!newer_snapshot(Some(older), Some(newer)));
+        assert!(newer_snapshot(Some(older), None));
+        assert!(!newer_snapshot(None, Some(older)));
+    }
+}
@@ -0,0 +1,140 @@
+//! Geographic inference from the phone area code (DDD)
+//!
+//! When neither the Work API nor the lead provides an address, the DDD of
+//! the lead phone still tells the state and the main city of the region.
+//! The inferred region is stored as a low-confidence address and exposed to
+//! routing rules (`states`).
+
+/// Region served by a DDD: state (UF) and its reference city
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DddRegion {
+    pub ddd: u8,
+    pub uf: &'static str,
+    pub city: &'static str,
+}
+
+const fn region(ddd: u8, uf: &'static str, city: &'static str) -> DddRegion {
+    DddRegion { ddd, uf, city }
+}
+
+/// All DDDs in use (Anatel), ordered by code
+static DDD_REGIONS: [DddRegion; 67] = [
+    region(11, "SP", "São Paulo"),
+    region(12, "SP", "São José dos Campos"),
+    region(13, "SP", "Santos"),
+    region(14, "SP", "Bauru"),
+    region(15, "SP", "Sorocaba"),
+    region(16, "SP", "Ribeirão Preto"),
+    region(17, "SP", "São José do Rio Preto"),
+    region(18, "SP", "Presidente Prudente"),
+    region(19, "SP", "Campinas"),
+    region(21, "RJ", "Rio de Janeiro"),
+    region(22, "RJ", "Campos dos Goytacazes"),
+    region(24, "RJ", "Volta Redonda"),
+    region(27, "ES", "Vitória"),
+    region(28, "ES", "Cachoeiro de Itapemirim"),
+    region(31, "MG", "Belo Horizonte"),
+    region(32, "MG", "Juiz de Fora"),
+    region(33, "MG", "Governador Valadares"),
+    region(34, "MG", "Uberlândia"),
+    region(35, "MG", "Poços de Caldas"),
+    region(37, "MG", "Divinópolis"),
+    region(38, "MG", "Montes Claros"),
+    region(41, "PR", "Curitiba"),
+    region(42, "PR", "Ponta Grossa"),
+    region(43, "PR", "Londrina"),
+    region(44, "PR", "Maringá"),
+    region(45, "PR", "Foz do Iguaçu"),
+    region(46, "PR", "Francisco Beltrão"),
+    region(47, "SC", "Joinville"),
+    region(48, "SC", "Florianópolis"),
+    region(49, "SC", "Chapecó"),
+    region(51, "RS", "Porto Alegre"),
+    region(53, "RS", "Pelotas"),
+    region(54, "RS", "Caxias do Sul"),
+    region(55, "RS", "Santa Maria"),
+    region(61, "DF", "Brasília"),
+    region(62, "GO", "Goiânia"),
+    region(63, "TO", "Palmas"),
+    region(64, "GO", "Rio Verde"),
+    region(65, "MT", "Cuiabá"),
+    region(66, "MT", "Rondonópolis"),
+    region(67, "MS", "Campo Grande"),
+    region(68, "AC", "Rio Branco"),
+    region(69, "RO", "Porto Velho"),
+    region(71, "BA", "Salvador"),
+    region(73, "BA", "Ilhéus"),
+    region(74, "BA", "Juazeiro"),
+    region(75, "BA", "Feira de Santana"),
+    region(77, "BA", "Vitória da Conquista"),
+    region(79, "SE", "Aracaju"),
+    region(81, "PE", "Recife"),
+    region(82, "AL", "Maceió"),
+    region(83, "PB", "João Pessoa"),
+    region(84, "RN", "Natal"),
+    region(85, "CE", "Fortaleza"),
+    region(86, "PI", "Teresina"),
+    region(87, "PE", "Petrolina"),
+    region(88, "CE", "Juazeiro do Norte"),
+    region(89, "PI", "Picos"),
+    region(91, "PA", "Belém"),
+    region(92, "AM", "Manaus"),
+    region(93, "PA", "Santarém"),
+    region(94, "PA", "Marabá"),
+    region(95, "RR", "Boa Vista"),
+    region(96, "AP", "Macapá"),
+    region(97, "AM", "Coari"),
+    region(98, "MA", "São Luís"),
+    region(99, "MA", "Imperatriz"),
+];
+
+/// Region of a Brazilian phone number (with or without +55 / leading 0)
+pub fn region_for_phone(phone: &str) -> Option<&'static DddRegion> {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    let national = match digits.len() {
+        12 | 13 if digits.starts_with("55") => &digits[2..],
+        11 | 12 if digits.starts_with('0') => &digits[1..],
+        _ => digits.as_str(),
+    };
+    if !matches!(national.len(), 10 | 11) {
+        return None;
+    }
+
+    let ddd: u8 = national[..2].parse().ok()?;
+    DDD_REGIONS
+        .binary_search_by_key(&ddd, |r| r.ddd)
+        .ok()
+        .map(|idx| &DDD_REGIONS[idx])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_for_phone_formats() {
+        for phone in [
+            "+55 (11) 98765-4321",
+            "5511987654321",
+            "11987654321",
+            "011987654321",
+            "1133334444",
+        ] {
+            let region = region_for_phone(phone).expect(phone);
+            assert_eq!((region.uf, region.city), ("SP", "São Paulo"), "{}", phone);
+        }
+        assert_eq!(region_for_phone("(61) 99999-0000").unwrap().uf, "DF");
+    }
+
+    #[test]
+    fn test_unknown_or_invalid_phones() {
+        assert!(region_for_phone("(20) 99999-0000").is_none());
+        assert!(region_for_phone("987654321").is_none());
+        assert!(region_for_phone("").is_none());
+    }
+
+    #[test]
+    fn test_table_is_sorted_for_binary_search() {
+        assert!(DDD_REGIONS.windows(2).all(|w| w[0].ddd < w[1].ddd));
+    }
+}
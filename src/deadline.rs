@@ -0,0 +1,195 @@
+//! Per-request deadlines
+//!
+//! Callers with their own SLA send `X-Request-Deadline`, either the budget in
+//! milliseconds (`2500`) or an absolute RFC 3339 timestamp. Without the
+//! header, synchronous enrichment routes get the budget of `ROUTE_SLOS`;
+//! budgets are capped at `MAX_BUDGET`.
+//!
+//! The deadline is held in a task-local for the duration of the request, so
+//! every provider call made while handling it sees the remaining budget:
+//! `circuit_breaker::guarded` cancels the call when the budget runs out and
+//! `ProviderLimiter::acquire` does not queue past it, both failing with
+//! `AppError::DeadlineExceeded` (504, `code: deadline_exceeded`). A request
+//! arriving with an exhausted budget is answered 504 right away. Work spawned
+//! in the background (queued jobs, batch callbacks) runs without a deadline.
+
+use crate::errors::AppError;
+use axum::{
+    extract::Request,
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Header carrying the caller's deadline
+pub const DEADLINE_HEADER: &str = "x-request-deadline";
+
+/// Longest budget accepted from a header
+pub const MAX_BUDGET: Duration = Duration::from_secs(300);
+
+/// Default budgets of the synchronous enrichment routes (method, path prefix)
+const ROUTE_SLOS: &[(Method, &str, Duration)] = &[
+    (
+        Method::POST,
+        "/api/v1/enrich/batch",
+        Duration::from_secs(120),
+    ),
+    (Method::POST, "/api/v1/enrich", Duration::from_secs(30)),
+    (Method::POST, "/api/v1/c2s/enrich/", Duration::from_secs(60)),
+    (
+        Method::GET,
+        "/api/v1/contributor/customer",
+        Duration::from_secs(30),
+    ),
+    (
+        Method::GET,
+        "/api/v1/work/modules/",
+        Duration::from_secs(30),
+    ),
+];
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Run `future` with `deadline` visible to the provider calls it makes
+pub async fn with_deadline<F: Future>(deadline: Instant, future: F) -> F::Output {
+    DEADLINE.scope(deadline, future).await
+}
+
+/// Deadline of the current request, if any
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Budget left before `operation`: `None` without a deadline, an error when
+/// none is left
+pub fn remaining(operation: &str) -> Result<Option<Duration>, AppError> {
+    match current() {
+        None => Ok(None),
+        Some(deadline) => {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                Err(exceeded(operation))
+            } else {
+                Ok(Some(left))
+            }
+        }
+    }
+}
+
+/// Error of a call cut short by the request deadline
+pub fn exceeded(operation: &str) -> AppError {
+    AppError::DeadlineExceeded(format!("Request deadline reached during {}", operation))
+}
+
+/// Budget given by an `X-Request-Deadline` value, relative to `now`
+///
+/// `None` when the value is neither milliseconds nor an RFC 3339 timestamp.
+/// A timestamp in the past gives a zero budget.
+pub fn parse_budget(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let budget = match value.parse::<u64>() {
+        Ok(ms) => Duration::from_millis(ms),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc3339(value).ok()?;
+            (at.with_timezone(&Utc) - now)
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(budget.min(MAX_BUDGET))
+}
+
+/// Default budget of a route, `None` for routes without an SLO
+pub fn route_slo(method: &Method, path: &str) -> Option<Duration> {
+    ROUTE_SLOS
+        .iter()
+        .find(|(slo_method, prefix, _)| slo_method == method && path.starts_with(prefix))
+        .map(|(_, _, budget)| *budget)
+}
+
+/// Budget of a request: its header, else the route SLO
+pub fn request_budget(
+    headers: &HeaderMap,
+    method: &Method,
+    path: &str,
+) -> Result<Option<Duration>, AppError> {
+    match headers.get(DEADLINE_HEADER) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| parse_budget(value, Utc::now()))
+            .map(Some)
+            .ok_or_else(|| {
+                AppError::BadRequest(
+                    "X-Request-Deadline must be milliseconds or an RFC 3339 timestamp".to_string(),
+                )
+            }),
+        None => Ok(route_slo(method, path)),
+    }
+}
+
+/// Middleware setting the request deadline for the handler
+pub async fn propagate_deadline(request: Request, next: Next) -> Result<Response, AppError> {
+    let budget = request_budget(request.headers(), request.method(), request.uri().path())?;
+    let Some(budget) = budget else {
+        return Ok(next.run(request).await);
+    };
+    if budget.is_zero() {
+        return Err(exceeded("request admission"));
+    }
+    Ok(with_deadline(Instant::now() + budget, next.run(request)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budget() {
+        let now = Utc::now();
+        assert_eq!(parse_budget("2500", now), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_budget("999999999", now), Some(MAX_BUDGET));
+        let at = (now + chrono::Duration::seconds(10)).to_rfc3339();
+        assert_eq!(parse_budget(&at, now), Some(Duration::from_secs(10)));
+        let past = (now - chrono::Duration::seconds(10)).to_rfc3339();
+        assert_eq!(parse_budget(&past, now), Some(Duration::ZERO));
+        assert_eq!(parse_budget("soon", now), None);
+    }
+
+    #[test]
+    fn test_route_slo() {
+        assert_eq!(
+            route_slo(&Method::POST, "/api/v1/enrich"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            route_slo(&Method::POST, "/api/v1/enrich/batch"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(route_slo(&Method::GET, "/api/v1/admin/dashboard"), None);
+        assert_eq!(route_slo(&Method::POST, "/api/v1/webhooks/c2s"), None);
+    }
+
+    #[tokio::test]
+    async fn test_remaining_budget() {
+        assert!(remaining("test").unwrap().is_none());
+
+        let left = with_deadline(Instant::now() + Duration::from_secs(5), async {
+            remaining("test")
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(left <= Duration::from_secs(5));
+
+        let err = with_deadline(Instant::now(), async { remaining("work_api") })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::DeadlineExceeded(_)));
+    }
+}
@@ -0,0 +1,255 @@
+//! Full payload capture of sampled enrichment workflows
+//!
+//! Reproducing a bad enrichment used to require guessing what Diretrix and
+//! Work API answered. Every workflow run records its steps in memory
+//! (`DebugCapture::record`); when the lead is sampled (`DEBUG_SAMPLE_RATE`) or
+//! one of its CPFs is on `DEBUG_CPF_ALLOWLIST`, the steps are stored in
+//! `debug_payloads` (migration 032) when the capture is dropped, so failed
+//! runs are kept too. Rows expire after `DEBUG_PAYLOAD_TTL_HOURS` and are
+//! purged by `spawn_debug_payload_purger`.
+//!
+//! ```rust,ignore
+//! let mut capture = DebugCapture::start(&state, lead_id);
+//! capture.add_cpfs(&cpf_result.cpfs);
+//! capture.record("diretrix", || (json!({"phone": phone}), json!(cpf_result.cpfs)));
+//! // stored (if sampled or allowlisted) when `capture` goes out of scope
+//! ```
+
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::leader::Leadership;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Whether a lead belongs to the debug sample (stable per lead)
+pub fn is_sampled(lead_id: &str, rate: f64) -> bool {
+    if rate <= 0.0 {
+        return false;
+    }
+    let digest = Sha256::digest(format!("debug:{}", lead_id).as_bytes());
+    let bucket = u16::from_be_bytes([digest[0], digest[1]]) as f64 / (u16::MAX as f64 + 1.0);
+    bucket < rate
+}
+
+/// Parse the comma-separated CPF allowlist (punctuation ignored)
+pub fn parse_allowlist(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|cpf| {
+            cpf.chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect::<String>()
+        })
+        .filter(|cpf| !cpf.is_empty())
+        .collect()
+}
+
+struct Step {
+    step: &'static str,
+    request: Value,
+    response: Value,
+    recorded_at: DateTime<Utc>,
+}
+
+/// Steps of one workflow run, stored on drop when the lead qualifies
+pub struct DebugCapture {
+    pool: PgPool,
+    lead_id: String,
+    sampled: bool,
+    allowlist: Vec<String>,
+    ttl_hours: i64,
+    cpfs: Vec<String>,
+    steps: Vec<Step>,
+}
+
+impl DebugCapture {
+    pub fn start(state: &AppState, lead_id: &str) -> Self {
+        Self::new(state.db.clone(), &state.config, lead_id)
+    }
+
+    pub fn new(pool: PgPool, config: &Config, lead_id: &str) -> Self {
+        Self {
+            pool,
+            lead_id: lead_id.to_string(),
+            sampled: is_sampled(lead_id, config.debug_sample_rate),
+            allowlist: config.debug_cpf_allowlist.clone(),
+            ttl_hours: config.debug_payload_ttl_hours,
+            cpfs: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Whether the capture would be stored now
+    pub fn is_enabled(&self) -> bool {
+        self.sampled || self.cpfs.iter().any(|cpf| self.allowlist.contains(cpf))
+    }
+
+    /// CPFs found for the lead (checked against the allowlist)
+    pub fn add_cpfs(&mut self, cpfs: &[String]) {
+        self.cpfs.extend(cpfs.iter().cloned());
+    }
+
+    /// Record a step's request and response
+    ///
+    /// `payloads` only runs when the lead may qualify: always before its CPFs
+    /// are known (they could be allowlisted), afterwards only when enabled.
+    pub fn record(&mut self, step: &'static str, payloads: impl FnOnce() -> (Value, Value)) {
+        if !self.cpfs.is_empty() && !self.is_enabled() {
+            return;
+        }
+        let (request, response) = payloads();
+        self.steps.push(Step {
+            step,
+            request,
+            response,
+            recorded_at: Utc::now(),
+        });
+    }
+}
+
+impl Drop for DebugCapture {
+    fn drop(&mut self) {
+        if self.steps.is_empty() || !self.is_enabled() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = self.pool.clone();
+        let lead_id = std::mem::take(&mut self.lead_id);
+        let cpf = self.cpfs.first().cloned();
+        let steps = std::mem::take(&mut self.steps);
+        let ttl_hours = self.ttl_hours;
+        runtime.spawn(async move {
+            if let Err(e) = store(&pool, &lead_id, cpf.as_deref(), steps, ttl_hours).await {
+                tracing::warn!("{}", e);
+            }
+        });
+    }
+}
+
+async fn store(
+    pool: &PgPool,
+    lead_id: &str,
+    cpf: Option<&str>,
+    steps: Vec<Step>,
+    ttl_hours: i64,
+) -> Result<(), AppError> {
+    let count = steps.len();
+    for step in steps {
+        sqlx::query(
+            r#"
+            INSERT INTO debug_payloads (lead_id, cpf, step, request, response, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6::timestamptz, $6::timestamptz + make_interval(hours => $7))
+            "#,
+        )
+        .bind(lead_id)
+        .bind(cpf)
+        .bind(step.step)
+        .bind(step.request)
+        .bind(step.response)
+        .bind(step.recorded_at)
+        .bind(ttl_hours as i32)
+        .execute(pool)
+        .timed("debug_payloads.insert")
+        .await
+        .context(format!("Failed to store debug payloads for lead_id: {}", lead_id))?;
+    }
+    tracing::info!("Stored {} debug payload(s) for lead {}", count, lead_id);
+    Ok(())
+}
+
+/// A stored workflow step
+#[derive(Debug, Serialize, FromRow)]
+pub struct DebugPayload {
+    pub id: i64,
+    pub lead_id: String,
+    pub cpf: Option<String>,
+    pub step: String,
+    pub request: Value,
+    pub response: Value,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Stored steps of a lead, oldest first
+pub async fn list_for_lead(pool: &PgPool, lead_id: &str) -> Result<Vec<DebugPayload>, AppError> {
+    let payloads = sqlx::query_as::<_, DebugPayload>(
+        r#"
+        SELECT id, lead_id, cpf, step, request, response, created_at, expires_at
+        FROM debug_payloads
+        WHERE lead_id = $1 AND expires_at > NOW()
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(lead_id)
+    .fetch_all(pool)
+    .timed("debug_payloads.list")
+    .await
+    .context(format!(
+        "Failed to load debug payloads for lead_id: {}",
+        lead_id
+    ))?;
+    Ok(payloads)
+}
+
+/// Delete expired payloads, returns how many were removed
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query("DELETE FROM debug_payloads WHERE expires_at <= NOW()")
+        .execute(pool)
+        .timed("debug_payloads.purge")
+        .await
+        .context("Failed to purge expired debug payloads")?;
+    Ok(result.rows_affected())
+}
+
+/// Background task deleting expired debug payloads every hour
+///
+/// Runs on the instance holding the "debug_payload_purge" leadership.
+pub fn spawn_debug_payload_purger(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let leader = Leadership::new("debug_payload_purge");
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+            match purge_expired(&state.db).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Purged {} expired debug payload(s)", n),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_allowlist() {
+        assert_eq!(
+            parse_allowlist("123.456.789-01, 98765432100,,"),
+            vec!["12345678901", "98765432100"]
+        );
+        assert!(parse_allowlist("").is_empty());
+    }
+
+    #[test]
+    fn test_sampling() {
+        assert!(!is_sampled("lead-1", 0.0));
+        assert!(is_sampled("lead-1", 1.0));
+        let sampled = (0..1000)
+            .filter(|i| is_sampled(&format!("lead-{}", i), 0.1))
+            .count();
+        assert!((50..150).contains(&sampled), "sampled {}", sampled);
+    }
+}
@@ -0,0 +1,223 @@
+//! Lead and CPF deduplication shared by every instance
+//!
+//! `trigger_lead_processing` claims a lead before processing it and skips
+//! CPFs enriched in the last minute. With one instance the in-process moka
+//! caches are enough (`DEDUP_BACKEND=memory`, the default); with several,
+//! `DEDUP_BACKEND=redis` keeps the claims in Redis (`SET key value NX EX ttl`)
+//! so two instances never process the same lead at once.
+
+use async_trait::async_trait;
+use moka::future::Cache;
+use redis::aio::ConnectionManager;
+use std::time::Duration;
+
+use crate::caches::Caches;
+use crate::errors::AppError;
+
+/// How long a lead stays claimed (matches the in-process cache TTL)
+pub const LEAD_CLAIM_TTL: Duration = Duration::from_secs(300);
+/// How long a processed CPF is remembered
+pub const RECENT_CPF_TTL: Duration = Duration::from_secs(300);
+
+/// Outcome of a claim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    /// This caller holds the key until it expires
+    Acquired,
+    /// Already held, since the given Unix timestamp (when known)
+    Held { since: Option<i64> },
+}
+
+/// Deduplication keys with a TTL (values are Unix timestamps)
+#[async_trait]
+pub trait DedupBackend: Send + Sync {
+    /// Set `key` to `value` only when absent (SET NX EX)
+    async fn claim(&self, key: &str, value: i64, ttl: Duration) -> Result<Claim, AppError>;
+
+    async fn get(&self, key: &str) -> Result<Option<i64>, AppError>;
+
+    async fn set(&self, key: &str, value: i64, ttl: Duration) -> Result<(), AppError>;
+
+    /// Backend name for logs and the admin view
+    fn name(&self) -> &'static str;
+}
+
+/// Key of a lead being processed
+pub fn lead_key(lead_id: &str) -> String {
+    format!("lead:{}", lead_id)
+}
+
+/// Key of a recently enriched CPF
+pub fn cpf_key(cpf: &str) -> String {
+    format!("cpf:{}", cpf)
+}
+
+/// In-process backend over the `processing_leads` and `recent_cpf` caches
+///
+/// The TTL of each key is the one of its cache (5 min), not the one passed.
+pub struct MemoryDedup {
+    processing_leads: Cache<String, i64>,
+    recent_cpf: Cache<String, i64>,
+}
+
+impl MemoryDedup {
+    pub fn new(caches: &Caches) -> Self {
+        Self {
+            processing_leads: caches.processing_leads().clone(),
+            recent_cpf: caches.recent_cpf().clone(),
+        }
+    }
+
+    /// Cache holding a key, and the key within it
+    fn cache_for<'a>(&self, key: &'a str) -> (&Cache<String, i64>, &'a str) {
+        match key.strip_prefix("cpf:") {
+            Some(cpf) => (&self.recent_cpf, cpf),
+            None => (
+                &self.processing_leads,
+                key.strip_prefix("lead:").unwrap_or(key),
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl DedupBackend for MemoryDedup {
+    async fn claim(&self, key: &str, value: i64, _ttl: Duration) -> Result<Claim, AppError> {
+        let (cache, key) = self.cache_for(key);
+        let entry = cache.entry(key.to_string()).or_insert(value).await;
+        Ok(if entry.is_fresh() {
+            Claim::Acquired
+        } else {
+            Claim::Held {
+                since: Some(entry.into_value()),
+            }
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<i64>, AppError> {
+        let (cache, key) = self.cache_for(key);
+        Ok(cache.get(key).await)
+    }
+
+    async fn set(&self, key: &str, value: i64, _ttl: Duration) -> Result<(), AppError> {
+        let (cache, key) = self.cache_for(key);
+        cache.insert(key.to_string(), value).await;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+/// Redis backend, keys prefixed with `c2s:dedup:`
+pub struct RedisDedup {
+    connection: ConnectionManager,
+}
+
+const REDIS_PREFIX: &str = "c2s:dedup:";
+
+impl RedisDedup {
+    /// Connect to `REDIS_URL` (fails when Redis is unreachable)
+    pub async fn connect(url: &str) -> Result<Self, AppError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| AppError::InternalError(format!("Invalid REDIS_URL: {}", e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| redis_error("connect", e))?;
+        Ok(Self { connection })
+    }
+}
+
+fn redis_error(operation: &str, error: redis::RedisError) -> AppError {
+    AppError::ExternalApiError(format!("Redis {} failed: {}", operation, error))
+}
+
+#[async_trait]
+impl DedupBackend for RedisDedup {
+    async fn claim(&self, key: &str, value: i64, ttl: Duration) -> Result<Claim, AppError> {
+        let key = format!("{}{}", REDIS_PREFIX, key);
+        let mut connection = self.connection.clone();
+        let set: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| redis_error("SET NX", e))?;
+        if set.is_some() {
+            return Ok(Claim::Acquired);
+        }
+        let since: Option<i64> = redis::cmd("GET")
+            .arg(&key)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| redis_error("GET", e))?;
+        Ok(Claim::Held { since })
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<i64>, AppError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("GET")
+            .arg(format!("{}{}", REDIS_PREFIX, key))
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| redis_error("GET", e))
+    }
+
+    async fn set(&self, key: &str, value: i64, ttl: Duration) -> Result<(), AppError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("SET")
+            .arg(format!("{}{}", REDIS_PREFIX, key))
+            .arg(value)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async::<()>(&mut connection)
+            .await
+            .map_err(|e| redis_error("SET", e))
+    }
+
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_claim_is_exclusive() {
+        let caches = Caches::new();
+        let dedup = MemoryDedup::new(&caches);
+
+        let key = lead_key("42");
+        assert_eq!(
+            dedup.claim(&key, 100, LEAD_CLAIM_TTL).await.unwrap(),
+            Claim::Acquired
+        );
+        assert_eq!(
+            dedup.claim(&key, 200, LEAD_CLAIM_TTL).await.unwrap(),
+            Claim::Held { since: Some(100) }
+        );
+        // Same cache as the admin view
+        assert_eq!(caches.processing_leads().get("42").await, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_memory_cpf_keys() {
+        let caches = Caches::new();
+        let dedup = MemoryDedup::new(&caches);
+
+        assert_eq!(dedup.get(&cpf_key("123")).await.unwrap(), None);
+        dedup
+            .set(&cpf_key("123"), 100, RECENT_CPF_TTL)
+            .await
+            .unwrap();
+        assert_eq!(dedup.get(&cpf_key("123")).await.unwrap(), Some(100));
+        assert_eq!(caches.recent_cpf().get("123").await, Some(100));
+        assert_eq!(caches.processing_leads().get("123").await, None);
+    }
+}
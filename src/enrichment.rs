@@ -6,18 +6,23 @@
 /// 3. Format enriched message
 /// 4. Send message to C2S
 /// 5. Store in database
+use crate::affordability::Affordability;
+use crate::clients::{DiretrixApi, DocumentModules, WorkApi};
 use crate::config::Config;
-use crate::db_storage::EnrichmentStorage;
+use crate::db::TimedQuery;
 use crate::errors::{AppError, ResultExt};
-use crate::gateway_client::C2sGatewayClient;
 use crate::handlers::AppState;
-use crate::models::WorkApiCompleteResponse;
-use crate::services::{C2SService, DiretrixService, WorkApiService};
+use crate::identity::{best_match, Candidate, LeadIdentity, MatchScore};
+use crate::listings::Listing;
+use crate::money::Brl;
+use crate::services::{
+    email_lookup_key, phone_lookup_key, DiretrixPersonData, DiretrixPersonSearch,
+};
+use crate::templates::Locale;
 use phonenumber::country::Id as CountryId;
 use phonenumber::Mode;
 use regex::Regex;
 use serde_json::{json, Value};
-use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -26,6 +31,10 @@ use uuid::Uuid;
 pub struct CpfLookupResult {
     pub cpfs: Vec<String>,
     pub same_person: bool,
+    /// Identity scores of the linked CPFs (empty when the lead has no name to score)
+    pub match_scores: Vec<MatchScore>,
+    /// Diretrix results for the linked CPFs, for cross-provider checks
+    pub diretrix_people: Vec<DiretrixPersonSearch>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,15 +51,18 @@ pub async fn find_existing_enrichment(
     email: Option<&str>,
 ) -> Result<Option<ExistingEnrichment>, AppError> {
     // 1. Check Cache
+    // Same keys as the customer lookup cache, so `EnrichmentStorage` invalidates both
     let cache_key = if let Some(p) = phone {
-        format!("phone:{}", p)
+        phone_lookup_key(p)
     } else if let Some(e) = email {
-        format!("email:{}", e)
+        email_lookup_key(e)
     } else {
         return Ok(None);
     };
 
-    if let Some(cached) = state.contact_to_cpf_cache.get(&cache_key).await {
+    let cached = state.caches.contact_to_cpf().get(&cache_key).await;
+    crate::metrics::record_cache_lookup("contact_to_cpf", cached.is_some());
+    if let Some(cached) = cached {
         return Ok(cached);
     }
 
@@ -79,6 +91,7 @@ pub async fn find_existing_enrichment(
     .bind(normalized_phone)
     .bind(email)
     .fetch_optional(&state.db)
+    .timed("enrichment.find_existing")
     .await
     .context("Failed to check database for existing enrichment")?;
 
@@ -88,22 +101,19 @@ pub async fn find_existing_enrichment(
         let cpf: Option<String> = row.try_get("cpf_cnpj").ok();
         let enriched_data: Option<serde_json::Value> = row.try_get("normalized_data").ok();
 
-        if let Some(c) = cpf {
-            Some(ExistingEnrichment {
-                party_id,
-                cpf: c,
-                enriched_data,
-            })
-        } else {
-            None
-        }
+        cpf.map(|c| ExistingEnrichment {
+            party_id,
+            cpf: c,
+            enriched_data,
+        })
     } else {
         None
     };
 
     // 3. Update Cache
     state
-        .contact_to_cpf_cache
+        .caches
+        .contact_to_cpf()
         .insert(cache_key, enrichment.clone())
         .await;
 
@@ -172,6 +182,134 @@ pub fn is_valid_email(email: &str) -> bool {
     true
 }
 
+/// Why a CPF or CNPJ was rejected before calling the Work API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentRejection {
+    /// Not 11 (CPF) or 14 (CNPJ) digits (e.g. an email or phone passed as document)
+    Malformed,
+    /// Check digits do not match
+    Checksum,
+    /// Valid checksum but a well-known placeholder (000.000.000-00, 123.456.789-09)
+    Placeholder,
+}
+
+impl DocumentRejection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DocumentRejection::Malformed => "malformed",
+            DocumentRejection::Checksum => "checksum",
+            DocumentRejection::Placeholder => "placeholder",
+        }
+    }
+}
+
+/// Placeholder CPFs with valid check digits (besides repeated digits)
+const TEST_CPFS: [&str; 1] = ["12345678909"];
+
+/// Digits of a document, if it only holds digits and formatting (`.`, `-`, `/`)
+fn document_digits(document: &str) -> Result<Vec<u32>, DocumentRejection> {
+    let trimmed = document.trim();
+    if !trimmed
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '.' || c == '-' || c == '/')
+    {
+        return Err(DocumentRejection::Malformed);
+    }
+    Ok(trimmed.chars().filter_map(|c| c.to_digit(10)).collect())
+}
+
+/// Modulo 11 check digit of `digits` weighted by `weights`
+fn mod11_check_digit(digits: &[u32], weights: impl Iterator<Item = u32>) -> u32 {
+    let sum: u32 = digits.iter().zip(weights).map(|(d, w)| d * w).sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        rest => 11 - rest,
+    }
+}
+
+/// Pre-flight CPF check, so paid Work API calls are not spent on bad documents
+///
+/// Accepts formatted input (`123.456.789-09`). Rejects anything that is not
+/// 11 digits, fails the check digits, or is a known placeholder CPF
+/// (repeated digits such as `000.000.000-00` pass the checksum).
+///
+/// # Examples
+/// ```
+/// use rust_c2s_api::enrichment::{check_cpf, DocumentRejection};
+///
+/// assert!(check_cpf("529.982.247-25").is_ok());
+/// assert_eq!(check_cpf("529.982.247-26"), Err(DocumentRejection::Checksum));
+/// assert_eq!(check_cpf("000.000.000-00"), Err(DocumentRejection::Placeholder));
+/// assert_eq!(check_cpf("user@example.com"), Err(DocumentRejection::Malformed));
+/// ```
+pub fn check_cpf(cpf: &str) -> Result<(), DocumentRejection> {
+    let digits = document_digits(cpf)?;
+    if digits.len() != 11 || cpf.contains('/') {
+        return Err(DocumentRejection::Malformed);
+    }
+
+    if mod11_check_digit(&digits[..9], (2..=10).rev()) != digits[9]
+        || mod11_check_digit(&digits[..10], (2..=11).rev()) != digits[10]
+    {
+        return Err(DocumentRejection::Checksum);
+    }
+
+    let plain: String = digits.iter().map(|d| d.to_string()).collect();
+    if digits.iter().all(|d| *d == digits[0]) || TEST_CPFS.contains(&plain.as_str()) {
+        return Err(DocumentRejection::Placeholder);
+    }
+
+    Ok(())
+}
+
+/// Pre-flight CNPJ check, the company counterpart of `check_cpf`
+///
+/// Accepts formatted input (`11.222.333/0001-81`). Only numeric CNPJs are
+/// supported, like the rest of the pipeline.
+///
+/// # Examples
+/// ```
+/// use rust_c2s_api::enrichment::{check_cnpj, DocumentRejection};
+///
+/// assert!(check_cnpj("11.222.333/0001-81").is_ok());
+/// assert_eq!(check_cnpj("11.222.333/0001-82"), Err(DocumentRejection::Checksum));
+/// assert_eq!(check_cnpj("00000000000000"), Err(DocumentRejection::Placeholder));
+/// ```
+pub fn check_cnpj(cnpj: &str) -> Result<(), DocumentRejection> {
+    let digits = document_digits(cnpj)?;
+    if digits.len() != 14 {
+        return Err(DocumentRejection::Malformed);
+    }
+
+    // Weights 5..2 then 9..2 (first digit), 6..2 then 9..2 (second digit)
+    let weights = |first: u32| (2..=first).rev().chain((2..=9).rev());
+    if mod11_check_digit(&digits[..12], weights(5)) != digits[12]
+        || mod11_check_digit(&digits[..13], weights(6)) != digits[13]
+    {
+        return Err(DocumentRejection::Checksum);
+    }
+
+    if digits.iter().all(|d| *d == digits[0]) {
+        return Err(DocumentRejection::Placeholder);
+    }
+
+    Ok(())
+}
+
+/// `check_cnpj` for 14-digit documents, `check_cpf` for anything else
+pub fn check_document(document: &str) -> Result<(), DocumentRejection> {
+    if is_cnpj(document) {
+        check_cnpj(document)
+    } else {
+        check_cpf(document)
+    }
+}
+
+/// A CNPJ has 14 digits (formatting ignored); anything else is treated as a CPF
+pub fn is_cnpj(document: &str) -> bool {
+    document.chars().filter(|c| c.is_ascii_digit()).count() == 14
+}
+
 /// Validates and normalizes Brazilian phone numbers to E.164 format
 ///
 /// Uses the phonenumber library (Google's libphonenumber) to parse and validate
@@ -227,13 +365,16 @@ pub fn validate_br_phone(raw: &str) -> (bool, String) {
 }
 
 /// Find CPF(s) from phone and/or email using Diretrix API
+///
+/// When `name` is given, Diretrix results are scored against the lead (see
+/// `identity`) and only confident matches are returned.
 pub async fn find_cpf_via_diretrix(
+    name: Option<&str>,
     phone: Option<&str>,
     email: Option<&str>,
-    config: &Config,
+    diretrix_service: &dyn DiretrixApi,
+    identity_match_threshold: f32,
 ) -> Result<CpfLookupResult, AppError> {
-    let diretrix_service = DiretrixService::new(config);
-
     // Validate and normalize phone before lookup
     let validated_phone = if let Some(phone_number) = phone {
         if !phone_number.is_empty() {
@@ -272,34 +413,52 @@ pub async fn find_cpf_via_diretrix(
     };
 
     // Parallel lookup - search by phone AND email separately (only if validated)
+    let mut provider_down = false;
+    let mut lookup_ok = |result: Result<Vec<DiretrixPersonSearch>, AppError>| match result {
+        Ok(results) => Some(results),
+        Err(e) => {
+            provider_down |= e.is_provider_unavailable();
+            None
+        }
+    };
+
     let phone_lookup = if let Some(ref phone_number) = validated_phone {
-        diretrix_service.search_by_phone(phone_number).await.ok()
+        lookup_ok(diretrix_service.search_by_phone(phone_number).await)
     } else {
         None
     };
 
     let email_lookup = if let Some(ref email_addr) = validated_email {
-        diretrix_service.search_by_email(email_addr).await.ok()
+        lookup_ok(diretrix_service.search_by_email(email_addr).await)
     } else {
         None
     };
 
-    // Extract CPFs from both lookups
-    let phone_cpf = phone_lookup.as_ref().and_then(|results| {
-        if !results.is_empty() {
-            Some(results[0].cpf.clone())
-        } else {
-            None
-        }
-    });
+    // Pick one CPF per lookup: scored against the lead when it has a name,
+    // Diretrix's first result otherwise
+    let lead = LeadIdentity {
+        name: name.filter(|n| !n.trim().is_empty()),
+        phone: validated_phone.as_deref(),
+        email: validated_email.as_deref(),
+        birth_date: None,
+    };
+    let pick = |results: Option<&Vec<DiretrixPersonSearch>>| {
+        pick_diretrix_candidate(
+            results?,
+            &lead,
+            phone_lookup.as_deref().unwrap_or_default(),
+            email_lookup.as_deref().unwrap_or_default(),
+            identity_match_threshold,
+        )
+    };
+    let phone_match = pick(phone_lookup.as_ref());
+    let email_match = pick(email_lookup.as_ref());
 
-    let email_cpf = email_lookup.as_ref().and_then(|results| {
-        if !results.is_empty() {
-            Some(results[0].cpf.clone())
-        } else {
-            None
-        }
-    });
+    let phone_cpf = phone_match.as_ref().map(|(cpf, _)| cpf.clone());
+    let email_cpf = email_match.as_ref().map(|(cpf, _)| cpf.clone());
+    let found_candidates = [&phone_lookup, &email_lookup]
+        .iter()
+        .any(|lookup| lookup.as_ref().is_some_and(|results| !results.is_empty()));
 
     // Check if both found and if they're the same person
     let (cpfs, same_person) = match (&phone_cpf, &email_cpf) {
@@ -322,6 +481,18 @@ pub async fn find_cpf_via_diretrix(
             tracing::info!("Found CPF from single source: {}", cpf);
             (vec![cpf.clone()], false)
         }
+        (None, None) if provider_down => {
+            tracing::warn!("Diretrix unavailable, CPF lookup deferred");
+            return Err(AppError::ProviderUnavailable(
+                "Diretrix unavailable for CPF lookup".to_string(),
+            ));
+        }
+        (None, None) if found_candidates => {
+            tracing::warn!("No Diretrix candidate matched the lead above the identity threshold");
+            return Err(AppError::NotFound(
+                "No confident identity match via Diretrix".to_string(),
+            ));
+        }
         (None, None) => {
             tracing::error!("Could not find CPF from either phone or email");
             return Err(AppError::NotFound(
@@ -330,35 +501,490 @@ pub async fn find_cpf_via_diretrix(
         }
     };
 
-    Ok(CpfLookupResult { cpfs, same_person })
+    let mut match_scores: Vec<MatchScore> = Vec::new();
+    for score in [phone_match, email_match]
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, s)| s)
+    {
+        if cpfs.contains(&score.cpf) && !match_scores.iter().any(|m| m.cpf == score.cpf) {
+            tracing::info!("Identity match {} score {:.2}", score.cpf, score.score);
+            match_scores.push(score);
+        }
+    }
+
+    let mut diretrix_people: Vec<DiretrixPersonSearch> = Vec::new();
+    for person in phone_lookup.iter().chain(email_lookup.iter()).flatten() {
+        if cpfs.contains(&person.cpf) && !diretrix_people.iter().any(|p| p.cpf == person.cpf) {
+            diretrix_people.push(person.clone());
+        }
+    }
+
+    Ok(CpfLookupResult {
+        cpfs,
+        same_person,
+        match_scores,
+        diretrix_people,
+    })
+}
+
+/// Choose the CPF a Diretrix lookup links the lead to
+///
+/// Without a lead name there is nothing to score, so the first result is
+/// used as before. Otherwise every result is scored (a result found by both
+/// phone and email shares both contacts) and only a match at or above
+/// `threshold` is returned.
+fn pick_diretrix_candidate(
+    results: &[DiretrixPersonSearch],
+    lead: &LeadIdentity,
+    phone_results: &[DiretrixPersonSearch],
+    email_results: &[DiretrixPersonSearch],
+    threshold: f32,
+) -> Option<(String, Option<MatchScore>)> {
+    let first = results.first()?;
+    if lead.name.is_none() {
+        return Some((first.cpf.clone(), None));
+    }
+
+    let found_in = |list: &[DiretrixPersonSearch], cpf: &str| list.iter().any(|r| r.cpf == cpf);
+    let candidates: Vec<Candidate> = results
+        .iter()
+        .map(|result| Candidate {
+            cpf: result.cpf.clone(),
+            name: result.nome.clone(),
+            phones: lead
+                .phone
+                .filter(|_| found_in(phone_results, &result.cpf))
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+            emails: lead
+                .email
+                .filter(|_| found_in(email_results, &result.cpf))
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+            birth_date: None,
+        })
+        .collect();
+
+    best_match(lead, &candidates, threshold).map(|m| (m.cpf.clone(), Some(m)))
+}
+
+/// Sections every complete CPF payload has (`modulo=cpf`)
+const CPF_SECTIONS: &[&str] = &["DadosBasicos", "DadosEconomicos"];
+
+/// A piece of enrichment data that could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MissingPiece {
+    pub cpf: String,
+    /// "work_api" when nothing was loaded for the document, else the missing module
+    pub piece: String,
+    pub reason: String,
+}
+
+/// Work API data of a lead's documents
+///
+/// Partial when a document could not be enriched (e.g. the second person of
+/// a different-person lead) or some of its modules are missing: the lead is
+/// still enriched with what loaded, and the gaps are listed in `missing`.
+#[derive(Debug, Clone, Default)]
+pub struct WorkApiEnrichment {
+    /// Documents with data, in lookup order (aligned with `data`)
+    pub cpfs: Vec<String>,
+    pub data: Vec<Value>,
+    pub missing: Vec<MissingPiece>,
+}
+
+impl WorkApiEnrichment {
+    pub fn is_partial(&self) -> bool {
+        !self.missing.is_empty()
+    }
+
+    /// Data in the order of `cpfs`, `Value::Null` for documents without data
+    pub fn aligned(&self, cpfs: &[String]) -> Vec<Value> {
+        cpfs.iter()
+            .map(|cpf| {
+                self.cpfs
+                    .iter()
+                    .position(|c| c == cpf)
+                    .map(|idx| self.data[idx].clone())
+                    .unwrap_or(Value::Null)
+            })
+            .collect()
+    }
+
+    /// `{"partial": .., "missing": [..]}`, for snapshots and API responses
+    pub fn completeness(&self) -> Value {
+        json!({ "partial": self.is_partial(), "missing": self.missing })
+    }
 }
 
 /// Enrich multiple CPFs with Work API
+///
+/// CNPJ documents go through the company modules instead
+/// (`WorkApi::fetch_company_modules`). Documents are fetched concurrently
+/// within the Work API limits (`WorkApi::fetch_documents`). Fails only when no
+/// document could be enriched; otherwise the result may be partial
+/// (`WorkApiEnrichment::missing`).
 pub async fn enrich_cpfs_with_work_api(
     cpfs: &[String],
-    config: &Config,
-) -> Result<Vec<Value>, AppError> {
-    let work_api_service = WorkApiService::new(config);
-
-    let mut enriched_data = Vec::new();
-    for cpf in cpfs {
-        tracing::info!("Enriching CPF: {}", cpf);
-        match work_api_service.fetch_all_modules(cpf).await {
-            Ok(data) => enriched_data.push(data),
+    work_api_service: &dyn WorkApi,
+) -> Result<WorkApiEnrichment, AppError> {
+    let mut enrichment = WorkApiEnrichment::default();
+    let mut provider_down = false;
+    tracing::info!("Enriching {} document(s): {}", cpfs.len(), cpfs.join(", "));
+    let results = work_api_service.fetch_documents(cpfs).await;
+    for (cpf, result) in cpfs.iter().zip(results) {
+        let result = result.map(|modules| match modules {
+            DocumentModules::Company(modules) => {
+                for module in &modules.failed_modules {
+                    enrichment.missing.push(MissingPiece {
+                        cpf: cpf.clone(),
+                        piece: module.clone(),
+                        reason: "módulo indisponível".to_string(),
+                    });
+                }
+                json!(modules)
+            }
+            DocumentModules::Person(data) => {
+                for section in CPF_SECTIONS {
+                    if data.get(section).is_none_or(Value::is_null) {
+                        enrichment.missing.push(MissingPiece {
+                            cpf: cpf.clone(),
+                            piece: section.to_string(),
+                            reason: "módulo não retornado".to_string(),
+                        });
+                    }
+                }
+                data
+            }
+        });
+        match result {
+            Ok(data) => {
+                enrichment.cpfs.push(cpf.clone());
+                enrichment.data.push(data);
+            }
             Err(e) => {
                 tracing::warn!("Failed to enrich CPF {}: {}", cpf, e);
+                provider_down |= e.is_provider_unavailable();
+                enrichment.missing.push(MissingPiece {
+                    cpf: cpf.clone(),
+                    piece: "work_api".to_string(),
+                    reason: e.to_string(),
+                });
                 // Continue with other CPFs even if one fails
             }
         }
     }
 
-    if enriched_data.is_empty() {
+    if enrichment.data.is_empty() && provider_down {
+        return Err(AppError::ProviderUnavailable(
+            "Work API unavailable for enrichment".to_string(),
+        ));
+    }
+
+    if enrichment.data.is_empty() {
         return Err(AppError::ExternalApiError(
             "No enrichment data available".to_string(),
         ));
     }
 
-    Ok(enriched_data)
+    if enrichment.is_partial() {
+        tracing::warn!(
+            "Partial enrichment: {} of {} document(s), {} missing piece(s)",
+            enrichment.data.len(),
+            cpfs.len(),
+            enrichment.missing.len()
+        );
+    }
+
+    Ok(enrichment)
+}
+
+/// Message section labeling a partial enrichment (empty when complete)
+pub fn format_partial_notice(missing: &[MissingPiece]) -> String {
+    if missing.is_empty() {
+        return String::new();
+    }
+    let mut notice = String::from("\n\n⚠️ ENRIQUECIMENTO PARCIAL\n");
+    for piece in missing {
+        if piece.piece == "work_api" {
+            notice.push_str(&format!(
+                "- CPF {}: dados indisponíveis na Work API\n",
+                piece.cpf
+            ));
+        } else {
+            notice.push_str(&format!(
+                "- CPF {}: {} ({})\n",
+                piece.cpf, piece.piece, piece.reason
+            ));
+        }
+    }
+    notice
+}
+
+/// Why an enrichment ended without any data for the lead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoDataReason {
+    /// Diretrix found no CPF matching the lead's contacts
+    NoCpf {
+        /// The lead was queued for a later lookup (`pending_enrichments`)
+        retry_scheduled: bool,
+    },
+    /// CPFs were found, but the Work API had no data for any of them
+    NoWorkApiData { cpfs: Vec<String> },
+}
+
+/// Note telling the seller that the enrichment found nothing
+///
+/// Lists the contacts of the lead with their validation status (invalid
+/// ones are never looked up) and what the lookup ended on, so an empty
+/// result is not mistaken for a failure of the integration.
+pub fn format_no_data_note(
+    phone: Option<&str>,
+    email: Option<&str>,
+    reason: &NoDataReason,
+) -> String {
+    let phone = phone.map(str::trim).filter(|p| !p.is_empty());
+    let email = email.map(str::trim).filter(|e| !e.is_empty());
+
+    let mut note = String::from("🔍 ENRIQUECIMENTO SEM RESULTADO\n");
+    let phone_status = match phone {
+        None => "não informado".to_string(),
+        Some(raw) => match validate_br_phone(raw) {
+            (true, normalized) => format!("{} (válido, consultado)", normalized),
+            (false, _) => format!("{} (inválido, não consultado)", raw),
+        },
+    };
+    note.push_str(&format!("Telefone: {}\n", phone_status));
+    let email_status = match email {
+        None => "não informado".to_string(),
+        Some(email) if is_valid_email(email) => format!("{} (válido, consultado)", email),
+        Some(email) => format!("{} (inválido, não consultado)", email),
+    };
+    note.push_str(&format!("E-mail: {}\n", email_status));
+
+    match reason {
+        NoDataReason::NoCpf { retry_scheduled } => {
+            note.push_str("Resultado: nenhum CPF encontrado para os contatos do lead\n");
+            if *retry_scheduled {
+                note.push_str("🔁 Nova consulta automática agendada\n");
+            }
+        }
+        NoDataReason::NoWorkApiData { cpfs } => {
+            note.push_str(&format!(
+                "Resultado: CPF {} sem dados na Work API\n",
+                cpfs.join(", ")
+            ));
+        }
+    }
+    note
+}
+
+/// Attach identity scores, cross-provider issues and (for partial results)
+/// the missing pieces to the Work API payloads
+///
+/// All end up in the stored enrichment snapshot (`raw_payload`); issues are
+/// also rendered in the C2S message by `format_enriched_message`.
+pub(crate) fn annotate_enrichments(cpf_result: &CpfLookupResult, work: &mut WorkApiEnrichment) {
+    let completeness = work.is_partial().then(|| work.completeness());
+    for (cpf, payload) in work.cpfs.iter().zip(work.data.iter_mut()) {
+        if let Some(completeness) = completeness.as_ref() {
+            payload["completeness"] = completeness.clone();
+        }
+
+        if let Some(score) = cpf_result.match_scores.iter().find(|m| &m.cpf == cpf) {
+            payload["identity_match"] = json!(score);
+        }
+
+        if let Some(person) = cpf_result.diretrix_people.iter().find(|p| &p.cpf == cpf) {
+            let issues = crate::consistency::compare(
+                &person.into(),
+                &crate::consistency::PersonFields::from_work_data(payload),
+            );
+            if !issues.is_empty() {
+                tracing::warn!(
+                    "Diretrix and Work API disagree for CPF {}: {:?}",
+                    cpf,
+                    issues.iter().map(|i| i.field.as_str()).collect::<Vec<_>>()
+                );
+            }
+            crate::consistency::record_issues(payload, &issues);
+        }
+    }
+}
+
+/// Confidence of contacts and addresses known only from Diretrix person data
+///
+/// Lower than the Work API ones (0.75-0.90 for addresses), so a Diretrix entry
+/// never outranks the primary source in `core.party_contacts`/`party_addresses`.
+pub const DIRETRIX_PERSON_CONFIDENCE: f64 = 0.6;
+
+/// Whether Diretrix person data is merged into the enrichment of a tenant's leads
+pub fn uses_diretrix_person(config: &Config, tenant: Option<&str>) -> bool {
+    tenant.is_some_and(|t| {
+        let tenant = t.trim().to_lowercase();
+        config.diretrix_person_tenants.contains(&tenant)
+    })
+}
+
+/// Fetch Diretrix person data for the enriched CPFs and merge it into their payloads
+///
+/// Diretrix is a secondary source: a failure only loses its extra data.
+/// Returns the number of entries added.
+pub async fn merge_diretrix_people(
+    cpfs: &[String],
+    enriched_data: &mut [Value],
+    diretrix_service: &dyn DiretrixApi,
+) -> usize {
+    let mut added = 0;
+    for (cpf, payload) in cpfs.iter().zip(enriched_data.iter_mut()) {
+        if is_cnpj(cpf) {
+            continue;
+        }
+        match diretrix_service.get_person_by_cpf(cpf).await {
+            Ok(person) => added += merge_diretrix_person(payload, &person),
+            Err(e) => tracing::warn!("Diretrix person data unavailable for CPF {}: {}", cpf, e),
+        }
+    }
+    added
+}
+
+/// Merge Diretrix person data into a Work API payload
+///
+/// Phones, emails and addresses the Work API does not know are appended in
+/// the Work API format, tagged with `fonte: "diretrix"` and
+/// `DIRETRIX_PERSON_CONFIDENCE` (stored as source/confidence by
+/// `EnrichmentStorage`). Basic data only fills fields the Work API left empty.
+/// Returns the number of entries added.
+pub fn merge_diretrix_person(payload: &mut Value, person: &DiretrixPersonData) -> usize {
+    if !payload.is_object() {
+        return 0;
+    }
+    let digits = |s: &str| s.chars().filter(|c| c.is_ascii_digit()).collect::<String>();
+    let mut added = 0;
+
+    let mut phones: Vec<String> = json_array(payload, "telefones")
+        .iter()
+        .filter_map(|p| p.get("telefone").and_then(|v| v.as_str()))
+        .map(digits)
+        .collect();
+    for phone in &person.telefones {
+        let number = digits(&format!("{}{}", phone.ddd, phone.numero));
+        if number.is_empty() || phones.contains(&number) {
+            continue;
+        }
+        push_json(
+            payload,
+            "telefones",
+            json!({
+                "telefone": number,
+                "tipo": phone.tipo,
+                "operadora": phone.operadora,
+                "fonte": "diretrix",
+                "confianca": DIRETRIX_PERSON_CONFIDENCE,
+            }),
+        );
+        phones.push(number);
+        added += 1;
+    }
+
+    let mut emails: Vec<String> = json_array(payload, "emails")
+        .iter()
+        .filter_map(|e| e.get("email").and_then(|v| v.as_str()))
+        .map(|e| e.trim().to_lowercase())
+        .collect();
+    for email in &person.emails {
+        let address = email.endereco.trim().to_lowercase();
+        if address.is_empty() || emails.contains(&address) {
+            continue;
+        }
+        push_json(
+            payload,
+            "emails",
+            json!({
+                "email": address,
+                "fonte": "diretrix",
+                "confianca": DIRETRIX_PERSON_CONFIDENCE,
+            }),
+        );
+        emails.push(address);
+        added += 1;
+    }
+
+    let address_key = |cep: &str, numero: &str| (digits(cep), numero.trim().to_lowercase());
+    let mut addresses: Vec<(String, String)> = json_array(payload, "enderecos")
+        .iter()
+        .map(|a| {
+            let field = |k: &str| a.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            address_key(field("cep"), field("numero"))
+        })
+        .collect();
+    for address in &person.enderecos {
+        let key = address_key(&address.cep, &address.numero);
+        if key.0.is_empty() || addresses.contains(&key) {
+            continue;
+        }
+        push_json(
+            payload,
+            "enderecos",
+            json!({
+                "logradouro": address.logadouro,
+                "numero": address.numero,
+                "bairro": address.bairro,
+                "cidade": address.cidade,
+                "uf": address.uf,
+                "cep": address.cep,
+                "complemento": address.complemento,
+                "fonte": "diretrix",
+                "confianca": DIRETRIX_PERSON_CONFIDENCE,
+            }),
+        );
+        addresses.push(key);
+        added += 1;
+    }
+
+    if !payload["DadosBasicos"].is_object() {
+        payload["DadosBasicos"] = json!({});
+    }
+    let basic = &mut payload["DadosBasicos"];
+    for (field, value) in [
+        ("dataNascimento", &person.data_nascimento),
+        ("sexo", &person.sexo),
+        ("nomeMae", &person.mae),
+        ("rg", &person.rg),
+    ] {
+        let missing = basic
+            .get(field)
+            .is_none_or(|v| v.is_null() || v.as_str() == Some(""));
+        if let (true, Some(value)) = (missing, value.as_deref().filter(|v| !v.is_empty())) {
+            basic[field] = json!(value);
+            added += 1;
+        }
+    }
+
+    if added > 0 {
+        payload["enrichment_sources"] = json!(["work_api", "diretrix"]);
+    }
+    added
+}
+
+fn json_array<'a>(payload: &'a Value, key: &str) -> &'a [Value] {
+    payload
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn push_json(payload: &mut Value, key: &str, entry: Value) {
+    if !payload[key].is_array() {
+        payload[key] = json!([]);
+    }
+    if let Some(entries) = payload[key].as_array_mut() {
+        entries.push(entry);
+    }
 }
 
 /// Formats enriched customer data into a message body for C2S
@@ -370,15 +996,16 @@ pub async fn enrich_cpfs_with_work_api(
 /// * `customer_name` - The customer's name
 /// * `phone` - The phone number
 /// * `email` - The email address
-/// * `enriched_data` - Array of enriched data from Work API (1 or 2 entries)
+/// * `enriched_data` - Array of enriched data from Work API (1 or 2 entries,
+///   `Value::Null` for a person whose data could not be loaded)
 /// * `same_person` - Whether phone and email belong to the same person
 ///
 /// # Returns
 /// A formatted string message ready to send to C2S
 ///
 /// # Message Format
-/// - Same person: Single enriched profile with "📞📧" header
-/// - Different people: Two separate profiles with "⚠️" warning header
+/// - Same person: Single enriched profile with the `same_person_header` template
+/// - Different people: Two separate profiles with the `different_people_header` template
 pub fn format_enriched_message_body(
     customer_name: &str,
     phone: &str,
@@ -386,60 +1013,173 @@ pub fn format_enriched_message_body(
     enriched_data: &[Value],
     same_person: bool,
 ) -> String {
+    format_enriched_message_body_in(
+        customer_name,
+        phone,
+        email,
+        enriched_data,
+        same_person,
+        Locale::Pt,
+    )
+}
+
+/// `format_enriched_message_body` with the header in the lead's language
+///
+/// For a lead writing in another language, the header is followed by the
+/// `language_notice` template telling the seller which one.
+pub fn format_enriched_message_body_in(
+    customer_name: &str,
+    phone: &str,
+    email: &str,
+    enriched_data: &[Value],
+    same_person: bool,
+    locale: Locale,
+) -> String {
+    let header_template = if same_person {
+        crate::templates::SAME_PERSON_HEADER
+    } else {
+        crate::templates::DIFFERENT_PEOPLE_HEADER
+    };
+    let mut header =
+        crate::templates::render_in(header_template, locale, &[("customer_name", customer_name)]);
+    if locale != Locale::Pt {
+        header.push('\n');
+        header.push_str(&crate::templates::render(
+            crate::templates::LANGUAGE_NOTICE,
+            &[("language", locale.language_name())],
+        ));
+    }
+
     if same_person {
         let enriched_msg =
             crate::handlers::format_enriched_message(customer_name, &enriched_data[0]);
         tracing::info!("Enriched message length: {} chars", enriched_msg.len());
-        format!("📞📧 Telefone e e-mail da mesma pessoa\n\n{}", enriched_msg)
+        format!("{}\n\n{}", header, enriched_msg)
     } else {
-        let mut combined_message =
-            String::from("⚠️ Telefone e e-mail relacionados a PESSOAS DIFERENTES!\n\n");
+        let mut combined_message = header;
+        combined_message.push_str("\n\n");
 
         combined_message.push_str(&format!("═══ PESSOA 1 (Telefone: {}) ═══\n", phone));
-        combined_message.push_str(&crate::handlers::format_enriched_message(
-            "",
-            &enriched_data[0],
-        ));
+        combined_message.push_str(&format_person_section(&enriched_data[0]));
 
         if enriched_data.len() > 1 {
             combined_message.push_str(&format!("\n\n═══ PESSOA 2 (Email: {}) ═══\n", email));
-            combined_message.push_str(&crate::handlers::format_enriched_message(
-                "",
-                &enriched_data[1],
-            ));
+            combined_message.push_str(&format_person_section(&enriched_data[1]));
         }
 
         combined_message
     }
 }
 
-/// Send enriched message to C2S (via gateway if available)
-pub async fn send_message_to_c2s(
-    lead_id: &str,
-    message: &str,
-    gateway_client: Option<&C2sGatewayClient>,
-    config: &Config,
-) -> Result<(), AppError> {
-    if let Some(gateway) = gateway_client {
-        tracing::info!("Using C2S Gateway to send message");
-        gateway.send_message(lead_id, message).await?;
+/// One person of a different-people message
+fn format_person_section(work_data: &Value) -> String {
+    if work_data.is_null() {
+        "⚠️ Dados indisponíveis (enriquecimento parcial)\n".to_string()
     } else {
-        tracing::info!("Using direct C2S API to send message");
-        let c2s_service = C2SService::new(config);
-        c2s_service.send_message(lead_id, message).await?;
+        crate::handlers::format_enriched_message("", work_data)
     }
+}
 
-    Ok(())
+/// Listing of the first resolvable property reference stored for the lead
+///
+/// `None` when the listings API is not configured, the lead mentions no
+/// property, or the lookup fails (the message is sent without it).
+pub async fn lead_listing(state: &AppState, lead_id: &str) -> Option<Listing> {
+    let client = state.listings_client.get()?;
+    let references = crate::property_refs::load_property_refs(&state.db, lead_id)
+        .await
+        .inspect_err(|e| tracing::warn!("{}", e))
+        .ok()?;
+
+    for reference in references {
+        match client.get_listing(&reference).await {
+            Ok(Some(listing)) => return Some(listing),
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Listing lookup failed for {}: {}", reference, e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// Lead a C2S message is written for
+#[derive(Debug, Clone, Copy)]
+pub struct MessageLead<'a> {
+    pub name: &'a str,
+    pub phone: Option<&'a str>,
+    pub email: Option<&'a str>,
+    pub locale: Locale,
+}
+
+/// Full C2S message for enriched data: dossier, listing of interest and affordability
+pub async fn render_lead_message(
+    state: &AppState,
+    lead_id: &str,
+    lead: MessageLead<'_>,
+    enriched_data: &[Value],
+    same_person: bool,
+) -> (String, Option<Affordability>) {
+    let mut message_body = format_enriched_message_body_in(
+        lead.name,
+        lead.phone.unwrap_or(""),
+        lead.email.unwrap_or(""),
+        enriched_data,
+        same_person,
+        lead.locale,
+    );
+    let listing = lead_listing(state, lead_id).await;
+    let affordability = listing
+        .as_ref()
+        .and_then(|l| l.price)
+        .zip(enriched_data.iter().find(|data| !data.is_null()))
+        .and_then(|(price, work_data)| Affordability::assess(work_data, price));
+    if let Some(listing) = listing.as_ref() {
+        message_body.push_str(&listing.format_section());
+    }
+    if let Some(affordability) = affordability.as_ref() {
+        message_body.push_str(&affordability.format_line());
+    }
+    (message_body, affordability)
+}
+
+/// Schedule the seller reminder when `FOLLOW_UP_NUDGE_HOURS` is set (non-fatal)
+pub(crate) async fn schedule_follow_up_nudge(
+    state: &AppState,
+    lead_id: &str,
+    tenant: Option<&str>,
+    customer_name: &str,
+) {
+    let Some(hours) = state.config.follow_up_nudge_hours else {
+        return;
+    };
+    match crate::outbox::schedule_follow_up(
+        state,
+        lead_id,
+        tenant,
+        customer_name,
+        chrono::Duration::hours(hours),
+    )
+    .await
+    {
+        Ok(id) => tracing::info!(
+            "Follow-up nudge for lead_id={} scheduled as outbox #{}",
+            lead_id,
+            id
+        ),
+        Err(e) => tracing::warn!("Failed to schedule follow-up nudge: {}", e),
+    }
 }
 
 /// Store enriched data in database
 pub async fn store_enriched_data(
-    db: &PgPool,
+    state: &AppState,
     cpfs: &[String],
     enriched_data: &[Value],
     lead_id: Option<&str>,
 ) -> Result<Vec<uuid::Uuid>, AppError> {
-    let storage = EnrichmentStorage::new(db.clone());
+    let storage = &state.services.storage;
 
     let mut stored_entity_ids = Vec::new();
     for (idx, cpf) in cpfs.iter().enumerate() {
@@ -449,7 +1189,7 @@ pub async fn store_enriched_data(
         }
 
         match storage
-            .store_enriched_person_with_lead(cpf, &enriched_data[idx], lead_id)
+            .store_enriched_party(cpf, &enriched_data[idx], lead_id)
             .await
         {
             Ok(entity_id) => {
@@ -473,108 +1213,215 @@ pub async fn store_enriched_data(
 
 /// Complete enrichment workflow for a lead
 ///
-/// This is the main entry point that orchestrates the entire enrichment process:
+/// This is the main entry point that orchestrates the entire enrichment process
+/// through the stages of the tenant's pipeline (see `pipeline`):
 /// 1. Find CPF(s) via Diretrix
 /// 2. Enrich with Work API
-/// 3. Format message
-/// 4. Send to C2S
+/// 3. Summarize and format message
+/// 4. Send to C2S (queued in the outbox during the tenant's quiet hours),
+///    headers in the lead's language (`locale`)
 /// 5. Store in database
+///
+/// When no CPF or no Work API data is found, a no-data note is sent to C2S
+/// instead (`format_no_data_note`) and the error is still returned. Webhook
+/// subscribers are notified of the outcome (`subscribers`), except for
+/// enrichments deferred while a provider is down.
 pub async fn enrich_and_send_workflow(
     state: Arc<AppState>,
     lead_id: &str,
     customer_name: &str,
     phone: Option<&str>,
     email: Option<&str>,
+    tenant: Option<&str>,
+    locale: Locale,
 ) -> Result<EnrichmentResult, AppError> {
-    let db = &state.db;
-    let config = &state.config;
-    let gateway_client = state.gateway_client.as_ref();
-
-    tracing::info!("Starting enrichment workflow for lead_id: {}", lead_id);
-
-    // OPTIMIZATION: Check DB/Cache first
-    if let Ok(Some(existing)) = find_existing_enrichment(&state, phone, email).await {
-        tracing::info!("✅ Found existing enrichment for CPF: {}", existing.cpf);
-
-        // Try to format message from existing data
-        if let Some(data_value) = existing.enriched_data {
-            if let Ok(work_data) = serde_json::from_value::<WorkApiCompleteResponse>(data_value) {
-                let message_body = format_enriched_message_body(
-                    customer_name,
-                    phone.unwrap_or(""),
-                    email.unwrap_or(""),
-                    &[serde_json::to_value(&work_data).unwrap()],
-                    true,
-                );
+    let pipeline = crate::pipeline::Pipeline::for_tenant(&state.config, tenant);
+    tracing::info!(
+        "Starting enrichment workflow for lead_id: {} (stages: {})",
+        lead_id,
+        pipeline
+            .stage_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(" → ")
+    );
 
-                tracing::info!("Sending cached message to C2S");
-                send_message_to_c2s(lead_id, &message_body, gateway_client, config).await?;
+    let mut ctx =
+        crate::pipeline::LeadContext::new(state, lead_id, customer_name, phone, email, tenant);
+    ctx.locale = locale;
+    let stages = pipeline.run(&mut ctx).await;
+    crate::metrics::record_enrichment(match &stages {
+        Ok(_) => "success",
+        Err(e) if e.is_provider_unavailable() => "deferred",
+        Err(_) => "failure",
+    });
+    // Silence reads as a bug to sellers: say what was tried and found nothing
+    if stages.is_err()
+        && pipeline
+            .stage_names()
+            .contains(&crate::pipeline::StageName::Deliver)
+    {
+        ctx.deliver_no_data_note().await;
+    }
 
-                return Ok(EnrichmentResult {
-                    lead_id: lead_id.to_string(),
-                    cpfs_enriched: vec![existing.cpf],
-                    same_person: true,
-                    message_sent: true,
-                    stored_count: 0,
-                    entity_ids: vec![existing.party_id],
-                });
-            }
+    let state = ctx.state.clone();
+    match stages {
+        Ok(stages) => {
+            let result = ctx.into_result(stages);
+            let event = crate::subscribers::completed_event(lead_id, tenant, &result);
+            crate::subscribers::notify(&state, tenant, event).await;
+            Ok(result)
+        }
+        Err(e) if e.is_provider_unavailable() => Err(e),
+        Err(e) => {
+            let reason = crate::subscribers::failure_reason(&e, ctx.no_data.as_ref());
+            let event = crate::subscribers::failed_event(lead_id, tenant, reason);
+            crate::subscribers::notify(&state, tenant, event).await;
+            Err(e)
         }
-        tracing::warn!(
-            "Found existing enrichment but failed to parse data, falling back to external APIs"
-        );
     }
+}
 
-    // Step 1: Find CPF(s) via Diretrix
-    tracing::info!("Step 1: Finding CPF via Diretrix");
-    let cpf_result = find_cpf_via_diretrix(phone, email, config).await?;
+/// Compact view of an enriched lead shared by downstream integrations
+/// (RD Station, Salesforce, Google Sheets)
+#[derive(Debug, Clone)]
+pub struct EnrichedLeadSummary {
+    pub lead_id: String,
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub cpf: Option<String>,
+    /// Declared monthly income (`DadosEconomicos.renda`), unadjusted
+    pub income: Option<f64>,
+    /// Credit score (`DadosEconomicos.score.scoreCSBA`)
+    pub credit_score: Option<i32>,
+    pub wealth: WealthSegment,
+    /// Price-to-income of the inquired property (when the listing is known)
+    pub affordability: Option<Affordability>,
+    /// State (UF) of the first Work API address, else inferred from the phone DDD
+    pub uf: Option<String>,
+}
 
-    tracing::info!(
-        "Found {} CPF(s), same_person: {}",
-        cpf_result.cpfs.len(),
-        cpf_result.same_person
-    );
+impl EnrichedLeadSummary {
+    pub fn from_work_data(
+        lead_id: &str,
+        customer_name: &str,
+        phone: Option<&str>,
+        email: Option<&str>,
+        cpf: Option<&str>,
+        work_data: &Value,
+    ) -> Self {
+        let dados_econ = work_data.get("DadosEconomicos");
+        let income = dados_econ
+            .and_then(|d| d.get("renda"))
+            .and_then(|v| v.as_str())
+            .and_then(Brl::parse)
+            .map(|renda| renda.to_f64());
+        let credit_score = dados_econ
+            .and_then(|d| d.get("score"))
+            .and_then(|s| s.get("scoreCSBA"))
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.trim().parse::<i32>().ok());
 
-    // Step 2: Enrich with Work API
-    tracing::info!(
-        "Step 2: Enriching {} CPF(s) with Work API",
-        cpf_result.cpfs.len()
-    );
-    let enriched_data = enrich_cpfs_with_work_api(&cpf_result.cpfs, config).await?;
+        Self {
+            lead_id: lead_id.to_string(),
+            name: customer_name.to_string(),
+            phone: phone.map(|p| p.to_string()),
+            email: email.filter(|e| is_valid_email(e)).map(|e| e.to_string()),
+            cpf: cpf.map(|c| c.to_string()),
+            income,
+            credit_score,
+            wealth: WealthSegment::from_work_data(work_data),
+            affordability: None,
+            uf: lead_uf(work_data, phone),
+        }
+    }
+}
 
-    // Step 3: Format message
-    tracing::info!("Step 3: Formatting enriched message");
-    let message_body = format_enriched_message_body(
-        customer_name,
-        phone.unwrap_or(""),
-        email.unwrap_or(""),
-        &enriched_data,
-        cpf_result.same_person,
-    );
+/// UF of the lead: first Work API address, falling back to the phone DDD
+fn lead_uf(work_data: &Value, phone: Option<&str>) -> Option<String> {
+    work_data
+        .get("enderecos")
+        .and_then(|e| e.as_array())
+        .and_then(|enderecos| {
+            enderecos
+                .iter()
+                .find_map(|e| e.get("uf").and_then(|v| v.as_str()))
+        })
+        .map(|uf| uf.trim().to_uppercase())
+        .filter(|uf| uf.len() == 2)
+        .or_else(|| {
+            phone
+                .and_then(crate::ddd::region_for_phone)
+                .map(|r| r.uf.to_string())
+        })
+}
 
-    // Step 4: Send to C2S
-    tracing::info!(
-        "Step 4: Sending message to C2S (length: {} chars)",
-        message_body.len()
-    );
-    send_message_to_c2s(lead_id, &message_body, gateway_client, config).await?;
+/// Push the enriched lead to the configured downstream integrations
+///
+/// Failures are logged and never interrupt the enrichment workflow.
+pub async fn notify_integrations(state: &AppState, summary: &EnrichedLeadSummary) {
+    let fields = crate::c2s_fields::custom_field_values(&state.config.c2s_custom_fields, summary);
+    if !fields.is_empty() {
+        if let Err(e) = state
+            .c2s
+            .update_custom_fields(&summary.lead_id, &fields)
+            .await
+        {
+            tracing::warn!(
+                "Failed to update C2S custom fields of lead {}: {}",
+                summary.lead_id,
+                e
+            );
+        }
+    }
 
-    // Step 5: Store in database
-    tracing::info!(
-        "Step 5: Storing {} person(s) in database",
-        cpf_result.cpfs.len()
-    );
-    let stored_entity_ids =
-        store_enriched_data(db, &cpf_result.cpfs, &enriched_data, Some(lead_id)).await?;
-
-    Ok(EnrichmentResult {
-        lead_id: lead_id.to_string(),
-        cpfs_enriched: cpf_result.cpfs.clone(),
-        same_person: cpf_result.same_person,
-        message_sent: true,
-        stored_count: stored_entity_ids.len(),
-        entity_ids: stored_entity_ids,
-    })
+    if let Some(client) = state.rdstation_client.get() {
+        // RD Station identifies contacts by email, so leads without one are skipped
+        match summary.email.as_ref() {
+            Some(email) => {
+                let lead = crate::rdstation::RdStationLead {
+                    lead_id: summary.lead_id.clone(),
+                    name: summary.name.clone(),
+                    email: email.clone(),
+                    phone: summary.phone.clone(),
+                    wealth: summary.wealth,
+                };
+                if let Err(e) = client.send_conversion(&lead).await {
+                    tracing::warn!(
+                        "Failed to push lead {} to RD Station: {}",
+                        summary.lead_id,
+                        e
+                    );
+                }
+            }
+            None => tracing::debug!(
+                "Skipping RD Station conversion for lead {}: no email",
+                summary.lead_id
+            ),
+        }
+    }
+
+    if let Some(client) = state.salesforce_client.get() {
+        if let Err(e) = client.upsert_lead(summary).await {
+            tracing::warn!(
+                "Failed to sync lead {} to Salesforce: {}",
+                summary.lead_id,
+                e
+            );
+        }
+    }
+
+    if let Some(client) = state.sheets_client.get() {
+        if let Err(e) = client.append_summary(summary).await {
+            tracing::warn!(
+                "Failed to append lead {} to Google Sheet: {}",
+                summary.lead_id,
+                e
+            );
+        }
+    }
 }
 
 /// Result of enrichment workflow
@@ -585,11 +1432,40 @@ pub struct EnrichmentResult {
     pub cpfs_enriched: Vec<String>,
     #[allow(dead_code)]
     pub same_person: bool,
+    /// False when the message was queued in the outbox (quiet hours) or had
+    /// nothing new for a recently messaged lead
     #[allow(dead_code)]
     pub message_sent: bool,
     pub stored_count: usize,
     #[allow(dead_code)]
     pub entity_ids: Vec<uuid::Uuid>,
+    /// Summary of the primary enriched person (used for routing/notifications)
+    pub summary: Option<EnrichedLeadSummary>,
+    /// Pieces that could not be loaded (empty for a complete enrichment)
+    pub missing: Vec<MissingPiece>,
+    /// Time spent in each step, for triaging slow leads
+    pub timings: StepTimings,
+    /// Pipeline stages that ran, in order (skipped ones left out)
+    pub stages: Vec<crate::pipeline::StageName>,
+}
+
+/// Milliseconds spent per workflow step (steps that did not run stay at 0)
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub struct StepTimings {
+    pub diretrix_ms: u64,
+    pub work_api_ms: u64,
+    pub c2s_send_ms: u64,
+    pub db_ms: u64,
+}
+
+impl StepTimings {
+    /// Await a step, adding its duration to `slot`
+    pub async fn measure<F: std::future::Future>(slot: &mut u64, step: F) -> F::Output {
+        let start = std::time::Instant::now();
+        let output = step.await;
+        *slot += start.elapsed().as_millis() as u64;
+        output
+    }
 }
 
 impl EnrichmentResult {
@@ -604,6 +1480,87 @@ impl EnrichmentResult {
             "message_sent": self.message_sent,
             "stored_in_db": self.stored_count,
             "entity_ids": self.entity_ids,
+            "timings": self.timings,
+            "stages": self.stages,
         })
     }
 }
+
+/// Wealth segment derived from Work API economic data (`DadosEconomicos`)
+///
+/// Used to tag leads in downstream marketing/CRM integrations and in routing rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WealthSegment {
+    MuitoAlto,
+    Alto,
+    Medio,
+    Baixo,
+    Desconhecido,
+}
+
+impl WealthSegment {
+    /// Classify a Work API response into a wealth segment
+    ///
+    /// Prefers the provider's purchasing power description and falls back to
+    /// declared income (`renda`) when the description is missing.
+    pub fn from_work_data(work_data: &Value) -> Self {
+        let dados_econ = match work_data.get("DadosEconomicos") {
+            Some(d) => d,
+            None => return Self::Desconhecido,
+        };
+
+        if let Some(desc) = dados_econ
+            .get("poderAquisitivo")
+            .and_then(|p| p.get("poderAquisitivoDescricao"))
+            .and_then(|v| v.as_str())
+        {
+            let desc = desc.trim().to_uppercase();
+            if desc.starts_with("MUITO ALTO") {
+                return Self::MuitoAlto;
+            } else if desc.starts_with("ALTO") || desc.starts_with("MEDIO ALTO") {
+                return Self::Alto;
+            } else if desc.starts_with("MEDIO") || desc.starts_with("MÉDIO") {
+                return Self::Medio;
+            } else if desc.contains("BAIXO") {
+                return Self::Baixo;
+            }
+        }
+
+        let renda = dados_econ
+            .get("renda")
+            .and_then(|v| v.as_str())
+            .and_then(Brl::parse)
+            .map(|renda| renda.to_f64());
+
+        match renda {
+            Some(r) if r >= 15_000.0 => Self::MuitoAlto,
+            Some(r) if r >= 7_000.0 => Self::Alto,
+            Some(r) if r >= 3_000.0 => Self::Medio,
+            Some(_) => Self::Baixo,
+            None => Self::Desconhecido,
+        }
+    }
+
+    /// Ordering used for threshold comparisons (higher is wealthier)
+    pub fn rank(&self) -> u8 {
+        match self {
+            Self::MuitoAlto => 4,
+            Self::Alto => 3,
+            Self::Medio => 2,
+            Self::Baixo => 1,
+            Self::Desconhecido => 0,
+        }
+    }
+
+    /// Tag used by marketing integrations (e.g. "patrimonio-alto")
+    pub fn as_tag(&self) -> &'static str {
+        match self {
+            Self::MuitoAlto => "patrimonio-muito-alto",
+            Self::Alto => "patrimonio-alto",
+            Self::Medio => "patrimonio-medio",
+            Self::Baixo => "patrimonio-baixo",
+            Self::Desconhecido => "patrimonio-desconhecido",
+        }
+    }
+}
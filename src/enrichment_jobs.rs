@@ -0,0 +1,193 @@
+//! Async C2S lead enrichment
+//!
+//! `POST /api/v1/c2s/enrich/:lead_id?async=true` answers 202 with a
+//! `job_id` instead of blocking for the Diretrix + Work API + C2S round trip.
+//! The enrichment is stored as a `lead_enrichment` job (see `jobs`) and run by
+//! `ENRICH_JOB_WORKERS` background workers per instance, which record the
+//! step reached and the response of the synchronous endpoint as the job's
+//! result. Callers poll `GET /api/v1/jobs/:id` with the API key that queued
+//! the job (ids are sequential). Provider outages are retried
+//! (`MAX_ATTEMPTS` runs); any other error fails the job.
+
+use crate::admin_handler::require_admin;
+use crate::api_keys::{AuthenticatedKey, Scope};
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use crate::jobs::{self, JobStatus, Visibility, LEAD_ENRICHMENT};
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs of a job whose providers are unavailable before it is marked 'failed'
+const MAX_ATTEMPTS: i32 = 3;
+
+/// Idle workers look for queued jobs at this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval between sweeps of abandoned and old completed jobs
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Payload of a `lead_enrichment` job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeadEnrichmentJob {
+    pub lead_id: String,
+}
+
+/// Queue the enrichment of a C2S lead for an API key, returns the job id
+pub async fn enqueue(
+    pool: &PgPool,
+    lead_id: &str,
+    api_key_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let payload = serde_json::to_value(LeadEnrichmentJob {
+        lead_id: lead_id.to_string(),
+    })
+    .map_err(|e| AppError::InternalError(format!("Failed to serialize job: {}", e)))?;
+    jobs::enqueue(pool, LEAD_ENRICHMENT, &payload, MAX_ATTEMPTS, api_key_id).await
+}
+
+/// Record the step reached by the job running an enrichment, if any
+///
+/// Progress is informational: a failed update is logged, not propagated.
+pub async fn report_progress(pool: &PgPool, job: Option<i64>, step: &str) {
+    let Some(id) = job else {
+        return;
+    };
+    if let Err(e) = jobs::set_progress(pool, id, step).await {
+        tracing::warn!("Failed to record progress of job #{}: {}", id, e);
+    }
+}
+
+/// GET /api/v1/jobs/:id
+///
+/// State of an async enrichment or background batch (`batch_jobs`): status,
+/// step reached, error and result. A job queued by another API key is 404,
+/// except for admin keys and the admin token.
+pub async fn job_status(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<JobStatus>, AppError> {
+    let key = key.map(|Extension(key)| key);
+    let visibility = match &key {
+        Some(key) if key.allows(Scope::Admin) => Visibility::All,
+        None if require_admin(&state.config, &headers).is_ok() => Visibility::All,
+        _ => Visibility::QueuedBy(key.as_ref().map(|key| key.id)),
+    };
+    jobs::status(&state.db, &[LEAD_ENRICHMENT, jobs::BATCH], id, visibility)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))
+}
+
+/// Spawn the workers running async enrichments
+pub fn spawn_workers(state: Arc<AppState>, workers: usize) {
+    for index in 0..workers.max(1) {
+        let state = state.clone();
+        let worker = jobs::worker_id(index);
+        tokio::spawn(async move {
+            loop {
+                match jobs::claim(&state.db, LEAD_ENRICHMENT, &worker).await {
+                    Ok(Some(job)) => run_job(&state, job).await,
+                    Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Err(e) => {
+                        tracing::error!("Failed to claim an enrichment job: {}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match jobs::sweep(&state.db, LEAD_ENRICHMENT).await {
+                Ok(0) => {}
+                Ok(failed) => tracing::warn!(
+                    "{} enrichment job(s) failed: worker lost on every run",
+                    failed
+                ),
+                Err(e) => tracing::warn!("Failed to sweep enrichment jobs: {}", e),
+            }
+        }
+    });
+    tracing::info!("✓ {} async enrichment worker(s) started", workers.max(1));
+}
+
+/// Run one claimed job and record its outcome
+async fn run_job(state: &Arc<AppState>, job: jobs::Job) {
+    let lead_id = match serde_json::from_value::<LeadEnrichmentJob>(job.payload.clone()) {
+        Ok(payload) => payload.lead_id,
+        Err(e) => {
+            tracing::error!("Enrichment job #{} has an invalid payload: {}", job.id, e);
+            let error = format!("Invalid job payload: {}", e);
+            if let Err(e) = jobs::fail(&state.db, &job, &error).await {
+                tracing::error!("Failed to mark enrichment job #{} as failed: {}", job.id, e);
+            }
+            return;
+        }
+    };
+
+    let run = crate::handlers::enrich_c2s_lead(state, &lead_id, Some(job.id));
+    let result = match jobs::with_heartbeat(&state.db, &job, run).await {
+        Ok(response) => jobs::complete(&state.db, &job, Some(&response))
+            .await
+            .map(|_| ()),
+        Err(e) if e.is_provider_unavailable() => {
+            tracing::warn!(
+                "Enrichment job #{} for lead_id={} deferred (run {}/{}): {}",
+                job.id,
+                lead_id,
+                job.attempts,
+                job.max_attempts,
+                e
+            );
+            jobs::retry_or_fail(&state.db, &job, &e).await.map(|_| ())
+        }
+        Err(e) => {
+            tracing::warn!(
+                error_chain = ?e.chain(),
+                "Enrichment job #{} for lead_id={} failed: {}",
+                job.id,
+                lead_id,
+                e.chain_message()
+            );
+            jobs::fail(&state.db, &job, &e.chain_message()).await
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!(
+            "Failed to record outcome of enrichment job #{}: {}",
+            job.id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_job_payload() {
+        let payload = serde_json::to_value(LeadEnrichmentJob {
+            lead_id: "bf1a88eaa4ab34b01a257536563fb42b".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            payload,
+            json!({"lead_id": "bf1a88eaa4ab34b01a257536563fb42b"})
+        );
+        assert!(serde_json::from_value::<LeadEnrichmentJob>(json!({})).is_err());
+    }
+}
@@ -14,6 +14,18 @@ pub enum AppError {
     ExternalApiError(String),
     InternalError(String),
     Unauthorized(String),
+    /// Authenticated caller not allowed to do this (scope or region of its API key)
+    Forbidden(String),
+    /// External provider temporarily unavailable (circuit breaker open)
+    ProviderUnavailable(String),
+    /// Tenant requires consent and none is recorded for the lead
+    ConsentRequired(String),
+    /// Request conflicts with the current state of the resource
+    Conflict(String),
+    /// CPF/CNPJ rejected before any provider call (see `enrichment::check_document`)
+    InvalidDocument(String),
+    /// The request's deadline ran out before a provider answered (see `deadline`)
+    DeadlineExceeded(String),
     /// Error with context chain for better debugging
     WithContext {
         source: Box<AppError>,
@@ -30,6 +42,12 @@ impl fmt::Display for AppError {
             AppError::ExternalApiError(msg) => write!(f, "External API error: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            AppError::ProviderUnavailable(msg) => write!(f, "Provider unavailable: {}", msg),
+            AppError::ConsentRequired(msg) => write!(f, "Consent required: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::InvalidDocument(msg) => write!(f, "Invalid document: {}", msg),
+            AppError::DeadlineExceeded(msg) => write!(f, "Deadline exceeded: {}", msg),
             AppError::WithContext { source, context } => {
                 write!(f, "{}: {}", context, source)
             }
@@ -67,11 +85,60 @@ impl IntoResponse for AppError {
                 tracing::warn!("Unauthorized access: {}", msg);
                 (StatusCode::UNAUTHORIZED, "Unauthorized".to_string())
             }
-            AppError::WithContext { source, context } => {
-                // Log full context chain for debugging
-                tracing::error!("Error with context: {} -> {}", context, source);
-                // Delegate to underlying error's response
-                return source.clone().into_response();
+            AppError::Forbidden(msg) => {
+                tracing::warn!("Forbidden: {}", msg);
+                (StatusCode::FORBIDDEN, msg.clone())
+            }
+            AppError::ProviderUnavailable(msg) => {
+                tracing::warn!("Provider unavailable: {}", msg);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Service temporarily unavailable".to_string(),
+                )
+            }
+            AppError::ConsentRequired(msg) => {
+                tracing::info!("Consent required: {}", msg);
+                let body = Json(json!({
+                    "error": msg,
+                    "code": "consent_required",
+                }));
+                return (StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, body).into_response();
+            }
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
+            AppError::InvalidDocument(msg) => {
+                let body = Json(json!({
+                    "error": msg,
+                    "code": "invalid_document",
+                }));
+                return (StatusCode::BAD_REQUEST, body).into_response();
+            }
+            AppError::DeadlineExceeded(msg) => {
+                tracing::warn!("Deadline exceeded: {}", msg);
+                let body = Json(json!({
+                    "error": msg,
+                    "code": "deadline_exceeded",
+                }));
+                return (StatusCode::GATEWAY_TIMEOUT, body).into_response();
+            }
+            AppError::WithContext { .. } => {
+                // Log full context chain for debugging (client errors as warnings)
+                let chain = self.chain();
+                match self.root() {
+                    AppError::DatabaseError(_)
+                    | AppError::ExternalApiError(_)
+                    | AppError::InternalError(_) => tracing::error!(
+                        error_chain = ?chain,
+                        "Error with context: {}",
+                        chain.join(CHAIN_SEPARATOR)
+                    ),
+                    _ => tracing::warn!(
+                        error_chain = ?chain,
+                        "Error with context: {}",
+                        chain.join(CHAIN_SEPARATOR)
+                    ),
+                }
+                // Delegate to the root error's response
+                return self.into_root().into_response();
             }
         };
 
@@ -93,6 +160,12 @@ impl Clone for AppError {
             AppError::ExternalApiError(msg) => AppError::ExternalApiError(msg.clone()),
             AppError::InternalError(msg) => AppError::InternalError(msg.clone()),
             AppError::Unauthorized(msg) => AppError::Unauthorized(msg.clone()),
+            AppError::Forbidden(msg) => AppError::Forbidden(msg.clone()),
+            AppError::ProviderUnavailable(msg) => AppError::ProviderUnavailable(msg.clone()),
+            AppError::ConsentRequired(msg) => AppError::ConsentRequired(msg.clone()),
+            AppError::Conflict(msg) => AppError::Conflict(msg.clone()),
+            AppError::InvalidDocument(msg) => AppError::InvalidDocument(msg.clone()),
+            AppError::DeadlineExceeded(msg) => AppError::DeadlineExceeded(msg.clone()),
             AppError::WithContext { source, context } => AppError::WithContext {
                 source: source.clone(),
                 context: context.clone(),
@@ -101,6 +174,132 @@ impl Clone for AppError {
     }
 }
 
+impl AppError {
+    /// Whether this error (or the error it wraps) is a provider outage
+    ///
+    /// Callers use this to degrade or defer instead of failing outright.
+    pub fn is_provider_unavailable(&self) -> bool {
+        match self {
+            AppError::ProviderUnavailable(_) => true,
+            AppError::WithContext { source, .. } => source.is_provider_unavailable(),
+            _ => false,
+        }
+    }
+
+    /// Whether this error (or the error it wraps) is a rejected CPF/CNPJ
+    ///
+    /// Retrying cannot help: the document itself is wrong.
+    pub fn is_invalid_document(&self) -> bool {
+        matches!(self.root(), AppError::InvalidDocument(_))
+    }
+
+    /// The error at the bottom of the context chain
+    pub fn root(&self) -> &AppError {
+        match self {
+            AppError::WithContext { source, .. } => source.root(),
+            root => root,
+        }
+    }
+
+    fn into_root(self) -> AppError {
+        match self {
+            AppError::WithContext { source, .. } => source.into_root(),
+            root => root,
+        }
+    }
+
+    /// Context chain as one line (`context -> context -> root error`), as
+    /// stored in the error columns shown by the admin views
+    pub fn chain_message(&self) -> String {
+        self.chain().join(CHAIN_SEPARATOR)
+    }
+
+    /// Messages of the context chain, outermost context first, root error last
+    pub fn chain(&self) -> Vec<String> {
+        match self {
+            AppError::WithContext { source, context } => {
+                let mut chain = vec![context.clone()];
+                chain.extend(source.chain());
+                chain
+            }
+            root => vec![root.to_string()],
+        }
+    }
+}
+
+/// Separator of the context chain in logs and stored error messages
+pub const CHAIN_SEPARATOR: &str = " -> ";
+
+/// Split a stored error message back into its context chain
+pub fn split_chain(message: &str) -> Vec<String> {
+    message
+        .split(CHAIN_SEPARATOR)
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Context of a failed external call or SQL statement: operation name,
+/// provider and the identifiers involved
+///
+/// `ErrorContext::new("c2s.fetch_lead").provider("c2s").id("lead_id", "abc")`
+/// renders as `c2s.fetch_lead [provider=c2s lead_id=abc]`. Operation names
+/// follow the `TimedQuery` labels (`table.action`, `provider.call`).
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    operation: &'static str,
+    provider: Option<&'static str>,
+    ids: Vec<(&'static str, String)>,
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            provider: None,
+            ids: Vec::new(),
+        }
+    }
+
+    /// SQL statement (provider `postgres`)
+    pub fn db(operation: &'static str) -> Self {
+        Self::new(operation).provider("postgres")
+    }
+
+    pub fn provider(mut self, provider: &'static str) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    pub fn id(mut self, name: &'static str, value: impl fmt::Display) -> Self {
+        self.ids.push((name, value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if self.provider.is_none() && self.ids.is_empty() {
+            return Ok(());
+        }
+        let fields: Vec<String> = self
+            .provider
+            .map(|p| format!("provider={}", p))
+            .into_iter()
+            .chain(self.ids.iter().map(|(k, v)| format!("{}={}", k, v)))
+            .collect();
+        write!(f, " [{}]", fields.join(" "))
+    }
+}
+
+impl From<ErrorContext> for String {
+    fn from(context: ErrorContext) -> Self {
+        context.to_string()
+    }
+}
+
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
         AppError::DatabaseError(err)
@@ -164,3 +363,17 @@ impl<T> ResultExt<T> for Result<T, sqlx::Error> {
         })
     }
 }
+
+/// Extension for reqwest::Error (calls made outside the provider clients)
+impl<T> ResultExt<T> for Result<T, reqwest::Error> {
+    fn context(self, context: impl Into<String>) -> Result<T, AppError> {
+        self.map_err(AppError::from).context(context)
+    }
+
+    fn with_context<F>(self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> String,
+    {
+        self.map_err(AppError::from).with_context(f)
+    }
+}
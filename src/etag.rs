@@ -0,0 +1,103 @@
+//! ETag / If-None-Match for the read endpoints
+//!
+//! Customer lookups and Work API module fetches are polled repeatedly by the
+//! dashboard and partner scripts while the data rarely changes. This
+//! middleware hashes successful GET response bodies into a weak ETag (weak:
+//! the same entity is also served gzip/br compressed) and answers
+//! `304 Not Modified` with no body when the request's `If-None-Match`
+//! already holds it. The handler still runs; only the transfer is saved.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Larger bodies are passed through without an ETag
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Weak ETag of a response body (first 16 bytes of its SHA-256)
+pub fn etag_for(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether `If-None-Match` matches `etag` (weak comparison, `*` included)
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// Middleware adding an ETag to successful GET responses, 304 when unchanged
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let request_headers = request.headers().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to buffer response body for ETag: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = etag_for(&bytes);
+    let Ok(value) = HeaderValue::from_str(&etag) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if if_none_match(&request_headers, &etag) {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        not_modified.headers_mut().insert(header::ETAG, value);
+        return not_modified;
+    }
+    parts.headers.insert(header::ETAG, value);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_is_stable_and_weak() {
+        let etag = etag_for(br#"{"id":1}"#);
+        assert!(etag.starts_with("W/\""));
+        assert_eq!(etag, etag_for(br#"{"id":1}"#));
+        assert_ne!(etag, etag_for(br#"{"id":2}"#));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = etag_for(b"body");
+        let mut headers = HeaderMap::new();
+        assert!(!if_none_match(&headers, &etag));
+
+        let strong = etag.trim_start_matches("W/").to_string();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("W/\"other\", {}", strong)).unwrap(),
+        );
+        assert!(if_none_match(&headers, &etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(if_none_match(&headers, &etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!if_none_match(&headers, &etag));
+    }
+}
@@ -1,69 +1,39 @@
+//! Compatibility shim for the former C2S "gateway" client
+//!
+//! `C2sGatewayClient` duplicated `services::C2SService` and had drifted from
+//! it (`create_lead` without the product argument, `send_message` accepting
+//! any 2xx). Both now go through `C2SService`; this wrapper keeps the old
+//! constructor and signatures for code that still uses them.
+#![allow(deprecated)]
+
 use crate::errors::AppError;
-use reqwest;
-use serde_json::json;
-use std::time::Duration;
-use tracing;
+use crate::services::C2SService;
 
-/// Client for interacting directly with the C2S API
-/// Formerly communicated via a Python Gateway, now direct.
+/// Former C2S client, now a thin wrapper over `C2SService`
+#[deprecated(note = "use services::C2SService (AppState::c2s)")]
 #[derive(Clone)]
 pub struct C2sGatewayClient {
-    client: reqwest::Client,
-    base_url: String,
-    token: String,
+    inner: C2SService,
 }
 
 impl C2sGatewayClient {
-    #[allow(dead_code)]
     pub fn new(base_url: String, token: String) -> Result<Self, AppError> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .map_err(|e| {
-                AppError::ExternalApiError(format!("Failed to create C2S client: {}", e))
-            })?;
-
         Ok(Self {
-            client,
-            base_url,
-            token,
+            inner: C2SService::with_credentials(base_url, token),
         })
     }
 
-    /// Get lead from C2S
-    pub async fn get_lead(&self, lead_id: &str) -> Result<serde_json::Value, AppError> {
-        let url = format!("{}/integration/leads/{}", self.base_url, lead_id);
-        tracing::info!("Fetching lead {} from C2S: {}", lead_id, url);
-
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalApiError(format!("C2S request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S returned {}: {}",
-                status, error_text
-            )));
-        }
-
-        let data = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse C2S response: {}", e))
-        })?;
+    /// The consolidated client
+    pub fn service(&self) -> &C2SService {
+        &self.inner
+    }
 
-        Ok(data)
+    /// Get lead from C2S (untyped)
+    pub async fn get_lead(&self, lead_id: &str) -> Result<serde_json::Value, AppError> {
+        self.inner.fetch_lead_json(lead_id).await
     }
 
-    /// Create new lead in C2S
-    #[allow(dead_code)]
+    /// Create new lead in C2S (without a product)
     pub async fn create_lead(
         &self,
         customer_name: &str,
@@ -73,130 +43,42 @@ impl C2sGatewayClient {
         source: Option<&str>,
         seller_id: Option<&str>,
     ) -> Result<String, AppError> {
-        let url = format!("{}/integration/leads", self.base_url);
-        tracing::info!("Creating new lead in C2S: {}", customer_name);
-
-        // Build attributes object
-        let mut attributes = serde_json::Map::new();
-        attributes.insert("name".to_string(), json!(customer_name));
-        attributes.insert("description".to_string(), json!(description));
-        attributes.insert("type_negotiation".to_string(), json!("Compra"));
-        attributes.insert("source".to_string(), json!(source.unwrap_or("Google Ads")));
-
-        if let Some(phone_val) = phone {
-            attributes.insert("phone".to_string(), json!(phone_val));
-        }
-        if let Some(email_val) = email {
-            attributes.insert("email".to_string(), json!(email_val));
-        }
-        if let Some(seller_val) = seller_id {
-            attributes.insert("seller_id".to_string(), json!(seller_val));
-        }
-
-        // Use JSON:API format (C2S requirement)
-        let body = json!({
-            "data": {
-                "type": "lead",
-                "attributes": attributes
-            }
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&body)
-            .send()
+        self.inner
+            .create_lead(
+                customer_name,
+                phone,
+                email,
+                description,
+                source,
+                None,
+                seller_id,
+            )
             .await
-            .map_err(|e| AppError::ExternalApiError(format!("Failed to create lead: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S lead creation failed {}: {}",
-                status, error_text
-            )));
-        }
-
-        let response_data: serde_json::Value = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse lead creation response: {}", e))
-        })?;
-
-        // Try to get ID from different possible locations in response
-        let lead_id = if let Some(id) = response_data
-            .get("data")
-            .and_then(|d| d.get("id"))
-            .and_then(|i| i.as_str())
-        {
-            id.to_string()
-        } else if let Some(id) = response_data.get("id").and_then(|i| i.as_str()) {
-            id.to_string()
-        } else if let Some(id) = response_data.get("lead_id").and_then(|i| i.as_str()) {
-            id.to_string()
-        } else {
-            // Fallback: check if it's a number and convert to string
-            if let Some(id) = response_data
-                .get("data")
-                .and_then(|d| d.get("id"))
-                .and_then(|i| i.as_i64())
-            {
-                id.to_string()
-            } else if let Some(id) = response_data.get("id").and_then(|i| i.as_i64()) {
-                id.to_string()
-            } else {
-                tracing::warn!("Unexpected C2S response format: {:?}", response_data);
-                return Err(AppError::ExternalApiError(
-                    "Lead creation response missing 'id' field".to_string(),
-                ));
-            }
-        };
-
-        tracing::info!("✓ Lead created successfully: {}", lead_id);
-        Ok(lead_id)
     }
 
-    /// Send message to lead in C2S
-    pub async fn send_message(&self, lead_id: &str, message: &str) -> Result<(), AppError> {
-        let url = format!(
-            "{}/integration/leads/{}/create_message",
-            self.base_url, lead_id
-        );
-        tracing::info!("Sending message to lead {} in C2S", lead_id);
-
-        // C2S expects { "leadId": "...", "body": "..." }
-        let body = json!({
-            "leadId": lead_id,
-            "body": message
-        });
+    /// Update custom fields of a lead in C2S
+    pub async fn update_custom_fields(
+        &self,
+        lead_id: &str,
+        fields: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), AppError> {
+        self.inner.update_custom_fields(lead_id, fields).await
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalApiError(format!("Failed to send message: {}", e)))?;
+    /// Send message to lead in C2S, returns the HTTP status of the accepted message
+    pub async fn send_message(&self, lead_id: &str, message: &str) -> Result<u16, AppError> {
+        self.inner.send_message(lead_id, message).await
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S message send failed {}: {}",
-                status, error_text
-            )));
-        }
+    /// List the sellers of the account
+    pub async fn list_sellers(&self) -> Result<Vec<crate::c2s_sellers::C2SSeller>, AppError> {
+        self.inner.list_sellers().await
+    }
+}
 
-        tracing::info!("✓ Message sent successfully to lead {}", lead_id);
-        Ok(())
+impl From<C2SService> for C2sGatewayClient {
+    fn from(inner: C2SService) -> Self {
+        Self { inner }
     }
 }
 
@@ -0,0 +1,288 @@
+//! Per-form configuration of Google Ads lead forms
+//!
+//! Each lead form can have a row in `google_ads_forms` (migration 036),
+//! edited through the admin API, so onboarding a form needs no deploy:
+//!
+//! - `field_mapping`: custom question (column id or question text) → the
+//!   standard column it holds, e.g. `{"QUESTION_2": "CPF", "Seu WhatsApp":
+//!   "PHONE_NUMBER"}`; answers are then read like the standard columns
+//! - `seller_id`: seller receiving the leads (default `C2S_DEFAULT_SELLER_ID`)
+//! - `product`: C2S product (default: resolved from the ad group)
+//! - `description_template`: header prepended to the lead description, with
+//!   `{{customer_name}}`, `{{form_name}}`, `{{form_id}}`, `{{campaign_id}}`
+//!   and `{{product}}` placeholders
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::google_ads_models::{GoogleAdsColumn, GoogleAdsWebhookPayload};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeMap;
+
+/// Mapping targets besides the standard columns
+const EXTRA_TARGETS: &[&str] = &["CPF", "CONSENT"];
+
+/// Variables of `description_template`
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "customer_name",
+    "form_name",
+    "form_id",
+    "campaign_id",
+    "product",
+];
+
+/// Stored configuration of a lead form
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct GoogleAdsForm {
+    pub form_id: i64,
+    pub name: Option<String>,
+    pub field_mapping: Json<BTreeMap<String, String>>,
+    pub seller_id: Option<String>,
+    pub product: Option<String>,
+    pub description_template: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of a form configuration edit (replaces the stored one)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GoogleAdsFormUpdate {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub field_mapping: BTreeMap<String, String>,
+    pub seller_id: Option<String>,
+    pub product: Option<String>,
+    pub description_template: Option<String>,
+}
+
+impl GoogleAdsFormUpdate {
+    /// Reject mappings to unknown columns and templates with unknown variables
+    pub fn validate(&self) -> Result<(), AppError> {
+        for (source, target) in &self.field_mapping {
+            let target = target.trim().to_uppercase();
+            if GoogleAdsColumn::from_column_id(&target) == GoogleAdsColumn::Custom
+                && !EXTRA_TARGETS.contains(&target.as_str())
+            {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown target column {} for {}",
+                    target, source
+                )));
+            }
+        }
+        if let Some(template) = &self.description_template {
+            if let Some(unknown) = crate::templates::placeholders(template)
+                .into_iter()
+                .find(|var| !TEMPLATE_VARIABLES.contains(var))
+            {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown variable {{{{{}}}}} in description template (allowed: {})",
+                    unknown,
+                    TEMPLATE_VARIABLES.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GoogleAdsForm {
+    /// Rewrite mapped answers to their standard column id
+    pub fn apply_mapping(&self, payload: &mut GoogleAdsWebhookPayload) {
+        for field in &mut payload.user_column_data {
+            let target = self
+                .field_mapping
+                .get(&field.column_id)
+                .or_else(|| self.field_mapping.get(field.column_name.trim()));
+            if let Some(target) = target {
+                field.column_id = target.trim().to_uppercase();
+            }
+        }
+    }
+
+    /// Description header for a lead of this form, if configured
+    pub fn render_header(
+        &self,
+        payload: &GoogleAdsWebhookPayload,
+        customer_name: &str,
+        product: Option<&str>,
+    ) -> Option<String> {
+        let template = self.description_template.as_deref()?;
+        let form_id = payload.form_id.to_string();
+        let campaign_id = payload.campaign_id.to_string();
+        Some(crate::templates::substitute(
+            template,
+            &[
+                ("customer_name", customer_name),
+                ("form_name", self.name.as_deref().unwrap_or("")),
+                ("form_id", &form_id),
+                ("campaign_id", &campaign_id),
+                ("product", product.unwrap_or("")),
+            ],
+        ))
+    }
+}
+
+/// Configuration of a form, if any
+pub async fn find(pool: &PgPool, form_id: i64) -> Result<Option<GoogleAdsForm>, AppError> {
+    let form = sqlx::query_as::<_, GoogleAdsForm>(
+        r#"
+        SELECT form_id, name, field_mapping, seller_id, product, description_template, updated_at
+        FROM google_ads_forms
+        WHERE form_id = $1
+        "#,
+    )
+    .bind(form_id)
+    .fetch_optional(pool)
+    .timed("google_ads_forms.find")
+    .await
+    .context(format!("Failed to load Google Ads form {}", form_id))?;
+    Ok(form)
+}
+
+/// Every configured form
+pub async fn list(pool: &PgPool) -> Result<Vec<GoogleAdsForm>, AppError> {
+    let forms = sqlx::query_as::<_, GoogleAdsForm>(
+        r#"
+        SELECT form_id, name, field_mapping, seller_id, product, description_template, updated_at
+        FROM google_ads_forms
+        ORDER BY form_id
+        "#,
+    )
+    .fetch_all(pool)
+    .timed("google_ads_forms.list")
+    .await
+    .context("Failed to load Google Ads forms")?;
+    Ok(forms)
+}
+
+/// Create or replace the configuration of a form
+pub async fn save(
+    pool: &PgPool,
+    form_id: i64,
+    update: &GoogleAdsFormUpdate,
+) -> Result<GoogleAdsForm, AppError> {
+    update.validate()?;
+
+    let form = sqlx::query_as::<_, GoogleAdsForm>(
+        r#"
+        INSERT INTO google_ads_forms (form_id, name, field_mapping, seller_id, product, description_template)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (form_id) DO UPDATE
+        SET name = EXCLUDED.name,
+            field_mapping = EXCLUDED.field_mapping,
+            seller_id = EXCLUDED.seller_id,
+            product = EXCLUDED.product,
+            description_template = EXCLUDED.description_template,
+            updated_at = NOW()
+        RETURNING form_id, name, field_mapping, seller_id, product, description_template, updated_at
+        "#,
+    )
+    .bind(form_id)
+    .bind(&update.name)
+    .bind(Json(&update.field_mapping))
+    .bind(&update.seller_id)
+    .bind(&update.product)
+    .bind(&update.description_template)
+    .fetch_one(pool)
+    .timed("google_ads_forms.upsert")
+    .await
+    .context(format!("Failed to save Google Ads form {}", form_id))?;
+    Ok(form)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google_ads_models::UserColumnData;
+
+    fn form(mapping: &[(&str, &str)], template: Option<&str>) -> GoogleAdsForm {
+        GoogleAdsForm {
+            form_id: 123,
+            name: Some("Lançamento Moema".to_string()),
+            field_mapping: Json(
+                mapping
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            seller_id: None,
+            product: None,
+            description_template: template.map(str::to_string),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn payload() -> GoogleAdsWebhookPayload {
+        let field = |column_id: &str, column_name: &str, value: &str| UserColumnData {
+            column_id: column_id.to_string(),
+            column_name: column_name.to_string(),
+            string_value: value.to_string(),
+        };
+        GoogleAdsWebhookPayload {
+            lead_id: "lead-1".to_string(),
+            api_version: "1.0".to_string(),
+            form_id: 123,
+            campaign_id: 456,
+            adgroup_id: None,
+            creative_id: None,
+            gcl_id: None,
+            google_key: "key".to_string(),
+            is_test: false,
+            user_column_data: vec![
+                field("FULL_NAME", "Nome", "Ana"),
+                field("QUESTION_1", "Número do documento", "123.456.789-01"),
+                field("QUESTION_2", "Seu WhatsApp", "11987654321"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_apply_mapping_by_id_and_question() {
+        let mut payload = payload();
+        assert_eq!(payload.get_cpf(), None);
+
+        form(
+            &[("QUESTION_1", "cpf"), ("Seu WhatsApp", "PHONE_NUMBER")],
+            None,
+        )
+        .apply_mapping(&mut payload);
+
+        assert_eq!(payload.get_cpf().as_deref(), Some("12345678901"));
+        assert_eq!(payload.get_phone().as_deref(), Some("11987654321"));
+        assert_eq!(payload.get_name().as_deref(), Some("Ana"));
+    }
+
+    #[test]
+    fn test_render_header() {
+        let header = form(
+            &[],
+            Some("🏢 {{form_name}} ({{product}}) - {{customer_name}}"),
+        )
+        .render_header(&payload(), "Ana", Some("Moema 2Q"));
+        assert_eq!(
+            header.as_deref(),
+            Some("🏢 Lançamento Moema (Moema 2Q) - Ana")
+        );
+        assert_eq!(form(&[], None).render_header(&payload(), "Ana", None), None);
+    }
+
+    #[test]
+    fn test_validate_update() {
+        let mut update = GoogleAdsFormUpdate {
+            field_mapping: BTreeMap::from([("QUESTION_1".to_string(), "CPF".to_string())]),
+            description_template: Some("{{customer_name}}".to_string()),
+            ..Default::default()
+        };
+        assert!(update.validate().is_ok());
+
+        update
+            .field_mapping
+            .insert("QUESTION_2".to_string(), "SHOE_SIZE".to_string());
+        assert!(update.validate().is_err());
+
+        update.field_mapping.remove("QUESTION_2");
+        update.description_template = Some("{{budget}}".to_string());
+        assert!(update.validate().is_err());
+    }
+}
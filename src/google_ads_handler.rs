@@ -8,11 +8,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
 use crate::{
+    c2s_accounts,
     config::Config,
+    consent::{self, SubjectKind},
+    db::TimedQuery,
     enrichment::{is_valid_email, validate_br_phone},
-    errors::AppError,
+    errors::{AppError, ErrorContext, ResultExt},
+    google_ads_forms,
     google_ads_models::GoogleAdsWebhookPayload,
-    services::{self, WorkApiService},
+    lead_dedup,
 };
 
 /// Query parameters for Google Ads webhook verification
@@ -35,19 +39,22 @@ pub struct GoogleAdsWebhookResponse {
 ///
 /// Flow:
 /// 1. Validate google_key (mandatory)
-/// 2. Check deduplication (google_ads_leads.google_lead_id unique constraint)
-/// 3. Extract contact info (name, phone, email)
-/// 4. Validate and normalize phone/email
+/// 2. Check deduplication (google_ads_leads.google_lead_id unique constraint);
+///    test deliveries (is_test) are stored and acknowledged without enrichment
+///    or C2S lead creation
+/// 3. Apply the form's configuration (google_ads_forms) and extract contact info
+/// 4. Validate and normalize phone/email, record the form's consent answer
 /// 5. Inline enrichment: Diretrix → Work API (if possible)
 /// 6. Format complete description (Google Ads context + enrichment)
-/// 7. Create lead in C2S via gateway (single API call)
+/// 7. Create lead in C2S via gateway (single API call), or append it to an
+///    open lead of the same contact (`lead_dedup`, per tenant)
 /// 8. Store tracking record in database
 ///
 /// Fallback: If enrichment fails, still create lead with warning
 pub async fn google_ads_webhook_handler(
     State(app_state): State<std::sync::Arc<crate::handlers::AppState>>,
     Query(query): Query<GoogleAdsWebhookQuery>,
-    Json(payload): Json<GoogleAdsWebhookPayload>,
+    Json(mut payload): Json<GoogleAdsWebhookPayload>,
 ) -> Result<impl IntoResponse, AppError> {
     tracing::info!(
         "📨 Received Google Ads webhook: lead_id={}, campaign={}",
@@ -76,7 +83,163 @@ pub async fn google_ads_webhook_handler(
         ));
     }
 
-    // Step 3: Extract contact info
+    if payload.is_test {
+        tracing::info!(
+            "🧪 Google Ads test lead {} (form {}), not sent to C2S",
+            payload.lead_id,
+            payload.form_id
+        );
+        store_google_ads_lead(&app_state.db, &payload, None, "test", 0, None).await?;
+        return Ok((
+            StatusCode::OK,
+            Json(GoogleAdsWebhookResponse {
+                success: true,
+                message: "Test lead received (not sent to C2S)".to_string(),
+                lead_id: Some(payload.lead_id.clone()),
+                c2s_lead_id: None,
+            }),
+        ));
+    }
+
+    let processed = process_lead(&app_state, &mut payload, true).await?;
+
+    // Step 8: Store tracking record
+    store_google_ads_lead(
+        &app_state.db,
+        &payload,
+        Some(&processed.c2s_lead_id),
+        processed.enrichment_status,
+        processed.description_length,
+        Some(processed.latency_ms),
+    )
+    .await?;
+
+    let (status, message) = if processed.appended {
+        (
+            StatusCode::OK,
+            "Lead appended to an open C2S lead of the same contact",
+        )
+    } else {
+        (
+            StatusCode::CREATED,
+            "Lead created and enriched successfully",
+        )
+    };
+    Ok((
+        status,
+        Json(GoogleAdsWebhookResponse {
+            success: true,
+            message: message.to_string(),
+            lead_id: Some(payload.lead_id.clone()),
+            c2s_lead_id: Some(processed.c2s_lead_id),
+        }),
+    ))
+}
+
+/// Re-run the processing of a stored Google Ads lead from its `payload_raw`
+///
+/// Used after fixing a form mapping or configuration. Safeguards against
+/// duplicate C2S leads: a lead that already has a C2S lead is refused with
+/// `AppError::Conflict` unless `force` is set, and concurrent replays of the
+/// same lead are rejected. Test and anonymized leads are never replayed.
+pub async fn replay_google_ads_lead(
+    state: &std::sync::Arc<crate::handlers::AppState>,
+    google_lead_id: &str,
+    force: bool,
+) -> Result<GoogleAdsWebhookResponse, AppError> {
+    let stored = fetch_stored_lead(&state.db, google_lead_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Google Ads lead {} not found", google_lead_id))
+        })?;
+
+    if stored.is_test {
+        return Err(AppError::BadRequest(
+            "Test leads are not sent to C2S".to_string(),
+        ));
+    }
+    if stored.anonymized_at.is_some() {
+        return Err(AppError::BadRequest(
+            "Lead was anonymized, its payload can no longer be replayed".to_string(),
+        ));
+    }
+    if let Some(existing) = &stored.c2s_lead_id {
+        if !force {
+            return Err(AppError::Conflict(format!(
+                "Lead already created in C2S as {} (use force=true to create a new one)",
+                existing
+            )));
+        }
+    }
+
+    let mut payload: GoogleAdsWebhookPayload = serde_json::from_value(stored.payload_raw)
+        .map_err(|e| AppError::InternalError(format!("Invalid stored payload: {}", e)))?;
+
+    if !claim_replay(&state.db, google_lead_id, stored.c2s_lead_id.as_deref()).await? {
+        return Err(AppError::Conflict(format!(
+            "Lead {} changed or is already being replayed",
+            google_lead_id
+        )));
+    }
+
+    // A forced replay asks for a new lead: never append it to the previous one
+    let processed = match process_lead(state, &mut payload, stored.c2s_lead_id.is_none()).await {
+        Ok(processed) => processed,
+        Err(e) => {
+            release_replay(&state.db, google_lead_id).await;
+            return Err(e);
+        }
+    };
+
+    record_replay(&state.db, &payload, &processed).await?;
+    tracing::info!(
+        "🔁 Google Ads lead {} replayed: C2S lead {} (previous: {})",
+        google_lead_id,
+        processed.c2s_lead_id,
+        stored.c2s_lead_id.as_deref().unwrap_or("none")
+    );
+
+    Ok(GoogleAdsWebhookResponse {
+        success: true,
+        message: "Lead replayed successfully".to_string(),
+        lead_id: Some(payload.lead_id.clone()),
+        c2s_lead_id: Some(processed.c2s_lead_id),
+    })
+}
+
+/// Outcome of sending a lead to C2S
+struct ProcessedLead {
+    c2s_lead_id: String,
+    /// Posted on an open lead of the same contact instead of a new lead
+    appended: bool,
+    enrichment_status: &'static str,
+    description_length: i32,
+    latency_ms: i32,
+}
+
+/// Steps 3-7: form configuration, validation, enrichment and C2S lead creation
+///
+/// Shared by the webhook and the admin replay; the form mapping is applied
+/// to `payload` in place, so the stored payload reflects it. With
+/// `allow_append`, a lead of a contact that already has an open lead is
+/// appended to it (see `lead_dedup`).
+async fn process_lead(
+    state: &std::sync::Arc<crate::handlers::AppState>,
+    payload: &mut GoogleAdsWebhookPayload,
+    allow_append: bool,
+) -> Result<ProcessedLead, AppError> {
+    // Step 3: Apply the form configuration, then extract contact info
+    let form = match google_ads_forms::find(&state.db, payload.form_id).await {
+        Ok(form) => form,
+        Err(e) => {
+            tracing::warn!("⚠️  Using default form settings: {}", e);
+            None
+        }
+    };
+    if let Some(form) = &form {
+        form.apply_mapping(payload);
+    }
+
     let customer_name = payload
         .get_name()
         .ok_or_else(|| AppError::BadRequest("Missing customer name in form data".to_string()))?;
@@ -105,9 +268,24 @@ pub async fn google_ads_webhook_handler(
         }
     });
 
+    if let Some(granted) = payload.get_consent() {
+        record_form_consent(
+            &state.db,
+            granted,
+            &[
+                (SubjectKind::Cpf, cpf_from_form.as_deref()),
+                (SubjectKind::Email, email_validated.as_deref()),
+                (SubjectKind::Phone, phone_validated.as_deref()),
+            ],
+        )
+        .await;
+    }
+
     // Step 5: Inline enrichment (Diretrix → Work API)
     let enrichment_result = perform_inline_enrichment(
-        &app_state,
+        state,
+        Some("Google Ads"),
+        Some(customer_name.as_str()),
         cpf_from_form.as_deref(),
         phone_validated.as_deref(),
         email_validated.as_deref(),
@@ -125,8 +303,50 @@ pub async fn google_ads_webhook_handler(
 
     let description = payload.format_description(enrichment_text);
 
-    // Truncate description if needed (UTF-8 safe)
-    let max_desc_len = app_state.config.c2s_description_max_length;
+    // Step 7: Resolve lead source to get ad group name for product field
+    let start = std::time::Instant::now();
+
+    let campaign_id = payload.campaign_id.to_string();
+    let form_id = payload.form_id.to_string();
+    let account = c2s_accounts::select_account(
+        &state.config.c2s_account_rules,
+        &c2s_accounts::LeadOrigin {
+            source: Some("Google Ads"),
+            campaign_id: Some(&campaign_id),
+            form_id: Some(&form_id),
+        },
+    );
+    let c2s_service = c2s_accounts::service_for(state, account);
+
+    // Product configured for the form, else the ad group name from Google Ads via gateway
+    let configured_product = form.as_ref().and_then(|f| f.product.clone());
+    let product = match configured_product {
+        Some(product) => Some(product),
+        None => match c2s_service.resolve_lead_source(&payload.lead_id).await {
+            Ok(Some(product_name)) => {
+                tracing::info!("✅ Resolved product from ad group: {}", product_name);
+                Some(product_name)
+            }
+            Ok(None) => {
+                tracing::warn!("⚠️  Could not resolve product from Google Ads lead");
+                None
+            }
+            Err(e) => {
+                tracing::warn!("⚠️  Error resolving product: {}", e);
+                None
+            }
+        },
+    };
+
+    // Form header (if configured), then truncate if needed (UTF-8 safe)
+    let description = match form
+        .as_ref()
+        .and_then(|f| f.render_header(payload, &customer_name, product.as_deref()))
+    {
+        Some(header) => format!("{}\n\n{}", header, description),
+        None => description,
+    };
+    let max_desc_len = state.config.c2s_description_max_length;
     let description_final = if description.chars().count() > max_desc_len {
         let truncated: String = description.chars().take(max_desc_len).collect();
         tracing::warn!(
@@ -138,29 +358,54 @@ pub async fn google_ads_webhook_handler(
     } else {
         description.clone()
     };
-
-    // Step 7: Resolve lead source to get ad group name for product field
-    let start = std::time::Instant::now();
-
-    let c2s_service = services::C2SService::new(&app_state.config);
-
-    // Try to resolve the ad group name from Google Ads via gateway
-    let product = match c2s_service.resolve_lead_source(&payload.lead_id).await {
-        Ok(Some(product_name)) => {
-            tracing::info!("✅ Resolved product from ad group: {}", product_name);
-            Some(product_name)
-        }
-        Ok(None) => {
-            tracing::warn!("⚠️  Could not resolve product from Google Ads lead");
-            None
-        }
-        Err(e) => {
-            tracing::warn!("⚠️  Error resolving product: {}", e);
-            None
+    let seller_id = form
+        .as_ref()
+        .and_then(|f| f.seller_id.as_deref())
+        .or(state.config.c2s_default_seller_id.as_deref());
+
+    // Step 8: Append to an open lead of the same contact (LEAD_DEDUP_TENANTS),
+    // else create the lead in C2S directly (using JSON:API format)
+    if allow_append && lead_dedup::enabled_for(&state.config, account) {
+        if let Some(existing) = lead_dedup::find_open_lead(
+            &state.db,
+            &state.config,
+            c2s_service.as_ref(),
+            account,
+            phone_validated.as_deref(),
+            email_validated.as_deref(),
+        )
+        .await
+        {
+            match append_to_lead(state, &existing, account, &description_final).await {
+                Ok(()) => {
+                    let latency_ms = start.elapsed().as_millis() as i32;
+                    tracing::info!(
+                        "🔁 Google Ads lead {} appended to open C2S lead {} ({}ms)",
+                        payload.lead_id,
+                        existing,
+                        latency_ms
+                    );
+                    return Ok(ProcessedLead {
+                        c2s_lead_id: existing,
+                        appended: true,
+                        enrichment_status: if enrichment_result.is_ok() {
+                            "completed"
+                        } else {
+                            "partial"
+                        },
+                        description_length: description_final.len() as i32,
+                        latency_ms,
+                    });
+                }
+                Err(e) => tracing::warn!(
+                    "⚠️  Could not append to C2S lead {}, creating a new lead: {}",
+                    existing,
+                    e
+                ),
+            }
         }
-    };
+    }
 
-    // Step 8: Create lead in C2S directly (using JSON:API format)
     let c2s_lead_id = c2s_service
         .create_lead(
             &customer_name,
@@ -169,32 +414,58 @@ pub async fn google_ads_webhook_handler(
             &description_final,
             Some("Google Ads"),
             product.as_deref(),
-            app_state.config.c2s_default_seller_id.as_deref(),
+            seller_id,
         )
-        .await?;
-
+        .await
+        .context(
+            ErrorContext::new("c2s.create_lead")
+                .provider("c2s")
+                .id("google_lead_id", &payload.lead_id)
+                .id("form_id", payload.form_id),
+        )?;
+
+    let latency_ms = start.elapsed().as_millis() as i32;
     tracing::info!("✅ Lead created in C2S: {} ({}ms)", c2s_lead_id, latency_ms);
-
-    // Step 8: Store tracking record
-    store_google_ads_lead(
-        &app_state.db,
-        &payload,
+    if let Err(e) = c2s_accounts::record_lead_account(&state.db, &c2s_lead_id, account).await {
+        tracing::warn!("{}", e);
+    }
+    if let Err(e) = lead_dedup::record_contacts(
+        &state.db,
         &c2s_lead_id,
-        enrichment_result.is_ok(),
-        description_final.len() as i32,
-        latency_ms,
+        account,
+        phone_validated.as_deref(),
+        email_validated.as_deref(),
     )
-    .await?;
+    .await
+    {
+        tracing::warn!("{}", e);
+    }
 
-    Ok((
-        StatusCode::CREATED,
-        Json(GoogleAdsWebhookResponse {
-            success: true,
-            message: "Lead created and enriched successfully".to_string(),
-            lead_id: Some(payload.lead_id.clone()),
-            c2s_lead_id: Some(c2s_lead_id.clone()),
-        }),
-    ))
+    Ok(ProcessedLead {
+        c2s_lead_id,
+        appended: false,
+        enrichment_status: if enrichment_result.is_ok() {
+            "completed"
+        } else {
+            "partial"
+        },
+        description_length: description_final.len() as i32,
+        latency_ms,
+    })
+}
+
+/// Post the description of a repeat contact on its open lead
+async fn append_to_lead(
+    state: &crate::handlers::AppState,
+    c2s_lead_id: &str,
+    account: &str,
+    description: &str,
+) -> Result<(), AppError> {
+    // Leads found in C2S may not be recorded yet: route messages to their account
+    c2s_accounts::record_lead_account(&state.db, c2s_lead_id, account).await?;
+    let message = lead_dedup::format_repeat_contact("Google Ads", description);
+    crate::lead_messages::send_deduplicated(state, c2s_lead_id, &message, "repeat_contact").await?;
+    Ok(())
 }
 
 /// Validate Google webhook verification key
@@ -214,6 +485,33 @@ fn validate_google_key(config: &Config, provided_key: &str) -> Result<(), AppErr
     Ok(())
 }
 
+/// Record (or revoke) the enrichment consent answered in a lead form
+///
+/// Failures are logged: the lead is still created, and enrichment of
+/// consent-required tenants is refused until a consent is recorded.
+async fn record_form_consent(db: &PgPool, granted: bool, subjects: &[(SubjectKind, Option<&str>)]) {
+    for (kind, value) in subjects {
+        let Some(value) = value else { continue };
+        let result = if granted {
+            consent::grant(
+                db,
+                *kind,
+                value,
+                consent::PURPOSE_ENRICHMENT,
+                "google_ads_form",
+            )
+            .await
+        } else {
+            consent::revoke(db, *kind, value, consent::PURPOSE_ENRICHMENT)
+                .await
+                .map(|_| ())
+        };
+        if let Err(e) = result {
+            tracing::warn!("⚠️  Failed to record form consent: {}", e);
+        }
+    }
+}
+
 /// Check if lead already processed (deduplication)
 async fn is_duplicate_lead(db: &PgPool, google_lead_id: &str) -> Result<bool, AppError> {
     let exists = sqlx::query_scalar::<_, bool>(
@@ -221,18 +519,35 @@ async fn is_duplicate_lead(db: &PgPool, google_lead_id: &str) -> Result<bool, Ap
     )
     .bind(google_lead_id)
     .fetch_one(db)
-    .await?;
+    .timed("google_ads_leads.exists")
+    .await
+    .context(ErrorContext::db("google_ads_leads.exists").id("google_lead_id", google_lead_id))?;
 
     Ok(exists)
 }
 
 /// Perform inline enrichment: Diretrix → Work API
-async fn perform_inline_enrichment(
+///
+/// Refused with `AppError::ConsentRequired` when the tenant requires a
+/// consent and none is recorded for the contact.
+pub(crate) async fn perform_inline_enrichment(
     state: &std::sync::Arc<crate::handlers::AppState>,
+    tenant: Option<&str>,
+    name: Option<&str>,
     cpf_from_form: Option<&str>,
     phone: Option<&str>,
     email: Option<&str>,
 ) -> Result<String, AppError> {
+    let subjects: Vec<(SubjectKind, &str)> = [
+        cpf_from_form.map(|c| (SubjectKind::Cpf, c)),
+        phone.map(|p| (SubjectKind::Phone, p)),
+        email.map(|e| (SubjectKind::Email, e)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    consent::ensure_enrichment_consent(&state.db, &state.config, tenant, &subjects).await?;
+
     let mut enrichment = String::new();
 
     // Try to get CPF (priority: form > Diretrix lookup)
@@ -249,8 +564,15 @@ async fn perform_inline_enrichment(
             Some(existing.cpf)
         } else {
             // Fallback to Diretrix
-            let lookup_result =
-                crate::enrichment::find_cpf_via_diretrix(phone, email, &state.config).await;
+            let diretrix = crate::tenant_credentials::diretrix_for(state, tenant);
+            let lookup_result = crate::enrichment::find_cpf_via_diretrix(
+                name,
+                phone,
+                email,
+                diretrix.as_ref(),
+                state.config.identity_match_threshold,
+            )
+            .await;
 
             match lookup_result {
                 Ok(result) if !result.cpfs.is_empty() => {
@@ -274,7 +596,7 @@ async fn perform_inline_enrichment(
     if let Some(cpf_val) = cpf {
         enrichment.push_str("\n💰 Dados Econômicos:\n");
 
-        let work_api = WorkApiService::new(&state.config);
+        let work_api = crate::tenant_credentials::work_api_for(state, tenant);
         match work_api.fetch_all_modules(&cpf_val).await {
             Ok(work_data) => {
                 // Extract key enrichment data from JSON
@@ -322,7 +644,7 @@ async fn perform_inline_enrichment(
                             if let Some(cep) = addr.get("cep").and_then(|v| v.as_str()) {
                                 enrichment.push_str(&format!(" (CEP: {})", cep));
                             }
-                            enrichment.push_str("\n");
+                            enrichment.push('\n');
                         }
                     }
                 }
@@ -366,17 +688,12 @@ async fn perform_inline_enrichment(
 async fn store_google_ads_lead(
     db: &PgPool,
     payload: &GoogleAdsWebhookPayload,
-    c2s_lead_id: &str,
-    enrichment_success: bool,
+    c2s_lead_id: Option<&str>,
+    enrichment_status: &str,
     description_length: i32,
-    c2s_latency_ms: i32,
+    c2s_latency_ms: Option<i32>,
 ) -> Result<(), AppError> {
     let cpf = payload.get_cpf();
-    let enrichment_status = if enrichment_success {
-        "completed"
-    } else {
-        "partial"
-    };
 
     sqlx::query(
         r#"
@@ -391,9 +708,10 @@ async fn store_google_ads_lead(
             cpf,
             description_length,
             c2s_latency_ms,
-            c2s_created_at
+            c2s_created_at,
+            is_test
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         "#,
     )
     .bind(&payload.lead_id)
@@ -406,10 +724,129 @@ async fn store_google_ads_lead(
     .bind(cpf)
     .bind(description_length)
     .bind(c2s_latency_ms)
-    .bind(Utc::now())
+    .bind(c2s_lead_id.map(|_| Utc::now()))
+    .bind(payload.is_test)
     .execute(db)
-    .await?;
+    .timed("google_ads_leads.insert")
+    .await
+    .context(ErrorContext::db("google_ads_leads.insert").id("google_lead_id", &payload.lead_id))?;
 
     tracing::info!("✓ Google Ads lead tracking record stored");
     Ok(())
 }
+
+/// Stored Google Ads lead, as read for a replay
+#[derive(sqlx::FromRow)]
+struct StoredGoogleAdsLead {
+    payload_raw: serde_json::Value,
+    c2s_lead_id: Option<String>,
+    is_test: bool,
+    anonymized_at: Option<chrono::DateTime<Utc>>,
+}
+
+async fn fetch_stored_lead(
+    db: &PgPool,
+    google_lead_id: &str,
+) -> Result<Option<StoredGoogleAdsLead>, AppError> {
+    let lead = sqlx::query_as::<_, StoredGoogleAdsLead>(
+        r#"
+        SELECT payload_raw, c2s_lead_id, is_test, anonymized_at
+        FROM google_ads_leads
+        WHERE google_lead_id = $1
+        "#,
+    )
+    .bind(google_lead_id)
+    .fetch_optional(db)
+    .timed("google_ads_leads.find")
+    .await
+    .context(ErrorContext::db("google_ads_leads.find").id("google_lead_id", google_lead_id))?;
+
+    Ok(lead)
+}
+
+/// Mark a lead as being replayed
+///
+/// False when another replay holds it (claims expire after 10 minutes) or
+/// its C2S lead changed since it was read.
+async fn claim_replay(
+    db: &PgPool,
+    google_lead_id: &str,
+    c2s_lead_id: Option<&str>,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE google_ads_leads
+        SET replay_started_at = NOW()
+        WHERE google_lead_id = $1
+          AND c2s_lead_id IS NOT DISTINCT FROM $2
+          AND (replay_started_at IS NULL OR replay_started_at < NOW() - INTERVAL '10 minutes')
+        "#,
+    )
+    .bind(google_lead_id)
+    .bind(c2s_lead_id)
+    .execute(db)
+    .timed("google_ads_leads.claim_replay")
+    .await
+    .context(
+        ErrorContext::db("google_ads_leads.claim_replay").id("google_lead_id", google_lead_id),
+    )?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Release the claim of a failed replay (logged, the claim expires anyway)
+async fn release_replay(db: &PgPool, google_lead_id: &str) {
+    let result = sqlx::query(
+        "UPDATE google_ads_leads SET replay_started_at = NULL WHERE google_lead_id = $1",
+    )
+    .bind(google_lead_id)
+    .execute(db)
+    .timed("google_ads_leads.release_replay")
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("⚠️  Failed to release replay of {}: {}", google_lead_id, e);
+    }
+}
+
+/// Point the tracking record at the replayed C2S lead, keeping the previous one
+async fn record_replay(
+    db: &PgPool,
+    payload: &GoogleAdsWebhookPayload,
+    processed: &ProcessedLead,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE google_ads_leads
+        SET previous_c2s_lead_id = COALESCE(c2s_lead_id, previous_c2s_lead_id),
+            c2s_lead_id = $2,
+            payload_raw = $3,
+            enrichment_status = $4,
+            cpf = $5,
+            description_length = $6,
+            c2s_latency_ms = $7,
+            c2s_created_at = NOW(),
+            replay_count = replay_count + 1,
+            replayed_at = NOW(),
+            replay_started_at = NULL
+        WHERE google_lead_id = $1
+        "#,
+    )
+    .bind(&payload.lead_id)
+    .bind(&processed.c2s_lead_id)
+    .bind(serde_json::to_value(payload).unwrap())
+    .bind(processed.enrichment_status)
+    .bind(payload.get_cpf())
+    .bind(processed.description_length)
+    .bind(processed.latency_ms)
+    .execute(db)
+    .timed("google_ads_leads.record_replay")
+    .await
+    .context(
+        ErrorContext::db("google_ads_leads.record_replay")
+            .id("google_lead_id", &payload.lead_id)
+            .id("c2s_lead_id", &processed.c2s_lead_id),
+    )?;
+
+    Ok(())
+}
@@ -7,7 +7,7 @@ pub struct GoogleAdsWebhookPayload {
     /// Unique lead identifier (used for deduplication)
     pub lead_id: String,
 
-    /// API version
+    /// Webhook contract version (e.g. "1.0")
     pub api_version: String,
 
     /// Google Ads form ID
@@ -16,6 +16,14 @@ pub struct GoogleAdsWebhookPayload {
     /// Google Ads campaign ID
     pub campaign_id: i64,
 
+    /// Ad group the form was shown in
+    #[serde(default)]
+    pub adgroup_id: Option<i64>,
+
+    /// Creative (ad) the form was attached to
+    #[serde(default)]
+    pub creative_id: Option<i64>,
+
     /// Google Click ID (gcl_id) for conversion tracking
     #[serde(default)]
     pub gcl_id: Option<String>,
@@ -23,88 +31,196 @@ pub struct GoogleAdsWebhookPayload {
     /// Webhook verification key (REQUIRED for security)
     pub google_key: String,
 
-    /// Whether this is a test lead
+    /// Whether this is a test lead (sent from the Google Ads UI)
+    #[serde(default)]
     pub is_test: bool,
 
     /// Dynamic form fields submitted by the user
+    #[serde(default)]
     pub user_column_data: Vec<UserColumnData>,
 }
 
 /// Individual form field data
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UserColumnData {
-    /// Column identifier (e.g., "FULL_NAME", "EMAIL", "PHONE_NUMBER")
+    /// Column identifier (e.g., "FULL_NAME", "EMAIL", "PHONE_NUMBER");
+    /// custom questions carry a form-specific id
+    #[serde(default)]
     pub column_id: String,
 
-    /// Human-readable column name
+    /// Human-readable column name (the question text for custom questions)
+    #[serde(default)]
     pub column_name: String,
 
     /// User-submitted value
+    #[serde(default)]
     pub string_value: String,
 }
 
+/// Standard lead form columns, plus custom questions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoogleAdsColumn {
+    FullName,
+    FirstName,
+    LastName,
+    Email,
+    PhoneNumber,
+    PostalCode,
+    StreetAddress,
+    City,
+    Region,
+    Country,
+    CompanyName,
+    JobTitle,
+    WorkEmail,
+    WorkPhone,
+    /// Answer to a question defined by the advertiser
+    Custom,
+}
+
+impl GoogleAdsColumn {
+    pub fn from_column_id(column_id: &str) -> Self {
+        match column_id.trim().to_uppercase().as_str() {
+            "FULL_NAME" | "NAME" => Self::FullName,
+            "FIRST_NAME" => Self::FirstName,
+            "LAST_NAME" => Self::LastName,
+            "EMAIL" => Self::Email,
+            "PHONE_NUMBER" | "PHONE" => Self::PhoneNumber,
+            "POSTAL_CODE" => Self::PostalCode,
+            "STREET_ADDRESS" => Self::StreetAddress,
+            "CITY" => Self::City,
+            "REGION" => Self::Region,
+            "COUNTRY" => Self::Country,
+            "COMPANY_NAME" => Self::CompanyName,
+            "JOB_TITLE" => Self::JobTitle,
+            "WORK_EMAIL" => Self::WorkEmail,
+            "WORK_PHONE" => Self::WorkPhone,
+            _ => Self::Custom,
+        }
+    }
+}
+
+impl UserColumnData {
+    pub fn column(&self) -> GoogleAdsColumn {
+        GoogleAdsColumn::from_column_id(&self.column_id)
+    }
+
+    /// Trimmed answer, None when left blank
+    pub fn value(&self) -> Option<&str> {
+        Some(self.string_value.trim()).filter(|v| !v.is_empty())
+    }
+}
+
 impl GoogleAdsWebhookPayload {
-    /// Extract full name from form data
-    pub fn get_name(&self) -> Option<String> {
+    /// First non-blank answer to a standard column
+    pub fn value(&self, column: GoogleAdsColumn) -> Option<&str> {
         self.user_column_data
             .iter()
-            .find(|field| field.column_id == "FULL_NAME" || field.column_id == "NAME")
-            .map(|field| field.string_value.clone())
+            .filter(|field| field.column() == column)
+            .find_map(|field| field.value())
     }
 
-    /// Extract email from form data
-    pub fn get_email(&self) -> Option<String> {
+    /// First non-blank custom answer whose question matches
+    fn custom_value(&self, matches: impl Fn(&UserColumnData) -> bool) -> Option<&str> {
+        self.custom_answers()
+            .filter(|field| matches(field))
+            .find_map(|field| field.value())
+    }
+
+    /// Answers to the advertiser's own questions
+    pub fn custom_answers(&self) -> impl Iterator<Item = &UserColumnData> {
         self.user_column_data
             .iter()
-            .find(|field| field.column_id == "EMAIL")
-            .map(|field| field.string_value.trim().to_lowercase())
+            .filter(|field| field.column() == GoogleAdsColumn::Custom)
     }
 
-    /// Extract phone number from form data
+    /// Full name, or first and last name when the form splits it
+    pub fn get_name(&self) -> Option<String> {
+        if let Some(name) = self.value(GoogleAdsColumn::FullName) {
+            return Some(name.to_string());
+        }
+        let parts: Vec<&str> = [
+            self.value(GoogleAdsColumn::FirstName),
+            self.value(GoogleAdsColumn::LastName),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Email (personal, else work), lowercased
+    pub fn get_email(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::Email)
+            .or_else(|| self.value(GoogleAdsColumn::WorkEmail))
+            .map(|email| email.to_lowercase())
+    }
+
+    /// Phone number (personal, else work), as submitted
     pub fn get_phone(&self) -> Option<String> {
-        self.user_column_data
-            .iter()
-            .find(|field| field.column_id == "PHONE_NUMBER" || field.column_id == "PHONE")
-            .map(|field| field.string_value.clone())
+        self.value(GoogleAdsColumn::PhoneNumber)
+            .or_else(|| self.value(GoogleAdsColumn::WorkPhone))
+            .map(str::to_string)
     }
 
-    /// Extract CPF from form data (if form includes CPF field)
+    /// CPF digits from a custom question (if the form asks for it)
     pub fn get_cpf(&self) -> Option<String> {
-        self.user_column_data
-            .iter()
-            .find(|field| {
-                field.column_id == "CPF"
-                    || field.column_id == "DOCUMENT"
-                    || field.column_name.to_lowercase().contains("cpf")
-            })
-            .map(|field| {
-                // Remove formatting: 123.456.789-01 -> 12345678901
-                field
-                    .string_value
-                    .chars()
-                    .filter(|c| c.is_numeric())
-                    .collect()
-            })
+        self.custom_value(|field| {
+            field.column_id.eq_ignore_ascii_case("CPF")
+                || field.column_id.eq_ignore_ascii_case("DOCUMENT")
+                || field.column_name.to_lowercase().contains("cpf")
+        })
+        .map(|cpf| cpf.chars().filter(|c| c.is_ascii_digit()).collect())
     }
 
-    /// Extract city from form data
-    #[allow(dead_code)]
+    /// Answer to the form's consent question (None when the form has none)
+    pub fn get_consent(&self) -> Option<bool> {
+        self.custom_value(|field| {
+            let name = field.column_name.to_lowercase();
+            field.column_id.eq_ignore_ascii_case("CONSENT")
+                || name.contains("consent")
+                || name.contains("autoriz")
+        })
+        .map(crate::consent::is_affirmative)
+    }
+
+    /// City (standard column, else a custom "cidade" question)
     pub fn get_city(&self) -> Option<String> {
-        self.user_column_data
-            .iter()
-            .find(|field| {
-                field.column_id == "CITY" || field.column_name.to_lowercase().contains("cidade")
+        self.value(GoogleAdsColumn::City)
+            .or_else(|| {
+                self.custom_value(|field| field.column_name.to_lowercase().contains("cidade"))
             })
-            .map(|field| field.string_value.clone())
+            .map(str::to_string)
+    }
+
+    pub fn get_postal_code(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::PostalCode).map(str::to_string)
+    }
+
+    pub fn get_street_address(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::StreetAddress)
+            .map(str::to_string)
+    }
+
+    pub fn get_region(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::Region).map(str::to_string)
     }
 
-    /// Extract custom field by column_id
-    #[allow(dead_code)]
+    pub fn get_company_name(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::CompanyName).map(str::to_string)
+    }
+
+    pub fn get_job_title(&self) -> Option<String> {
+        self.value(GoogleAdsColumn::JobTitle).map(str::to_string)
+    }
+
+    /// Answer of any field by column_id (standard or custom)
     pub fn get_field(&self, column_id: &str) -> Option<String> {
         self.user_column_data
             .iter()
-            .find(|field| field.column_id == column_id)
-            .map(|field| field.string_value.clone())
+            .filter(|field| field.column_id.eq_ignore_ascii_case(column_id))
+            .find_map(|field| field.value())
+            .map(str::to_string)
     }
 
     /// Generate formatted description for C2S
@@ -115,6 +231,9 @@ impl GoogleAdsWebhookPayload {
         desc.push_str("🎯 Lead do Google Ads\n\n");
         desc.push_str(&format!("📊 Campanha ID: {}\n", self.campaign_id));
         desc.push_str(&format!("📝 Formulário ID: {}\n", self.form_id));
+        if let Some(adgroup_id) = self.adgroup_id {
+            desc.push_str(&format!("🗂️  Grupo de Anúncios ID: {}\n", adgroup_id));
+        }
 
         if let Some(gcl_id) = &self.gcl_id {
             desc.push_str(&format!("🔗 GCLID: {}\n", gcl_id));
@@ -134,7 +253,7 @@ impl GoogleAdsWebhookPayload {
 
         // Add enrichment data if available
         if let Some(enrichment) = enrichment_data {
-            desc.push_str("\n");
+            desc.push('\n');
             desc.push_str(enrichment);
         }
 
@@ -146,63 +265,183 @@ impl GoogleAdsWebhookPayload {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_extract_name() {
-        let payload = GoogleAdsWebhookPayload {
+    fn field(column_id: &str, column_name: &str, value: &str) -> UserColumnData {
+        UserColumnData {
+            column_id: column_id.to_string(),
+            column_name: column_name.to_string(),
+            string_value: value.to_string(),
+        }
+    }
+
+    fn payload(user_column_data: Vec<UserColumnData>) -> GoogleAdsWebhookPayload {
+        GoogleAdsWebhookPayload {
             lead_id: "test123".to_string(),
             api_version: "v1".to_string(),
             form_id: 123,
             campaign_id: 456,
+            adgroup_id: None,
+            creative_id: None,
             gcl_id: None,
             google_key: "test_key".to_string(),
             is_test: true,
-            user_column_data: vec![UserColumnData {
-                column_id: "FULL_NAME".to_string(),
-                column_name: "Nome Completo".to_string(),
-                string_value: "João Silva".to_string(),
-            }],
-        };
+            user_column_data,
+        }
+    }
+
+    #[test]
+    fn test_extract_name() {
+        let payload = payload(vec![field("FULL_NAME", "Nome Completo", "João Silva")]);
+
+        assert_eq!(payload.get_name(), Some("João Silva".to_string()));
+    }
+
+    #[test]
+    fn test_extract_split_name() {
+        let payload = payload(vec![
+            field("FIRST_NAME", "Nome", "João"),
+            field("LAST_NAME", "Sobrenome", " Silva "),
+        ]);
 
         assert_eq!(payload.get_name(), Some("João Silva".to_string()));
     }
 
     #[test]
     fn test_extract_email() {
-        let payload = GoogleAdsWebhookPayload {
-            lead_id: "test123".to_string(),
-            api_version: "v1".to_string(),
-            form_id: 123,
-            campaign_id: 456,
-            gcl_id: None,
-            google_key: "test_key".to_string(),
-            is_test: true,
-            user_column_data: vec![UserColumnData {
-                column_id: "EMAIL".to_string(),
-                column_name: "E-mail".to_string(),
-                string_value: "  JOAO@EXAMPLE.COM  ".to_string(),
-            }],
-        };
+        let payload = payload(vec![field("EMAIL", "E-mail", "  JOAO@EXAMPLE.COM  ")]);
 
         assert_eq!(payload.get_email(), Some("joao@example.com".to_string()));
     }
 
+    #[test]
+    fn test_work_contacts_are_fallbacks() {
+        let payload = payload(vec![
+            field("WORK_EMAIL", "E-mail comercial", "joao@empresa.com"),
+            field("WORK_PHONE", "Telefone comercial", "+551130000000"),
+            field("PHONE_NUMBER", "Telefone", ""),
+        ]);
+
+        assert_eq!(payload.get_email(), Some("joao@empresa.com".to_string()));
+        assert_eq!(payload.get_phone(), Some("+551130000000".to_string()));
+    }
+
     #[test]
     fn test_extract_cpf() {
-        let payload = GoogleAdsWebhookPayload {
-            lead_id: "test123".to_string(),
-            api_version: "v1".to_string(),
-            form_id: 123,
-            campaign_id: 456,
-            gcl_id: None,
-            google_key: "test_key".to_string(),
-            is_test: true,
-            user_column_data: vec![UserColumnData {
-                column_id: "CPF".to_string(),
-                column_name: "CPF".to_string(),
-                string_value: "123.456.789-01".to_string(),
-            }],
-        };
+        let payload = payload(vec![field("CPF", "CPF", "123.456.789-01")]);
 
         assert_eq!(payload.get_cpf(), Some("12345678901".to_string()));
     }
+
+    #[test]
+    fn test_standard_columns() {
+        let payload = payload(vec![
+            field("POSTAL_CODE", "CEP", "01310-100"),
+            field("STREET_ADDRESS", "Endereço", "Av. Paulista, 1000"),
+            field("CITY", "Cidade", "São Paulo"),
+            field("REGION", "Estado", "SP"),
+            field("COUNTRY", "País", "BR"),
+            field("COMPANY_NAME", "Empresa", "ACME"),
+            field("JOB_TITLE", "Cargo", "Diretor"),
+        ]);
+
+        assert_eq!(payload.get_postal_code().as_deref(), Some("01310-100"));
+        assert_eq!(
+            payload.get_street_address().as_deref(),
+            Some("Av. Paulista, 1000")
+        );
+        assert_eq!(payload.get_city().as_deref(), Some("São Paulo"));
+        assert_eq!(payload.get_region().as_deref(), Some("SP"));
+        assert_eq!(payload.value(GoogleAdsColumn::Country), Some("BR"));
+        assert_eq!(payload.get_company_name().as_deref(), Some("ACME"));
+        assert_eq!(payload.get_job_title().as_deref(), Some("Diretor"));
+        assert_eq!(payload.custom_answers().count(), 0);
+    }
+
+    #[test]
+    fn test_custom_answers() {
+        let payload = payload(vec![
+            field("FULL_NAME", "Nome", "João"),
+            field("QUESTION_1", "Qual o seu orçamento?", "Até R$ 500 mil"),
+            field("QUESTION_2", "Em qual cidade procura?", "Santos"),
+        ]);
+
+        let answers: Vec<(&str, &str)> = payload
+            .custom_answers()
+            .map(|f| (f.column_name.as_str(), f.string_value.as_str()))
+            .collect();
+        assert_eq!(
+            answers,
+            vec![
+                ("Qual o seu orçamento?", "Até R$ 500 mil"),
+                ("Em qual cidade procura?", "Santos"),
+            ]
+        );
+        assert_eq!(payload.get_city().as_deref(), Some("Santos"));
+        assert_eq!(
+            payload.get_field("question_1").as_deref(),
+            Some("Até R$ 500 mil")
+        );
+        assert_eq!(payload.get_cpf(), None);
+    }
+
+    #[test]
+    fn test_deserialize_full_contract() {
+        let payload: GoogleAdsWebhookPayload = serde_json::from_value(serde_json::json!({
+            "lead_id": "TeSter-123-ABCDEFGHIJKLMNOPQRSTUVWXYZ-abcdefghijklmnopqrstuvwxyz-0123456789",
+            "api_version": "1.0",
+            "form_id": 40000000000_i64,
+            "campaign_id": 20000000000_i64,
+            "adgroup_id": 30000000000_i64,
+            "creative_id": 50000000000_i64,
+            "gcl_id": "TeSter-123",
+            "google_key": "secret",
+            "is_test": true,
+            "user_column_data": [
+                {"column_name": "Full Name", "string_value": "FirstName LastName", "column_id": "FULL_NAME"},
+                {"string_value": "test@example.com", "column_id": "EMAIL"},
+                {"column_name": "Phone Number", "string_value": "+16505550123", "column_id": "PHONE_NUMBER"}
+            ]
+        }))
+        .unwrap();
+
+        assert!(payload.is_test);
+        assert_eq!(payload.api_version, "1.0");
+        assert_eq!(payload.adgroup_id, Some(30000000000));
+        assert_eq!(payload.creative_id, Some(50000000000));
+        assert_eq!(payload.get_name().as_deref(), Some("FirstName LastName"));
+        assert_eq!(payload.get_email().as_deref(), Some("test@example.com"));
+        assert_eq!(payload.get_phone().as_deref(), Some("+16505550123"));
+    }
+
+    #[test]
+    fn test_minimal_payload_defaults() {
+        let payload: GoogleAdsWebhookPayload = serde_json::from_value(serde_json::json!({
+            "lead_id": "1",
+            "api_version": "1.0",
+            "form_id": 1,
+            "campaign_id": 2,
+            "google_key": "secret",
+            "user_column_data": []
+        }))
+        .unwrap();
+
+        assert!(!payload.is_test);
+        assert_eq!(payload.adgroup_id, None);
+        assert_eq!(payload.get_name(), None);
+    }
+
+    #[test]
+    fn test_extract_consent() {
+        let mut payload = payload(vec![field(
+            "CUSTOM_1",
+            "Autoriza o uso dos seus dados?",
+            "Sim",
+        )]);
+        assert_eq!(payload.get_consent(), Some(true));
+
+        payload.user_column_data[0].string_value = "Não".to_string();
+        assert_eq!(payload.get_consent(), Some(false));
+
+        payload.user_column_data.clear();
+        assert_eq!(payload.get_consent(), None);
+    }
 }
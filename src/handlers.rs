@@ -1,14 +1,18 @@
+use crate::api_keys::AuthenticatedKey;
+use crate::clients::{C2sApi, DiretrixApi, WorkApi};
 use crate::config::Config;
-use crate::errors::AppError;
-use crate::gateway_client::C2sGatewayClient;
+use crate::db::TimedQuery;
+use crate::enrichment::StepTimings;
+use crate::errors::{AppError, ErrorContext, ResultExt};
 use crate::models::*;
-use crate::services::{DiretrixService, EnrichmentService, WorkApiService};
+use crate::money::Brl;
+use crate::readiness::{LazyClient, Readiness};
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
-use moka::future::Cache;
 use serde_json::json;
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -19,27 +23,80 @@ use uuid::Uuid;
 pub struct AppState {
     pub db: PgPool,
     pub config: Config,
-    pub gateway_client: Option<C2sGatewayClient>, // Optional gateway client
-    /// Global deduplication cache to prevent processing same CPF within short time window
-    pub recent_cpf_cache: Cache<String, i64>,
-    /// Lead-level deduplication cache to prevent concurrent processing of same lead_id
-    pub processing_leads_cache: Cache<String, i64>,
-    // Cache for contact (phone/email) -> Existing Enrichment Data
-    // Key: phone or email, Value: Option<ExistingEnrichment> (None means checked and not found)
-    pub contact_to_cpf_cache: Cache<String, Option<crate::enrichment::ExistingEnrichment>>,
-    /// Work API response cache (1 hour TTL) to reduce external API calls
-    // Key: "all:{cpf}" or "module:{module}:{cpf}" or "cep:{cep}", Value: JSON response string
-    pub work_api_cache: Cache<String, String>,
+    /// Optional clients, built on first use (see `readiness`)
+    pub rdstation_client: LazyClient<crate::rdstation::RdStationClient>,
+    pub listings_client: LazyClient<crate::listings::ListingsClient>,
+    pub salesforce_client: LazyClient<crate::salesforce::SalesforceClient>,
+    pub sheets_client: LazyClient<crate::google_sheets::GoogleSheetsClient>,
+    pub sms_client: LazyClient<crate::sms::SmsClient>, // SMS sender for routing alerts
+    /// Provider clients (see `clients`); the C2S one is the default account
+    pub work_api: Arc<dyn WorkApi>,
+    pub diretrix: Arc<dyn DiretrixApi>,
+    pub c2s: Arc<dyn C2sApi>,
+    /// Deduplication, contact, Work API and customer lookup caches
+    pub caches: crate::caches::Caches,
+    /// Enrichment, customer lookup and storage services (see `app_services`)
+    pub services: Arc<crate::app_services::Services>,
+    /// Lead/CPF deduplication (in-process or Redis, see `dedup`)
+    pub dedup: Arc<dyn crate::dedup::DedupBackend>,
+    /// C2S webhook events waiting for background enrichment
+    pub webhook_queue: crate::webhook_queue::WebhookQueue,
+    /// Startup steps and optional client states (`GET /health/ready`)
+    pub readiness: Arc<Readiness>,
 }
 
 /// Health check endpoint
-pub async fn health() -> (StatusCode, Json<serde_json::Value>) {
+///
+/// Always 200 (liveness); `status` is `degraded` and `degraded` lists the
+/// failed components when a client (e.g. C2S) failed to initialize.
+pub async fn health(State(state): State<Arc<AppState>>) -> (StatusCode, Json<serde_json::Value>) {
+    let degraded = state.readiness.failed_components();
     (
         StatusCode::OK,
         Json(json!({
-            "status": "healthy",
+            "status": if degraded.is_empty() { "healthy" } else { "degraded" },
             "service": "rust-c2s-api",
-            "version": "0.1.0"
+            "version": "0.1.0",
+            "degraded": degraded
+        })),
+    )
+}
+
+/// GET /health/ready
+///
+/// 200 once startup completed and the database answers, 503 otherwise.
+/// `status` is `degraded` when an optional provider client failed to
+/// initialize; `components` lists every startup step and client.
+pub async fn readiness(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let database = matches!(
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            sqlx::query_scalar!("SELECT 1 AS one").fetch_one(&state.db),
+        )
+        .await,
+        Ok(Ok(_))
+    );
+    let ready = state.readiness.is_started() && database;
+
+    let status = if !ready {
+        "not_ready"
+    } else if state.readiness.is_degraded() {
+        "degraded"
+    } else {
+        "ready"
+    };
+    (
+        if ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(json!({
+            "status": status,
+            "database": database,
+            "components": state.readiness.snapshot(),
         })),
     )
 }
@@ -64,40 +121,113 @@ pub async fn get_customer(
         ));
     }
 
-    let enrichment_service = EnrichmentService::new(&state.config, state.db.clone());
-    let customer_data = enrichment_service.get_customer_unified(&params).await?;
+    let customer_data = state
+        .services
+        .enrichment
+        .get_customer_unified(&params)
+        .await
+        .context(ErrorContext::new("customers.get_unified"))?;
 
     tracing::info!(
-        "Successfully retrieved customer data. Enriched: {}, Sources: {:?}",
+        "Successfully retrieved customer data. Enriched: {}, Degraded: {}, Sources: {:?}",
         customer_data.metadata.enriched,
+        customer_data.metadata.degraded,
         customer_data.metadata.sources
     );
 
     Ok(Json(customer_data))
 }
 
+/// GET /api/v1/customers?uf=SP,RJ&after=&limit=
+/// Parties tagged with one of the given UFs, ordered by id; page through
+/// with `after` set to the `next_after` of the previous page
+///
+/// Always needs an API key, even without `API_KEYS_REQUIRED`; a key
+/// restricted to some states must pass `uf` and only gets those states.
+pub async fn list_customers(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
+    Query(params): Query<CustomerListParams>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let Some(Extension(key)) = key else {
+        return Err(AppError::Unauthorized(
+            "Missing X-Api-Key header".to_string(),
+        ));
+    };
+    let ufs = crate::residency::restrict_ufs(
+        crate::residency::parse_uf_filter(params.uf.as_deref())?,
+        key.ufs.as_deref(),
+    )?;
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    tracing::info!(
+        "GET /customers - key: {}, ufs: {:?}, after: {:?}",
+        key.id,
+        ufs,
+        params.after
+    );
+
+    let parties = crate::residency::list_parties(&state.db, &ufs, params.after, limit).await?;
+    let next_after = parties
+        .last()
+        .filter(|_| parties.len() as i64 == limit)
+        .map(|p| p.id);
+
+    Ok(Json(json!({
+        "ufs": ufs,
+        "count": parties.len(),
+        "next_after": next_after,
+        "customers": parties,
+    })))
+}
+
 /// GET /api/v1/customers/:id
 /// Get customer by UUID
 pub async fn get_customer_by_id(
     State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<EnrichedCustomerData>, AppError> {
     tracing::info!("GET /customers/{}", id);
 
-    let customer = sqlx::query_as::<_, Customer>(
-        "SELECT * FROM core.parties WHERE id = $1 AND party_type = 'person'",
+    let customer = sqlx::query_as!(
+        Customer,
+        r#"
+        SELECT id, party_type, cpf_cnpj AS "cpf_cnpj!", full_name, normalized_name, sex,
+               birth_date, mother_name, father_name, rg, fantasy_name,
+               normalized_fantasy_name, opening_date, registration_status_date,
+               company_type, company_size, enriched, uf, created_at AS "created_at!",
+               updated_at
+        FROM core.parties
+        WHERE id = $1 AND party_type = 'person'
+        "#,
+        id
     )
-    .bind(id)
     .fetch_optional(&state.db)
-    .await?
+    .timed("parties.find_by_id")
+    .await
+    .context(ErrorContext::db("parties.find_by_id").id("party_id", id))?
+    // Parties outside a restricted key's states are not disclosed
+    .filter(|c| crate::residency::visible_to(c.uf.as_deref(), key.as_deref()))
     .ok_or_else(|| AppError::NotFound(format!("Customer with id {} not found", id)))?;
 
-    let contacts = sqlx::query_as::<_, crate::models::PartyContact>(
-        "SELECT * FROM core.party_contacts WHERE party_id = $1 ORDER BY is_primary DESC, created_at ASC",
+    let contacts = sqlx::query_as!(
+        crate::models::PartyContact,
+        r#"
+        SELECT
+            contact_id, party_id, contact_type::text AS "contact_type!",
+            value, is_primary AS "is_primary!", is_verified AS "is_verified!",
+            is_whatsapp AS "is_whatsapp!", source, confidence::float8 AS confidence,
+            valid_from, valid_to, created_at AS "created_at!", updated_at
+        FROM core.party_contacts
+        WHERE party_id = $1
+        ORDER BY is_primary DESC, created_at ASC
+        "#,
+        id
     )
-    .bind(id)
     .fetch_all(&state.db)
-    .await?;
+    .timed("party_contacts.list_by_party")
+    .await
+    .context(ErrorContext::db("party_contacts.list_by_party").id("party_id", id))?;
 
     let emails: Vec<Email> = contacts
         .iter()
@@ -128,6 +258,38 @@ pub async fn get_customer_by_id(
     }))
 }
 
+/// GET /api/v1/customers/:id/contacts/history
+/// Contacts and addresses of a customer, oldest first, with when each was
+/// first seen, last confirmed and (if so) dropped by the provider
+pub async fn get_customer_contact_history(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if key.as_ref().is_some_and(|Extension(k)| k.ufs.is_some()) {
+        let uf = crate::residency::party_uf(&state.db, id).await?;
+        if !crate::residency::visible_to(uf.as_deref(), key.as_deref()) {
+            return Err(AppError::NotFound(format!(
+                "No contacts recorded for customer {}",
+                id
+            )));
+        }
+    }
+    let entries = crate::contact_history::party_contact_history(&state.db, id).await?;
+    if entries.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No contacts recorded for customer {}",
+            id
+        )));
+    }
+
+    Ok(Json(json!({
+        "party_id": id,
+        "count": entries.len(),
+        "contacts": entries,
+    })))
+}
+
 /// POST /api/v1/enrich
 /// Enrich customer data via Work API
 pub async fn enrich_customer(
@@ -136,8 +298,12 @@ pub async fn enrich_customer(
 ) -> Result<Json<UnifiedCustomerResponse>, AppError> {
     tracing::info!("POST /enrich - params: {:?}", params);
 
-    let enrichment_service = EnrichmentService::new(&state.config, state.db.clone());
-    let customer_data = enrichment_service.get_customer_unified(&params).await?;
+    let customer_data = state
+        .services
+        .enrichment
+        .get_customer_unified(&params)
+        .await
+        .context(ErrorContext::new("customers.enrich"))?;
 
     Ok(Json(customer_data))
 }
@@ -155,26 +321,16 @@ pub async fn fetch_all_modules(
 
     let cache_key = format!("all:{}", documento);
 
-    // Check cache first with validation
-    if let Some(cached) = state.work_api_cache.get(&cache_key).await {
-        // Validate cache integrity
-        if let Some(valid_data) =
-            crate::cache_validator::ValidatedCacheEntry::deserialize_and_validate(&cached)
+    // Check cache first (validated, within the effective TTL)
+    if let Some(entry) = crate::cache_ttl::get(state.caches.work_api(), &cache_key).await {
+        if let Ok(result) =
+            serde_json::from_str::<crate::models::WorkApiCompleteResponse>(&entry.data)
         {
-            if let Ok(result) =
-                serde_json::from_str::<crate::models::WorkApiCompleteResponse>(&valid_data)
-            {
-                tracing::debug!(
-                    "Work API cache HIT (validated) for all modules: {}",
-                    documento
-                );
-                return Ok(Json(result));
-            }
-        } else {
-            tracing::warn!(
-                "Cache validation failed for {}, refetching from Work API",
+            tracing::debug!(
+                "Work API cache HIT (validated) for all modules: {}",
                 documento
             );
+            return Ok(Json(result));
         }
     }
 
@@ -182,14 +338,18 @@ pub async fn fetch_all_modules(
         "Work API cache MISS - Fetching all modules for: {}",
         documento
     );
-    let work_api = crate::services::WorkApiService::new(&state.config);
-    let result = work_api.fetch_all_modules(documento).await?;
+    let result = state.work_api.fetch_all_modules(documento).await.context(
+        ErrorContext::new("work_api.fetch_all_modules")
+            .provider("work_api")
+            .id("documento", documento),
+    )?;
 
     // Cache successful response with checksum validation
     if let Ok(json_str) = serde_json::to_string(&result) {
         let validated_entry = crate::cache_validator::ValidatedCacheEntry::new(json_str);
         state
-            .work_api_cache
+            .caches
+            .work_api()
             .insert(cache_key, validated_entry.serialize())
             .await;
     }
@@ -211,25 +371,15 @@ pub async fn fetch_module(
 
     let cache_key = format!("module:{}:{}", module, documento);
 
-    // Check cache first with validation
-    if let Some(cached) = state.work_api_cache.get(&cache_key).await {
-        // Validate cache integrity
-        if let Some(valid_data) =
-            crate::cache_validator::ValidatedCacheEntry::deserialize_and_validate(&cached)
-        {
-            if let Ok(result) = serde_json::from_str::<serde_json::Value>(&valid_data) {
-                tracing::debug!(
-                    "Work API cache HIT (validated) for module '{}': {}",
-                    module,
-                    documento
-                );
-                return Ok(Json(result));
-            }
-        } else {
-            tracing::warn!(
-                "Cache validation failed for module '{}', refetching from Work API",
-                module
+    // Check cache first (validated, within the effective TTL)
+    if let Some(entry) = crate::cache_ttl::get(state.caches.work_api(), &cache_key).await {
+        if let Ok(result) = serde_json::from_str::<serde_json::Value>(&entry.data) {
+            tracing::debug!(
+                "Work API cache HIT (validated) for module '{}': {}",
+                module,
+                documento
             );
+            return Ok(Json(result));
         }
     }
 
@@ -238,8 +388,16 @@ pub async fn fetch_module(
         module,
         documento
     );
-    let work_api = crate::services::WorkApiService::new(&state.config);
-    let result = work_api.fetch_module(&module, documento).await?;
+    let result = state
+        .work_api
+        .fetch_module(&module, documento)
+        .await
+        .context(
+            ErrorContext::new("work_api.fetch_module")
+                .provider("work_api")
+                .id("module", &module)
+                .id("documento", documento),
+        )?;
 
     let response = result.unwrap_or(serde_json::json!({"error": "No data"}));
 
@@ -247,7 +405,8 @@ pub async fn fetch_module(
     if let Ok(json_str) = serde_json::to_string(&response) {
         let validated_entry = crate::cache_validator::ValidatedCacheEntry::new(json_str);
         state
-            .work_api_cache
+            .caches
+            .work_api()
             .insert(cache_key, validated_entry.serialize())
             .await;
     }
@@ -271,9 +430,12 @@ pub async fn process_lead(
         name: Some(payload.personal_info.name.clone()),
     };
 
-    let enrichment_service = EnrichmentService::new(&state.config, state.db.clone());
-
-    match enrichment_service.get_customer_unified(&params).await {
+    match state
+        .services
+        .enrichment
+        .get_customer_unified(&params)
+        .await
+    {
         Ok(customer_data) => {
             // Check if we have useful contact data
             let has_data = !customer_data.contact_info.emails.is_empty()
@@ -312,6 +474,7 @@ pub async fn process_lead(
                             company_type: None,
                             company_size: None,
                             enriched: Some(customer_data.metadata.enriched),
+                            uf: None,
                             created_at: chrono::Utc::now(),
                             updated_at: None,
                         },
@@ -362,32 +525,177 @@ pub async fn process_lead(
     }
 }
 
+/// Query parameters for the resend endpoint
+#[derive(Debug, serde::Deserialize)]
+pub struct ResendQuery {
+    /// Send even when identical to the last message delivered to the lead
+    pub force: Option<bool>,
+}
+
+/// POST /api/v1/leads/:lead_id/resend-enrichment
+/// Re-render the latest stored enrichment of a lead and send it to C2S again
+/// (e.g. the original message was deleted in C2S). A message identical to the
+/// last one delivered is skipped unless `?force=true`.
+pub async fn resend_enrichment(
+    State(state): State<Arc<AppState>>,
+    Path(lead_id): Path<String>,
+    Query(query): Query<ResendQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    tracing::info!("Resend enrichment for lead: {}", lead_id);
+
+    let enriched_data = state
+        .services
+        .storage
+        .find_lead_enrichments(&lead_id)
+        .await
+        .context(ErrorContext::db("enrichments.find_by_lead").id("lead_id", &lead_id))?;
+    if enriched_data.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No stored enrichment for lead {}",
+            lead_id
+        )));
+    }
+
+    // Customer as received in the latest webhook, else the enriched name
+    let event = crate::webhook_handler::latest_webhook_event(&state.db, &lead_id)
+        .await
+        .context(ErrorContext::db("webhook_events.latest").id("lead_id", &lead_id))?;
+    let locale = event
+        .as_ref()
+        .map(|event| crate::language::lead_locale(event.attributes.lead_texts()))
+        .unwrap_or_default();
+    let customer = event.and_then(|event| event.attributes.customer);
+    let enriched_name = enriched_data[0]
+        .get("DadosBasicos")
+        .and_then(|d| d.get("nome"))
+        .and_then(|v| v.as_str());
+    let customer_name = customer
+        .as_ref()
+        .and_then(|c| c.name.as_deref())
+        .or(enriched_name)
+        .unwrap_or("Unknown");
+    let phone = customer
+        .as_ref()
+        .and_then(|c| c.phone.as_deref())
+        .filter(|s| !s.is_empty());
+    let email = customer
+        .as_ref()
+        .and_then(|c| c.email.as_deref())
+        .filter(|s| !s.is_empty());
+
+    let lead = crate::enrichment::MessageLead {
+        name: customer_name,
+        phone,
+        email,
+        locale,
+    };
+    let (message_body, _) = crate::enrichment::render_lead_message(
+        &state,
+        &lead_id,
+        lead,
+        &enriched_data,
+        enriched_data.len() == 1,
+    )
+    .await;
+
+    let force = query.force.unwrap_or(false);
+    let send_context = || {
+        ErrorContext::new("c2s.send_message")
+            .provider("c2s")
+            .id("lead_id", &lead_id)
+            .id("force", force)
+    };
+    let message_sent = if force {
+        crate::lead_messages::send_logged(&state, &lead_id, &message_body, "resend")
+            .await
+            .context(send_context())?;
+        true
+    } else {
+        crate::lead_messages::send_deduplicated(&state, &lead_id, &message_body, "resend")
+            .await
+            .context(send_context())?
+    };
+
+    Ok(Json(json!({
+        "success": true,
+        "lead_id": lead_id,
+        "message_sent": message_sent,
+        "forced": force,
+        "message_length": message_body.len(),
+    })))
+}
+
+/// Query parameters for the C2S enrichment endpoint
+#[derive(Debug, serde::Deserialize)]
+pub struct EnrichLeadQuery {
+    /// Queue the enrichment and answer 202 with a job to poll
+    #[serde(rename = "async")]
+    pub run_async: Option<bool>,
+}
+
 /// POST /api/v1/c2s/enrich/:lead_id
 /// Complete C2S integration flow:
 /// 1. Fetch lead from C2S
 /// 2. Enrich with Work API
 /// 3. Send enriched data back to C2S
+///
+/// With `?async=true` the flow runs as a job: 202 with its `job_id`, progress
+/// and result polled at `GET /api/v1/jobs/:id` with the same API key.
 pub async fn c2s_enrich_lead(
     State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
     Path(lead_id): Path<String>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    Query(query): Query<EnrichLeadQuery>,
+) -> Result<Response, AppError> {
+    if query.run_async.unwrap_or(false) {
+        let job_id =
+            crate::enrichment_jobs::enqueue(&state.db, &lead_id, key.map(|Extension(key)| key.id))
+                .await?;
+        let status_url = format!("/api/v1/jobs/{}", job_id);
+        tracing::info!("C2S Enrich Lead: {} queued as job #{}", lead_id, job_id);
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, status_url.clone())],
+            Json(json!({
+                "job_id": job_id,
+                "lead_id": lead_id,
+                "status": "queued",
+                "status_url": status_url,
+            })),
+        )
+            .into_response());
+    }
+
+    Ok(Json(enrich_c2s_lead(&state, &lead_id, None).await?).into_response())
+}
+
+/// Run the C2S enrichment flow of a lead, returning the endpoint's response
+///
+/// `job` is the async job running the flow, whose progress is recorded.
+pub async fn enrich_c2s_lead(
+    state: &Arc<AppState>,
+    lead_id: &str,
+    job: Option<i64>,
+) -> Result<serde_json::Value, AppError> {
+    use crate::enrichment_jobs::report_progress;
+
     tracing::info!("C2S Enrich Lead: {}", lead_id);
+    let lead_id = lead_id.to_string();
+    let mut timings = StepTimings::default();
 
     // Initialize services
-    let diretrix_service = DiretrixService::new(&state.config);
-    let work_api_service = WorkApiService::new(&state.config);
+    let diretrix_service = state.diretrix.as_ref();
+    let work_api_service = state.work_api.as_ref();
 
     // Step 1: Fetch lead from C2S
     tracing::info!("Step 1: Fetching lead from C2S");
+    report_progress(&state.db, job, "fetching_lead").await;
 
-    let gateway = state
-        .gateway_client
-        .as_ref()
-        .ok_or_else(|| AppError::InternalError("C2S Client not initialized".to_string()))?;
-
-    let response = gateway.get_lead(&lead_id).await?;
-    let lead_data: crate::services::C2SLeadResponse = serde_json::from_value(response)
-        .map_err(|e| AppError::ExternalApiError(format!("Failed to parse C2S response: {}", e)))?;
+    let lead_data = state.c2s.fetch_lead(&lead_id).await.context(
+        ErrorContext::new("c2s.fetch_lead")
+            .provider("c2s")
+            .id("lead_id", &lead_id),
+    )?;
 
     let customer = &lead_data.data.attributes.customer;
     tracing::info!(
@@ -396,8 +704,23 @@ pub async fn c2s_enrich_lead(
         customer.phone
     );
 
+    let attributes = &lead_data.data.attributes;
+    let property_refs = crate::property_refs::extract_property_refs(
+        attributes.product.prop_ref.as_deref(),
+        &[attributes.description.as_str()],
+    );
+    if let Err(e) = StepTimings::measure(
+        &mut timings.db_ms,
+        crate::property_refs::store_property_refs(&state.db, &lead_id, &property_refs),
+    )
+    .await
+    {
+        tracing::warn!("Failed to store property references: {}", e);
+    }
+
     // Step 2: Use Diretrix to find CPF from phone/email
     tracing::info!("Step 2: Using Diretrix to find CPF");
+    report_progress(&state.db, job, "finding_cpf").await;
     let _phone_opt = if !customer.phone.is_empty() {
         Some(customer.phone.as_str())
     } else {
@@ -411,13 +734,23 @@ pub async fn c2s_enrich_lead(
 
     // Parallel lookup - search by phone AND email separately
     let phone_lookup = if !customer.phone.is_empty() {
-        diretrix_service.search_by_phone(&customer.phone).await.ok()
+        StepTimings::measure(
+            &mut timings.diretrix_ms,
+            diretrix_service.search_by_phone(&customer.phone),
+        )
+        .await
+        .ok()
     } else {
         None
     };
 
     let email_lookup = if !customer.email.is_empty() {
-        diretrix_service.search_by_email(&customer.email).await.ok()
+        StepTimings::measure(
+            &mut timings.diretrix_ms,
+            diretrix_service.search_by_email(&customer.email),
+        )
+        .await
+        .ok()
     } else {
         None
     };
@@ -473,51 +806,44 @@ pub async fn c2s_enrich_lead(
         "Step 3: Enriching {} person(s) with Work API",
         cpf_list.len()
     );
+    report_progress(&state.db, job, "enriching").await;
 
-    let mut enriched_data = Vec::new();
-    for cpf in &cpf_list {
-        tracing::info!("Enriching CPF: {}", cpf);
-        match work_api_service.fetch_all_modules(cpf).await {
-            Ok(data) => enriched_data.push(data),
-            Err(e) => tracing::warn!("Failed to enrich CPF {}: {}", cpf, e),
-        }
-    }
-
-    if enriched_data.is_empty() {
-        return Err(AppError::ExternalApiError(
-            "No enrichment data available".to_string(),
-        ));
-    }
+    let work = StepTimings::measure(
+        &mut timings.work_api_ms,
+        crate::enrichment::enrich_cpfs_with_work_api(&cpf_list, work_api_service),
+    )
+    .await
+    .context(
+        ErrorContext::new("work_api.enrich_cpfs")
+            .provider("work_api")
+            .id("lead_id", &lead_id)
+            .id("cpfs", cpf_list.len()),
+    )?;
+    let enriched_data = work.aligned(&cpf_list);
 
     // Step 4: Format enriched data as message body
     tracing::info!(
-        "Step 4: Formatting enriched data (same_person: {})",
-        same_person
+        "Step 4: Formatting enriched data (same_person: {}, partial: {})",
+        same_person,
+        work.is_partial()
     );
-    let message_body = if same_person {
-        let enriched_msg = format_enriched_message(&customer.name, &enriched_data[0]);
-        tracing::info!("Enriched message length: {} chars", enriched_msg.len());
-        format!("📞📧 Telefone e e-mail da mesma pessoa\n\n{}", enriched_msg)
-    } else {
-        let mut combined_message =
-            String::from("⚠️ Telefone e e-mail relacionados a PESSOAS DIFERENTES!\n\n");
-
-        combined_message.push_str(&format!(
-            "═══ PESSOA 1 (Telefone: {}) ═══\n",
-            customer.phone
-        ));
-        combined_message.push_str(&format_enriched_message("", &enriched_data[0]));
-
-        if enriched_data.len() > 1 {
-            combined_message.push_str(&format!(
-                "\n\n═══ PESSOA 2 (Email: {}) ═══\n",
-                customer.email
-            ));
-            combined_message.push_str(&format_enriched_message("", &enriched_data[1]));
+    let mut message_body = crate::enrichment::format_enriched_message_body(
+        &customer.name,
+        &customer.phone,
+        &customer.email,
+        &enriched_data,
+        same_person,
+    );
+    message_body.push_str(&crate::enrichment::format_partial_notice(&work.missing));
+    if let Some(listing) = crate::enrichment::lead_listing(state, &lead_id).await {
+        message_body.push_str(&listing.format_section());
+        if let Some(affordability) = listing
+            .price
+            .and_then(|price| crate::affordability::Affordability::assess(&work.data[0], price))
+        {
+            message_body.push_str(&affordability.format_line());
         }
-
-        combined_message
-    };
+    }
 
     tracing::info!(
         "Step 4: Sending enriched data back to C2S (message length: {} chars)",
@@ -525,22 +851,29 @@ pub async fn c2s_enrich_lead(
     );
 
     // Step 5: Send back to C2S
-    let gateway = state
-        .gateway_client
-        .as_ref()
-        .ok_or_else(|| AppError::InternalError("C2S Client not initialized".to_string()))?;
-
-    tracing::info!("Using C2S Client to send message");
-    gateway.send_message(&lead_id, &message_body).await?;
+    report_progress(&state.db, job, "sending").await;
+    StepTimings::measure(
+        &mut timings.c2s_send_ms,
+        crate::lead_messages::send_deduplicated(state, &lead_id, &message_body, "c2s_enrich"),
+    )
+    .await
+    .context(
+        ErrorContext::new("c2s.send_message")
+            .provider("c2s")
+            .id("lead_id", &lead_id),
+    )?;
 
     // Step 6: Store enriched data in database
     tracing::info!("Step 5: Storing enriched data in database");
-    let storage = crate::db_storage::EnrichmentStorage::new(state.db.clone());
+    report_progress(&state.db, job, "storing").await;
+    let storage = &state.services.storage;
     let mut stored_entity_ids = Vec::new();
-    for (idx, cpf) in cpf_list.iter().enumerate() {
-        match storage
-            .store_enriched_person_with_lead(cpf, &enriched_data[idx], Some(&lead_id))
-            .await
+    for (cpf, work_data) in work.cpfs.iter().zip(&work.data) {
+        match StepTimings::measure(
+            &mut timings.db_ms,
+            storage.store_enriched_party(cpf, work_data, Some(&lead_id)),
+        )
+        .await
         {
             Ok(entity_id) => {
                 tracing::info!(
@@ -558,36 +891,17 @@ pub async fn c2s_enrich_lead(
         }
     }
 
-    Ok(Json(json!({
+    Ok(json!({
         "success": true,
         "lead_id": lead_id,
         "customer_name": customer.name,
         "enriched": true,
         "message_sent": true,
         "stored_in_db": stored_entity_ids.len(),
-        "entity_ids": stored_entity_ids
-    })))
-}
-
-/// Helper function to multiply currency values in a range string
-/// Example: "De R$ 1630 até R$ 4082" -> "De R$ 3097.00 até R$ 7755.80"
-fn multiply_range_values(range_str: &str, multiplier: f64) -> String {
-    use regex::Regex;
-
-    // Pattern to match currency values in the format "R$ 1630" or "R$ 4082"
-    let re = Regex::new(r"R\$\s*(\d+)").unwrap();
-
-    let result = re.replace_all(range_str, |caps: &regex::Captures| {
-        if let Some(num_str) = caps.get(1) {
-            if let Ok(value) = num_str.as_str().parse::<f64>() {
-                let adjusted = value * multiplier;
-                return format!("R$ {:.2}", adjusted);
-            }
-        }
-        caps[0].to_string()
-    });
-
-    result.to_string()
+        "entity_ids": stored_entity_ids,
+        "completeness": work.completeness(),
+        "timings": timings
+    }))
 }
 
 /// Format enriched Work API data into a readable message for C2S
@@ -622,15 +936,24 @@ pub fn format_enriched_message(customer_name: &str, work_data: &WorkApiCompleteR
         }
     }
 
+    let issues = crate::consistency::recorded_issues(work_data);
+    if !issues.is_empty() {
+        message.push_str(&crate::consistency::format_issues(&issues));
+    }
+
+    let partners = crate::partners::extract_partners(work_data);
+    if !partners.is_empty() {
+        message.push_str(&crate::partners::format_partners(&partners));
+    }
+
     // Financial data
     if let Some(dados_econ) = work_data.get("DadosEconomicos") {
         message.push_str("\n💰 DADOS FINANCEIROS\n");
 
         if let Some(renda_str) = dados_econ.get("renda").and_then(|v| v.as_str()) {
             // Multiply renda by 1.9
-            if let Ok(renda_val) = renda_str.replace(",", ".").parse::<f64>() {
-                let renda_adjusted = renda_val * 1.9;
-                message.push_str(&format!("Renda: R$ {:.2}\n", renda_adjusted));
+            if let Some(renda) = Brl::parse(renda_str) {
+                message.push_str(&format!("Renda: {}\n", renda.scaled(1.9)));
             } else {
                 message.push_str(&format!("Renda: R$ {}\n", renda_str));
             }
@@ -649,7 +972,7 @@ pub fn format_enriched_message(customer_name: &str, work_data: &WorkApiCompleteR
             {
                 // Parse and multiply the range values by 1.9
                 // Format: "De R$ 1630 até R$ 4082"
-                let faixa_adjusted = multiply_range_values(faixa, 1.9);
+                let faixa_adjusted = crate::money::scale_amounts(faixa, 1.9);
                 message.push_str(&format!("Faixa de Renda: {}\n", faixa_adjusted));
             }
         }
@@ -770,15 +1093,32 @@ pub async fn trigger_lead_processing(
         .ok_or_else(|| AppError::BadRequest("Missing 'id' parameter".to_string()))?;
 
     tracing::info!("=== Trigger Lead Processing: {} ===", lead_id);
+    let mut timings = StepTimings::default();
 
-    // ATOMIC DEDUPLICATION: Check if this lead is already being processed
-    // This prevents concurrent requests from processing the same lead multiple times
-    // NOTE: This uses in-memory cache which works for single instance deployments
-    // For multi-instance production, replace with Redis: SET lead:{id} NX EX 300
+    // ATOMIC DEDUPLICATION: claim the lead (SET NX EX semantics), so concurrent
+    // requests, on this instance or another one, process it only once
     let now = chrono::Utc::now().timestamp();
+    let claim = state
+        .dedup
+        .claim(
+            &crate::dedup::lead_key(lead_id),
+            now,
+            crate::dedup::LEAD_CLAIM_TTL,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            // Fail open: a dedup outage must not block lead processing
+            tracing::warn!(
+                "Lead dedup ({}) unavailable, processing lead {} anyway: {}",
+                state.dedup.name(),
+                lead_id,
+                e
+            );
+            crate::dedup::Claim::Acquired
+        });
 
-    if let Some(processing_since) = state.processing_leads_cache.get(lead_id).await {
-        let seconds_ago = now - processing_since;
+    if let crate::dedup::Claim::Held { since } = claim {
+        let seconds_ago = since.map_or(0, |since| now - since);
         tracing::warn!(
             "⏭ DUPLICATE REQUEST BLOCKED - Lead {} already being processed ({} seconds ago)",
             lead_id,
@@ -791,56 +1131,16 @@ pub async fn trigger_lead_processing(
             "duplicate_request": true
         })));
     }
-
-    // Mark lead as being processed IMMEDIATELY (first request wins in most cases)
-    state
-        .processing_leads_cache
-        .insert(lead_id.to_string(), now)
-        .await;
     tracing::info!("✓ Lead {} marked as processing at {}", lead_id, now);
 
-    // Small delay to allow cache propagation and catch racing requests
-    tokio::time::sleep(Duration::from_millis(100)).await;
-
-    // Double-check: if timestamp changed, another request won the race
-    if let Some(cached_time) = state.processing_leads_cache.get(lead_id).await {
-        if cached_time != now {
-            tracing::warn!(
-                "⏭ RACE CONDITION DETECTED - Another request won for lead {}. Backing off.",
-                lead_id
-            );
-            return Ok(Json(json!({
-                "success": true,
-                "message": "Another concurrent request is processing this lead. Request deduplicated.",
-                "lead_id": lead_id,
-                "duplicate_request": true
-            })));
-        }
-    }
-
     // Fetch lead from C2S
     tracing::info!("Step 1: Fetching lead from C2S");
 
-    let gateway = state
-        .gateway_client
-        .as_ref()
-        .ok_or_else(|| AppError::InternalError("C2S Client not initialized".to_string()))?;
-
-    let lead_data: crate::services::C2SLeadResponse = match gateway.get_lead(lead_id).await {
-        Ok(response) => match serde_json::from_value(response) {
-            Ok(data) => {
-                tracing::info!("✓ Successfully fetched lead from C2S");
-                data
-            }
-            Err(e) => {
-                tracing::error!("✗ Failed to parse C2S response: {}", e);
-                return Ok(Json(json!({
-                    "success": false,
-                    "message": format!("Failed to parse C2S response: {}", e),
-                    "lead_id": lead_id
-                })));
-            }
-        },
+    let lead_data = match state.c2s.fetch_lead(lead_id).await {
+        Ok(data) => {
+            tracing::info!("✓ Successfully fetched lead from C2S");
+            data
+        }
         Err(e) => {
             tracing::error!("✗ Failed to fetch lead from C2S: {}", e);
             return Ok(Json(json!({
@@ -860,22 +1160,32 @@ pub async fn trigger_lead_processing(
     );
 
     // Initialize services for enrichment
-    let diretrix_service = DiretrixService::new(&state.config);
-    let work_api_service = WorkApiService::new(&state.config);
-    let storage = crate::db_storage::EnrichmentStorage::new(state.db.clone());
+    let diretrix_service = state.diretrix.as_ref();
+    let work_api_service = state.work_api.as_ref();
+    let storage = &state.services.storage;
 
     // Step 2: Use Diretrix to find CPF from phone/email
     tracing::info!("Step 2: Using Diretrix to find CPF");
 
     // Parallel lookup - search by phone AND email separately
     let phone_lookup = if !customer.phone.is_empty() {
-        diretrix_service.search_by_phone(&customer.phone).await.ok()
+        StepTimings::measure(
+            &mut timings.diretrix_ms,
+            diretrix_service.search_by_phone(&customer.phone),
+        )
+        .await
+        .ok()
     } else {
         None
     };
 
     let email_lookup = if !customer.email.is_empty() {
-        diretrix_service.search_by_email(&customer.email).await.ok()
+        StepTimings::measure(
+            &mut timings.diretrix_ms,
+            diretrix_service.search_by_email(&customer.email),
+        )
+        .await
+        .ok()
     } else {
         None
     };
@@ -923,19 +1233,26 @@ pub async fn trigger_lead_processing(
             return Ok(Json(json!({
                 "success": false,
                 "message": "Could not find CPF from phone or email",
-                "lead_id": lead_id
+                "lead_id": lead_id,
+                "timings": timings
             })));
         }
     };
 
     // Step 3: Enrich each CPF with Work API (with deduplication)
     tracing::info!("Step 3: Enriching {} CPF(s) with Work API", cpf_list.len());
-    let mut enriched_data = Vec::new();
     let mut cpfs_to_process = Vec::new();
 
     // Check cache for recently processed CPFs
     for cpf in &cpf_list {
-        if let Some(timestamp) = state.recent_cpf_cache.get(cpf).await {
+        let recent = match state.dedup.get(&crate::dedup::cpf_key(cpf)).await {
+            Ok(recent) => recent,
+            Err(e) => {
+                tracing::warn!("CPF dedup ({}) unavailable: {}", state.dedup.name(), e);
+                None
+            }
+        };
+        if let Some(timestamp) = recent {
             let now = chrono::Utc::now().timestamp();
             let seconds_ago = now - timestamp;
 
@@ -958,32 +1275,44 @@ pub async fn trigger_lead_processing(
             "message": "CPFs already recently processed (deduplication)",
             "lead_id": lead_id,
             "cpfs_processed": cpf_list,
-            "entities_stored": 0
+            "entities_stored": 0,
+            "timings": timings
         })));
     }
 
     // Enrich only CPFs that haven't been recently processed
-    for cpf in &cpfs_to_process {
-        match work_api_service.fetch_all_modules(cpf).await {
-            Ok(data) => {
-                tracing::info!("✓ Enriched CPF: {}", cpf);
-                enriched_data.push(data);
-                // Mark as processed immediately after successful enrichment
-                let now = chrono::Utc::now().timestamp();
-                state.recent_cpf_cache.insert(cpf.clone(), now).await;
-            }
-            Err(e) => {
-                tracing::error!("✗ Failed to enrich CPF {}: {}", cpf, e);
-            }
+    let work = match StepTimings::measure(
+        &mut timings.work_api_ms,
+        crate::enrichment::enrich_cpfs_with_work_api(&cpfs_to_process, work_api_service),
+    )
+    .await
+    {
+        Ok(work) => work,
+        Err(e) => {
+            tracing::error!("✗ Failed to enrich any CPF: {}", e);
+            return Ok(Json(json!({
+                "success": false,
+                "message": "Failed to enrich any CPFs",
+                "lead_id": lead_id,
+                "timings": timings
+            })));
+        }
+    };
+    // Mark as processed right after a successful enrichment
+    for cpf in &work.cpfs {
+        tracing::info!("✓ Enriched CPF: {}", cpf);
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = state
+            .dedup
+            .set(
+                &crate::dedup::cpf_key(cpf),
+                now,
+                crate::dedup::RECENT_CPF_TTL,
+            )
+            .await
+        {
+            tracing::warn!("Failed to record processed CPF {}: {}", cpf, e);
         }
-    }
-
-    if enriched_data.is_empty() {
-        return Ok(Json(json!({
-            "success": false,
-            "message": "Failed to enrich any CPFs",
-            "lead_id": lead_id
-        })));
     }
 
     // Step 4: Format enriched message
@@ -992,17 +1321,22 @@ pub async fn trigger_lead_processing(
 
     // Add phone/email match indicator if both were found
     if same_person && phone_cpf.is_some() && email_cpf.is_some() {
-        full_message.push_str("📞📧 Telefone e e-mail da mesma pessoa\n\n");
+        full_message.push_str(&crate::templates::render(
+            crate::templates::SAME_PERSON_HEADER,
+            &[("customer_name", &customer.name)],
+        ));
+        full_message.push_str("\n\n");
     }
 
     // Format enriched data for each person
-    for (idx, data) in enriched_data.iter().enumerate() {
+    for (idx, data) in work.data.iter().enumerate() {
         if idx > 0 {
             full_message.push_str("\n---\n\n");
         }
         let formatted = format_enriched_message(&customer.name, data);
         full_message.push_str(&formatted);
     }
+    full_message.push_str(&crate::enrichment::format_partial_notice(&work.missing));
 
     tracing::info!("Formatted message length: {} chars", full_message.len());
 
@@ -1010,10 +1344,12 @@ pub async fn trigger_lead_processing(
     tracing::info!("Step 5: Storing enriched data in database");
     let mut stored_entity_ids: Vec<uuid::Uuid> = Vec::new();
 
-    for (idx, cpf) in cpfs_to_process.iter().enumerate() {
-        match storage
-            .store_enriched_person_with_lead(cpf, &enriched_data[idx], Some(lead_id))
-            .await
+    for (cpf, work_data) in work.cpfs.iter().zip(&work.data) {
+        match StepTimings::measure(
+            &mut timings.db_ms,
+            storage.store_enriched_party(cpf, work_data, Some(lead_id)),
+        )
+        .await
         {
             Ok(entity_id) => {
                 tracing::info!(
@@ -1033,13 +1369,11 @@ pub async fn trigger_lead_processing(
     // Step 6: Send enriched data back to C2S
     tracing::info!("Step 6: Sending enriched data to C2S");
 
-    let gateway = state
-        .gateway_client
-        .as_ref()
-        .ok_or_else(|| AppError::InternalError("C2S Client not initialized".to_string()))?;
-
-    tracing::info!("Using C2S Client to send message");
-    let send_result = gateway.send_message(lead_id, &full_message).await;
+    let send_result = StepTimings::measure(
+        &mut timings.c2s_send_ms,
+        crate::lead_messages::send_deduplicated(&state, lead_id, &full_message, "lead_processing"),
+    )
+    .await;
 
     match send_result {
         Ok(_) => {
@@ -1052,7 +1386,9 @@ pub async fn trigger_lead_processing(
                 "message": format!("Successfully processed and enriched lead. Stored {} entities in database.", stored_entity_ids.len()),
                 "lead_id": lead_id,
                 "cpfs_processed": cpf_list,
-                "entities_stored": stored_entity_ids.len()
+                "entities_stored": stored_entity_ids.len(),
+                "completeness": work.completeness(),
+                "timings": timings
             })))
         }
         Err(e) => {
@@ -1060,7 +1396,8 @@ pub async fn trigger_lead_processing(
             Ok(Json(json!({
                 "success": false,
                 "message": format!("Enriched data but failed to send to C2S: {}", e),
-                "lead_id": lead_id
+                "lead_id": lead_id,
+                "timings": timings
             })))
         }
     }
@@ -0,0 +1,243 @@
+//! Identity resolution: scoring candidate parties for a lead
+//!
+//! Diretrix returns every person registered to a phone or email, often more
+//! than one. Instead of taking the first result, each candidate is scored on
+//! name similarity, contacts shared with the lead and birth date, and only
+//! candidates at or above `IDENTITY_MATCH_THRESHOLD` are linked automatically.
+
+use crate::phonetic::phonetic_key;
+use crate::services::normalize_name;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Weight of name similarity (0-1) in the final score
+const NAME_WEIGHT: f32 = 0.5;
+/// Score for the first contact (phone/email) shared with the lead
+const FIRST_CONTACT_SCORE: f32 = 0.4;
+/// Score for each additional shared contact
+const EXTRA_CONTACT_SCORE: f32 = 0.1;
+/// Bonus when birth dates agree, penalty when they disagree
+const BIRTH_DATE_MATCH_SCORE: f32 = 0.1;
+const BIRTH_DATE_MISMATCH_PENALTY: f32 = 0.5;
+/// Credit for a name word that only matches phonetically ("Luis" vs "Luiz")
+const PHONETIC_TOKEN_SCORE: f32 = 0.8;
+
+/// Name particles ignored when comparing names
+const PARTICLES: [&str; 6] = ["DA", "DAS", "DE", "DO", "DOS", "E"];
+
+/// What the lead tells us about the person
+#[derive(Debug, Clone, Default)]
+pub struct LeadIdentity<'a> {
+    pub name: Option<&'a str>,
+    pub phone: Option<&'a str>,
+    pub email: Option<&'a str>,
+    pub birth_date: Option<NaiveDate>,
+}
+
+/// A party the lead could belong to
+#[derive(Debug, Clone, Default)]
+pub struct Candidate {
+    pub cpf: String,
+    pub name: String,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
+    pub birth_date: Option<NaiveDate>,
+}
+
+/// Score of one candidate, recorded with the enrichment
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchScore {
+    pub cpf: String,
+    pub score: f32,
+    pub name_similarity: f32,
+    pub shared_contacts: usize,
+    pub birth_date_match: Option<bool>,
+}
+
+/// Score how likely `candidate` is the person behind `lead` (0-1)
+pub fn score_candidate(lead: &LeadIdentity, candidate: &Candidate) -> MatchScore {
+    let name_similarity = lead
+        .name
+        .map(|name| name_similarity(name, &candidate.name))
+        .unwrap_or(0.0);
+
+    let shared_phone = lead.phone.is_some_and(|phone| {
+        let phone = digits(phone);
+        candidate
+            .phones
+            .iter()
+            .any(|p| same_phone(&digits(p), &phone))
+    });
+    let shared_email = lead.email.is_some_and(|email| {
+        candidate
+            .emails
+            .iter()
+            .any(|e| e.trim().eq_ignore_ascii_case(email.trim()))
+    });
+    let shared_contacts = usize::from(shared_phone) + usize::from(shared_email);
+
+    let birth_date_match = match (lead.birth_date, candidate.birth_date) {
+        (Some(a), Some(b)) => Some(a == b),
+        _ => None,
+    };
+
+    let mut score = NAME_WEIGHT * name_similarity;
+    if shared_contacts > 0 {
+        score += FIRST_CONTACT_SCORE + EXTRA_CONTACT_SCORE * (shared_contacts - 1) as f32;
+    }
+    match birth_date_match {
+        Some(true) => score += BIRTH_DATE_MATCH_SCORE,
+        Some(false) => score -= BIRTH_DATE_MISMATCH_PENALTY,
+        None => {}
+    }
+
+    MatchScore {
+        cpf: candidate.cpf.clone(),
+        score: score.clamp(0.0, 1.0),
+        name_similarity,
+        shared_contacts,
+        birth_date_match,
+    }
+}
+
+/// Highest-scoring candidate, if it reaches `threshold`
+///
+/// Candidates below the threshold are logged and never linked.
+pub fn best_match(
+    lead: &LeadIdentity,
+    candidates: &[Candidate],
+    threshold: f32,
+) -> Option<MatchScore> {
+    let best = candidates
+        .iter()
+        .map(|candidate| score_candidate(lead, candidate))
+        .max_by(|a, b| a.score.total_cmp(&b.score))?;
+
+    if best.score < threshold {
+        tracing::info!(
+            "Best identity match {} scored {:.2} (< {:.2} threshold), not linking",
+            best.cpf,
+            best.score,
+            threshold
+        );
+        return None;
+    }
+
+    Some(best)
+}
+
+/// Share of the lead's name words found in the candidate name (0-1)
+///
+/// Leads often carry only a first name ("Maria"), so words missing from the
+/// lead are not penalized.
+pub fn name_similarity(lead_name: &str, candidate_name: &str) -> f32 {
+    let lead_tokens = name_tokens(lead_name);
+    if lead_tokens.is_empty() {
+        return 0.0;
+    }
+    let candidate_tokens = name_tokens(candidate_name);
+    let candidate_keys: Vec<String> = candidate_tokens.iter().map(|t| phonetic_key(t)).collect();
+
+    let matched: f32 = lead_tokens
+        .iter()
+        .map(|token| {
+            if candidate_tokens.contains(token) {
+                1.0
+            } else if candidate_keys.contains(&phonetic_key(token)) {
+                PHONETIC_TOKEN_SCORE
+            } else {
+                0.0
+            }
+        })
+        .sum();
+
+    matched / lead_tokens.len() as f32
+}
+
+fn name_tokens(name: &str) -> Vec<String> {
+    normalize_name(name)
+        .split(' ')
+        .filter(|t| t.len() > 1 && !PARTICLES.contains(t))
+        .map(str::to_string)
+        .collect()
+}
+
+fn digits(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// Compare phones ignoring the country code (+55) when only one side has it
+fn same_phone(a: &str, b: &str) -> bool {
+    !a.is_empty()
+        && !b.is_empty()
+        && (a == b || a.trim_start_matches("55") == b.trim_start_matches("55"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(cpf: &str, name: &str, phone: &str) -> Candidate {
+        Candidate {
+            cpf: cpf.to_string(),
+            name: name.to_string(),
+            phones: vec![phone.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_name_similarity() {
+        assert_eq!(name_similarity("Maria", "MARIA APARECIDA DA SILVA"), 1.0);
+        assert_eq!(name_similarity("Luis Souza", "LUIZ CARLOS DE SOUZA"), 0.9);
+        assert_eq!(name_similarity("Pedro", "MARIA SILVA"), 0.0);
+        assert_eq!(name_similarity("", "MARIA SILVA"), 0.0);
+    }
+
+    #[test]
+    fn test_best_match_prefers_name_over_result_order() {
+        let lead = LeadIdentity {
+            name: Some("Ana Paula"),
+            phone: Some("+5511987654321"),
+            ..Default::default()
+        };
+        // Diretrix returned the spouse first for a shared household phone
+        let candidates = [
+            candidate("11111111111", "JOSE ROBERTO LIMA", "11987654321"),
+            candidate("22222222222", "ANA PAULA LIMA", "11987654321"),
+        ];
+
+        let best = best_match(&lead, &candidates, 0.6).expect("match");
+        assert_eq!(best.cpf, "22222222222");
+        assert_eq!(best.shared_contacts, 1);
+        assert!((best.score - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_best_match_below_threshold() {
+        let lead = LeadIdentity {
+            name: Some("Carla"),
+            phone: Some("11987654321"),
+            ..Default::default()
+        };
+        let candidates = [candidate("11111111111", "JOSE ROBERTO LIMA", "11987654321")];
+
+        assert!(best_match(&lead, &candidates, 0.6).is_none());
+    }
+
+    #[test]
+    fn test_birth_date_mismatch_penalized() {
+        let lead = LeadIdentity {
+            name: Some("Ana Paula"),
+            phone: Some("11987654321"),
+            birth_date: NaiveDate::from_ymd_opt(1990, 1, 1),
+            ..Default::default()
+        };
+        let mut other = candidate("22222222222", "ANA PAULA LIMA", "11987654321");
+        other.birth_date = NaiveDate::from_ymd_opt(1955, 6, 30);
+
+        let score = score_candidate(&lead, &other);
+        assert_eq!(score.birth_date_match, Some(false));
+        assert!(score.score < 0.6);
+    }
+}
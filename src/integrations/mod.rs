@@ -1,8 +1,25 @@
 // External integrations (HTTP clients, webhooks, Google Ads, etc.)
+pub mod clients {
+    pub use crate::clients::*;
+}
+
+#[allow(deprecated)]
 pub mod gateway_client {
     pub use crate::gateway_client::*;
 }
 
+pub mod c2s_accounts {
+    pub use crate::c2s_accounts::*;
+}
+
+pub mod c2s_fields {
+    pub use crate::c2s_fields::*;
+}
+
+pub mod google_ads_forms {
+    pub use crate::google_ads_forms::*;
+}
+
 pub mod google_ads_models {
     pub use crate::google_ads_models::*;
 }
@@ -10,3 +27,31 @@ pub mod google_ads_models {
 pub mod webhook_models {
     pub use crate::webhook_models::*;
 }
+
+pub mod whatsapp_models {
+    pub use crate::whatsapp_models::*;
+}
+
+pub mod listings {
+    pub use crate::listings::*;
+}
+
+pub mod rdstation {
+    pub use crate::rdstation::*;
+}
+
+pub mod salesforce {
+    pub use crate::salesforce::*;
+}
+
+pub mod google_sheets {
+    pub use crate::google_sheets::*;
+}
+
+pub mod sms {
+    pub use crate::sms::*;
+}
+
+pub mod shadow {
+    pub use crate::shadow::*;
+}
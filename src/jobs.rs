@@ -0,0 +1,450 @@
+//! Persistent job queue in Postgres
+//!
+//! Background work that must survive a restart is stored in the `jobs` table
+//! (migration 044) rather than an in-process channel. Workers of every
+//! instance claim due jobs of their kind with `FOR UPDATE SKIP LOCKED`, so a
+//! job runs on one worker at a time. A job left 'running' by a crashed worker
+//! is claimed again once its lock is older than `STALE_LOCK_SECS`; a live
+//! worker keeps its lock fresh with `with_heartbeat`, and the outcome of a
+//! run is only recorded while the job is still locked by that run. A failed
+//! run is retried after `retry_delay` until the job's `max_attempts`, then the
+//! job is marked 'failed' and kept for inspection. Completed jobs are removed
+//! after `COMPLETED_RETENTION_DAYS`. A job can record the step it reached
+//! (`set_progress`) and its result (migration 045), read back by `status`
+//! for the API key that queued it (migration 056).
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
+use std::future::Future;
+use std::time::Duration;
+
+/// Enrichment of a stored C2S webhook event (see `webhook_queue`)
+pub const WEBHOOK_ENRICHMENT: &str = "webhook_enrichment";
+
+/// C2S lead enrichment requested through the async API (see `enrichment_jobs`)
+pub const LEAD_ENRICHMENT: &str = "lead_enrichment";
+
+/// Lead import or batch enrichment run in the background (see `batch_jobs`)
+pub const BATCH: &str = "batch";
+
+/// Delivery of an event to a webhook subscriber (see `subscribers`)
+pub const SUBSCRIBER_NOTIFICATION: &str = "subscriber_notification";
+
+/// Jobs left 'running' longer than this (crashed worker) are claimed again
+const STALE_LOCK_SECS: f64 = 900.0;
+
+/// How often a running job's lock is refreshed, well within `STALE_LOCK_SECS`
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Days completed jobs are kept
+const COMPLETED_RETENTION_DAYS: i32 = 7;
+
+/// Delay before the next run, after `attempts` failed ones
+///
+/// 30s, 1min, 2min, ... capped at 15 minutes.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 6) as u32 - 1;
+    Duration::from_secs(30 * 2u64.pow(exponent)).min(Duration::from_secs(900))
+}
+
+/// Identifier of a worker of this instance, stored in `locked_by`
+pub fn worker_id(index: usize) -> String {
+    let instance =
+        std::env::var("FLY_MACHINE_ID").unwrap_or_else(|_| format!("pid-{}", std::process::id()));
+    format!("{}#{}", instance, index)
+}
+
+/// A job claimed by a worker
+#[derive(Debug, sqlx::FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    /// Runs of the job, this one included
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Worker running the job
+    pub locked_by: String,
+}
+
+/// Jobs of a kind by status, for the admin dashboard
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+pub struct JobCounts {
+    pub queued: i64,
+    pub running: i64,
+    /// Gave up after `max_attempts` runs
+    pub failed: i64,
+}
+
+/// State of a job, as polled by API callers
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JobStatus {
+    pub id: i64,
+    pub kind: String,
+    /// queued, running, completed or failed
+    pub status: String,
+    /// Last step reached by the worker
+    pub progress: Option<String>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub result: Option<Value>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Jobs whose status a caller may read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Admin key or token
+    All,
+    /// Jobs queued by this API key (None: queued without a key)
+    QueuedBy(Option<i64>),
+}
+
+/// Queue a job, due now
+///
+/// Takes any executor, so a job can be queued in the transaction storing the
+/// data it works on. `api_key_id` is the key of the request queueing it, if
+/// any (see `Visibility`).
+pub async fn enqueue<'e>(
+    executor: impl PgExecutor<'e>,
+    kind: &str,
+    payload: &Value,
+    max_attempts: i32,
+    api_key_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let (id,): (i64,) = sqlx::query_as(
+        r#"
+        INSERT INTO jobs (kind, payload, max_attempts, api_key_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(kind)
+    .bind(payload)
+    .bind(max_attempts.max(1))
+    .bind(api_key_id)
+    .fetch_one(executor)
+    .timed("jobs.enqueue")
+    .await
+    .context(ErrorContext::db("jobs.enqueue").id("kind", kind))?;
+
+    Ok(id)
+}
+
+/// Claim the oldest due job of a kind (or a stale one), if any
+pub async fn claim(pool: &PgPool, kind: &str, worker: &str) -> Result<Option<Job>, AppError> {
+    sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, locked_at = now(), locked_by = $2
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE kind = $1
+              AND ((status = 'queued' AND run_after <= now())
+                OR (status = 'running' AND locked_at < now() - make_interval(secs => $3)
+                    AND attempts < max_attempts))
+            ORDER BY run_after
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, payload, attempts, max_attempts, locked_by
+        "#,
+    )
+    .bind(kind)
+    .bind(worker)
+    .bind(STALE_LOCK_SECS)
+    .fetch_optional(pool)
+    .timed("jobs.claim")
+    .await
+    .context(ErrorContext::db("jobs.claim").id("kind", kind))
+}
+
+/// Refresh the lock of a running job, so it is not claimed again as stale
+///
+/// Returns false when the job is no longer locked by this run.
+pub async fn heartbeat(pool: &PgPool, job: &Job) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs SET locked_at = now()
+        WHERE id = $1 AND status = 'running' AND locked_by = $2 AND attempts = $3
+        "#,
+    )
+    .bind(job.id)
+    .bind(&job.locked_by)
+    .bind(job.attempts)
+    .execute(pool)
+    .timed("jobs.heartbeat")
+    .await
+    .context(ErrorContext::db("jobs.heartbeat").id("job_id", job.id))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Run a claimed job's work, refreshing its lock every `HEARTBEAT_INTERVAL`
+///
+/// Heartbeat failures are logged: at worst the job is claimed again and the
+/// outcome of this run is dropped (see `complete`).
+pub async fn with_heartbeat<F: Future>(pool: &PgPool, job: &Job, work: F) -> F::Output {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick completes immediately, the job was just claimed
+    interval.tick().await;
+    tokio::pin!(work);
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            _ = interval.tick() => match heartbeat(pool, job).await {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!(
+                    "Job #{} ({}) lost its lock while running",
+                    job.id,
+                    job.kind
+                ),
+                Err(e) => tracing::warn!("Failed to refresh the lock of job #{}: {}", job.id, e),
+            },
+        }
+    }
+}
+
+/// Record the step a running job reached
+pub async fn set_progress(pool: &PgPool, id: i64, progress: &str) -> Result<(), AppError> {
+    sqlx::query("UPDATE jobs SET progress = $2 WHERE id = $1")
+        .bind(id)
+        .bind(progress)
+        .execute(pool)
+        .timed("jobs.set_progress")
+        .await
+        .context(ErrorContext::db("jobs.set_progress").id("job_id", id))?;
+    Ok(())
+}
+
+/// The job ran to completion, with its result when it has one
+///
+/// Returns false, recording nothing, when the job is no longer locked by this
+/// run: its lock went stale and another worker claimed it again.
+pub async fn complete(pool: &PgPool, job: &Job, result: Option<&Value>) -> Result<bool, AppError> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = 'completed', finished_at = now(), locked_at = NULL, last_error = NULL,
+            result = $4
+        WHERE id = $1 AND status = 'running' AND locked_by = $2 AND attempts = $3
+        "#,
+    )
+    .bind(job.id)
+    .bind(&job.locked_by)
+    .bind(job.attempts)
+    .bind(result)
+    .execute(pool)
+    .timed("jobs.complete")
+    .await
+    .context(ErrorContext::db("jobs.complete").id("job_id", job.id))?
+    .rows_affected()
+        > 0;
+    if !updated {
+        lost_lock(job, "completion");
+    }
+    Ok(updated)
+}
+
+/// The run failed: retry later, or mark the job 'failed' after its last attempt
+///
+/// Returns whether the job was rescheduled by this run.
+pub async fn retry_or_fail(pool: &PgPool, job: &Job, error: &AppError) -> Result<bool, AppError> {
+    if job.attempts >= job.max_attempts {
+        fail(pool, job, &error.chain_message()).await?;
+        return Ok(false);
+    }
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = 'queued', last_error = $4, locked_at = NULL, locked_by = NULL,
+            run_after = now() + make_interval(secs => $5)
+        WHERE id = $1 AND status = 'running' AND locked_by = $2 AND attempts = $3
+        "#,
+    )
+    .bind(job.id)
+    .bind(&job.locked_by)
+    .bind(job.attempts)
+    .bind(error.chain_message())
+    .bind(retry_delay(job.attempts).as_secs_f64())
+    .execute(pool)
+    .timed("jobs.reschedule")
+    .await
+    .context(ErrorContext::db("jobs.reschedule").id("job_id", job.id))?
+    .rows_affected()
+        > 0;
+    if !updated {
+        lost_lock(job, "retry");
+    }
+    Ok(updated)
+}
+
+/// Give up on a job (not retried)
+///
+/// Like `complete`, a no-op once the job is no longer locked by this run.
+pub async fn fail(pool: &PgPool, job: &Job, error: &str) -> Result<(), AppError> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = 'failed', last_error = $4, finished_at = now(), locked_at = NULL
+        WHERE id = $1 AND status = 'running' AND locked_by = $2 AND attempts = $3
+        "#,
+    )
+    .bind(job.id)
+    .bind(&job.locked_by)
+    .bind(job.attempts)
+    .bind(error)
+    .execute(pool)
+    .timed("jobs.fail")
+    .await
+    .context(ErrorContext::db("jobs.fail").id("job_id", job.id))?
+    .rows_affected()
+        > 0;
+    if !updated {
+        lost_lock(job, "failure");
+    }
+    Ok(())
+}
+
+fn lost_lock(job: &Job, outcome: &str) {
+    tracing::warn!(
+        "Job #{} ({}) was claimed again by another worker, {} of run {} by {} dropped",
+        job.id,
+        job.kind,
+        outcome,
+        job.attempts,
+        job.locked_by
+    );
+}
+
+/// Fail stale jobs without attempts left and remove old completed ones
+///
+/// Run periodically by the queue owning the kind. Returns the number of jobs
+/// marked 'failed'.
+pub async fn sweep(pool: &PgPool, kind: &str) -> Result<u64, AppError> {
+    let abandoned = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = 'failed', finished_at = now(), locked_at = NULL,
+            last_error = COALESCE(last_error, 'Worker lost while running the job')
+        WHERE kind = $1 AND status = 'running'
+          AND locked_at < now() - make_interval(secs => $2)
+          AND attempts >= max_attempts
+        "#,
+    )
+    .bind(kind)
+    .bind(STALE_LOCK_SECS)
+    .execute(pool)
+    .timed("jobs.fail_abandoned")
+    .await
+    .context(ErrorContext::db("jobs.fail_abandoned").id("kind", kind))?
+    .rows_affected();
+
+    sqlx::query(
+        r#"
+        DELETE FROM jobs
+        WHERE kind = $1 AND status = 'completed'
+          AND finished_at < now() - make_interval(days => $2)
+        "#,
+    )
+    .bind(kind)
+    .bind(COMPLETED_RETENTION_DAYS)
+    .execute(pool)
+    .timed("jobs.prune_completed")
+    .await
+    .context(ErrorContext::db("jobs.prune_completed").id("kind", kind))?;
+
+    Ok(abandoned)
+}
+
+/// Due or running jobs of a kind (backlog waiting for a worker)
+pub async fn backlog(pool: &PgPool, kind: &str) -> Result<i64, AppError> {
+    let (count,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM jobs
+        WHERE kind = $1 AND status IN ('queued', 'running')
+        "#,
+    )
+    .bind(kind)
+    .fetch_one(pool)
+    .timed("jobs.backlog")
+    .await
+    .context(ErrorContext::db("jobs.backlog").id("kind", kind))?;
+    Ok(count)
+}
+
+/// Jobs of a kind by status
+pub async fn counts(pool: &PgPool, kind: &str) -> Result<JobCounts, AppError> {
+    sqlx::query_as::<_, JobCounts>(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE status = 'queued') AS queued,
+            COUNT(*) FILTER (WHERE status = 'running') AS running,
+            COUNT(*) FILTER (WHERE status = 'failed') AS failed
+        FROM jobs
+        WHERE kind = $1 AND status IN ('queued', 'running', 'failed')
+        "#,
+    )
+    .bind(kind)
+    .fetch_one(pool)
+    .timed("jobs.counts")
+    .await
+    .context(ErrorContext::db("jobs.counts").id("kind", kind))
+}
+
+/// A job of one of the given kinds, `None` when unknown (or pruned) or not
+/// visible to the caller
+pub async fn status(
+    pool: &PgPool,
+    kinds: &[&str],
+    id: i64,
+    visibility: Visibility,
+) -> Result<Option<JobStatus>, AppError> {
+    let (all, api_key_id) = match visibility {
+        Visibility::All => (true, None),
+        Visibility::QueuedBy(api_key_id) => (false, api_key_id),
+    };
+    sqlx::query_as::<_, JobStatus>(
+        r#"
+        SELECT id, kind, status, progress, attempts, max_attempts, last_error, result,
+               created_at, finished_at
+        FROM jobs
+        WHERE id = $1 AND kind = ANY($2)
+          AND ($3 OR api_key_id IS NOT DISTINCT FROM $4)
+        "#,
+    )
+    .bind(id)
+    .bind(kinds)
+    .bind(all)
+    .bind(api_key_id)
+    .fetch_optional(pool)
+    .timed("jobs.status")
+    .await
+    .context(ErrorContext::db("jobs.status").id("job_id", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles_then_caps() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(5), Duration::from_secs(480));
+        assert_eq!(retry_delay(6), Duration::from_secs(900));
+        assert_eq!(retry_delay(20), Duration::from_secs(900));
+    }
+
+    #[test]
+    fn test_worker_ids_are_distinct() {
+        assert_ne!(worker_id(0), worker_id(1));
+    }
+}
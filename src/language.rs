@@ -0,0 +1,169 @@
+//! Language of a lead, detected from its own text
+//!
+//! Most leads write in Portuguese; international ones sometimes write in
+//! English or Spanish. `detect` counts common words of each language in the
+//! lead's description and messages and only answers when one language
+//! clearly wins, so short or mixed texts stay in Portuguese. The C2S message
+//! headers are then rendered in that language (`templates::render_in`).
+
+use crate::templates::Locale;
+
+/// Minimum number of common words of the winning language
+const MIN_HITS: usize = 2;
+
+// Words shared by Portuguese and Spanish ("de", "que", "casa"...) are left
+// out: they tell nothing between the two.
+const PORTUGUESE: &[&str] = &[
+    "e",
+    "do",
+    "da",
+    "dos",
+    "das",
+    "na",
+    "em",
+    "um",
+    "uma",
+    "com",
+    "não",
+    "é",
+    "olá",
+    "ola",
+    "oi",
+    "obrigado",
+    "obrigada",
+    "gostaria",
+    "quero",
+    "tenho",
+    "estou",
+    "você",
+    "pode",
+    "mais",
+    "informações",
+    "interessado",
+    "interessada",
+    "interesse",
+    "preço",
+    "valor",
+    "apartamento",
+    "imóvel",
+    "visita",
+];
+
+const SPANISH: &[&str] = &[
+    "y",
+    "el",
+    "los",
+    "las",
+    "del",
+    "en",
+    "un",
+    "una",
+    "con",
+    "es",
+    "hola",
+    "gracias",
+    "quiero",
+    "quisiera",
+    "tengo",
+    "estoy",
+    "usted",
+    "puede",
+    "más",
+    "información",
+    "interesado",
+    "interesada",
+    "precio",
+    "departamento",
+    "piso",
+    "inmueble",
+    "visitar",
+];
+
+const ENGLISH: &[&str] = &[
+    "the",
+    "and",
+    "i",
+    "you",
+    "is",
+    "are",
+    "to",
+    "of",
+    "for",
+    "with",
+    "my",
+    "in",
+    "hi",
+    "hello",
+    "thanks",
+    "thank",
+    "would",
+    "like",
+    "want",
+    "please",
+    "can",
+    "could",
+    "more",
+    "information",
+    "interested",
+    "price",
+    "apartment",
+    "property",
+    "visit",
+];
+
+/// Language of a text, `None` when it is not clearly English or Spanish
+/// (or clearly Portuguese)
+pub fn detect(text: &str) -> Option<Locale> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let hits = |list: &[&str]| words.iter().filter(|w| list.contains(&w.as_str())).count();
+
+    let mut scores = [
+        (Locale::Pt, hits(PORTUGUESE)),
+        (Locale::En, hits(ENGLISH)),
+        (Locale::Es, hits(SPANISH)),
+    ];
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+    let (best, best_hits) = scores[0];
+    (best_hits >= MIN_HITS && best_hits > scores[1].1).then_some(best)
+}
+
+/// Locale of a lead's texts (Portuguese unless clearly another language)
+pub fn lead_locale<'a>(texts: impl IntoIterator<Item = &'a str>) -> Locale {
+    let text = texts.into_iter().collect::<Vec<_>>().join("\n");
+    detect(&text).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_english_and_spanish() {
+        assert_eq!(
+            detect("Hi, I would like more information about the apartment, thanks!"),
+            Some(Locale::En)
+        );
+        assert_eq!(
+            detect("Hola, estoy interesado en el departamento. ¿Puede enviarme más información?"),
+            Some(Locale::Es)
+        );
+        assert_eq!(
+            detect("Olá, gostaria de mais informações sobre o apartamento"),
+            Some(Locale::Pt)
+        );
+    }
+
+    #[test]
+    fn test_short_or_ambiguous_texts_are_not_detected() {
+        assert_eq!(detect(""), None);
+        assert_eq!(detect("Casa"), None);
+        assert_eq!(detect("Apartamento 3 quartos, 120m²"), None);
+        // One word of each language
+        assert_eq!(detect("hello gracias obrigado"), None);
+        assert_eq!(lead_locale(["", "ok"]), Locale::Pt);
+    }
+}
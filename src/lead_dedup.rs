@@ -0,0 +1,199 @@
+//! Duplicate C2S lead detection
+//!
+//! A person filling a Google Ads form twice should not become two C2S leads.
+//! For tenants listed in `LEAD_DEDUP_TENANTS` (C2S account names, see
+//! `c2s_accounts`), a new lead is first matched by phone / email against:
+//!
+//! - leads created by this service for the same contact in the last
+//!   `LEAD_DEDUP_WINDOW_DAYS` days (`c2s_lead_contacts`, migration 049, and
+//!   imported leads, `c2s_imported_leads`),
+//! - open leads of the contact in C2S (`C2sApi::find_leads`); leads whose
+//!   status is closed (`CLOSED_STATUSES`) are ignored.
+//!
+//! On a match, the description of the new lead is appended to the existing
+//! one as a message. A failed lookup never blocks the lead: it is created.
+
+use crate::clients::C2sApi;
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use crate::lead_import::contact_hashes;
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// Lead status aliases of closed C2S leads
+pub const CLOSED_STATUSES: &[&str] = &["resolvido", "arquivado", "finalizado", "perdido"];
+
+/// A C2S lead found for a contact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct C2sLeadMatch {
+    pub id: String,
+    /// Status alias (`lead_status.alias`), when C2S sends it
+    pub status: Option<String>,
+}
+
+impl C2sLeadMatch {
+    pub fn is_open(&self) -> bool {
+        self.status
+            .as_deref()
+            .is_none_or(|status| !CLOSED_STATUSES.contains(&status.trim().to_lowercase().as_str()))
+    }
+}
+
+/// Leads of a JSON:API page (`data: [{id, attributes: {lead_status}}]`)
+pub fn parse_lead_matches(page: &Value) -> Vec<C2sLeadMatch> {
+    let Some(entries) = page.get("data").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let id = match entry.get("id")? {
+                Value::String(id) => id.clone(),
+                Value::Number(id) => id.to_string(),
+                _ => return None,
+            };
+            let status = entry
+                .pointer("/attributes/lead_status/alias")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            Some(C2sLeadMatch { id, status })
+        })
+        .collect()
+}
+
+/// Whether new leads of a tenant (C2S account) are matched against existing ones
+pub fn enabled_for(config: &Config, account: &str) -> bool {
+    config
+        .lead_dedup_tenants
+        .contains(&account.trim().to_lowercase())
+}
+
+/// Open lead of the contact in the account, if any
+///
+/// Local records are checked first, then C2S. Lookup errors are logged and
+/// taken as "no lead", so the caller creates one.
+pub async fn find_open_lead(
+    pool: &PgPool,
+    config: &Config,
+    c2s: &dyn C2sApi,
+    account: &str,
+    phone: Option<&str>,
+    email: Option<&str>,
+) -> Option<String> {
+    let hashes = contact_hashes(phone, email);
+    if hashes.is_empty() {
+        return None;
+    }
+
+    match find_recent(pool, &hashes, account, config.lead_dedup_window_days).await {
+        Ok(Some(lead_id)) => return Some(lead_id),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("{}", e),
+    }
+
+    match c2s.find_leads(phone, email).await {
+        Ok(leads) => leads
+            .into_iter()
+            .find(C2sLeadMatch::is_open)
+            .map(|lead| lead.id),
+        Err(e) => {
+            tracing::warn!("⚠️  C2S lead search failed, creating a new lead: {}", e);
+            None
+        }
+    }
+}
+
+/// Lead created for one of the contacts within the window (newest first)
+async fn find_recent(
+    pool: &PgPool,
+    hashes: &[String],
+    account: &str,
+    window_days: i64,
+) -> Result<Option<String>, AppError> {
+    let existing: Option<(String,)> = sqlx::query_as(
+        r#"
+        SELECT c2s_lead_id FROM (
+            SELECT c2s_lead_id, account, created_at FROM c2s_lead_contacts
+            WHERE contact_hash = ANY($1)
+            UNION ALL
+            SELECT c2s_lead_id, account, created_at FROM c2s_imported_leads
+            WHERE contact_hash = ANY($1)
+        ) leads
+        WHERE account = $2 AND created_at > NOW() - make_interval(days => $3::int)
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(hashes)
+    .bind(account)
+    .bind(window_days)
+    .fetch_optional(pool)
+    .timed("c2s_lead_contacts.find_recent")
+    .await
+    .context(ErrorContext::db("c2s_lead_contacts.find_recent").id("account", account))?;
+
+    Ok(existing.map(|(id,)| id))
+}
+
+/// Remember the contacts of a created lead for later matches
+pub async fn record_contacts(
+    pool: &PgPool,
+    c2s_lead_id: &str,
+    account: &str,
+    phone: Option<&str>,
+    email: Option<&str>,
+) -> Result<(), AppError> {
+    let hashes = contact_hashes(phone, email);
+    if hashes.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO c2s_lead_contacts (contact_hash, c2s_lead_id, account)
+        SELECT hash, $2, $3 FROM UNNEST($1::text[]) AS hash
+        ON CONFLICT (contact_hash, c2s_lead_id) DO NOTHING
+        "#,
+    )
+    .bind(&hashes)
+    .bind(c2s_lead_id)
+    .bind(account)
+    .execute(pool)
+    .timed("c2s_lead_contacts.insert")
+    .await
+    .context(ErrorContext::db("c2s_lead_contacts.insert").id("c2s_lead_id", c2s_lead_id))?;
+
+    Ok(())
+}
+
+/// Message appended to an existing lead instead of creating a duplicate
+pub fn format_repeat_contact(source: &str, description: &str) -> String {
+    format!("🔁 NOVO CONTATO ({})\n\n{}", source, description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_lead_matches() {
+        let page = json!({
+            "data": [
+                {"id": "abc", "attributes": {"lead_status": {"alias": "arquivado"}}},
+                {"id": 42, "attributes": {"lead_status": {"alias": "novo"}}},
+                {"id": "no-status", "attributes": {}},
+                {"attributes": {}},
+            ]
+        });
+        let leads = parse_lead_matches(&page);
+        assert_eq!(leads.len(), 3);
+        assert!(!leads[0].is_open());
+        assert_eq!(leads[1].id, "42");
+        assert!(leads[1].is_open());
+        assert!(leads[2].is_open());
+        assert!(parse_lead_matches(&json!({})).is_empty());
+    }
+}
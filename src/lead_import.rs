@@ -0,0 +1,415 @@
+//! Batch creation of C2S leads (imported lists)
+//!
+//! `POST /api/v1/c2s/leads/batch` creates one C2S lead per item (e.g. rows of
+//! an imported CSV) and answers with a result per item instead of failing
+//! the whole batch. Items are:
+//!
+//! - validated like Google Ads leads (name plus a valid phone or email),
+//! - deduplicated by contact hash: SHA-256 of the normalized phone / email
+//!   (`phone_lookup_key` / `email_lookup_key`), within the batch and against
+//!   leads created by earlier imports (`c2s_imported_leads`, migration 041),
+//! - throttled against the global C2S rate limiter (`acquire_global`), so an
+//!   import does not starve enrichment messages.
+//!
+//! With a `callback_url`, the import runs in the background (see `batch_jobs`).
+
+use crate::admin_handler::require_admin;
+use crate::api_keys::AuthenticatedKey;
+use crate::batch_jobs::{self, BatchRequest};
+use crate::c2s_accounts;
+use crate::db::TimedQuery;
+use crate::enrichment::{is_valid_email, validate_br_phone};
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::rate_limit;
+use crate::services::{email_lookup_key, phone_lookup_key};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Maximum items per batch request
+pub const MAX_BATCH_SIZE: usize = 500;
+
+/// Source of imported leads when the request sets none
+const DEFAULT_SOURCE: &str = "Importação";
+
+/// One lead to create
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchLeadItem {
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub description: Option<String>,
+    pub product: Option<String>,
+    pub seller_id: Option<String>,
+}
+
+/// Request body of `POST /api/v1/c2s/leads/batch`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchLeadRequest {
+    /// C2S lead source of every item (default "Importação")
+    pub source: Option<String>,
+    pub leads: Vec<BatchLeadItem>,
+    /// Run in the background and POST the summary here when done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+/// Outcome of one item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Created,
+    Duplicate,
+    Invalid,
+    Failed,
+}
+
+/// Result of one item, in request order
+#[derive(Debug, Serialize)]
+pub struct BatchLeadResult {
+    pub index: usize,
+    pub status: ItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c2s_lead_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A validated item, contacts normalized
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedLead {
+    pub index: usize,
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub contact_hashes: Vec<String>,
+}
+
+/// SHA-256 of the normalized phone and email (one hash per contact)
+pub fn contact_hashes(phone: Option<&str>, email: Option<&str>) -> Vec<String> {
+    phone
+        .map(phone_lookup_key)
+        .into_iter()
+        .chain(email.map(email_lookup_key))
+        .map(|key| hex::encode(Sha256::digest(key.as_bytes())))
+        .collect()
+}
+
+/// Validate the items and drop duplicates within the batch
+///
+/// Returns the leads to create and the results of refused items. An item
+/// sharing a phone or email with an earlier item is a duplicate.
+pub fn plan_batch(items: &[BatchLeadItem]) -> (Vec<PlannedLead>, Vec<BatchLeadResult>) {
+    let mut planned = Vec::new();
+    let mut refused = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for (index, item) in items.iter().enumerate() {
+        let name = item.name.trim();
+        let phone = item.phone.as_deref().and_then(|p| {
+            let (valid, normalized) = validate_br_phone(p);
+            valid.then_some(normalized)
+        });
+        let email = item
+            .email
+            .as_deref()
+            .map(str::trim)
+            .filter(|e| is_valid_email(e))
+            .map(str::to_lowercase);
+
+        let invalid = if name.is_empty() {
+            Some("Missing name")
+        } else if phone.is_none() && email.is_none() {
+            Some("No valid phone or email")
+        } else {
+            None
+        };
+        if let Some(reason) = invalid {
+            refused.push(BatchLeadResult {
+                index,
+                status: ItemStatus::Invalid,
+                c2s_lead_id: None,
+                error: Some(reason.to_string()),
+            });
+            continue;
+        }
+
+        let contact_hashes = contact_hashes(phone.as_deref(), email.as_deref());
+        if contact_hashes.iter().any(|hash| seen.contains(hash)) {
+            refused.push(BatchLeadResult {
+                index,
+                status: ItemStatus::Duplicate,
+                c2s_lead_id: None,
+                error: Some("Same contact as an earlier item of the batch".to_string()),
+            });
+            continue;
+        }
+        seen.extend(contact_hashes.iter().cloned());
+
+        planned.push(PlannedLead {
+            index,
+            name: name.to_string(),
+            phone,
+            email,
+            contact_hashes,
+        });
+    }
+
+    (planned, refused)
+}
+
+/// POST /api/v1/c2s/leads/batch
+///
+/// Creates the leads one by one (X-Admin-Token required). Always answers 200
+/// with per-item results; counts by status are in `summary`. With a
+/// `callback_url`, answers 202 with the job running the import.
+pub async fn create_leads_batch(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<AuthenticatedKey>>,
+    headers: HeaderMap,
+    Json(mut request): Json<BatchLeadRequest>,
+) -> Result<Response, AppError> {
+    require_admin(&state.config, &headers)?;
+
+    if request.leads.is_empty() {
+        return Err(AppError::BadRequest("No leads in batch".to_string()));
+    }
+    if request.leads.len() > MAX_BATCH_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "Batch too large: {} leads (max {})",
+            request.leads.len(),
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    if let Some(callback_url) = request.callback_url.take() {
+        return batch_jobs::accept(
+            &state,
+            key.map(|Extension(key)| key.id),
+            BatchRequest::LeadImport(request),
+            callback_url,
+        )
+        .await;
+    }
+    Ok(Json(import_leads(state, request).await).into_response())
+}
+
+/// Create the leads of a batch, returning the endpoint's response
+///
+/// Owns the state and the request so the future is `Send` and `'static`,
+/// as the batch workers spawn it.
+pub async fn import_leads(state: Arc<AppState>, request: BatchLeadRequest) -> serde_json::Value {
+    let source = request.source.as_deref().unwrap_or(DEFAULT_SOURCE);
+    let (planned, mut results) = plan_batch(&request.leads);
+    tracing::info!(
+        "C2S batch import: {} lead(s), {} to create",
+        request.leads.len(),
+        planned.len()
+    );
+
+    for lead in planned {
+        let item = &request.leads[lead.index];
+        results.push(create_one(&state, source, &lead, item).await);
+    }
+    results.sort_by_key(|r| r.index);
+
+    let count = |status: ItemStatus| results.iter().filter(|r| r.status == status).count();
+    let summary = serde_json::json!({
+        "total": results.len(),
+        "created": count(ItemStatus::Created),
+        "duplicate": count(ItemStatus::Duplicate),
+        "invalid": count(ItemStatus::Invalid),
+        "failed": count(ItemStatus::Failed),
+    });
+    tracing::info!("C2S batch import done: {}", summary);
+
+    serde_json::json!({
+        "summary": summary,
+        "results": results,
+    })
+}
+
+/// Create one planned lead, unless an earlier import already created it
+async fn create_one(
+    state: &AppState,
+    source: &str,
+    lead: &PlannedLead,
+    item: &BatchLeadItem,
+) -> BatchLeadResult {
+    let result = |status, c2s_lead_id, error| BatchLeadResult {
+        index: lead.index,
+        status,
+        c2s_lead_id,
+        error,
+    };
+
+    match find_imported(&state.db, &lead.contact_hashes).await {
+        Ok(Some(existing)) => {
+            return result(
+                ItemStatus::Duplicate,
+                Some(existing),
+                Some("Lead already imported".to_string()),
+            )
+        }
+        Ok(None) => {}
+        Err(e) => return result(ItemStatus::Failed, None, Some(e.to_string())),
+    }
+
+    if let Err(e) = rate_limit::c2s_limiter()
+        .acquire_global("create_lead")
+        .await
+    {
+        return result(ItemStatus::Failed, None, Some(e.to_string()));
+    }
+
+    let account = c2s_accounts::select_account(
+        &state.config.c2s_account_rules,
+        &c2s_accounts::LeadOrigin {
+            source: Some(source),
+            campaign_id: None,
+            form_id: None,
+        },
+    );
+    let seller_id = item
+        .seller_id
+        .as_deref()
+        .or(state.config.c2s_default_seller_id.as_deref());
+    let created = c2s_accounts::service_for(state, account)
+        .create_lead(
+            &lead.name,
+            lead.phone.as_deref(),
+            lead.email.as_deref(),
+            item.description.as_deref().unwrap_or(source),
+            Some(source),
+            item.product.as_deref(),
+            seller_id,
+        )
+        .await;
+
+    let c2s_lead_id = match created {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!("C2S batch import: item {} failed: {}", lead.index, e);
+            return result(ItemStatus::Failed, None, Some(e.to_string()));
+        }
+    };
+
+    if let Err(e) = c2s_accounts::record_lead_account(&state.db, &c2s_lead_id, account).await {
+        tracing::warn!("{}", e);
+    }
+    // The lead exists in C2S: a failed record only weakens later dedup
+    if let Err(e) = record_imported(&state.db, &lead.contact_hashes, &c2s_lead_id, account).await {
+        tracing::warn!("{}", e);
+    }
+
+    result(ItemStatus::Created, Some(c2s_lead_id), None)
+}
+
+/// C2S lead already created by an import for one of the contacts
+async fn find_imported(pool: &PgPool, hashes: &[String]) -> Result<Option<String>, AppError> {
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT c2s_lead_id FROM c2s_imported_leads WHERE contact_hash = ANY($1) LIMIT 1",
+    )
+    .bind(hashes)
+    .fetch_optional(pool)
+    .timed("c2s_imported_leads.find")
+    .await
+    .context("Failed to check imported leads")?;
+
+    Ok(existing.map(|(id,)| id))
+}
+
+async fn record_imported(
+    pool: &PgPool,
+    hashes: &[String],
+    c2s_lead_id: &str,
+    account: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO c2s_imported_leads (contact_hash, c2s_lead_id, account)
+        SELECT hash, $2, $3 FROM UNNEST($1::text[]) AS hash
+        ON CONFLICT (contact_hash) DO NOTHING
+        "#,
+    )
+    .bind(hashes)
+    .bind(c2s_lead_id)
+    .bind(account)
+    .execute(pool)
+    .timed("c2s_imported_leads.insert")
+    .await
+    .context(format!(
+        "Failed to record imported contacts of lead_id: {}",
+        c2s_lead_id
+    ))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, phone: Option<&str>, email: Option<&str>) -> BatchLeadItem {
+        BatchLeadItem {
+            name: name.to_string(),
+            phone: phone.map(str::to_string),
+            email: email.map(str::to_string),
+            description: None,
+            product: None,
+            seller_id: None,
+        }
+    }
+
+    #[test]
+    fn test_contact_hashes_use_normalized_contacts() {
+        assert_eq!(
+            contact_hashes(Some("(11) 98765-4321"), None),
+            contact_hashes(Some("11987654321"), None)
+        );
+        assert_eq!(
+            contact_hashes(None, Some("Ana@Example.com")),
+            contact_hashes(None, Some("ana@example.com"))
+        );
+        assert_eq!(
+            contact_hashes(Some("11987654321"), Some("ana@example.com")).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_plan_batch_refuses_invalid_and_duplicates() {
+        let items = vec![
+            item("Ana", Some("11987654321"), Some("ana@example.com")),
+            item("  ", Some("11912345678"), None),
+            item("Bruno", Some("123"), Some("not-an-email")),
+            // Same email as the first item, other phone
+            item("Ana Souza", Some("21987654321"), Some("ANA@example.com")),
+            item("Carla", None, Some("carla@example.com")),
+        ];
+
+        let (planned, refused) = plan_batch(&items);
+
+        let planned: Vec<usize> = planned.iter().map(|p| p.index).collect();
+        assert_eq!(planned, vec![0, 4]);
+        let refused: Vec<(usize, ItemStatus)> =
+            refused.iter().map(|r| (r.index, r.status)).collect();
+        assert_eq!(
+            refused,
+            vec![
+                (1, ItemStatus::Invalid),
+                (2, ItemStatus::Invalid),
+                (3, ItemStatus::Duplicate),
+            ]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Last enrichment message per C2S lead
+//!
+//! Duplicate webhooks (same lead, new `updated_at`) re-run the enrichment a
+//! few minutes after the first message. Instead of posting the full dossier
+//! again, the new message is compared with the last one stored in
+//! `c2s_last_messages` (migration 026): within `C2S_COALESCE_WINDOW_MINUTES`
+//! only the changed lines are sent, and nothing at all when the content hash
+//! is identical.
+//!
+//! Independently of that window, every send goes through `send_deduplicated`,
+//! which skips a message byte-identical to the last one delivered to the lead
+//! (`last_sent_hash` in the same table), and logs every attempt in
+//! `sent_messages` (migration 028) with its outcome and trigger. Both are
+//! written by `record_message`.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::templates;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+/// Last enrichment message stored for a lead
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LastMessage {
+    pub body_hash: String,
+    pub body: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// A logged message to a C2S lead (`sent_messages`)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SentMessage {
+    pub id: i64,
+    pub body_hash: String,
+    pub body_length: i32,
+    /// sent, failed, skipped (duplicate)
+    pub status: String,
+    /// HTTP status returned by C2S for delivered messages
+    pub status_code: Option<i32>,
+    pub error_message: Option<String>,
+    /// enrichment, outbox:<delay reason>, c2s_enrich, lead_processing, resend
+    pub triggered_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What to send for a new enrichment message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Coalesced {
+    /// No recent message: send the full dossier
+    Full,
+    /// Recent message with different content: send only the changes
+    Update(String),
+    /// Recent message with the same content (or no new lines): send nothing
+    Unchanged,
+}
+
+/// SHA-256 of a message body (hex)
+pub fn message_hash(body: &str) -> String {
+    hex::encode(Sha256::digest(body.as_bytes()))
+}
+
+/// Compare a new message with the last one sent within `window`
+pub fn coalesce(
+    previous: Option<&LastMessage>,
+    body: &str,
+    now: DateTime<Utc>,
+    window: Duration,
+) -> Coalesced {
+    let Some(previous) = previous.filter(|p| now - p.sent_at <= window) else {
+        return Coalesced::Full;
+    };
+    if previous.body_hash == message_hash(body) {
+        return Coalesced::Unchanged;
+    }
+
+    let known: HashSet<&str> = previous.body.lines().map(str::trim).collect();
+    let changed: Vec<&str> = body
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !known.contains(line))
+        .collect();
+    if changed.is_empty() {
+        return Coalesced::Unchanged;
+    }
+
+    Coalesced::Update(format!(
+        "{}\n{}\n",
+        templates::body(templates::UPDATE_HEADER),
+        changed.join("\n")
+    ))
+}
+
+/// Last enrichment message stored for a lead
+pub async fn load_last_message(
+    pool: &PgPool,
+    lead_id: &str,
+) -> Result<Option<LastMessage>, AppError> {
+    sqlx::query_as::<_, LastMessage>(
+        r#"
+        SELECT body_hash, body, sent_at FROM c2s_last_messages
+        WHERE lead_id = $1 AND body IS NOT NULL
+        "#,
+    )
+    .bind(lead_id)
+    .fetch_optional(pool)
+    .timed("c2s_last_messages.select")
+    .await
+    .context(format!(
+        "Failed to load last message for lead_id: {}",
+        lead_id
+    ))
+}
+
+/// A message to remember in `c2s_last_messages`
+#[derive(Debug, Clone, Copy)]
+pub enum Recorded<'a> {
+    /// Full enrichment message sent (or queued), compared by `coalesce`
+    Enrichment(&'a str),
+    /// Message delivered to C2S, compared by `send_deduplicated`
+    Delivered(&'a str),
+}
+
+/// Remember a message for a lead, keeping the other column as is
+pub async fn record_message(
+    pool: &PgPool,
+    lead_id: &str,
+    message: Recorded<'_>,
+) -> Result<(), AppError> {
+    let (body, delivered) = match message {
+        Recorded::Enrichment(body) => (Some(body), None),
+        Recorded::Delivered(body) => (None, Some(body)),
+    };
+    sqlx::query(RECORD_MESSAGE_SQL)
+        .bind(lead_id)
+        .bind(body.map(message_hash))
+        .bind(body)
+        .bind(delivered.map(message_hash))
+        .execute(pool)
+        .timed("c2s_last_messages.upsert")
+        .await
+        .context(format!(
+            "Failed to record last message for lead_id: {}",
+            lead_id
+        ))?;
+    Ok(())
+}
+
+pub(crate) const RECORD_MESSAGE_SQL: &str = r#"
+    INSERT INTO c2s_last_messages (lead_id, body_hash, body, sent_at, last_sent_hash)
+    VALUES ($1, $2, $3, NOW(), $4)
+    ON CONFLICT (lead_id) DO UPDATE
+    SET body_hash = COALESCE(EXCLUDED.body_hash, c2s_last_messages.body_hash),
+        body = COALESCE(EXCLUDED.body, c2s_last_messages.body),
+        sent_at = CASE
+            WHEN EXCLUDED.body IS NULL THEN c2s_last_messages.sent_at
+            ELSE EXCLUDED.sent_at
+        END,
+        last_sent_hash = COALESCE(EXCLUDED.last_sent_hash, c2s_last_messages.last_sent_hash)
+"#;
+
+/// Send a message to C2S unless it is identical to the last one delivered
+///
+/// Every attempt is logged in `sent_messages` with `triggered_by`. Returns
+/// `true` when the message was sent.
+pub async fn send_deduplicated(
+    state: &AppState,
+    lead_id: &str,
+    body: &str,
+    triggered_by: &str,
+) -> Result<bool, AppError> {
+    match last_sent_hash(&state.db, lead_id).await {
+        Ok(Some(last)) if last == message_hash(body) => {
+            tracing::info!(
+                "Skipping message to lead {}: identical to the last one sent",
+                lead_id
+            );
+            crate::metrics::record_deduplicated_message("identical");
+            log_attempt(state, lead_id, body, triggered_by, "skipped", None, None).await;
+            return Ok(false);
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to load last message hash, sending anyway: {}", e),
+    }
+
+    send_logged(state, lead_id, body, triggered_by).await?;
+    Ok(true)
+}
+
+/// Send a message to C2S without the duplicate check (logged in `sent_messages`)
+///
+/// Returns the HTTP status from C2S.
+pub async fn send_logged(
+    state: &AppState,
+    lead_id: &str,
+    body: &str,
+    triggered_by: &str,
+) -> Result<u16, AppError> {
+    let status_code = match crate::c2s_accounts::send_message(state, lead_id, body).await {
+        Ok(status_code) => status_code,
+        Err(e) => {
+            let error = e.to_string();
+            log_attempt(
+                state,
+                lead_id,
+                body,
+                triggered_by,
+                "failed",
+                None,
+                Some(&error),
+            )
+            .await;
+            return Err(e);
+        }
+    };
+    log_attempt(
+        state,
+        lead_id,
+        body,
+        triggered_by,
+        "sent",
+        Some(status_code),
+        None,
+    )
+    .await;
+
+    if let Err(e) = record_message(&state.db, lead_id, Recorded::Delivered(body)).await {
+        tracing::warn!("Failed to record message hash for lead {}: {}", lead_id, e);
+    }
+    Ok(status_code)
+}
+
+/// Log a send attempt (non-fatal)
+async fn log_attempt(
+    state: &AppState,
+    lead_id: &str,
+    body: &str,
+    triggered_by: &str,
+    status: &str,
+    status_code: Option<u16>,
+    error_message: Option<&str>,
+) {
+    let entry = SentMessageLog {
+        lead_id,
+        body_hash: &message_hash(body),
+        body_length: body.chars().count(),
+        status,
+        status_code,
+        error_message,
+        triggered_by,
+    };
+    if let Err(e) = log_sent_message(&state.db, &entry).await {
+        tracing::warn!("Failed to log message for lead {}: {}", lead_id, e);
+    }
+}
+
+/// One `sent_messages` row to insert
+struct SentMessageLog<'a> {
+    lead_id: &'a str,
+    body_hash: &'a str,
+    body_length: usize,
+    status: &'a str,
+    status_code: Option<u16>,
+    error_message: Option<&'a str>,
+    triggered_by: &'a str,
+}
+
+async fn log_sent_message(pool: &PgPool, entry: &SentMessageLog<'_>) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO sent_messages
+            (lead_id, body_hash, body_length, status, status_code, error_message, triggered_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(entry.lead_id)
+    .bind(entry.body_hash)
+    .bind(entry.body_length as i32)
+    .bind(entry.status)
+    .bind(entry.status_code.map(i32::from))
+    .bind(entry.error_message)
+    .bind(entry.triggered_by)
+    .execute(pool)
+    .timed("sent_messages.insert")
+    .await
+    .context(format!(
+        "Failed to log message for lead_id: {}",
+        entry.lead_id
+    ))?;
+    Ok(())
+}
+
+/// Messages logged for a lead, most recent first
+pub async fn list_sent_messages(
+    pool: &PgPool,
+    lead_id: &str,
+    limit: i64,
+) -> Result<Vec<SentMessage>, AppError> {
+    sqlx::query_as::<_, SentMessage>(
+        r#"
+        SELECT id, body_hash, body_length, status, status_code, error_message,
+               triggered_by, created_at
+        FROM sent_messages
+        WHERE lead_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(lead_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .timed("sent_messages.list")
+    .await
+    .context(format!("Failed to list messages for lead_id: {}", lead_id))
+}
+
+async fn last_sent_hash(pool: &PgPool, lead_id: &str) -> Result<Option<String>, AppError> {
+    let hash: Option<Option<String>> =
+        sqlx::query_scalar("SELECT last_sent_hash FROM c2s_last_messages WHERE lead_id = $1")
+            .bind(lead_id)
+            .fetch_optional(pool)
+            .timed("c2s_last_messages.select_hash")
+            .await
+            .context(format!(
+                "Failed to load last message hash for lead_id: {}",
+                lead_id
+            ))?;
+    Ok(hash.flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last(body: &str, minutes_ago: i64) -> LastMessage {
+        LastMessage {
+            body_hash: message_hash(body),
+            body: body.to_string(),
+            sent_at: Utc::now() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    #[test]
+    fn test_recent_identical_message_is_unchanged() {
+        let body = "Nome: Maria\nRenda: R$ 10.000\n";
+        let previous = last(body, 5);
+        assert_eq!(
+            coalesce(Some(&previous), body, Utc::now(), Duration::minutes(60)),
+            Coalesced::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_recent_changed_message_sends_only_new_lines() {
+        let previous = last("Nome: Maria\nRenda: R$ 10.000\n", 5);
+        let body = "Nome: Maria\nRenda: R$ 12.000\nEmail: maria@example.com\n";
+
+        assert_eq!(
+            coalesce(Some(&previous), body, Utc::now(), Duration::minutes(60)),
+            Coalesced::Update(format!(
+                "{}\nRenda: R$ 12.000\nEmail: maria@example.com\n",
+                templates::body(templates::UPDATE_HEADER)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_old_or_missing_message_sends_full() {
+        let body = "Nome: Maria\n";
+        let previous = last(body, 120);
+        assert_eq!(
+            coalesce(Some(&previous), body, Utc::now(), Duration::minutes(60)),
+            Coalesced::Full
+        );
+        assert_eq!(
+            coalesce(None, body, Utc::now(), Duration::minutes(60)),
+            Coalesced::Full
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Postgres leader election for singleton background jobs
+//!
+//! Several instances run the same binary, but periodic jobs (deferred webhook
+//! retries, outbox dispatch, and future schedulers) must run on one of them.
+//! Each job holds a session-level advisory lock on a dedicated connection
+//! (detached from the pool, so it does not reduce its capacity). The lock is
+//! re-checked on every tick with a cheap heartbeat query: when the connection
+//! dies the lock is released by Postgres and another instance takes over on
+//! its next tick.
+//!
+//! ```rust,ignore
+//! let leader = Leadership::new("outbox_dispatcher");
+//! loop {
+//!     interval.tick().await;
+//!     if !leader.is_leader(&state.db).await {
+//!         continue;
+//!     }
+//!     // ... singleton work
+//! }
+//! ```
+
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::Mutex;
+
+/// Advisory lock key of a job name (first 8 bytes of its SHA-256)
+pub fn lock_key(name: &str) -> i64 {
+    let digest = Sha256::digest(format!("rust-c2s-api:leader:{}", name).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+/// Leadership of one singleton job
+pub struct Leadership {
+    name: &'static str,
+    key: i64,
+    /// Connection holding the advisory lock while this instance is leader
+    conn: Mutex<Option<PgConnection>>,
+}
+
+impl Leadership {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            key: lock_key(name),
+            conn: Mutex::new(None),
+        }
+    }
+
+    /// Whether this instance leads the job, acquiring leadership when free
+    ///
+    /// Database errors are treated as "not leader": skipping one tick is
+    /// safer than two instances running the job.
+    pub async fn is_leader(&self, pool: &PgPool) -> bool {
+        let mut held = self.conn.lock().await;
+
+        if let Some(conn) = held.as_mut() {
+            match sqlx::query("SELECT 1").execute(&mut *conn).await {
+                Ok(_) => return true,
+                Err(e) => {
+                    tracing::warn!("Lost leadership of {}: {}", self.name, e);
+                    *held = None;
+                    return false;
+                }
+            }
+        }
+
+        let mut conn = match pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("Leader election for {} failed: {}", self.name, e);
+                return false;
+            }
+        };
+        let acquired: Result<bool, sqlx::Error> =
+            sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+                .bind(self.key)
+                .fetch_one(&mut *conn)
+                .await;
+        match acquired {
+            Ok(true) => {
+                tracing::info!("Acquired leadership of {}", self.name);
+                *held = Some(conn.detach());
+                true
+            }
+            // Not holding the lock: the connection goes back to the pool
+            Ok(false) => false,
+            Err(e) => {
+                tracing::warn!("Leader election for {} failed: {}", self.name, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_keys_are_stable_and_distinct() {
+        assert_eq!(lock_key("outbox_dispatcher"), lock_key("outbox_dispatcher"));
+        assert_ne!(
+            lock_key("outbox_dispatcher"),
+            lock_key("deferred_webhook_retry")
+        );
+    }
+}
@@ -5,18 +5,86 @@ pub mod integrations;
 pub mod obs;
 
 // Re-export primary modules for shared use in tests and other binaries
+pub mod admin_handler;
+pub mod affordability;
+pub mod anonymizer;
+pub mod api_keys;
+pub mod app_services;
+pub mod audit;
+pub mod batch_enrich;
+pub mod batch_jobs;
+pub mod c2s_accounts;
+pub mod c2s_fields;
+pub mod c2s_sellers;
+pub mod cache_ttl;
 pub mod cache_validator;
+pub mod caches;
 pub mod circuit_breaker;
+pub mod clients;
 pub mod config;
+pub mod consent;
+pub mod consistency;
+pub mod contact_history;
+pub mod cors;
 pub mod db;
 pub mod db_storage;
+pub mod ddd;
+pub mod deadline;
+pub mod debug_payloads;
+pub mod dedup;
 pub mod enrichment;
+pub mod enrichment_jobs;
 pub mod errors;
+pub mod etag;
 pub mod gateway_client;
+pub mod google_ads_forms;
 pub mod google_ads_handler;
 pub mod google_ads_models;
+pub mod google_sheets;
 pub mod handlers;
+pub mod identity;
+pub mod jobs;
+pub mod language;
+pub mod lead_dedup;
+pub mod lead_import;
+pub mod lead_messages;
+pub mod leader;
+pub mod listings;
+pub mod metrics;
 pub mod models;
+pub mod money;
+pub mod outbox;
+pub mod partners;
+pub mod pending_enrichments;
+pub mod phonetic;
+pub mod pipeline;
+pub mod preflight;
+pub mod primary_contact;
+pub mod privacy;
+pub mod property_refs;
+pub mod provider_stats;
+pub mod quiet_hours;
+pub mod rate_limit;
+pub mod raw_payloads;
+pub mod rdstation;
+pub mod readiness;
+pub mod residency;
+pub mod retention;
+pub mod routing;
+pub mod salesforce;
+pub mod schema_check;
 pub mod services;
+pub mod shadow;
+pub mod sms;
+pub mod subscribers;
+pub mod templates;
+pub mod tenant_credentials;
+#[cfg(feature = "testsupport")]
+pub mod testsupport;
+pub mod timeline;
 pub mod webhook_handler;
 pub mod webhook_models;
+pub mod webhook_queue;
+pub mod whatsapp_handler;
+pub mod whatsapp_models;
+pub mod work_api_fixtures;
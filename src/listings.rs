@@ -0,0 +1,160 @@
+use crate::errors::AppError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A listing from the MBRAS listings API (`GET /listings/{reference}`)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Listing {
+    pub reference: String,
+    /// Asking price in BRL
+    #[serde(default)]
+    pub price: Option<f64>,
+    #[serde(default)]
+    pub neighborhood: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    /// "Apartamento", "Casa", "Cobertura", ...
+    #[serde(default)]
+    pub typology: Option<String>,
+    #[serde(default)]
+    pub bedrooms: Option<u32>,
+    #[serde(default)]
+    pub area_m2: Option<f64>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Listing {
+    /// Message section describing the property the lead asked about
+    pub fn format_section(&self) -> String {
+        let mut section = String::from("\n🏠 IMÓVEL DE INTERESSE\n");
+        section.push_str(&format!("Ref: {}\n", self.reference));
+        if let Some(typology) = &self.typology {
+            match self.bedrooms {
+                Some(bedrooms) => {
+                    section.push_str(&format!("Tipo: {} ({} dorms)\n", typology, bedrooms))
+                }
+                None => section.push_str(&format!("Tipo: {}\n", typology)),
+            }
+        }
+        if let Some(area) = self.area_m2 {
+            section.push_str(&format!("Área: {:.0} m²\n", area));
+        }
+        match (&self.neighborhood, &self.city) {
+            (Some(neighborhood), Some(city)) => {
+                section.push_str(&format!("Bairro: {} - {}\n", neighborhood, city))
+            }
+            (Some(neighborhood), None) => section.push_str(&format!("Bairro: {}\n", neighborhood)),
+            (None, Some(city)) => section.push_str(&format!("Cidade: {}\n", city)),
+            (None, None) => {}
+        }
+        if let Some(price) = self.price {
+            section.push_str(&format!("Preço: R$ {:.2}\n", price));
+        }
+        if let Some(url) = &self.url {
+            section.push_str(&format!("{}\n", url));
+        }
+        section
+    }
+}
+
+/// Client for the MBRAS listings API
+#[derive(Clone)]
+pub struct ListingsClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl ListingsClient {
+    pub fn new(base_url: String, api_key: String) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to create listings client: {}", e))
+            })?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    /// Resolve a property reference (`prop_ref`), `None` when it is unknown
+    pub async fn get_listing(&self, reference: &str) -> Result<Option<Listing>, AppError> {
+        let mut url = reqwest::Url::parse(&self.base_url)
+            .map_err(|e| AppError::ExternalApiError(format!("Invalid listings URL: {}", e)))?;
+        url.path_segments_mut()
+            .map_err(|_| AppError::ExternalApiError("Invalid listings URL".to_string()))?
+            .extend(["listings", reference]);
+
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApiError(format!("Listings request failed: {}", e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            tracing::debug!("Listing {} not found", reference);
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::ExternalApiError(format!(
+                "Listings API returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        let listing = response.json().await.map_err(|e| {
+            AppError::ExternalApiError(format!("Failed to parse listing {}: {}", reference, e))
+        })?;
+        Ok(Some(listing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing_section() {
+        let listing: Listing = serde_json::from_value(serde_json::json!({
+            "reference": "AP1234",
+            "price": 1250000.0,
+            "neighborhood": "Jardins",
+            "city": "São Paulo",
+            "typology": "Apartamento",
+            "bedrooms": 3,
+            "area_m2": 180.0
+        }))
+        .unwrap();
+
+        let section = listing.format_section();
+        assert!(section.contains("Ref: AP1234"));
+        assert!(section.contains("Tipo: Apartamento (3 dorms)"));
+        assert!(section.contains("Bairro: Jardins - São Paulo"));
+        assert!(section.contains("Preço: R$ 1250000.00"));
+    }
+
+    #[test]
+    fn test_listing_section_skips_missing_fields() {
+        let listing = Listing {
+            reference: "5821".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            listing.format_section(),
+            "\n🏠 IMÓVEL DE INTERESSE\nRef: 5821\n"
+        );
+    }
+}
@@ -1,37 +1,30 @@
-mod cache_validator;
-mod circuit_breaker;
-mod config;
-mod db;
-mod db_storage;
-mod enrichment;
-mod errors;
-mod gateway_client;
-mod google_ads_handler;
-mod google_ads_models;
-mod handlers;
-mod models;
-mod services;
-mod webhook_handler;
-mod webhook_models;
-
 use axum::{
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
-use moka::future::Cache;
 use std::sync::Arc;
-use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_governor::{
     governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor, GovernorLayer,
 };
-use tower_http::{cors::CorsLayer, limit::RequestBodyLimitLayer, trace::TraceLayer};
+use tower_http::{compression::CompressionLayer, limit::RequestBodyLimitLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
-use crate::db::Database;
+use rust_c2s_api::app_services::Services;
+use rust_c2s_api::clients::{C2sApi, UnavailableC2s, WorkApi};
+use rust_c2s_api::config::Config;
+use rust_c2s_api::db::Database;
+use rust_c2s_api::readiness::{ComponentState, LazyClient, Readiness};
+use rust_c2s_api::services::{C2SService, DiretrixService, WorkApiService};
+use rust_c2s_api::{
+    admin_handler, anonymizer, api_keys, batch_enrich, batch_jobs, cache_ttl, cache_validator,
+    caches, deadline, debug_payloads, dedup, enrichment_jobs, etag, google_ads_handler,
+    google_sheets, handlers, lead_import, listings, metrics, outbox, pending_enrichments,
+    preflight, rate_limit, rdstation, retention, salesforce, schema_check, sms, subscribers,
+    templates, webhook_handler, webhook_queue, whatsapp_handler,
+};
 
 /// Serves the OpenAPI specification YAML file
 async fn serve_openapi_spec() -> impl IntoResponse {
@@ -103,72 +96,223 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // Configuration / connectivity check only (deploy preflight)
+    if std::env::args().any(|arg| arg == "--check") {
+        let report = preflight::run().await;
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!("Configuration loaded successfully");
 
+    // Outbound C2S message rate limits (shared by every C2S client)
+    rate_limit::init_c2s_limiter(&config);
+    rate_limit::init_work_api_limiter(&config);
+    rate_limit::init_diretrix_limits(&config);
+    cache_validator::init_signing_key(&config);
+    cache_ttl::init(&config);
+
+    // Startup steps below complete before the listener is bound
+    let readiness = Readiness::new();
+
     // Initialize database connection pool
-    let db = Database::new(&config.database_url).await?;
+    let db = Database::with_slow_query_threshold(
+        &config.database_url,
+        std::time::Duration::from_millis(config.slow_query_ms),
+    )
+    .await?;
     tracing::info!("Database connection pool established");
+    readiness.set("database", ComponentState::Ready);
 
-    // Create global CPF deduplication cache (5 minute TTL, 10k max entries)
-    let recent_cpf_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .max_capacity(10_000)
-        .build();
-    tracing::info!("CPF deduplication cache initialized");
-
-    // Create lead-level deduplication cache to prevent concurrent duplicate requests
-    // 5 minute TTL is enough to cover typical request processing time
-    let processing_leads_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .max_capacity(10_000)
-        .build();
-    tracing::info!("Lead deduplication cache initialized");
-
-    // Create contact -> CPF cache (24 hour TTL)
-    // Used to skip external API calls for known contacts
-    let contact_to_cpf_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(86400))
-        .max_capacity(50_000)
-        .build();
-    tracing::info!("Contact enrichment cache initialized");
-
-    // Create Work API response cache (1 hour TTL, 100k max entries)
-    // Caches raw Work API responses to reduce external API calls and improve performance
-    let work_api_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(3600)) // 1 hour
-        .max_capacity(100_000)
-        .build();
-    tracing::info!("Work API response cache initialized (1h TTL, 100k capacity)");
-
-    // Initialize C2S direct client
-    // Formerly "gateway client", now communicates directly with C2S API
-    let gateway_client = match gateway_client::C2sGatewayClient::new(
-        config.c2s_base_url.clone(),
-        config.c2s_token.clone(),
-    ) {
+    // Fail fast when the live schema does not match the critical statements
+    if config.sql_self_test {
+        let issues = schema_check::verify(&db.pool).await;
+        if !issues.is_empty() {
+            anyhow::bail!(schema_check::render_report(&issues));
+        }
+        tracing::info!(
+            "✓ SQL self-test passed ({} statements)",
+            schema_check::CRITICAL_STATEMENTS.len()
+        );
+        readiness.set("schema", ComponentState::Ready);
+    } else {
+        readiness.set("schema", ComponentState::Disabled);
+    }
+
+    // Dedup (5 min), contact (24h), Work API (see cache_ttl) and customer lookup (60s) caches
+    let caches = caches::Caches::new();
+    tracing::info!("In-process caches initialized");
+    readiness.set("caches", ComponentState::Ready);
+
+    // Lead/CPF deduplication: Redis when shared by several instances
+    let dedup: Arc<dyn dedup::DedupBackend> = match config.redis_url.as_deref() {
+        Some(url) if config.dedup_backend == "redis" => Arc::new(
+            dedup::RedisDedup::connect(url)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?,
+        ),
+        _ => Arc::new(dedup::MemoryDedup::new(&caches)),
+    };
+    tracing::info!("✓ Deduplication backend: {}", dedup.name());
+    readiness.set("dedup", ComponentState::Ready);
+
+    // C2S client of the default account (services::C2SService); on failure,
+    // C2S_STARTUP_POLICY=degraded serves without it instead of exiting
+    let c2s: Arc<dyn C2sApi> = match C2SService::try_new(&config) {
         Ok(client) => {
-            tracing::info!("✓ C2S Direct Client initialized: {}", config.c2s_base_url);
-            Some(client)
+            tracing::info!("✓ C2S client initialized: {}", config.c2s_base_url);
+            readiness.set("c2s", ComponentState::Ready);
+            Arc::new(client)
+        }
+        Err(e) if config.c2s_startup_policy == "degraded" => {
+            tracing::error!(
+                alert = "c2s_client_unavailable",
+                "C2S client failed to initialize, serving degraded: {}",
+                e
+            );
+            readiness.set("c2s", ComponentState::Failed(e.to_string()));
+            Arc::new(UnavailableC2s::new(e.to_string()))
+        }
+        Err(e) => anyhow::bail!(
+            "C2S client failed to initialize (C2S_STARTUP_POLICY=fail_fast): {}",
+            e
+        ),
+    };
+
+    // Optional provider clients: built on first use, state in /health/ready
+    let rdstation_client = match config.rdstation_api_key.clone() {
+        Some(api_key) => {
+            let base_url = config.rdstation_base_url.clone();
+            LazyClient::new("rdstation", &readiness, move || {
+                rdstation::RdStationClient::new(base_url, api_key)
+            })
+        }
+        None => LazyClient::disabled("rdstation", &readiness),
+    };
+
+    let listings_client = match (
+        config.listings_api_url.clone(),
+        config.listings_api_key.clone(),
+    ) {
+        (Some(url), Some(api_key)) => LazyClient::new("listings", &readiness, move || {
+            listings::ListingsClient::new(url, api_key)
+        }),
+        _ => LazyClient::disabled("listings", &readiness),
+    };
+
+    // Feature flag: SALESFORCE_ENABLED
+    let salesforce_client = if config.salesforce_enabled {
+        let login_url = config.salesforce_login_url.clone();
+        let client_id = config.salesforce_client_id.clone().unwrap_or_default();
+        let client_secret = config.salesforce_client_secret.clone().unwrap_or_default();
+        let api_version = config.salesforce_api_version.clone();
+        LazyClient::new("salesforce", &readiness, move || {
+            salesforce::SalesforceClient::new(login_url, client_id, client_secret, api_version)
+        })
+    } else {
+        LazyClient::disabled("salesforce", &readiness)
+    };
+
+    let sheets_client = match (
+        config.google_sheets_spreadsheet_id.clone(),
+        config.google_service_account_json.clone(),
+    ) {
+        (Some(spreadsheet_id), Some(service_account)) => {
+            let range = config.google_sheets_range.clone();
+            LazyClient::new("google_sheets", &readiness, move || {
+                google_sheets::GoogleSheetsClient::new(&service_account, spreadsheet_id, range)
+            })
         }
-        Err(e) => {
-            tracing::error!("Failed to initialize C2S client: {}", e);
-            None
+        _ => LazyClient::disabled("google_sheets", &readiness),
+    };
+
+    // SMS sender (used by routing rules)
+    let sms_provider = match config.sms_provider.as_deref() {
+        Some("twilio") => Some(sms::SmsProvider::Twilio {
+            account_sid: config.twilio_account_sid.clone().unwrap_or_default(),
+            auth_token: config.twilio_auth_token.clone().unwrap_or_default(),
+        }),
+        Some("zenvia") => Some(sms::SmsProvider::Zenvia {
+            api_token: config.zenvia_api_token.clone().unwrap_or_default(),
+        }),
+        _ => None,
+    };
+    let sms_client = match sms_provider {
+        Some(provider) => {
+            let from = config.sms_from.clone().unwrap_or_default();
+            LazyClient::new("sms", &readiness, move || {
+                sms::SmsClient::new(provider, from)
+            })
         }
+        None => LazyClient::disabled("sms", &readiness),
     };
 
+    // Persistent queue of webhook enrichments (jobs table, workers started below)
+    let webhook_queue = webhook_queue::WebhookQueue::new(
+        config.webhook_queue_capacity,
+        config.webhook_max_attempts,
+    );
+
+    // Services shared by every request, caches attached once
+    let work_api: Arc<dyn WorkApi> =
+        Arc::new(WorkApiService::new(&config).with_cache(caches.work_api().clone()));
+    let services = Arc::new(Services::new(&db.pool, work_api.clone(), &caches));
+
     // Build application state
-    let app_state = std::sync::Arc::new(crate::handlers::AppState {
+    let app_state = std::sync::Arc::new(handlers::AppState {
         db: db.pool.clone(),
         config: config.clone(),
-        gateway_client,
-        recent_cpf_cache,
-        processing_leads_cache,
-        contact_to_cpf_cache,
-        work_api_cache,
+        rdstation_client,
+        listings_client,
+        salesforce_client,
+        sheets_client,
+        sms_client,
+        work_api,
+        diretrix: Arc::new(DiretrixService::new(&config)),
+        c2s,
+        caches,
+        services,
+        dedup,
+        webhook_queue,
+        readiness: readiness.clone(),
     });
 
+    // Enrich queued webhook events in the background (jobs table, resumed after a restart)
+    webhook_queue::spawn_workers(app_state.clone(), config.webhook_workers);
+    readiness.set("webhook_workers", ComponentState::Ready);
+
+    // Run enrichments requested with ?async=true (jobs table, polled at /api/v1/jobs/:id)
+    enrichment_jobs::spawn_workers(app_state.clone(), config.enrich_job_workers);
+
+    // Run batches sent with a callback_url, then call the caller back
+    batch_jobs::spawn_workers(app_state.clone());
+
+    // Deliver enrichment events to registered webhook subscribers
+    subscribers::spawn_workers(app_state.clone());
+
+    // Retry webhook enrichments deferred while providers were down
+    webhook_handler::spawn_deferred_retry_task(app_state.clone());
+
+    // Deliver C2S messages queued during quiet hours
+    outbox::spawn_outbox_dispatcher(app_state.clone());
+
+    // Retry leads queued because Diretrix found no CPF
+    pending_enrichments::spawn_pending_enrichment_retry(app_state.clone());
+
+    // Drop captured debug payloads past their retention
+    debug_payloads::spawn_debug_payload_purger(app_state.clone());
+
+    // Anonymize leads without activity past ANONYMIZE_AFTER_DAYS
+    anonymizer::spawn_anonymizer(app_state.clone());
+
+    // Prune (and archive) webhook payloads past their retention
+    retention::spawn_retention_job(app_state.clone());
+
+    // Keep message templates in sync with edits from any worker
+    templates::spawn_template_refresher(app_state.db.clone());
+
     // Configure rate limiter: 10 requests/second per IP, burst of 20
     let governor_conf = Arc::new(
         GovernorConfigBuilder::default()
@@ -179,6 +323,20 @@ async fn main() -> anyhow::Result<()> {
             .unwrap(),
     );
 
+    // Read endpoints polled by the dashboard and partners: ETag / 304
+    let read_routes = Router::new()
+        .route("/api/v1/contributor/customer", get(handlers::get_customer))
+        .route("/api/v1/customers", get(handlers::list_customers))
+        .route("/api/v1/customers/:id", get(handlers::get_customer_by_id))
+        .route(
+            "/api/v1/customers/:id/contacts/history",
+            get(handlers::get_customer_contact_history),
+        )
+        // Work API module endpoints
+        .route("/api/v1/work/modules/all", get(handlers::fetch_all_modules))
+        .route("/api/v1/work/modules/:module", get(handlers::fetch_module))
+        .route_layer(axum::middleware::from_fn(etag::conditional_get));
+
     // Build protected routes with security layers
     let protected_routes = Router::new()
         // API Documentation
@@ -186,21 +344,114 @@ async fn main() -> anyhow::Result<()> {
         .route("/api-docs/openapi.yml", get(serve_openapi_spec))
         // API endpoints
         .route("/api/v1/leads", post(handlers::process_lead))
-        .route("/api/v1/contributor/customer", get(handlers::get_customer))
-        .route("/api/v1/customers/:id", get(handlers::get_customer_by_id))
         .route("/api/v1/enrich", post(handlers::enrich_customer))
-        // Work API module endpoints
-        .route("/api/v1/work/modules/all", get(handlers::fetch_all_modules))
-        .route("/api/v1/work/modules/:module", get(handlers::fetch_module))
+        .route("/api/v1/enrich/batch", post(batch_enrich::enrich_batch))
+        .merge(read_routes)
         // C2S integration endpoints
         .route(
             "/api/v1/c2s/enrich/:lead_id",
             post(handlers::c2s_enrich_lead),
         )
+        .route("/api/v1/jobs/:id", get(enrichment_jobs::job_status))
+        .route(
+            "/api/v1/c2s/leads/batch",
+            post(lead_import::create_leads_batch),
+        )
+        .route("/api/v1/c2s/sellers", get(admin_handler::c2s_sellers))
+        .route(
+            "/api/v1/leads/:lead_id/resend-enrichment",
+            post(handlers::resend_enrichment),
+        )
+        .route(
+            "/api/v1/leads/:lead_id/timeline",
+            get(admin_handler::lead_timeline),
+        )
         .route(
             "/api/v1/leads/process",
             get(handlers::trigger_lead_processing),
         )
+        // Admin / operations dashboard (X-Admin-Token)
+        .route("/api/v1/admin/dashboard", get(admin_handler::dashboard))
+        .route(
+            "/api/v1/admin/dashboard/providers",
+            get(admin_handler::dashboard_providers),
+        )
+        .route("/api/v1/admin/outbox", get(admin_handler::outbox_messages))
+        .route(
+            "/api/v1/admin/pending-enrichments",
+            get(admin_handler::pending_enrichments),
+        )
+        .route(
+            "/api/v1/admin/leads/:lead_id/messages",
+            get(admin_handler::lead_message_log),
+        )
+        .route(
+            "/api/v1/admin/templates",
+            get(admin_handler::list_templates),
+        )
+        .route(
+            "/api/v1/admin/templates/:name",
+            put(admin_handler::update_template),
+        )
+        .route(
+            "/api/v1/admin/google-ads/forms",
+            get(admin_handler::list_google_ads_forms),
+        )
+        .route(
+            "/api/v1/admin/google-ads/forms/:form_id",
+            put(admin_handler::update_google_ads_form),
+        )
+        .route(
+            "/api/v1/admin/google-ads/:google_lead_id/replay",
+            post(admin_handler::replay_google_ads_lead),
+        )
+        .route("/api/v1/admin/caches", get(admin_handler::cache_stats))
+        .route(
+            "/api/v1/admin/caches/:name",
+            get(admin_handler::cache_entries),
+        )
+        .route("/api/v1/admin/shadow", get(admin_handler::shadow_summary))
+        .route(
+            "/api/v1/admin/leads/:lead_id/debug",
+            get(admin_handler::lead_debug_payloads),
+        )
+        .route(
+            "/api/v1/admin/work-api-fixtures",
+            get(admin_handler::work_api_fixtures),
+        )
+        .route(
+            "/api/v1/admin/consents",
+            get(admin_handler::list_consents).post(admin_handler::update_consent),
+        )
+        .route(
+            "/api/v1/admin/parties/merge",
+            post(admin_handler::merge_parties),
+        )
+        .route(
+            "/api/v1/admin/parties/:id/raw-payload",
+            get(admin_handler::party_raw_payload),
+        )
+        .route(
+            "/api/v1/admin/api-keys",
+            get(admin_handler::list_api_keys).post(admin_handler::create_api_key),
+        )
+        .route(
+            "/api/v1/admin/api-keys/:id",
+            delete(admin_handler::revoke_api_key),
+        )
+        .route(
+            "/api/v1/admin/subscribers",
+            get(admin_handler::list_subscribers).post(admin_handler::create_subscriber),
+        )
+        .route(
+            "/api/v1/admin/subscribers/:id",
+            delete(admin_handler::disable_subscriber),
+        )
+        .route(
+            "/api/v1/admin/webhooks/retry",
+            post(admin_handler::retry_webhook_events),
+        )
+        .route("/api/v1/privacy/export", get(admin_handler::privacy_export))
         // C2S webhook endpoint (replaces Make.com)
         .route("/api/v1/webhooks/c2s", post(webhook_handler::c2s_webhook))
         // Google Ads webhook endpoint (direct lead creation with inline enrichment)
@@ -208,6 +459,19 @@ async fn main() -> anyhow::Result<()> {
             "/api/v1/webhooks/google-ads",
             post(google_ads_handler::google_ads_webhook_handler),
         )
+        // WhatsApp Business webhook (inbound messages → C2S leads with enrichment)
+        .route(
+            "/api/v1/webhooks/whatsapp",
+            get(whatsapp_handler::whatsapp_verify_handler)
+                .post(whatsapp_handler::whatsapp_webhook_handler),
+        )
+        // Provider calls bounded by X-Request-Deadline or the route SLO (see deadline)
+        .route_layer(axum::middleware::from_fn(deadline::propagate_deadline))
+        // X-Api-Key with the scope of the route (see api_keys)
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            api_keys::require_api_key,
+        ))
         .layer(
             ServiceBuilder::new()
                 // Request size limit: 5MB max payload (prevents memory exhaustion)
@@ -218,17 +482,29 @@ async fn main() -> anyhow::Result<()> {
                 }),
         );
 
-    // Build final app with health check (bypasses rate limiting for Fly.io)
+    // Build final app with health check and metrics (bypass rate limiting)
     let app = Router::new()
         .route("/health", get(handlers::health))
+        .route("/health/ready", get(handlers::readiness))
+        .route("/metrics", get(metrics::metrics_handler))
         .merge(protected_routes)
+        // Per-route request counts and latency histograms (exported via /metrics)
+        .layer(axum::middleware::from_fn(metrics::track_metrics))
+        // X-Degraded: <components> while a client failed to initialize
+        .layer(axum::middleware::from_fn_with_state(
+            readiness.clone(),
+            rust_c2s_api::readiness::degraded_header,
+        ))
         .with_state(app_state)
+        // gzip/br per Accept-Encoding (after ETags, which hash the plain body)
+        .layer(CompressionLayer::new())
         .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::permissive());
+        .layer(config.cors.layer());
 
-    // Start server
+    // Start server: bound only now that every startup step completed
     let addr = format!("0.0.0.0:{}", config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    readiness.mark_started();
     tracing::info!("Server listening on {}", addr);
 
     axum::serve(listener, app).await?;
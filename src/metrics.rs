@@ -0,0 +1,672 @@
+//! Per-route HTTP metrics (request counts by status code, latency histograms)
+//! and per-operation database query latencies
+//!
+//! HTTP metrics are recorded by the `track_metrics` middleware, DB metrics by
+//! `db::TimedQuery`. Both are exported in Prometheus text format at `/metrics`,
+//! along with the count of paid Work API calls skipped by the CPF pre-flight
+//! and of C2S messages skipped as duplicates, the runs of each enrichment
+//! pipeline stage (`pipeline::MetricsHook`) and the webhook events given up
+//! on as poisoned. Provider calls (Work API, Diretrix, C2S) are recorded by
+//! `circuit_breaker::guarded`, cache lookups where the shared caches are
+//! read, and enrichment outcomes by `enrichment::enrich_and_send_workflow`.
+//! Work API cache lookups per key category and the adaptive cache TTL are
+//! read from `cache_ttl` when rendering.
+//! Complements the access log (TraceLayer), which does not aggregate anything.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Histogram bucket upper bounds, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Route label for requests that matched no route (keeps label cardinality bounded)
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RouteKey {
+    method: String,
+    route: String,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket; cumulated when rendering
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum_secs: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|le| secs <= *le) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum_secs += secs;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(self.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, le, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, self.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum_secs);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct RouteStats {
+    statuses: BTreeMap<u16, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct StageStats {
+    outcomes: BTreeMap<&'static str, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct QueryStats {
+    errors: u64,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct ProviderStats {
+    outcomes: BTreeMap<&'static str, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct CacheLookups {
+    hits: u64,
+    misses: u64,
+}
+
+static ROUTES: LazyLock<Mutex<BTreeMap<RouteKey, RouteStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+static QUERIES: LazyLock<Mutex<BTreeMap<&'static str, QueryStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Work API calls skipped by the CPF pre-flight, by rejection reason
+static AVOIDED_CALLS: LazyLock<Mutex<BTreeMap<&'static str, u64>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// C2S messages not sent because they repeated the last one, by reason
+static DEDUPLICATED_MESSAGES: LazyLock<Mutex<BTreeMap<&'static str, u64>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Events given up on after repeated failures, by source
+static POISONED_EVENTS: LazyLock<Mutex<BTreeMap<&'static str, u64>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Enrichment pipeline stage runs, by stage
+static PIPELINE_STAGES: LazyLock<Mutex<BTreeMap<&'static str, StageStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// External provider calls, by provider
+static PROVIDER_CALLS: LazyLock<Mutex<BTreeMap<&'static str, ProviderStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Shared cache lookups, by cache
+static CACHE_LOOKUPS: LazyLock<Mutex<BTreeMap<&'static str, CacheLookups>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Enrichment workflow runs, by outcome
+static ENRICHMENTS: LazyLock<Mutex<BTreeMap<&'static str, u64>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Record one completed HTTP request
+pub fn record_request(method: &str, route: &str, status: u16, latency: Duration) {
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = routes
+        .entry(RouteKey {
+            method: method.to_string(),
+            route: route.to_string(),
+        })
+        .or_default();
+
+    *stats.statuses.entry(status).or_default() += 1;
+    stats.latency.observe(latency);
+}
+
+/// Record one database query for a named operation
+pub fn record_query(operation: &'static str, latency: Duration, success: bool) {
+    let mut queries = QUERIES.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = queries.entry(operation).or_default();
+
+    if !success {
+        stats.errors += 1;
+    }
+    stats.latency.observe(latency);
+}
+
+/// Record one paid Work API call avoided by the CPF/CNPJ pre-flight
+pub fn record_avoided_call(reason: &'static str) {
+    let mut avoided = AVOIDED_CALLS.lock().unwrap_or_else(|e| e.into_inner());
+    *avoided.entry(reason).or_default() += 1;
+}
+
+/// Record one C2S message skipped as a duplicate ("identical", "coalesced")
+pub fn record_deduplicated_message(reason: &'static str) {
+    let mut deduplicated = DEDUPLICATED_MESSAGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    *deduplicated.entry(reason).or_default() += 1;
+}
+
+/// Record one event marked 'poisoned' (e.g. "c2s_webhook")
+pub fn record_poisoned_event(source: &'static str) {
+    let mut poisoned = POISONED_EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    *poisoned.entry(source).or_default() += 1;
+}
+
+/// Record one enrichment pipeline stage run ("ok", "skipped" or "error")
+pub fn record_pipeline_stage(stage: &'static str, outcome: &'static str, latency: Duration) {
+    let mut stages = PIPELINE_STAGES.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = stages.entry(stage).or_default();
+
+    *stats.outcomes.entry(outcome).or_default() += 1;
+    stats.latency.observe(latency);
+}
+
+/// Record one external provider call ("ok", "error", "circuit_open" or "deadline")
+///
+/// Calls rejected by an open breaker have no latency and are only counted.
+pub fn record_provider_call(provider: &'static str, outcome: &'static str, latency: Duration) {
+    let mut providers = PROVIDER_CALLS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = providers.entry(provider).or_default();
+
+    *stats.outcomes.entry(outcome).or_default() += 1;
+    if outcome != "circuit_open" {
+        stats.latency.observe(latency);
+    }
+}
+
+/// Record one lookup in a shared cache (named like `caches::CacheName`)
+pub fn record_cache_lookup(cache: &'static str, hit: bool) {
+    let mut lookups = CACHE_LOOKUPS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = lookups.entry(cache).or_default();
+    if hit {
+        stats.hits += 1;
+    } else {
+        stats.misses += 1;
+    }
+}
+
+/// Record one enrichment workflow run ("success", "failure" or "deferred")
+pub fn record_enrichment(outcome: &'static str) {
+    let mut enrichments = ENRICHMENTS.lock().unwrap_or_else(|e| e.into_inner());
+    *enrichments.entry(outcome).or_default() += 1;
+}
+
+/// Middleware recording count, status and latency per matched route
+///
+/// Uses the route template (e.g. `/api/v1/customers/:id`), never the raw URI.
+pub async fn track_metrics(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let response = next.run(req).await;
+
+    record_request(&method, &route, response.status().as_u16(), start.elapsed());
+    response
+}
+
+/// GET /metrics
+///
+/// Prometheus text exposition format.
+pub async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(),
+    )
+}
+
+/// Render all recorded metrics in Prometheus text format
+pub fn render() -> String {
+    let mut out = String::new();
+    render_routes(&mut out);
+    render_queries(&mut out);
+    render_avoided_calls(&mut out);
+    render_deduplicated_messages(&mut out);
+    render_pipeline_stages(&mut out);
+    render_poisoned_events(&mut out);
+    render_provider_calls(&mut out);
+    render_cache_lookups(&mut out);
+    render_work_api_cache(&mut out);
+    render_enrichments(&mut out);
+    out
+}
+
+fn render_routes(out: &mut String) {
+    let routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP http_requests_total Total HTTP requests by route and status code\n");
+    out.push_str("# TYPE http_requests_total counter\n");
+    for (key, stats) in routes.iter() {
+        for (status, count) in &stats.statuses {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{{},status=\"{}\"}} {}",
+                labels(key),
+                status,
+                count
+            );
+        }
+    }
+
+    out.push_str("# HELP http_request_duration_seconds HTTP request latency by route\n");
+    out.push_str("# TYPE http_request_duration_seconds histogram\n");
+    for (key, stats) in routes.iter() {
+        stats
+            .latency
+            .render(out, "http_request_duration_seconds", &labels(key));
+    }
+}
+
+fn render_queries(out: &mut String) {
+    let queries = QUERIES.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP db_query_errors_total Failed database queries by operation\n");
+    out.push_str("# TYPE db_query_errors_total counter\n");
+    for (operation, stats) in queries.iter() {
+        let _ = writeln!(
+            out,
+            "db_query_errors_total{{operation=\"{}\"}} {}",
+            escape_label(operation),
+            stats.errors
+        );
+    }
+
+    out.push_str("# HELP db_query_duration_seconds Database query latency by operation\n");
+    out.push_str("# TYPE db_query_duration_seconds histogram\n");
+    for (operation, stats) in queries.iter() {
+        let labels = format!("operation=\"{}\"", escape_label(operation));
+        stats
+            .latency
+            .render(out, "db_query_duration_seconds", &labels);
+    }
+}
+
+fn render_avoided_calls(out: &mut String) {
+    let avoided = AVOIDED_CALLS.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str(
+        "# HELP work_api_calls_avoided_total Paid Work API calls skipped by the CPF/CNPJ pre-flight\n",
+    );
+    out.push_str("# TYPE work_api_calls_avoided_total counter\n");
+    for (reason, count) in avoided.iter() {
+        let _ = writeln!(
+            out,
+            "work_api_calls_avoided_total{{reason=\"{}\"}} {}",
+            escape_label(reason),
+            count
+        );
+    }
+}
+
+fn render_deduplicated_messages(out: &mut String) {
+    let deduplicated = DEDUPLICATED_MESSAGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    out.push_str(
+        "# HELP c2s_messages_deduplicated_total C2S messages skipped as duplicates of the last one\n",
+    );
+    out.push_str("# TYPE c2s_messages_deduplicated_total counter\n");
+    for (reason, count) in deduplicated.iter() {
+        let _ = writeln!(
+            out,
+            "c2s_messages_deduplicated_total{{reason=\"{}\"}} {}",
+            escape_label(reason),
+            count
+        );
+    }
+}
+
+fn render_pipeline_stages(out: &mut String) {
+    let stages = PIPELINE_STAGES.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP enrichment_stage_runs_total Enrichment pipeline stage runs by outcome\n");
+    out.push_str("# TYPE enrichment_stage_runs_total counter\n");
+    for (stage, stats) in stages.iter() {
+        for (outcome, count) in &stats.outcomes {
+            let _ = writeln!(
+                out,
+                "enrichment_stage_runs_total{{stage=\"{}\",outcome=\"{}\"}} {}",
+                escape_label(stage),
+                escape_label(outcome),
+                count
+            );
+        }
+    }
+
+    out.push_str("# HELP enrichment_stage_duration_seconds Enrichment pipeline stage latency\n");
+    out.push_str("# TYPE enrichment_stage_duration_seconds histogram\n");
+    for (stage, stats) in stages.iter() {
+        let labels = format!("stage=\"{}\"", escape_label(stage));
+        stats
+            .latency
+            .render(out, "enrichment_stage_duration_seconds", &labels);
+    }
+}
+
+fn render_poisoned_events(out: &mut String) {
+    let poisoned = POISONED_EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP poisoned_events_total Events no longer retried after repeated failures\n");
+    out.push_str("# TYPE poisoned_events_total counter\n");
+    for (source, count) in poisoned.iter() {
+        let _ = writeln!(
+            out,
+            "poisoned_events_total{{source=\"{}\"}} {}",
+            escape_label(source),
+            count
+        );
+    }
+}
+
+fn render_provider_calls(out: &mut String) {
+    let providers = PROVIDER_CALLS.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP provider_calls_total External provider calls by outcome\n");
+    out.push_str("# TYPE provider_calls_total counter\n");
+    for (provider, stats) in providers.iter() {
+        for (outcome, count) in &stats.outcomes {
+            let _ = writeln!(
+                out,
+                "provider_calls_total{{provider=\"{}\",outcome=\"{}\"}} {}",
+                escape_label(provider),
+                escape_label(outcome),
+                count
+            );
+        }
+    }
+
+    out.push_str("# HELP provider_call_duration_seconds External provider call latency\n");
+    out.push_str("# TYPE provider_call_duration_seconds histogram\n");
+    for (provider, stats) in providers.iter() {
+        let labels = format!("provider=\"{}\"", escape_label(provider));
+        stats
+            .latency
+            .render(out, "provider_call_duration_seconds", &labels);
+    }
+}
+
+fn render_cache_lookups(out: &mut String) {
+    let lookups = CACHE_LOOKUPS.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP cache_lookups_total Shared cache lookups by result\n");
+    out.push_str("# TYPE cache_lookups_total counter\n");
+    for (cache, stats) in lookups.iter() {
+        for (result, count) in [("hit", stats.hits), ("miss", stats.misses)] {
+            let _ = writeln!(
+                out,
+                "cache_lookups_total{{cache=\"{}\",result=\"{}\"}} {}",
+                escape_label(cache),
+                result,
+                count
+            );
+        }
+    }
+}
+
+fn render_work_api_cache(out: &mut String) {
+    out.push_str(
+        "# HELP work_api_cache_lookups_total Work API cache lookups by key category and result\n",
+    );
+    out.push_str("# TYPE work_api_cache_lookups_total counter\n");
+    for stats in crate::cache_ttl::category_stats() {
+        for (result, count) in [
+            ("hit", stats.hits),
+            ("expired", stats.expired),
+            ("miss", stats.misses),
+        ] {
+            let _ = writeln!(
+                out,
+                "work_api_cache_lookups_total{{category=\"{}\",result=\"{}\"}} {}",
+                escape_label(stats.category),
+                result,
+                count
+            );
+        }
+    }
+
+    let ttl = crate::cache_ttl::current();
+    out.push_str("# HELP work_api_cache_ttl_seconds Effective Work API cache TTL, by reason\n");
+    out.push_str("# TYPE work_api_cache_ttl_seconds gauge\n");
+    let _ = writeln!(
+        out,
+        "work_api_cache_ttl_seconds{{reason=\"{}\"}} {}",
+        ttl.reason.as_str(),
+        ttl.ttl_secs
+    );
+    out.push_str("# HELP work_api_cache_ttl_bound_seconds Bounds of the Work API cache TTL\n");
+    out.push_str("# TYPE work_api_cache_ttl_bound_seconds gauge\n");
+    let _ = writeln!(
+        out,
+        "work_api_cache_ttl_bound_seconds{{bound=\"base\"}} {}",
+        ttl.base_secs
+    );
+    let _ = writeln!(
+        out,
+        "work_api_cache_ttl_bound_seconds{{bound=\"max\"}} {}",
+        ttl.max_secs
+    );
+    if let Some(rate) = ttl.error_rate {
+        out.push_str("# HELP work_api_recent_error_rate Error rate of the last Work API calls\n");
+        out.push_str("# TYPE work_api_recent_error_rate gauge\n");
+        let _ = writeln!(out, "work_api_recent_error_rate {}", rate);
+    }
+}
+
+fn render_enrichments(out: &mut String) {
+    let enrichments = ENRICHMENTS.lock().unwrap_or_else(|e| e.into_inner());
+
+    out.push_str("# HELP enrichments_total Enrichment workflow runs by outcome\n");
+    out.push_str("# TYPE enrichments_total counter\n");
+    for (outcome, count) in enrichments.iter() {
+        let _ = writeln!(
+            out,
+            "enrichments_total{{outcome=\"{}\"}} {}",
+            escape_label(outcome),
+            count
+        );
+    }
+}
+
+fn labels(key: &RouteKey) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\"",
+        escape_label(&key.method),
+        escape_label(&key.route)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_and_cumulative_buckets() {
+        let route = "/test/metrics/:id";
+        record_request("GET", route, 200, Duration::from_millis(3));
+        record_request("GET", route, 200, Duration::from_millis(80));
+        record_request("GET", route, 404, Duration::from_secs(30));
+
+        let text = render();
+        let labels = format!("method=\"GET\",route=\"{}\"", route);
+
+        assert!(text.contains(&format!(
+            "http_requests_total{{{},status=\"200\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "http_requests_total{{{},status=\"404\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"0.005\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"0.1\"}} 2",
+            labels
+        )));
+        // 30s exceeds the largest bucket: only counted in +Inf
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"10\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 3",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_count{{{}}} 3",
+            labels
+        )));
+    }
+
+    #[test]
+    fn test_render_deduplicated_messages() {
+        record_deduplicated_message("identical");
+        record_deduplicated_message("identical");
+
+        let text = render();
+        assert!(text.contains("# TYPE c2s_messages_deduplicated_total counter"));
+        assert!(text.contains("c2s_messages_deduplicated_total{reason=\"identical\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_middleware_labels_by_route_template() {
+        let app = axum::Router::new()
+            .route("/test/items/:id", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(track_metrics));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        reqwest::get(format!("http://{}/test/items/42", addr))
+            .await
+            .unwrap();
+
+        let text = render();
+        assert!(text.contains(
+            "http_requests_total{method=\"GET\",route=\"/test/items/:id\",status=\"200\"} 1"
+        ));
+        assert!(!text.contains("/test/items/42"));
+    }
+
+    #[test]
+    fn test_render_db_queries() {
+        record_query("test.find_lead", Duration::from_millis(40), true);
+        record_query("test.find_lead", Duration::from_millis(700), false);
+
+        let text = render();
+
+        assert!(text.contains("db_query_errors_total{operation=\"test.find_lead\"} 1"));
+        assert!(text.contains(
+            "db_query_duration_seconds_bucket{operation=\"test.find_lead\",le=\"0.05\"} 1"
+        ));
+        assert!(text.contains("db_query_duration_seconds_count{operation=\"test.find_lead\"} 2"));
+    }
+
+    #[test]
+    fn test_render_avoided_calls() {
+        record_avoided_call("test_reason");
+        record_avoided_call("test_reason");
+
+        assert!(render().contains("work_api_calls_avoided_total{reason=\"test_reason\"} 2"));
+    }
+
+    #[test]
+    fn test_render_pipeline_stages() {
+        record_pipeline_stage("test_stage", "ok", Duration::from_millis(20));
+        record_pipeline_stage("test_stage", "skipped", Duration::from_millis(1));
+
+        let text = render();
+        assert!(text.contains("enrichment_stage_runs_total{stage=\"test_stage\",outcome=\"ok\"} 1"));
+        assert!(text.contains("enrichment_stage_duration_seconds_count{stage=\"test_stage\"} 2"));
+    }
+
+    #[test]
+    fn test_render_provider_calls() {
+        record_provider_call("test_provider", "ok", Duration::from_millis(200));
+        record_provider_call("test_provider", "error", Duration::from_millis(900));
+        record_provider_call("test_provider", "circuit_open", Duration::ZERO);
+
+        let text = render();
+        assert!(
+            text.contains("provider_calls_total{provider=\"test_provider\",outcome=\"error\"} 1")
+        );
+        assert!(text.contains(
+            "provider_calls_total{provider=\"test_provider\",outcome=\"circuit_open\"} 1"
+        ));
+        // Rejected calls have no latency
+        assert!(text.contains("provider_call_duration_seconds_count{provider=\"test_provider\"} 2"));
+    }
+
+    #[test]
+    fn test_render_cache_lookups_and_enrichments() {
+        record_cache_lookup("test_cache", true);
+        record_cache_lookup("test_cache", true);
+        record_cache_lookup("test_cache", false);
+        record_enrichment("test_outcome");
+
+        let text = render();
+        assert!(text.contains("cache_lookups_total{cache=\"test_cache\",result=\"hit\"} 2"));
+        assert!(text.contains("cache_lookups_total{cache=\"test_cache\",result=\"miss\"} 1"));
+        assert!(text.contains("enrichments_total{outcome=\"test_outcome\"} 1"));
+    }
+
+    #[test]
+    fn test_render_work_api_cache_ttl() {
+        let text = render();
+        assert!(text.contains("# TYPE work_api_cache_ttl_seconds gauge"));
+        assert!(text.contains("work_api_cache_ttl_bound_seconds{bound=\"base\"} 3600"));
+        assert!(text.contains("work_api_cache_ttl_bound_seconds{bound=\"max\"} 86400"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+}
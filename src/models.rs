@@ -1,3 +1,4 @@
+use crate::money::Brl;
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -24,6 +25,8 @@ pub struct Party {
     pub company_type: Option<String>,
     pub company_size: Option<String>,
     pub enriched: Option<bool>,
+    /// State of the best current address (`residency`)
+    pub uf: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
@@ -277,6 +280,91 @@ pub struct LookupInterests {
     pub early_adopter: f64,
 }
 
+impl LookupInterests {
+    /// Interests from the consumption profile of a Work API payload
+    /// (`PerfilConsumo`), `None` without one
+    ///
+    /// Flags come as booleans or "SIM"/"NAO" and propensities as numbers or
+    /// numeric text; anything missing or unreadable is `false`/`0.0`.
+    pub fn from_work_data(
+        work_data: &WorkApiCompleteResponse,
+        customer_id: &str,
+        fetched_at: &str,
+    ) -> Option<Self> {
+        let profile = work_data.get("PerfilConsumo").filter(|p| p.is_object())?;
+        let flag = |key: &str| profile.get(key).is_some_and(consumption_flag);
+        let score = |key: &str| profile.get(key).and_then(consumption_score).unwrap_or(0.0);
+
+        Some(Self {
+            middle_class: flag("classeMedia"),
+            has_accumulated_miles: flag("possuiMilhasAcumuladas"),
+            online_shopping: score("comprasOnline"),
+            car_insurance: score("seguroAuto"),
+            fitness: score("fitness"),
+            customer_id: customer_id.to_string(),
+            owns_luxury_goods: flag("possuiLuxo"),
+            owns_home: flag("possuiCasaPropria"),
+            multiple_credit_card: score("multiplosCartoesCredito"),
+            health_insurance: score("seguroSaude"),
+            travel: score("turismo"),
+            created_at: fetched_at.to_string(),
+            owns_investments: flag("possuiInvestimentos"),
+            owns_current_accounts: flag("possuiContaCorrente"),
+            prime_credit_card: score("cartaoCreditoPrime"),
+            life_insurance: score("seguroVida"),
+            luxury: score("luxo"),
+            updated_at: fetched_at.to_string(),
+            owns_premium_bank_account: flag("possuiContaAltoPadrao"),
+            owns_car_insurance: flag("possuiSeguroAuto"),
+            cable_tv: score("tvPorAssinatura"),
+            home_insurance: score("seguroResidencial"),
+            moviegoer: score("cinefilo"),
+            pre_approved_personal_loan: flag("creditoPessoalPreAprovado"),
+            owns_credit_card: flag("possuiCartaoCredito"),
+            has_private_retirement_plan: flag("possuiPrevidenciaPrivada"),
+            broadband_internet: score("internetBandaLarga"),
+            investments: score("investimentos"),
+            public_transportation: score("transportePublico"),
+            id: customer_id.to_string(),
+            owns_multiple_credit_cards: flag("possuiMultiplosCartoesCredito"),
+            personal_loan: score("emprestimoPessoal"),
+            own_home: score("casaPropria"),
+            consignment_loan: score("emprestimoConsignado"),
+            online_games: score("jogosOnline"),
+            pre_approved_mortgage: flag("creditoImobiliarioPreAprovado"),
+            owns_black_credit_card: flag("possuiCartaoBlack"),
+            vehicle_loan: score("financiamentoVeiculo"),
+            private_retirement_plan: score("previdenciaPrivada"),
+            frequent_flyer_miles_redemption: score("resgateMilhas"),
+            video_games: score("videoGames"),
+            pre_approved_vehicle_financing: flag("creditoVeiculoPreAprovado"),
+            owns_prime_credit_card: flag("possuiCartaoPrime"),
+            mortgage: score("financiamentoImobiliario"),
+            discount_hunting: score("cacadorDescontos"),
+            early_adopter: score("earlyAdopters"),
+        })
+    }
+}
+
+fn consumption_flag(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        serde_json::Value::String(s) => {
+            matches!(s.trim().to_uppercase().as_str(), "S" | "SIM" | "TRUE" | "1")
+        }
+        _ => false,
+    }
+}
+
+fn consumption_score(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().replace(',', ".").parse().ok(),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LookupEducation {
     pub id: String,
@@ -306,17 +394,154 @@ pub struct CustomerQueryParams {
     pub cpf: Option<String>,
 }
 
+/// Query parameters of the customer listing (`residency`)
+#[derive(Debug, Deserialize)]
+pub struct CustomerListParams {
+    /// Comma-separated UFs (`SP,RJ`); all tagged parties when absent
+    pub uf: Option<String>,
+    /// Last id of the previous page
+    pub after: Option<Uuid>,
+    /// Number of parties to return (default 100, max 1000)
+    pub limit: Option<i64>,
+}
+
 // ============ Work API Models ============
 
 // When querying modulo=cpf, Work API returns data directly at root level
 pub type WorkApiCompleteResponse = serde_json::Value;
 
+/// CNPJ-oriented Work API modules (`WorkApiService::fetch_company_modules`)
+///
+/// Serialized with the same PascalCase section names as the CPF payload, so it
+/// can be stored as the enrichment snapshot of a company party. A module the
+/// API had no data for (or that failed) is `None`; failed ones are also
+/// listed in `failed_modules`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompanyModules {
+    pub cnpj: String,
+    /// Quadro de sócios e administradores (QSA)
+    #[serde(rename = "Socios", skip_serializing_if = "Option::is_none")]
+    pub socios: Option<serde_json::Value>,
+    #[serde(
+        rename = "FaturamentoPresumido",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub faturamento_presumido: Option<serde_json::Value>,
+    /// Registration data (razão social, CNAE, porte, situação)
+    #[serde(rename = "SituacaoCadastral", skip_serializing_if = "Option::is_none")]
+    pub situacao_cadastral: Option<serde_json::Value>,
+    /// Modules whose call failed (partial result)
+    #[serde(
+        rename = "ModulosComFalha",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub failed_modules: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkApiModule {
     pub status: String,
     pub data: Option<serde_json::Value>,
 }
 
+/// Economic section of a CPF payload (`DadosEconomicos`)
+///
+/// Values come as text or numbers depending on the record; both are read as
+/// text, and anything else (or an empty string) as missing.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkEconomicData {
+    /// Declared monthly income ("15.000,00")
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub renda: Option<String>,
+    #[serde(rename = "poderAquisitivo", default)]
+    pub poder_aquisitivo: Option<WorkPurchasingPower>,
+    #[serde(default)]
+    pub score: Option<WorkCreditScore>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkPurchasingPower {
+    #[serde(
+        rename = "poderAquisitivoDescricao",
+        default,
+        deserialize_with = "lenient_string"
+    )]
+    pub descricao: Option<String>,
+    /// "De R$ 1630 até R$ 4082"
+    #[serde(
+        rename = "faixaPoderAquisitivo",
+        default,
+        deserialize_with = "lenient_string"
+    )]
+    pub faixa: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkCreditScore {
+    #[serde(rename = "scoreCSBA", default, deserialize_with = "lenient_string")]
+    pub score_csba: Option<String>,
+    #[serde(
+        rename = "scoreCSBAFaixaRisco",
+        default,
+        deserialize_with = "lenient_string"
+    )]
+    pub faixa_risco: Option<String>,
+}
+
+fn lenient_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(s)) if !s.trim().is_empty() => {
+                Some(s.trim().to_string())
+            }
+            Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        },
+    )
+}
+
+impl WorkEconomicData {
+    /// Economic section of a Work API payload, `None` without one
+    pub fn from_work_data(work_data: &WorkApiCompleteResponse) -> Option<Self> {
+        work_data
+            .get("DadosEconomicos")
+            .filter(|d| d.is_object())
+            .and_then(|d| serde_json::from_value(d.clone()).ok())
+    }
+
+    /// Declared monthly income, unadjusted
+    pub fn income(&self) -> Option<Brl> {
+        self.renda.as_deref().and_then(Brl::parse)
+    }
+
+    pub fn income_range(&self) -> Option<&str> {
+        self.poder_aquisitivo.as_ref()?.faixa.as_deref()
+    }
+
+    pub fn credit_score(&self) -> Option<f64> {
+        self.score
+            .as_ref()?
+            .score_csba
+            .as_deref()
+            .and_then(|s| s.replace(',', ".").parse().ok())
+    }
+
+    /// Financial fields of a unified response, `None` when none is known
+    pub fn financial_info(&self) -> Option<UnifiedFinancialInfo> {
+        let info = UnifiedFinancialInfo {
+            income: self.income().map(|renda| renda.to_f64() as f32),
+            income_range: self.income_range().map(str::to_string),
+            credit_score: self.credit_score(),
+        };
+        (info.income.is_some() || info.income_range.is_some() || info.credit_score.is_some())
+            .then_some(info)
+    }
+}
+
 // ============ Wealth Assessment (Summarized) ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -443,7 +668,7 @@ pub struct UnifiedCustomerResponse {
     pub contact_info: UnifiedContactInfo,
     pub addresses: Vec<UnifiedAddress>,
     pub financial_info: Option<UnifiedFinancialInfo>,
-    pub interests: Option<serde_json::Value>,
+    pub interests: Option<LookupInterests>,
     pub metadata: ResponseMetadata,
     pub wealth_assessment: Option<WealthAssessment>,
 }
@@ -510,4 +735,131 @@ pub struct ResponseMetadata {
     pub sources: Vec<String>,
     pub timestamp: String,
     pub modules_consulted: Vec<String>,
+    /// Set when a provider was unavailable and only DB/cached data was served
+    #[serde(default)]
+    pub degraded: bool,
+    /// How the local party was matched (absent when not found in the DB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_by: Option<MatchKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_confidence: Option<f32>,
+    /// Cache hit, latency and data age of each source in `sources`
+    #[serde(default)]
+    pub source_details: Vec<SourceDetail>,
+}
+
+/// How one source contributed to a unified response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceDetail {
+    /// local_db, work_api or cache
+    pub source: String,
+    /// Served from the in-process cache instead of the provider
+    pub cached: bool,
+    pub latency_ms: u64,
+    /// Seconds since the data was fetched from the provider (None when unknown)
+    pub age_secs: Option<i64>,
+}
+
+impl SourceDetail {
+    pub fn new(
+        source: &str,
+        cached: bool,
+        started: std::time::Instant,
+        age_secs: Option<i64>,
+    ) -> Self {
+        Self {
+            source: source.to_string(),
+            cached,
+            latency_ms: started.elapsed().as_millis() as u64,
+            age_secs,
+        }
+    }
+}
+
+/// Identifier that matched a customer lookup to a local party
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Cpf,
+    Email,
+    Phone,
+    /// Normalized name contains the search term
+    Name,
+    /// Only the phonetic keys agree ("Luis" vs "Luiz"): weakest match
+    PhoneticName,
+}
+
+impl MatchKind {
+    /// Confidence that the matched party is the person searched for
+    pub fn confidence(self) -> f32 {
+        match self {
+            MatchKind::Cpf => 1.0,
+            MatchKind::Email => 0.9,
+            MatchKind::Phone => 0.85,
+            MatchKind::Name => 0.6,
+            MatchKind::PhoneticName => 0.4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_financial_info_from_economic_data() {
+        let work_data = json!({"DadosEconomicos": {
+            "renda": "R$ 15.000,00",
+            "poderAquisitivo": {
+                "poderAquisitivoDescricao": "MUITO ALTO",
+                "faixaPoderAquisitivo": "De R$ 10000 até R$ 20000"
+            },
+            "score": {"scoreCSBA": 812, "scoreCSBAFaixaRisco": "BAIXO RISCO"}
+        }});
+
+        let info = WorkEconomicData::from_work_data(&work_data)
+            .and_then(|e| e.financial_info())
+            .unwrap();
+        assert_eq!(info.income, Some(15000.0));
+        assert_eq!(
+            info.income_range.as_deref(),
+            Some("De R$ 10000 até R$ 20000")
+        );
+        assert_eq!(info.credit_score, Some(812.0));
+    }
+
+    #[test]
+    fn test_financial_info_missing_or_unusable() {
+        assert!(WorkEconomicData::from_work_data(&json!({"DadosBasicos": {}})).is_none());
+
+        let unusable = json!({"DadosEconomicos": {"renda": "", "score": {"scoreCSBA": null}}});
+        let economic = WorkEconomicData::from_work_data(&unusable).unwrap();
+        assert!(economic.financial_info().is_none());
+    }
+
+    #[test]
+    fn test_interests_from_consumption_profile() {
+        let work_data = json!({"PerfilConsumo": {
+            "possuiLuxo": "SIM",
+            "possuiInvestimentos": true,
+            "possuiCartaoBlack": "NAO",
+            "luxo": "0,87",
+            "turismo": 0.42,
+            "investimentos": "ALTA"
+        }});
+
+        let interests =
+            LookupInterests::from_work_data(&work_data, "12345678901", "2025-11-28T00:00:00Z")
+                .unwrap();
+        assert!(interests.owns_luxury_goods);
+        assert!(interests.owns_investments);
+        assert!(!interests.owns_black_credit_card);
+        assert_eq!(interests.luxury, 0.87);
+        assert_eq!(interests.travel, 0.42);
+        assert_eq!(interests.investments, 0.0);
+        assert_eq!(interests.customer_id, "12345678901");
+
+        assert!(LookupInterests::from_work_data(&json!({}), "12345678901", "now").is_none());
+    }
 }
@@ -0,0 +1,232 @@
+//! Brazilian Real amounts (decimal-based parsing and formatting)
+//!
+//! The Work API sends money as text in several shapes: `"15000,00"`,
+//! `"15.000,00"`, `"4082.5"`, `"R$ 1630"`, and ranges such as
+//! `"De R$ 1630 até R$ 4082"`. `Brl` parses all of them into an exact
+//! decimal and formats back in the Brazilian convention (`R$ 15.000,00`),
+//! for the C2S message and the financial fields of API responses.
+
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive, Zero};
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// An amount in reais, with cents
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Brl(BigDecimal);
+
+/// Amounts written in a text (`R$ 1.630,00`, `R$1630`)
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"R\$\s*(\d[\d.,]*\d|\d)").unwrap());
+
+impl Brl {
+    /// Parse an amount, with or without `R$`, thousands separators and cents
+    ///
+    /// With both `.` and `,` the last one is the decimal separator. With a
+    /// single kind of separator, it is decimal when followed by one or two
+    /// digits at the end (`"1630,5"`, `"4082.00"`) and a thousands separator
+    /// otherwise (`"15.000"`, `"1,630,000"`).
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix("R$").unwrap_or(text).trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, text),
+        };
+        if digits.is_empty()
+            || !digits.starts_with(|c: char| c.is_ascii_digit())
+            || digits.ends_with(['.', ','])
+            || !digits
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+        {
+            return None;
+        }
+
+        let decimal_separator = match (digits.rfind('.'), digits.rfind(',')) {
+            (Some(dot), Some(comma)) => {
+                let pos = dot.max(comma);
+                if digits.matches(digits.as_bytes()[pos] as char).count() > 1 {
+                    return None;
+                }
+                Some(pos)
+            }
+            (Some(pos), None) | (None, Some(pos)) => {
+                let separator = digits.as_bytes()[pos] as char;
+                let decimals = digits.len() - pos - 1;
+                (digits.matches(separator).count() == 1 && (1..=2).contains(&decimals))
+                    .then_some(pos)
+            }
+            (None, None) => None,
+        };
+        let normalized: String = match decimal_separator {
+            Some(pos) => format!(
+                "{}.{}",
+                digits[..pos].replace(['.', ','], ""),
+                &digits[pos + 1..]
+            ),
+            None => digits.replace(['.', ','], ""),
+        };
+        let value = BigDecimal::from_str(&normalized).ok()?;
+        Some(Self(if negative { -value } else { value }).rounded())
+    }
+
+    pub fn from_f64(value: f64) -> Option<Self> {
+        BigDecimal::from_str(&value.to_string())
+            .ok()
+            .map(|v| Self(v).rounded())
+    }
+
+    fn rounded(self) -> Self {
+        Self(self.0.with_scale_round(2, RoundingMode::HalfUp))
+    }
+
+    /// Amount multiplied by a factor (e.g. the 1.9 income adjustment), rounded to cents
+    pub fn scaled(&self, factor: f64) -> Self {
+        let factor =
+            BigDecimal::from_str(&factor.to_string()).unwrap_or_else(|_| BigDecimal::zero());
+        Self(&self.0 * factor).rounded()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > BigDecimal::zero()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or_default()
+    }
+
+    pub fn as_decimal(&self) -> &BigDecimal {
+        &self.0
+    }
+}
+
+impl fmt::Display for Brl {
+    /// `R$ 15.000,00`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plain = self.0.with_scale_round(2, RoundingMode::HalfUp).to_string();
+        let (sign, plain) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain.as_str()),
+        };
+        let (units, cents) = plain.split_once('.').unwrap_or((plain, "00"));
+
+        let mut grouped = String::new();
+        for (idx, digit) in units.chars().enumerate() {
+            if idx > 0 && (units.len() - idx) % 3 == 0 {
+                grouped.push('.');
+            }
+            grouped.push(digit);
+        }
+        write!(f, "{}R$ {},{}", sign, grouped, cents)
+    }
+}
+
+/// Income range of a Work API description, e.g. `"De R$ 1630 até R$ 4082"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrlRange {
+    /// `None` for "Até R$ X"
+    pub min: Option<Brl>,
+    /// `None` for "Acima de R$ X"
+    pub max: Option<Brl>,
+}
+
+impl BrlRange {
+    /// Parse the amounts of a range description (`None` without any amount)
+    pub fn parse(text: &str) -> Option<Self> {
+        let amounts: Vec<Brl> = AMOUNT
+            .captures_iter(text)
+            .filter_map(|caps| Brl::parse(&caps[1]))
+            .collect();
+        let lower = text.trim_start().to_lowercase();
+        match amounts.as_slice() {
+            [] => None,
+            [only] if lower.starts_with("até") || lower.starts_with("ate") => Some(Self {
+                min: None,
+                max: Some(only.clone()),
+            }),
+            [only] if lower.starts_with("acima") => Some(Self {
+                min: Some(only.clone()),
+                max: None,
+            }),
+            [only] => Some(Self {
+                min: Some(only.clone()),
+                max: Some(only.clone()),
+            }),
+            [first, .., last] => Some(Self {
+                min: Some(first.clone()),
+                max: Some(last.clone()),
+            }),
+        }
+    }
+}
+
+/// Multiply every `R$` amount of a text by a factor, keeping the rest as is
+///
+/// `"De R$ 1630 até R$ 4082"` × 1.9 → `"De R$ 3.097,00 até R$ 7.755,80"`
+pub fn scale_amounts(text: &str, factor: f64) -> String {
+    AMOUNT
+        .replace_all(text, |caps: &regex::Captures| match Brl::parse(&caps[1]) {
+            Some(amount) => amount.scaled(factor).to_string(),
+            None => caps[0].to_string(),
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brl(text: &str) -> String {
+        Brl::parse(text).map(|b| b.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_parse_separators() {
+        assert_eq!(brl("15000,00"), "R$ 15.000,00");
+        assert_eq!(brl("15.000,00"), "R$ 15.000,00");
+        assert_eq!(brl("R$ 1.630,5"), "R$ 1.630,50");
+        assert_eq!(brl("4082.00"), "R$ 4.082,00");
+        assert_eq!(brl("15.000"), "R$ 15.000,00");
+        assert_eq!(brl("1,234,567.891"), "R$ 1.234.567,89");
+        assert_eq!(brl("R$1630"), "R$ 1.630,00");
+        assert_eq!(brl("0,5"), "R$ 0,50");
+        assert_eq!(brl("-120"), "-R$ 120,00");
+        for invalid in ["", "R$", "abc", "12a", ",50", "1,", "1.2.3,4,5"] {
+            assert!(Brl::parse(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_scaled_is_exact() {
+        let amount = Brl::parse("1630").unwrap().scaled(1.9);
+        assert_eq!(amount.to_string(), "R$ 3.097,00");
+        assert_eq!(Brl::parse("10,01").unwrap().scaled(1.9).to_f64(), 19.02);
+    }
+
+    #[test]
+    fn test_scale_amounts_in_text() {
+        assert_eq!(
+            scale_amounts("De R$ 1630 até R$ 4082", 1.9),
+            "De R$ 3.097,00 até R$ 7.755,80"
+        );
+        assert_eq!(
+            scale_amounts("Acima de R$ 20.000,00", 2.0),
+            "Acima de R$ 40.000,00"
+        );
+        assert_eq!(scale_amounts("Sem renda", 1.9), "Sem renda");
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = BrlRange::parse("De R$ 1630 até R$ 4082").unwrap();
+        assert_eq!(range.min, Brl::parse("1630"));
+        assert_eq!(range.max, Brl::parse("4082"));
+
+        let up_to = BrlRange::parse("Até R$ 1.000").unwrap();
+        assert_eq!((up_to.min, up_to.max), (None, Brl::parse("1000")));
+        let above = BrlRange::parse("Acima de R$ 20.000").unwrap();
+        assert_eq!((above.min, above.max), (Brl::parse("20000"), None));
+        assert!(BrlRange::parse("Não informado").is_none());
+    }
+}
@@ -1,2 +1,15 @@
-// Observability helpers (logging/tracing/metrics) placeholder for future expansion.
-// Currently, tracing setup lives in main.rs; extract here if/when centralizing observability.
+// Observability helpers (logging/tracing/metrics).
+// Tracing setup lives in main.rs; provider call statistics, Prometheus
+// metrics (routes, providers, caches, enrichments) and startup readiness are
+// re-exported here.
+pub mod provider_stats {
+    pub use crate::provider_stats::*;
+}
+
+pub mod metrics {
+    pub use crate::metrics::*;
+}
+
+pub mod readiness {
+    pub use crate::readiness::*;
+}
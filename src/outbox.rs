@@ -0,0 +1,512 @@
+//! Scheduled delivery of C2S messages
+//!
+//! Messages that must not be sent right away are stored in `c2s_outbox`
+//! (migration 025) with a `send_after` timestamp and a delay reason:
+//! - quiet hours (`quiet_hours`): held until the end of the window
+//! - follow-up nudges: reminders sent some hours after the enrichment
+//! - throttled batches: spaced out so a bulk send does not hit C2S at once
+//!
+//! A background dispatcher delivers due messages and retries failed sends a
+//! few times before marking them 'failed'. Pending messages are listed by
+//! `GET /api/v1/admin/outbox`.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::lead_messages::{self, Coalesced, Recorded};
+use crate::leader::Leadership;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Interval between outbox dispatcher runs
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maximum messages delivered per dispatcher run
+const DISPATCH_BATCH: i64 = 20;
+
+/// Send attempts before a message is marked 'failed'
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before retrying a failed send
+const RETRY_DELAY_SECS: f64 = 300.0;
+
+/// Rows left in 'sending' longer than this (crashed dispatcher) are reclaimed
+const STALE_LOCK_SECS: f64 = 600.0;
+
+/// Why a message was scheduled instead of sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayReason {
+    /// Triggered during the tenant's quiet hours
+    QuietHours,
+    /// Reminder to the seller after the enrichment message
+    FollowUp,
+    /// Part of a batch spaced out over time
+    Throttled,
+}
+
+impl DelayReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DelayReason::QuietHours => "quiet_hours",
+            DelayReason::FollowUp => "follow_up",
+            DelayReason::Throttled => "throttled",
+        }
+    }
+}
+
+/// A message to deliver to a C2S lead at `send_after`
+#[derive(Debug, Clone)]
+pub struct ScheduledMessage<'a> {
+    pub lead_id: &'a str,
+    pub tenant: Option<&'a str>,
+    pub body: &'a str,
+    pub send_after: DateTime<Utc>,
+    pub reason: DelayReason,
+}
+
+/// A due message claimed by the dispatcher
+#[derive(Debug, sqlx::FromRow)]
+struct OutboxMessage {
+    id: i64,
+    lead_id: String,
+    body: String,
+    delay_reason: Option<String>,
+    attempts: i32,
+    created_at: DateTime<Utc>,
+}
+
+/// Outbox row as shown by the admin view
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub lead_id: String,
+    pub tenant: Option<String>,
+    pub status: String,
+    pub delay_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub send_after: DateTime<Utc>,
+    /// Seconds between creation and scheduled delivery
+    pub delay_seconds: i64,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// `last_error` split into its context chain
+    #[sqlx(skip)]
+    pub error_chain: Vec<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    /// First 200 characters of the message
+    pub body_preview: String,
+}
+
+/// Schedule a message, returns the outbox id
+pub async fn schedule(pool: &PgPool, message: &ScheduledMessage<'_>) -> Result<i64, AppError> {
+    let (id,): (i64,) = sqlx::query_as(
+        r#"
+        INSERT INTO c2s_outbox (lead_id, tenant, body, send_after, delay_reason)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(message.lead_id)
+    .bind(message.tenant)
+    .bind(message.body)
+    .bind(message.send_after)
+    .bind(message.reason.as_str())
+    .fetch_one(pool)
+    .timed("c2s_outbox.insert")
+    .await
+    .context(format!(
+        "Failed to schedule C2S message for lead_id: {}",
+        message.lead_id
+    ))?;
+
+    Ok(id)
+}
+
+/// Delivery times for `count` messages starting at `start`, `spacing` apart
+pub fn spaced_send_times(
+    start: DateTime<Utc>,
+    spacing: chrono::Duration,
+    count: usize,
+) -> Vec<DateTime<Utc>> {
+    (0..count).map(|i| start + spacing * i as i32).collect()
+}
+
+/// Schedule a batch of (lead_id, body) messages spaced `spacing` apart
+///
+/// Quiet hours of the tenant still apply to each delivery time.
+pub async fn schedule_batch(
+    state: &AppState,
+    tenant: Option<&str>,
+    messages: &[(String, String)],
+    spacing: chrono::Duration,
+) -> Result<Vec<i64>, AppError> {
+    let times = spaced_send_times(Utc::now(), spacing, messages.len());
+    let mut ids = Vec::with_capacity(messages.len());
+    for ((lead_id, body), send_at) in messages.iter().zip(times) {
+        let send_after = state
+            .config
+            .quiet_hours
+            .deferred_until(tenant, send_at)
+            .unwrap_or(send_at);
+        let message = ScheduledMessage {
+            lead_id,
+            tenant,
+            body,
+            send_after,
+            reason: DelayReason::Throttled,
+        };
+        ids.push(schedule(&state.db, &message).await?);
+    }
+    Ok(ids)
+}
+
+/// Schedule a follow-up reminder for the seller `after` the enrichment
+///
+/// Pushed to the end of the tenant's quiet hours when it would fall inside.
+pub async fn schedule_follow_up(
+    state: &AppState,
+    lead_id: &str,
+    tenant: Option<&str>,
+    customer_name: &str,
+    after: chrono::Duration,
+) -> Result<i64, AppError> {
+    let send_at = Utc::now() + after;
+    let send_after = state
+        .config
+        .quiet_hours
+        .deferred_until(tenant, send_at)
+        .unwrap_or(send_at);
+    let body = crate::templates::render(
+        crate::templates::FOLLOW_UP,
+        &[
+            ("customer_name", customer_name),
+            ("hours", &after.num_hours().to_string()),
+        ],
+    );
+
+    schedule(
+        &state.db,
+        &ScheduledMessage {
+            lead_id,
+            tenant,
+            body: &body,
+            send_after,
+            reason: DelayReason::FollowUp,
+        },
+    )
+    .await
+}
+
+/// Send an enrichment message to C2S now, or queue it during quiet hours
+///
+/// A message repeating a recent one for the same lead is first coalesced
+/// (`lead_messages::coalesce`): only the changed lines are sent, or nothing.
+/// Returns `true` when a message was sent immediately.
+pub async fn deliver_or_schedule(
+    state: &AppState,
+    lead_id: &str,
+    tenant: Option<&str>,
+    body: &str,
+) -> Result<bool, AppError> {
+    let window = chrono::Duration::minutes(state.config.c2s_coalesce_window_minutes);
+    let previous = if window > chrono::Duration::zero() {
+        lead_messages::load_last_message(&state.db, lead_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load last message, sending full message: {}", e);
+                None
+            })
+    } else {
+        None
+    };
+    let message = match lead_messages::coalesce(previous.as_ref(), body, Utc::now(), window) {
+        Coalesced::Full => body.to_string(),
+        Coalesced::Update(update) => {
+            tracing::info!(
+                "Lead {} messaged recently, sending only updated data",
+                lead_id
+            );
+            update
+        }
+        Coalesced::Unchanged => {
+            tracing::info!(
+                "Lead {} messaged recently with the same data, nothing to send",
+                lead_id
+            );
+            crate::metrics::record_deduplicated_message("coalesced");
+            return Ok(false);
+        }
+    };
+
+    if let Some(send_after) = state.config.quiet_hours.deferred_until(tenant, Utc::now()) {
+        let scheduled = ScheduledMessage {
+            lead_id,
+            tenant,
+            body: &message,
+            send_after,
+            reason: DelayReason::QuietHours,
+        };
+        let id = schedule(&state.db, &scheduled).await?;
+        tracing::info!(
+            "Quiet hours for lead_id={} (tenant: {}), message queued as outbox #{} until {}",
+            lead_id,
+            tenant.unwrap_or("default"),
+            id,
+            send_after
+        );
+        record_last_message(state, lead_id, body).await;
+        return Ok(false);
+    }
+
+    let sent = lead_messages::send_deduplicated(state, lead_id, &message, "enrichment").await?;
+    record_last_message(state, lead_id, body).await;
+    Ok(sent)
+}
+
+/// Send a note to C2S now, or queue it during quiet hours
+///
+/// Unlike `deliver_or_schedule`, a note is neither coalesced nor remembered
+/// as the lead's last message: a later enrichment message is sent in full.
+/// Returns `true` when the note was sent immediately.
+pub async fn deliver_note_or_schedule(
+    state: &AppState,
+    lead_id: &str,
+    tenant: Option<&str>,
+    body: &str,
+    triggered_by: &str,
+) -> Result<bool, AppError> {
+    if let Some(send_after) = state.config.quiet_hours.deferred_until(tenant, Utc::now()) {
+        let scheduled = ScheduledMessage {
+            lead_id,
+            tenant,
+            body,
+            send_after,
+            reason: DelayReason::QuietHours,
+        };
+        let id = schedule(&state.db, &scheduled).await?;
+        tracing::info!(
+            "Quiet hours for lead_id={}, {} note queued as outbox #{} until {}",
+            lead_id,
+            triggered_by,
+            id,
+            send_after
+        );
+        return Ok(false);
+    }
+    lead_messages::send_deduplicated(state, lead_id, body, triggered_by).await
+}
+
+/// Remember the full message for coalescing (non-fatal)
+async fn record_last_message(state: &AppState, lead_id: &str, body: &str) {
+    if let Err(e) =
+        lead_messages::record_message(&state.db, lead_id, Recorded::Enrichment(body)).await
+    {
+        tracing::warn!("Failed to record last message for lead {}: {}", lead_id, e);
+    }
+}
+
+/// Outbox messages for the admin view, soonest first
+///
+pub async fn list_messages(
+    pool: &PgPool,
+    status: &str,
+    limit: i64,
+) -> Result<Vec<OutboxEntry>, AppError> {
+    let mut entries = sqlx::query_as::<_, OutboxEntry>(
+        r#"
+        SELECT id, lead_id, tenant, status, delay_reason, created_at, send_after,
+               EXTRACT(EPOCH FROM (send_after - created_at))::BIGINT AS delay_seconds,
+               attempts, last_error, sent_at, LEFT(body, 200) AS body_preview
+        FROM c2s_outbox
+        WHERE status = $1
+        ORDER BY send_after
+        LIMIT $2
+        "#,
+    )
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .timed("c2s_outbox.list")
+    .await
+    .context("Failed to list outbox messages")?;
+
+    for entry in &mut entries {
+        entry.error_chain = entry
+            .last_error
+            .as_deref()
+            .map(crate::errors::split_chain)
+            .unwrap_or_default();
+    }
+    Ok(entries)
+}
+
+/// Spawn the background task that delivers due outbox messages
+///
+/// Runs on the instance holding the `outbox_dispatcher` leadership.
+pub fn spawn_outbox_dispatcher(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let leader = Leadership::new("outbox_dispatcher");
+        let mut interval = tokio::time::interval(DISPATCH_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+
+            match dispatch_due(&state).await {
+                Ok(0) => {}
+                Ok(sent) => tracing::info!("Delivered {} queued C2S message(s)", sent),
+                Err(e) => tracing::error!("Outbox dispatch failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Deliver one batch of due messages, returns how many were sent
+async fn dispatch_due(state: &AppState) -> Result<usize, AppError> {
+    let messages: Vec<OutboxMessage> = sqlx::query_as(
+        r#"
+        UPDATE c2s_outbox
+        SET status = 'sending', locked_at = now()
+        WHERE id IN (
+            SELECT id FROM c2s_outbox
+            WHERE (status = 'pending' AND send_after <= now())
+               OR (status = 'sending' AND locked_at < now() - make_interval(secs => $2))
+            ORDER BY send_after
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, lead_id, body, delay_reason, attempts, created_at
+        "#,
+    )
+    .bind(DISPATCH_BATCH)
+    .bind(STALE_LOCK_SECS)
+    .fetch_all(&state.db)
+    .timed("c2s_outbox.claim_due")
+    .await
+    .context("Failed to claim due outbox messages")?;
+
+    let mut sent = 0;
+    for message in messages {
+        let triggered_by = format!(
+            "outbox:{}",
+            message.delay_reason.as_deref().unwrap_or("scheduled")
+        );
+        let result =
+            lead_messages::send_deduplicated(state, &message.lead_id, &message.body, &triggered_by)
+                .await;
+
+        match result {
+            Ok(delivered) => {
+                if delivered {
+                    tracing::info!(
+                        "Sent queued message #{} for lead_id={} (held {}s)",
+                        message.id,
+                        message.lead_id,
+                        (Utc::now() - message.created_at).num_seconds()
+                    );
+                    sent += 1;
+                }
+                mark_sent(&state.db, message.id).await?;
+            }
+            Err(e) => {
+                let attempts = message.attempts + 1;
+                tracing::warn!(
+                    "Failed to send queued message #{} for lead_id={} (attempt {}/{}): {}",
+                    message.id,
+                    message.lead_id,
+                    attempts,
+                    MAX_ATTEMPTS,
+                    e
+                );
+                mark_failed_attempt(&state.db, message.id, attempts, &e.chain_message()).await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+async fn mark_sent(pool: &PgPool, id: i64) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE c2s_outbox
+        SET status = 'sent', sent_at = now(), attempts = attempts + 1,
+            last_error = NULL, locked_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .timed("c2s_outbox.mark_sent")
+    .await
+    .context(format!("Failed to mark outbox message #{} as sent", id))?;
+    Ok(())
+}
+
+/// Record a failed send: retry later, or give up after `MAX_ATTEMPTS`
+async fn mark_failed_attempt(
+    pool: &PgPool,
+    id: i64,
+    attempts: i32,
+    error: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE c2s_outbox
+        SET status = CASE WHEN $2 >= $4 THEN 'failed' ELSE 'pending' END,
+            attempts = $2,
+            last_error = $3,
+            send_after = CASE WHEN $2 >= $4 THEN send_after
+                              ELSE now() + make_interval(secs => $5) END,
+            locked_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(attempts)
+    .bind(error)
+    .bind(MAX_ATTEMPTS)
+    .bind(RETRY_DELAY_SECS)
+    .execute(pool)
+    .timed("c2s_outbox.mark_failed")
+    .await
+    .context(format!(
+        "Failed to record send error for outbox message #{}",
+        id
+    ))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spaced_send_times() {
+        let start = Utc::now();
+        let times = spaced_send_times(start, chrono::Duration::seconds(30), 3);
+        assert_eq!(
+            times,
+            [
+                start,
+                start + chrono::Duration::seconds(30),
+                start + chrono::Duration::seconds(60)
+            ]
+        );
+        assert!(spaced_send_times(start, chrono::Duration::seconds(30), 0).is_empty());
+    }
+
+    #[test]
+    fn test_delay_reason_names() {
+        assert_eq!(DelayReason::QuietHours.as_str(), "quiet_hours");
+        assert_eq!(DelayReason::FollowUp.as_str(), "follow_up");
+        assert_eq!(DelayReason::Throttled.as_str(), "throttled");
+    }
+}
@@ -0,0 +1,113 @@
+//! Company partners (QSA - quadro de sócios e administradores)
+//!
+//! The Work API `socios` module lists who owns and runs a company. Partners
+//! are linked to the company party (`core.party_relationships`, type
+//! `ownership`), so a lead coming from a company email can surface its
+//! decision-makers.
+
+use crate::enrichment::check_cpf;
+use chrono::NaiveDate;
+use serde_json::Value;
+
+/// One partner listed in the company QSA
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompanyPartner {
+    pub name: String,
+    /// Only set when the full CPF is present and valid (the QSA usually masks it)
+    pub cpf: Option<String>,
+    /// Qualificação ("Sócio-Administrador", "Diretor", ...)
+    pub role: Option<String>,
+    pub since: Option<NaiveDate>,
+}
+
+/// Person partners listed in a company payload
+///
+/// Reads `Socios` (`WorkApiService::fetch_company_modules`), either as a list
+/// or wrapped in `{"socios": [...]}`. Partners that are companies themselves
+/// (CNPJ document) are skipped.
+pub fn extract_partners(company_data: &Value) -> Vec<CompanyPartner> {
+    let Some(socios) = company_data.get("Socios") else {
+        return Vec::new();
+    };
+    let entries = socios
+        .as_array()
+        .or_else(|| socios.get("socios").and_then(|s| s.as_array()));
+
+    entries
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let text = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| entry.get(*key).and_then(|v| v.as_str()))
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+
+            let document: String = text(&["cpf", "documento", "cpfCnpj"])
+                .unwrap_or("")
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect();
+            if document.len() == 14 {
+                return None;
+            }
+
+            Some(CompanyPartner {
+                name: text(&["nome", "nomeSocio"])?.to_string(),
+                cpf: check_cpf(&document).is_ok().then_some(document),
+                role: text(&["qualificacao", "cargo"]).map(str::to_string),
+                since: text(&["dataEntrada"])
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%d/%m/%Y").ok()),
+            })
+        })
+        .collect()
+}
+
+/// Message section listing the partners
+pub fn format_partners(partners: &[CompanyPartner]) -> String {
+    let mut section = String::from("\n👥 SÓCIOS\n");
+    for partner in partners {
+        match &partner.role {
+            Some(role) => section.push_str(&format!("{} ({})\n", partner.name, role)),
+            None => section.push_str(&format!("{}\n", partner.name)),
+        }
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_partners() {
+        let payload = json!({
+            "Socios": [
+                {"nome": "MARIA SILVA", "cpf": "529.982.247-25", "qualificacao": "Sócio-Administrador", "dataEntrada": "15/03/2010"},
+                {"nome": "JOSE SILVA", "cpf": "***.982.247-**", "qualificacao": "Sócio"},
+                {"nome": "HOLDING TESTE LTDA", "documento": "11222333000181"},
+                {"cpf": "52998224725"}
+            ]
+        });
+
+        let partners = extract_partners(&payload);
+        assert_eq!(partners.len(), 2);
+        assert_eq!(partners[0].cpf.as_deref(), Some("52998224725"));
+        assert_eq!(partners[0].since, NaiveDate::from_ymd_opt(2010, 3, 15));
+        assert_eq!(partners[1].cpf, None);
+        assert!(format_partners(&partners).contains("JOSE SILVA (Sócio)"));
+    }
+
+    #[test]
+    fn test_extract_partners_wrapped_or_missing() {
+        let wrapped =
+            json!({"Socios": {"socios": [{"nomeSocio": "ANA LIMA", "cargo": "Diretora"}]}});
+        assert_eq!(
+            extract_partners(&wrapped)[0].role.as_deref(),
+            Some("Diretora")
+        );
+        assert!(extract_partners(&json!({"SituacaoCadastral": {}})).is_empty());
+    }
+}
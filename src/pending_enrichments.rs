@@ -0,0 +1,345 @@
+//! Deferred enrichment of leads without CPF
+//!
+//! When Diretrix finds no CPF for a lead, the enrichment workflow queues it in
+//! `pending_enrichments` (migration 039) instead of dropping it. A background
+//! job retries due leads with a growing delay (`retry_delay`), since phones
+//! and emails often become resolvable days later, up to
+//! `WORK_API_MAX_CONCURRENCY` leads at once. A successful retry runs the
+//! normal workflow, so C2S receives the enriched message late. Leads still
+//! unresolved after `MAX_ATTEMPTS` are marked 'expired'. Queued leads are
+//! listed by `GET /api/v1/admin/pending-enrichments`.
+
+use crate::circuit_breaker::Provider;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::leader::Leadership;
+use crate::templates::Locale;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Interval between retry job runs
+const RETRY_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Maximum leads retried per run
+const RETRY_BATCH: i64 = 20;
+
+/// Lookups (including the first one) before a lead is marked 'expired'
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Rows left in 'resolving' longer than this (crashed job) are reclaimed
+const STALE_LOCK_SECS: f64 = 1800.0;
+
+/// Delay before the next lookup, after `attempts` failed ones
+///
+/// 1h, 6h, 1 day, 2 days, then every 3 days.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    match attempts {
+        ..=1 => chrono::Duration::hours(1),
+        2 => chrono::Duration::hours(6),
+        3 => chrono::Duration::days(1),
+        4 => chrono::Duration::days(2),
+        _ => chrono::Duration::days(3),
+    }
+}
+
+/// A due lead claimed by the retry job
+#[derive(Debug, sqlx::FromRow)]
+struct PendingLead {
+    id: i64,
+    lead_id: String,
+    tenant: Option<String>,
+    customer_name: Option<String>,
+    phone: Option<String>,
+    email: Option<String>,
+    attempts: i32,
+    created_at: DateTime<Utc>,
+}
+
+/// Queue row as shown by the admin view
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PendingEnrichment {
+    pub id: i64,
+    pub lead_id: String,
+    pub tenant: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    /// `last_error` split into its context chain
+    #[sqlx(skip)]
+    pub error_chain: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub enriched_at: Option<DateTime<Utc>>,
+}
+
+/// Queue a lead whose CPF could not be found (first lookup already done)
+///
+/// A lead already queued keeps its row, so retries run by the job itself do
+/// not reset the schedule. Returns whether the lead was newly queued.
+pub async fn defer(
+    pool: &PgPool,
+    lead_id: &str,
+    customer_name: &str,
+    phone: Option<&str>,
+    email: Option<&str>,
+    tenant: Option<&str>,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO pending_enrichments (
+            lead_id, tenant, customer_name, phone, email, attempts, next_attempt_at
+        )
+        VALUES ($1, $2, $3, $4, $5, 1, now() + make_interval(secs => $6))
+        ON CONFLICT (lead_id) DO NOTHING
+        "#,
+    )
+    .bind(lead_id)
+    .bind(tenant)
+    .bind(customer_name)
+    .bind(phone)
+    .bind(email)
+    .bind(retry_delay(1).num_seconds() as f64)
+    .execute(pool)
+    .timed("pending_enrichments.insert")
+    .await
+    .context(format!(
+        "Failed to queue deferred enrichment for lead_id: {}",
+        lead_id
+    ))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Queued leads for the admin view, next retry first
+pub async fn list_pending(
+    pool: &PgPool,
+    status: &str,
+    limit: i64,
+) -> Result<Vec<PendingEnrichment>, AppError> {
+    let mut entries = sqlx::query_as::<_, PendingEnrichment>(
+        r#"
+        SELECT id, lead_id, tenant, status, attempts, next_attempt_at, last_error,
+               created_at, enriched_at
+        FROM pending_enrichments
+        WHERE status = $1
+        ORDER BY next_attempt_at
+        LIMIT $2
+        "#,
+    )
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .timed("pending_enrichments.list")
+    .await
+    .context("Failed to list pending enrichments")?;
+
+    for entry in &mut entries {
+        entry.error_chain = entry
+            .last_error
+            .as_deref()
+            .map(crate::errors::split_chain)
+            .unwrap_or_default();
+    }
+    Ok(entries)
+}
+
+/// Spawn the background task that retries due deferred enrichments
+///
+/// Runs on the instance holding the `pending_enrichment_retry` leadership,
+/// and only while the Diretrix breaker lets calls through.
+pub fn spawn_pending_enrichment_retry(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let leader = Leadership::new("pending_enrichment_retry");
+        let mut interval = tokio::time::interval(RETRY_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+
+            if !Provider::Diretrix.breaker().is_call_permitted() {
+                tracing::debug!("Diretrix unavailable, keeping enrichments pending");
+                continue;
+            }
+
+            match retry_due(&state).await {
+                Ok(0) => {}
+                Ok(enriched) => tracing::info!("Late enrichment of {} lead(s)", enriched),
+                Err(e) => tracing::error!("Pending enrichment retry failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Retry one batch of due leads, returns how many were enriched
+async fn retry_due(state: &Arc<AppState>) -> Result<usize, AppError> {
+    let leads: Vec<PendingLead> = sqlx::query_as(
+        r#"
+        UPDATE pending_enrichments
+        SET status = 'resolving', locked_at = now()
+        WHERE id IN (
+            SELECT id FROM pending_enrichments
+            WHERE (status = 'pending' AND next_attempt_at <= now())
+               OR (status = 'resolving' AND locked_at < now() - make_interval(secs => $2))
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, lead_id, tenant, customer_name, phone, email, attempts, created_at
+        "#,
+    )
+    .bind(RETRY_BATCH)
+    .bind(STALE_LOCK_SECS)
+    .fetch_all(&state.db)
+    .timed("pending_enrichments.claim_due")
+    .await
+    .context("Failed to claim due pending enrichments")?;
+
+    // Leads are retried concurrently; Work API calls stay within its limits
+    let concurrency = crate::rate_limit::work_api_limiter().concurrency();
+    let outcomes: Vec<Result<bool, AppError>> = stream::iter(leads)
+        .map(|lead| retry_lead(state, lead))
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut enriched = 0;
+    for outcome in outcomes {
+        if outcome? {
+            enriched += 1;
+        }
+    }
+    Ok(enriched)
+}
+
+/// Run the workflow for one claimed lead, returns whether it was enriched
+async fn retry_lead(state: &Arc<AppState>, lead: PendingLead) -> Result<bool, AppError> {
+    let result = crate::enrichment::enrich_and_send_workflow(
+        state.clone(),
+        &lead.lead_id,
+        lead.customer_name.as_deref().unwrap_or("Unknown"),
+        lead.phone.as_deref(),
+        lead.email.as_deref(),
+        lead.tenant.as_deref(),
+        Locale::default(),
+    )
+    .await;
+
+    match result {
+        Ok(result) => {
+            tracing::info!(
+                "Late enrichment of lead_id={} after {} attempt(s) ({} days queued), message sent: {}",
+                lead.lead_id,
+                lead.attempts + 1,
+                (Utc::now() - lead.created_at).num_days(),
+                result.message_sent
+            );
+            mark_enriched(&state.db, lead.id).await?;
+            return Ok(true);
+        }
+        // Provider outage: not a lookup, retry at the same pace
+        Err(e) if e.is_provider_unavailable() => {
+            reschedule(&state.db, lead.id, lead.attempts, &e.chain_message()).await?;
+        }
+        Err(e) => {
+            let attempts = lead.attempts + 1;
+            tracing::info!(
+                "Lead {} still not enrichable (attempt {}/{}): {}",
+                lead.lead_id,
+                attempts,
+                MAX_ATTEMPTS,
+                e
+            );
+            if attempts >= MAX_ATTEMPTS {
+                mark_expired(&state.db, lead.id, attempts, &e.chain_message()).await?;
+            } else {
+                reschedule(&state.db, lead.id, attempts, &e.chain_message()).await?;
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Final state: contact data is no longer needed
+async fn mark_enriched(pool: &PgPool, id: i64) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE pending_enrichments
+        SET status = 'enriched', enriched_at = now(), attempts = attempts + 1,
+            last_error = NULL, locked_at = NULL,
+            customer_name = NULL, phone = NULL, email = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .timed("pending_enrichments.mark_enriched")
+    .await
+    .context(format!(
+        "Failed to mark pending enrichment #{} as enriched",
+        id
+    ))?;
+    Ok(())
+}
+
+async fn mark_expired(pool: &PgPool, id: i64, attempts: i32, error: &str) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE pending_enrichments
+        SET status = 'expired', attempts = $2, last_error = $3, locked_at = NULL,
+            customer_name = NULL, phone = NULL, email = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(attempts)
+    .bind(error)
+    .execute(pool)
+    .timed("pending_enrichments.mark_expired")
+    .await
+    .context(format!("Failed to expire pending enrichment #{}", id))?;
+    Ok(())
+}
+
+async fn reschedule(pool: &PgPool, id: i64, attempts: i32, error: &str) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE pending_enrichments
+        SET status = 'pending', attempts = $2, last_error = $3, locked_at = NULL,
+            next_attempt_at = now() + make_interval(secs => $4)
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(attempts)
+    .bind(error)
+    .bind(retry_delay(attempts).num_seconds() as f64)
+    .execute(pool)
+    .timed("pending_enrichments.reschedule")
+    .await
+    .context(format!("Failed to reschedule pending enrichment #{}", id))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_grows_then_caps() {
+        assert_eq!(retry_delay(1), chrono::Duration::hours(1));
+        assert_eq!(retry_delay(2), chrono::Duration::hours(6));
+        assert_eq!(retry_delay(3), chrono::Duration::days(1));
+        assert_eq!(retry_delay(4), chrono::Duration::days(2));
+        assert_eq!(retry_delay(5), chrono::Duration::days(3));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), chrono::Duration::days(3));
+    }
+}
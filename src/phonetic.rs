@@ -0,0 +1,114 @@
+//! Phonetic keys for Brazilian Portuguese names (metaphone-style)
+//!
+//! Spelling variants that sound alike get the same key ("Luis", "Luís" and
+//! "Luiz" all become `LS`), so name lookups can fall back to a sound-alike
+//! match when the normalized spelling differs. Keys are stored in
+//! `core.parties.phonetic_name` (migration 022).
+
+use crate::services::normalize_name;
+
+/// Name particles ignored when building keys ("Maria da Silva" = "Maria Silva")
+const PARTICLES: [&str; 6] = ["DA", "DAS", "DE", "DO", "DOS", "E"];
+
+/// Phonetic key of a full name: one code per word, space-separated
+///
+/// Returns an empty string when the name has no usable letters.
+pub fn phonetic_key(name: &str) -> String {
+    // Ç sounds like S; normalization alone would fold it into C (K sound)
+    let name = name.replace(['ç', 'Ç'], "S");
+
+    normalize_name(&name)
+        .split(' ')
+        .filter(|word| !word.is_empty() && !PARTICLES.contains(word))
+        .map(word_key)
+        .filter(|key| !key.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Phonetic code of a single uppercase, unaccented word
+///
+/// Vowels are kept only in the first position; digraphs and soft C/G are
+/// folded to the consonant they sound like; doubled letters count once.
+fn word_key(word: &str) -> String {
+    let chars: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    let at = |i: usize| chars.get(i).copied();
+    let front_vowel = |i: usize| matches!(at(i), Some('E' | 'I' | 'Y'));
+
+    let mut key = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = at(i + 1);
+
+        // Doubled letters (SS, RR, LL, NN, ...) sound like one
+        if i > 0 && chars[i - 1] == c {
+            i += 1;
+            continue;
+        }
+
+        let (code, len) = match c {
+            'A' | 'E' | 'I' | 'O' | 'U' | 'Y' if i == 0 => {
+                (Some(if c == 'Y' { 'I' } else { c }), 1)
+            }
+            'A' | 'E' | 'I' | 'O' | 'U' | 'Y' | 'H' => (None, 1),
+            'C' if next == Some('H') => (Some('X'), 2),
+            'C' if front_vowel(i + 1) => (Some('S'), 1),
+            'C' | 'K' => (Some('K'), 1),
+            'Q' if next == Some('U') => (Some('K'), 2),
+            'Q' => (Some('K'), 1),
+            'G' if next == Some('U') && front_vowel(i + 2) => (Some('G'), 2),
+            'G' if front_vowel(i + 1) => (Some('J'), 1),
+            'L' | 'N' if next == Some('H') => (Some(c), 2),
+            'P' if next == Some('H') => (Some('F'), 2),
+            'T' if next == Some('H') => (Some('T'), 2),
+            'S' if next == Some('H') => (Some('X'), 2),
+            'S' if next == Some('C') && front_vowel(i + 2) => (Some('S'), 2),
+            'Z' => (Some('S'), 1),
+            'W' => (Some('V'), 1),
+            other => (Some(other), 1),
+        };
+
+        if let Some(code) = code {
+            key.push(code);
+        }
+        i += len;
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spelling_variants_share_key() {
+        let groups: [&[&str]; 6] = [
+            &["Luis", "Luís", "Luiz"],
+            &["Tereza", "Teresa", "Thereza"],
+            &["Felipe", "Phelipe", "Fellipe"],
+            &["Sousa", "Souza"],
+            &["Gisele", "Jisele"],
+            &["Conceição", "Conseisão"],
+        ];
+        for group in groups {
+            let expected = phonetic_key(group[0]);
+            assert!(!expected.is_empty());
+            for name in group {
+                assert_eq!(phonetic_key(name), expected, "{} vs {}", name, group[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_name_key() {
+        assert_eq!(
+            phonetic_key("Luiz Henrique de Souza"),
+            phonetic_key("LUIS HENRIQUE SOUSA")
+        );
+        assert_eq!(phonetic_key("Maria da Silva"), "MR SLV");
+        assert_ne!(phonetic_key("Luis"), phonetic_key("Lucas"));
+        assert_eq!(phonetic_key("  "), "");
+    }
+}
@@ -0,0 +1,982 @@
+//! Enrichment workflow as a pipeline of named stages
+//!
+//! `enrichment::enrich_and_send_workflow` runs the stages of the lead's
+//! tenant (lead source) over a `LeadContext`:
+//!
+//! - `resolve`: consent check, existing enrichment, CPF(s) via Diretrix
+//! - `enrich`: Work API data, annotations and Diretrix person data
+//! - `score`: summary of the primary person (wealth segment, credit score)
+//! - `format`: the C2S message (dossier, listing of interest, affordability)
+//! - `deliver`: C2S message (or outbox), follow-up nudge and downstream
+//!   integrations (notified with the `score` summary)
+//! - `persist`: storage of the enriched parties
+//!
+//! All stages run in this order by default. `TENANT_PIPELINE_STAGES` gives a
+//! tenant its own list, e.g. `["resolve","enrich","persist"]` to enrich
+//! without messaging the seller; a stage only runs after the ones it needs
+//! (`StageName::requires`). Every run goes through the pipeline's
+//! `StageHook`s; `MetricsHook` exports runs and latencies per stage.
+
+use crate::affordability::Affordability;
+use crate::config::Config;
+use crate::debug_payloads::DebugCapture;
+use crate::enrichment::{
+    annotate_enrichments, enrich_cpfs_with_work_api, find_cpf_via_diretrix,
+    find_existing_enrichment, format_enriched_message_body_in, format_no_data_note,
+    format_partial_notice, merge_diretrix_people, notify_integrations, render_lead_message,
+    schedule_follow_up_nudge, store_enriched_data, uses_diretrix_person, CpfLookupResult,
+    EnrichedLeadSummary, EnrichmentResult, ExistingEnrichment, MessageLead, NoDataReason,
+    StepTimings, WorkApiEnrichment,
+};
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use crate::templates::Locale;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// A stage of the enrichment pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageName {
+    Resolve,
+    Enrich,
+    Score,
+    Format,
+    Deliver,
+    Persist,
+}
+
+impl StageName {
+    /// Default pipeline, in order
+    pub const ALL: [StageName; 6] = [
+        StageName::Resolve,
+        StageName::Enrich,
+        StageName::Score,
+        StageName::Format,
+        StageName::Deliver,
+        StageName::Persist,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StageName::Resolve => "resolve",
+            StageName::Enrich => "enrich",
+            StageName::Score => "score",
+            StageName::Format => "format",
+            StageName::Deliver => "deliver",
+            StageName::Persist => "persist",
+        }
+    }
+
+    /// Stages that must run earlier in the pipeline
+    pub fn requires(self) -> &'static [StageName] {
+        match self {
+            StageName::Resolve => &[],
+            StageName::Enrich => &[StageName::Resolve],
+            StageName::Score | StageName::Format | StageName::Persist => &[StageName::Enrich],
+            // Downstream integrations are notified with the `score` summary
+            StageName::Deliver => &[StageName::Score, StageName::Format],
+        }
+    }
+}
+
+/// Check a stage list: not empty, no stage twice, requirements met in order
+pub fn validate_stages(stages: &[StageName]) -> Result<(), String> {
+    if stages.is_empty() {
+        return Err("no stages".to_string());
+    }
+    for (idx, stage) in stages.iter().enumerate() {
+        let earlier = &stages[..idx];
+        if earlier.contains(stage) {
+            return Err(format!("stage '{}' listed twice", stage.as_str()));
+        }
+        if let Some(missing) = stage.requires().iter().find(|r| !earlier.contains(r)) {
+            return Err(format!(
+                "stage '{}' needs '{}' before it",
+                stage.as_str(),
+                missing.as_str()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Tenant (lowercase) -> stages of its leads
+pub type TenantStages = HashMap<String, Vec<StageName>>;
+
+/// Parse `TENANT_PIPELINE_STAGES` (JSON, empty string = default pipeline for all)
+pub fn parse_tenant_stages(json: &str) -> anyhow::Result<TenantStages> {
+    if json.trim().is_empty() {
+        return Ok(TenantStages::new());
+    }
+    let stages: TenantStages = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid TENANT_PIPELINE_STAGES JSON: {}", e))?;
+    stages
+        .into_iter()
+        .map(|(tenant, stages)| {
+            validate_stages(&stages).map_err(|e| {
+                anyhow::anyhow!("TENANT_PIPELINE_STAGES: tenant '{}': {}", tenant, e)
+            })?;
+            Ok((tenant.trim().to_lowercase(), stages))
+        })
+        .collect()
+}
+
+/// Stages run for a tenant's leads (the default pipeline unless configured)
+pub fn stages_for(config: &Config, tenant: Option<&str>) -> Vec<StageName> {
+    tenant
+        .and_then(|t| config.tenant_pipeline_stages.get(&t.trim().to_lowercase()))
+        .cloned()
+        .unwrap_or_else(|| StageName::ALL.to_vec())
+}
+
+/// A lead flowing through the stages
+///
+/// Inputs are set on creation; each stage fills the fields it produces.
+pub struct LeadContext {
+    pub state: Arc<AppState>,
+    pub lead_id: String,
+    pub customer_name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub tenant: Option<String>,
+    /// Language of the message headers (Portuguese unless set)
+    pub locale: Locale,
+    pub timings: StepTimings,
+    pub capture: DebugCapture,
+    /// Enrichment found by `resolve`: `enrich` and `persist` are skipped
+    pub existing: Option<ExistingEnrichment>,
+    pub cpf_result: Option<CpfLookupResult>,
+    pub work: Option<WorkApiEnrichment>,
+    pub summary: Option<EnrichedLeadSummary>,
+    pub affordability: Option<Affordability>,
+    pub message_body: Option<String>,
+    pub message_sent: bool,
+    pub entity_ids: Vec<Uuid>,
+    pub stored_count: usize,
+    /// Set when the lookup ended without data; the seller is told so
+    pub no_data: Option<NoDataReason>,
+}
+
+impl LeadContext {
+    pub fn new(
+        state: Arc<AppState>,
+        lead_id: &str,
+        customer_name: &str,
+        phone: Option<&str>,
+        email: Option<&str>,
+        tenant: Option<&str>,
+    ) -> Self {
+        let mut capture = DebugCapture::start(&state, lead_id);
+        capture.record("input", || {
+            (
+                json!({
+                    "customer_name": customer_name,
+                    "phone": phone,
+                    "email": email,
+                    "tenant": tenant,
+                }),
+                Value::Null,
+            )
+        });
+
+        Self {
+            state,
+            lead_id: lead_id.to_string(),
+            customer_name: customer_name.to_string(),
+            phone: phone.map(str::to_string),
+            email: email.map(str::to_string),
+            tenant: tenant.map(str::to_string),
+            locale: Locale::default(),
+            timings: StepTimings::default(),
+            capture,
+            existing: None,
+            cpf_result: None,
+            work: None,
+            summary: None,
+            affordability: None,
+            message_body: None,
+            message_sent: false,
+            entity_ids: Vec::new(),
+            stored_count: 0,
+            no_data: None,
+        }
+    }
+
+    /// Result of the workflow, `stages` being the ones that ran
+    pub fn into_result(self, stages: Vec<StageName>) -> EnrichmentResult {
+        let work = self.work.unwrap_or_default();
+        EnrichmentResult {
+            lead_id: self.lead_id,
+            cpfs_enriched: work.cpfs,
+            same_person: self.cpf_result.as_ref().is_some_and(|c| c.same_person),
+            message_sent: self.message_sent,
+            stored_count: self.stored_count,
+            entity_ids: self.entity_ids,
+            summary: self.summary,
+            missing: work.missing,
+            timings: self.timings,
+            stages,
+        }
+    }
+
+    /// Tell the seller the enrichment found nothing, when it did (non-fatal)
+    ///
+    /// The note is not remembered as the lead's last message, so the dossier
+    /// of a later successful retry is sent in full.
+    pub async fn deliver_no_data_note(&mut self) {
+        let Some(reason) = self.no_data.as_ref() else {
+            return;
+        };
+        let note = format_no_data_note(self.phone.as_deref(), self.email.as_deref(), reason);
+        let sent = crate::outbox::deliver_note_or_schedule(
+            &self.state,
+            &self.lead_id,
+            self.tenant.as_deref(),
+            &note,
+            "no_data",
+        )
+        .await;
+        match &sent {
+            Ok(_) => tracing::info!("No-data note for lead {} delivered", self.lead_id),
+            Err(e) => tracing::warn!(
+                "Failed to send no-data note for lead {}: {}",
+                self.lead_id,
+                e
+            ),
+        }
+        self.capture.record("no_data_note", || {
+            (
+                json!({ "body": note }),
+                match &sent {
+                    Ok(sent) => json!({ "message_sent": sent }),
+                    Err(e) => json!({ "error": e.to_string() }),
+                },
+            )
+        });
+    }
+}
+
+/// Outcome of a stage that did not fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageOutcome {
+    Done,
+    /// Nothing to do for this lead (e.g. `enrich` for an already enriched lead)
+    Skipped,
+}
+
+#[async_trait]
+pub trait Stage: Send + Sync {
+    fn name(&self) -> StageName;
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError>;
+}
+
+/// Called around every stage run
+pub trait StageHook: Send + Sync {
+    fn before(&self, _stage: StageName, _ctx: &LeadContext) {}
+    fn after(
+        &self,
+        _stage: StageName,
+        _ctx: &LeadContext,
+        _outcome: &Result<StageOutcome, AppError>,
+        _elapsed: Duration,
+    ) {
+    }
+}
+
+/// Runs and latencies per stage, exported at `/metrics`
+pub struct MetricsHook;
+
+impl StageHook for MetricsHook {
+    fn after(
+        &self,
+        stage: StageName,
+        _ctx: &LeadContext,
+        outcome: &Result<StageOutcome, AppError>,
+        elapsed: Duration,
+    ) {
+        let outcome = match outcome {
+            Ok(StageOutcome::Done) => "ok",
+            Ok(StageOutcome::Skipped) => "skipped",
+            Err(_) => "error",
+        };
+        crate::metrics::record_pipeline_stage(stage.as_str(), outcome, elapsed);
+    }
+}
+
+/// Stages to run, in order, and their hooks
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+    hooks: Vec<Box<dyn StageHook>>,
+}
+
+impl Pipeline {
+    /// Pipeline of the given stages (validated by `validate_stages`), with `MetricsHook`
+    pub fn new(stages: &[StageName]) -> Self {
+        Self {
+            stages: stages.iter().map(|name| stage(*name)).collect(),
+            hooks: vec![Box::new(MetricsHook)],
+        }
+    }
+
+    pub fn for_tenant(config: &Config, tenant: Option<&str>) -> Self {
+        Self::new(&stages_for(config, tenant))
+    }
+
+    pub fn with_hook(mut self, hook: impl StageHook + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn stage_names(&self) -> Vec<StageName> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// Run the stages in order, stopping at the first failure
+    ///
+    /// Returns the stages that ran (skipped ones left out).
+    pub async fn run(&self, ctx: &mut LeadContext) -> Result<Vec<StageName>, AppError> {
+        let mut ran = Vec::new();
+        for stage in &self.stages {
+            let name = stage.name();
+            for hook in &self.hooks {
+                hook.before(name, ctx);
+            }
+            let start = Instant::now();
+            let outcome = stage.run(ctx).await;
+            let elapsed = start.elapsed();
+            for hook in &self.hooks {
+                hook.after(name, ctx, &outcome, elapsed);
+            }
+            if outcome? == StageOutcome::Done {
+                ran.push(name);
+            }
+        }
+        Ok(ran)
+    }
+}
+
+fn stage(name: StageName) -> Box<dyn Stage> {
+    match name {
+        StageName::Resolve => Box::new(ResolveStage),
+        StageName::Enrich => Box::new(EnrichStage),
+        StageName::Score => Box::new(ScoreStage),
+        StageName::Format => Box::new(FormatStage),
+        StageName::Deliver => Box::new(DeliverStage),
+        StageName::Persist => Box::new(PersistStage),
+    }
+}
+
+/// Error of a stage whose input was not produced (stages out of order)
+fn missing_input(stage: StageName, input: StageName) -> AppError {
+    AppError::InternalError(format!(
+        "Pipeline stage '{}' ran before '{}'",
+        stage.as_str(),
+        input.as_str()
+    ))
+}
+
+struct ResolveStage;
+
+#[async_trait]
+impl Stage for ResolveStage {
+    fn name(&self) -> StageName {
+        StageName::Resolve
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        let state = ctx.state.clone();
+        let config = &state.config;
+        let customer_name = ctx.customer_name.as_str();
+        let phone = ctx.phone.as_deref();
+        let email = ctx.email.as_deref();
+        let tenant = ctx.tenant.as_deref();
+
+        // Tenants requiring consent are never enriched without one
+        let subjects: Vec<(crate::consent::SubjectKind, &str)> = [
+            phone.map(|p| (crate::consent::SubjectKind::Phone, p)),
+            email.map(|e| (crate::consent::SubjectKind::Email, e)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        crate::consent::ensure_enrichment_consent(&state.db, config, tenant, &subjects).await?;
+
+        // OPTIMIZATION: Check DB/Cache first
+        if let Ok(Some(existing)) = StepTimings::measure(
+            &mut ctx.timings.db_ms,
+            find_existing_enrichment(&state, phone, email),
+        )
+        .await
+        {
+            tracing::info!("✅ Found existing enrichment for CPF: {}", existing.cpf);
+            ctx.capture.add_cpfs(std::slice::from_ref(&existing.cpf));
+            ctx.capture.record("existing_enrichment", || {
+                (
+                    json!({ "phone": phone, "email": email }),
+                    json!({
+                        "party_id": existing.party_id,
+                        "cpf": existing.cpf,
+                        "enriched_data": existing.enriched_data,
+                    }),
+                )
+            });
+
+            if let Some(data) = existing.enriched_data.clone() {
+                ctx.cpf_result = Some(CpfLookupResult {
+                    cpfs: vec![existing.cpf.clone()],
+                    same_person: true,
+                    match_scores: vec![],
+                    diretrix_people: vec![],
+                });
+                ctx.work = Some(WorkApiEnrichment {
+                    cpfs: vec![existing.cpf.clone()],
+                    data: vec![data],
+                    missing: vec![],
+                });
+                ctx.entity_ids = vec![existing.party_id];
+                ctx.existing = Some(existing);
+                return Ok(StageOutcome::Done);
+            }
+            tracing::warn!(
+                "Found existing enrichment but failed to parse data, falling back to external APIs"
+            );
+        }
+
+        tracing::info!("Finding CPF via Diretrix");
+        let diretrix = crate::tenant_credentials::diretrix_for(&state, tenant);
+        let diretrix_request = || json!({ "name": customer_name, "phone": phone, "email": email });
+        let cpf_result = StepTimings::measure(
+            &mut ctx.timings.diretrix_ms,
+            find_cpf_via_diretrix(
+                Some(customer_name),
+                phone,
+                email,
+                diretrix.as_ref(),
+                config.identity_match_threshold,
+            ),
+        )
+        .await
+        .inspect_err(|e| {
+            ctx.capture.record("diretrix", || {
+                (diretrix_request(), json!({ "error": e.to_string() }))
+            })
+        });
+        // No CPF yet: queue the lead for a later lookup instead of dropping it
+        if let Err(AppError::NotFound(_)) = &cpf_result {
+            let mut retry_scheduled = false;
+            let mut already_pending = false;
+            if phone.is_some() || email.is_some() {
+                match crate::pending_enrichments::defer(
+                    &state.db,
+                    &ctx.lead_id,
+                    customer_name,
+                    phone,
+                    email,
+                    tenant,
+                )
+                .await
+                {
+                    Ok(true) => {
+                        tracing::info!("No CPF for lead {}, enrichment deferred", ctx.lead_id);
+                        retry_scheduled = true;
+                    }
+                    Ok(false) => already_pending = true,
+                    Err(e) => tracing::warn!("{}", e),
+                }
+            }
+            // A retry of a pending lead: the seller was told on the first attempt
+            if !already_pending {
+                ctx.no_data = Some(NoDataReason::NoCpf { retry_scheduled });
+            }
+        }
+        let cpf_result = cpf_result?;
+        ctx.capture.add_cpfs(&cpf_result.cpfs);
+        ctx.capture.record("diretrix", || {
+            (
+                diretrix_request(),
+                json!({
+                    "cpfs": cpf_result.cpfs,
+                    "same_person": cpf_result.same_person,
+                    "match_scores": cpf_result.match_scores,
+                    "diretrix_people": cpf_result.diretrix_people,
+                }),
+            )
+        });
+
+        tracing::info!(
+            "Found {} CPF(s), same_person: {}",
+            cpf_result.cpfs.len(),
+            cpf_result.same_person
+        );
+        ctx.cpf_result = Some(cpf_result);
+        Ok(StageOutcome::Done)
+    }
+}
+
+struct EnrichStage;
+
+#[async_trait]
+impl Stage for EnrichStage {
+    fn name(&self) -> StageName {
+        StageName::Enrich
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        if ctx.existing.is_some() {
+            return Ok(StageOutcome::Skipped);
+        }
+        let state = ctx.state.clone();
+        let config = &state.config;
+        let cpf_result = ctx
+            .cpf_result
+            .as_ref()
+            .ok_or_else(|| missing_input(StageName::Enrich, StageName::Resolve))?;
+
+        tracing::info!("Enriching {} CPF(s) with Work API", cpf_result.cpfs.len());
+        // Sampled leads also go to the shadow provider, compared in the background
+        let shadow = crate::shadow::start(config, &ctx.lead_id, &cpf_result.cpfs);
+        let work_api = crate::tenant_credentials::work_api_for(&state, ctx.tenant.as_deref());
+        let mut work = StepTimings::measure(
+            &mut ctx.timings.work_api_ms,
+            enrich_cpfs_with_work_api(&cpf_result.cpfs, work_api.as_ref()),
+        )
+        .await
+        .inspect_err(|e| {
+            ctx.capture.record("work_api", || {
+                (
+                    json!({ "cpfs": cpf_result.cpfs }),
+                    json!({ "error": e.to_string() }),
+                )
+            });
+            // An outage is not an empty result: the lead is retried as a failure
+            if !matches!(e, AppError::ProviderUnavailable(_)) {
+                ctx.no_data = Some(NoDataReason::NoWorkApiData {
+                    cpfs: cpf_result.cpfs.clone(),
+                });
+            }
+        })?;
+        ctx.capture.record("work_api", || {
+            (
+                json!({ "cpfs": cpf_result.cpfs }),
+                json!({ "data": work.data, "completeness": work.completeness() }),
+            )
+        });
+        if let Some(shadow) = shadow {
+            shadow.finish(
+                state.db.clone(),
+                &work.cpfs,
+                &work.data,
+                ctx.timings.work_api_ms,
+            );
+        }
+        crate::work_api_fixtures::record_sampled(&state.db, config, &work.cpfs, &work.data);
+        annotate_enrichments(cpf_result, &mut work);
+
+        // Secondary source: Diretrix person data, for the tenants that enabled it
+        if uses_diretrix_person(config, ctx.tenant.as_deref()) {
+            let diretrix = crate::tenant_credentials::diretrix_for(&state, ctx.tenant.as_deref());
+            let added = StepTimings::measure(
+                &mut ctx.timings.diretrix_ms,
+                merge_diretrix_people(&work.cpfs, &mut work.data, diretrix.as_ref()),
+            )
+            .await;
+            tracing::info!("Merged {} Diretrix person entries", added);
+            ctx.capture.record("diretrix_person", || {
+                (
+                    json!({ "cpfs": cpf_result.cpfs }),
+                    json!({ "entries_added": added }),
+                )
+            });
+        }
+
+        ctx.work = Some(work);
+        Ok(StageOutcome::Done)
+    }
+}
+
+struct ScoreStage;
+
+#[async_trait]
+impl Stage for ScoreStage {
+    fn name(&self) -> StageName {
+        StageName::Score
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        let work = ctx
+            .work
+            .as_ref()
+            .ok_or_else(|| missing_input(StageName::Score, StageName::Enrich))?;
+        let Some(primary) = work.data.first() else {
+            return Ok(StageOutcome::Skipped);
+        };
+
+        let mut summary = EnrichedLeadSummary::from_work_data(
+            &ctx.lead_id,
+            &ctx.customer_name,
+            ctx.phone.as_deref(),
+            ctx.email.as_deref(),
+            work.cpfs.first().map(|c| c.as_str()),
+            primary,
+        );
+        summary.affordability = ctx.affordability;
+        ctx.summary = Some(summary);
+        Ok(StageOutcome::Done)
+    }
+}
+
+struct FormatStage;
+
+#[async_trait]
+impl Stage for FormatStage {
+    fn name(&self) -> StageName {
+        StageName::Format
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        let work = ctx
+            .work
+            .as_ref()
+            .ok_or_else(|| missing_input(StageName::Format, StageName::Enrich))?;
+        let phone = ctx.phone.as_deref();
+        let email = ctx.email.as_deref();
+
+        tracing::info!("Formatting enriched message");
+        let (message_body, affordability) = match (&ctx.existing, &ctx.cpf_result) {
+            // Already enriched lead: dossier of the stored data only
+            (Some(_), _) => (
+                format_enriched_message_body_in(
+                    &ctx.customer_name,
+                    phone.unwrap_or(""),
+                    email.unwrap_or(""),
+                    &work.data,
+                    true,
+                    ctx.locale,
+                ),
+                None,
+            ),
+            // People keep their phone/email position when one is missing
+            (None, Some(cpf_result)) => {
+                let lead = MessageLead {
+                    name: &ctx.customer_name,
+                    phone,
+                    email,
+                    locale: ctx.locale,
+                };
+                let (mut message_body, affordability) = render_lead_message(
+                    &ctx.state,
+                    &ctx.lead_id,
+                    lead,
+                    &work.aligned(&cpf_result.cpfs),
+                    cpf_result.same_person,
+                )
+                .await;
+                message_body.push_str(&format_partial_notice(&work.missing));
+                (message_body, affordability)
+            }
+            (None, None) => return Err(missing_input(StageName::Format, StageName::Resolve)),
+        };
+
+        if let Some(summary) = ctx.summary.as_mut() {
+            summary.affordability = affordability;
+        }
+        ctx.affordability = affordability;
+        ctx.message_body = Some(message_body);
+        Ok(StageOutcome::Done)
+    }
+}
+
+struct DeliverStage;
+
+#[async_trait]
+impl Stage for DeliverStage {
+    fn name(&self) -> StageName {
+        StageName::Deliver
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        let state = ctx.state.clone();
+        let message_body = ctx
+            .message_body
+            .as_deref()
+            .ok_or_else(|| missing_input(StageName::Deliver, StageName::Format))?;
+        let tenant = ctx.tenant.as_deref();
+
+        tracing::info!(
+            "Sending message to C2S (length: {} chars)",
+            message_body.len()
+        );
+        let message_sent = StepTimings::measure(
+            &mut ctx.timings.c2s_send_ms,
+            crate::outbox::deliver_or_schedule(&state, &ctx.lead_id, tenant, message_body),
+        )
+        .await?;
+        ctx.capture.record("c2s_message", || {
+            (
+                json!({ "body": message_body }),
+                json!({ "message_sent": message_sent }),
+            )
+        });
+        ctx.message_sent = message_sent;
+        schedule_follow_up_nudge(&state, &ctx.lead_id, tenant, &ctx.customer_name).await;
+
+        // Downstream integrations (non-fatal)
+        match ctx.summary.as_ref() {
+            Some(summary) => notify_integrations(&state, summary).await,
+            None => tracing::info!(
+                "No summary for lead {} (score skipped), integrations not notified",
+                ctx.lead_id
+            ),
+        }
+        Ok(StageOutcome::Done)
+    }
+}
+
+struct PersistStage;
+
+#[async_trait]
+impl Stage for PersistStage {
+    fn name(&self) -> StageName {
+        StageName::Persist
+    }
+
+    async fn run(&self, ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+        if ctx.existing.is_some() {
+            return Ok(StageOutcome::Skipped);
+        }
+        let state = ctx.state.clone();
+        let work = ctx
+            .work
+            .as_ref()
+            .ok_or_else(|| missing_input(StageName::Persist, StageName::Enrich))?;
+
+        tracing::info!("Storing {} person(s) in database", work.cpfs.len());
+        let stored_entity_ids = StepTimings::measure(
+            &mut ctx.timings.db_ms,
+            store_enriched_data(&state, &work.cpfs, &work.data, Some(ctx.lead_id.as_str())),
+        )
+        .await?;
+        ctx.capture.record("storage", || {
+            (
+                json!({ "cpfs": work.cpfs }),
+                json!({ "entity_ids": stored_entity_ids }),
+            )
+        });
+
+        // The first CPF is the phone match: without any address, fall back to the DDD region
+        if let (Some(party_id), Some(region)) = (
+            stored_entity_ids.first(),
+            ctx.phone.as_deref().and_then(crate::ddd::region_for_phone),
+        ) {
+            match StepTimings::measure(
+                &mut ctx.timings.db_ms,
+                state.services.storage.store_ddd_address(*party_id, region),
+            )
+            .await
+            {
+                Ok(true) => tracing::info!(
+                    "No address for party {}, stored DDD {} region ({} - {})",
+                    party_id,
+                    region.ddd,
+                    region.city,
+                    region.uf
+                ),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Failed to store DDD address: {}", e),
+            }
+        }
+
+        ctx.stored_count = stored_entity_ids.len();
+        ctx.entity_ids = stored_entity_ids;
+        Ok(StageOutcome::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{test_config, test_state};
+    use std::sync::Mutex;
+    use StageName::*;
+
+    /// Stage ending with a fixed outcome (`None`: failure)
+    struct FixedStage(StageName, Option<StageOutcome>);
+
+    #[async_trait]
+    impl Stage for FixedStage {
+        fn name(&self) -> StageName {
+            self.0
+        }
+
+        async fn run(&self, _ctx: &mut LeadContext) -> Result<StageOutcome, AppError> {
+            self.1
+                .ok_or_else(|| AppError::InternalError(format!("{} failed", self.0.as_str())))
+        }
+    }
+
+    /// Hook recording every call, e.g. "before resolve" and "after resolve: ok"
+    #[derive(Clone, Default)]
+    struct RecordingHook(Arc<Mutex<Vec<String>>>);
+
+    impl StageHook for RecordingHook {
+        fn before(&self, stage: StageName, _ctx: &LeadContext) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("before {}", stage.as_str()));
+        }
+
+        fn after(
+            &self,
+            stage: StageName,
+            _ctx: &LeadContext,
+            outcome: &Result<StageOutcome, AppError>,
+            _elapsed: Duration,
+        ) {
+            let outcome = match outcome {
+                Ok(StageOutcome::Done) => "ok",
+                Ok(StageOutcome::Skipped) => "skipped",
+                Err(_) => "error",
+            };
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("after {}: {}", stage.as_str(), outcome));
+        }
+    }
+
+    fn pipeline(stages: Vec<FixedStage>, hook: &RecordingHook) -> Pipeline {
+        Pipeline {
+            stages: stages
+                .into_iter()
+                .map(|s| Box::new(s) as Box<dyn Stage>)
+                .collect(),
+            hooks: vec![],
+        }
+        .with_hook(hook.clone())
+    }
+
+    fn lead_context() -> LeadContext {
+        let config = test_config(
+            "http://work-api.invalid".to_string(),
+            "http://diretrix.invalid".to_string(),
+            "http://c2s.invalid".to_string(),
+        );
+        LeadContext::new(
+            Arc::new(test_state(config)),
+            "lead-1",
+            "Maria Souza",
+            Some("11987654321"),
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_runs_stages_in_order_through_hooks() {
+        let hook = RecordingHook::default();
+        let pipeline = pipeline(
+            vec![
+                FixedStage(Resolve, Some(StageOutcome::Done)),
+                FixedStage(Enrich, Some(StageOutcome::Skipped)),
+                FixedStage(Persist, Some(StageOutcome::Done)),
+            ],
+            &hook,
+        );
+
+        let ran = pipeline.run(&mut lead_context()).await.unwrap();
+
+        // Skipped stages are hooked but not reported as run
+        assert_eq!(ran, vec![Resolve, Persist]);
+        assert_eq!(
+            *hook.0.lock().unwrap(),
+            vec![
+                "before resolve",
+                "after resolve: ok",
+                "before enrich",
+                "after enrich: skipped",
+                "before persist",
+                "after persist: ok",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stops_at_failed_stage() {
+        let hook = RecordingHook::default();
+        let pipeline = pipeline(
+            vec![
+                FixedStage(Resolve, Some(StageOutcome::Done)),
+                FixedStage(Enrich, None),
+                FixedStage(Persist, Some(StageOutcome::Done)),
+            ],
+            &hook,
+        );
+
+        let result = pipeline.run(&mut lead_context()).await;
+
+        assert!(matches!(result, Err(AppError::InternalError(msg)) if msg == "enrich failed"));
+        assert_eq!(
+            *hook.0.lock().unwrap(),
+            vec![
+                "before resolve",
+                "after resolve: ok",
+                "before enrich",
+                "after enrich: error",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_pipeline_is_valid() {
+        assert_eq!(validate_stages(&StageName::ALL), Ok(()));
+        assert_eq!(validate_stages(&[Resolve, Enrich, Persist]), Ok(()));
+        // Reordered: stored before the seller is messaged
+        assert_eq!(
+            validate_stages(&[Resolve, Enrich, Persist, Score, Format, Deliver]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_stages_rejects_missing_and_repeated_stages() {
+        assert!(validate_stages(&[]).is_err());
+        assert_eq!(
+            validate_stages(&[Resolve, Enrich, Deliver]),
+            Err("stage 'deliver' needs 'score' before it".to_string())
+        );
+        assert_eq!(
+            validate_stages(&[Resolve, Enrich, Format, Deliver]),
+            Err("stage 'deliver' needs 'score' before it".to_string())
+        );
+        assert_eq!(
+            validate_stages(&[Resolve, Enrich, Score, Deliver]),
+            Err("stage 'deliver' needs 'format' before it".to_string())
+        );
+        assert_eq!(
+            validate_stages(&[Enrich, Resolve]),
+            Err("stage 'enrich' needs 'resolve' before it".to_string())
+        );
+        assert_eq!(
+            validate_stages(&[Resolve, Resolve]),
+            Err("stage 'resolve' listed twice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_tenant_stages() {
+        let stages =
+            parse_tenant_stages(r#"{" Parceiro-X ": ["resolve", "enrich", "persist"]}"#).unwrap();
+        assert_eq!(stages["parceiro-x"], vec![Resolve, Enrich, Persist]);
+
+        assert!(parse_tenant_stages("").unwrap().is_empty());
+        assert!(parse_tenant_stages(r#"{"x": ["resolve", "deliver"]}"#).is_err());
+        assert!(parse_tenant_stages(r#"{"x": ["notify"]}"#).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Startup configuration and connectivity check (`rust-c2s-api --check`)
+//!
+//! Validates the configuration, connects to the database, prepares the
+//! critical SQL statements (`schema_check`) and sends one cheap
+//! authenticated request to C2S, Work API and Diretrix, then prints a report.
+//! Deploys run it before switching traffic so a bad token or an unreachable
+//! provider fails the deploy instead of the first real lead.
+//!
+//! Provider probes only look at the HTTP status: 401/403 means the
+//! credentials were rejected, 5xx or a connection error means the provider is
+//! unreachable. Any other status (including 404 for the dummy lookups) proves
+//! the credentials were accepted.
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::schema_check;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Timeout of each provider probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of one check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+    pub elapsed: Duration,
+}
+
+/// Every check run by `--check`
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.ok)
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Preflight check")?;
+        for check in &self.checks {
+            writeln!(
+                f,
+                "  {} {:<10} {} ({}ms)",
+                if check.ok { "✓" } else { "✗" },
+                check.name,
+                check.detail,
+                check.elapsed.as_millis()
+            )?;
+        }
+        let failed = self.checks.iter().filter(|c| !c.ok).count();
+        if failed == 0 {
+            write!(f, "All {} checks passed", self.checks.len())
+        } else {
+            write!(f, "{} of {} checks failed", failed, self.checks.len())
+        }
+    }
+}
+
+/// Interpret the HTTP status of a provider probe
+pub fn classify_status(status: u16) -> Result<String, String> {
+    match status {
+        401 | 403 => Err(format!("credentials rejected (HTTP {})", status)),
+        500..=599 => Err(format!("provider error (HTTP {})", status)),
+        _ => Ok(format!("reachable, authenticated (HTTP {})", status)),
+    }
+}
+
+/// Load the configuration and run every check
+///
+/// A configuration error stops the check (nothing else can be tested).
+pub async fn run() -> PreflightReport {
+    let mut report = PreflightReport::default();
+
+    let start = Instant::now();
+    let config = match Config::from_env() {
+        Ok(config) => {
+            report.checks.push(CheckResult {
+                name: "config",
+                ok: true,
+                detail: "all values valid".to_string(),
+                elapsed: start.elapsed(),
+            });
+            config
+        }
+        Err(e) => {
+            report.checks.push(CheckResult {
+                name: "config",
+                ok: false,
+                detail: e.to_string(),
+                elapsed: start.elapsed(),
+            });
+            return report;
+        }
+    };
+
+    report.checks.extend(check_database(&config).await);
+
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            report.checks.push(CheckResult {
+                name: "http",
+                ok: false,
+                detail: format!("Failed to create HTTP client: {}", e),
+                elapsed: Duration::ZERO,
+            });
+            return report;
+        }
+    };
+
+    // Listing a single lead is the cheapest authenticated C2S call
+    let c2s = client
+        .get(format!("{}/integration/leads", config.c2s_base_url))
+        .query(&[("perpage", "1")])
+        .bearer_auth(&config.c2s_token);
+    report.checks.push(probe("c2s", c2s).await);
+
+    // An all-zeros CPF is rejected before any billable lookup
+    let work_api = client
+        .get(format!("{}/api", config.work_api_base_url))
+        .query(&[
+            ("token", config.worker_api_key.as_str()),
+            ("modulo", "cpf"),
+            ("consulta", "00000000000"),
+        ]);
+    report.checks.push(probe("work_api", work_api).await);
+
+    let diretrix = client
+        .get(format!(
+            "{}/Consultas/Pessoa/Telefone/0000000000",
+            config.diretrix_base_url
+        ))
+        .basic_auth(&config.diretrix_user, Some(&config.diretrix_pass));
+    report.checks.push(probe("diretrix", diretrix).await);
+
+    report
+}
+
+/// Database connection, then the schema self-test when connected
+async fn check_database(config: &Config) -> Vec<CheckResult> {
+    let start = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, Database::new(&config.database_url)).await;
+    let db = match result {
+        Ok(Ok(db)) => db,
+        Ok(Err(e)) => {
+            return vec![failed(
+                "database",
+                format!("connection failed: {}", e),
+                start,
+            )]
+        }
+        Err(_) => {
+            let detail = format!("connection timed out after {}s", PROBE_TIMEOUT.as_secs());
+            return vec![failed("database", detail, start)];
+        }
+    };
+    let connected = CheckResult {
+        name: "database",
+        ok: true,
+        detail: "connected".to_string(),
+        elapsed: start.elapsed(),
+    };
+
+    let start = Instant::now();
+    let issues = schema_check::verify(&db.pool).await;
+    let schema = if issues.is_empty() {
+        CheckResult {
+            name: "schema",
+            ok: true,
+            detail: format!(
+                "{} critical statements prepared",
+                schema_check::CRITICAL_STATEMENTS.len()
+            ),
+            elapsed: start.elapsed(),
+        }
+    } else {
+        failed("schema", schema_check::render_report(&issues), start)
+    };
+    vec![connected, schema]
+}
+
+fn failed(name: &'static str, detail: String, start: Instant) -> CheckResult {
+    CheckResult {
+        name,
+        ok: false,
+        detail,
+        elapsed: start.elapsed(),
+    }
+}
+
+async fn probe(name: &'static str, request: reqwest::RequestBuilder) -> CheckResult {
+    let start = Instant::now();
+    let (ok, detail) = match request.send().await {
+        Ok(response) => match classify_status(response.status().as_u16()) {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        },
+        // Without the URL: the Work API token is a query parameter
+        Err(e) => (false, format!("unreachable: {}", e.without_url())),
+    };
+    CheckResult {
+        name,
+        ok,
+        detail,
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_status() {
+        assert!(classify_status(200).is_ok());
+        assert!(classify_status(404).is_ok());
+        assert!(classify_status(401).is_err());
+        assert!(classify_status(403).is_err());
+        assert!(classify_status(503).is_err());
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let check = |name, ok| CheckResult {
+            name,
+            ok,
+            detail: String::new(),
+            elapsed: Duration::ZERO,
+        };
+        let report = PreflightReport {
+            checks: vec![check("config", true), check("c2s", false)],
+        };
+        assert!(!report.passed());
+        assert!(report.to_string().ends_with("1 of 2 checks failed"));
+    }
+}
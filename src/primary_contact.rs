@@ -0,0 +1,62 @@
+//! Election of the primary contacts of a party
+//!
+//! `is_primary` used to be set on whichever phone or email came first in the
+//! provider answer at insert time, and never revisited. It is now re-elected
+//! each time contacts are stored, once among the party's emails and once
+//! among its phones (WhatsApp included), preferring in order:
+//!
+//! 1. contacts still returned by the provider (`valid_to` unset, migration 046)
+//! 2. verified contacts
+//! 3. WhatsApp-capable phones
+//! 4. the most recently confirmed
+//! 5. the highest confidence
+//!
+//! Ties go to the oldest contact. `src/bin/elect_primary_contacts.rs`
+//! backfills parties stored before the election existed.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// Re-elect the primary email and phone of the given parties
+///
+/// Returns the number of contacts whose `is_primary` changed. Takes a pool or
+/// a connection, so it can run inside the transaction storing the contacts.
+pub async fn elect_primary_contacts<'e>(
+    executor: impl PgExecutor<'e>,
+    party_ids: &[Uuid],
+) -> Result<u64, AppError> {
+    if party_ids.is_empty() {
+        return Ok(0);
+    }
+    let result = sqlx::query(ELECT_PRIMARY_SQL)
+        .bind(party_ids)
+        .execute(executor)
+        .timed("party_contacts.elect_primary")
+        .await
+        .context(ErrorContext::db("party_contacts.elect_primary").id("parties", party_ids.len()))?;
+    Ok(result.rows_affected())
+}
+
+pub(crate) const ELECT_PRIMARY_SQL: &str = r#"
+    WITH ranked AS (
+        SELECT contact_id,
+               ROW_NUMBER() OVER (
+                   PARTITION BY party_id, (contact_type::text = 'email')
+                   ORDER BY (valid_to IS NULL) DESC,
+                            is_verified DESC,
+                            is_whatsapp DESC,
+                            COALESCE(last_confirmed_at, created_at) DESC,
+                            confidence DESC NULLS LAST,
+                            created_at ASC
+               ) = 1 AS elected
+        FROM core.party_contacts
+        WHERE party_id = ANY($1)
+    )
+    UPDATE core.party_contacts pc
+    SET is_primary = ranked.elected, updated_at = now()
+    FROM ranked
+    WHERE pc.contact_id = ranked.contact_id
+      AND pc.is_primary IS DISTINCT FROM ranked.elected
+"#;
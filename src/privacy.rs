@@ -0,0 +1,296 @@
+//! LGPD data subject requests
+//!
+//! `export` gathers everything stored about a person, starting from the CPF:
+//! the party and its person record, contacts, addresses, relationships,
+//! provider enrichments and the duplicates merged into it, then every lead linked to it (by the enrichment
+//! payloads, Google Ads forms, WhatsApp messages, debug captures and shadow
+//! comparisons) with the messages sent to C2S and the raw webhook payloads,
+//! plus recorded consents. Rows are exported whole (`to_jsonb`), so new
+//! columns show up without touching this module.
+
+use crate::consent::SubjectKind;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryScalar;
+use sqlx::{PgPool, Postgres};
+use uuid::Uuid;
+
+/// Digits of a CPF, rejecting anything that is not 11 digits long
+pub fn normalize_cpf(raw: &str) -> Result<String, AppError> {
+    let cpf: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+    if cpf.len() != 11 {
+        return Err(AppError::BadRequest("cpf must have 11 digits".to_string()));
+    }
+    Ok(cpf)
+}
+
+async fn rows(
+    pool: &PgPool,
+    operation: &'static str,
+    query: QueryScalar<'_, Postgres, Value, PgArguments>,
+) -> Result<Vec<Value>, AppError> {
+    query
+        .fetch_all(pool)
+        .timed(operation)
+        .await
+        .context(format!("Failed to export {}", operation))
+}
+
+/// Machine-readable export of everything stored about the person with `cpf`
+pub async fn export(pool: &PgPool, cpf: &str) -> Result<Value, AppError> {
+    let cpf = normalize_cpf(cpf)?;
+
+    let party_rows: Vec<(Uuid, Value)> =
+        sqlx::query_as("SELECT p.id, to_jsonb(p) FROM core.parties p WHERE p.cpf_cnpj = $1")
+            .bind(&cpf)
+            .fetch_all(pool)
+            .timed("privacy.parties")
+            .await
+            .context(format!("Failed to export parties for CPF: {}", cpf))?;
+    let (party_ids, parties): (Vec<Uuid>, Vec<Value>) = party_rows.into_iter().unzip();
+
+    let people = rows(
+        pool,
+        "privacy.people",
+        sqlx::query_scalar("SELECT to_jsonb(p) FROM core.people p WHERE p.party_id = ANY($1)")
+            .bind(&party_ids),
+    )
+    .await?;
+    let contacts = rows(
+        pool,
+        "privacy.contacts",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(c) FROM core.party_contacts c
+             WHERE c.party_id = ANY($1)
+             ORDER BY c.created_at",
+        )
+        .bind(&party_ids),
+    )
+    .await?;
+    let addresses = rows(
+        pool,
+        "privacy.addresses",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(pa) || jsonb_build_object('address', to_jsonb(a))
+             FROM core.party_addresses pa
+             JOIN core.addresses a ON a.id = pa.address_id
+             WHERE pa.party_id = ANY($1)
+             ORDER BY pa.created_at",
+        )
+        .bind(&party_ids),
+    )
+    .await?;
+    let relationships = rows(
+        pool,
+        "privacy.relationships",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(r) FROM core.party_relationships r
+             WHERE r.source_party_id = ANY($1) OR r.target_party_id = ANY($1)",
+        )
+        .bind(&party_ids),
+    )
+    .await?;
+    let enrichments = rows(
+        pool,
+        "privacy.enrichments",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(e) FROM core.party_enrichments e
+             WHERE e.party_id = ANY($1)
+             ORDER BY e.enriched_at",
+        )
+        .bind(&party_ids),
+    )
+    .await?;
+    let merges = rows(
+        pool,
+        "privacy.party_merges",
+        sqlx::query_scalar(
+            r"SELECT to_jsonb(m) FROM core.party_merges m
+             WHERE m.survivor_id = ANY($1)
+                OR regexp_replace(m.merged_document, '\D', '', 'g') = $2
+             ORDER BY m.merged_at",
+        )
+        .bind(&party_ids)
+        .bind(&cpf),
+    )
+    .await?;
+
+    // Contacts in the canonical form used by consents and WhatsApp leads
+    let mut emails = Vec::new();
+    let mut phones = Vec::new();
+    for contact in &contacts {
+        let value = contact.get("value").and_then(Value::as_str).unwrap_or("");
+        match contact.get("contact_type").and_then(Value::as_str) {
+            Some("email") => emails.extend(SubjectKind::Email.normalize(value)),
+            Some("phone") | Some("whatsapp") => phones.extend(SubjectKind::Phone.normalize(value)),
+            _ => {}
+        }
+    }
+
+    let google_ads_leads = rows(
+        pool,
+        "privacy.google_ads_leads",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(g) FROM google_ads_leads g WHERE g.cpf = $1 ORDER BY g.created_at",
+        )
+        .bind(&cpf),
+    )
+    .await?;
+    let whatsapp_leads = rows(
+        pool,
+        "privacy.whatsapp_leads",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(w) FROM whatsapp_leads w
+             WHERE w.phone_normalized = ANY($1)
+             ORDER BY w.created_at",
+        )
+        .bind(&phones),
+    )
+    .await?;
+
+    let lead_ids: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT lead_id FROM (
+            SELECT raw_payload->>'lead_id' AS lead_id FROM core.party_enrichments WHERE party_id = ANY($1)
+            UNION SELECT c2s_lead_id FROM google_ads_leads WHERE cpf = $2
+            UNION SELECT c2s_lead_id FROM whatsapp_leads WHERE phone_normalized = ANY($3)
+            UNION SELECT lead_id FROM debug_payloads WHERE cpf = $2
+            UNION SELECT lead_id FROM shadow_comparisons WHERE cpf = $2
+        ) leads
+        WHERE lead_id IS NOT NULL
+        ORDER BY lead_id
+        "#,
+    )
+    .bind(&party_ids)
+    .bind(&cpf)
+    .bind(&phones)
+    .fetch_all(pool)
+    .timed("privacy.lead_ids")
+    .await
+    .context(format!("Failed to export lead ids for CPF: {}", cpf))?;
+
+    let webhook_events = rows(
+        pool,
+        "privacy.webhook_events",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(w) FROM webhook_events w
+             WHERE w.lead_id = ANY($1)
+             ORDER BY w.received_at",
+        )
+        .bind(&lead_ids),
+    )
+    .await?;
+    let outbox = rows(
+        pool,
+        "privacy.outbox",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(o) FROM c2s_outbox o WHERE o.lead_id = ANY($1) ORDER BY o.created_at",
+        )
+        .bind(&lead_ids),
+    )
+    .await?;
+    let last_messages = rows(
+        pool,
+        "privacy.last_messages",
+        sqlx::query_scalar("SELECT to_jsonb(m) FROM c2s_last_messages m WHERE m.lead_id = ANY($1)")
+            .bind(&lead_ids),
+    )
+    .await?;
+    let sent_messages = rows(
+        pool,
+        "privacy.sent_messages",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(s) FROM sent_messages s WHERE s.lead_id = ANY($1) ORDER BY s.created_at",
+        )
+        .bind(&lead_ids),
+    )
+    .await?;
+    let property_refs = rows(
+        pool,
+        "privacy.property_refs",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(r) FROM lead_property_refs r WHERE r.lead_id = ANY($1)",
+        )
+        .bind(&lead_ids),
+    )
+    .await?;
+    let debug_payloads = rows(
+        pool,
+        "privacy.debug_payloads",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(d) FROM debug_payloads d
+             WHERE d.cpf = $1 OR d.lead_id = ANY($2)
+             ORDER BY d.created_at, d.id",
+        )
+        .bind(&cpf)
+        .bind(&lead_ids),
+    )
+    .await?;
+    let shadow_comparisons = rows(
+        pool,
+        "privacy.shadow_comparisons",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(s) FROM shadow_comparisons s WHERE s.cpf = $1 ORDER BY s.created_at",
+        )
+        .bind(&cpf),
+    )
+    .await?;
+    let consents = rows(
+        pool,
+        "privacy.consents",
+        sqlx::query_scalar(
+            "SELECT to_jsonb(c) FROM consents c
+             WHERE (c.subject_type = 'cpf' AND c.subject = $1)
+                OR (c.subject_type = 'email' AND c.subject = ANY($2))
+                OR (c.subject_type = 'phone' AND c.subject = ANY($3))
+             ORDER BY c.granted_at",
+        )
+        .bind(&cpf)
+        .bind(&emails)
+        .bind(&phones),
+    )
+    .await?;
+
+    Ok(json!({
+        "cpf": cpf,
+        "generated_at": Utc::now(),
+        "found": !party_ids.is_empty() || !lead_ids.is_empty(),
+        "parties": parties,
+        "people": people,
+        "contacts": contacts,
+        "addresses": addresses,
+        "relationships": relationships,
+        "enrichments": enrichments,
+        "merges": merges,
+        "leads": {
+            "lead_ids": lead_ids,
+            "webhook_events": webhook_events,
+            "google_ads_leads": google_ads_leads,
+            "whatsapp_leads": whatsapp_leads,
+            "property_refs": property_refs,
+        },
+        "messages": {
+            "outbox": outbox,
+            "last_sent": last_messages,
+            "sent_log": sent_messages,
+        },
+        "debug_payloads": debug_payloads,
+        "shadow_comparisons": shadow_comparisons,
+        "consents": consents,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_cpf() {
+        assert_eq!(normalize_cpf("123.456.789-01").unwrap(), "12345678901");
+        assert!(normalize_cpf("1234").is_err());
+        assert!(normalize_cpf("").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Property references mentioned in C2S leads
+//!
+//! Portal leads carry the listing code in `product.prop_ref`; leads from
+//! other channels often only mention it in free text ("Ref: AP1234",
+//! "código do imóvel 5821"). References are extracted from both and stored
+//! in `lead_property_refs` (migration 024), so routing and messaging can
+//! take the listing into account.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use regex::Regex;
+use sqlx::PgPool;
+use std::sync::LazyLock;
+
+/// "ref", "referência", "cód", "código", optionally "do imóvel", then the code
+static REF_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:ref(?:er[eê]ncia)?|c[oó]d(?:igo)?)\b\.?(?:\s+do\s+im[oó]vel)?\s*(?:n[º°o]\.?)?\s*[:#-]?\s*([A-Z]{0,4}-?\d{2,8}[A-Z]?)\b",
+    )
+    .expect("valid property reference regex")
+});
+
+/// Where a reference was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefSource {
+    /// `product.prop_ref` (set by the portal)
+    PropRef,
+    /// Lead or product description, messages
+    Text,
+}
+
+impl RefSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RefSource::PropRef => "prop_ref",
+            RefSource::Text => "text",
+        }
+    }
+}
+
+/// A listing code mentioned by a lead (uppercase, without spaces)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyRef {
+    pub code: String,
+    pub source: RefSource,
+}
+
+/// Property references of a lead, `prop_ref` first, without duplicates
+pub fn extract_property_refs(prop_ref: Option<&str>, texts: &[&str]) -> Vec<PropertyRef> {
+    let mut refs: Vec<PropertyRef> = Vec::new();
+    let mut push = |code: &str, source: RefSource| {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase();
+        if !code.is_empty() && !refs.iter().any(|r| r.code == code) {
+            refs.push(PropertyRef { code, source });
+        }
+    };
+
+    if let Some(prop_ref) = prop_ref {
+        push(prop_ref, RefSource::PropRef);
+    }
+    for text in texts {
+        for capture in REF_PATTERN.captures_iter(text) {
+            push(&capture[1], RefSource::Text);
+        }
+    }
+
+    refs
+}
+
+/// Store the references of a lead (already stored ones are kept)
+pub async fn store_property_refs(
+    pool: &PgPool,
+    lead_id: &str,
+    refs: &[PropertyRef],
+) -> Result<(), AppError> {
+    for property_ref in refs {
+        sqlx::query(
+            r#"
+            INSERT INTO lead_property_refs (lead_id, ref_code, source)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (lead_id, ref_code) DO NOTHING
+            "#,
+        )
+        .bind(lead_id)
+        .bind(&property_ref.code)
+        .bind(property_ref.source.as_str())
+        .execute(pool)
+        .timed("lead_property_refs.insert")
+        .await
+        .context(format!(
+            "Failed to store property reference for lead_id: {}",
+            lead_id
+        ))?;
+    }
+    Ok(())
+}
+
+/// Stored reference codes of a lead, `prop_ref` first
+pub async fn load_property_refs(pool: &PgPool, lead_id: &str) -> Result<Vec<String>, AppError> {
+    let codes: Vec<(String,)> = sqlx::query_as(
+        r#"
+        SELECT ref_code FROM lead_property_refs
+        WHERE lead_id = $1
+        ORDER BY (source = 'prop_ref') DESC, id
+        "#,
+    )
+    .bind(lead_id)
+    .fetch_all(pool)
+    .timed("lead_property_refs.select")
+    .await
+    .context(format!(
+        "Failed to load property references for lead_id: {}",
+        lead_id
+    ))?;
+
+    Ok(codes.into_iter().map(|(code,)| code).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(refs: &[PropertyRef]) -> Vec<&str> {
+        refs.iter().map(|r| r.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_refs_from_text() {
+        let refs = extract_property_refs(
+            None,
+            &[
+                "Olá, tenho interesse no imóvel Ref: AP1234",
+                "Código do imóvel 5821, e também o cód. CA-0012",
+                "Referência nº 77810",
+            ],
+        );
+        assert_eq!(codes(&refs), ["AP1234", "5821", "CA-0012", "77810"]);
+        assert!(refs.iter().all(|r| r.source == RefSource::Text));
+    }
+
+    #[test]
+    fn test_prop_ref_first_and_deduplicated() {
+        let refs = extract_property_refs(Some(" ap1234 "), &["Vi o anúncio ref AP1234"]);
+        assert_eq!(codes(&refs), ["AP1234"]);
+        assert_eq!(refs[0].source, RefSource::PropRef);
+    }
+
+    #[test]
+    fn test_text_without_refs() {
+        let refs = extract_property_refs(None, &["Gostaria de agendar uma visita amanhã às 10h"]);
+        assert!(refs.is_empty());
+    }
+}
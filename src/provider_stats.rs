@@ -0,0 +1,145 @@
+//! In-process latency statistics for external providers (Work API, Diretrix, C2S)
+//!
+//! Keeps a bounded window of recent calls per provider; intended for the
+//! admin dashboard, not long-term metrics storage.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// Number of recent calls kept per provider
+const WINDOW_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    latency_ms: u64,
+    success: bool,
+}
+
+static SAMPLES: LazyLock<Mutex<HashMap<&'static str, VecDeque<Sample>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Latency summary for one provider over the recent window
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderLatency {
+    pub provider: String,
+    pub calls: usize,
+    pub error_rate: f64,
+    pub avg_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Record the outcome of an external provider call
+pub fn record_call(provider: &'static str, latency: Duration, success: bool) {
+    let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    let window = samples.entry(provider).or_default();
+    if window.len() == WINDOW_SIZE {
+        window.pop_front();
+    }
+    window.push_back(Sample {
+        latency_ms: latency.as_millis() as u64,
+        success,
+    });
+}
+
+/// Latency summaries for all providers that have recorded calls, sorted by name
+pub fn snapshot() -> Vec<ProviderLatency> {
+    let samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut result: Vec<ProviderLatency> = samples
+        .iter()
+        .filter(|(_, window)| !window.is_empty())
+        .map(|(provider, window)| summarize(provider, window))
+        .collect();
+    result.sort_by(|a, b| a.provider.cmp(&b.provider));
+    result
+}
+
+/// Number and error rate of the last `last` calls of a provider (None before any call)
+pub fn recent_error_rate(provider: &str, last: usize) -> Option<(usize, f64)> {
+    let samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    let window = samples.get(provider)?;
+    let calls = window.len().min(last);
+    if calls == 0 {
+        return None;
+    }
+    let errors = window
+        .iter()
+        .rev()
+        .take(calls)
+        .filter(|s| !s.success)
+        .count();
+    Some((calls, errors as f64 / calls as f64))
+}
+
+fn summarize(provider: &str, window: &VecDeque<Sample>) -> ProviderLatency {
+    let mut latencies: Vec<u64> = window.iter().map(|s| s.latency_ms).collect();
+    latencies.sort_unstable();
+
+    let calls = latencies.len();
+    let errors = window.iter().filter(|s| !s.success).count();
+    let percentile = |p: f64| latencies[((calls - 1) as f64 * p).round() as usize];
+
+    ProviderLatency {
+        provider: provider.to_string(),
+        calls,
+        error_rate: errors as f64 / calls as f64,
+        avg_ms: latencies.iter().sum::<u64>() / calls as u64,
+        p50_ms: percentile(0.50),
+        p95_ms: percentile(0.95),
+        max_ms: latencies[calls - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_percentiles() {
+        let window: VecDeque<Sample> = (1..=100)
+            .map(|ms| Sample {
+                latency_ms: ms,
+                success: ms % 10 != 0,
+            })
+            .collect();
+        let summary = summarize("test", &window);
+
+        assert_eq!(summary.calls, 100);
+        assert_eq!(summary.p50_ms, 51);
+        assert_eq!(summary.p95_ms, 95);
+        assert_eq!(summary.max_ms, 100);
+        assert_eq!(summary.avg_ms, 50);
+        assert!((summary.error_rate - 0.1).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_record_call_appears_in_snapshot() {
+        record_call("test_provider", Duration::from_millis(120), true);
+
+        let snapshot = snapshot();
+        let entry = snapshot
+            .iter()
+            .find(|p| p.provider == "test_provider")
+            .expect("provider recorded");
+        assert!(entry.calls >= 1);
+    }
+
+    #[test]
+    fn test_recent_error_rate_uses_latest_calls() {
+        assert_eq!(recent_error_rate("test_error_rate", 10), None);
+        for _ in 0..10 {
+            record_call("test_error_rate", Duration::from_millis(5), true);
+        }
+        for _ in 0..5 {
+            record_call("test_error_rate", Duration::from_millis(5), false);
+        }
+
+        assert_eq!(recent_error_rate("test_error_rate", 10), Some((10, 0.5)));
+        let (calls, rate) = recent_error_rate("test_error_rate", 100).unwrap();
+        assert_eq!(calls, 15);
+        assert!((rate - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+}
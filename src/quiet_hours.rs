@@ -0,0 +1,179 @@
+//! Quiet hours for outbound C2S messages
+//!
+//! Enrichment messages triggered outside business hours (a lead arriving at
+//! 3am) would notify sellers in the middle of the night. During quiet hours
+//! messages are written to the outbox (`outbox`) with `send_after` set to the
+//! end of the window, and delivered by the outbox dispatcher.
+//!
+//! Windows are local to America/Sao_Paulo, which has been UTC-3 all year
+//! since daylight saving time was abolished in 2019.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, TimeZone, Utc};
+use std::collections::HashMap;
+
+/// America/Sao_Paulo offset (no DST since 2019)
+fn sao_paulo() -> FixedOffset {
+    FixedOffset::west_opt(3 * 3600).expect("valid offset")
+}
+
+/// A daily window ("22:00-08:00"); may wrap past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietWindow {
+    /// Parse "HH:MM-HH:MM"
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (start, end) = value
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("Quiet hours must be HH:MM-HH:MM, got '{}'", value))?;
+        let time = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .map_err(|_| anyhow::anyhow!("Invalid quiet hours time '{}'", t.trim()))
+        };
+        let window = Self {
+            start: time(start)?,
+            end: time(end)?,
+        };
+        if window.start == window.end {
+            anyhow::bail!("Quiet hours window '{}' is empty", value);
+        }
+        Ok(window)
+    }
+
+    /// Whether a local time falls inside the window (end excluded)
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// End of the window when `now` is inside it (`None` = send now)
+    pub fn deferred_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&sao_paulo());
+        if !self.contains(local.time()) {
+            return None;
+        }
+
+        let mut end_date = local.date_naive();
+        if self.start > self.end && local.time() >= self.start {
+            end_date += Duration::days(1);
+        }
+        sao_paulo()
+            .from_local_datetime(&end_date.and_time(self.end))
+            .single()
+            .map(|end| end.with_timezone(&Utc))
+    }
+}
+
+/// Quiet hours configuration: a default window and per-tenant overrides
+///
+/// Tenants are keyed by lead source name (e.g. "Google Ads"), matched
+/// case-insensitively. A tenant mapped to `null` has no quiet hours.
+#[derive(Debug, Clone, Default)]
+pub struct QuietHours {
+    pub default: Option<QuietWindow>,
+    pub tenants: HashMap<String, Option<QuietWindow>>,
+}
+
+impl QuietHours {
+    /// Parse `QUIET_HOURS` ("22:00-08:00", empty = disabled) and
+    /// `QUIET_HOURS_BY_TENANT` (JSON object of tenant → window or null)
+    pub fn parse(default: &str, by_tenant: &str) -> anyhow::Result<Self> {
+        let default = match default.trim() {
+            "" => None,
+            window => Some(QuietWindow::parse(window)?),
+        };
+
+        let mut tenants = HashMap::new();
+        if !by_tenant.trim().is_empty() {
+            let raw: HashMap<String, Option<String>> = serde_json::from_str(by_tenant)
+                .map_err(|e| anyhow::anyhow!("Invalid QUIET_HOURS_BY_TENANT JSON: {}", e))?;
+            for (tenant, window) in raw {
+                let window = window.as_deref().map(QuietWindow::parse).transpose()?;
+                tenants.insert(tenant.trim().to_lowercase(), window);
+            }
+        }
+
+        Ok(Self { default, tenants })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.default.is_some() || self.tenants.values().any(Option::is_some)
+    }
+
+    /// Window applying to a tenant (its override, else the default)
+    pub fn window_for(&self, tenant: Option<&str>) -> Option<&QuietWindow> {
+        tenant
+            .and_then(|t| self.tenants.get(&t.trim().to_lowercase()))
+            .map(Option::as_ref)
+            .unwrap_or(self.default.as_ref())
+    }
+
+    /// When a message for `tenant` may be sent (`None` = now)
+    pub fn deferred_until(
+        &self,
+        tenant: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.window_for(tenant)?.deferred_until(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UTC instant for a Sao Paulo local time on 2025-11-26
+    fn sp(hour: u32, minute: u32) -> DateTime<Utc> {
+        sao_paulo()
+            .with_ymd_and_hms(2025, 11, 26, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        let window = QuietWindow::parse("22:00-08:00").unwrap();
+
+        assert_eq!(window.deferred_until(sp(3, 0)), Some(sp(8, 0)));
+        assert_eq!(
+            window.deferred_until(sp(23, 30)),
+            Some(sp(8, 0) + Duration::days(1))
+        );
+        assert_eq!(window.deferred_until(sp(8, 0)), None);
+        assert_eq!(window.deferred_until(sp(14, 0)), None);
+    }
+
+    #[test]
+    fn test_tenant_overrides() {
+        let quiet = QuietHours::parse(
+            "22:00-08:00",
+            r#"{"Google Ads": "20:00-09:00", "WhatsApp": null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(quiet.deferred_until(None, sp(21, 0)), None);
+        assert_eq!(
+            quiet.deferred_until(Some("google ads"), sp(21, 0)),
+            Some(sp(9, 0) + Duration::days(1))
+        );
+        assert_eq!(quiet.deferred_until(Some("WhatsApp"), sp(3, 0)), None);
+        assert_eq!(quiet.deferred_until(Some("Site"), sp(3, 0)), Some(sp(8, 0)));
+    }
+
+    #[test]
+    fn test_parse_errors_and_disabled() {
+        assert!(QuietWindow::parse("22h-8h").is_err());
+        assert!(QuietWindow::parse("08:00-08:00").is_err());
+        assert!(QuietHours::parse("", r#"{"x": "bad"}"#).is_err());
+
+        let disabled = QuietHours::parse("", "").unwrap();
+        assert!(!disabled.is_enabled());
+        assert_eq!(disabled.deferred_until(None, sp(3, 0)), None);
+    }
+}
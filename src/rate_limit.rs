@@ -0,0 +1,421 @@
+//! Outbound rate limiting for C2S messages, Work API and Diretrix calls
+//!
+//! Reprocessing jobs (deferred webhook retries, outbox releases at the end of
+//! quiet hours, manual re-enrichment) can send many messages at once and
+//! trigger account-level throttling on the C2S API. Every `send_message`
+//! takes a token from a global bucket and from a per-lead bucket; when none
+//! is available the call waits for one, up to `C2S_SEND_MAX_WAIT_SECS`, and
+//! then fails with `ProviderUnavailable` (deferred webhook / outbox retry).
+//! Lead creation (batch imports) only takes from the global bucket
+//! (`acquire_global`).
+//!
+//! Work API calls share one `ProviderLimiter`: at most
+//! `WORK_API_MAX_CONCURRENCY` requests in flight and `WORK_API_RATE_PER_MINUTE`
+//! started per minute, whichever caller (webhook workers, deferred enrichment
+//! retries, bulk fetches of `WorkApi::fetch_documents`) makes them.
+//!
+//! Diretrix limits requests per account (basic auth user), and a burst gets
+//! the account temporarily blocked. Each account (the shared one and those of
+//! `tenant_credentials`) gets its own `ProviderLimiter`, with the
+//! `DIRETRIX_RATE_PER_MINUTE` / `DIRETRIX_MAX_CONCURRENCY` limits.
+
+use crate::config::Config;
+use crate::errors::AppError;
+use moka::future::Cache;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Token bucket refilled continuously at `rate_per_minute`
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    /// (available tokens, last refill)
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, rate_per_minute: u32) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            refill_per_sec: f64::from(rate_per_minute.max(1)) / 60.0,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Reserve a token at `now`, returning how long to wait before using it
+    ///
+    /// Nothing is reserved (`Err(wait)`) when the wait would exceed `max_wait`.
+    pub fn try_reserve_at(&self, now: Instant, max_wait: Duration) -> Result<Duration, Duration> {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, last) = *state;
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        let tokens = (tokens + elapsed * self.refill_per_sec).min(self.capacity);
+
+        let wait = if tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - tokens) / self.refill_per_sec)
+        };
+        if wait > max_wait {
+            *state = (tokens, now.max(last));
+            return Err(wait);
+        }
+
+        *state = (tokens - 1.0, now.max(last));
+        Ok(wait)
+    }
+
+    /// Give back a reserved token (the other bucket refused)
+    fn refund(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.0 = (state.0 + 1.0).min(self.capacity);
+    }
+}
+
+/// Global and per-lead limits for C2S `send_message`
+pub struct SendLimiter {
+    global: TokenBucket,
+    per_lead: Cache<String, Arc<TokenBucket>>,
+    per_lead_rate: u32,
+    max_wait: Duration,
+}
+
+impl SendLimiter {
+    /// `global_per_minute` allows a burst of 10 seconds worth of messages;
+    /// a lead can receive `per_lead_per_minute` messages back to back.
+    pub fn new(global_per_minute: u32, per_lead_per_minute: u32, max_wait: Duration) -> Self {
+        Self {
+            global: TokenBucket::new(global_per_minute / 6, global_per_minute),
+            per_lead: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_idle(Duration::from_secs(600))
+                .build(),
+            per_lead_rate: per_lead_per_minute,
+            max_wait,
+        }
+    }
+
+    /// Wait until a message to `lead_id` may be sent
+    pub async fn acquire(&self, lead_id: &str) -> Result<(), AppError> {
+        let rate = self.per_lead_rate;
+        let lead_bucket = self
+            .per_lead
+            .get_with(lead_id.to_string(), async move {
+                Arc::new(TokenBucket::new(rate, rate))
+            })
+            .await;
+
+        let now = Instant::now();
+        let lead_wait = lead_bucket
+            .try_reserve_at(now, self.max_wait)
+            .map_err(|wait| {
+                AppError::ProviderUnavailable(format!(
+                    "C2S message rate limit for lead {} (next slot in {}s)",
+                    lead_id,
+                    wait.as_secs()
+                ))
+            })?;
+        let global_wait = match self.global.try_reserve_at(now, self.max_wait) {
+            Ok(wait) => wait,
+            Err(wait) => {
+                lead_bucket.refund();
+                return Err(AppError::ProviderUnavailable(format!(
+                    "C2S global message rate limit (next slot in {}s)",
+                    wait.as_secs()
+                )));
+            }
+        };
+
+        self.wait(lead_id, lead_wait.max(global_wait)).await;
+        Ok(())
+    }
+
+    /// Wait for a global slot only (C2S calls not tied to an existing lead)
+    pub async fn acquire_global(&self, operation: &str) -> Result<(), AppError> {
+        let wait = self
+            .global
+            .try_reserve_at(Instant::now(), self.max_wait)
+            .map_err(|wait| {
+                AppError::ProviderUnavailable(format!(
+                    "C2S global rate limit for {} (next slot in {}s)",
+                    operation,
+                    wait.as_secs()
+                ))
+            })?;
+        self.wait(operation, wait).await;
+        Ok(())
+    }
+
+    async fn wait(&self, target: &str, wait: Duration) {
+        if !wait.is_zero() {
+            tracing::debug!(
+                "Throttling C2S call ({}) for {}ms",
+                target,
+                wait.as_millis()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+static C2S_SEND_LIMITER: OnceLock<SendLimiter> = OnceLock::new();
+
+/// Configure the process-wide C2S limiter (called once at startup)
+pub fn init_c2s_limiter(config: &Config) {
+    let limiter = SendLimiter::new(
+        config.c2s_send_rate_per_minute,
+        config.c2s_send_rate_per_lead_per_minute,
+        Duration::from_secs(config.c2s_send_max_wait_secs),
+    );
+    if C2S_SEND_LIMITER.set(limiter).is_err() {
+        tracing::warn!("C2S send limiter already initialized");
+    }
+}
+
+/// Process-wide C2S limiter (defaults when `init_c2s_limiter` was not called)
+pub fn c2s_limiter() -> &'static SendLimiter {
+    C2S_SEND_LIMITER.get_or_init(|| SendLimiter::new(60, 5, Duration::from_secs(30)))
+}
+
+/// Concurrency and rate limits shared by every call to a provider (account)
+pub struct ProviderLimiter {
+    /// Provider name, for errors and logs
+    name: &'static str,
+    bucket: TokenBucket,
+    slots: Semaphore,
+    concurrency: usize,
+    max_wait: Duration,
+}
+
+impl ProviderLimiter {
+    /// `rate_per_minute` allows a burst of `concurrency` calls
+    pub fn new(
+        name: &'static str,
+        rate_per_minute: u32,
+        concurrency: usize,
+        max_wait: Duration,
+    ) -> Self {
+        let concurrency = concurrency.max(1);
+        Self {
+            name,
+            bucket: TokenBucket::new(concurrency as u32, rate_per_minute),
+            slots: Semaphore::new(concurrency),
+            concurrency,
+            max_wait,
+        }
+    }
+
+    /// Requests allowed in flight at once
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Wait for a slot and a token; hold the permit for the duration of the call
+    ///
+    /// Never waits past the request deadline (see `deadline`).
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, AppError> {
+        let started = Instant::now();
+        let budget = crate::deadline::remaining(self.name)?;
+        let max_wait = budget.map_or(self.max_wait, |budget| budget.min(self.max_wait));
+        let deadline_bound = max_wait < self.max_wait;
+
+        let permit = tokio::time::timeout(max_wait, self.slots.acquire())
+            .await
+            .map_err(|_| {
+                if deadline_bound {
+                    return crate::deadline::exceeded(self.name);
+                }
+                AppError::ProviderUnavailable(format!(
+                    "{} concurrency limit ({} in flight for {}s)",
+                    self.name,
+                    self.concurrency,
+                    self.max_wait.as_secs()
+                ))
+            })?
+            .map_err(|e| AppError::InternalError(format!("{} limiter closed: {}", self.name, e)))?;
+
+        let remaining = max_wait.saturating_sub(started.elapsed());
+        let wait = self
+            .bucket
+            .try_reserve_at(Instant::now(), remaining)
+            .map_err(|wait| {
+                if deadline_bound {
+                    return crate::deadline::exceeded(self.name);
+                }
+                AppError::ProviderUnavailable(format!(
+                    "{} rate limit (next slot in {}s)",
+                    self.name,
+                    wait.as_secs()
+                ))
+            })?;
+        if !wait.is_zero() {
+            tracing::debug!("Throttling {} call for {}ms", self.name, wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+        Ok(permit)
+    }
+}
+
+static WORK_API_LIMITER: OnceLock<ProviderLimiter> = OnceLock::new();
+
+/// Configure the process-wide Work API limiter (called once at startup)
+pub fn init_work_api_limiter(config: &Config) {
+    let limiter = ProviderLimiter::new(
+        "Work API",
+        config.work_api_rate_per_minute,
+        config.work_api_max_concurrency,
+        Duration::from_secs(config.work_api_max_wait_secs),
+    );
+    if WORK_API_LIMITER.set(limiter).is_err() {
+        tracing::warn!("Work API limiter already initialized");
+    }
+}
+
+/// Process-wide Work API limiter (defaults when `init_work_api_limiter` was not called)
+pub fn work_api_limiter() -> &'static ProviderLimiter {
+    WORK_API_LIMITER
+        .get_or_init(|| ProviderLimiter::new("Work API", 30, 3, Duration::from_secs(60)))
+}
+
+/// Diretrix limits of each account: (rate per minute, concurrency, max wait)
+static DIRETRIX_LIMITS: OnceLock<(u32, usize, Duration)> = OnceLock::new();
+
+/// Limiters of the Diretrix accounts used so far, by user
+static DIRETRIX_LIMITERS: OnceLock<Mutex<HashMap<String, Arc<ProviderLimiter>>>> = OnceLock::new();
+
+/// Configure the limits of every Diretrix account (called once at startup)
+pub fn init_diretrix_limits(config: &Config) {
+    let limits = (
+        config.diretrix_rate_per_minute,
+        config.diretrix_max_concurrency,
+        Duration::from_secs(config.diretrix_max_wait_secs),
+    );
+    if DIRETRIX_LIMITS.set(limits).is_err() {
+        tracing::warn!("Diretrix limits already initialized");
+    }
+}
+
+/// Limiter of a Diretrix account, shared by every client using it
+///
+/// Defaults apply when `init_diretrix_limits` was not called.
+pub fn diretrix_limiter(user: &str) -> Arc<ProviderLimiter> {
+    let (rate_per_minute, concurrency, max_wait) =
+        *DIRETRIX_LIMITS.get_or_init(|| (60, 4, Duration::from_secs(30)));
+    let mut limiters = DIRETRIX_LIMITERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    limiters
+        .entry(user.to_string())
+        .or_insert_with(|| {
+            Arc::new(ProviderLimiter::new(
+                "Diretrix",
+                rate_per_minute,
+                concurrency,
+                max_wait,
+            ))
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_burst_then_wait() {
+        let bucket = TokenBucket::new(2, 60);
+        let start = Instant::now();
+        let max_wait = Duration::from_secs(5);
+
+        assert_eq!(bucket.try_reserve_at(start, max_wait), Ok(Duration::ZERO));
+        assert_eq!(bucket.try_reserve_at(start, max_wait), Ok(Duration::ZERO));
+        // Empty: next token refills in 1s at 60/min
+        let wait = bucket.try_reserve_at(start, max_wait).unwrap();
+        assert!((wait.as_secs_f64() - 1.0).abs() < 1e-6);
+        // Refilled after waiting
+        let later = start + Duration::from_secs(3);
+        assert_eq!(bucket.try_reserve_at(later, max_wait), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_bucket_rejects_beyond_max_wait_without_reserving() {
+        let bucket = TokenBucket::new(1, 6); // one token every 10s
+        let start = Instant::now();
+
+        assert!(bucket.try_reserve_at(start, Duration::ZERO).is_ok());
+        assert!(bucket
+            .try_reserve_at(start, Duration::from_secs(5))
+            .is_err());
+        // The rejected call did not consume the next token
+        let wait = bucket
+            .try_reserve_at(start, Duration::from_secs(15))
+            .unwrap();
+        assert!((wait.as_secs_f64() - 10.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_per_lead_limit_is_independent() {
+        let limiter = SendLimiter::new(600, 1, Duration::ZERO);
+
+        assert!(limiter.acquire("lead-1").await.is_ok());
+        let err = limiter.acquire("lead-1").await.unwrap_err();
+        assert!(err.is_provider_unavailable());
+        assert!(limiter.acquire("lead-2").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_global_acquire_shares_the_global_bucket() {
+        // Burst of one token (6/min), refilled every 10s
+        let limiter = SendLimiter::new(6, 5, Duration::ZERO);
+
+        assert!(limiter.acquire_global("create_lead").await.is_ok());
+        let err = limiter.acquire("lead-1").await.unwrap_err();
+        assert!(err.is_provider_unavailable());
+    }
+
+    #[tokio::test]
+    async fn test_provider_limiter_bounds_concurrency() {
+        let limiter = ProviderLimiter::new("Work API", 600, 2, Duration::ZERO);
+
+        let first = limiter.acquire().await.unwrap();
+        let _second = limiter.acquire().await.unwrap();
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.to_string().contains("concurrency limit"));
+
+        // A finished call frees its slot, the burst of 2 tokens is spent though
+        drop(first);
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.to_string().contains("rate limit"));
+    }
+
+    #[tokio::test]
+    async fn test_provider_limiter_stops_waiting_at_deadline() {
+        let limiter = ProviderLimiter::new("Work API", 600, 1, Duration::from_secs(60));
+        let _held = limiter.acquire().await.unwrap();
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let err = crate::deadline::with_deadline(deadline, limiter.acquire())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::DeadlineExceeded(_)));
+        assert!(Instant::now() < deadline + Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_diretrix_limiter_per_account() {
+        let shared = diretrix_limiter("test-shared-account");
+        assert!(Arc::ptr_eq(
+            &shared,
+            &diretrix_limiter("test-shared-account")
+        ));
+        assert!(!Arc::ptr_eq(
+            &shared,
+            &diretrix_limiter("test-partner-account")
+        ));
+    }
+}
@@ -0,0 +1,185 @@
+//! Raw provider payloads of a party, for debugging mappings
+//!
+//! `GET /api/v1/admin/parties/:id/raw-payload` returns the enrichment snapshot
+//! stored in `core.party_enrichments` as received from the provider, so
+//! engineers can compare it with the mapped rows without database access.
+//! Every read is audited (`audit`). With `?mask=`, values are masked before
+//! they leave the service, keeping their layout (digits become `1`, letters
+//! `X`/`x`, as in `work_api_fixtures::anonymize`):
+//!
+//! - `mask=emails,telefones,nome`: values under these keys, at any depth,
+//! - `mask=*`: every value (categorical fields such as `uf` are kept).
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use crate::work_api_fixtures::anonymize;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// Stored enrichment snapshot of a party
+#[derive(Debug, Serialize, FromRow)]
+pub struct RawPayload {
+    pub party_id: Uuid,
+    pub party_type: Option<String>,
+    pub provider: String,
+    pub enriched_at: Option<DateTime<Utc>>,
+    pub quality_score: Option<f64>,
+    pub raw_payload: Value,
+    /// Document of the party, masked along with the payload (not returned)
+    #[serde(skip)]
+    pub document: Option<String>,
+}
+
+/// Values to mask in a returned payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadMask {
+    None,
+    All,
+    /// Keys (lowercased) whose values are masked, at any depth
+    Fields(Vec<String>),
+}
+
+impl PayloadMask {
+    /// `*` (or `all`) for everything, else a comma-separated list of keys
+    pub fn parse(raw: Option<&str>) -> Self {
+        let Some(raw) = raw.map(str::trim).filter(|r| !r.is_empty()) else {
+            return PayloadMask::None;
+        };
+        if raw == "*" || raw.eq_ignore_ascii_case("all") {
+            return PayloadMask::All;
+        }
+        let mut fields: Vec<String> = raw
+            .split(',')
+            .map(|f| f.trim().to_lowercase())
+            .filter(|f| !f.is_empty())
+            .collect();
+        fields.sort();
+        fields.dedup();
+        if fields.is_empty() {
+            PayloadMask::None
+        } else {
+            PayloadMask::Fields(fields)
+        }
+    }
+
+    /// Description recorded in the audit log
+    pub fn describe(&self) -> Value {
+        match self {
+            PayloadMask::None => Value::Null,
+            PayloadMask::All => Value::from("*"),
+            PayloadMask::Fields(fields) => Value::from(fields.clone()),
+        }
+    }
+}
+
+/// Copy of `payload` with the values selected by `mask` masked
+///
+/// `document` (the party's CPF/CNPJ) is replaced by the fixture CPF wherever
+/// a masked value equals it.
+pub fn mask_payload(payload: &Value, mask: &PayloadMask, document: &str) -> Value {
+    match mask {
+        PayloadMask::None => payload.clone(),
+        PayloadMask::All => anonymize(payload, document),
+        PayloadMask::Fields(fields) => mask_fields(payload, fields, document),
+    }
+}
+
+fn mask_fields(value: &Value, fields: &[String], document: &str) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, v)| {
+                    let v = if fields.contains(&key.to_lowercase()) {
+                        anonymize(v, document)
+                    } else {
+                        mask_fields(v, fields, document)
+                    };
+                    (key.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|v| mask_fields(v, fields, document))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Enrichment snapshot of a party, if it has one
+pub async fn find(pool: &PgPool, party_id: Uuid) -> Result<Option<RawPayload>, AppError> {
+    sqlx::query_as(
+        r#"
+        SELECT pe.party_id, p.party_type, pe.provider, pe.enriched_at,
+               pe.quality_score::float8 AS quality_score, pe.raw_payload,
+               p.cpf_cnpj AS document
+        FROM core.party_enrichments pe
+        JOIN core.parties p ON p.id = pe.party_id
+        WHERE pe.party_id = $1
+        "#,
+    )
+    .bind(party_id)
+    .fetch_optional(pool)
+    .timed("party_enrichments.raw_payload")
+    .await
+    .context(ErrorContext::db("party_enrichments.raw_payload").id("party_id", party_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::work_api_fixtures::FIXTURE_CPF;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({
+            "DadosBasicos": {"nome": "Maria Souza", "cpf": "12345678909", "sexo": "F"},
+            "emails": [{"email": "maria@example.com", "prioridade": "1"}],
+            "telefones": [{"telefone": "(11) 98765-4321", "whatsapp": "SIM"}],
+            "lead_id": "lead-1"
+        })
+    }
+
+    #[test]
+    fn test_parse_mask() {
+        assert_eq!(PayloadMask::parse(None), PayloadMask::None);
+        assert_eq!(PayloadMask::parse(Some(" ")), PayloadMask::None);
+        assert_eq!(PayloadMask::parse(Some("*")), PayloadMask::All);
+        assert_eq!(PayloadMask::parse(Some("ALL")), PayloadMask::All);
+        assert_eq!(
+            PayloadMask::parse(Some("Telefones, nome,,nome")),
+            PayloadMask::Fields(vec!["nome".to_string(), "telefones".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_mask_fields_keeps_layout() {
+        let mask = PayloadMask::parse(Some("nome,telefones,cpf"));
+        let masked = mask_payload(&payload(), &mask, "12345678909");
+
+        assert_eq!(masked["DadosBasicos"]["nome"], "Xxxxx Xxxxx");
+        assert_eq!(masked["DadosBasicos"]["cpf"], FIXTURE_CPF);
+        assert_eq!(masked["telefones"][0]["telefone"], "(11) 11111-1111");
+        assert_eq!(masked["telefones"][0]["whatsapp"], "SIM");
+        // Fields not listed are returned as stored
+        assert_eq!(masked["emails"][0]["email"], "maria@example.com");
+        assert_eq!(masked["DadosBasicos"]["sexo"], "F");
+    }
+
+    #[test]
+    fn test_mask_all_and_none() {
+        let all = mask_payload(&payload(), &PayloadMask::All, "12345678909");
+        assert_eq!(all["emails"][0]["email"], "xxxxx@xxxxxxx.xxx");
+        assert_eq!(all["DadosBasicos"]["sexo"], "F");
+
+        assert_eq!(
+            mask_payload(&payload(), &PayloadMask::None, "12345678909"),
+            payload()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::enrichment::WealthSegment;
+use crate::errors::AppError;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Conversion identifier used for enriched leads in RD Station
+pub const ENRICHED_LEAD_CONVERSION: &str = "mbras-lead-enriquecido";
+
+/// Enriched lead data pushed to RD Station as a conversion event
+///
+/// Documents (CPF) are never sent: RD Station is a marketing tool and only
+/// needs the contact and the wealth segment.
+#[derive(Debug, Clone)]
+pub struct RdStationLead {
+    pub lead_id: String,
+    pub name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub wealth: WealthSegment,
+}
+
+impl RdStationLead {
+    /// Build the RD Station conversion event body (API 2.0 `platform/conversions`)
+    pub fn to_conversion_event(&self) -> Value {
+        let mut payload = serde_json::Map::new();
+        payload.insert(
+            "conversion_identifier".to_string(),
+            json!(ENRICHED_LEAD_CONVERSION),
+        );
+        payload.insert("email".to_string(), json!(self.email));
+        payload.insert("tags".to_string(), json!(["c2s", self.wealth.as_tag()]));
+        payload.insert("cf_c2s_lead_id".to_string(), json!(self.lead_id));
+
+        if !self.name.trim().is_empty() {
+            payload.insert("name".to_string(), json!(self.name));
+        }
+        if let Some(phone) = &self.phone {
+            payload.insert("mobile_phone".to_string(), json!(phone));
+        }
+
+        json!({
+            "event_type": "CONVERSION",
+            "event_family": "CDP",
+            "payload": payload
+        })
+    }
+}
+
+/// Client for the RD Station Marketing API
+#[derive(Clone)]
+pub struct RdStationClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl RdStationClient {
+    pub fn new(base_url: String, api_key: String) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to create RD Station client: {}", e))
+            })?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    /// Push an enriched lead conversion event into RD Station
+    pub async fn send_conversion(&self, lead: &RdStationLead) -> Result<(), AppError> {
+        let url = format!("{}/platform/conversions", self.base_url);
+        tracing::info!(
+            "Sending conversion for lead {} to RD Station (tag: {})",
+            lead.lead_id,
+            lead.wealth.as_tag()
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .query(&[("api_key", &self.api_key)])
+            .json(&lead.to_conversion_event())
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApiError(format!("RD Station request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::ExternalApiError(format!(
+                "RD Station returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_lead() -> RdStationLead {
+        RdStationLead {
+            lead_id: "lead-123".to_string(),
+            name: "Maria Silva".to_string(),
+            email: "maria@example.com".to_string(),
+            phone: Some("5511987654321".to_string()),
+            wealth: WealthSegment::Alto,
+        }
+    }
+
+    #[test]
+    fn test_conversion_event_includes_wealth_tag() {
+        let event = sample_lead().to_conversion_event();
+
+        assert_eq!(event["event_type"], "CONVERSION");
+        assert_eq!(
+            event["payload"]["conversion_identifier"],
+            ENRICHED_LEAD_CONVERSION
+        );
+        assert_eq!(event["payload"]["email"], "maria@example.com");
+        assert_eq!(event["payload"]["mobile_phone"], "5511987654321");
+        assert_eq!(event["payload"]["tags"], json!(["c2s", "patrimonio-alto"]));
+        assert!(event["payload"].get("cf_cpf").is_none());
+    }
+
+    #[test]
+    fn test_conversion_event_omits_missing_fields() {
+        let lead = RdStationLead {
+            name: "".to_string(),
+            phone: None,
+            ..sample_lead()
+        };
+        let event = lead.to_conversion_event();
+
+        assert!(event["payload"].get("name").is_none());
+        assert!(event["payload"].get("mobile_phone").is_none());
+    }
+
+    #[test]
+    fn test_wealth_segment_from_work_data() {
+        let data = json!({
+            "DadosEconomicos": {
+                "renda": "8500,50",
+                "poderAquisitivo": { "poderAquisitivoDescricao": "MUITO ALTO" }
+            }
+        });
+        assert_eq!(
+            WealthSegment::from_work_data(&data),
+            WealthSegment::MuitoAlto
+        );
+
+        let income_only = json!({ "DadosEconomicos": { "renda": "3200,00" } });
+        assert_eq!(
+            WealthSegment::from_work_data(&income_only),
+            WealthSegment::Medio
+        );
+
+        assert_eq!(
+            WealthSegment::from_work_data(&json!({})),
+            WealthSegment::Desconhecido
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Startup readiness of the process and its optional provider clients
+//!
+//! The HTTP listener is bound only once the database pool, the SQL self-test,
+//! the caches and the background workers are up (`Readiness::mark_started`),
+//! so no request sees a half-built `AppState`. Optional provider clients
+//! (RD Station, listings, Salesforce, Google Sheets, SMS) are `LazyClient`s:
+//! built on first use, with their state (not initialized, ready, failed,
+//! disabled) reported by `GET /health/ready` instead of being silently `None`.
+//! A failed client marks the process degraded; it keeps serving without it,
+//! and responses carry an `X-Degraded` header naming the failed components.
+
+use crate::errors::AppError;
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// State of a startup step or provider client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", content = "error", rename_all = "snake_case")]
+pub enum ComponentState {
+    /// Optional client not built yet (built on first use)
+    NotInitialized,
+    Ready,
+    Failed(String),
+    /// Not configured
+    Disabled,
+}
+
+/// Startup steps and provider clients of this process
+#[derive(Debug, Default)]
+pub struct Readiness {
+    started: AtomicBool,
+    components: Mutex<BTreeMap<&'static str, ComponentState>>,
+}
+
+impl Readiness {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn set(&self, component: &'static str, state: ComponentState) {
+        self.components.lock().unwrap().insert(component, state);
+    }
+
+    /// Every startup step completed, the listener can accept requests
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// A client failed to initialize: requests are served, without it
+    pub fn is_degraded(&self) -> bool {
+        !self.failed_components().is_empty()
+    }
+
+    /// Components that failed to initialize
+    pub fn failed_components(&self) -> Vec<&'static str> {
+        self.components
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| matches!(state, ComponentState::Failed(_)))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, ComponentState> {
+        self.components.lock().unwrap().clone()
+    }
+}
+
+/// Middleware adding `X-Degraded: <components>` to responses while running degraded
+pub async fn degraded_header(
+    State(readiness): State<Arc<Readiness>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let failed = readiness.failed_components();
+    if !failed.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&failed.join(",")) {
+            response.headers_mut().insert("x-degraded", value);
+        }
+    }
+    response
+}
+
+type ClientInit<T> = Box<dyn FnOnce() -> Result<T, AppError> + Send>;
+
+struct LazyInner<T> {
+    name: &'static str,
+    readiness: Arc<Readiness>,
+    init: Mutex<Option<ClientInit<T>>>,
+    client: OnceLock<Option<T>>,
+}
+
+/// Optional provider client built on first use
+///
+/// A failed initialization is logged once and reported as `failed` by the
+/// readiness endpoint; `get` then returns `None`, as for a disabled client.
+pub struct LazyClient<T>(Arc<LazyInner<T>>);
+
+impl<T> Clone for LazyClient<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> LazyClient<T> {
+    /// Client built by `init` on first use
+    pub fn new(
+        name: &'static str,
+        readiness: &Arc<Readiness>,
+        init: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+    ) -> Self {
+        readiness.set(name, ComponentState::NotInitialized);
+        Self(Arc::new(LazyInner {
+            name,
+            readiness: readiness.clone(),
+            init: Mutex::new(Some(Box::new(init))),
+            client: OnceLock::new(),
+        }))
+    }
+
+    /// Client that is not configured
+    pub fn disabled(name: &'static str, readiness: &Arc<Readiness>) -> Self {
+        readiness.set(name, ComponentState::Disabled);
+        Self(Arc::new(LazyInner {
+            name,
+            readiness: readiness.clone(),
+            init: Mutex::new(None),
+            client: OnceLock::from(None),
+        }))
+    }
+
+    /// The client, built now if needed (`None` when disabled or failed)
+    pub fn get(&self) -> Option<&T> {
+        let inner = &*self.0;
+        inner
+            .client
+            .get_or_init(|| {
+                let init = inner.init.lock().unwrap().take()?;
+                match init() {
+                    Ok(client) => {
+                        tracing::info!("✓ {} client initialized", inner.name);
+                        inner.readiness.set(inner.name, ComponentState::Ready);
+                        Some(client)
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to initialize {} client: {}", inner.name, e);
+                        inner
+                            .readiness
+                            .set(inner.name, ComponentState::Failed(e.to_string()));
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy_client_reports_initialization() {
+        let readiness = Readiness::new();
+        let client = LazyClient::new("listings", &readiness, || Ok(42));
+        let disabled = LazyClient::<i32>::disabled("sms", &readiness);
+        readiness.mark_started();
+
+        assert_eq!(
+            readiness.snapshot()["listings"],
+            ComponentState::NotInitialized
+        );
+        assert_eq!(client.clone().get(), Some(&42));
+        assert_eq!(readiness.snapshot()["listings"], ComponentState::Ready);
+        assert_eq!(disabled.get(), None);
+        assert_eq!(readiness.snapshot()["sms"], ComponentState::Disabled);
+        assert!(!readiness.is_degraded());
+    }
+
+    #[test]
+    fn test_failed_client_degrades_readiness() {
+        let readiness = Readiness::new();
+        let client = LazyClient::<i32>::new("salesforce", &readiness, || {
+            Err(AppError::InternalError("bad credentials".to_string()))
+        });
+        assert!(!readiness.is_started());
+        readiness.mark_started();
+        assert!(!readiness.is_degraded());
+
+        assert_eq!(client.get(), None);
+        assert_eq!(client.get(), None);
+        assert!(matches!(
+            readiness.snapshot()["salesforce"],
+            ComponentState::Failed(_)
+        ));
+        assert!(readiness.is_degraded());
+        assert_eq!(readiness.failed_components(), vec!["salesforce"]);
+    }
+}
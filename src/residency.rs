@@ -0,0 +1,324 @@
+//! UF (state) residency tagging of parties
+//!
+//! Each party carries the UF of its best current address in
+//! `core.parties.uf` (migration 053): the primary address first, then the
+//! most confident and most recently confirmed one, so the low-confidence
+//! address inferred from the phone DDD (`EnrichmentStorage::store_ddd_address`)
+//! only counts when nothing better is known. `refresh_party_uf` re-derives it
+//! every time addresses are stored or parties merged; parties without an
+//! address in a known UF are left untagged.
+//!
+//! `GET /api/v1/customers?uf=SP,RJ` lists the parties of one or more states,
+//! for regional campaigns and for regional partners that only get the leads
+//! of their region: the filter is intersected with the states of a
+//! restricted API key (`restrict_ufs`), and such a key only sees the parties
+//! of its states on the other customer endpoints (`visible_to`) and only gets
+//! the privacy export and consents of their data subjects
+//! (`subject_visible_to`).
+
+use crate::api_keys::AuthenticatedKey;
+use crate::consent::SubjectKind;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// The 26 states and the Federal District
+pub const UFS: [&str; 27] = [
+    "AC", "AL", "AP", "AM", "BA", "CE", "DF", "ES", "GO", "MA", "MT", "MS", "MG", "PA", "PB", "PR",
+    "PE", "PI", "RJ", "RN", "RS", "RO", "RR", "SC", "SP", "SE", "TO",
+];
+
+/// Canonical UF of a provider value ("sp", " SP ") if it names a known state
+pub fn normalize_uf(value: &str) -> Option<&'static str> {
+    let value = value.trim();
+    UFS.iter()
+        .copied()
+        .find(|uf| uf.eq_ignore_ascii_case(value))
+}
+
+/// UFs of a `?uf=SP,RJ` filter, sorted and deduplicated (empty: no filter)
+pub fn parse_uf_filter(raw: Option<&str>) -> Result<Vec<&'static str>, AppError> {
+    let mut ufs = Vec::new();
+    for value in raw.unwrap_or_default().split(',') {
+        if value.trim().is_empty() {
+            continue;
+        }
+        let uf = normalize_uf(value)
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid UF: {}", value.trim())))?;
+        ufs.push(uf);
+    }
+    ufs.sort_unstable();
+    ufs.dedup();
+    Ok(ufs)
+}
+
+/// UFs a listing may return: the requested ones, within those of a restricted key
+///
+/// An unrestricted key keeps the request as is (empty: every state). A
+/// restricted key must name the states it wants, and gets the ones it may see.
+pub fn restrict_ufs(
+    requested: Vec<&'static str>,
+    allowed: Option<&[String]>,
+) -> Result<Vec<&'static str>, AppError> {
+    let Some(allowed) = allowed else {
+        return Ok(requested);
+    };
+    if requested.is_empty() {
+        return Err(AppError::BadRequest(
+            "uf is required with a region-restricted API key".to_string(),
+        ));
+    }
+    let ufs: Vec<&'static str> = requested
+        .into_iter()
+        .filter(|uf| allowed.iter().any(|a| a == uf))
+        .collect();
+    if ufs.is_empty() {
+        return Err(AppError::Forbidden(format!(
+            "API key is restricted to {}",
+            allowed.join(",")
+        )));
+    }
+    Ok(ufs)
+}
+
+/// Whether a party tagged `uf` may be returned to `key` (no key: unrestricted)
+pub fn visible_to(uf: Option<&str>, key: Option<&AuthenticatedKey>) -> bool {
+    match key.and_then(|k| k.ufs.as_deref()) {
+        None => true,
+        Some(allowed) => uf.is_some_and(|uf| allowed.iter().any(|a| a == uf)),
+    }
+}
+
+/// Whether the data of a subject (CPF, email or phone) may be returned to `key`
+///
+/// Unrestricted keys see every subject. For a restricted key the subject must
+/// belong to at least one party, and every party it belongs to must be in one
+/// of the key's states: a person known in several states is not split.
+pub async fn subject_visible_to(
+    pool: &PgPool,
+    kind: SubjectKind,
+    value: &str,
+    key: Option<&AuthenticatedKey>,
+) -> Result<bool, AppError> {
+    if key.and_then(|k| k.ufs.as_ref()).is_none() {
+        return Ok(true);
+    }
+    let ufs = subject_ufs(pool, kind, value).await?;
+    Ok(!ufs.is_empty() && ufs.iter().all(|uf| visible_to(uf.as_deref(), key)))
+}
+
+/// UFs of the parties a subject belongs to (one entry per party)
+async fn subject_ufs(
+    pool: &PgPool,
+    kind: SubjectKind,
+    value: &str,
+) -> Result<Vec<Option<String>>, AppError> {
+    let Some(normalized) = kind.normalize(value) else {
+        return Ok(Vec::new());
+    };
+    // Contacts store phones as national digits, subjects in E.164
+    let stored = match kind {
+        SubjectKind::Phone => normalized
+            .strip_prefix("+55")
+            .unwrap_or(&normalized)
+            .to_string(),
+        _ => normalized,
+    };
+    sqlx::query_scalar(
+        r#"
+        SELECT p.uf::text FROM core.parties p
+        WHERE ($1 = 'cpf' AND p.cpf_cnpj = $2)
+           OR ($1 <> 'cpf' AND p.id IN (
+                SELECT pc.party_id FROM core.party_contacts pc
+                WHERE pc.value = $2
+                  AND (pc.contact_type = 'email') = ($1 = 'email')
+              ))
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(&stored)
+    .fetch_all(pool)
+    .timed("parties.subject_ufs")
+    .await
+    .context(ErrorContext::db("parties.subject_ufs").id("subject_type", kind.as_str()))
+}
+
+/// UF of a party, if it exists and is tagged
+pub async fn party_uf(pool: &PgPool, party_id: Uuid) -> Result<Option<String>, AppError> {
+    let uf: Option<Option<String>> =
+        sqlx::query_scalar("SELECT uf::text FROM core.parties WHERE id = $1")
+            .bind(party_id)
+            .fetch_optional(pool)
+            .timed("parties.uf")
+            .await
+            .context(ErrorContext::db("parties.uf").id("party_id", party_id))?;
+    Ok(uf.flatten())
+}
+
+/// Re-derive the UF of the given parties from their current addresses
+///
+/// Returns the number of parties whose UF changed. Takes a pool or a
+/// connection, so it can run inside the transaction storing the addresses.
+pub async fn refresh_party_uf<'e>(
+    executor: impl PgExecutor<'e>,
+    party_ids: &[Uuid],
+) -> Result<u64, AppError> {
+    if party_ids.is_empty() {
+        return Ok(0);
+    }
+    let result = sqlx::query(REFRESH_UF_SQL)
+        .bind(party_ids)
+        .bind(&UFS[..])
+        .execute(executor)
+        .timed("parties.refresh_uf")
+        .await
+        .context(ErrorContext::db("parties.refresh_uf").id("parties", party_ids.len()))?;
+    Ok(result.rows_affected())
+}
+
+pub(crate) const REFRESH_UF_SQL: &str = r#"
+    WITH best AS (
+        SELECT ids.party_id,
+               (SELECT UPPER(TRIM(a.state))
+                FROM core.party_addresses pa
+                JOIN core.addresses a ON a.id = pa.address_id
+                WHERE pa.party_id = ids.party_id
+                  AND pa.is_current
+                  AND UPPER(TRIM(a.state)) = ANY($2)
+                ORDER BY pa.is_primary DESC NULLS LAST,
+                         pa.confidence_score DESC NULLS LAST,
+                         COALESCE(pa.last_confirmed_at, pa.created_at) DESC NULLS LAST
+                LIMIT 1) AS uf
+        FROM UNNEST($1::uuid[]) AS ids(party_id)
+    )
+    UPDATE core.parties p
+    SET uf = best.uf
+    FROM best
+    WHERE p.id = best.party_id
+      AND p.uf IS DISTINCT FROM best.uf
+"#;
+
+/// One party of a regional listing
+#[derive(Debug, Serialize, FromRow)]
+pub struct PartySummary {
+    pub id: Uuid,
+    pub party_type: String,
+    pub full_name: String,
+    pub uf: Option<String>,
+    pub enriched: Option<bool>,
+    pub primary_email: Option<String>,
+    pub primary_phone: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Parties of the given UFs (all tagged parties when empty), ordered by id
+///
+/// `after` is the last id of the previous page, so a full export can be
+/// paged through without offsets.
+pub async fn list_parties(
+    pool: &PgPool,
+    ufs: &[&str],
+    after: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<PartySummary>, AppError> {
+    sqlx::query_as(
+        r#"
+        SELECT p.id, p.party_type, p.full_name, p.uf, p.enriched, p.updated_at,
+               (SELECT pc.value FROM core.party_contacts pc
+                WHERE pc.party_id = p.id AND pc.is_primary AND pc.contact_type = 'email'
+                LIMIT 1) AS primary_email,
+               (SELECT pc.value FROM core.party_contacts pc
+                WHERE pc.party_id = p.id AND pc.is_primary AND pc.contact_type <> 'email'
+                LIMIT 1) AS primary_phone
+        FROM core.parties p
+        WHERE p.uf IS NOT NULL
+          AND (cardinality($1::text[]) = 0 OR p.uf = ANY($1))
+          AND ($2::uuid IS NULL OR p.id > $2)
+        ORDER BY p.id
+        LIMIT $3
+        "#,
+    )
+    .bind(ufs)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .timed("parties.list_by_uf")
+    .await
+    .context(ErrorContext::db("parties.list_by_uf").id("ufs", ufs.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_uf() {
+        assert_eq!(normalize_uf("sp"), Some("SP"));
+        assert_eq!(normalize_uf(" Rj "), Some("RJ"));
+        assert_eq!(normalize_uf("DF"), Some("DF"));
+        assert_eq!(normalize_uf("XX"), None);
+        assert_eq!(normalize_uf("São Paulo"), None);
+        assert_eq!(normalize_uf(""), None);
+    }
+
+    #[test]
+    fn test_parse_uf_filter() {
+        assert!(parse_uf_filter(None).unwrap().is_empty());
+        assert!(parse_uf_filter(Some(" , ")).unwrap().is_empty());
+        assert_eq!(
+            parse_uf_filter(Some("rj, SP,sp")).unwrap(),
+            vec!["RJ", "SP"]
+        );
+        assert!(matches!(
+            parse_uf_filter(Some("SP,ZZ")),
+            Err(AppError::BadRequest(msg)) if msg.contains("ZZ")
+        ));
+    }
+
+    #[test]
+    fn test_restrict_ufs() {
+        let allowed = vec!["PR".to_string(), "SC".to_string()];
+        assert_eq!(restrict_ufs(vec![], None).unwrap(), Vec::<&str>::new());
+        assert_eq!(restrict_ufs(vec!["SP"], None).unwrap(), vec!["SP"]);
+        assert_eq!(
+            restrict_ufs(vec!["PR", "SP"], Some(&allowed)).unwrap(),
+            vec!["PR"]
+        );
+        assert!(matches!(
+            restrict_ufs(vec![], Some(&allowed)),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            restrict_ufs(vec!["SP"], Some(&allowed)),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_visible_to() {
+        let key = |ufs: Option<Vec<&str>>| AuthenticatedKey {
+            id: 1,
+            name: "partner-sul".to_string(),
+            scopes: vec![crate::api_keys::Scope::Read],
+            ufs: ufs.map(|ufs| ufs.into_iter().map(str::to_string).collect()),
+        };
+        assert!(visible_to(Some("SP"), None));
+        assert!(visible_to(None, Some(&key(None))));
+        assert!(visible_to(Some("PR"), Some(&key(Some(vec!["PR"])))));
+        assert!(!visible_to(Some("SP"), Some(&key(Some(vec!["PR"])))));
+        assert!(!visible_to(None, Some(&key(Some(vec!["PR"])))));
+    }
+
+    #[test]
+    fn test_ddd_regions_use_known_ufs() {
+        for ddd in 11..=99 {
+            let phone = format!("{}987654321", ddd);
+            if let Some(region) = crate::ddd::region_for_phone(&phone) {
+                assert_eq!(normalize_uf(region.uf), Some(region.uf), "DDD {}", ddd);
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Retention of stored webhook payloads
+//!
+//! Raw C2S webhook payloads are only needed while a lead is being processed
+//! and for a while after, to investigate issues. Past
+//! `WEBHOOK_PAYLOAD_RETENTION_DAYS`, the retention job removes `payload_raw`
+//! from completed and failed `webhook_events` rows, keeping the row metadata
+//! (lead, action, status, timestamps). When `WEBHOOK_ARCHIVE_URL` is set, each
+//! payload is first PUT to object storage under
+//! `<url>/webhook_events/<yyyy>/<mm>/<dd>/<lead_id>-<updated_at>.json` and the
+//! key is kept in `payload_archive_key` (migration 035); a payload whose
+//! upload fails stays in Postgres until the next run.
+
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::handlers::AppState;
+use crate::leader::Leadership;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Payloads pruned per query
+const BATCH_SIZE: i64 = 200;
+
+/// Object storage receiving payloads before they are pruned
+#[derive(Clone)]
+pub struct PayloadArchive {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl PayloadArchive {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let base_url = config.webhook_archive_url.clone()?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            client,
+            base_url,
+            token: config.webhook_archive_token.clone(),
+        })
+    }
+
+    /// Store a payload under `key`
+    pub async fn put(&self, key: &str, payload: &Value) -> Result<(), AppError> {
+        let mut request = self
+            .client
+            .put(format!("{}/{}", self.base_url, key))
+            .json(payload);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| {
+            AppError::ExternalApiError(format!(
+                "Payload archive upload failed: {}",
+                e.without_url()
+            ))
+        })?;
+        if !response.status().is_success() {
+            return Err(AppError::ExternalApiError(format!(
+                "Payload archive upload failed: HTTP {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Archive key of a webhook event payload
+pub fn archive_key(lead_id: &str, updated_at: DateTime<Utc>, received_at: DateTime<Utc>) -> String {
+    let lead: String = lead_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    format!(
+        "webhook_events/{}/{}-{}.json",
+        received_at.format("%Y/%m/%d"),
+        lead,
+        updated_at.timestamp_millis()
+    )
+}
+
+/// Outcome of one pruning run
+#[derive(Debug, Default, Serialize)]
+pub struct PruneReport {
+    pub pruned: u64,
+    pub archived: u64,
+    pub archive_failures: u64,
+}
+
+/// Prune the payloads of completed/failed events received before `cutoff`
+pub async fn prune_webhook_payloads(
+    pool: &PgPool,
+    archive: Option<&PayloadArchive>,
+    cutoff: DateTime<Utc>,
+) -> Result<PruneReport, AppError> {
+    let mut report = PruneReport::default();
+    loop {
+        let rows: Vec<(String, DateTime<Utc>, DateTime<Utc>, Value)> = sqlx::query_as(
+            r#"
+            SELECT lead_id, updated_at, received_at, payload_raw FROM webhook_events
+            WHERE payload_pruned_at IS NULL
+              AND payload_raw IS NOT NULL
+              AND received_at < $1
+              AND status IN ('completed', 'failed')
+            ORDER BY received_at
+            LIMIT $2
+            "#,
+        )
+        .bind(cutoff)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .timed("webhook_events.select_prunable")
+        .await
+        .context("Failed to select prunable webhook payloads")?;
+
+        let batch = rows.len() as i64;
+        let mut failed = 0;
+        for (lead_id, updated_at, received_at, payload) in rows {
+            let key = match archive {
+                Some(archive) => {
+                    let key = archive_key(&lead_id, updated_at, received_at);
+                    if let Err(e) = archive.put(&key, &payload).await {
+                        tracing::warn!("Keeping webhook payload of lead {}: {}", lead_id, e);
+                        failed += 1;
+                        continue;
+                    }
+                    report.archived += 1;
+                    Some(key)
+                }
+                None => None,
+            };
+            sqlx::query(
+                r#"
+                UPDATE webhook_events
+                SET payload_raw = NULL, payload_archive_key = $3, payload_pruned_at = NOW()
+                WHERE lead_id = $1 AND updated_at = $2
+                "#,
+            )
+            .bind(&lead_id)
+            .bind(updated_at)
+            .bind(key)
+            .execute(pool)
+            .timed("webhook_events.prune_payload")
+            .await
+            .context(format!(
+                "Failed to prune webhook payload for lead_id: {}",
+                lead_id
+            ))?;
+            report.pruned += 1;
+        }
+        report.archive_failures += failed;
+
+        // Failed uploads would be selected again: retry them on the next run
+        if batch < BATCH_SIZE || failed > 0 {
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// Background task applying the retention policies every 6 hours
+///
+/// Runs on the instance holding the "retention" leadership, only when
+/// `WEBHOOK_PAYLOAD_RETENTION_DAYS` is set.
+pub fn spawn_retention_job(state: Arc<AppState>) {
+    let Some(days) = state.config.webhook_payload_retention_days else {
+        return;
+    };
+    let archive = PayloadArchive::from_config(&state.config);
+    tokio::spawn(async move {
+        let leader = Leadership::new("retention");
+        let mut interval = tokio::time::interval(Duration::from_secs(6 * 3600));
+        loop {
+            interval.tick().await;
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+            let cutoff = Utc::now() - chrono::Duration::days(days);
+            match prune_webhook_payloads(&state.db, archive.as_ref(), cutoff).await {
+                Ok(report) if report.pruned == 0 && report.archive_failures == 0 => {}
+                Ok(report) => tracing::info!("Pruned webhook payloads: {:?}", report),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_archive_key() {
+        let received = Utc.with_ymd_and_hms(2025, 3, 7, 10, 0, 0).unwrap();
+        let updated = Utc.with_ymd_and_hms(2025, 3, 7, 9, 59, 0).unwrap();
+        assert_eq!(
+            archive_key("ab12/../cd", updated, received),
+            format!(
+                "webhook_events/2025/03/07/ab12cd-{}.json",
+                updated.timestamp_millis()
+            )
+        );
+    }
+}
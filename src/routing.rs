@@ -0,0 +1,247 @@
+use crate::enrichment::{EnrichedLeadSummary, WealthSegment};
+use crate::handlers::AppState;
+use serde::{Deserialize, Serialize};
+
+/// Lead routing rule, loaded from the `ROUTING_RULES` JSON array
+///
+/// A rule matches when every configured condition holds; unset conditions
+/// match anything. Rules are evaluated in order and the first match wins.
+///
+/// Example:
+/// ```json
+/// [{"name": "alto-padrao", "sources": ["Google Ads"], "min_wealth": "alto",
+///   "sms": {"notify_seller": true, "to": ["+5511999990000"]}}]
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoutingRule {
+    pub name: String,
+
+    /// Lead sources this rule applies to (case-insensitive, empty = any)
+    #[serde(default)]
+    pub sources: Vec<String>,
+
+    /// Minimum wealth segment
+    #[serde(default)]
+    pub min_wealth: Option<WealthSegment>,
+
+    /// Minimum credit score (scoreCSBA)
+    #[serde(default)]
+    pub min_credit_score: Option<i32>,
+
+    /// States (UF) this rule applies to (empty = any). Uses the Work API
+    /// address, or the region inferred from the phone DDD.
+    #[serde(default)]
+    pub states: Vec<String>,
+
+    /// SMS notification for matched leads
+    #[serde(default)]
+    pub sms: Option<SmsAction>,
+}
+
+/// SMS notification action of a routing rule
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SmsAction {
+    /// Send to the seller assigned to the lead in C2S (when the phone is known)
+    #[serde(default)]
+    pub notify_seller: bool,
+
+    /// Fixed recipients (E.164)
+    #[serde(default)]
+    pub to: Vec<String>,
+}
+
+impl RoutingRule {
+    /// Check whether an enriched lead from `source` matches this rule
+    pub fn matches(&self, source: Option<&str>, summary: &EnrichedLeadSummary) -> bool {
+        if !self.sources.is_empty() {
+            let Some(source) = source else {
+                return false;
+            };
+            if !self
+                .sources
+                .iter()
+                .any(|s| s.trim().eq_ignore_ascii_case(source.trim()))
+            {
+                return false;
+            }
+        }
+
+        if !self.states.is_empty() {
+            let Some(uf) = summary.uf.as_deref() else {
+                return false;
+            };
+            if !self
+                .states
+                .iter()
+                .any(|s| s.trim().eq_ignore_ascii_case(uf))
+            {
+                return false;
+            }
+        }
+
+        if let Some(min_wealth) = self.min_wealth {
+            if summary.wealth.rank() < min_wealth.rank() {
+                return false;
+            }
+        }
+
+        if let Some(min_score) = self.min_credit_score {
+            match summary.credit_score {
+                Some(score) if score >= min_score => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Parse routing rules from JSON (empty string = no rules)
+pub fn parse_rules(json: &str) -> anyhow::Result<Vec<RoutingRule>> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(json).map_err(|e| anyhow::anyhow!("Invalid ROUTING_RULES JSON: {}", e))
+}
+
+/// First rule matching the lead, if any
+pub fn find_matching_rule<'a>(
+    rules: &'a [RoutingRule],
+    source: Option<&str>,
+    summary: &EnrichedLeadSummary,
+) -> Option<&'a RoutingRule> {
+    rules.iter().find(|rule| rule.matches(source, summary))
+}
+
+/// Apply the first matching routing rule to an enriched lead
+///
+/// Currently sends the SMS alert configured on the rule. Failures are logged
+/// and never interrupt the enrichment workflow.
+pub async fn apply_routing(
+    state: &AppState,
+    source: Option<&str>,
+    seller_phone: Option<&str>,
+    summary: &EnrichedLeadSummary,
+) {
+    let Some(rule) = find_matching_rule(&state.config.routing_rules, source, summary) else {
+        return;
+    };
+    tracing::info!(
+        "Lead {} matched routing rule '{}'",
+        summary.lead_id,
+        rule.name
+    );
+
+    let Some(sms) = rule.sms.as_ref() else {
+        return;
+    };
+    let Some(client) = state.sms_client.get() else {
+        tracing::warn!(
+            "Routing rule '{}' requests SMS but no SMS provider is configured",
+            rule.name
+        );
+        return;
+    };
+
+    let mut recipients: Vec<String> = sms.to.clone();
+    if sms.notify_seller {
+        match seller_phone.map(crate::enrichment::validate_br_phone) {
+            Some((true, normalized)) => recipients.push(normalized),
+            _ => tracing::warn!(
+                "Routing rule '{}': no valid seller phone for lead {}",
+                rule.name,
+                summary.lead_id
+            ),
+        }
+    }
+    recipients.sort();
+    recipients.dedup();
+
+    let text = crate::sms::format_lead_alert(summary);
+    for to in &recipients {
+        if let Err(e) = client.send(to, &text).await {
+            tracing::warn!(
+                "Failed to send SMS alert for lead {} to {}: {}",
+                summary.lead_id,
+                to,
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(wealth: WealthSegment, credit_score: Option<i32>) -> EnrichedLeadSummary {
+        EnrichedLeadSummary {
+            lead_id: "lead-1".to_string(),
+            name: "Maria".to_string(),
+            phone: None,
+            email: None,
+            cpf: None,
+            income: None,
+            credit_score,
+            wealth,
+            affordability: None,
+            uf: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(
+            r#"[{"name": "vip", "sources": ["Google Ads"], "min_wealth": "muito_alto",
+                 "sms": {"notify_seller": true}}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].min_wealth, Some(WealthSegment::MuitoAlto));
+        assert!(rules[0].sms.as_ref().unwrap().notify_seller);
+        assert!(parse_rules("").unwrap().is_empty());
+        assert!(parse_rules("{not json").is_err());
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let rules = parse_rules(
+            r#"[{"name": "google-alto", "sources": ["google ads"], "min_wealth": "alto"},
+                {"name": "score", "min_credit_score": 800}]"#,
+        )
+        .unwrap();
+
+        let rich = summary(WealthSegment::MuitoAlto, None);
+        let good_score = summary(WealthSegment::Medio, Some(850));
+        let neither = summary(WealthSegment::Medio, Some(500));
+
+        assert_eq!(
+            find_matching_rule(&rules, Some("Google Ads"), &rich).map(|r| r.name.as_str()),
+            Some("google-alto")
+        );
+        assert!(find_matching_rule(&rules, Some("Site"), &rich).is_none());
+        assert_eq!(
+            find_matching_rule(&rules, None, &good_score).map(|r| r.name.as_str()),
+            Some("score")
+        );
+        assert!(find_matching_rule(&rules, Some("Google Ads"), &neither).is_none());
+    }
+
+    #[test]
+    fn test_rule_matching_by_state() {
+        let rules = parse_rules(r#"[{"name": "sul", "states": ["pr", "SC", "RS"]}]"#).unwrap();
+
+        let mut lead = summary(WealthSegment::Medio, None);
+        assert!(find_matching_rule(&rules, None, &lead).is_none());
+
+        lead.uf = Some("SC".to_string());
+        assert_eq!(
+            find_matching_rule(&rules, None, &lead).map(|r| r.name.as_str()),
+            Some("sul")
+        );
+
+        lead.uf = Some("SP".to_string());
+        assert!(find_matching_rule(&rules, None, &lead).is_none());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::enrichment::EnrichedLeadSummary;
+use crate::errors::AppError;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// External ID field on the Salesforce Lead object used for upserts
+pub const C2S_LEAD_ID_FIELD: &str = "C2S_Lead_Id__c";
+
+/// Access tokens are refreshed after this interval even without a 401
+const TOKEN_REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    instance_url: String,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    instance_url: String,
+    fetched_at: Instant,
+}
+
+/// Build the Salesforce Lead record for an enriched lead
+///
+/// `LastName` and `Company` are mandatory on the standard Lead object, so
+/// placeholders are used when the lead has no name (leads are individuals).
+/// Documents (CPF) are not synced.
+pub fn lead_record(summary: &EnrichedLeadSummary) -> Value {
+    let last_name = if summary.name.trim().is_empty() {
+        format!("Lead C2S {}", summary.lead_id)
+    } else {
+        summary.name.clone()
+    };
+
+    let mut record = serde_json::Map::new();
+    record.insert("LastName".to_string(), json!(last_name));
+    record.insert("Company".to_string(), json!("Pessoa Física"));
+    record.insert("LeadSource".to_string(), json!("C2S"));
+    record.insert(
+        "Wealth_Segment__c".to_string(),
+        json!(summary.wealth.as_tag()),
+    );
+
+    if let Some(email) = &summary.email {
+        record.insert("Email".to_string(), json!(email));
+    }
+    if let Some(phone) = &summary.phone {
+        record.insert("MobilePhone".to_string(), json!(phone));
+    }
+    if let Some(score) = summary.credit_score {
+        record.insert("Credit_Score__c".to_string(), json!(score));
+    }
+    if let Some(income) = summary.income {
+        record.insert("Income__c".to_string(), json!(income));
+    }
+    if let Some(affordability) = &summary.affordability {
+        record.insert(
+            "Price_To_Income__c".to_string(),
+            json!((affordability.price_to_income * 10.0).round() / 10.0),
+        );
+    }
+
+    Value::Object(record)
+}
+
+/// Upsert URL of the Lead with `lead_id` as its C2S external ID
+///
+/// The lead id comes from C2S, so it is percent-encoded as a path segment.
+fn upsert_url(
+    instance_url: &str,
+    api_version: &str,
+    lead_id: &str,
+) -> Result<reqwest::Url, AppError> {
+    let mut url = reqwest::Url::parse(instance_url).map_err(|e| {
+        AppError::ExternalApiError(format!("Invalid Salesforce instance URL: {}", e))
+    })?;
+    url.path_segments_mut()
+        .map_err(|_| AppError::ExternalApiError("Invalid Salesforce instance URL".to_string()))?
+        .pop_if_empty()
+        .extend([
+            "services",
+            "data",
+            api_version,
+            "sobjects",
+            "Lead",
+            C2S_LEAD_ID_FIELD,
+            lead_id,
+        ]);
+    Ok(url)
+}
+
+/// Salesforce REST client using the OAuth 2.0 client-credentials flow
+#[derive(Clone)]
+pub struct SalesforceClient {
+    client: reqwest::Client,
+    login_url: String,
+    client_id: String,
+    client_secret: String,
+    api_version: String,
+    token: Arc<RwLock<Option<CachedToken>>>,
+}
+
+impl SalesforceClient {
+    pub fn new(
+        login_url: String,
+        client_id: String,
+        client_secret: String,
+        api_version: String,
+    ) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(20))
+            .build()
+            .map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to create Salesforce client: {}", e))
+            })?;
+
+        Ok(Self {
+            client,
+            login_url: login_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret,
+            api_version,
+            token: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    /// Request a new access token (client-credentials grant)
+    async fn fetch_token(&self) -> Result<CachedToken, AppError> {
+        let url = format!("{}/services/oauth2/token", self.login_url);
+        tracing::info!("Requesting Salesforce access token");
+
+        let response = self
+            .client
+            .post(&url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::ExternalApiError(format!("Salesforce token request failed: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::ExternalApiError(format!(
+                "Salesforce token request returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        let token: TokenResponse = response.json().await.map_err(|e| {
+            AppError::ExternalApiError(format!("Failed to parse Salesforce token: {}", e))
+        })?;
+
+        Ok(CachedToken {
+            access_token: token.access_token,
+            instance_url: token.instance_url.trim_end_matches('/').to_string(),
+            fetched_at: Instant::now(),
+        })
+    }
+
+    /// Get a cached access token, refreshing when missing, stale or forced
+    async fn access_token(&self, force_refresh: bool) -> Result<CachedToken, AppError> {
+        if !force_refresh {
+            if let Some(token) = self.token.read().await.as_ref() {
+                if token.fetched_at.elapsed() < TOKEN_REFRESH_INTERVAL {
+                    return Ok(token.clone());
+                }
+            }
+        }
+
+        let token = self.fetch_token().await?;
+        *self.token.write().await = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Create or update the Salesforce Lead mirroring an enriched C2S lead
+    ///
+    /// Upserts on the `C2S_Lead_Id__c` external ID so re-enrichments update the
+    /// existing record instead of creating duplicates.
+    pub async fn upsert_lead(&self, summary: &EnrichedLeadSummary) -> Result<(), AppError> {
+        let record = lead_record(summary);
+        let mut token = self.access_token(false).await?;
+
+        for attempt in 0..2 {
+            let url = upsert_url(&token.instance_url, &self.api_version, &summary.lead_id)?;
+            tracing::info!("Upserting lead {} into Salesforce", summary.lead_id);
+
+            let response = self
+                .client
+                .patch(url)
+                .bearer_auth(&token.access_token)
+                .json(&record)
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("Salesforce request failed: {}", e))
+                })?;
+
+            // Token revoked/expired: refresh once and retry
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED && attempt == 0 {
+                tracing::warn!("Salesforce token rejected, refreshing");
+                token = self.access_token(true).await?;
+                continue;
+            }
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "Salesforce lead upsert failed {}: {}",
+                    status, error_text
+                )));
+            }
+
+            tracing::info!("✓ Lead {} synced to Salesforce", summary.lead_id);
+            return Ok(());
+        }
+
+        Err(AppError::ExternalApiError(
+            "Salesforce rejected refreshed token".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrichment::WealthSegment;
+
+    fn sample_summary() -> EnrichedLeadSummary {
+        EnrichedLeadSummary {
+            lead_id: "lead-123".to_string(),
+            name: "Maria Silva".to_string(),
+            phone: Some("5511987654321".to_string()),
+            email: Some("maria@example.com".to_string()),
+            cpf: Some("12345678901".to_string()),
+            income: Some(8500.5),
+            credit_score: Some(920),
+            wealth: WealthSegment::Alto,
+            affordability: None,
+            uf: None,
+        }
+    }
+
+    #[test]
+    fn test_lead_record_custom_fields() {
+        let record = lead_record(&sample_summary());
+
+        assert_eq!(record["LastName"], "Maria Silva");
+        assert_eq!(record["Credit_Score__c"], 920);
+        assert_eq!(record["Income__c"], 8500.5);
+        assert_eq!(record["Wealth_Segment__c"], "patrimonio-alto");
+        assert!(record.get("CPF__c").is_none());
+        assert!(record.get("Price_To_Income__c").is_none());
+    }
+
+    #[test]
+    fn test_upsert_url_encodes_lead_id() {
+        let url = upsert_url("https://acme.my.salesforce.com/", "v59.0", "lead/1?x=2").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://acme.my.salesforce.com/services/data/v59.0/sobjects/Lead/C2S_Lead_Id__c/lead%2F1%3Fx=2"
+        );
+    }
+
+    #[test]
+    fn test_lead_record_price_to_income() {
+        let summary = EnrichedLeadSummary {
+            affordability: crate::affordability::Affordability::assess(
+                &json!({"DadosEconomicos": {"renda": "8500,50"}}),
+                510_000.0,
+            ),
+            ..sample_summary()
+        };
+        let record = lead_record(&summary);
+
+        assert_eq!(record["Price_To_Income__c"], 5.0);
+    }
+
+    #[test]
+    fn test_lead_record_without_name_or_enrichment() {
+        let summary = EnrichedLeadSummary {
+            name: " ".to_string(),
+            income: None,
+            credit_score: None,
+            email: None,
+            ..sample_summary()
+        };
+        let record = lead_record(&summary);
+
+        assert_eq!(record["LastName"], "Lead C2S lead-123");
+        assert!(record.get("Income__c").is_none());
+        assert!(record.get("Credit_Score__c").is_none());
+        assert!(record.get("Email").is_none());
+    }
+}
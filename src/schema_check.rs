@@ -0,0 +1,384 @@
+//! Self-test of the critical SQL statements against the live schema
+//!
+//! Most queries are built at runtime (`sqlx::query`), so a missing table,
+//! column, enum value or `ON CONFLICT` constraint only shows up when the
+//! first lead reaches that statement. `verify` prepares each critical
+//! statement (parse + analyze, nothing is executed) and reports the ones
+//! the database rejects. It runs on boot with `SQL_SELF_TEST=true` and as
+//! part of `--check`.
+//!
+//! Keep the statements in sync with the queries in `db_storage`, `services`,
+//! `webhook_handler`, `outbox`, `lead_messages`, `c2s_accounts`, `lead_import`,
+//! `shadow`, `debug_payloads`, `consent`, `google_ads_forms`, `templates`,
+//! `timeline`, `work_api_fixtures`, `jobs`, `api_keys` and `residency` when
+//! their shape changes.
+
+use sqlx::{Executor, PgPool};
+use std::fmt;
+
+/// (operation, statement) pairs, named like the `.timed()` operations
+pub const CRITICAL_STATEMENTS: &[(&str, &str)] = &[
+    (
+        "parties.insert",
+        r#"
+        INSERT INTO core.parties (
+            id, party_type, cpf_cnpj, full_name, normalized_name, enriched,
+            birth_date, sex, mother_name, opening_date, company_type, company_size,
+            phonetic_name, created_at, updated_at
+        )
+        VALUES (gen_random_uuid(), $1, $2, $3, $4, true, $5, $6, $7, $8, $9, $10, $11, now(), now())
+        RETURNING id
+        "#,
+    ),
+    (
+        "parties.upsert_company",
+        r#"
+        INSERT INTO core.parties (
+            id, party_type, cpf_cnpj, full_name, normalized_name, phonetic_name,
+            fantasy_name, normalized_fantasy_name, opening_date, registration_status_date,
+            company_type, company_size, enriched, created_at, updated_at
+        )
+        VALUES (gen_random_uuid(), 'company', $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, true, now(), now())
+        ON CONFLICT (cpf_cnpj) WHERE cpf_cnpj IS NOT NULL DO UPDATE
+        SET updated_at = now()
+        RETURNING id
+        "#,
+    ),
+    (
+        "people.upsert",
+        r#"
+        INSERT INTO core.people (
+            party_id, full_name, mothers_name, birth_date, sex,
+            marital_status, document_cpf, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, now(), now())
+        ON CONFLICT (party_id) DO UPDATE
+        SET updated_at = EXCLUDED.updated_at
+        "#,
+    ),
+    (
+        "companies.upsert",
+        r#"
+        INSERT INTO core.companies (
+            party_id, legal_name, trade_name, cnpj, company_size, industry, cnae,
+            foundation_date, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now(), now())
+        ON CONFLICT (party_id) DO UPDATE
+        SET updated_at = now()
+        "#,
+    ),
+    (
+        "party_enrichments.upsert",
+        r#"
+        INSERT INTO core.party_enrichments (
+            enrichment_id, party_id, provider, raw_payload, normalized_data,
+            quality_score, enriched_at, created_at
+        )
+        VALUES (gen_random_uuid(), $1, 'work_api', $2, '{}'::jsonb, $3, now(), now())
+        ON CONFLICT (party_id) DO UPDATE
+        SET raw_payload = EXCLUDED.raw_payload
+        "#,
+    ),
+    (
+        "addresses.insert",
+        r#"
+        INSERT INTO core.addresses (
+            id, street, number, neighborhood, city, state, zip_code,
+            complement, latitude, longitude, formatted_address,
+            created_at, updated_at
+        )
+        VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, now(), now())
+        RETURNING id
+        "#,
+    ),
+    (
+        "party_addresses.upsert",
+        r#"
+        INSERT INTO core.party_addresses (
+            id, party_id, address_id, address_type, is_primary, is_current,
+            verified, confidence_score, metadata, valid_from, last_confirmed_at,
+            created_at, updated_at
+        )
+        VALUES (gen_random_uuid(), $1, $2, $3, $4, true, false, $5, $6, now(), now(), now(), now())
+        ON CONFLICT (party_id, address_id) DO UPDATE
+        SET is_current = true, valid_to = NULL, last_confirmed_at = now(), updated_at = now()
+        "#,
+    ),
+    (
+        "party_contacts.insert",
+        r#"
+        INSERT INTO core.party_contacts (
+            contact_id, party_id, contact_type, value,
+            is_primary, is_verified, is_whatsapp, source,
+            confidence, valid_from, valid_to, last_confirmed_at, created_at, updated_at
+        )
+        VALUES (
+            gen_random_uuid(), $1,
+            CASE $2::text
+                WHEN 'email' THEN 'email'::core.contact_type_enum
+                WHEN 'whatsapp' THEN 'whatsapp'::core.contact_type_enum
+                ELSE 'phone'::core.contact_type_enum
+            END,
+            $3, $4, $5, $6, $7, $8, now(), NULL, now(), now(), now()
+        )
+        ON CONFLICT ON CONSTRAINT uq_party_contact_unique DO UPDATE
+        SET last_confirmed_at = now(), valid_to = NULL
+        "#,
+    ),
+    (
+        "customers.find_by_email",
+        r#"
+        SELECT * FROM core.parties p
+        WHERE p.party_type = 'person'
+          AND p.id IN (
+            SELECT pc.party_id FROM core.party_contacts pc
+            WHERE pc.value = $1 AND pc.contact_type = 'email'
+          )
+        LIMIT 1
+        "#,
+    ),
+    (
+        "webhook_events.insert",
+        r#"
+        INSERT INTO webhook_events (lead_id, updated_at, hook_action, payload_raw, status)
+        VALUES ($1, $2, $3, $4, 'received')
+        "#,
+    ),
+    (
+        "webhook_events.mark_processing",
+        r#"
+        UPDATE webhook_events
+        SET status = 'processing', attempts = attempts + 1, updated_at_ts = now()
+        WHERE lead_id = $1 AND updated_at = $2 AND status IN ('received', 'processing')
+        RETURNING attempts
+        "#,
+    ),
+    (
+        "webhook_events.requeue_deferred",
+        r#"
+        UPDATE webhook_events
+        SET status = 'received', updated_at_ts = now()
+        WHERE id IN (
+            SELECT id FROM webhook_events
+            WHERE status = 'deferred'
+            ORDER BY received_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING lead_id, updated_at, payload_raw
+        "#,
+    ),
+    (
+        "jobs.enqueue",
+        r#"
+        INSERT INTO jobs (kind, payload, max_attempts)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+    ),
+    (
+        "jobs.claim",
+        r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, locked_at = now(), locked_by = $2
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE kind = $1
+              AND ((status = 'queued' AND run_after <= now())
+                OR (status = 'running' AND locked_at < now() - make_interval(secs => $3)
+                    AND attempts < max_attempts))
+            ORDER BY run_after
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, payload, attempts, max_attempts
+        "#,
+    ),
+    (
+        "jobs.status",
+        r#"
+        SELECT id, kind, status, progress, attempts, max_attempts, last_error, result,
+               created_at, finished_at
+        FROM jobs
+        WHERE id = $1 AND kind = ANY($2)
+        "#,
+    ),
+    (
+        "c2s_outbox.insert",
+        r#"
+        INSERT INTO c2s_outbox (lead_id, tenant, body, send_after, delay_reason)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    ),
+    (
+        "pending_enrichments.insert",
+        r#"
+        INSERT INTO pending_enrichments (
+            lead_id, tenant, customer_name, phone, email, attempts, next_attempt_at
+        )
+        VALUES ($1, $2, $3, $4, $5, 1, now() + make_interval(secs => $6))
+        ON CONFLICT (lead_id) DO NOTHING
+        "#,
+    ),
+    (
+        "sent_messages.insert",
+        r#"
+        INSERT INTO sent_messages
+            (lead_id, body_hash, body_length, status, status_code, error_message, triggered_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    ),
+    (
+        "c2s_lead_accounts.insert",
+        r#"
+        INSERT INTO c2s_lead_accounts (lead_id, account)
+        VALUES ($1, $2)
+        ON CONFLICT (lead_id) DO NOTHING
+        "#,
+    ),
+    (
+        "c2s_imported_leads.insert",
+        r#"
+        INSERT INTO c2s_imported_leads (contact_hash, c2s_lead_id, account)
+        SELECT hash, $2, $3 FROM UNNEST($1::text[]) AS hash
+        ON CONFLICT (contact_hash) DO NOTHING
+        "#,
+    ),
+    (
+        "shadow_comparisons.insert",
+        r#"
+        INSERT INTO shadow_comparisons
+            (lead_id, cpf, shadow_error, primary_ms, shadow_ms, fields_compared, mismatches, shadow_payload)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    ),
+    (
+        "debug_payloads.insert",
+        r#"
+        INSERT INTO debug_payloads (lead_id, cpf, step, request, response, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6::timestamptz, $6::timestamptz + make_interval(hours => $7))
+        "#,
+    ),
+    (
+        "consents.grant",
+        r#"
+        INSERT INTO consents (subject_type, subject, purpose, source, granted_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (subject_type, subject, purpose) DO UPDATE
+        SET source = EXCLUDED.source, granted_at = EXCLUDED.granted_at, revoked_at = NULL
+        "#,
+    ),
+    (
+        "consents.check",
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM consents c
+            JOIN UNNEST($1::text[], $2::text[]) AS s(subject_type, subject)
+              ON c.subject_type = s.subject_type AND c.subject = s.subject
+            WHERE c.purpose = $3 AND c.revoked_at IS NULL
+        )
+        "#,
+    ),
+    (
+        "google_ads_forms.find",
+        r#"
+        SELECT form_id, name, field_mapping, seller_id, product, description_template, updated_at
+        FROM google_ads_forms
+        WHERE form_id = $1
+        "#,
+    ),
+    (
+        "message_templates.list",
+        "SELECT name, body, version, updated_at FROM message_templates ORDER BY name",
+    ),
+    (
+        "work_api_fixtures.insert",
+        r#"
+        INSERT INTO work_api_fixtures (shape_hash, payload)
+        VALUES ($1, $2)
+        ON CONFLICT (shape_hash) DO NOTHING
+        "#,
+    ),
+    ("lead_timeline.load", crate::timeline::TIMELINE_SQL),
+    (
+        "contact_history.load",
+        crate::contact_history::CONTACT_HISTORY_SQL,
+    ),
+    (
+        "party_contacts.elect_primary",
+        crate::primary_contact::ELECT_PRIMARY_SQL,
+    ),
+    ("parties.refresh_uf", crate::residency::REFRESH_UF_SQL),
+    (
+        "c2s_last_messages.upsert",
+        crate::lead_messages::RECORD_MESSAGE_SQL,
+    ),
+    ("api_keys.authenticate", crate::api_keys::AUTHENTICATE_SQL),
+];
+
+/// A critical statement rejected by the database
+#[derive(Debug, Clone)]
+pub struct SchemaIssue {
+    pub operation: &'static str,
+    pub error: String,
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.operation, self.error)
+    }
+}
+
+/// Prepare every critical statement, returning the ones that fail
+pub async fn verify(pool: &PgPool) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+    for (operation, sql) in CRITICAL_STATEMENTS {
+        if let Err(e) = pool.prepare(sql).await {
+            let error = match e.as_database_error() {
+                Some(db_err) => db_err.message().to_string(),
+                None => e.to_string(),
+            };
+            issues.push(SchemaIssue { operation, error });
+        }
+    }
+    issues
+}
+
+/// Readable report of the failed statements
+pub fn render_report(issues: &[SchemaIssue]) -> String {
+    let mut report = format!(
+        "{} of {} critical SQL statements failed against the live schema:",
+        issues.len(),
+        CRITICAL_STATEMENTS.len()
+    );
+    for issue in issues {
+        report.push_str(&format!("\n  - {}", issue));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_are_unique() {
+        let mut names: Vec<&str> = CRITICAL_STATEMENTS.iter().map(|(name, _)| *name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), CRITICAL_STATEMENTS.len());
+    }
+
+    #[test]
+    fn test_render_report() {
+        let issues = vec![SchemaIssue {
+            operation: "sent_messages.insert",
+            error: "relation \"sent_messages\" does not exist".to_string(),
+        }];
+        let report = render_report(&issues);
+        assert!(report.starts_with(&format!("1 of {} critical", CRITICAL_STATEMENTS.len())));
+        assert!(report.contains("- sent_messages.insert: relation"));
+    }
+}
@@ -1,25 +1,145 @@
+use crate::c2s_sellers::{self, C2SSeller};
+use crate::cache_validator::ValidatedCacheEntry;
+use crate::circuit_breaker::{self, Provider};
+use crate::clients::WorkApi;
 use crate::config::Config;
+use crate::db::TimedQuery;
 use crate::errors::AppError;
+use crate::lead_dedup;
 use crate::models::*;
+use crate::phonetic::phonetic_key;
+use crate::rate_limit;
 use chrono::Utc;
+use moka::future::Cache;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Work API modules queried for a CNPJ (`modulo` parameter)
+const MODULE_SOCIOS: &str = "socios";
+const MODULE_FATURAMENTO_PRESUMIDO: &str = "faturamento_presumido";
+const MODULE_SITUACAO_CADASTRAL: &str = "situacao_cadastral";
+
+/// Default Work API endpoint (`WORK_API_BASE_URL` overrides it)
+pub const WORK_API_BASE_URL: &str = "https://completa.workbuscas.com";
 
 pub struct WorkApiService {
     client: Client,
     base_url: String,
     api_token: String,
+    cache: Option<Cache<String, String>>,
 }
 
 impl WorkApiService {
     pub fn new(config: &Config) -> Self {
+        Self::with_credentials(
+            config.work_api_base_url.clone(),
+            config.worker_api_key.clone(),
+        )
+    }
+
+    /// Client for a tenant's own Work API contract (see `tenant_credentials`)
+    pub fn with_credentials(base_url: String, api_token: String) -> Self {
         Self {
             client: Client::new(),
-            base_url: "https://completa.workbuscas.com".to_string(),
-            api_token: config.worker_api_key.clone(),
+            base_url,
+            api_token,
+            cache: None,
+        }
+    }
+
+    /// Share the Work API response cache (`Caches::work_api`)
+    ///
+    /// Module responses use the same `module:{modulo}:{documento}` entries as
+    /// the `/work/modules/:module` endpoint.
+    pub fn with_cache(mut self, cache: Cache<String, String>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Fetch the company modules (sócios, faturamento presumido, situação
+    /// cadastral) for a CNPJ
+    ///
+    /// Modules are fetched concurrently and cached individually. A module that
+    /// fails is left empty; the call only fails when every module failed.
+    pub async fn fetch_company_modules(&self, cnpj: &str) -> Result<CompanyModules, AppError> {
+        // Every call is billed: never spend one on a document that cannot be a real CNPJ
+        if let Err(rejection) = crate::enrichment::check_cnpj(cnpj) {
+            crate::metrics::record_avoided_call(rejection.as_str());
+            return Err(AppError::InvalidDocument(format!(
+                "Invalid CNPJ ({}), Work API not called",
+                rejection.as_str()
+            )));
+        }
+        let cnpj: String = cnpj.chars().filter(|c| c.is_ascii_digit()).collect();
+
+        let (socios, faturamento, situacao) = tokio::join!(
+            self.fetch_module_cached(MODULE_SOCIOS, &cnpj),
+            self.fetch_module_cached(MODULE_FATURAMENTO_PRESUMIDO, &cnpj),
+            self.fetch_module_cached(MODULE_SITUACAO_CADASTRAL, &cnpj),
+        );
+
+        let (socios, faturamento, situacao) = match (socios, faturamento, situacao) {
+            (Err(e), Err(_), Err(_)) => return Err(e),
+            results => results,
+        };
+
+        let mut failed_modules = Vec::new();
+        let mut module = |name: &str, result: Result<Option<Value>, AppError>| {
+            result
+                .inspect_err(|e| {
+                    tracing::warn!("Work API module '{}' failed for CNPJ {}: {}", name, cnpj, e);
+                    failed_modules.push(name.to_string());
+                })
+                .ok()
+                .flatten()
+        };
+
+        let socios = module(MODULE_SOCIOS, socios);
+        let faturamento_presumido = module(MODULE_FATURAMENTO_PRESUMIDO, faturamento);
+        let situacao_cadastral = module(MODULE_SITUACAO_CADASTRAL, situacao);
+        Ok(CompanyModules {
+            socios,
+            faturamento_presumido,
+            situacao_cadastral,
+            failed_modules,
+            cnpj,
+        })
+    }
+
+    /// `fetch_module` backed by the response cache (only data is cached)
+    async fn fetch_module_cached(
+        &self,
+        module: &str,
+        consulta: &str,
+    ) -> Result<Option<Value>, AppError> {
+        let cache_key = format!("module:{}:{}", module, consulta);
+
+        if let Some(cache) = self.cache.as_ref() {
+            if let Some(result) = crate::cache_ttl::get(cache, &cache_key)
+                .await
+                .and_then(|entry| serde_json::from_str::<Value>(&entry.data).ok())
+            {
+                tracing::debug!("Work API cache HIT for module '{}': {}", module, consulta);
+                return Ok(Some(result));
+            }
+        }
+
+        let result = self.fetch_module(module, consulta).await?;
+
+        if let (Some(cache), Some(data)) = (self.cache.as_ref(), result.as_ref()) {
+            if let Ok(json_str) = serde_json::to_string(data) {
+                cache
+                    .insert(cache_key, ValidatedCacheEntry::new(json_str).serialize())
+                    .await;
+            }
         }
+
+        Ok(result)
     }
 
     /// Fetch all available modules from Work API for a given document (CPF)
@@ -27,52 +147,69 @@ impl WorkApiService {
         &self,
         documento: &str,
     ) -> Result<WorkApiCompleteResponse, AppError> {
-        // Using modulo=cpf returns all data at root level (DadosBasicos, DadosEconomicos, etc.)
-        // Using multiple modules returns a different structure with only status/reason
-
-        // Build URL with proper parameter encoding to prevent injection attacks
-        let url = reqwest::Url::parse_with_params(
-            &format!("{}/api", self.base_url),
-            &[
-                ("token", self.api_token.as_str()),
-                ("modulo", "cpf"),
-                ("consulta", documento),
-            ],
-        )
-        .map_err(|e| AppError::ExternalApiError(format!("Failed to build URL: {}", e)))?;
-
-        tracing::info!("Fetching all Work API modules for document: {}", documento);
-        // Redact token from logs to prevent credential exposure
-        tracing::debug!(
-            "Work API URL: {}?token=[REDACTED]&modulo=cpf&consulta={}",
-            self.base_url,
-            documento
-        );
+        // Every call is billed: never spend one on a document that cannot be a real CPF
+        if let Err(rejection) = crate::enrichment::check_cpf(documento) {
+            crate::metrics::record_avoided_call(rejection.as_str());
+            tracing::warn!(
+                "Skipping Work API call for invalid CPF {} ({})",
+                documento,
+                rejection.as_str()
+            );
+            return Err(AppError::InvalidDocument(format!(
+                "Invalid CPF ({}), Work API not called",
+                rejection.as_str()
+            )));
+        }
 
-        let response =
-            self.client.get(url).send().await.map_err(|e| {
+        let _slot = crate::rate_limit::work_api_limiter().acquire().await?;
+        circuit_breaker::guarded(Provider::WorkApi, async {
+            // Using modulo=cpf returns all data at root level (DadosBasicos, DadosEconomicos, etc.)
+            // Using multiple modules returns a different structure with only status/reason
+
+            // Build URL with proper parameter encoding to prevent injection attacks
+            let url = reqwest::Url::parse_with_params(
+                &format!("{}/api", self.base_url),
+                &[
+                    ("token", self.api_token.as_str()),
+                    ("modulo", "cpf"),
+                    ("consulta", documento),
+                ],
+            )
+            .map_err(|e| AppError::ExternalApiError(format!("Failed to build URL: {}", e)))?;
+
+            tracing::info!("Fetching all Work API modules for document: {}", documento);
+            // Redact token from logs to prevent credential exposure
+            tracing::debug!(
+                "Work API URL: {}?token=[REDACTED]&modulo=cpf&consulta={}",
+                self.base_url,
+                documento
+            );
+
+            let response = self.client.get(url).send().await.map_err(|e| {
                 AppError::ExternalApiError(format!("Work API request failed: {}", e))
             })?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            tracing::error!("Work API returned error {}: {}", status, error_text);
-            return Err(AppError::ExternalApiError(format!(
-                "Work API returned status {}: {}",
-                status, error_text
-            )));
-        }
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                tracing::error!("Work API returned error {}: {}", status, error_text);
+                return Err(AppError::ExternalApiError(format!(
+                    "Work API returned status {}: {}",
+                    status, error_text
+                )));
+            }
 
-        let result: WorkApiCompleteResponse = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse Work API response: {}", e))
-        })?;
+            let result: WorkApiCompleteResponse = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to parse Work API response: {}", e))
+            })?;
 
-        tracing::info!("Successfully fetched Work API modules");
-        Ok(result)
+            tracing::info!("Successfully fetched Work API modules");
+            Ok(result)
+        })
+        .await
     }
 
     /// Fetch a specific module from Work API
@@ -81,102 +218,256 @@ impl WorkApiService {
         module: &str,
         consulta: &str,
     ) -> Result<Option<Value>, AppError> {
-        // Build URL with proper parameter encoding to prevent injection attacks
-        let url = reqwest::Url::parse_with_params(
-            &format!("{}/api", self.base_url),
-            &[
-                ("token", self.api_token.as_str()),
-                ("modulo", module),
-                ("consulta", consulta),
-            ],
-        )
-        .map_err(|e| AppError::ExternalApiError(format!("Failed to build URL: {}", e)))?;
-
-        tracing::info!("Fetching Work API module '{}' for: {}", module, consulta);
-
-        let response =
-            self.client.get(url).send().await.map_err(|e| {
+        let _slot = crate::rate_limit::work_api_limiter().acquire().await?;
+        circuit_breaker::guarded(Provider::WorkApi, async {
+            // Build URL with proper parameter encoding to prevent injection attacks
+            let url = reqwest::Url::parse_with_params(
+                &format!("{}/api", self.base_url),
+                &[
+                    ("token", self.api_token.as_str()),
+                    ("modulo", module),
+                    ("consulta", consulta),
+                ],
+            )
+            .map_err(|e| AppError::ExternalApiError(format!("Failed to build URL: {}", e)))?;
+
+            tracing::info!("Fetching Work API module '{}' for: {}", module, consulta);
+
+            let response = self.client.get(url).send().await.map_err(|e| {
                 AppError::ExternalApiError(format!("Work API request failed: {}", e))
             })?;
 
-        if !response.status().is_success() {
-            tracing::warn!("Work API module '{}' returned non-success status", module);
-            return Ok(None);
-        }
+            if !response.status().is_success() {
+                tracing::warn!("Work API module '{}' returned non-success status", module);
+                return Ok(None);
+            }
 
-        let result: Value = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse Work API response: {}", e))
-        })?;
+            let result: Value = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to parse Work API response: {}", e))
+            })?;
 
-        Ok(Some(result))
+            Ok(Some(result))
+        })
+        .await
     }
 }
 
 pub struct CustomerService {
     pool: PgPool,
+    lookup_cache: Option<Cache<String, Uuid>>,
+}
+
+/// Lookup cache key for a CPF identifier
+pub fn cpf_lookup_key(cpf: &str) -> String {
+    format!("cpf:{}", cpf)
+}
+
+/// Lookup cache key for an email identifier (stored lowercased)
+pub fn email_lookup_key(email: &str) -> String {
+    format!("email:{}", email.to_lowercase())
+}
+
+/// Lookup cache key for a phone identifier (stored digits-only)
+pub fn phone_lookup_key(phone: &str) -> String {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    format!("phone:{}", digits)
+}
+
+/// Canonical form of a person name, as stored in `core.parties.normalized_name`
+///
+/// Uppercase, without accents, single-spaced. Must match the backfill in
+/// migration 021 so name searches hit the trigram index.
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .map(|c| match c {
+                    'á' | 'à' | 'â' | 'ã' | 'ä' | 'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+                    'é' | 'è' | 'ê' | 'ë' | 'É' | 'È' | 'Ê' | 'Ë' => 'E',
+                    'í' | 'ì' | 'î' | 'ï' | 'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+                    'ó' | 'ò' | 'ô' | 'õ' | 'ö' | 'Ó' | 'Ò' | 'Ô' | 'Õ' | 'Ö' => 'O',
+                    'ú' | 'ù' | 'û' | 'ü' | 'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+                    'ç' | 'Ç' => 'C',
+                    'ñ' | 'Ñ' => 'N',
+                    other => other.to_ascii_uppercase(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escape LIKE wildcards so user input is matched literally
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 impl CustomerService {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            lookup_cache: None,
+        }
+    }
+
+    /// Cache identifier → party_id for hot lookups (see `Caches::customer_lookup`)
+    pub fn with_lookup_cache(mut self, cache: Cache<String, Uuid>) -> Self {
+        self.lookup_cache = Some(cache);
+        self
     }
 
-    /// Find customer by CPF, email, phone, or name
+    /// Find customer by CPF, email, phone, or name, with the identifier that matched
+    ///
+    /// CPF, email and phone matches are cached as identifier → party_id, so a
+    /// repeated lookup costs one primary-key query instead of the sequential
+    /// search. Name searches are fuzzy and never cached.
     pub async fn find_customer(
         &self,
         params: &CustomerQueryParams,
-    ) -> Result<Option<Customer>, AppError> {
+    ) -> Result<Option<(Customer, MatchKind)>, AppError> {
         // Priority: CPF > Email > Phone > Name
         if let Some(ref cpf) = params.cpf {
+            let key = cpf_lookup_key(cpf);
+            if let Some(customer) = self.cached_lookup(&key).await? {
+                return Ok(Some((customer, MatchKind::Cpf)));
+            }
             if let Some(customer) = self.find_by_cpf(cpf).await? {
-                return Ok(Some(customer));
+                self.remember_lookup(key, &customer).await;
+                return Ok(Some((customer, MatchKind::Cpf)));
             }
         }
 
         if let Some(ref email) = params.email {
+            let key = email_lookup_key(email);
+            if let Some(customer) = self.cached_lookup(&key).await? {
+                return Ok(Some((customer, MatchKind::Email)));
+            }
             if let Some(customer) = self.find_by_email(email).await? {
-                return Ok(Some(customer));
+                self.remember_lookup(key, &customer).await;
+                return Ok(Some((customer, MatchKind::Email)));
             }
         }
 
         if let Some(ref phone) = params.phone {
+            let key = phone_lookup_key(phone);
+            if let Some(customer) = self.cached_lookup(&key).await? {
+                return Ok(Some((customer, MatchKind::Phone)));
+            }
             if let Some(customer) = self.find_by_phone(phone).await? {
-                return Ok(Some(customer));
+                self.remember_lookup(key, &customer).await;
+                return Ok(Some((customer, MatchKind::Phone)));
             }
         }
 
         if let Some(ref name) = params.name {
             if let Some(customer) = self.find_by_name(name).await? {
-                return Ok(Some(customer));
+                return Ok(Some((customer, MatchKind::Name)));
+            }
+            if let Some(customer) = self.find_by_phonetic_name(name).await? {
+                tracing::info!(
+                    "Customer matched by phonetic name only (weak match): {}",
+                    customer.id
+                );
+                return Ok(Some((customer, MatchKind::PhoneticName)));
             }
         }
 
         Ok(None)
     }
 
+    /// Resolve a cached identifier to its party (evicts the entry if the party is gone)
+    async fn cached_lookup(&self, key: &str) -> Result<Option<Customer>, AppError> {
+        let Some(cache) = self.lookup_cache.as_ref() else {
+            return Ok(None);
+        };
+        let party_id = cache.get(key).await;
+        crate::metrics::record_cache_lookup("customer_lookup", party_id.is_some());
+        let Some(party_id) = party_id else {
+            return Ok(None);
+        };
+
+        let customer = self.find_by_id(&party_id).await?;
+        if customer.is_some() {
+            tracing::debug!("Customer lookup cache HIT: {}", key);
+        } else {
+            cache.invalidate(key).await;
+        }
+        Ok(customer)
+    }
+
+    async fn remember_lookup(&self, key: String, customer: &Customer) {
+        if let Some(cache) = self.lookup_cache.as_ref() {
+            cache.insert(key, customer.id).await;
+        }
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<Customer>, AppError> {
+        let customer = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, party_type, cpf_cnpj AS "cpf_cnpj!", full_name, normalized_name, sex,
+                   birth_date, mother_name, father_name, rg, fantasy_name,
+                   normalized_fantasy_name, opening_date, registration_status_date,
+                   company_type, company_size, enriched, uf, created_at AS "created_at!",
+                   updated_at
+            FROM core.parties
+            WHERE id = $1 AND party_type = 'person'
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .timed("customers.find_by_id")
+        .await?;
+
+        Ok(customer)
+    }
+
     async fn find_by_cpf(&self, cpf: &str) -> Result<Option<Customer>, AppError> {
-        let customer = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM core.parties WHERE cpf_cnpj = $1 AND party_type = 'person' LIMIT 1",
+        let customer = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, party_type, cpf_cnpj AS "cpf_cnpj!", full_name, normalized_name, sex,
+                   birth_date, mother_name, father_name, rg, fantasy_name,
+                   normalized_fantasy_name, opening_date, registration_status_date,
+                   company_type, company_size, enriched, uf, created_at AS "created_at!",
+                   updated_at
+            FROM core.parties
+            WHERE cpf_cnpj = $1 AND party_type = 'person'
+            LIMIT 1
+            "#,
+            cpf
         )
-        .bind(cpf)
         .fetch_optional(&self.pool)
+        .timed("customers.find_by_cpf")
         .await?;
 
         Ok(customer)
     }
 
     async fn find_by_email(&self, email: &str) -> Result<Option<Customer>, AppError> {
-        let result = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM core.parties p
-             WHERE p.party_type = 'person'
-               AND p.id IN (
-                 SELECT pc.party_id FROM core.party_contacts pc
-                 WHERE pc.contact_type::text = 'email' AND pc.value = $1
-               )
-             LIMIT 1",
+        let result = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT p.id, p.party_type, p.cpf_cnpj AS "cpf_cnpj!", p.full_name, p.normalized_name, p.sex,
+                   p.birth_date, p.mother_name, p.father_name, p.rg, p.fantasy_name,
+                   p.normalized_fantasy_name, p.opening_date, p.registration_status_date,
+                   p.company_type, p.company_size, p.enriched, p.uf, p.created_at AS "created_at!",
+                   p.updated_at
+            FROM core.parties p
+            WHERE p.party_type = 'person'
+              AND p.id IN (
+                SELECT pc.party_id FROM core.party_contacts pc
+                WHERE pc.value = $1 AND pc.contact_type = 'email'
+              )
+            LIMIT 1
+            "#,
+            email.to_lowercase()
         )
-        .bind(email.to_lowercase())
         .fetch_optional(&self.pool)
+        .timed("customers.find_by_email")
         .await
         .map_err(|e| {
             tracing::error!("Database error in find_by_email for '{}': {:?}", email, e);
@@ -189,29 +480,86 @@ impl CustomerService {
     async fn find_by_phone(&self, phone: &str) -> Result<Option<Customer>, AppError> {
         // Normalize to digits for matching stored values
         let normalized: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
-        let result = sqlx::query_as::<_, Customer>(
-            "SELECT p.* FROM core.parties p
-             INNER JOIN core.party_contacts pc ON p.id = pc.party_id
-             WHERE pc.contact_type IN ('phone', 'whatsapp')
-               AND pc.value = $1
-               AND p.party_type = 'person'
-             LIMIT 1",
+        let result = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT p.id, p.party_type, p.cpf_cnpj AS "cpf_cnpj!", p.full_name, p.normalized_name, p.sex,
+                   p.birth_date, p.mother_name, p.father_name, p.rg, p.fantasy_name,
+                   p.normalized_fantasy_name, p.opening_date, p.registration_status_date,
+                   p.company_type, p.company_size, p.enriched, p.uf, p.created_at AS "created_at!",
+                   p.updated_at
+            FROM core.parties p
+            INNER JOIN core.party_contacts pc ON p.id = pc.party_id
+            WHERE pc.contact_type IN ('phone', 'whatsapp')
+              AND pc.value = $1
+              AND p.party_type = 'person'
+            LIMIT 1
+            "#,
+            normalized
         )
-        .bind(&normalized)
         .fetch_optional(&self.pool)
+        .timed("customers.find_by_phone")
         .await?;
 
         Ok(result)
     }
 
     async fn find_by_name(&self, name: &str) -> Result<Option<Customer>, AppError> {
-        let result = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM core.parties
-             WHERE LOWER(full_name) LIKE LOWER($1) AND party_type = 'person'
-             LIMIT 1",
+        let normalized = normalize_name(name);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        // normalized_name is covered by a trigram index (migration 021);
+        // closest match first when several parties contain the search term
+        let result = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, party_type, cpf_cnpj AS "cpf_cnpj!", full_name, normalized_name, sex,
+                   birth_date, mother_name, father_name, rg, fantasy_name,
+                   normalized_fantasy_name, opening_date, registration_status_date,
+                   company_type, company_size, enriched, uf, created_at AS "created_at!",
+                   updated_at
+            FROM core.parties
+            WHERE normalized_name LIKE $1 ESCAPE '\' AND party_type = 'person'
+            ORDER BY similarity(normalized_name, $2) DESC
+            LIMIT 1
+            "#,
+            format!("%{}%", escape_like(&normalized)),
+            normalized
+        )
+        .fetch_optional(&self.pool)
+        .timed("customers.find_by_name")
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Sound-alike fallback for `find_by_name` ("Luis" finds "Luiz")
+    async fn find_by_phonetic_name(&self, name: &str) -> Result<Option<Customer>, AppError> {
+        let key = phonetic_key(name);
+        if key.is_empty() {
+            return Ok(None);
+        }
+
+        let result = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, party_type, cpf_cnpj AS "cpf_cnpj!", full_name, normalized_name, sex,
+                   birth_date, mother_name, father_name, rg, fantasy_name,
+                   normalized_fantasy_name, opening_date, registration_status_date,
+                   company_type, company_size, enriched, uf, created_at AS "created_at!",
+                   updated_at
+            FROM core.parties
+            WHERE phonetic_name = $1 AND party_type = 'person'
+            ORDER BY similarity(normalized_name, $2) DESC
+            LIMIT 1
+            "#,
+            key,
+            normalize_name(name)
         )
-        .bind(format!("%{}%", name))
         .fetch_optional(&self.pool)
+        .timed("customers.find_by_phonetic_name")
         .await?;
 
         Ok(result)
@@ -222,19 +570,22 @@ impl CustomerService {
         &self,
         customer_id: &uuid::Uuid,
     ) -> Result<Vec<Email>, AppError> {
-        let contacts = sqlx::query_as::<_, PartyContact>(
+        let contacts = sqlx::query_as!(
+            PartyContact,
             r#"
             SELECT
-                contact_id, party_id, contact_type::text as contact_type,
-                value, is_primary, is_verified, is_whatsapp,
-                source, confidence::float8, valid_from, valid_to, created_at, updated_at
+                contact_id, party_id, contact_type::text AS "contact_type!",
+                value, is_primary AS "is_primary!", is_verified AS "is_verified!",
+                is_whatsapp AS "is_whatsapp!", source, confidence::float8 AS confidence,
+                valid_from, valid_to, created_at AS "created_at!", updated_at
             FROM core.party_contacts
             WHERE party_id = $1 AND contact_type = 'email'
             ORDER BY is_primary DESC, created_at ASC
             "#,
+            customer_id
         )
-        .bind(customer_id)
         .fetch_all(&self.pool)
+        .timed("customers.emails")
         .await?;
 
         let emails = contacts
@@ -254,19 +605,22 @@ impl CustomerService {
         &self,
         customer_id: &uuid::Uuid,
     ) -> Result<Vec<Phone>, AppError> {
-        let contacts = sqlx::query_as::<_, PartyContact>(
+        let contacts = sqlx::query_as!(
+            PartyContact,
             r#"
             SELECT
-                contact_id, party_id, contact_type::text as contact_type,
-                value, is_primary, is_verified, is_whatsapp,
-                source, confidence::float8, valid_from, valid_to, created_at, updated_at
+                contact_id, party_id, contact_type::text AS "contact_type!",
+                value, is_primary AS "is_primary!", is_verified AS "is_verified!",
+                is_whatsapp AS "is_whatsapp!", source, confidence::float8 AS confidence,
+                valid_from, valid_to, created_at AS "created_at!", updated_at
             FROM core.party_contacts
             WHERE party_id = $1 AND contact_type IN ('phone', 'whatsapp')
             ORDER BY is_primary DESC, created_at ASC
             "#,
+            customer_id
         )
-        .bind(customer_id)
         .fetch_all(&self.pool)
+        .timed("customers.phones")
         .await?;
 
         let phones = contacts
@@ -284,15 +638,57 @@ impl CustomerService {
 }
 
 pub struct EnrichmentService {
-    work_api: WorkApiService,
+    work_api: Arc<dyn WorkApi>,
     customer_service: CustomerService,
+    work_api_cache: Option<Cache<String, String>>,
 }
 
 impl EnrichmentService {
-    pub fn new(config: &Config, pool: PgPool) -> Self {
+    pub fn new(work_api: Arc<dyn WorkApi>, pool: PgPool) -> Self {
         Self {
-            work_api: WorkApiService::new(config),
+            work_api,
             customer_service: CustomerService::new(pool),
+            work_api_cache: None,
+        }
+    }
+
+    /// Share the identifier → party_id lookup cache with the customer service
+    pub fn with_lookup_cache(mut self, cache: Cache<String, Uuid>) -> Self {
+        self.customer_service = self.customer_service.with_lookup_cache(cache);
+        self
+    }
+
+    /// Share the Work API response cache (same `all:{documento}` entries as
+    /// the `/work/modules/all` endpoint), used as fallback in degraded mode
+    pub fn with_work_api_cache(mut self, cache: Cache<String, String>) -> Self {
+        self.work_api_cache = Some(cache);
+        self
+    }
+
+    /// Cached Work API response for a document (with its age), if present and valid
+    ///
+    /// Only read once the Work API failed: any entry up to the TTL bound is served.
+    async fn cached_work_data(
+        &self,
+        documento: &str,
+    ) -> Option<(WorkApiCompleteResponse, Option<i64>)> {
+        let cache = self.work_api_cache.as_ref()?;
+        let entry = crate::cache_ttl::get_stale(cache, &format!("all:{}", documento)).await?;
+        let work_data = serde_json::from_str(&entry.data).ok()?;
+        Some((work_data, Some(entry.age_secs())))
+    }
+
+    async fn cache_work_data(&self, documento: &str, work_data: &WorkApiCompleteResponse) {
+        let Some(cache) = self.work_api_cache.as_ref() else {
+            return;
+        };
+        if let Ok(json_str) = serde_json::to_string(work_data) {
+            cache
+                .insert(
+                    format!("all:{}", documento),
+                    ValidatedCacheEntry::new(json_str).serialize(),
+                )
+                .await;
         }
     }
 
@@ -305,9 +701,8 @@ impl EnrichmentService {
         let mut sources = Vec::new();
 
         // Try to find customer in local database first
-        if let Some(customer) = self.customer_service.find_customer(params).await? {
-            sources.push("local_db".to_string());
-
+        let started = Instant::now();
+        if let Some((customer, matched_by)) = self.customer_service.find_customer(params).await? {
             let emails = self
                 .customer_service
                 .get_customer_emails(&customer.id)
@@ -316,15 +711,24 @@ impl EnrichmentService {
                 .customer_service
                 .get_customer_phones(&customer.id)
                 .await?;
+            let last_written = customer.updated_at.unwrap_or(customer.created_at);
+            sources.push(SourceDetail::new(
+                "local_db",
+                false,
+                started,
+                Some((Utc::now() - last_written).num_seconds().max(0)),
+            ));
 
             // If customer exists but not enriched, enrich via Work API
             if !customer.enriched.unwrap_or(false) {
                 let cpf = customer.cpf_cnpj.clone();
+                let started = Instant::now();
                 match self.work_api.fetch_all_modules(&cpf).await {
                     Ok(work_data) => {
-                        sources.push("work_api".to_string());
+                        self.cache_work_data(&cpf, &work_data).await;
+                        sources.push(SourceDetail::new("work_api", false, started, Some(0)));
                         return Ok(self.build_unified_response(
-                            Some(customer),
+                            Some((customer, matched_by)),
                             emails,
                             phones,
                             Some(work_data),
@@ -332,6 +736,24 @@ impl EnrichmentService {
                             sources,
                         ));
                     }
+                    Err(e) if e.is_provider_unavailable() => {
+                        tracing::warn!("Work API unavailable, serving degraded response: {}", e);
+                        let started = Instant::now();
+                        let cached = self.cached_work_data(&cpf).await.map(|(work_data, age)| {
+                            sources.push(SourceDetail::new("cache", true, started, age));
+                            work_data
+                        });
+                        let mut response = self.build_unified_response(
+                            Some((customer, matched_by)),
+                            emails,
+                            phones,
+                            cached,
+                            &mut modules_consulted,
+                            sources,
+                        );
+                        response.metadata.degraded = true;
+                        return Ok(response);
+                    }
                     Err(e) => {
                         tracing::warn!("Failed to fetch Work API data: {:?}", e);
                     }
@@ -340,7 +762,7 @@ impl EnrichmentService {
 
             // Return data from database only
             return Ok(self.build_unified_response(
-                Some(customer),
+                Some((customer, matched_by)),
                 emails,
                 phones,
                 None,
@@ -357,9 +779,11 @@ impl EnrichmentService {
             .or(params.phone.as_ref())
             .ok_or_else(|| AppError::BadRequest("At least one identifier required".to_string()))?;
 
+        let started = Instant::now();
         match self.work_api.fetch_all_modules(documento).await {
             Ok(work_data) => {
-                sources.push("work_api".to_string());
+                self.cache_work_data(documento, &work_data).await;
+                sources.push(SourceDetail::new("work_api", false, started, Some(0)));
                 Ok(self.build_unified_response(
                     None,
                     vec![],
@@ -369,6 +793,26 @@ impl EnrichmentService {
                     sources,
                 ))
             }
+            Err(e) if e.is_provider_unavailable() => {
+                // Nothing in the DB: the cache is the only fallback left
+                let started = Instant::now();
+                let Some((cached, age)) = self.cached_work_data(documento).await else {
+                    tracing::warn!("Work API unavailable and no cached data for customer");
+                    return Err(e);
+                };
+                tracing::warn!("Work API unavailable, serving cached data: {}", e);
+                sources.push(SourceDetail::new("cache", true, started, age));
+                let mut response = self.build_unified_response(
+                    None,
+                    vec![],
+                    vec![],
+                    Some(cached),
+                    &mut modules_consulted,
+                    sources,
+                );
+                response.metadata.degraded = true;
+                Ok(response)
+            }
             Err(e) => {
                 tracing::error!("Failed to fetch Work API data for new customer: {:?}", e);
                 Err(AppError::NotFound(
@@ -381,16 +825,17 @@ impl EnrichmentService {
     /// Build unified response from various data sources
     fn build_unified_response(
         &self,
-        customer: Option<Customer>,
+        matched: Option<(Customer, MatchKind)>,
         emails: Vec<Email>,
         phones: Vec<Phone>,
         work_data: Option<WorkApiCompleteResponse>,
         modules_consulted: &mut Vec<String>,
-        sources: Vec<String>,
+        source_details: Vec<SourceDetail>,
     ) -> UnifiedCustomerResponse {
         let mut unified_emails = Vec::new();
         let mut unified_phones = Vec::new();
         let mut unified_addresses = Vec::new();
+        let (customer, matched_by) = matched.unzip();
 
         // Process database data
         if let Some(ref _cust) = customer {
@@ -442,6 +887,12 @@ impl EnrichmentService {
             self.extract_addresses(work, &mut unified_addresses);
         }
 
+        let timestamp = Utc::now().to_rfc3339();
+        let interests = work_data.as_ref().and_then(|work| {
+            let customer_id = personal_info.cpf.as_deref().unwrap_or_default();
+            LookupInterests::from_work_data(work, customer_id, &timestamp)
+        });
+
         UnifiedCustomerResponse {
             source: "rust-c2s-api".to_string(),
             type_: "customer".to_string(),
@@ -451,14 +902,21 @@ impl EnrichmentService {
                 phones: unified_phones,
             },
             addresses: unified_addresses,
-            financial_info: None,
-            interests: None,
+            financial_info: work_data
+                .as_ref()
+                .and_then(WorkEconomicData::from_work_data)
+                .and_then(|economic| economic.financial_info()),
+            interests,
             wealth_assessment: None,
             metadata: ResponseMetadata {
                 enriched: work_data.is_some(),
-                sources,
-                timestamp: Utc::now().to_rfc3339(),
+                sources: source_details.iter().map(|d| d.source.clone()).collect(),
+                timestamp,
                 modules_consulted: modules_consulted.clone(),
+                degraded: false,
+                matched_by,
+                match_confidence: matched_by.map(MatchKind::confidence),
+                source_details,
             },
         }
     }
@@ -645,124 +1103,297 @@ pub struct C2SMessagePayload {
     pub body: String,
 }
 
+/// C2S integration API client (the only one; `gateway_client` is a shim over it)
+#[derive(Clone)]
 pub struct C2SService {
     client: Client,
     base_url: String,
     token: String,
 }
 
+/// Timeout of every C2S request
+const C2S_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Sellers requested per page of `GET /integration/sellers`
+const SELLERS_PAGE_SIZE: usize = 100;
+
+/// Page limit of a seller listing (10k sellers)
+const SELLERS_MAX_PAGES: usize = 100;
+
+/// Leads returned per contact by a lead search (the newest ones)
+const LEAD_SEARCH_PAGE_SIZE: &str = "10";
+
 impl C2SService {
     pub fn new(config: &Config) -> Self {
-        Self {
-            client: Client::new(),
+        Self::with_credentials(config.c2s_base_url.clone(), config.c2s_token.clone())
+    }
+
+    /// Client of the default account, failing instead of falling back to a
+    /// client without timeout (startup, see `C2S_STARTUP_POLICY`)
+    pub fn try_new(config: &Config) -> Result<Self, AppError> {
+        let client = Client::builder()
+            .timeout(C2S_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Failed to build C2S client: {}", e)))?;
+        Ok(Self {
+            client,
             base_url: config.c2s_base_url.clone(),
             token: config.c2s_token.clone(),
+        })
+    }
+
+    /// Client for an additional C2S account (see `c2s_accounts`)
+    pub fn with_credentials(base_url: String, token: String) -> Self {
+        let client = Client::builder()
+            .timeout(C2S_TIMEOUT)
+            .build()
+            .unwrap_or_else(|e| {
+                tracing::warn!("C2S client without timeout ({})", e);
+                Client::new()
+            });
+        Self {
+            client,
+            base_url,
+            token,
         }
     }
 
     /// Fetch lead data from C2S by lead ID
-    #[allow(dead_code)]
     pub async fn fetch_lead(&self, lead_id: &str) -> Result<C2SLeadResponse, AppError> {
-        let url = format!("{}/integration/leads/{}", self.base_url, lead_id);
+        let response = self.fetch_lead_json(lead_id).await?;
+        serde_json::from_value(response)
+            .map_err(|e| AppError::ExternalApiError(format!("Failed to parse C2S response: {}", e)))
+    }
 
-        tracing::info!("Fetching C2S lead: {}", lead_id);
+    /// Fetch a lead as returned by C2S (all attributes, untyped)
+    pub async fn fetch_lead_json(&self, lead_id: &str) -> Result<Value, AppError> {
+        circuit_breaker::guarded(Provider::C2s, async {
+            let url = format!("{}/integration/leads/{}", self.base_url, lead_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalApiError(format!("C2S request failed: {}", e)))?;
+            tracing::info!("Fetching C2S lead: {}", lead_id);
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
+            let response = self
+                .client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .send()
                 .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S API returned status {}: {}",
-                status, error_text
-            )));
-        }
+                .map_err(|e| AppError::ExternalApiError(format!("C2S request failed: {}", e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "C2S API returned status {}: {}",
+                    status, error_text
+                )));
+            }
 
-        let lead_data: C2SLeadResponse = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse C2S response: {}", e))
-        })?;
+            let lead_data: Value = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to parse C2S response: {}", e))
+            })?;
 
-        tracing::info!("Successfully fetched C2S lead: {}", lead_id);
-        Ok(lead_data)
+            tracing::info!("Successfully fetched C2S lead: {}", lead_id);
+            Ok(lead_data)
+        })
+        .await
     }
 
-    /// Send enriched data back to C2S as a message
-    pub async fn send_message(&self, lead_id: &str, body: &str) -> Result<(), AppError> {
-        let url = format!(
-            "{}/integration/leads/{}/create_message",
-            self.base_url, lead_id
-        );
+    /// All sellers of the account (`GET /integration/sellers`, every page)
+    pub async fn list_sellers(&self) -> Result<Vec<C2SSeller>, AppError> {
+        circuit_breaker::guarded(Provider::C2s, async {
+            let mut sellers = Vec::new();
+            for page in 1..=SELLERS_MAX_PAGES {
+                let url = format!(
+                    "{}/integration/sellers?page={}&perpage={}",
+                    self.base_url, page, SELLERS_PAGE_SIZE
+                );
+                let response = self
+                    .client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {}", self.token))
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        AppError::ExternalApiError(format!("C2S list sellers failed: {}", e))
+                    })?;
+
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let error_text = response
+                        .text()
+                        .await
+                        .unwrap_or_else(|_| "Unknown error".to_string());
+                    return Err(AppError::ExternalApiError(format!(
+                        "C2S API returned status {}: {}",
+                        status, error_text
+                    )));
+                }
 
-        let payload = C2SMessagePayload {
-            lead_id: lead_id.to_string(),
-            body: body.to_string(),
-        };
+                let body: Value = response.json().await.map_err(|e| {
+                    AppError::ExternalApiError(format!("Failed to parse C2S sellers: {}", e))
+                })?;
+                let batch = c2s_sellers::parse_sellers(&body);
+                let last_page = batch.len() < SELLERS_PAGE_SIZE;
+                sellers.extend(batch);
+                if last_page {
+                    break;
+                }
+            }
+            Ok(sellers)
+        })
+        .await
+    }
 
-        tracing::info!("Sending enriched data to C2S for lead: {}", lead_id);
+    /// Leads with this phone or email (`GET /integration/leads`, newest first)
+    ///
+    /// One search per contact; a lead found by both is listed once.
+    pub async fn find_leads(
+        &self,
+        phone: Option<&str>,
+        email: Option<&str>,
+    ) -> Result<Vec<lead_dedup::C2sLeadMatch>, AppError> {
+        circuit_breaker::guarded(Provider::C2s, async {
+            let url = format!("{}/integration/leads", self.base_url);
+            let mut leads: Vec<lead_dedup::C2sLeadMatch> = Vec::new();
+            let filters = [("phone", phone), ("email", email)];
+            for (filter, value) in filters {
+                let Some(value) = value else { continue };
+                let response = self
+                    .client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {}", self.token))
+                    .query(&[
+                        (filter, value),
+                        ("sort", "-created_at"),
+                        ("perpage", LEAD_SEARCH_PAGE_SIZE),
+                    ])
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        AppError::ExternalApiError(format!("C2S lead search failed: {}", e))
+                    })?;
+
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let error_text = response
+                        .text()
+                        .await
+                        .unwrap_or_else(|_| "Unknown error".to_string());
+                    return Err(AppError::ExternalApiError(format!(
+                        "C2S API returned status {}: {}",
+                        status, error_text
+                    )));
+                }
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalApiError(format!("C2S send message failed: {}", e)))?;
+                let body: Value = response.json().await.map_err(|e| {
+                    AppError::ExternalApiError(format!("Failed to parse C2S leads: {}", e))
+                })?;
+                for lead in lead_dedup::parse_lead_matches(&body) {
+                    if !leads.iter().any(|l| l.id == lead.id) {
+                        leads.push(lead);
+                    }
+                }
+            }
+            Ok(leads)
+        })
+        .await
+    }
 
-        if response.status().as_u16() != 201 {
-            let status = response.status();
-            let error_text = response
-                .text()
+    /// Send enriched data back to C2S as a message, returns the HTTP status (201)
+    ///
+    /// Any 2xx is accepted (the former gateway client did the same).
+    pub async fn send_message(&self, lead_id: &str, body: &str) -> Result<u16, AppError> {
+        rate_limit::c2s_limiter().acquire(lead_id).await?;
+        circuit_breaker::guarded(Provider::C2s, async {
+            let url = format!(
+                "{}/integration/leads/{}/create_message",
+                self.base_url, lead_id
+            );
+
+            let payload = C2SMessagePayload {
+                lead_id: lead_id.to_string(),
+                body: body.to_string(),
+            };
+
+            tracing::info!("Sending enriched data to C2S for lead: {}", lead_id);
+
+            let response = self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+                .send()
                 .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S API returned status {} (expected 201): {}",
-                status, error_text
-            )));
-        }
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("C2S send message failed: {}", e))
+                })?;
 
-        tracing::info!("Successfully sent message to C2S for lead: {}", lead_id);
-        Ok(())
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "C2S API returned status {}: {}",
+                    status, error_text
+                )));
+            }
+
+            tracing::info!("Successfully sent message to C2S for lead: {}", lead_id);
+            Ok(response.status().as_u16())
+        })
+        .await
     }
 
     /// Resolve Google Ads lead source to get ad group name for product field
     /// Calls ibvi-ads-gateway /v1/leads/resolve-source endpoint
-    pub async fn resolve_lead_source(&self, google_lead_id: &str) -> Result<Option<String>, AppError> {
+    pub async fn resolve_lead_source(
+        &self,
+        google_lead_id: &str,
+    ) -> Result<Option<String>, AppError> {
         let gateway_url = std::env::var("C2S_GATEWAY_URL")
             .unwrap_or_else(|_| "https://mbras-c2s-gateway.fly.dev".to_string());
-        
-        let url = format!("{}/leads/resolve-source?google_lead_id={}", gateway_url, google_lead_id);
-        
-        tracing::info!("Resolving lead source for google_lead_id: {}", google_lead_id);
-        
+
+        let url = format!(
+            "{}/leads/resolve-source?google_lead_id={}",
+            gateway_url, google_lead_id
+        );
+
+        tracing::info!(
+            "Resolving lead source for google_lead_id: {}",
+            google_lead_id
+        );
+
         let response = self
             .client
             .get(&url)
             .timeout(std::time::Duration::from_secs(10))
             .send()
             .await;
-        
+
         match response {
             Ok(resp) if resp.status().is_success() => {
                 let data: serde_json::Value = resp.json().await.map_err(|e| {
-                    AppError::ExternalApiError(format!("Failed to parse resolve-source response: {}", e))
+                    AppError::ExternalApiError(format!(
+                        "Failed to parse resolve-source response: {}",
+                        e
+                    ))
                 })?;
-                
+
                 // Extract product_description from response
-                if let Some(product_desc) = data.get("product_description").and_then(|v| v.as_str()) {
+                if let Some(product_desc) = data.get("product_description").and_then(|v| v.as_str())
+                {
                     tracing::info!("✅ Resolved product: {}", product_desc);
                     Ok(Some(product_desc.to_string()))
-                } else if let Some(ad_group_name) = data.get("ad_group_name").and_then(|v| v.as_str()) {
+                } else if let Some(ad_group_name) =
+                    data.get("ad_group_name").and_then(|v| v.as_str())
+                {
                     tracing::info!("✅ Resolved ad_group_name: {}", ad_group_name);
                     Ok(Some(ad_group_name.to_string()))
                 } else {
@@ -784,6 +1415,7 @@ impl C2SService {
     }
 
     /// Create a new lead in C2S
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_lead(
         &self,
         customer_name: &str,
@@ -794,93 +1426,153 @@ impl C2SService {
         product: Option<&str>,
         seller_id: Option<&str>,
     ) -> Result<String, AppError> {
-        let url = format!("{}/integration/leads", self.base_url);
-
-        // Build attributes using JSON:API format
-        let mut attributes = serde_json::Map::new();
-        attributes.insert("name".to_string(), json!(customer_name));
-        attributes.insert("description".to_string(), json!(description));
-        attributes.insert("type_negotiation".to_string(), json!("Compra"));
-        attributes.insert("source".to_string(), json!(source.unwrap_or("Google Ads")));
-
-        if let Some(phone_val) = phone {
-            attributes.insert("phone".to_string(), json!(phone_val));
-        }
-        if let Some(email_val) = email {
-            attributes.insert("email".to_string(), json!(email_val));
-        }
-        if let Some(product_val) = product {
-            attributes.insert("product".to_string(), json!(product_val));
-        }
-        if let Some(seller_val) = seller_id {
-            attributes.insert("seller_id".to_string(), json!(seller_val));
-        }
-
-        let payload = json!({
-            "data": {
-                "type": "lead",
-                "attributes": attributes
+        circuit_breaker::guarded(Provider::C2s, async {
+            let url = format!("{}/integration/leads", self.base_url);
+
+            // Build attributes using JSON:API format
+            let mut attributes = serde_json::Map::new();
+            attributes.insert("name".to_string(), json!(customer_name));
+            attributes.insert("description".to_string(), json!(description));
+            attributes.insert("type_negotiation".to_string(), json!("Compra"));
+            attributes.insert("source".to_string(), json!(source.unwrap_or("Google Ads")));
+
+            if let Some(phone_val) = phone {
+                attributes.insert("phone".to_string(), json!(phone_val));
+            }
+            if let Some(email_val) = email {
+                attributes.insert("email".to_string(), json!(email_val));
+            }
+            if let Some(product_val) = product {
+                attributes.insert("product".to_string(), json!(product_val));
+            }
+            if let Some(seller_val) = seller_id {
+                attributes.insert("seller_id".to_string(), json!(seller_val));
             }
-        });
 
-        tracing::info!("Creating new lead in C2S: {}", customer_name);
+            let payload = json!({
+                "data": {
+                    "type": "lead",
+                    "attributes": attributes
+                }
+            });
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalApiError(format!("C2S create lead failed: {}", e)))?;
+            tracing::info!("Creating new lead in C2S: {}", customer_name);
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
+            let response = self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+                .send()
                 .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "C2S API create lead failed {}: {}",
-                status, error_text
-            )));
-        }
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("C2S create lead failed: {}", e))
+                })?;
 
-        let response_data: serde_json::Value = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse C2S create lead response: {}", e))
-        })?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "C2S API create lead failed {}: {}",
+                    status, error_text
+                )));
+            }
 
-        // Try to get ID from different possible locations in response
-        let lead_id = if let Some(id) = response_data
-            .get("data")
-            .and_then(|d| d.get("id"))
-            .and_then(|i| i.as_str())
-        {
-            id.to_string()
-        } else if let Some(id) = response_data.get("id").and_then(|i| i.as_str()) {
-            id.to_string()
-        } else if let Some(id) = response_data.get("lead_id").and_then(|i| i.as_str()) {
-            id.to_string()
-        } else {
-            // Try numeric IDs converted to string
-            if let Some(id) = response_data
+            let response_data: serde_json::Value = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!(
+                    "Failed to parse C2S create lead response: {}",
+                    e
+                ))
+            })?;
+
+            // Try to get ID from different possible locations in response
+            let lead_id = if let Some(id) = response_data
                 .get("data")
                 .and_then(|d| d.get("id"))
-                .and_then(|i| i.as_i64())
+                .and_then(|i| i.as_str())
             {
                 id.to_string()
-            } else if let Some(id) = response_data.get("id").and_then(|i| i.as_i64()) {
+            } else if let Some(id) = response_data.get("id").and_then(|i| i.as_str()) {
+                id.to_string()
+            } else if let Some(id) = response_data.get("lead_id").and_then(|i| i.as_str()) {
                 id.to_string()
             } else {
-                return Err(AppError::ExternalApiError(
-                    "C2S response missing 'id', 'data.id' or 'lead_id' field".to_string(),
-                ));
+                // Try numeric IDs converted to string
+                if let Some(id) = response_data
+                    .get("data")
+                    .and_then(|d| d.get("id"))
+                    .and_then(|i| i.as_i64())
+                {
+                    id.to_string()
+                } else if let Some(id) = response_data.get("id").and_then(|i| i.as_i64()) {
+                    id.to_string()
+                } else {
+                    return Err(AppError::ExternalApiError(
+                        "C2S response missing 'id', 'data.id' or 'lead_id' field".to_string(),
+                    ));
+                }
+            };
+
+            tracing::info!("✅ Created lead in C2S: {}", lead_id);
+            Ok(lead_id)
+        })
+        .await
+    }
+
+    /// Update custom fields of a lead in C2S (JSON:API PATCH on the lead)
+    pub async fn update_custom_fields(
+        &self,
+        lead_id: &str,
+        fields: &serde_json::Map<String, Value>,
+    ) -> Result<(), AppError> {
+        circuit_breaker::guarded(Provider::C2s, async {
+            let url = format!("{}/integration/leads/{}", self.base_url, lead_id);
+            tracing::info!(
+                "Updating {} custom field(s) of lead {} in C2S",
+                fields.len(),
+                lead_id
+            );
+
+            let body = json!({
+                "data": {
+                    "type": "lead",
+                    "id": lead_id,
+                    "attributes": {
+                        "custom_fields": fields
+                    }
+                }
+            });
+
+            let response = self
+                .client
+                .patch(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("Failed to update custom fields: {}", e))
+                })?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "C2S custom fields update failed {}: {}",
+                    status, error_text
+                )));
             }
-        };
 
-        tracing::info!("✅ Created lead in C2S: {}", lead_id);
-        Ok(lead_id)
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -944,15 +1636,27 @@ pub struct DiretrixService {
     base_url: String,
     username: String,
     password: String,
+    /// Limits of the account, shared with other clients using it
+    limiter: Arc<rate_limit::ProviderLimiter>,
 }
 
 impl DiretrixService {
     pub fn new(config: &Config) -> Self {
+        Self::with_credentials(
+            config.diretrix_base_url.clone(),
+            config.diretrix_user.clone(),
+            config.diretrix_pass.clone(),
+        )
+    }
+
+    /// Client for a tenant's own Diretrix contract (see `tenant_credentials`)
+    pub fn with_credentials(base_url: String, username: String, password: String) -> Self {
         Self {
             client: Client::new(),
-            base_url: config.diretrix_base_url.clone(),
-            username: config.diretrix_user.clone(),
-            password: config.diretrix_pass.clone(),
+            base_url,
+            limiter: rate_limit::diretrix_limiter(&username),
+            username,
+            password,
         }
     }
 
@@ -961,56 +1665,63 @@ impl DiretrixService {
         &self,
         phone: &str,
     ) -> Result<Vec<DiretrixPersonSearch>, AppError> {
-        // Remove 55 prefix if present (Diretrix expects phone without country code)
-        let phone_clean = if phone.starts_with("55") && phone.len() > 2 {
-            &phone[2..]
-        } else {
-            phone
-        };
-
-        let url = format!(
-            "{}/Consultas/Pessoa/Telefone/{}",
-            self.base_url, phone_clean
-        );
+        let _slot = self.limiter.acquire().await?;
+        circuit_breaker::guarded(Provider::Diretrix, async {
+            // Remove 55 prefix if present (Diretrix expects phone without country code)
+            let phone_clean = if phone.starts_with("55") && phone.len() > 2 {
+                &phone[2..]
+            } else {
+                phone
+            };
+
+            let url = format!(
+                "{}/Consultas/Pessoa/Telefone/{}",
+                self.base_url, phone_clean
+            );
+
+            tracing::info!(
+                "Diretrix: Searching by phone: {} (cleaned: {})",
+                phone,
+                phone_clean
+            );
+
+            let response = self
+                .client
+                .get(&url)
+                .basic_auth(&self.username, Some(&self.password))
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("Diretrix phone search failed: {}", e))
+                })?;
 
-        tracing::info!(
-            "Diretrix: Searching by phone: {} (cleaned: {})",
-            phone,
-            phone_clean
-        );
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "Diretrix API returned status {}: {}",
+                    status, error_text
+                )));
+            }
 
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.username, Some(&self.password))
-            .send()
-            .await
-            .map_err(|e| {
-                AppError::ExternalApiError(format!("Diretrix phone search failed: {}", e))
+            let results: Vec<DiretrixPersonSearch> = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!(
+                    "Failed to parse Diretrix phone response: {}",
+                    e
+                ))
             })?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "Diretrix API returned status {}: {}",
-                status, error_text
-            )));
-        }
-
-        let results: Vec<DiretrixPersonSearch> = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse Diretrix phone response: {}", e))
-        })?;
-
-        tracing::info!(
-            "Diretrix: Found {} matches for phone {}",
-            results.len(),
-            phone
-        );
-        Ok(results)
+            tracing::info!(
+                "Diretrix: Found {} matches for phone {}",
+                results.len(),
+                phone
+            );
+            Ok(results)
+        })
+        .await
     }
 
     /// Search person by email - returns list of possible matches
@@ -1018,78 +1729,88 @@ impl DiretrixService {
         &self,
         email: &str,
     ) -> Result<Vec<DiretrixPersonSearch>, AppError> {
-        let url = format!("{}/Consultas/Pessoa/Email/{}", self.base_url, email);
+        let _slot = self.limiter.acquire().await?;
+        circuit_breaker::guarded(Provider::Diretrix, async {
+            let url = format!("{}/Consultas/Pessoa/Email/{}", self.base_url, email);
 
-        tracing::info!("Diretrix: Searching by email: {}", email);
+            tracing::info!("Diretrix: Searching by email: {}", email);
 
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.username, Some(&self.password))
-            .send()
-            .await
-            .map_err(|e| {
-                AppError::ExternalApiError(format!("Diretrix email search failed: {}", e))
-            })?;
+            let response = self
+                .client
+                .get(&url)
+                .basic_auth(&self.username, Some(&self.password))
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("Diretrix email search failed: {}", e))
+                })?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::ExternalApiError(format!(
-                "Diretrix API returned status {}",
-                status
-            )));
-        }
+            if !response.status().is_success() {
+                let status = response.status();
+                return Err(AppError::ExternalApiError(format!(
+                    "Diretrix API returned status {}",
+                    status
+                )));
+            }
 
-        let results: Vec<DiretrixPersonSearch> = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse Diretrix email response: {}", e))
-        })?;
+            let results: Vec<DiretrixPersonSearch> = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!(
+                    "Failed to parse Diretrix email response: {}",
+                    e
+                ))
+            })?;
 
-        tracing::info!(
-            "Diretrix: Found {} matches for email {}",
-            results.len(),
-            email
-        );
-        Ok(results)
+            tracing::info!(
+                "Diretrix: Found {} matches for email {}",
+                results.len(),
+                email
+            );
+            Ok(results)
+        })
+        .await
     }
 
     /// Get full person data by CPF
-    #[allow(dead_code)]
     pub async fn get_person_by_cpf(&self, cpf: &str) -> Result<DiretrixPersonData, AppError> {
-        let url = format!("{}/Consultas/Pessoa/{}", self.base_url, cpf);
+        let _slot = self.limiter.acquire().await?;
+        circuit_breaker::guarded(Provider::Diretrix, async {
+            let url = format!("{}/Consultas/Pessoa/{}", self.base_url, cpf);
 
-        tracing::info!("Diretrix: Getting person data for CPF: {}", cpf);
-
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.username, Some(&self.password))
-            .send()
-            .await
-            .map_err(|e| {
-                AppError::ExternalApiError(format!("Diretrix CPF lookup failed: {}", e))
-            })?;
+            tracing::info!("Diretrix: Getting person data for CPF: {}", cpf);
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
+            let response = self
+                .client
+                .get(&url)
+                .basic_auth(&self.username, Some(&self.password))
+                .send()
                 .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::ExternalApiError(format!(
-                "Diretrix API returned status {}: {}",
-                status, error_text
-            )));
-        }
+                .map_err(|e| {
+                    AppError::ExternalApiError(format!("Diretrix CPF lookup failed: {}", e))
+                })?;
 
-        let person_data: DiretrixPersonData = response.json().await.map_err(|e| {
-            AppError::ExternalApiError(format!("Failed to parse Diretrix person data: {}", e))
-        })?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AppError::ExternalApiError(format!(
+                    "Diretrix API returned status {}: {}",
+                    status, error_text
+                )));
+            }
 
-        tracing::info!(
-            "Diretrix: Successfully retrieved data for {}",
-            person_data.nome
-        );
-        Ok(person_data)
+            let person_data: DiretrixPersonData = response.json().await.map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to parse Diretrix person data: {}", e))
+            })?;
+
+            tracing::info!(
+                "Diretrix: Successfully retrieved data for {}",
+                person_data.nome
+            );
+            Ok(person_data)
+        })
+        .await
     }
 
     /// Enrich person data - search by phone/email, then get full data by CPF
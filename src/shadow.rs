@@ -0,0 +1,400 @@
+//! Shadow-provider comparison
+//!
+//! Before switching data vendors we want to see how a candidate performs on
+//! real traffic. For a sample of leads (`SHADOW_SAMPLE_RATE`, picked by a hash
+//! of the lead ID so retries of a lead stay in or out of the sample) the
+//! candidate at `SHADOW_PROVIDER_URL` is called in parallel with Work API.
+//! Once both answered, the identity and economic fields are diffed and the
+//! result is stored in `shadow_comparisons` (migration 031).
+//!
+//! Shadow data never reaches sellers: the workflow does not wait for the
+//! shadow call and failures are only recorded. The endpoint receives
+//! `GET {SHADOW_PROVIDER_URL}?cpf={cpf}` (bearer `SHADOW_PROVIDER_TOKEN`) and
+//! must answer in the Work API `modulo=cpf` shape (vendor adapters map their
+//! payload onto it).
+
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use crate::services::normalize_name;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Fields compared across providers: (name, JSON pointer, kind)
+const COMPARED_FIELDS: &[(&str, &str, FieldKind)] = &[
+    ("nome", "/DadosBasicos/nome", FieldKind::Name),
+    (
+        "data_nascimento",
+        "/DadosBasicos/dataNascimento",
+        FieldKind::Text,
+    ),
+    ("nome_mae", "/DadosBasicos/nomeMae", FieldKind::Name),
+    ("renda", "/DadosEconomicos/renda", FieldKind::Number),
+    (
+        "score",
+        "/DadosEconomicos/score/scoreCSBA",
+        FieldKind::Number,
+    ),
+    (
+        "poder_aquisitivo",
+        "/DadosEconomicos/poderAquisitivo/poderAquisitivoDescricao",
+        FieldKind::Text,
+    ),
+    ("telefones", "/telefones", FieldKind::Count),
+    ("emails", "/emails", FieldKind::Count),
+    ("enderecos", "/enderecos", FieldKind::Count),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum FieldKind {
+    /// Compared after `normalize_name` (accents, case, spacing)
+    Name,
+    Text,
+    /// "1234,56" and "1234.56" are the same value
+    Number,
+    /// Array length
+    Count,
+}
+
+/// A field the providers disagree on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldMismatch {
+    pub field: &'static str,
+    pub primary: Option<String>,
+    pub shadow: Option<String>,
+}
+
+/// Outcome of diffing one CPF
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    /// Fields present in at least one of the responses
+    pub fields_compared: usize,
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+/// Whether a lead belongs to the shadow sample (stable per lead)
+pub fn is_sampled(lead_id: &str, rate: f64) -> bool {
+    if rate <= 0.0 {
+        return false;
+    }
+    let digest = Sha256::digest(lead_id.as_bytes());
+    let bucket = u16::from_be_bytes([digest[0], digest[1]]) as f64 / (u16::MAX as f64 + 1.0);
+    bucket < rate
+}
+
+fn field_value(data: &Value, pointer: &str, kind: FieldKind) -> Option<String> {
+    let value = data.pointer(pointer)?;
+    let text = match kind {
+        FieldKind::Count => return value.as_array().map(|a| a.len().to_string()),
+        _ => match value {
+            Value::String(s) => s.trim().to_string(),
+            Value::Number(n) => n.to_string(),
+            _ => return None,
+        },
+    };
+    if text.is_empty() {
+        return None;
+    }
+    Some(match kind {
+        FieldKind::Name => normalize_name(&text),
+        FieldKind::Number => text
+            .replace(',', ".")
+            .parse::<f64>()
+            .map(|n| n.to_string())
+            .unwrap_or(text),
+        _ => text.to_uppercase(),
+    })
+}
+
+/// Diff the compared fields of a primary and a shadow response
+pub fn compare(primary: &Value, shadow: &Value) -> Comparison {
+    let mut comparison = Comparison::default();
+    for (field, pointer, kind) in COMPARED_FIELDS {
+        let primary = field_value(primary, pointer, *kind);
+        let shadow = field_value(shadow, pointer, *kind);
+        if primary.is_none() && shadow.is_none() {
+            continue;
+        }
+        comparison.fields_compared += 1;
+        if primary != shadow {
+            comparison.mismatches.push(FieldMismatch {
+                field,
+                primary,
+                shadow,
+            });
+        }
+    }
+    comparison
+}
+
+struct ShadowFetch {
+    cpf: String,
+    result: Result<Value, String>,
+    elapsed_ms: u64,
+}
+
+/// Shadow calls running in the background for one lead
+pub struct ShadowRun {
+    lead_id: String,
+    handle: JoinHandle<Vec<ShadowFetch>>,
+}
+
+/// Start the shadow calls for a sampled lead (None when disabled or not sampled)
+pub fn start(config: &Config, lead_id: &str, cpfs: &[String]) -> Option<ShadowRun> {
+    let url = config.shadow_provider_url.clone()?;
+    if !is_sampled(lead_id, config.shadow_sample_rate) {
+        return None;
+    }
+    let token = config.shadow_provider_token.clone();
+    let cpfs: Vec<String> = cpfs
+        .iter()
+        .filter(|cpf| !crate::enrichment::is_cnpj(cpf))
+        .cloned()
+        .collect();
+    if cpfs.is_empty() {
+        return None;
+    }
+
+    tracing::info!("Lead {} sampled for shadow provider comparison", lead_id);
+    let handle = tokio::spawn(async move {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        let mut fetches = Vec::with_capacity(cpfs.len());
+        for cpf in cpfs {
+            let started = Instant::now();
+            let result = fetch(&client, &url, token.as_deref(), &cpf).await;
+            fetches.push(ShadowFetch {
+                cpf,
+                result,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        fetches
+    });
+
+    Some(ShadowRun {
+        lead_id: lead_id.to_string(),
+        handle,
+    })
+}
+
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    cpf: &str,
+) -> Result<Value, String> {
+    let mut request = client.get(url).query(&[("cpf", cpf)]);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| e.without_url().to_string())
+}
+
+impl ShadowRun {
+    /// Diff against the primary results once the shadow calls finish, in the background
+    ///
+    /// `primary` must be aligned with `cpfs`; CPFs without a primary response
+    /// are not compared.
+    pub fn finish(self, pool: PgPool, cpfs: &[String], primary: &[Value], primary_ms: u64) {
+        let primary: Vec<(String, Value)> = if cpfs.len() == primary.len() {
+            cpfs.iter().cloned().zip(primary.iter().cloned()).collect()
+        } else {
+            // Work API skipped a CPF: only the order is known, not which one
+            tracing::debug!(
+                "Skipping shadow comparison for lead {}: partial primary results",
+                self.lead_id
+            );
+            Vec::new()
+        };
+
+        tokio::spawn(async move {
+            let fetches = match self.handle.await {
+                Ok(fetches) => fetches,
+                Err(e) => {
+                    tracing::warn!("Shadow provider task failed: {}", e);
+                    return;
+                }
+            };
+            for fetch in fetches {
+                let Some((_, primary_data)) = primary.iter().find(|(cpf, _)| *cpf == fetch.cpf)
+                else {
+                    continue;
+                };
+                if let Err(e) = record(&pool, &self.lead_id, &fetch, primary_data, primary_ms).await
+                {
+                    tracing::warn!("{}", e);
+                }
+            }
+        });
+    }
+}
+
+async fn record(
+    pool: &PgPool,
+    lead_id: &str,
+    fetch: &ShadowFetch,
+    primary: &Value,
+    primary_ms: u64,
+) -> Result<(), AppError> {
+    let (comparison, error, payload) = match &fetch.result {
+        Ok(shadow) => (compare(primary, shadow), None, Some(shadow)),
+        Err(e) => (Comparison::default(), Some(e.as_str()), None),
+    };
+    if !comparison.mismatches.is_empty() {
+        tracing::info!(
+            "Shadow provider disagrees on {} of {} field(s) for lead {}",
+            comparison.mismatches.len(),
+            comparison.fields_compared,
+            lead_id
+        );
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO shadow_comparisons
+            (lead_id, cpf, shadow_error, primary_ms, shadow_ms, fields_compared, mismatches, shadow_payload)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(lead_id)
+    .bind(&fetch.cpf)
+    .bind(error)
+    .bind(primary_ms as i32)
+    .bind(fetch.elapsed_ms as i32)
+    .bind(comparison.fields_compared as i32)
+    .bind(serde_json::to_value(&comparison.mismatches).unwrap_or_default())
+    .bind(payload)
+    .execute(pool)
+    .timed("shadow_comparisons.insert")
+    .await
+    .context(format!("Failed to store shadow comparison for lead_id: {}", lead_id))?;
+    Ok(())
+}
+
+/// Mismatch count of one field over the summary window
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FieldMismatchCount {
+    pub field: String,
+    pub mismatches: i64,
+}
+
+/// Aggregated comparisons over the last days
+#[derive(Debug, Serialize)]
+pub struct ShadowSummary {
+    pub days: i32,
+    pub comparisons: i64,
+    pub shadow_errors: i64,
+    /// Comparisons where every compared field agreed
+    pub full_matches: i64,
+    pub avg_primary_ms: Option<f64>,
+    pub avg_shadow_ms: Option<f64>,
+    pub fields: Vec<FieldMismatchCount>,
+}
+
+/// Summary of the comparisons stored in the last `days` days
+pub async fn summary(pool: &PgPool, days: i32) -> Result<ShadowSummary, AppError> {
+    let (comparisons, shadow_errors, full_matches, avg_primary_ms, avg_shadow_ms): (
+        i64,
+        i64,
+        i64,
+        Option<f64>,
+        Option<f64>,
+    ) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE shadow_error IS NOT NULL),
+            COUNT(*) FILTER (WHERE shadow_error IS NULL AND mismatches = '[]'::jsonb),
+            AVG(primary_ms)::float8,
+            AVG(shadow_ms) FILTER (WHERE shadow_error IS NULL)::float8
+        FROM shadow_comparisons
+        WHERE created_at > NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(days)
+    .fetch_one(pool)
+    .timed("shadow_comparisons.summary")
+    .await
+    .context("Failed to summarize shadow comparisons")?;
+
+    let fields = sqlx::query_as::<_, FieldMismatchCount>(
+        r#"
+        SELECT m->>'field' AS field, COUNT(*) AS mismatches
+        FROM shadow_comparisons, jsonb_array_elements(mismatches) m
+        WHERE created_at > NOW() - make_interval(days => $1)
+        GROUP BY 1
+        ORDER BY 2 DESC
+        "#,
+    )
+    .bind(days)
+    .fetch_all(pool)
+    .timed("shadow_comparisons.field_mismatches")
+    .await
+    .context("Failed to count shadow field mismatches")?;
+
+    Ok(ShadowSummary {
+        days,
+        comparisons,
+        shadow_errors,
+        full_matches,
+        avg_primary_ms,
+        avg_shadow_ms,
+        fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sampling_is_stable() {
+        assert!(!is_sampled("lead-1", 0.0));
+        assert!(is_sampled("lead-1", 1.0));
+        assert_eq!(is_sampled("lead-1", 0.5), is_sampled("lead-1", 0.5));
+
+        let sampled = (0..1000)
+            .filter(|i| is_sampled(&format!("lead-{}", i), 0.1))
+            .count();
+        assert!((50..150).contains(&sampled), "sampled {}", sampled);
+    }
+
+    #[test]
+    fn test_compare() {
+        let primary = json!({
+            "DadosBasicos": {"nome": "José da Silva", "dataNascimento": "01/02/1980"},
+            "DadosEconomicos": {"renda": "5000,50"},
+            "telefones": [{}, {}]
+        });
+        let shadow = json!({
+            "DadosBasicos": {"nome": "JOSE DA SILVA", "dataNascimento": "02/02/1980"},
+            "DadosEconomicos": {"renda": 5000.5, "score": {"scoreCSBA": "700"}},
+            "telefones": [{}, {}]
+        });
+
+        let comparison = compare(&primary, &shadow);
+        assert_eq!(comparison.fields_compared, 5);
+        let fields: Vec<&str> = comparison.mismatches.iter().map(|m| m.field).collect();
+        assert_eq!(fields, vec!["data_nascimento", "score"]);
+        assert_eq!(comparison.mismatches[1].primary, None);
+    }
+}
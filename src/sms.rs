@@ -0,0 +1,136 @@
+use crate::enrichment::EnrichedLeadSummary;
+use crate::errors::AppError;
+use serde_json::json;
+use std::time::Duration;
+
+/// Supported SMS providers
+#[derive(Debug, Clone)]
+pub enum SmsProvider {
+    Twilio {
+        account_sid: String,
+        auth_token: String,
+    },
+    Zenvia {
+        api_token: String,
+    },
+}
+
+/// SMS sender for seller notifications (Twilio or Zenvia)
+#[derive(Clone)]
+pub struct SmsClient {
+    client: reqwest::Client,
+    provider: SmsProvider,
+    from: String,
+}
+
+/// Short notification text for a new high-score lead (kept close to one SMS segment)
+pub fn format_lead_alert(summary: &EnrichedLeadSummary) -> String {
+    let mut text = format!(
+        "Novo lead {}: {}",
+        summary.wealth.as_tag().trim_start_matches("patrimonio-"),
+        summary.name
+    );
+    if let Some(score) = summary.credit_score {
+        text.push_str(&format!(" | Score {}", score));
+    }
+    if let Some(phone) = &summary.phone {
+        text.push_str(&format!(" | {}", phone));
+    }
+    text.push_str(&format!(" | C2S #{}", summary.lead_id));
+    text
+}
+
+impl SmsClient {
+    pub fn new(provider: SmsProvider, from: String) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| {
+                AppError::ExternalApiError(format!("Failed to create SMS client: {}", e))
+            })?;
+
+        Ok(Self {
+            client,
+            provider,
+            from,
+        })
+    }
+
+    /// Send a text message to a phone number (E.164)
+    pub async fn send(&self, to: &str, body: &str) -> Result<(), AppError> {
+        tracing::info!("Sending SMS to {}", to);
+
+        let request = match &self.provider {
+            SmsProvider::Twilio {
+                account_sid,
+                auth_token,
+            } => self
+                .client
+                .post(format!(
+                    "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+                    account_sid
+                ))
+                .basic_auth(account_sid, Some(auth_token))
+                .form(&[("To", to), ("From", self.from.as_str()), ("Body", body)]),
+            SmsProvider::Zenvia { api_token } => self
+                .client
+                .post("https://api.zenvia.com/v2/channels/sms/messages")
+                .header("X-API-TOKEN", api_token)
+                .json(&json!({
+                    "from": self.from,
+                    // Zenvia expects digits only (country code included)
+                    "to": to.trim_start_matches('+'),
+                    "contents": [{ "type": "text", "text": body }]
+                })),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApiError(format!("SMS request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::ExternalApiError(format!(
+                "SMS provider returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrichment::WealthSegment;
+
+    #[test]
+    fn test_format_lead_alert() {
+        let summary = EnrichedLeadSummary {
+            lead_id: "abc123".to_string(),
+            name: "Maria Silva".to_string(),
+            phone: Some("+5511987654321".to_string()),
+            email: None,
+            cpf: Some("12345678901".to_string()),
+            income: Some(25000.0),
+            credit_score: Some(920),
+            wealth: WealthSegment::MuitoAlto,
+            affordability: None,
+            uf: None,
+        };
+        let text = format_lead_alert(&summary);
+
+        assert_eq!(
+            text,
+            "Novo lead muito-alto: Maria Silva | Score 920 | +5511987654321 | C2S #abc123"
+        );
+        // CPF must never be sent over SMS
+        assert!(!text.contains("12345678901"));
+    }
+}
@@ -0,0 +1,554 @@
+//! Outbound webhooks on enrichment completion
+//!
+//! External systems register a callback URL with the admin endpoints
+//! (`/api/v1/admin/subscribers`) instead of polling job or lead status.
+//! Subscribers are stored in `webhook_subscribers` (migration 050), each with
+//! its own signing secret, shown once at registration. Whenever the enrichment
+//! workflow of a lead ends, every active subscriber of the event (and of the
+//! lead's tenant, when it has one) gets a POST with:
+//!
+//! - `event`: `enrichment.completed` or `enrichment.failed`, plus `event_id`
+//!   (the same on redeliveries) and `occurred_at`,
+//! - `lead_id`, `tenant`, `status`, `party_id` of the primary enriched person,
+//! - `wealth`: segment, declared income and credit score (completed only),
+//! - `reason` of a failure (`no_cpf`, `no_data`, `consent_required`, ...).
+//!
+//! Enrichments deferred while a provider is down are not reported: they run
+//! again later. Bodies are signed like batch callbacks (`X-Signature:
+//! sha256=<hex HMAC-SHA256 of the body>`, with the subscriber's secret) and
+//! carry the event in `X-Event`. Deliveries are `subscriber_notification`
+//! jobs, retried with the queue's backoff up to `DELIVERY_ATTEMPTS` times.
+//! Subscriber URLs must resolve to public addresses, like batch callbacks
+//! (`batch_jobs::check_callback_url`), checked again before each delivery.
+
+use crate::batch_jobs::{check_callback_url, ensure_public_host, sign};
+use crate::db::TimedQuery;
+use crate::enrichment::{EnrichmentResult, NoDataReason};
+use crate::errors::{AppError, ErrorContext, ResultExt};
+use crate::handlers::AppState;
+use crate::jobs::{self, SUBSCRIBER_NOTIFICATION};
+use axum::http::header;
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// An enrichment stored its results (the message may be queued)
+pub const ENRICHMENT_COMPLETED: &str = "enrichment.completed";
+
+/// An enrichment ended without results
+pub const ENRICHMENT_FAILED: &str = "enrichment.failed";
+
+/// Events a subscriber can receive
+pub const EVENTS: &[&str] = &[ENRICHMENT_COMPLETED, ENRICHMENT_FAILED];
+
+/// Prefix of every generated signing secret
+pub const SECRET_PREFIX: &str = "whsec_";
+
+/// Random bytes of a secret (hex-encoded after the prefix)
+const SECRET_BYTES: usize = 32;
+
+/// Deliveries of an event to a subscriber before giving up
+const DELIVERY_ATTEMPTS: i32 = 6;
+
+/// Timeout of one delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries run at once per instance
+const WORKERS: usize = 2;
+
+/// Idle workers look for queued deliveries at this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval between sweeps of abandoned and old completed deliveries
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// A registered subscriber, as listed by the admin endpoint (never the secret)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Subscriber {
+    pub id: i64,
+    pub url: String,
+    /// Only events of this tenant (lead source); all tenants when unset
+    pub tenant: Option<String>,
+    pub events: Vec<String>,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+/// Body of `POST /api/v1/admin/subscribers`
+#[derive(Debug, Deserialize)]
+pub struct SubscriberCreate {
+    pub url: String,
+    /// Defaults to every event
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Payload of a `subscriber_notification` job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Delivery {
+    pub subscriber_id: i64,
+    pub event: Value,
+}
+
+/// New random secret: `whsec_` followed by 64 hex characters
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("failed to generate subscriber secret");
+    format!("{}{}", SECRET_PREFIX, hex::encode(bytes))
+}
+
+/// Requested events, deduplicated; every event when none is given
+pub fn parse_events(events: &[String]) -> Result<Vec<String>, AppError> {
+    if events.is_empty() {
+        return Ok(EVENTS.iter().map(|e| e.to_string()).collect());
+    }
+    let mut parsed = Vec::new();
+    for event in events {
+        let event = event.trim().to_lowercase();
+        if !EVENTS.contains(&event.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown event '{}' (expected one of: {})",
+                event,
+                EVENTS.join(", ")
+            )));
+        }
+        if !parsed.contains(&event) {
+            parsed.push(event);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Register a subscriber, returning it with its secret (shown only this once)
+pub async fn create(
+    pool: &PgPool,
+    request: &SubscriberCreate,
+) -> Result<(Subscriber, String), AppError> {
+    let url = check_callback_url(&request.url).await?;
+    let events = parse_events(&request.events)?;
+    let tenant = request
+        .tenant
+        .as_deref()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty());
+    let description = request
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+
+    let secret = generate_secret();
+    let subscriber: Subscriber = sqlx::query_as(
+        r#"
+        INSERT INTO webhook_subscribers (url, secret, tenant, events, description)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, url, tenant, events, description, created_at, disabled_at
+        "#,
+    )
+    .bind(&url)
+    .bind(&secret)
+    .bind(&tenant)
+    .bind(&events)
+    .bind(description)
+    .fetch_one(pool)
+    .timed("webhook_subscribers.create")
+    .await
+    .context(ErrorContext::db("webhook_subscribers.create").id("url", &url))?;
+    Ok((subscriber, secret))
+}
+
+/// Every subscriber, newest first (disabled ones included)
+pub async fn list(pool: &PgPool) -> Result<Vec<Subscriber>, AppError> {
+    sqlx::query_as(
+        r#"
+        SELECT id, url, tenant, events, description, created_at, disabled_at
+        FROM webhook_subscribers
+        ORDER BY created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .timed("webhook_subscribers.list")
+    .await
+    .context(ErrorContext::db("webhook_subscribers.list"))
+}
+
+/// Stop notifying a subscriber; queued deliveries are dropped when they run
+///
+/// Returns whether an active subscriber was disabled.
+pub async fn disable(pool: &PgPool, id: i64) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE webhook_subscribers SET disabled_at = NOW() WHERE id = $1 AND disabled_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .timed("webhook_subscribers.disable")
+    .await
+    .context(ErrorContext::db("webhook_subscribers.disable").id("id", id))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Envelope shared by every event
+fn event_body(event: &str, lead_id: &str, tenant: Option<&str>, status: &str) -> Value {
+    json!({
+        "event": event,
+        "event_id": Uuid::new_v4(),
+        "occurred_at": Utc::now(),
+        "lead_id": lead_id,
+        "tenant": tenant,
+        "status": status,
+    })
+}
+
+/// `enrichment.completed` body of a finished workflow
+pub fn completed_event(lead_id: &str, tenant: Option<&str>, result: &EnrichmentResult) -> Value {
+    let mut body = event_body(ENRICHMENT_COMPLETED, lead_id, tenant, "completed");
+    body["party_id"] = json!(result.entity_ids.first());
+    body["party_ids"] = json!(result.entity_ids);
+    body["cpfs_enriched"] = json!(result.cpfs_enriched.len());
+    body["message_sent"] = json!(result.message_sent);
+    body["wealth"] = match &result.summary {
+        Some(summary) => json!({
+            "segment": summary.wealth,
+            "income": summary.income,
+            "credit_score": summary.credit_score,
+        }),
+        None => Value::Null,
+    };
+    body
+}
+
+/// `enrichment.failed` body of a workflow that ended with `reason`
+pub fn failed_event(lead_id: &str, tenant: Option<&str>, reason: &str) -> Value {
+    let mut body = event_body(ENRICHMENT_FAILED, lead_id, tenant, "failed");
+    body["party_id"] = Value::Null;
+    body["wealth"] = Value::Null;
+    body["reason"] = json!(reason);
+    body
+}
+
+/// Short reason of a failed enrichment, safe to send outside
+///
+/// Error messages are not sent: they may carry CPFs or internal details.
+pub fn failure_reason(error: &AppError, no_data: Option<&NoDataReason>) -> &'static str {
+    match no_data {
+        Some(NoDataReason::NoCpf { .. }) => return "no_cpf",
+        Some(NoDataReason::NoWorkApiData { .. }) => return "no_data",
+        None => {}
+    }
+    match error.root() {
+        AppError::ConsentRequired(_) => "consent_required",
+        AppError::InvalidDocument(_) => "invalid_document",
+        AppError::DeadlineExceeded(_) => "deadline_exceeded",
+        AppError::NotFound(_) => "not_found",
+        _ => "error",
+    }
+}
+
+/// Queue the delivery of an event to its active subscribers
+///
+/// Returns the number of deliveries queued.
+pub async fn publish(
+    pool: &PgPool,
+    tenant: Option<&str>,
+    event: &Value,
+) -> Result<usize, AppError> {
+    let name = event["event"].as_str().unwrap_or_default();
+    let subscriber_ids: Vec<i64> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM webhook_subscribers
+        WHERE disabled_at IS NULL
+          AND $1 = ANY(events)
+          AND (tenant IS NULL OR tenant = lower($2))
+        "#,
+    )
+    .bind(name)
+    .bind(tenant)
+    .fetch_all(pool)
+    .timed("webhook_subscribers.find_active")
+    .await
+    .context(ErrorContext::db("webhook_subscribers.find_active").id("event", name))?;
+
+    for subscriber_id in &subscriber_ids {
+        let payload = serde_json::to_value(Delivery {
+            subscriber_id: *subscriber_id,
+            event: event.clone(),
+        })
+        .map_err(|e| AppError::InternalError(format!("Failed to serialize delivery: {}", e)))?;
+        jobs::enqueue(
+            pool,
+            SUBSCRIBER_NOTIFICATION,
+            &payload,
+            DELIVERY_ATTEMPTS,
+            None,
+        )
+        .await?;
+    }
+    Ok(subscriber_ids.len())
+}
+
+/// Publish an event, logging failures (never interrupts the workflow)
+pub async fn notify(state: &AppState, tenant: Option<&str>, event: Value) {
+    let lead_id = event["lead_id"].as_str().unwrap_or_default();
+    match publish(&state.db, tenant, &event).await {
+        Ok(0) => {}
+        Ok(queued) => tracing::info!(
+            "Queued {} {} notification(s) for lead {}",
+            queued,
+            event["event"].as_str().unwrap_or_default(),
+            lead_id
+        ),
+        Err(e) => tracing::warn!(
+            "Failed to queue subscriber notifications for lead {}: {}",
+            lead_id,
+            e
+        ),
+    }
+}
+
+/// Spawn the workers delivering subscriber notifications
+pub fn spawn_workers(state: Arc<AppState>) {
+    // No redirects: a public subscriber could otherwise bounce into our network
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default();
+
+    for index in 0..WORKERS {
+        let state = state.clone();
+        let client = client.clone();
+        let worker = jobs::worker_id(index);
+        tokio::spawn(async move {
+            loop {
+                match jobs::claim(&state.db, SUBSCRIBER_NOTIFICATION, &worker).await {
+                    Ok(Some(job)) => run_job(&state, &client, job).await,
+                    Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Err(e) => {
+                        tracing::error!("Failed to claim a subscriber notification: {}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match jobs::sweep(&state.db, SUBSCRIBER_NOTIFICATION).await {
+                Ok(0) => {}
+                Ok(failed) => tracing::warn!(
+                    "{} subscriber notification(s) failed: worker lost on every run",
+                    failed
+                ),
+                Err(e) => tracing::warn!("Failed to sweep subscriber notifications: {}", e),
+            }
+        }
+    });
+    tracing::info!("✓ {} subscriber notification worker(s) started", WORKERS);
+}
+
+/// Deliver one claimed notification and record its outcome
+async fn run_job(state: &Arc<AppState>, client: &reqwest::Client, job: jobs::Job) {
+    let delivery = match serde_json::from_value::<Delivery>(job.payload.clone()) {
+        Ok(delivery) => delivery,
+        Err(e) => {
+            tracing::error!("Notification job #{} has an invalid payload: {}", job.id, e);
+            let error = format!("Invalid job payload: {}", e);
+            if let Err(e) = jobs::fail(&state.db, &job, &error).await {
+                tracing::error!(
+                    "Failed to mark notification job #{} as failed: {}",
+                    job.id,
+                    e
+                );
+            }
+            return;
+        }
+    };
+
+    let result =
+        match jobs::with_heartbeat(&state.db, &job, deliver(state, client, &delivery)).await {
+            Ok(()) => jobs::complete(&state.db, &job, None).await.map(|_| ()),
+            Err(e) => {
+                tracing::warn!(
+                    "Notification job #{} to subscriber {} not delivered (run {}/{}): {}",
+                    job.id,
+                    delivery.subscriber_id,
+                    job.attempts,
+                    job.max_attempts,
+                    e
+                );
+                jobs::retry_or_fail(&state.db, &job, &e).await.map(|_| ())
+            }
+        };
+    if let Err(e) = result {
+        tracing::error!("Failed to record notification job #{}: {}", job.id, e);
+    }
+}
+
+/// POST the event to the subscriber, signed with its secret
+///
+/// A subscriber disabled (or deleted) since the event was queued is skipped.
+async fn deliver(
+    state: &AppState,
+    client: &reqwest::Client,
+    delivery: &Delivery,
+) -> Result<(), AppError> {
+    let subscriber: Option<(String, String)> = sqlx::query_as(
+        "SELECT url, secret FROM webhook_subscribers WHERE id = $1 AND disabled_at IS NULL",
+    )
+    .bind(delivery.subscriber_id)
+    .fetch_optional(&state.db)
+    .timed("webhook_subscribers.get")
+    .await
+    .context(ErrorContext::db("webhook_subscribers.get").id("id", delivery.subscriber_id))?;
+    let Some((url, secret)) = subscriber else {
+        tracing::debug!(
+            "Subscriber {} disabled, notification dropped",
+            delivery.subscriber_id
+        );
+        return Ok(());
+    };
+
+    let parsed = url::Url::parse(&url)
+        .map_err(|e| AppError::InternalError(format!("Stored subscriber url invalid: {}", e)))?;
+    ensure_public_host(&parsed).await?;
+
+    let body = serde_json::to_vec(&delivery.event)
+        .map_err(|e| AppError::InternalError(format!("Failed to serialize event: {}", e)))?;
+    let response = client
+        .post(&url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            "X-Event",
+            delivery.event["event"].as_str().unwrap_or_default(),
+        )
+        .header("X-Signature", sign(&secret, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| AppError::ExternalApiError(format!("POST {} failed: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::ExternalApiError(format!(
+            "POST {} answered HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrichment::{EnrichedLeadSummary, WealthSegment};
+
+    #[test]
+    fn test_generated_secrets() {
+        let secret = generate_secret();
+        assert!(secret.starts_with(SECRET_PREFIX));
+        assert_eq!(secret.len(), SECRET_PREFIX.len() + 2 * SECRET_BYTES);
+        assert_ne!(secret, generate_secret());
+    }
+
+    #[test]
+    fn test_parse_events() {
+        assert_eq!(parse_events(&[]).unwrap(), EVENTS);
+        assert_eq!(
+            parse_events(&[
+                " Enrichment.Failed".to_string(),
+                "enrichment.failed".to_string()
+            ])
+            .unwrap(),
+            vec![ENRICHMENT_FAILED]
+        );
+        assert!(parse_events(&["lead.created".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_completed_event_carries_wealth_summary() {
+        let party_id = Uuid::new_v4();
+        let mut summary = EnrichedLeadSummary::from_work_data(
+            "lead-1",
+            "Maria",
+            None,
+            None,
+            Some("52998224725"),
+            &json!({"DadosEconomicos": {"renda": "R$ 12.000,00", "score": {"scoreCSBA": "812"}}}),
+        );
+        summary.wealth = WealthSegment::Alto;
+        let result = EnrichmentResult {
+            lead_id: "lead-1".to_string(),
+            cpfs_enriched: vec!["52998224725".to_string()],
+            same_person: true,
+            message_sent: true,
+            stored_count: 1,
+            entity_ids: vec![party_id],
+            summary: Some(summary),
+            missing: Vec::new(),
+            timings: Default::default(),
+            stages: Vec::new(),
+        };
+
+        let event = completed_event("lead-1", Some("google ads"), &result);
+        assert_eq!(event["event"], ENRICHMENT_COMPLETED);
+        assert_eq!(event["status"], "completed");
+        assert_eq!(event["lead_id"], "lead-1");
+        assert_eq!(event["tenant"], "google ads");
+        assert_eq!(event["party_id"], json!(party_id));
+        assert_eq!(event["wealth"]["segment"], "alto");
+        assert_eq!(event["wealth"]["income"], 12000.0);
+        assert_eq!(event["wealth"]["credit_score"], 812);
+        // The CPF itself is never sent
+        assert!(!event.to_string().contains("52998224725"));
+    }
+
+    #[test]
+    fn test_failure_reasons() {
+        let error = AppError::NotFound("No CPF".to_string());
+        assert_eq!(
+            failure_reason(
+                &error,
+                Some(&NoDataReason::NoCpf {
+                    retry_scheduled: false
+                })
+            ),
+            "no_cpf"
+        );
+        assert_eq!(
+            failure_reason(
+                &error,
+                Some(&NoDataReason::NoWorkApiData {
+                    cpfs: vec!["52998224725".to_string()]
+                })
+            ),
+            "no_data"
+        );
+        assert_eq!(
+            failure_reason(&AppError::ConsentRequired("opt-out".to_string()), None),
+            "consent_required"
+        );
+        assert_eq!(
+            failure_reason(&AppError::InternalError("db down".to_string()), None),
+            "error"
+        );
+
+        let event = failed_event("lead-2", None, "no_cpf");
+        assert_eq!(event["event"], ENRICHMENT_FAILED);
+        assert_eq!(event["reason"], "no_cpf");
+        assert!(event["party_id"].is_null());
+    }
+}
@@ -0,0 +1,426 @@
+//! Runtime-editable C2S message templates
+//!
+//! Built-in defaults can be overridden per template in `message_templates`
+//! (migration 029). Every process keeps a snapshot of the stored templates,
+//! refreshed every `REFRESH_INTERVAL` when a version changed and immediately
+//! after an edit through the admin endpoint, so edits reach every worker
+//! within seconds without a restart.
+//!
+//! Templates use `{{variable}}` placeholders; each template declares the
+//! variables it accepts and edits using unknown ones are rejected.
+//!
+//! Lead-facing headers also exist in English and Spanish (`Locale`, see
+//! `language`). A locale variant is stored under `name.locale` (e.g.
+//! `same_person_header.en`) and falls back to the built-in translation, then
+//! to the Portuguese template.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+/// How often workers check for edited templates
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Header of a dossier whose phone and email belong to the same person
+pub const SAME_PERSON_HEADER: &str = "same_person_header";
+/// Header of a dossier with one profile per contact
+pub const DIFFERENT_PEOPLE_HEADER: &str = "different_people_header";
+/// Note telling the seller the lead wrote in another language
+pub const LANGUAGE_NOTICE: &str = "language_notice";
+/// Seller reminder scheduled after the enrichment message
+pub const FOLLOW_UP: &str = "follow_up";
+/// Header of a coalesced re-enrichment message
+pub const UPDATE_HEADER: &str = "update_header";
+/// Status note after a webhook enrichment completed (C2S_STATUS_NOTES)
+pub const STATUS_COMPLETED: &str = "status_completed";
+/// Status note after a webhook enrichment failed (C2S_STATUS_NOTES)
+pub const STATUS_FAILED: &str = "status_failed";
+
+/// Language of a rendered template (Portuguese unless the lead wrote in another one)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    Pt,
+    En,
+    Es,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::Pt, Locale::En, Locale::Es];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Pt => "pt",
+            Locale::En => "en",
+            Locale::Es => "es",
+        }
+    }
+
+    /// Name of the language for the seller (in Portuguese)
+    pub fn language_name(self) -> &'static str {
+        match self {
+            Locale::Pt => "português",
+            Locale::En => "inglês",
+            Locale::Es => "espanhol",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.code() == code)
+    }
+}
+
+/// A template known to the service
+pub struct TemplateDef {
+    pub name: &'static str,
+    pub default_body: &'static str,
+    pub variables: &'static [&'static str],
+    /// Built-in bodies in other locales (lead-facing headers only)
+    pub translations: &'static [(Locale, &'static str)],
+}
+
+pub const TEMPLATES: &[TemplateDef] = &[
+    TemplateDef {
+        name: SAME_PERSON_HEADER,
+        default_body: "📞📧 Telefone e e-mail da mesma pessoa",
+        variables: &["customer_name"],
+        translations: &[
+            (Locale::En, "📞📧 Phone and e-mail of the same person"),
+            (Locale::Es, "📞📧 Teléfono y e-mail de la misma persona"),
+        ],
+    },
+    TemplateDef {
+        name: DIFFERENT_PEOPLE_HEADER,
+        default_body: "⚠️ Telefone e e-mail relacionados a PESSOAS DIFERENTES!",
+        variables: &["customer_name"],
+        translations: &[
+            (
+                Locale::En,
+                "⚠️ Phone and e-mail related to DIFFERENT PEOPLE!",
+            ),
+            (
+                Locale::Es,
+                "⚠️ ¡Teléfono y e-mail relacionados a PERSONAS DIFERENTES!",
+            ),
+        ],
+    },
+    TemplateDef {
+        name: LANGUAGE_NOTICE,
+        default_body: "🌐 Lead escreveu em {{language}}",
+        variables: &["language"],
+        translations: &[],
+    },
+    TemplateDef {
+        name: FOLLOW_UP,
+        default_body:
+            "⏰ LEMBRETE\nLead {{customer_name}} enriquecido há {{hours}}h. Já houve contato?",
+        variables: &["customer_name", "hours"],
+        translations: &[],
+    },
+    TemplateDef {
+        name: UPDATE_HEADER,
+        default_body: "🔄 DADOS ATUALIZADOS\nAlterações desde a última mensagem:",
+        variables: &[],
+        translations: &[],
+    },
+    TemplateDef {
+        name: STATUS_COMPLETED,
+        default_body: "✅ Enriquecimento concluído",
+        variables: &[],
+        translations: &[],
+    },
+    TemplateDef {
+        name: STATUS_FAILED,
+        default_body: "🔍 Dados não encontrados",
+        variables: &[],
+        translations: &[],
+    },
+];
+
+/// A template override stored in `message_templates`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StoredTemplate {
+    pub name: String,
+    pub body: String,
+    pub version: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Template as currently used by this process
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveTemplate {
+    pub name: &'static str,
+    pub locale: Locale,
+    pub body: String,
+    pub variables: &'static [&'static str],
+    /// Stored version, `None` when the built-in default is used
+    pub version: Option<i32>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+static SNAPSHOT: LazyLock<RwLock<HashMap<String, StoredTemplate>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn definition(name: &str) -> Option<&'static TemplateDef> {
+    TEMPLATES.iter().find(|t| t.name == name)
+}
+
+/// Stored name of a template in a locale (`name` for Portuguese, `name.en`...)
+pub fn localized_name(name: &str, locale: Locale) -> String {
+    match locale {
+        Locale::Pt => name.to_string(),
+        other => format!("{}.{}", name, other.code()),
+    }
+}
+
+/// Template and locale of a stored name
+fn split_name(name: &str) -> Option<(&'static TemplateDef, Locale)> {
+    match name.split_once('.') {
+        Some((base, code)) => {
+            let locale = Locale::from_code(code).filter(|l| *l != Locale::Pt)?;
+            Some((definition(base)?, locale))
+        }
+        None => Some((definition(name)?, Locale::Pt)),
+    }
+}
+
+/// Current body of a template (stored override or built-in default)
+pub fn body(name: &str) -> String {
+    if let Some(stored) = SNAPSHOT.read().unwrap().get(name) {
+        return stored.body.clone();
+    }
+    definition(name)
+        .map(|t| t.default_body.to_string())
+        .unwrap_or_default()
+}
+
+/// Current body of a template in a locale
+///
+/// Stored variant, else built-in translation, else the Portuguese body.
+pub fn body_in(name: &str, locale: Locale) -> String {
+    if locale == Locale::Pt {
+        return body(name);
+    }
+    if let Some(stored) = SNAPSHOT.read().unwrap().get(&localized_name(name, locale)) {
+        return stored.body.clone();
+    }
+    definition(name)
+        .and_then(|t| t.translations.iter().find(|(l, _)| *l == locale))
+        .map(|(_, body)| body.to_string())
+        .unwrap_or_else(|| body(name))
+}
+
+/// Render a template, replacing `{{variable}}` placeholders
+pub fn render(name: &str, vars: &[(&str, &str)]) -> String {
+    substitute(&body(name), vars)
+}
+
+/// Render a template in a locale
+pub fn render_in(name: &str, locale: Locale, vars: &[(&str, &str)]) -> String {
+    substitute(&body_in(name, locale), vars)
+}
+
+/// Replace `{{variable}}` placeholders in an arbitrary body
+pub fn substitute(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter()
+        .fold(template.to_string(), |body, (key, value)| {
+            body.replace(&format!("{{{{{}}}}}", key), value)
+        })
+}
+
+/// Placeholders used in a template body
+pub fn placeholders(body: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        found.push(&after[..end]);
+        rest = &after[end + 2..];
+    }
+    found
+}
+
+/// Check that a template exists and its body only uses declared variables
+///
+/// `name` may be a locale variant (`same_person_header.en`).
+pub fn validate(name: &str, body: &str) -> Result<(), AppError> {
+    let (def, _) = split_name(name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown message template: {}", name)))?;
+    if body.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Template body cannot be empty".to_string(),
+        ));
+    }
+    if let Some(unknown) = placeholders(body)
+        .into_iter()
+        .find(|var| !def.variables.contains(var))
+    {
+        return Err(AppError::BadRequest(format!(
+            "Unknown variable {{{{{}}}}} in template {} (allowed: {})",
+            unknown,
+            name,
+            def.variables.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Every known template with the body this process currently uses
+///
+/// Locale variants are listed when they have a translation or a stored body.
+pub fn effective() -> Vec<EffectiveTemplate> {
+    let snapshot = SNAPSHOT.read().unwrap();
+    TEMPLATES
+        .iter()
+        .flat_map(|def| Locale::ALL.into_iter().map(move |locale| (def, locale)))
+        .filter_map(|(def, locale)| {
+            let stored = snapshot.get(&localized_name(def.name, locale));
+            let default_body = match locale {
+                Locale::Pt => Some(def.default_body),
+                other => def
+                    .translations
+                    .iter()
+                    .find(|(l, _)| *l == other)
+                    .map(|(_, body)| *body),
+            };
+            let body = stored
+                .map(|s| s.body.clone())
+                .or_else(|| default_body.map(str::to_string))?;
+            Some(EffectiveTemplate {
+                name: def.name,
+                locale,
+                body,
+                variables: def.variables,
+                version: stored.map(|s| s.version),
+                updated_at: stored.map(|s| s.updated_at),
+            })
+        })
+        .collect()
+}
+
+/// Reload the stored templates when any version changed, returns whether it did
+pub async fn refresh(pool: &PgPool) -> Result<bool, AppError> {
+    let stored: Vec<StoredTemplate> = sqlx::query_as(
+        "SELECT name, body, version, updated_at FROM message_templates ORDER BY name",
+    )
+    .fetch_all(pool)
+    .timed("message_templates.list")
+    .await
+    .context("Failed to load message templates")?;
+
+    let mut snapshot = SNAPSHOT.write().unwrap();
+    let unchanged = stored.len() == snapshot.len()
+        && stored.iter().all(|t| {
+            snapshot
+                .get(&t.name)
+                .is_some_and(|current| current.version == t.version)
+        });
+    if unchanged {
+        return Ok(false);
+    }
+    *snapshot = stored.into_iter().map(|t| (t.name.clone(), t)).collect();
+    Ok(true)
+}
+
+/// Store a template edit (version bumped) and reload this process's snapshot
+pub async fn save(pool: &PgPool, name: &str, body: &str) -> Result<StoredTemplate, AppError> {
+    validate(name, body)?;
+
+    let stored: StoredTemplate = sqlx::query_as(
+        r#"
+        INSERT INTO message_templates (name, body)
+        VALUES ($1, $2)
+        ON CONFLICT (name) DO UPDATE
+        SET body = EXCLUDED.body,
+            version = message_templates.version + 1,
+            updated_at = NOW()
+        RETURNING name, body, version, updated_at
+        "#,
+    )
+    .bind(name)
+    .bind(body)
+    .fetch_one(pool)
+    .timed("message_templates.upsert")
+    .await
+    .context(format!("Failed to save message template: {}", name))?;
+
+    refresh(pool).await?;
+    Ok(stored)
+}
+
+/// Load the templates now and keep them in sync with the database
+pub fn spawn_template_refresher(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match refresh(&pool).await {
+                Ok(true) => tracing::info!("Message templates reloaded"),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Message template refresh failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_render_without_overrides() {
+        assert_eq!(
+            render(FOLLOW_UP, &[("customer_name", "Maria"), ("hours", "24")]),
+            "⏰ LEMBRETE\nLead Maria enriquecido há 24h. Já houve contato?"
+        );
+        assert!(body("missing").is_empty());
+    }
+
+    #[test]
+    fn test_validate_variables() {
+        assert!(validate(FOLLOW_UP, "Lead {{customer_name}} há {{hours}}h").is_ok());
+        assert!(validate(FOLLOW_UP, "Lead {{cpf}}").is_err());
+        assert!(validate(FOLLOW_UP, "Lead {{ hours }}").is_err());
+        assert!(validate(UPDATE_HEADER, "  ").is_err());
+        assert!(validate("unknown", "x").is_err());
+    }
+
+    #[test]
+    fn test_default_bodies_are_valid() {
+        for def in TEMPLATES {
+            assert!(validate(def.name, def.default_body).is_ok(), "{}", def.name);
+            for (locale, body) in def.translations {
+                let name = localized_name(def.name, *locale);
+                assert!(validate(&name, body).is_ok(), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_locale_variants() {
+        assert_eq!(
+            render_in(SAME_PERSON_HEADER, Locale::En, &[]),
+            "📞📧 Phone and e-mail of the same person"
+        );
+        // No translation: the Portuguese body
+        assert_eq!(body_in(UPDATE_HEADER, Locale::Es), body(UPDATE_HEADER));
+        assert!(validate("same_person_header.es", "Hola {{customer_name}}").is_ok());
+        assert!(validate("same_person_header.fr", "Bonjour").is_err());
+        assert!(validate("same_person_header.pt", "Olá").is_err());
+        assert!(validate("same_person_header.en", "Hi {{hours}}").is_err());
+        assert!(effective()
+            .iter()
+            .any(|t| t.name == DIFFERENT_PEOPLE_HEADER && t.locale == Locale::Es));
+    }
+}
@@ -0,0 +1,198 @@
+//! Per-tenant provider credentials
+//!
+//! Some partners bring their own Work API or Diretrix contract. Leads of
+//! their tenant (lead source) are enriched with the credentials configured in
+//! `TENANT_PROVIDER_CREDENTIALS`; other tenants, and any provider a partner
+//! leaves out, use the global ones (`WORK_API`, `DIRETRIX_USER`/`DIRETRIX_PASS`):
+//!
+//! ```json
+//! TENANT_PROVIDER_CREDENTIALS={"parceiro-x": {"work_api": {"token": "..."},
+//!                              "diretrix": {"user": "...", "pass": "..."}}}
+//! ```
+//!
+//! Base URLs default to the global ones. Tenant Work API clients do not use
+//! the response cache (`Caches::work_api`): data bought under a partner's
+//! contract is not served to other tenants, nor the other way round.
+
+use crate::clients::{DiretrixApi, WorkApi};
+use crate::config::Config;
+use crate::handlers::AppState;
+use crate::services::{DiretrixService, WorkApiService};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A tenant's Work API contract
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkApiCredentials {
+    pub token: String,
+    /// Defaults to `WORK_API_BASE_URL`
+    pub base_url: Option<String>,
+}
+
+/// A tenant's Diretrix contract
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiretrixCredentials {
+    pub user: String,
+    pub pass: String,
+    /// Defaults to `DIRETRIX_BASE_URL`
+    pub base_url: Option<String>,
+}
+
+/// Providers a tenant has its own credentials for
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantCredentials {
+    pub work_api: Option<WorkApiCredentials>,
+    pub diretrix: Option<DiretrixCredentials>,
+}
+
+impl TenantCredentials {
+    /// Providers with own credentials, for logs
+    pub fn providers(&self) -> Vec<&'static str> {
+        [
+            self.work_api.as_ref().map(|_| "work_api"),
+            self.diretrix.as_ref().map(|_| "diretrix"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Tenant (lowercase) -> its credentials
+pub type TenantCredentialMap = HashMap<String, TenantCredentials>;
+
+/// Parse `TENANT_PROVIDER_CREDENTIALS` (JSON, empty string = none)
+pub fn parse_credentials(json: &str) -> anyhow::Result<TenantCredentialMap> {
+    if json.trim().is_empty() {
+        return Ok(TenantCredentialMap::new());
+    }
+    let credentials: TenantCredentialMap = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid TENANT_PROVIDER_CREDENTIALS JSON: {}", e))?;
+
+    let invalid_url = |url: &Option<String>| {
+        url.as_deref()
+            .is_some_and(|u| !u.starts_with("http://") && !u.starts_with("https://"))
+    };
+    for (tenant, creds) in &credentials {
+        if creds.providers().is_empty() {
+            anyhow::bail!(
+                "TENANT_PROVIDER_CREDENTIALS: no provider for tenant '{}'",
+                tenant
+            );
+        }
+        if let Some(work_api) = creds.work_api.as_ref() {
+            if work_api.token.trim().is_empty() {
+                anyhow::bail!(
+                    "TENANT_PROVIDER_CREDENTIALS: Work API token of '{}' cannot be empty",
+                    tenant
+                );
+            }
+            if invalid_url(&work_api.base_url) {
+                anyhow::bail!(
+                    "TENANT_PROVIDER_CREDENTIALS: Work API base_url of '{}' must be an http(s) URL",
+                    tenant
+                );
+            }
+        }
+        if let Some(diretrix) = creds.diretrix.as_ref() {
+            if diretrix.user.trim().is_empty() || diretrix.pass.is_empty() {
+                anyhow::bail!(
+                    "TENANT_PROVIDER_CREDENTIALS: Diretrix user and pass of '{}' cannot be empty",
+                    tenant
+                );
+            }
+            if invalid_url(&diretrix.base_url) {
+                anyhow::bail!(
+                    "TENANT_PROVIDER_CREDENTIALS: Diretrix base_url of '{}' must be an http(s) URL",
+                    tenant
+                );
+            }
+        }
+    }
+
+    Ok(credentials
+        .into_iter()
+        .map(|(tenant, creds)| (tenant.trim().to_lowercase(), creds))
+        .collect())
+}
+
+/// Credentials of a tenant, when it has its own
+pub fn for_tenant<'a>(config: &'a Config, tenant: Option<&str>) -> Option<&'a TenantCredentials> {
+    tenant.and_then(|t| {
+        config
+            .tenant_provider_credentials
+            .get(&t.trim().to_lowercase())
+    })
+}
+
+/// Work API client of a tenant (the shared one, `AppState::work_api`, unless it has its own)
+pub fn work_api_for(state: &AppState, tenant: Option<&str>) -> Arc<dyn WorkApi> {
+    match for_tenant(&state.config, tenant).and_then(|c| c.work_api.as_ref()) {
+        Some(work_api) => Arc::new(WorkApiService::with_credentials(
+            work_api
+                .base_url
+                .clone()
+                .unwrap_or_else(|| state.config.work_api_base_url.clone()),
+            work_api.token.clone(),
+        )),
+        None => state.work_api.clone(),
+    }
+}
+
+/// Diretrix client of a tenant (the shared one, `AppState::diretrix`, unless it has its own)
+pub fn diretrix_for(state: &AppState, tenant: Option<&str>) -> Arc<dyn DiretrixApi> {
+    match for_tenant(&state.config, tenant).and_then(|c| c.diretrix.as_ref()) {
+        Some(diretrix) => Arc::new(DiretrixService::with_credentials(
+            diretrix
+                .base_url
+                .clone()
+                .unwrap_or_else(|| state.config.diretrix_base_url.clone()),
+            diretrix.user.clone(),
+            diretrix.pass.clone(),
+        )),
+        None => state.diretrix.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_credentials() {
+        let credentials = parse_credentials(
+            r#"{
+                " Parceiro-X ": {"work_api": {"token": "tok-x"}},
+                "parceiro-y": {
+                    "work_api": {"token": "tok-y", "base_url": "https://work.example.com"},
+                    "diretrix": {"user": "y", "pass": "secret"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let x = &credentials["parceiro-x"];
+        assert_eq!(x.providers(), vec!["work_api"]);
+        assert!(x.work_api.as_ref().unwrap().base_url.is_none());
+        assert_eq!(
+            credentials["parceiro-y"].providers(),
+            vec!["work_api", "diretrix"]
+        );
+        assert!(parse_credentials("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_credentials_rejects_incomplete_entries() {
+        assert!(parse_credentials(r#"{"x": {}}"#).is_err());
+        assert!(parse_credentials(r#"{"x": {"work_api": {"token": " "}}}"#).is_err());
+        assert!(parse_credentials(r#"{"x": {"diretrix": {"user": "u", "pass": ""}}}"#).is_err());
+        assert!(parse_credentials(
+            r#"{"x": {"work_api": {"token": "t", "base_url": "work.example.com"}}}"#
+        )
+        .is_err());
+        // Typos are not silently ignored
+        assert!(parse_credentials(r#"{"x": {"workapi": {"token": "t"}}}"#).is_err());
+    }
+}
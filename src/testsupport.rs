@@ -0,0 +1,561 @@
+//! Fake providers and payload fixtures for integration tests
+//!
+//! Enabled by the `testsupport` feature. Each fake is a wiremock server
+//! speaking the provider's HTTP contract (Work API, Diretrix, C2S);
+//! `FakeProviders::config` points a `Config` at all of them, so the whole
+//! workflow runs deterministically, without network access or billable calls.
+//! `test_state` builds an `AppState` on such a config for code taking one.
+//!
+//! Provider circuit breakers are process-wide: a test that makes a fake fail
+//! repeatedly can open a breaker for the other tests of the same binary.
+
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method, path, path_regex, query_param};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use crate::app_services::Services;
+use crate::caches::Caches;
+use crate::clients::WorkApi;
+use crate::config::Config;
+use crate::dedup::MemoryDedup;
+use crate::google_ads_models::{GoogleAdsWebhookPayload, UserColumnData};
+use crate::handlers::AppState;
+use crate::readiness::{LazyClient, Readiness};
+use crate::services::{C2SService, DiretrixService, WorkApiService};
+use crate::webhook_models::WebhookEvent;
+use crate::webhook_queue::WebhookQueue;
+
+/// Token and credentials expected by the fakes (see `FakeProviders::config`)
+pub const TEST_WORK_API_KEY: &str = "test_work_key";
+pub const TEST_C2S_TOKEN: &str = "test_c2s_token";
+pub const TEST_DIRETRIX_USER: &str = "test_user";
+pub const TEST_DIRETRIX_PASS: &str = "test_pass";
+pub const TEST_GOOGLE_KEY: &str = "test_google_key";
+
+/// Fake Work API (`GET /api?token=&modulo=&consulta=`)
+pub struct FakeWorkApi {
+    server: MockServer,
+}
+
+impl FakeWorkApi {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Answer the complete (`modulo=cpf`) lookup of a CPF
+    pub async fn with_person(&self, cpf: &str, response: Value) -> &Self {
+        Mock::given(method("GET"))
+            .and(path("/api"))
+            .and(query_param("modulo", "cpf"))
+            .and(query_param("consulta", cpf))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Answer a single module lookup (e.g. `socios` for a CNPJ)
+    pub async fn with_module(&self, module: &str, consulta: &str, response: Value) -> &Self {
+        Mock::given(method("GET"))
+            .and(path("/api"))
+            .and(query_param("modulo", module))
+            .and(query_param("consulta", consulta))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Fail every lookup of a CPF with the given HTTP status
+    pub async fn with_error(&self, cpf: &str, status: u16) -> &Self {
+        Mock::given(method("GET"))
+            .and(path("/api"))
+            .and(query_param("consulta", cpf))
+            .respond_with(ResponseTemplate::new(status).set_body_string("Work API error"))
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Number of lookups received (each one is billed in production)
+    pub async fn lookup_count(&self) -> usize {
+        received(&self.server).await.len()
+    }
+}
+
+/// Fake Diretrix (`/Consultas/Pessoa/...`, basic auth)
+pub struct FakeDiretrix {
+    server: MockServer,
+}
+
+impl FakeDiretrix {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Match a phone (without the 55 country code, as Diretrix expects it)
+    pub async fn with_phone_match(&self, phone: &str, name: &str, cpf: &str) -> &Self {
+        self.mount_search(
+            format!("/Consultas/Pessoa/Telefone/{}", phone),
+            json!([{ "nome": name, "cpf": cpf }]),
+        )
+        .await;
+        self
+    }
+
+    /// Match an email
+    pub async fn with_email_match(&self, email: &str, name: &str, cpf: &str) -> &Self {
+        self.mount_search(
+            format!("/Consultas/Pessoa/Email/{}", email),
+            json!([{ "nome": name, "cpf": cpf }]),
+        )
+        .await;
+        self
+    }
+
+    /// Full person data of a CPF (`get_person_by_cpf`)
+    pub async fn with_person(&self, cpf: &str, response: Value) -> &Self {
+        self.mount_search(format!("/Consultas/Pessoa/{}", cpf), response)
+            .await;
+        self
+    }
+
+    /// Answer every unmatched search with "not found" (an empty list)
+    pub async fn with_no_match(&self) -> &Self {
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/Consultas/Pessoa/(Telefone|Email)/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .with_priority(u8::MAX)
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    async fn mount_search(&self, route: String, response: Value) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .mount(&self.server)
+            .await;
+    }
+}
+
+/// Fake C2S integration API (`/integration/leads`)
+pub struct FakeC2s {
+    server: MockServer,
+}
+
+impl FakeC2s {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Accept lead creation, answering with the given C2S lead id
+    pub async fn accepts_leads(&self, c2s_lead_id: &str) -> &Self {
+        Mock::given(method("POST"))
+            .and(path("/integration/leads"))
+            .and(body_partial_json(json!({ "data": { "type": "lead" } })))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(json!({ "data": { "id": c2s_lead_id, "type": "lead" } })),
+            )
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Accept messages on any lead (201, as C2S answers)
+    pub async fn accepts_messages(&self) -> &Self {
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/integration/leads/[^/]+/create_message$"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "success": true })))
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Serve a lead (`fetch_lead`)
+    pub async fn with_lead(&self, lead_id: &str, name: &str, email: &str, phone: &str) -> &Self {
+        Mock::given(method("GET"))
+            .and(path(format!("/integration/leads/{}", lead_id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "id": lead_id,
+                    "attributes": {
+                        "customer": {
+                            "id": format!("customer-{}", lead_id),
+                            "name": name,
+                            "email": email,
+                            "phone": phone
+                        },
+                        "description": "",
+                        "product": { "prop_ref": null }
+                    }
+                }
+            })))
+            .mount(&self.server)
+            .await;
+        self
+    }
+
+    /// Attributes of every lead created so far
+    pub async fn created_leads(&self) -> Vec<Value> {
+        received(&self.server)
+            .await
+            .iter()
+            .filter(|r| r.method.as_str() == "POST" && r.url.path() == "/integration/leads")
+            .filter_map(|r| serde_json::from_slice::<Value>(&r.body).ok())
+            .map(|body| body["data"]["attributes"].clone())
+            .collect()
+    }
+
+    /// (lead id, body) of every message sent so far
+    pub async fn sent_messages(&self) -> Vec<(String, String)> {
+        received(&self.server)
+            .await
+            .iter()
+            .filter(|r| r.url.path().ends_with("/create_message"))
+            .filter_map(|r| serde_json::from_slice::<Value>(&r.body).ok())
+            .map(|body| {
+                (
+                    body["leadId"].as_str().unwrap_or_default().to_string(),
+                    body["body"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The three provider fakes, started together
+pub struct FakeProviders {
+    pub work_api: FakeWorkApi,
+    pub diretrix: FakeDiretrix,
+    pub c2s: FakeC2s,
+}
+
+impl FakeProviders {
+    pub async fn start() -> Self {
+        Self {
+            work_api: FakeWorkApi::start().await,
+            diretrix: FakeDiretrix::start().await,
+            c2s: FakeC2s::start().await,
+        }
+    }
+
+    /// Configuration pointing every provider at its fake
+    ///
+    /// Optional integrations (shadow provider, RD Station, Salesforce, SMS,
+    /// WhatsApp, listings, archiving) are disabled.
+    pub fn config(&self) -> Config {
+        test_config(self.work_api.uri(), self.diretrix.uri(), self.c2s.uri())
+    }
+}
+
+/// Configuration for tests, with every optional integration disabled
+///
+/// The database URL is a placeholder: tests that need Postgres set it.
+pub fn test_config(
+    work_api_base_url: String,
+    diretrix_base_url: String,
+    c2s_base_url: String,
+) -> Config {
+    Config {
+        worker_api_key: TEST_WORK_API_KEY.to_string(),
+        work_api_base_url,
+        c2s_token: TEST_C2S_TOKEN.to_string(),
+        c2s_base_url,
+        diretrix_base_url,
+        diretrix_user: TEST_DIRETRIX_USER.to_string(),
+        diretrix_pass: TEST_DIRETRIX_PASS.to_string(),
+        identity_match_threshold: 0.6,
+        shadow_provider_url: None,
+        shadow_provider_token: None,
+        shadow_sample_rate: 0.0,
+        debug_sample_rate: 0.0,
+        debug_cpf_allowlist: vec![],
+        debug_payload_ttl_hours: 72,
+        work_api_fixture_sample_rate: 0.0,
+        work_api_fixture_dir: None,
+        consent_required_tenants: vec![],
+        diretrix_person_tenants: vec![],
+        lead_dedup_tenants: vec![],
+        lead_dedup_window_days: 30,
+        tenant_provider_credentials: Default::default(),
+        tenant_pipeline_stages: Default::default(),
+        anonymize_after_days: None,
+        anonymization_salt: None,
+        cache_signing_secret: None,
+        webhook_payload_retention_days: None,
+        webhook_archive_url: None,
+        webhook_archive_token: None,
+        webhook_max_events: 100,
+        webhook_workers: 2,
+        webhook_queue_capacity: 100,
+        webhook_max_attempts: 3,
+        enrich_job_workers: 2,
+        batch_callback_secret: None,
+        public_base_url: None,
+        database_url: "postgresql://test".to_string(),
+        slow_query_ms: 500,
+        dedup_backend: "memory".to_string(),
+        redis_url: None,
+        sql_self_test: false,
+        port: 8080,
+        webhook_secret: None,
+        admin_api_token: None,
+        api_keys_required: false,
+        cors: Default::default(),
+        google_ads_webhook_key: Some(TEST_GOOGLE_KEY.to_string()),
+        c2s_default_seller_id: Some("test_seller".to_string()),
+        c2s_description_max_length: 1000,
+        c2s_send_rate_per_minute: 60,
+        c2s_send_rate_per_lead_per_minute: 5,
+        c2s_send_max_wait_secs: 30,
+        work_api_rate_per_minute: 30,
+        work_api_max_concurrency: 3,
+        work_api_max_wait_secs: 60,
+        work_api_cache_ttl_secs: 3600,
+        work_api_cache_max_ttl_secs: 86400,
+        diretrix_rate_per_minute: 60,
+        diretrix_max_concurrency: 4,
+        diretrix_max_wait_secs: 30,
+        c2s_coalesce_window_minutes: 60,
+        c2s_status_notes: false,
+        c2s_startup_policy: "fail_fast".to_string(),
+        whatsapp_verify_token: None,
+        whatsapp_app_secret: None,
+        rdstation_api_key: None,
+        rdstation_base_url: "https://api.rd.services".to_string(),
+        listings_api_url: None,
+        listings_api_key: None,
+        salesforce_enabled: false,
+        salesforce_login_url: "https://login.salesforce.com".to_string(),
+        salesforce_client_id: None,
+        salesforce_client_secret: None,
+        salesforce_api_version: "v59.0".to_string(),
+        google_sheets_spreadsheet_id: None,
+        google_sheets_range: "Leads!A:I".to_string(),
+        google_service_account_json: None,
+        routing_rules: vec![],
+        c2s_accounts: Default::default(),
+        c2s_account_rules: vec![],
+        c2s_custom_fields: Default::default(),
+        quiet_hours: Default::default(),
+        follow_up_nudge_hours: None,
+        sms_provider: None,
+        sms_from: None,
+        twilio_account_sid: None,
+        twilio_auth_token: None,
+        zenvia_api_token: None,
+    }
+}
+
+/// Application state for tests, with every optional client disabled
+///
+/// The pool connects lazily: only tests that query it need Postgres.
+pub fn test_state(config: Config) -> AppState {
+    let readiness = Readiness::new();
+    let caches = Caches::new();
+    let db = PgPoolOptions::new()
+        .connect_lazy(&config.database_url)
+        .expect("test database URL");
+    let work_api: Arc<dyn WorkApi> = Arc::new(WorkApiService::new(&config));
+    AppState {
+        services: Arc::new(Services::new(&db, work_api.clone(), &caches)),
+        dedup: Arc::new(MemoryDedup::new(&caches)),
+        db,
+        rdstation_client: LazyClient::disabled("rdstation", &readiness),
+        listings_client: LazyClient::disabled("listings", &readiness),
+        salesforce_client: LazyClient::disabled("salesforce", &readiness),
+        sheets_client: LazyClient::disabled("google_sheets", &readiness),
+        sms_client: LazyClient::disabled("sms", &readiness),
+        work_api,
+        diretrix: Arc::new(DiretrixService::new(&config)),
+        c2s: Arc::new(C2SService::new(&config)),
+        caches,
+        webhook_queue: WebhookQueue::new(
+            config.webhook_queue_capacity,
+            config.webhook_max_attempts,
+        ),
+        readiness,
+        config,
+    }
+}
+
+/// Complete Work API answer for a CPF (basic, economic, address, contacts)
+pub fn work_api_person(cpf: &str, name: &str) -> Value {
+    json!({
+        "status": 200,
+        "DadosBasicos": {
+            "nome": name,
+            "cpf": cpf,
+            "dataNascimento": "01/01/1985",
+            "idade": 40,
+            "sexo": "M - MASCULINO",
+            "nomeMae": "Maria da Silva"
+        },
+        "DadosEconomicos": {
+            "renda": "R$ 15.000,00",
+            "score": { "nota": "850" }
+        },
+        "enderecos": [{
+            "logradouro": "Rua Oscar Freire",
+            "numero": "100",
+            "bairro": "Jardins",
+            "cidade": "São Paulo",
+            "uf": "SP",
+            "cep": "01426000"
+        }],
+        "telefones": [
+            { "telefone": "11987654321", "tipo": "CELULAR", "whatsapp": "SIM" }
+        ],
+        "emails": [
+            { "email": "cliente@example.com", "prioridade": "1" }
+        ]
+    })
+}
+
+/// Builder of Google Ads lead form webhook payloads
+#[derive(Debug, Clone)]
+pub struct GoogleAdsPayloadBuilder {
+    payload: GoogleAdsWebhookPayload,
+}
+
+impl GoogleAdsPayloadBuilder {
+    /// Payload of a real (non-test) lead with no answers yet
+    pub fn new(lead_id: &str) -> Self {
+        Self {
+            payload: GoogleAdsWebhookPayload {
+                lead_id: lead_id.to_string(),
+                api_version: "1.0".to_string(),
+                form_id: 1001,
+                campaign_id: 2002,
+                adgroup_id: Some(3003),
+                creative_id: None,
+                gcl_id: Some("test-gclid".to_string()),
+                google_key: TEST_GOOGLE_KEY.to_string(),
+                is_test: false,
+                user_column_data: vec![],
+            },
+        }
+    }
+
+    pub fn form_id(mut self, form_id: i64) -> Self {
+        self.payload.form_id = form_id;
+        self
+    }
+
+    pub fn campaign_id(mut self, campaign_id: i64) -> Self {
+        self.payload.campaign_id = campaign_id;
+        self
+    }
+
+    pub fn test_lead(mut self) -> Self {
+        self.payload.is_test = true;
+        self
+    }
+
+    pub fn full_name(self, name: &str) -> Self {
+        self.column("FULL_NAME", "Full Name", name)
+    }
+
+    pub fn email(self, email: &str) -> Self {
+        self.column("EMAIL", "User Email", email)
+    }
+
+    pub fn phone(self, phone: &str) -> Self {
+        self.column("PHONE_NUMBER", "User Phone", phone)
+    }
+
+    pub fn cpf(self, cpf: &str) -> Self {
+        self.column("CPF", "CPF", cpf)
+    }
+
+    /// Any answer, standard or custom
+    pub fn column(mut self, column_id: &str, column_name: &str, value: &str) -> Self {
+        self.payload.user_column_data.push(UserColumnData {
+            column_id: column_id.to_string(),
+            column_name: column_name.to_string(),
+            string_value: value.to_string(),
+        });
+        self
+    }
+
+    pub fn build(self) -> GoogleAdsWebhookPayload {
+        self.payload
+    }
+
+    /// The payload as Google sends it
+    pub fn build_json(self) -> Value {
+        serde_json::to_value(self.payload).expect("payload serializes")
+    }
+}
+
+/// C2S `lead.created` webhook event for a customer
+pub fn c2s_webhook_event(
+    lead_id: &str,
+    name: &str,
+    email: Option<&str>,
+    phone: Option<&str>,
+) -> WebhookEvent {
+    serde_json::from_value(c2s_webhook_json(lead_id, name, email, phone))
+        .expect("fixture matches WebhookEvent")
+}
+
+/// C2S `lead.created` webhook body, as C2S sends it
+pub fn c2s_webhook_json(
+    lead_id: &str,
+    name: &str,
+    email: Option<&str>,
+    phone: Option<&str>,
+) -> Value {
+    json!({
+        "id": lead_id,
+        "hook_action": "lead.created",
+        "attributes": {
+            "updated_at": "2025-11-28T12:00:00Z",
+            "customer": { "name": name, "email": email, "phone": phone },
+            "product": { "description": null, "prop_ref": null, "price": null },
+            "lead_status": { "alias": "novo", "name": "Novo" },
+            "lead_source": { "name": "Site" },
+            "log": [],
+            "messages": []
+        }
+    })
+}
+
+async fn received(server: &MockServer) -> Vec<Request> {
+    server.received_requests().await.unwrap_or_default()
+}
@@ -0,0 +1,164 @@
+//! Chronological view of everything that happened to a lead
+//!
+//! Support investigations used to mean querying half a dozen tables by hand.
+//! `lead_timeline` merges, in time order:
+//!
+//! - `webhook`: webhook receipts (`webhook_events`)
+//! - `job`: enrichment job status changes (`webhook_event_transitions`, migration 040)
+//! - `provider`: Diretrix / Work API calls, only for sampled or allowlisted
+//!   leads (`debug_payloads`, payloads themselves via the debug endpoint)
+//! - `message`: C2S message sends (`sent_messages`) and held messages (`c2s_outbox`)
+//! - `storage`: enrichment snapshots written to `core.party_enrichments`
+//! - `pending`: deferred enrichment queue (`pending_enrichments`)
+//!
+//! Served by `GET /api/v1/leads/:lead_id/timeline`. Details never include
+//! message bodies or provider payloads.
+
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+
+/// Maximum events returned for a lead
+const MAX_EVENTS: i64 = 500;
+
+/// One entry of the timeline
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    /// `webhook`, `job`, `provider`, `message`, `storage` or `pending`
+    pub source: String,
+    /// What happened, e.g. `job.completed` or `message.sent`
+    pub event: String,
+    pub detail: Value,
+    /// Milliseconds since the first event of the lead
+    #[sqlx(skip)]
+    pub offset_ms: i64,
+}
+
+/// All events of a lead, oldest first
+pub async fn lead_timeline(pool: &PgPool, lead_id: &str) -> Result<Vec<TimelineEvent>, AppError> {
+    let events = sqlx::query_as::<_, TimelineEvent>(TIMELINE_SQL)
+        .bind(lead_id)
+        .bind(MAX_EVENTS)
+        .fetch_all(pool)
+        .timed("lead_timeline.load")
+        .await
+        .context(format!("Failed to load timeline for lead_id: {}", lead_id))?;
+
+    Ok(with_offsets(events))
+}
+
+/// Sort events by time and fill `offset_ms` from the first one
+pub fn with_offsets(mut events: Vec<TimelineEvent>) -> Vec<TimelineEvent> {
+    // Stable sort keeps the query order (receipt before its job) on ties
+    events.sort_by_key(|e| e.at);
+    if let Some(start) = events.first().map(|e| e.at) {
+        for event in &mut events {
+            event.offset_ms = (event.at - start).num_milliseconds();
+        }
+    }
+    events
+}
+
+pub(crate) const TIMELINE_SQL: &str = r#"
+    SELECT at, source, event, detail FROM (
+        SELECT received_at AS at, 'webhook' AS source, 'webhook.received' AS event,
+               jsonb_build_object('hook_action', hook_action, 'updated_at', updated_at) AS detail,
+               0 AS ord
+        FROM webhook_events WHERE lead_id = $1
+        UNION ALL
+        SELECT changed_at, 'job', 'job.' || to_status,
+               jsonb_strip_nulls(jsonb_build_object(
+                   'from', from_status, 'error', error_message, 'updated_at', updated_at
+               )),
+               1
+        FROM webhook_event_transitions WHERE lead_id = $1
+        UNION ALL
+        SELECT created_at, 'provider', 'provider.' || step,
+               jsonb_build_object('step', step, 'sampled', true),
+               2
+        FROM debug_payloads WHERE lead_id = $1
+        UNION ALL
+        SELECT pe.enriched_at, 'storage', 'storage.enrichment_stored',
+               jsonb_build_object(
+                   'party_id', pe.party_id, 'provider', pe.provider,
+                   'quality_score', pe.quality_score
+               ),
+               3
+        FROM core.party_enrichments pe WHERE pe.raw_payload->>'lead_id' = $1
+        UNION ALL
+        SELECT created_at, 'message', 'message.queued',
+               jsonb_strip_nulls(jsonb_build_object(
+                   'send_after', send_after, 'delay_reason', delay_reason, 'status', status
+               )),
+               4
+        FROM c2s_outbox WHERE lead_id = $1
+        UNION ALL
+        SELECT created_at, 'message', 'message.' || status,
+               jsonb_strip_nulls(jsonb_build_object(
+                   'status_code', status_code, 'error', error_message,
+                   'triggered_by', triggered_by, 'body_length', body_length
+               )),
+               5
+        FROM sent_messages WHERE lead_id = $1
+        UNION ALL
+        SELECT created_at, 'pending', 'pending.queued',
+               jsonb_build_object('status', status, 'attempts', attempts),
+               6
+        FROM pending_enrichments WHERE lead_id = $1
+        UNION ALL
+        SELECT enriched_at, 'pending', 'pending.enriched',
+               jsonb_build_object('attempts', attempts),
+               7
+        FROM pending_enrichments WHERE lead_id = $1 AND enriched_at IS NOT NULL
+    ) events
+    ORDER BY at, ord
+    LIMIT $2
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(secs: i64, name: &str) -> TimelineEvent {
+        TimelineEvent {
+            at: DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+            source: name.split('.').next().unwrap().to_string(),
+            event: name.to_string(),
+            detail: json!({}),
+            offset_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_offsets_from_first_event() {
+        let events = with_offsets(vec![
+            event(5, "message.sent"),
+            event(0, "webhook.received"),
+            event(0, "job.received"),
+            event(2, "job.completed"),
+        ]);
+
+        let names: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "webhook.received",
+                "job.received",
+                "job.completed",
+                "message.sent"
+            ]
+        );
+        let offsets: Vec<i64> = events.iter().map(|e| e.offset_ms).collect();
+        assert_eq!(offsets, vec![0, 0, 2000, 5000]);
+    }
+
+    #[test]
+    fn test_empty_timeline() {
+        assert!(with_offsets(vec![]).is_empty());
+    }
+}
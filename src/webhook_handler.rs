@@ -1,72 +1,107 @@
-use crate::errors::AppError;
+use crate::circuit_breaker::Provider;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ErrorContext, ResultExt};
 use crate::handlers::AppState;
-use crate::webhook_models::{WebhookEvent, WebhookPayload, WebhookResponse};
+use crate::leader::Leadership;
+use crate::webhook_models::{WebhookBatch, WebhookEvent, WebhookResponse};
+use crate::webhook_queue::WebhookJob;
 use axum::{
     extract::State,
     http::{HeaderMap, StatusCode},
     Json,
 };
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// C2S Webhook Handler
 ///
 /// Receives webhook events from Contact2Sale (C2S) when leads are created/updated.
 /// Validates the webhook secret, deduplicates events, and triggers background enrichment.
 ///
-/// Expected payload: Single event object OR array of events
+/// Expected payload: Single event object OR array of events (at most
+/// WEBHOOK_MAX_EVENTS are processed; the rest, malformed events and events
+/// not fitting in the enrichment queue are counted as rejected)
 /// Authentication: X-Webhook-Token header must match WEBHOOK_SECRET env var
 pub async fn c2s_webhook(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(payload): Json<WebhookPayload>,
+    Json(payload): Json<Value>,
 ) -> Result<(StatusCode, Json<WebhookResponse>), AppError> {
     tracing::info!("Received C2S webhook");
 
     // 1. Validate webhook secret (if configured)
     validate_webhook_secret(&state, &headers)?;
 
-    // 2. Convert payload to vec of events (handles both single and batch)
-    let events = payload.into_events();
-    let total_received = events.len();
+    // 2. Events of the body (handles both single and batch), up to the limit
+    let batch = WebhookBatch::new(payload, state.config.webhook_max_events);
+    let total_received = batch.received;
     tracing::info!("Processing {} webhook event(s)", total_received);
 
-    let mut processed = 0;
+    let mut accepted = 0;
+    let mut rejected = batch.over_limit;
     let mut duplicates = 0;
+    if rejected > 0 {
+        tracing::warn!(
+            "Webhook batch of {} events exceeds WEBHOOK_MAX_EVENTS={}, rejecting {}",
+            total_received,
+            state.config.webhook_max_events,
+            rejected
+        );
+    }
 
     // 3. Process each event
-    for event in events {
+    for event in batch {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Rejected malformed webhook event: {}", e);
+                rejected += 1;
+                continue;
+            }
+        };
         match process_webhook_event(&state, event).await {
             Ok(ProcessResult::Processed) => {
-                processed += 1;
+                accepted += 1;
             }
             Ok(ProcessResult::Duplicate) => {
                 duplicates += 1;
                 tracing::debug!("Skipped duplicate webhook event");
             }
+            Ok(ProcessResult::QueueFull) => {
+                rejected += 1;
+            }
             Err(e) => {
-                tracing::error!("Failed to process webhook event: {}", e);
+                tracing::error!(
+                    error_chain = ?e.chain(),
+                    "Failed to process webhook event: {}",
+                    e.chain_message()
+                );
                 // Continue processing other events even if one fails
+                rejected += 1;
             }
         }
     }
 
     tracing::info!(
-        "Webhook processing complete: {} received, {} processed, {} duplicates",
+        "Webhook processing complete: {} received, {} accepted, {} rejected, {} duplicates",
         total_received,
-        processed,
+        accepted,
+        rejected,
         duplicates
     );
 
-    // 4. Return 200 immediately (background jobs will handle enrichment)
+    // 4. Return 200 immediately (background workers will handle enrichment)
     Ok((
         StatusCode::OK,
         Json(WebhookResponse {
-            status: "received".to_string(),
+            status: if rejected > 0 { "partial" } else { "received" }.to_string(),
             received: total_received,
-            processed,
+            accepted,
+            rejected,
             duplicates,
         }),
     ))
@@ -121,6 +156,8 @@ fn constant_time_compare(a: &str, b: &str) -> bool {
 enum ProcessResult {
     Processed,
     Duplicate,
+    /// Enrichment queue full, event not stored
+    QueueFull,
 }
 
 /// Parse timestamp string to DateTime<Utc>
@@ -173,22 +210,54 @@ async fn process_webhook_event(
         return Ok(ProcessResult::Duplicate);
     }
 
-    // 2. Store webhook receipt
+    // 2. Check the queue first: a rejected event is not stored, so a
+    //    redelivery is not taken as a duplicate
+    if !state.webhook_queue.has_room(&state.db).await? {
+        tracing::warn!(
+            "Webhook queue full (WEBHOOK_QUEUE_CAPACITY={}), rejecting lead_id={}",
+            state.config.webhook_queue_capacity,
+            lead_id
+        );
+        return Ok(ProcessResult::QueueFull);
+    }
+
+    // 3. Store webhook receipt and its enrichment job together, so a stored
+    //    event is always enriched, even across a restart
     let hook_action = event.hook_action.clone();
     let payload_raw = serde_json::to_value(&event)
         .map_err(|e| AppError::InternalError(format!("Failed to serialize event: {}", e)))?;
 
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .context(ErrorContext::db("webhook_events.begin").id("lead_id", &lead_id))?;
     store_webhook_receipt(
-        &state.db,
+        &mut tx,
         &lead_id,
         &updated_at_ts,
         hook_action.as_deref(),
         payload_raw,
     )
     .await?;
+    let job_id = state
+        .webhook_queue
+        .push(
+            &mut *tx,
+            &WebhookJob {
+                lead_id: lead_id.clone(),
+                updated_at: updated_at_ts,
+                event,
+            },
+        )
+        .await?;
+    tx.commit()
+        .await
+        .context(ErrorContext::db("webhook_events.commit").id("lead_id", &lead_id))?;
 
-    // 3. Spawn background enrichment job
-    spawn_enrichment_job(state.clone(), lead_id.clone(), updated_at_ts, event);
+    // 4. Wake a worker for the background enrichment
+    state.webhook_queue.wake();
+    tracing::debug!("Queued webhook job #{} for lead_id={}", job_id, lead_id);
 
     Ok(ProcessResult::Processed)
 }
@@ -199,129 +268,297 @@ async fn already_processed(
     lead_id: &str,
     updated_at: &DateTime<Utc>,
 ) -> Result<bool, AppError> {
-    let exists = sqlx::query_scalar::<_, bool>(
+    let exists = sqlx::query_scalar!(
         r#"
         SELECT EXISTS(
             SELECT 1 FROM webhook_events
             WHERE lead_id = $1 AND updated_at = $2
-        )
+        ) AS "exists!"
         "#,
+        lead_id,
+        updated_at
     )
-    .bind(lead_id)
-    .bind(updated_at)
     .fetch_one(db)
-    .await?;
+    .timed("webhook_events.exists")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.exists")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
 
     Ok(exists)
 }
 
 /// Store webhook receipt in database
 async fn store_webhook_receipt(
-    db: &PgPool,
+    conn: &mut PgConnection,
     lead_id: &str,
     updated_at: &DateTime<Utc>,
     hook_action: Option<&str>,
     payload_raw: Value,
 ) -> Result<(), AppError> {
-    sqlx::query(
+    sqlx::query!(
         r#"
         INSERT INTO webhook_events (lead_id, updated_at, hook_action, payload_raw, status)
         VALUES ($1, $2, $3, $4, 'received')
         "#,
+        lead_id,
+        updated_at,
+        hook_action,
+        payload_raw
     )
-    .bind(lead_id)
-    .bind(updated_at)
-    .bind(hook_action)
-    .bind(payload_raw)
-    .execute(db)
-    .await?;
+    .execute(conn)
+    .timed("webhook_events.insert")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.insert")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
 
     tracing::debug!("Stored webhook receipt for lead_id={}", lead_id);
     Ok(())
 }
 
-/// Spawn background enrichment job (non-blocking)
+/// Background enrichment of a queued webhook event (run by `webhook_queue` workers)
 ///
-/// This function spawns a tokio task that will:
-/// 1. Mark webhook event as 'processing'
-/// 2. Fetch full lead data from C2S
-/// 3. Extract CPF from customer data
-/// 4. Enrich via Work API
-/// 5. Store in database
-/// 6. Send enriched message back to C2S
-/// 7. Mark webhook event as 'completed' or 'failed'
-/// Spawn background enrichment job (non-blocking)
+/// Returns an error only when the run could not start (database down): the
+/// job is then retried. Enrichment failures are recorded on the event.
 ///
-/// This function spawns a tokio task that will:
+/// This function will:
 /// 1. Mark webhook event as 'processing'
 /// 2. Fetch full lead data from C2S
 /// 3. Extract CPF from customer data
 /// 4. Enrich via Work API
 /// 5. Store in database
 /// 6. Send enriched message back to C2S
-/// 7. Mark webhook event as 'completed' or 'failed'
-fn spawn_enrichment_job(
-    state: Arc<AppState>,
-    lead_id: String,
-    updated_at: DateTime<Utc>,
-    event: WebhookEvent,
-) {
-    tokio::spawn(async move {
-        tracing::info!("Starting background enrichment for lead_id={}", lead_id);
+/// 7. Mark webhook event as 'completed', 'failed', or 'deferred' (provider
+///    circuit breaker open; retried by `spawn_deferred_retry_task`)
+/// 8. Post a status note to the lead when completed or failed (`C2S_STATUS_NOTES`)
+pub(crate) async fn run_enrichment_job(
+    state: &Arc<AppState>,
+    job: WebhookJob,
+) -> Result<(), AppError> {
+    let WebhookJob {
+        lead_id,
+        updated_at,
+        event,
+    } = job;
+    tracing::info!("Starting background enrichment for lead_id={}", lead_id);
+    let tenant = event
+        .attributes
+        .lead_source
+        .as_ref()
+        .and_then(|s| s.name.clone());
+
+    // Update status to processing (with specific updated_at to target correct row)
+    let Some(attempts) = mark_webhook_processing(&state.db, &lead_id, &updated_at).await? else {
+        // Already completed, failed or poisoned (job resumed after its worker
+        // was lost once the run had finished)
+        tracing::warn!(
+            "No webhook event to process for lead_id={}, updated_at={}, skipping",
+            lead_id,
+            updated_at
+        );
+        return Ok(());
+    };
 
-        // Update status to processing (with specific updated_at to target correct row)
-        if let Err(e) = mark_webhook_processing(&state.db, &lead_id, &updated_at).await {
-            tracing::error!("Failed to mark webhook as processing: {}", e);
-            return;
+    // Run full enrichment workflow in its own task, so a panic on a malformed
+    // lead fails this event instead of taking the worker down
+    let workflow = tokio::spawn({
+        let state = state.clone();
+        let lead_id = lead_id.clone();
+        async move { enrich_lead_workflow(&state, &lead_id, event).await }
+    })
+    .await;
+    let (result, retryable) = match workflow {
+        Ok(result) => {
+            let retryable = result.as_ref().is_err_and(|e| e.is_provider_unavailable());
+            (result, retryable)
         }
+        Err(e) => (
+            Err(AppError::InternalError(format!(
+                "Enrichment job panicked: {}",
+                panic_message(e)
+            ))),
+            true,
+        ),
+    };
 
-        // Run full enrichment workflow
-        match enrich_lead_workflow(&state, &lead_id, event).await {
-            Ok(_) => {
-                tracing::info!("Successfully enriched lead_id={}", lead_id);
-                if let Err(e) = mark_webhook_completed(&state.db, &lead_id, &updated_at).await {
-                    tracing::error!("Failed to mark webhook as completed: {}", e);
-                }
+    match result {
+        Ok(_) => {
+            tracing::info!("Successfully enriched lead_id={}", lead_id);
+            if let Err(e) = mark_webhook_completed(&state.db, &lead_id, &updated_at).await {
+                tracing::error!("Failed to mark webhook as completed: {}", e);
             }
-            Err(e) => {
-                tracing::error!("Failed to enrich lead_id={}: {}", lead_id, e);
-                if let Err(e) =
-                    mark_webhook_failed(&state.db, &lead_id, &updated_at, &e.to_string()).await
-                {
-                    tracing::error!("Failed to mark webhook as failed: {}", e);
-                }
+            post_status_note(
+                state,
+                &lead_id,
+                tenant.as_deref(),
+                crate::templates::STATUS_COMPLETED,
+            )
+            .await;
+        }
+        Err(e) if retryable && attempts >= state.config.webhook_max_attempts => {
+            poison_webhook_event(state, &lead_id, &updated_at, attempts, &e).await;
+            post_status_note(
+                state,
+                &lead_id,
+                tenant.as_deref(),
+                crate::templates::STATUS_FAILED,
+            )
+            .await;
+        }
+        Err(e) if retryable => {
+            tracing::warn!(
+                error_chain = ?e.chain(),
+                "Deferring enrichment for lead_id={}: {}",
+                lead_id,
+                e.chain_message()
+            );
+            if let Err(e) =
+                mark_webhook_deferred(&state.db, &lead_id, &updated_at, &e.chain_message()).await
+            {
+                tracing::error!("Failed to mark webhook as deferred: {}", e);
             }
         }
-    });
+        Err(e) => {
+            tracing::error!(
+                error_chain = ?e.chain(),
+                "Failed to enrich lead_id={}: {}",
+                lead_id,
+                e.chain_message()
+            );
+            if let Err(e) =
+                mark_webhook_failed(&state.db, &lead_id, &updated_at, &e.chain_message()).await
+            {
+                tracing::error!("Failed to mark webhook as failed: {}", e);
+            }
+            post_status_note(
+                state,
+                &lead_id,
+                tenant.as_deref(),
+                crate::templates::STATUS_FAILED,
+            )
+            .await;
+        }
+    }
+
+    Ok(())
 }
 
-/// Mark webhook event as processing (scoped by lead_id AND updated_at)
-async fn mark_webhook_processing(
-    db: &PgPool,
+/// Message of a panicked enrichment task
+fn panic_message(error: tokio::task::JoinError) -> String {
+    match error.try_into_panic() {
+        Ok(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string()),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Give up on an event that kept failing: mark it 'poisoned' and raise an alert
+///
+/// Poisoned events are never re-queued. The alert is an error log carrying
+/// the whole error chain (`alert = "webhook_poisoned"`) and the
+/// `poisoned_events_total` counter.
+async fn poison_webhook_event(
+    state: &AppState,
     lead_id: &str,
     updated_at: &DateTime<Utc>,
-) -> Result<(), AppError> {
+    attempts: i32,
+    error: &AppError,
+) {
+    let chain = error.chain();
+    tracing::error!(
+        alert = "webhook_poisoned",
+        %lead_id,
+        attempts,
+        error_chain = ?chain,
+        "Webhook event for lead_id={} poisoned after {} attempts: {}",
+        lead_id,
+        attempts,
+        error.chain_message()
+    );
+    crate::metrics::record_poisoned_event("c2s_webhook");
+
     let result = sqlx::query(
         r#"
         UPDATE webhook_events
-        SET status = 'processing', updated_at_ts = now()
-        WHERE lead_id = $1 AND updated_at = $2 AND status = 'received'
+        SET status = 'poisoned', error_message = $3, updated_at_ts = now()
+        WHERE lead_id = $1 AND updated_at = $2 AND status = 'processing'
         "#,
     )
     .bind(lead_id)
     .bind(updated_at)
-    .execute(db)
-    .await?;
+    .bind(error.chain_message())
+    .execute(&state.db)
+    .timed("webhook_events.mark_poisoned")
+    .await;
+    if let Err(e) = result {
+        tracing::error!("Failed to mark webhook as poisoned: {}", e);
+    }
+}
 
-    if result.rows_affected() == 0 {
-        tracing::warn!(
-            "No webhook event found to mark as processing: lead_id={}, updated_at={}",
-            lead_id,
-            updated_at
-        );
+/// Post a short status note to the lead (non-fatal, only with `C2S_STATUS_NOTES`)
+///
+/// Skipped during the tenant's quiet hours: the enrichment message itself is
+/// then queued, and a note ahead of it would break the silence.
+async fn post_status_note(state: &AppState, lead_id: &str, tenant: Option<&str>, template: &str) {
+    if !state.config.c2s_status_notes {
+        return;
+    }
+    if state
+        .config
+        .quiet_hours
+        .deferred_until(tenant, Utc::now())
+        .is_some()
+    {
+        tracing::debug!("Quiet hours, no status note for lead_id={}", lead_id);
+        return;
     }
 
-    Ok(())
+    let note = crate::templates::render(template, &[]);
+    if let Err(e) =
+        crate::lead_messages::send_deduplicated(state, lead_id, &note, "status_note").await
+    {
+        tracing::warn!("Failed to post status note to lead_id={}: {}", lead_id, e);
+    }
+}
+
+/// Mark webhook event as processing (scoped by lead_id AND updated_at)
+///
+/// An event already 'processing' belongs to a job resumed after its worker
+/// was lost. Returns the number of runs of the event, this one included, or
+/// `None` when the event is not waiting for a run.
+async fn mark_webhook_processing(
+    db: &PgPool,
+    lead_id: &str,
+    updated_at: &DateTime<Utc>,
+) -> Result<Option<i32>, AppError> {
+    let attempts: Option<(i32,)> = sqlx::query_as(
+        r#"
+        UPDATE webhook_events
+        SET status = 'processing', attempts = attempts + 1, updated_at_ts = now()
+        WHERE lead_id = $1 AND updated_at = $2 AND status IN ('received', 'processing')
+        RETURNING attempts
+        "#,
+    )
+    .bind(lead_id)
+    .bind(updated_at)
+    .fetch_optional(db)
+    .timed("webhook_events.mark_processing")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.mark_processing")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
+
+    Ok(attempts.map(|(attempts,)| attempts))
 }
 
 /// Mark webhook event as completed (scoped by lead_id AND updated_at)
@@ -330,17 +567,23 @@ async fn mark_webhook_completed(
     lead_id: &str,
     updated_at: &DateTime<Utc>,
 ) -> Result<(), AppError> {
-    let result = sqlx::query(
+    let result = sqlx::query!(
         r#"
         UPDATE webhook_events
         SET status = 'completed', processed_at = now(), updated_at_ts = now()
         WHERE lead_id = $1 AND updated_at = $2 AND status = 'processing'
         "#,
+        lead_id,
+        updated_at
     )
-    .bind(lead_id)
-    .bind(updated_at)
     .execute(db)
-    .await?;
+    .timed("webhook_events.mark_completed")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.mark_completed")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
 
     if result.rows_affected() == 0 {
         tracing::warn!(
@@ -360,18 +603,24 @@ async fn mark_webhook_failed(
     updated_at: &DateTime<Utc>,
     error_message: &str,
 ) -> Result<(), AppError> {
-    let result = sqlx::query(
+    let result = sqlx::query!(
         r#"
         UPDATE webhook_events
         SET status = 'failed', error_message = $2, updated_at_ts = now()
         WHERE lead_id = $1 AND updated_at = $3 AND status = 'processing'
         "#,
+        lead_id,
+        error_message,
+        updated_at
     )
-    .bind(lead_id)
-    .bind(error_message)
-    .bind(updated_at)
     .execute(db)
-    .await?;
+    .timed("webhook_events.mark_failed")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.mark_failed")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
 
     if result.rows_affected() == 0 {
         tracing::warn!(
@@ -384,6 +633,335 @@ async fn mark_webhook_failed(
     Ok(())
 }
 
+/// Mark webhook event as deferred (scoped by lead_id AND updated_at)
+async fn mark_webhook_deferred(
+    db: &PgPool,
+    lead_id: &str,
+    updated_at: &DateTime<Utc>,
+    error_message: &str,
+) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE webhook_events
+        SET status = 'deferred', error_message = $2, updated_at_ts = now()
+        WHERE lead_id = $1 AND updated_at = $3 AND status = 'processing'
+        "#,
+        lead_id,
+        error_message,
+        updated_at
+    )
+    .execute(db)
+    .timed("webhook_events.mark_deferred")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.mark_deferred")
+            .id("lead_id", lead_id)
+            .id("updated_at", updated_at),
+    )?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!(
+            "No webhook event found to mark as deferred: lead_id={}, updated_at={}",
+            lead_id,
+            updated_at
+        );
+    }
+
+    Ok(())
+}
+
+/// Interval between retries of deferred webhook events
+const DEFERRED_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum deferred events re-queued per retry tick
+const DEFERRED_RETRY_BATCH: i64 = 20;
+
+/// Spawn the background task that retries deferred webhook events
+///
+/// Events are deferred while the Work API or Diretrix breaker is open (or
+/// after a crashed enrichment job), up to WEBHOOK_MAX_ATTEMPTS runs. Once
+/// both breakers let calls through again, deferred events are moved back to
+/// 'received' and queued for the normal enrichment job. Runs on the
+/// instance holding the `deferred_webhook_retry` leadership.
+pub fn spawn_deferred_retry_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let leader = Leadership::new("deferred_webhook_retry");
+        let mut interval = tokio::time::interval(DEFERRED_RETRY_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            if !leader.is_leader(&state.db).await {
+                continue;
+            }
+
+            if !Provider::WorkApi.breaker().is_call_permitted()
+                || !Provider::Diretrix.breaker().is_call_permitted()
+            {
+                tracing::debug!("Providers still unavailable, keeping webhooks deferred");
+                continue;
+            }
+
+            match requeue_deferred_events(&state, DEFERRED_RETRY_BATCH).await {
+                Ok(0) => {}
+                Ok(queued) => tracing::info!("Retrying {} deferred webhook event(s)", queued),
+                Err(e) => tracing::error!("Failed to re-queue deferred webhooks: {}", e),
+            }
+        }
+    });
+}
+
+/// Most recent webhook event received for a lead, if any
+pub(crate) async fn latest_webhook_event(
+    db: &PgPool,
+    lead_id: &str,
+) -> Result<Option<WebhookEvent>, AppError> {
+    let payload: Option<(Value,)> = sqlx::query_as(
+        r#"
+        SELECT payload_raw FROM webhook_events
+        WHERE lead_id = $1 AND payload_raw IS NOT NULL
+        ORDER BY received_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(lead_id)
+    .fetch_optional(db)
+    .timed("webhook_events.latest_for_lead")
+    .await
+    .context(ErrorContext::db("webhook_events.latest_for_lead").id("lead_id", lead_id))?;
+
+    Ok(payload.and_then(|(payload,)| serde_json::from_value(payload).ok()))
+}
+
+/// Move the oldest deferred events back to 'received' and queue their jobs
+///
+/// Returns the number of events queued.
+async fn requeue_deferred_events(state: &AppState, limit: i64) -> Result<usize, AppError> {
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .context(ErrorContext::db("webhook_events.begin"))?;
+    let rows = sqlx::query!(
+        r#"
+        UPDATE webhook_events
+        SET status = 'received', updated_at_ts = now()
+        WHERE id IN (
+            SELECT id FROM webhook_events
+            WHERE status = 'deferred'
+              AND payload_raw IS NOT NULL
+            ORDER BY received_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING lead_id, updated_at, payload_raw AS "payload_raw!"
+        "#,
+        limit
+    )
+    .fetch_all(&mut *tx)
+    .timed("webhook_events.requeue_deferred")
+    .await
+    .context(ErrorContext::db("webhook_events.requeue_deferred").id("limit", limit))?;
+
+    let rows = rows
+        .into_iter()
+        .map(|row| (row.lead_id, row.updated_at, row.payload_raw))
+        .collect();
+    let queued = queue_stored_events(state, &mut tx, rows).await?;
+
+    tx.commit()
+        .await
+        .context(ErrorContext::db("webhook_events.requeue_deferred_commit"))?;
+    for _ in 0..queued {
+        state.webhook_queue.wake();
+    }
+    Ok(queued)
+}
+
+/// Statuses of the events a manual retry may re-run
+const RETRYABLE_STATUSES: &[&str] = &["failed", "poisoned"];
+
+/// Default and maximum number of events of a bulk retry
+const RETRY_DEFAULT_LIMIT: i64 = 100;
+const RETRY_MAX_LIMIT: i64 = 500;
+
+/// Events to re-run (`POST /api/v1/admin/webhooks/retry`)
+///
+/// Either a single event (`id`, failed or poisoned), or the oldest events of
+/// `status` received in `[from, to)`.
+#[derive(Debug, Default, Deserialize)]
+pub struct WebhookRetryRequest {
+    pub id: Option<i64>,
+    /// failed (default) or poisoned
+    pub status: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Number of events to retry (default 100, max 500)
+    pub limit: Option<i64>,
+}
+
+/// Outcome of a manual retry
+#[derive(Debug, Serialize)]
+pub struct WebhookRetryOutcome {
+    /// Events queued for enrichment
+    pub queued: usize,
+    /// Events whose stored payload is not a valid event (left 'failed')
+    pub invalid: usize,
+}
+
+/// Move failed or poisoned events back to 'received' and queue their jobs
+///
+/// The events get a fresh `attempts` budget, and `retry_count` and
+/// `last_retried_at` record the retry. Events whose payload was pruned or
+/// anonymized cannot be re-run and are left out. The retry is bounded by the
+/// room left in the webhook queue.
+pub async fn retry_webhook_events(
+    state: &AppState,
+    request: &WebhookRetryRequest,
+) -> Result<WebhookRetryOutcome, AppError> {
+    let statuses: Vec<&str> = match (request.id, request.status.as_deref()) {
+        (_, Some(status)) if RETRYABLE_STATUSES.contains(&status) => vec![status],
+        (_, Some(status)) => {
+            return Err(AppError::BadRequest(format!(
+                "Cannot retry '{}' webhook events (failed or poisoned)",
+                status
+            )))
+        }
+        (Some(_), None) => RETRYABLE_STATUSES.to_vec(),
+        (None, None) => vec!["failed"],
+    };
+    if let (Some(from), Some(to)) = (request.from, request.to) {
+        if from >= to {
+            return Err(AppError::BadRequest("from must be before to".to_string()));
+        }
+    }
+    let room =
+        state.config.webhook_queue_capacity as i64 - state.webhook_queue.backlog(&state.db).await?;
+    if room <= 0 {
+        return Err(AppError::Conflict(
+            "Webhook queue full, retry once it has drained".to_string(),
+        ));
+    }
+    let limit = request
+        .limit
+        .unwrap_or(RETRY_DEFAULT_LIMIT)
+        .clamp(1, RETRY_MAX_LIMIT)
+        .min(room);
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .context(ErrorContext::db("webhook_events.begin"))?;
+    let rows: Vec<(String, DateTime<Utc>, Value)> = sqlx::query_as(
+        r#"
+        UPDATE webhook_events
+        SET status = 'received', attempts = 0, retry_count = retry_count + 1,
+            last_retried_at = now(), updated_at_ts = now()
+        WHERE id IN (
+            SELECT id FROM webhook_events
+            WHERE status = ANY($1)
+              AND ($2::BIGINT IS NULL OR id = $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR received_at >= $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR received_at < $4)
+              AND payload_raw IS NOT NULL
+              AND anonymized_at IS NULL
+            ORDER BY received_at
+            LIMIT $5
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING lead_id, updated_at, payload_raw
+        "#,
+    )
+    .bind(&statuses)
+    .bind(request.id)
+    .bind(request.from)
+    .bind(request.to)
+    .bind(limit)
+    .fetch_all(&mut *tx)
+    .timed("webhook_events.retry")
+    .await
+    .context(
+        ErrorContext::db("webhook_events.retry")
+            .id("statuses", statuses.join(","))
+            .id("limit", limit),
+    )?;
+
+    if rows.is_empty() {
+        if let Some(id) = request.id {
+            return Err(AppError::NotFound(format!(
+                "No {} webhook event {} with a stored payload",
+                statuses.join(" or "),
+                id
+            )));
+        }
+    }
+    let total = rows.len();
+    let queued = queue_stored_events(state, &mut tx, rows).await?;
+    tx.commit()
+        .await
+        .context(ErrorContext::db("webhook_events.retry_commit"))?;
+    for _ in 0..queued {
+        state.webhook_queue.wake();
+    }
+    Ok(WebhookRetryOutcome {
+        queued,
+        invalid: total - queued,
+    })
+}
+
+/// Queue the jobs of events moved back to 'received', in the caller's transaction
+///
+/// An event whose stored payload is not a valid event is marked 'failed'.
+/// Returns the number of events queued; call `wake` once committed.
+async fn queue_stored_events(
+    state: &AppState,
+    tx: &mut PgConnection,
+    rows: Vec<(String, DateTime<Utc>, Value)>,
+) -> Result<usize, AppError> {
+    let mut queued = 0;
+    for (lead_id, updated_at, payload_raw) in rows {
+        match serde_json::from_value::<WebhookEvent>(payload_raw) {
+            Ok(event) => {
+                let job = WebhookJob {
+                    lead_id,
+                    updated_at,
+                    event,
+                };
+                state.webhook_queue.push(&mut *tx, &job).await?;
+                queued += 1;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Stored webhook payload for lead_id={} is not a valid event: {}",
+                    lead_id,
+                    e
+                );
+                sqlx::query!(
+                    r#"
+                    UPDATE webhook_events
+                    SET status = 'failed', error_message = $3, updated_at_ts = now()
+                    WHERE lead_id = $1 AND updated_at = $2
+                    "#,
+                    &lead_id,
+                    updated_at,
+                    format!("Invalid stored payload: {}", e)
+                )
+                .execute(&mut *tx)
+                .timed("webhook_events.mark_invalid")
+                .await
+                .context(
+                    ErrorContext::db("webhook_events.mark_invalid")
+                        .id("lead_id", &lead_id)
+                        .id("updated_at", updated_at),
+                )?;
+            }
+        }
+    }
+    Ok(queued)
+}
+
 /// Full enrichment workflow for webhook events
 ///
 /// This function orchestrates the complete enrichment process:
@@ -399,6 +977,9 @@ async fn enrich_lead_workflow(
 ) -> Result<(), AppError> {
     tracing::info!("Starting enrichment workflow for lead_id={}", lead_id);
 
+    // Message headers in the lead's language (English/Spanish leads)
+    let locale = crate::language::lead_locale(event.attributes.lead_texts());
+
     // Extract customer data from webhook
     let customer = event
         .attributes
@@ -416,21 +997,88 @@ async fn enrich_lead_workflow(
         email
     );
 
-    // Run full enrichment workflow using shared module
+    // Property references are stored even if enrichment fails later
+    let product = event.attributes.product.as_ref();
+    let texts: Vec<&str> = product
+        .and_then(|p| p.description.as_deref())
+        .into_iter()
+        .chain(
+            event
+                .attributes
+                .messages
+                .iter()
+                .filter_map(|m| m.message.as_deref()),
+        )
+        .collect();
+    let property_refs = crate::property_refs::extract_property_refs(
+        product.and_then(|p| p.prop_ref.as_deref()),
+        &texts,
+    );
+    if !property_refs.is_empty() {
+        tracing::info!(
+            "Lead {} mentions {} property reference(s)",
+            lead_id,
+            property_refs.len()
+        );
+        if let Err(e) =
+            crate::property_refs::store_property_refs(&state.db, lead_id, &property_refs).await
+        {
+            tracing::warn!("Failed to store property references: {}", e);
+        }
+    }
+
+    // Run full enrichment workflow using shared module (tenant = lead source, for quiet hours)
+    let source = event
+        .attributes
+        .lead_source
+        .as_ref()
+        .and_then(|s| s.name.as_deref());
+    // Leads of a source routed to another C2S account are answered through it
+    let account = crate::c2s_accounts::select_account(
+        &state.config.c2s_account_rules,
+        &crate::c2s_accounts::LeadOrigin {
+            source,
+            ..Default::default()
+        },
+    );
+    if let Err(e) = crate::c2s_accounts::record_lead_account(&state.db, lead_id, account).await {
+        tracing::warn!("{}", e);
+    }
     let result = crate::enrichment::enrich_and_send_workflow(
         state.clone(),
         lead_id,
         customer_name,
         phone,
         email,
+        source,
+        locale,
     )
-    .await?;
+    .await
+    .context(
+        ErrorContext::new("enrichment.enrich_and_send")
+            .id("lead_id", lead_id)
+            .id("source", source.unwrap_or("-")),
+    )?;
 
     tracing::info!(
-        "Enrichment complete: {} CPFs enriched, {} stored in DB",
+        "Enrichment complete: {} CPFs enriched, {} stored in DB (diretrix {}ms, work_api {}ms, c2s {}ms, db {}ms)",
         result.cpfs_enriched.len(),
-        result.stored_count
+        result.stored_count,
+        result.timings.diretrix_ms,
+        result.timings.work_api_ms,
+        result.timings.c2s_send_ms,
+        result.timings.db_ms
     );
 
+    // Apply routing rules (e.g. SMS alert to the assigned seller)
+    if let Some(summary) = result.summary.as_ref() {
+        let seller_phone = event
+            .attributes
+            .seller
+            .as_ref()
+            .and_then(|s| s.phone.as_deref());
+        crate::routing::apply_routing(state, source, seller_phone, summary).await;
+    }
+
     Ok(())
 }
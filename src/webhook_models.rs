@@ -1,24 +1,44 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-/// C2S Webhook Event - can be single object or array
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(untagged)]
-pub enum WebhookPayload {
-    Single(WebhookEvent),
-    Batch(Vec<WebhookEvent>),
-}
-
-impl WebhookPayload {
-    /// Convert to a vec of events for uniform processing
-    pub fn into_events(self) -> Vec<WebhookEvent> {
-        match self {
-            WebhookPayload::Single(event) => vec![event],
-            WebhookPayload::Batch(events) => events,
+/// Events of a C2S webhook body (a single event object or an array of events)
+///
+/// Only the first `max_events` of a batch are kept, and events are
+/// deserialized one at a time while iterating: a malformed event only fails
+/// itself, and a huge batch is never copied into typed events all at once.
+#[derive(Debug)]
+pub struct WebhookBatch {
+    events: std::vec::IntoIter<Value>,
+    /// Events in the body
+    pub received: usize,
+    /// Events past `max_events`, dropped without processing
+    pub over_limit: usize,
+}
+
+impl WebhookBatch {
+    pub fn new(body: Value, max_events: usize) -> Self {
+        let mut events = match body {
+            Value::Array(events) => events,
+            event => vec![event],
+        };
+        let received = events.len();
+        events.truncate(max_events);
+        Self {
+            received,
+            over_limit: received - events.len(),
+            events: events.into_iter(),
         }
     }
 }
 
+impl Iterator for WebhookBatch {
+    type Item = Result<WebhookEvent, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next().map(serde_json::from_value)
+    }
+}
+
 /// Individual webhook event from C2S
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebhookEvent {
@@ -48,9 +68,21 @@ pub struct WebhookAttributes {
     /// Product/property information
     pub product: Option<WebhookProduct>,
 
+    /// Free text of the lead (form message, first contact)
+    #[serde(default)]
+    pub description: Option<String>,
+
     /// Lead status
     pub lead_status: Option<WebhookLeadStatus>,
 
+    /// Assigned seller
+    #[serde(default)]
+    pub seller: Option<WebhookSeller>,
+
+    /// Lead source (e.g., "Google Ads", "Site")
+    #[serde(default)]
+    pub lead_source: Option<WebhookLeadSource>,
+
     /// Log entries
     #[serde(default)]
     pub log: Vec<WebhookLogEntry>,
@@ -64,6 +96,16 @@ pub struct WebhookAttributes {
     pub raw: Value,
 }
 
+impl WebhookAttributes {
+    /// Texts written by the lead (description, then messages), for language detection
+    pub fn lead_texts(&self) -> impl Iterator<Item = &str> {
+        self.description
+            .as_deref()
+            .into_iter()
+            .chain(self.messages.iter().filter_map(|m| m.message.as_deref()))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebhookCustomer {
     pub name: Option<String>,
@@ -96,6 +138,27 @@ pub struct WebhookLeadStatus {
     pub raw: Value,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSeller {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+
+    /// Raw seller data
+    #[serde(flatten)]
+    pub raw: Value,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookLeadSource {
+    pub name: Option<String>,
+
+    /// Raw source data
+    #[serde(flatten)]
+    pub raw: Value,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebhookLogEntry {
     pub created_at: Option<String>,
@@ -120,9 +183,13 @@ pub struct WebhookMessage {
 /// Response sent back to C2S webhook
 #[derive(Debug, Serialize)]
 pub struct WebhookResponse {
+    /// "received", or "partial" when some events were rejected
     pub status: String,
     pub received: usize,
-    pub processed: usize,
+    /// Queued for background enrichment
+    pub accepted: usize,
+    /// Over the batch limit, malformed, or not queued (queue full)
+    pub rejected: usize,
     pub duplicates: usize,
 }
 
@@ -141,10 +208,11 @@ impl IdempotencyKey {
             updated_at,
         }
     }
+}
 
-    #[allow(dead_code)]
-    pub fn to_string(&self) -> String {
-        format!("{}:{}", self.lead_id, self.updated_at)
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.lead_id, self.updated_at)
     }
 }
 
@@ -168,14 +236,12 @@ mod tests {
         }
         "#;
 
-        let payload: WebhookPayload = serde_json::from_str(json).unwrap();
-        match payload {
-            WebhookPayload::Single(event) => {
-                assert_eq!(event.id, "test123");
-                assert_eq!(event.hook_action, Some("lead.created".to_string()));
-            }
-            _ => panic!("Expected single event"),
-        }
+        let mut batch = WebhookBatch::new(serde_json::from_str(json).unwrap(), 10);
+        assert_eq!((batch.received, batch.over_limit), (1, 0));
+        let event = batch.next().unwrap().unwrap();
+        assert_eq!(event.id, "test123");
+        assert_eq!(event.hook_action, Some("lead.created".to_string()));
+        assert!(batch.next().is_none());
     }
 
     #[test]
@@ -193,12 +259,25 @@ mod tests {
         ]
         "#;
 
-        let payload: WebhookPayload = serde_json::from_str(json).unwrap();
-        match payload {
-            WebhookPayload::Batch(events) => {
-                assert_eq!(events.len(), 2);
-            }
-            _ => panic!("Expected batch events"),
-        }
+        let batch = WebhookBatch::new(serde_json::from_str(json).unwrap(), 10);
+        assert_eq!(batch.received, 2);
+        let events: Vec<_> = batch.collect::<Result<_, _>>().unwrap();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_batch_limit_and_malformed_events() {
+        let body = serde_json::json!([
+            {"id": "ok", "attributes": {}},
+            {"attributes": {}},
+            {"id": "over", "attributes": {}}
+        ]);
+
+        let batch = WebhookBatch::new(body, 2);
+        assert_eq!((batch.received, batch.over_limit), (3, 1));
+        let events: Vec<_> = batch.collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap().id, "ok");
+        assert!(events[1].is_err());
     }
 }
@@ -0,0 +1,195 @@
+//! Durable queue of C2S webhook enrichments
+//!
+//! Accepted webhook events are stored as `webhook_enrichment` jobs (see
+//! `jobs`) in the transaction storing the event, and enriched in the
+//! background by a fixed number of workers (`WEBHOOK_WORKERS`) claiming them
+//! with `FOR UPDATE SKIP LOCKED`. An enrichment interrupted by a restart is
+//! picked up again, by this instance or another one. When
+//! `WEBHOOK_QUEUE_CAPACITY` jobs are already waiting the webhook handler
+//! rejects new events before storing them, so a redelivery of the same event
+//! is processed normally (the check is approximate under concurrent batches).
+
+use crate::errors::AppError;
+use crate::handlers::AppState;
+use crate::jobs::{self, WEBHOOK_ENRICHMENT};
+use crate::webhook_models::WebhookEvent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Idle workers look for jobs queued by other instances at this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval between sweeps of abandoned and old completed jobs
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// A stored webhook event waiting for enrichment (payload of its job)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookJob {
+    pub lead_id: String,
+    pub updated_at: DateTime<Utc>,
+    pub event: WebhookEvent,
+}
+
+/// Handle on the queue (cheap to clone)
+#[derive(Debug, Clone)]
+pub struct WebhookQueue {
+    capacity: usize,
+    max_attempts: i32,
+    /// Wakes an idle worker of this instance when a job is queued
+    notify: Arc<Notify>,
+}
+
+impl WebhookQueue {
+    /// Queue holding up to `capacity` waiting jobs, each run up to `max_attempts` times
+    pub fn new(capacity: usize, max_attempts: i32) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_attempts,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Whether another job fits in the queue
+    pub async fn has_room(&self, db: &PgPool) -> Result<bool, AppError> {
+        Ok(self.backlog(db).await? < self.capacity as i64)
+    }
+
+    /// Jobs waiting for or held by a worker, on any instance
+    pub async fn backlog(&self, db: &PgPool) -> Result<i64, AppError> {
+        jobs::backlog(db, WEBHOOK_ENRICHMENT).await
+    }
+
+    /// Store a job (in the caller's transaction when given one)
+    ///
+    /// Call `wake` once the transaction is committed.
+    pub async fn push<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        job: &WebhookJob,
+    ) -> Result<i64, AppError> {
+        let payload = serde_json::to_value(job)
+            .map_err(|e| AppError::InternalError(format!("Failed to serialize job: {}", e)))?;
+        jobs::enqueue(
+            executor,
+            WEBHOOK_ENRICHMENT,
+            &payload,
+            self.max_attempts,
+            None,
+        )
+        .await
+    }
+
+    /// Wake an idle worker after jobs were queued
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+/// Spawn the workers enriching queued webhook events
+pub fn spawn_workers(state: Arc<AppState>, workers: usize) {
+    for index in 0..workers.max(1) {
+        let state = state.clone();
+        let worker = jobs::worker_id(index);
+        tokio::spawn(async move {
+            loop {
+                match jobs::claim(&state.db, WEBHOOK_ENRICHMENT, &worker).await {
+                    Ok(Some(job)) => run_job(&state, job).await,
+                    Ok(None) => {
+                        let notified = state.webhook_queue.notify.notified();
+                        let _ = tokio::time::timeout(POLL_INTERVAL, notified).await;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to claim a webhook job: {}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match jobs::sweep(&state.db, WEBHOOK_ENRICHMENT).await {
+                Ok(0) => {}
+                Ok(failed) => tracing::error!(
+                    alert = "webhook_job_failed",
+                    "{} webhook job(s) failed: worker lost on every run",
+                    failed
+                ),
+                Err(e) => tracing::warn!("Failed to sweep webhook jobs: {}", e),
+            }
+        }
+    });
+    tracing::info!("✓ {} webhook enrichment worker(s) started", workers.max(1));
+}
+
+/// Run one claimed job and record its outcome
+async fn run_job(state: &Arc<AppState>, job: jobs::Job) {
+    let webhook_job = match serde_json::from_value::<WebhookJob>(job.payload.clone()) {
+        Ok(webhook_job) => webhook_job,
+        Err(e) => {
+            tracing::error!("Webhook job #{} has an invalid payload: {}", job.id, e);
+            let error = format!("Invalid job payload: {}", e);
+            if let Err(e) = jobs::fail(&state.db, &job, &error).await {
+                tracing::error!("Failed to mark webhook job #{} as failed: {}", job.id, e);
+            }
+            return;
+        }
+    };
+    if job.attempts > 1 {
+        tracing::info!(
+            "Resuming webhook job #{} for lead_id={} (run {}/{})",
+            job.id,
+            webhook_job.lead_id,
+            job.attempts,
+            job.max_attempts
+        );
+    }
+
+    let run = crate::webhook_handler::run_enrichment_job(state, webhook_job);
+    let result = match jobs::with_heartbeat(&state.db, &job, run).await {
+        Ok(()) => jobs::complete(&state.db, &job, None).await.map(|_| ()),
+        Err(e) => {
+            tracing::warn!(
+                error_chain = ?e.chain(),
+                "Webhook job #{} failed (run {}/{}): {}",
+                job.id,
+                job.attempts,
+                job.max_attempts,
+                e.chain_message()
+            );
+            jobs::retry_or_fail(&state.db, &job, &e).await.map(|_| ())
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("Failed to record outcome of webhook job #{}: {}", job.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_job_payload_roundtrip() {
+        let job = WebhookJob {
+            lead_id: "lead-1".to_string(),
+            updated_at: "2025-11-28T12:00:00Z".parse().unwrap(),
+            event: serde_json::from_value(json!({"id": "lead-1", "attributes": {}})).unwrap(),
+        };
+        let payload = serde_json::to_value(&job).unwrap();
+        assert_eq!(payload["lead_id"], "lead-1");
+
+        let decoded: WebhookJob = serde_json::from_value(payload).unwrap();
+        assert_eq!(decoded.lead_id, "lead-1");
+        assert_eq!(decoded.updated_at, job.updated_at);
+        assert_eq!(decoded.event.id, "lead-1");
+    }
+}
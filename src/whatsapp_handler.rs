@@ -0,0 +1,461 @@
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{
+    c2s_accounts,
+    db::TimedQuery,
+    enrichment::validate_br_phone,
+    errors::{AppError, ErrorContext, ResultExt},
+    google_ads_handler::perform_inline_enrichment,
+    whatsapp_models::{InboundWhatsAppMessage, WhatsAppWebhookPayload},
+};
+
+/// Only one C2S lead is created per WhatsApp sender within this window
+const CONVERSATION_WINDOW_HOURS: i32 = 24;
+
+/// A 'processing' claim older than this is treated as abandoned (the process
+/// died mid-flight) and may be taken over by Meta's next redelivery
+const CLAIM_TIMEOUT_MINUTES: i32 = 10;
+
+/// Query parameters for Meta webhook subscription verification
+#[derive(Debug, Deserialize)]
+pub struct WhatsAppVerifyQuery {
+    #[serde(rename = "hub.mode")]
+    mode: Option<String>,
+    #[serde(rename = "hub.verify_token")]
+    verify_token: Option<String>,
+    #[serde(rename = "hub.challenge")]
+    challenge: Option<String>,
+}
+
+/// Response for WhatsApp webhook
+#[derive(Debug, Serialize)]
+pub struct WhatsAppWebhookResponse {
+    pub success: bool,
+    pub message: String,
+    pub c2s_lead_ids: Vec<String>,
+}
+
+/// Meta webhook subscription verification (GET)
+///
+/// Meta calls this once when the webhook URL is configured and expects the
+/// `hub.challenge` value echoed back when `hub.verify_token` matches.
+pub async fn whatsapp_verify_handler(
+    State(app_state): State<std::sync::Arc<crate::handlers::AppState>>,
+    Query(query): Query<WhatsAppVerifyQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let expected = app_state
+        .config
+        .whatsapp_verify_token
+        .as_deref()
+        .ok_or_else(|| {
+            AppError::InternalError("WHATSAPP_VERIFY_TOKEN not configured (required)".to_string())
+        })?;
+
+    if query.mode.as_deref() != Some("subscribe") || query.verify_token.as_deref() != Some(expected)
+    {
+        tracing::error!("❌ Invalid WhatsApp webhook verification request");
+        return Err(AppError::Unauthorized(
+            "Invalid hub.verify_token".to_string(),
+        ));
+    }
+
+    tracing::info!("✓ WhatsApp webhook subscription verified");
+    Ok((StatusCode::OK, query.challenge.unwrap_or_default()))
+}
+
+/// WhatsApp Business inbound message webhook (POST)
+///
+/// Flow:
+/// 1. Validate X-Hub-Signature-256 (HMAC-SHA256 of raw body with app secret)
+/// 2. Extract inbound messages (status updates are ignored)
+/// 3. Deduplicate by message ID and by sender within the conversation window
+/// 4. Validate and normalize sender phone
+/// 5. Inline enrichment: phone → CPF (cache/DB, Diretrix) → Work API
+/// 6. Create lead in C2S with message + enrichment in description
+/// 7. Store tracking record in database
+///
+/// Fallback: If enrichment fails, still create lead with warning
+pub async fn whatsapp_webhook_handler(
+    State(app_state): State<std::sync::Arc<crate::handlers::AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    // Step 1: Validate signature BEFORE parsing the payload
+    let signature = headers
+        .get("x-hub-signature-256")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing X-Hub-Signature-256".to_string()))?;
+    validate_signature(
+        app_state.config.whatsapp_app_secret.as_deref(),
+        &body,
+        signature,
+    )?;
+
+    let payload: WhatsAppWebhookPayload = serde_json::from_slice(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid WhatsApp payload: {}", e)))?;
+
+    // Step 2: Extract inbound messages
+    let messages = payload.inbound_messages();
+    if messages.is_empty() {
+        tracing::debug!("WhatsApp webhook without inbound messages (status update)");
+        return Ok((
+            StatusCode::OK,
+            Json(WhatsAppWebhookResponse {
+                success: true,
+                message: "No inbound messages".to_string(),
+                c2s_lead_ids: vec![],
+            }),
+        ));
+    }
+
+    tracing::info!("📨 Received {} WhatsApp message(s)", messages.len());
+
+    let mut c2s_lead_ids = Vec::new();
+    for message in &messages {
+        match process_inbound_message(&app_state, message).await {
+            Ok(Some(c2s_lead_id)) => c2s_lead_ids.push(c2s_lead_id),
+            Ok(None) => {}
+            Err(e) => {
+                // Meta retries non-2xx responses; fail so the message is redelivered
+                tracing::error!(
+                    "❌ Failed to process WhatsApp message {}: {}",
+                    message.message_id,
+                    e
+                );
+                return Err(e);
+            }
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(WhatsAppWebhookResponse {
+            success: true,
+            message: format!("{} lead(s) created", c2s_lead_ids.len()),
+            c2s_lead_ids,
+        }),
+    ))
+}
+
+/// Create a C2S lead for an inbound message. Returns None when deduplicated.
+async fn process_inbound_message(
+    app_state: &std::sync::Arc<crate::handlers::AppState>,
+    message: &InboundWhatsAppMessage,
+) -> Result<Option<String>, AppError> {
+    // Step 3: Deduplicate. Claiming the message row is the check itself, so
+    // concurrent deliveries of the same message cannot both get past it.
+    if !claim_message(&app_state.db, message).await? {
+        tracing::warn!("⚠️  Duplicate WhatsApp message: {}", message.message_id);
+        return Ok(None);
+    }
+
+    let result = create_lead_for_message(app_state, message).await;
+    if result.is_err() {
+        // Drop the claim so Meta's redelivery is processed instead of deduplicated
+        release_message(&app_state.db, &message.message_id).await;
+    }
+    result
+}
+
+/// Steps 3–7 for a message claimed by this delivery
+async fn create_lead_for_message(
+    app_state: &std::sync::Arc<crate::handlers::AppState>,
+    message: &InboundWhatsAppMessage,
+) -> Result<Option<String>, AppError> {
+    if has_recent_lead(&app_state.db, &message.wa_id).await? {
+        tracing::info!(
+            "WhatsApp sender {} already has a lead in the last {}h, skipping",
+            message.wa_id,
+            CONVERSATION_WINDOW_HOURS
+        );
+        store_whatsapp_lead(&app_state.db, message, None, "skipped", None, None).await?;
+        return Ok(None);
+    }
+
+    // Step 4: Validate and normalize phone (wa_id has no "+")
+    let phone_validated = {
+        let (valid, normalized) = validate_br_phone(&format!("+{}", message.wa_id));
+        if valid {
+            Some(normalized)
+        } else {
+            tracing::warn!("❌ Invalid phone in WhatsApp lead: {}", message.wa_id);
+            None
+        }
+    };
+
+    let customer_name = message
+        .profile_name
+        .clone()
+        .unwrap_or_else(|| format!("WhatsApp +{}", message.wa_id));
+
+    // Step 5: Inline enrichment (phone-first CPF resolution)
+    let enrichment_result = perform_inline_enrichment(
+        app_state,
+        Some("WhatsApp"),
+        message.profile_name.as_deref(),
+        None,
+        phone_validated.as_deref(),
+        None,
+    )
+    .await;
+
+    let enrichment_text = match &enrichment_result {
+        Ok(text) => Some(text.as_str()),
+        Err(e) => {
+            tracing::warn!("⚠️  Enrichment failed: {}", e);
+            None
+        }
+    };
+
+    let description = message.format_description(enrichment_text);
+
+    // Truncate description if needed (UTF-8 safe)
+    let max_desc_len = app_state.config.c2s_description_max_length;
+    let description_final = if description.chars().count() > max_desc_len {
+        let truncated: String = description.chars().take(max_desc_len).collect();
+        tracing::warn!(
+            "⚠️  Description truncated from {} to {} chars",
+            description.chars().count(),
+            truncated.chars().count()
+        );
+        truncated
+    } else {
+        description
+    };
+
+    // Step 6: Create lead in C2S
+    let start = std::time::Instant::now();
+    let account = c2s_accounts::select_account(
+        &app_state.config.c2s_account_rules,
+        &c2s_accounts::LeadOrigin {
+            source: Some("WhatsApp"),
+            ..Default::default()
+        },
+    );
+    let c2s_service = c2s_accounts::service_for(app_state, account);
+    let c2s_lead_id = c2s_service
+        .create_lead(
+            &customer_name,
+            phone_validated.as_deref().or(Some(message.wa_id.as_str())),
+            None,
+            &description_final,
+            Some("WhatsApp"),
+            None,
+            app_state.config.c2s_default_seller_id.as_deref(),
+        )
+        .await
+        .context(
+            ErrorContext::new("c2s.create_lead")
+                .provider("c2s")
+                .id("wa_message_id", &message.message_id),
+        )?;
+
+    let latency_ms = start.elapsed().as_millis() as i32;
+    tracing::info!(
+        "✅ Lead created in C2S from WhatsApp: {} ({}ms)",
+        c2s_lead_id,
+        latency_ms
+    );
+    if let Err(e) = c2s_accounts::record_lead_account(&app_state.db, &c2s_lead_id, account).await {
+        tracing::warn!("{}", e);
+    }
+
+    // Step 7: Store tracking record
+    let enrichment_status = if enrichment_result.is_ok() {
+        "completed"
+    } else {
+        "partial"
+    };
+    store_whatsapp_lead(
+        &app_state.db,
+        message,
+        Some(&c2s_lead_id),
+        enrichment_status,
+        phone_validated.as_deref(),
+        Some(latency_ms),
+    )
+    .await?;
+
+    Ok(Some(c2s_lead_id))
+}
+
+/// Validate Meta's X-Hub-Signature-256 header ("sha256=<hex hmac>")
+fn validate_signature(
+    app_secret: Option<&str>,
+    body: &[u8],
+    signature: &str,
+) -> Result<(), AppError> {
+    let app_secret = app_secret.ok_or_else(|| {
+        AppError::InternalError("WHATSAPP_APP_SECRET not configured (required)".to_string())
+    })?;
+
+    let provided = signature
+        .strip_prefix("sha256=")
+        .and_then(|hex_sig| hex::decode(hex_sig).ok())
+        .ok_or_else(|| AppError::Unauthorized("Malformed X-Hub-Signature-256".to_string()))?;
+
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, app_secret.as_bytes());
+    ring::hmac::verify(&key, body, &provided).map_err(|_| {
+        tracing::error!("❌ Invalid WhatsApp webhook signature");
+        AppError::Unauthorized("Invalid X-Hub-Signature-256".to_string())
+    })?;
+
+    tracing::debug!("✓ WhatsApp webhook signature validated");
+    Ok(())
+}
+
+/// Claim a message for processing (deduplication)
+///
+/// Inserts the tracking row as 'processing'; the unique wa_message_id makes
+/// this atomic, so false means another delivery already has (or had) it.
+/// A stale 'processing' claim is taken over instead of blocking forever.
+async fn claim_message(db: &PgPool, message: &InboundWhatsAppMessage) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO whatsapp_leads (
+            wa_message_id,
+            wa_id,
+            profile_name,
+            phone_number_id,
+            message_type,
+            message_text,
+            enrichment_status
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, 'processing')
+        ON CONFLICT (wa_message_id) DO UPDATE SET created_at = NOW()
+        WHERE whatsapp_leads.enrichment_status = 'processing'
+          AND whatsapp_leads.created_at < NOW() - make_interval(mins => $7)
+        "#,
+        &message.message_id,
+        &message.wa_id,
+        message.profile_name.as_deref(),
+        message.business_phone_number_id.as_deref(),
+        &message.message_type,
+        message.text.as_deref(),
+        CLAIM_TIMEOUT_MINUTES
+    )
+    .execute(db)
+    .timed("whatsapp_leads.claim")
+    .await
+    .context(ErrorContext::db("whatsapp_leads.claim").id("wa_message_id", &message.message_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Release a claim after a failed attempt (best effort: a leftover claim
+/// expires after CLAIM_TIMEOUT_MINUTES)
+async fn release_message(db: &PgPool, message_id: &str) {
+    let result = sqlx::query!(
+        "DELETE FROM whatsapp_leads WHERE wa_message_id = $1 AND enrichment_status = 'processing'",
+        message_id
+    )
+    .execute(db)
+    .timed("whatsapp_leads.release")
+    .await
+    .context(ErrorContext::db("whatsapp_leads.release").id("wa_message_id", message_id));
+
+    if let Err(e) = result {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// Check if sender already generated a C2S lead within the conversation window
+async fn has_recent_lead(db: &PgPool, wa_id: &str) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM whatsapp_leads
+            WHERE wa_id = $1
+              AND c2s_lead_id IS NOT NULL
+              AND created_at > NOW() - make_interval(hours => $2)
+        ) AS "exists!"
+        "#,
+        wa_id,
+        CONVERSATION_WINDOW_HOURS
+    )
+    .fetch_one(db)
+    .timed("whatsapp_leads.recent_for_contact")
+    .await
+    .context(ErrorContext::db("whatsapp_leads.recent_for_contact").id("wa_id", wa_id))?;
+
+    Ok(exists)
+}
+
+/// Store the outcome on the claimed tracking record
+async fn store_whatsapp_lead(
+    db: &PgPool,
+    message: &InboundWhatsAppMessage,
+    c2s_lead_id: Option<&str>,
+    enrichment_status: &str,
+    phone_normalized: Option<&str>,
+    c2s_latency_ms: Option<i32>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE whatsapp_leads
+        SET phone_normalized = $2,
+            c2s_lead_id = $3,
+            enrichment_status = $4,
+            c2s_latency_ms = $5
+        WHERE wa_message_id = $1
+        "#,
+        &message.message_id,
+        phone_normalized,
+        c2s_lead_id,
+        enrichment_status,
+        c2s_latency_ms
+    )
+    .execute(db)
+    .timed("whatsapp_leads.update")
+    .await
+    .context(ErrorContext::db("whatsapp_leads.update").id("wa_message_id", &message.message_id))?;
+
+    tracing::info!("✓ WhatsApp lead tracking record stored");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+        format!(
+            "sha256={}",
+            hex::encode(ring::hmac::sign(&key, body).as_ref())
+        )
+    }
+
+    #[test]
+    fn test_valid_signature() {
+        let body = br#"{"object":"whatsapp_business_account"}"#;
+
+        assert!(validate_signature(Some("app_secret"), body, &sign("app_secret", body)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let body = br#"{"object":"whatsapp_business_account"}"#;
+
+        assert!(validate_signature(Some("app_secret"), body, &sign("other", body)).is_err());
+        assert!(validate_signature(Some("app_secret"), body, "sha256=zz").is_err());
+        assert!(validate_signature(Some("app_secret"), body, "deadbeef").is_err());
+    }
+
+    #[test]
+    fn test_missing_secret_rejects() {
+        let body = b"{}";
+
+        assert!(matches!(
+            validate_signature(None, body, &sign("app_secret", body)),
+            Err(AppError::InternalError(_))
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+/// WhatsApp Business (Meta Cloud API) webhook payload
+/// Documentation: https://developers.facebook.com/docs/whatsapp/cloud-api/webhooks/components
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppWebhookPayload {
+    /// Always "whatsapp_business_account" for WhatsApp events
+    pub object: String,
+
+    #[serde(default)]
+    pub entry: Vec<WhatsAppEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppEntry {
+    /// WhatsApp Business Account ID
+    pub id: String,
+
+    #[serde(default)]
+    pub changes: Vec<WhatsAppChange>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppChange {
+    /// Subscribed field ("messages" for inbound messages and status updates)
+    pub field: String,
+
+    pub value: WhatsAppChangeValue,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppChangeValue {
+    #[serde(default)]
+    pub metadata: Option<WhatsAppMetadata>,
+
+    /// Sender profiles (name + WhatsApp ID)
+    #[serde(default)]
+    pub contacts: Vec<WhatsAppContact>,
+
+    /// Inbound messages (absent for delivery status updates)
+    #[serde(default)]
+    pub messages: Vec<WhatsAppMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppMetadata {
+    pub display_phone_number: String,
+    pub phone_number_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppContact {
+    #[serde(default)]
+    pub profile: Option<WhatsAppProfile>,
+
+    /// WhatsApp ID (phone number with country code, no "+")
+    pub wa_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppProfile {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppMessage {
+    /// Unique message ID (used for deduplication)
+    pub id: String,
+
+    /// Sender phone number (WhatsApp ID)
+    pub from: String,
+
+    pub timestamp: String,
+
+    /// Message type: text, image, audio, interactive, button, ...
+    #[serde(rename = "type")]
+    pub message_type: String,
+
+    #[serde(default)]
+    pub text: Option<WhatsAppText>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WhatsAppText {
+    pub body: String,
+}
+
+/// Inbound WhatsApp message flattened with its sender profile
+#[derive(Debug, Clone)]
+pub struct InboundWhatsAppMessage {
+    pub message_id: String,
+    pub wa_id: String,
+    pub profile_name: Option<String>,
+    pub business_phone_number_id: Option<String>,
+    pub message_type: String,
+    pub text: Option<String>,
+}
+
+impl WhatsAppWebhookPayload {
+    /// Extract inbound messages, ignoring status updates and other fields
+    pub fn inbound_messages(&self) -> Vec<InboundWhatsAppMessage> {
+        let mut result = Vec::new();
+
+        for entry in &self.entry {
+            for change in entry.changes.iter().filter(|c| c.field == "messages") {
+                for message in &change.value.messages {
+                    let profile_name = change
+                        .value
+                        .contacts
+                        .iter()
+                        .find(|c| c.wa_id == message.from)
+                        .and_then(|c| c.profile.as_ref())
+                        .map(|p| p.name.trim().to_string())
+                        .filter(|n| !n.is_empty());
+
+                    result.push(InboundWhatsAppMessage {
+                        message_id: message.id.clone(),
+                        wa_id: message.from.clone(),
+                        profile_name,
+                        business_phone_number_id: change
+                            .value
+                            .metadata
+                            .as_ref()
+                            .map(|m| m.phone_number_id.clone()),
+                        message_type: message.message_type.clone(),
+                        text: message.text.as_ref().map(|t| t.body.clone()),
+                    });
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl InboundWhatsAppMessage {
+    /// Format C2S lead description with WhatsApp context + enrichment data
+    pub fn format_description(&self, enrichment_data: Option<&str>) -> String {
+        let mut desc = String::from("💬 Lead via WhatsApp\n");
+        desc.push_str(&format!("📱 WhatsApp: +{}\n", self.wa_id));
+
+        match &self.text {
+            Some(text) => desc.push_str(&format!("\n📝 Mensagem:\n{}\n", text.trim())),
+            None => desc.push_str(&format!("\n📎 Mensagem do tipo: {}\n", self.message_type)),
+        }
+
+        if let Some(enrichment) = enrichment_data {
+            desc.push('\n');
+            desc.push_str(enrichment);
+        }
+
+        desc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_payload() -> WhatsAppWebhookPayload {
+        serde_json::from_value(serde_json::json!({
+            "object": "whatsapp_business_account",
+            "entry": [{
+                "id": "102290129340398",
+                "changes": [{
+                    "field": "messages",
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "5511999990000",
+                            "phone_number_id": "106540352242922"
+                        },
+                        "contacts": [{
+                            "profile": { "name": "João Silva" },
+                            "wa_id": "5511987654321"
+                        }],
+                        "messages": [{
+                            "from": "5511987654321",
+                            "id": "wamid.HBgLMTY1MDM4Nzk0MzkVAgASGBQzQTRBNjU5OUFFRTAzODEwMTQ0RgA=",
+                            "timestamp": "1749416383",
+                            "type": "text",
+                            "text": { "body": "Olá, tenho interesse no apartamento" }
+                        }]
+                    }
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_extract_inbound_messages() {
+        let messages = sample_payload().inbound_messages();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].wa_id, "5511987654321");
+        assert_eq!(messages[0].profile_name.as_deref(), Some("João Silva"));
+        assert_eq!(
+            messages[0].text.as_deref(),
+            Some("Olá, tenho interesse no apartamento")
+        );
+    }
+
+    #[test]
+    fn test_status_updates_are_ignored() {
+        let payload: WhatsAppWebhookPayload = serde_json::from_value(serde_json::json!({
+            "object": "whatsapp_business_account",
+            "entry": [{
+                "id": "102290129340398",
+                "changes": [{
+                    "field": "messages",
+                    "value": {
+                        "statuses": [{ "id": "wamid.x", "status": "delivered" }]
+                    }
+                }]
+            }]
+        }))
+        .unwrap();
+
+        assert!(payload.inbound_messages().is_empty());
+    }
+
+    #[test]
+    fn test_format_description() {
+        let message = &sample_payload().inbound_messages()[0];
+        let desc = message.format_description(Some("🔍 CPF Encontrado: 12345678901\n"));
+
+        assert!(desc.contains("Lead via WhatsApp"));
+        assert!(desc.contains("+5511987654321"));
+        assert!(desc.contains("tenho interesse"));
+        assert!(desc.contains("CPF Encontrado"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Anonymized Work API responses recorded as test fixtures
+//!
+//! The Work API payload is parsed as untyped JSON in several places (message
+//! formatting, storage, shadow comparison, consistency checks), and real
+//! answers vary a lot in shape: missing sections, arrays instead of objects,
+//! numbers as strings. With `WORK_API_FIXTURE_SAMPLE_RATE` set, a sample of
+//! CPFs has its Work API response anonymized (`anonymize`) and recorded in
+//! `work_api_fixtures` (migration 042), one row per distinct shape
+//! (`shape_hash`), and also written to `WORK_API_FIXTURE_DIR` when set.
+//! Recorded fixtures are listed by `GET /api/v1/admin/work-api-fixtures`;
+//! copied to `tests/fixtures/work_api/` they run in the parser corpus test.
+//!
+//! Anonymization keeps keys, numbers, booleans and the layout of strings:
+//! digits become `1`, letters `X`/`x`, punctuation is kept, so dates, money
+//! and phones keep their format. The queried CPF becomes `FIXTURE_CPF`.
+//! Categorical fields (`KEPT_KEYS`) are kept as is.
+
+use crate::config::Config;
+use crate::db::TimedQuery;
+use crate::errors::{AppError, ResultExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// CPF standing for the queried one in fixtures (valid check digits)
+pub const FIXTURE_CPF: &str = "52998224725";
+
+/// Categorical fields, not identifying, kept unchanged
+const KEPT_KEYS: &[&str] = &["sexo", "uf", "tipo", "whatsapp", "prioridade", "status"];
+
+/// A recorded fixture
+#[derive(Debug, Serialize, FromRow)]
+pub struct WorkApiFixture {
+    pub shape_hash: String,
+    pub payload: Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Whether a CPF belongs to the fixture sample (stable per CPF)
+pub fn is_sampled(cpf: &str, rate: f64) -> bool {
+    if rate <= 0.0 {
+        return false;
+    }
+    let digest = Sha256::digest(format!("fixture:{}", cpf).as_bytes());
+    let bucket = u16::from_be_bytes([digest[0], digest[1]]) as f64 / (u16::MAX as f64 + 1.0);
+    bucket < rate
+}
+
+/// Copy of a Work API response without personal data
+pub fn anonymize(value: &Value, cpf: &str) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, v)| {
+                    let kept = KEPT_KEYS.contains(&key.as_str()) && !v.is_object() && !v.is_array();
+                    let v = if kept { v.clone() } else { anonymize(v, cpf) };
+                    (key.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| anonymize(v, cpf)).collect()),
+        Value::String(s) => Value::String(mask(s, cpf)),
+        other => other.clone(),
+    }
+}
+
+fn mask(text: &str, cpf: &str) -> String {
+    if text == cpf || text == FIXTURE_CPF {
+        return FIXTURE_CPF.to_string();
+    }
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                '1'
+            } else if c.is_uppercase() {
+                'X'
+            } else if c.is_alphabetic() {
+                'x'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Hash of the structure of a payload (keys and value types, not values)
+///
+/// Arrays contribute the set of their element shapes, so a list with more
+/// entries of the same kind has the same shape.
+pub fn shape_hash(value: &Value) -> String {
+    hex::encode(Sha256::digest(shape(value).as_bytes()))
+}
+
+fn shape(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let fields: BTreeSet<String> = map
+                .iter()
+                .map(|(key, v)| format!("{}:{}", key, shape(v)))
+                .collect();
+            format!("{{{}}}", fields.into_iter().collect::<Vec<_>>().join(","))
+        }
+        Value::Array(items) => {
+            let kinds: BTreeSet<String> = items.iter().map(shape).collect();
+            format!("[{}]", kinds.into_iter().collect::<Vec<_>>().join("|"))
+        }
+        Value::String(_) => "s".to_string(),
+        Value::Number(_) => "n".to_string(),
+        Value::Bool(_) => "b".to_string(),
+        Value::Null => "z".to_string(),
+    }
+}
+
+/// Record the responses of sampled CPFs in the background
+///
+/// `cpfs` and `data` are aligned as in `WorkApiEnrichment`. CNPJ entries
+/// (assembled from several modules, not a raw response) are skipped.
+pub fn record_sampled(pool: &PgPool, config: &Config, cpfs: &[String], data: &[Value]) {
+    let rate = config.work_api_fixture_sample_rate;
+    let fixtures: Vec<Value> = cpfs
+        .iter()
+        .zip(data)
+        .filter(|(cpf, payload)| {
+            !crate::enrichment::is_cnpj(cpf) && !payload.is_null() && is_sampled(cpf, rate)
+        })
+        .map(|(cpf, payload)| anonymize(payload, cpf))
+        .collect();
+    if fixtures.is_empty() {
+        return;
+    }
+
+    let pool = pool.clone();
+    let dir = config.work_api_fixture_dir.clone();
+    tokio::spawn(async move {
+        for fixture in fixtures {
+            let hash = shape_hash(&fixture);
+            match store(&pool, &hash, &fixture).await {
+                Ok(true) => {
+                    tracing::info!("Recorded Work API fixture with new shape {}", &hash[..16])
+                }
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    continue;
+                }
+            }
+            if let Some(dir) = dir.as_deref() {
+                if let Err(e) = write_file(Path::new(dir), &hash, &fixture).await {
+                    tracing::warn!("Failed to write Work API fixture to {}: {}", dir, e);
+                }
+            }
+        }
+    });
+}
+
+/// Store a fixture, returns false when its shape was already recorded
+async fn store(pool: &PgPool, shape_hash: &str, payload: &Value) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO work_api_fixtures (shape_hash, payload)
+        VALUES ($1, $2)
+        ON CONFLICT (shape_hash) DO NOTHING
+        "#,
+    )
+    .bind(shape_hash)
+    .bind(payload)
+    .execute(pool)
+    .timed("work_api_fixtures.insert")
+    .await
+    .context("Failed to record Work API fixture")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn write_file(dir: &Path, shape_hash: &str, payload: &Value) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let body = serde_json::to_string_pretty(payload).unwrap_or_default();
+    tokio::fs::write(dir.join(format!("{}.json", &shape_hash[..16])), body + "\n").await
+}
+
+/// Recorded fixtures, newest first
+pub async fn list(pool: &PgPool, limit: i64) -> Result<Vec<WorkApiFixture>, AppError> {
+    let fixtures = sqlx::query_as::<_, WorkApiFixture>(
+        r#"
+        SELECT shape_hash, payload, recorded_at
+        FROM work_api_fixtures
+        ORDER BY recorded_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .timed("work_api_fixtures.list")
+    .await
+    .context("Failed to list Work API fixtures")?;
+
+    Ok(fixtures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_anonymize_keeps_layout() {
+        let payload = json!({
+            "status": 200,
+            "DadosBasicos": {
+                "nome": "José da Silva",
+                "cpf": "12345678909",
+                "dataNascimento": "01/02/1980",
+                "sexo": "M - MASCULINO"
+            },
+            "DadosEconomicos": {"renda": "R$ 15.000,00", "score": {"nota": 850}},
+            "emails": [{"email": "jose@example.com", "prioridade": "1"}]
+        });
+
+        let anonymized = anonymize(&payload, "12345678909");
+
+        assert_eq!(
+            anonymized,
+            json!({
+                "status": 200,
+                "DadosBasicos": {
+                    "nome": "Xxxx xx Xxxxx",
+                    "cpf": FIXTURE_CPF,
+                    "dataNascimento": "11/11/1111",
+                    "sexo": "M - MASCULINO"
+                },
+                "DadosEconomicos": {"renda": "X$ 11.111,11", "score": {"nota": 850}},
+                "emails": [{"email": "xxxx@xxxxxxx.xxx", "prioridade": "1"}]
+            })
+        );
+        // Already anonymized data is left unchanged
+        assert_eq!(anonymize(&anonymized, FIXTURE_CPF), anonymized);
+    }
+
+    #[test]
+    fn test_shape_hash_ignores_values_and_list_length() {
+        let one = json!({"telefones": [{"telefone": "11987654321"}], "idade": 40});
+        let two = json!({"idade": 31, "telefones": [{"telefone": "1"}, {"telefone": "2"}]});
+        let other = json!({"telefones": [{"telefone": 11987654321_u64}], "idade": 40});
+
+        assert_eq!(shape_hash(&one), shape_hash(&two));
+        assert_ne!(shape_hash(&one), shape_hash(&other));
+    }
+
+    #[test]
+    fn test_sampling() {
+        assert!(!is_sampled("52998224725", 0.0));
+        assert!(is_sampled("52998224725", 1.0));
+    }
+}
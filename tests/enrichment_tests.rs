@@ -146,7 +146,7 @@ mod phone_validation_tests {
 
 #[cfg(test)]
 mod cpf_validation_tests {
-    // CPF validation is handled by Work API, but we can test our CPF formatting
+    use rust_c2s_api::enrichment::{check_cnpj, check_cpf, check_document, DocumentRejection};
 
     #[test]
     fn test_cpf_digit_extraction() {
@@ -169,11 +169,87 @@ mod cpf_validation_tests {
         let too_long = "123456789012";
         assert!(too_long.len() > 11);
     }
+
+    #[test]
+    fn test_cpf_checksum() {
+        assert!(check_cpf("52998224725").is_ok());
+        assert!(check_cpf(" 529.982.247-25 ").is_ok());
+        assert_eq!(check_cpf("12345678901"), Err(DocumentRejection::Checksum));
+        assert_eq!(check_cpf("52998224752"), Err(DocumentRejection::Checksum));
+    }
+
+    #[test]
+    fn test_cpf_preflight_rejects_placeholders_and_non_cpfs() {
+        for placeholder in [
+            "00000000000",
+            "111.111.111-11",
+            "99999999999",
+            "12345678909",
+        ] {
+            assert_eq!(check_cpf(placeholder), Err(DocumentRejection::Placeholder));
+        }
+        for document in ["", "123456789", "123456789012", "+5511987654321", "a@b.com"] {
+            assert_eq!(check_cpf(document), Err(DocumentRejection::Malformed));
+        }
+    }
+
+    #[test]
+    fn test_cnpj_documents_take_company_path() {
+        use rust_c2s_api::enrichment::is_cnpj;
+
+        assert!(is_cnpj("11.222.333/0001-81"));
+        assert!(is_cnpj("11222333000181"));
+        assert!(!is_cnpj("529.982.247-25"));
+        assert_eq!(
+            check_cpf("11222333000181"),
+            Err(DocumentRejection::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_cnpj_check_digits() {
+        assert!(check_cnpj("11.222.333/0001-81").is_ok());
+        assert!(check_cnpj("11222333000181").is_ok());
+        assert_eq!(
+            check_cnpj("11222333000182"),
+            Err(DocumentRejection::Checksum)
+        );
+        assert_eq!(
+            check_cnpj("11222333000118"),
+            Err(DocumentRejection::Checksum)
+        );
+        assert_eq!(
+            check_cnpj("11.111.111/1111-11"),
+            Err(DocumentRejection::Checksum)
+        );
+        assert_eq!(
+            check_cnpj("00.000.000/0000-00"),
+            Err(DocumentRejection::Placeholder)
+        );
+        for document in ["", "1122233300018", "52998224725", "AB.CDE.FGH/0001-81"] {
+            assert_eq!(check_cnpj(document), Err(DocumentRejection::Malformed));
+        }
+    }
+
+    #[test]
+    fn test_check_document_picks_cpf_or_cnpj() {
+        assert!(check_document("529.982.247-25").is_ok());
+        assert!(check_document("11.222.333/0001-81").is_ok());
+        assert_eq!(
+            check_document("11.222.333/0001-80"),
+            Err(DocumentRejection::Checksum)
+        );
+        assert_eq!(
+            check_document("529.982.247-26"),
+            Err(DocumentRejection::Checksum)
+        );
+    }
 }
 
 #[cfg(test)]
 mod message_formatting_tests {
-    use rust_c2s_api::enrichment::format_enriched_message_body;
+    use rust_c2s_api::enrichment::{format_enriched_message_body, format_enriched_message_body_in};
+    use rust_c2s_api::templates::Locale;
     use serde_json::json;
 
     #[test]
@@ -228,6 +304,171 @@ mod message_formatting_tests {
         assert!(message.contains("11987654321"));
         assert!(message.contains("maria@example.com"));
     }
+
+    #[test]
+    fn test_format_different_people_with_missing_person() {
+        let enriched_data = vec![
+            serde_json::Value::Null,
+            json!({
+                "DadosBasicos": {
+                    "nome": "Maria Santos",
+                    "cpf": "98765432100"
+                }
+            }),
+        ];
+
+        let message = format_enriched_message_body(
+            "João Silva",
+            "11987654321",
+            "maria@example.com",
+            &enriched_data,
+            false,
+        );
+
+        let person_1 = message.split("PESSOA 2").next().unwrap();
+        assert!(person_1.contains("Dados indisponíveis"));
+        assert!(message.contains("Maria Santos"));
+    }
+
+    #[test]
+    fn test_format_message_in_lead_language() {
+        let enriched_data = vec![json!({
+            "DadosBasicos": {
+                "nome": "John Smith",
+                "cpf": "12345678901"
+            }
+        })];
+
+        let message = format_enriched_message_body_in(
+            "John Smith",
+            "11987654321",
+            "john@example.com",
+            &enriched_data,
+            true,
+            Locale::En,
+        );
+
+        assert!(message.starts_with("📞📧 Phone and e-mail of the same person\n"));
+        assert!(message.contains("🌐 Lead escreveu em inglês"));
+        assert!(message.contains("John Smith"));
+    }
+
+    #[test]
+    fn test_format_financial_amounts_in_brl() {
+        let enriched_data = vec![json!({
+            "DadosEconomicos": {
+                "renda": "R$ 15.000,00",
+                "poderAquisitivo": {"faixaPoderAquisitivo": "De R$ 1630 até R$ 4082"}
+            }
+        })];
+
+        let message = format_enriched_message_body(
+            "João Silva",
+            "11987654321",
+            "joao@example.com",
+            &enriched_data,
+            true,
+        );
+
+        assert!(message.contains("Renda: R$ 28.500,00\n"));
+        assert!(message.contains("Faixa de Renda: De R$ 3.097,00 até R$ 7.755,80\n"));
+    }
+}
+
+#[cfg(test)]
+mod partial_enrichment_tests {
+    use async_trait::async_trait;
+    use rust_c2s_api::clients::{DocumentModules, WorkApi};
+    use rust_c2s_api::enrichment::{enrich_cpfs_with_work_api, format_partial_notice};
+    use rust_c2s_api::errors::AppError;
+    use rust_c2s_api::models::{CompanyModules, WorkApiCompleteResponse};
+    use serde_json::{json, Value};
+
+    const PHONE_CPF: &str = "52998224725";
+    const EMAIL_CPF: &str = "11144477735";
+
+    /// Work API knowing only the phone person, without economic data
+    struct HalfWorkApi;
+
+    #[async_trait]
+    impl WorkApi for HalfWorkApi {
+        async fn fetch_all_modules(
+            &self,
+            documento: &str,
+        ) -> Result<WorkApiCompleteResponse, AppError> {
+            if documento == PHONE_CPF {
+                Ok(json!({ "DadosBasicos": { "nome": "João Silva", "cpf": PHONE_CPF } }))
+            } else {
+                Err(AppError::ExternalApiError("status 500".to_string()))
+            }
+        }
+
+        async fn fetch_module(&self, _: &str, _: &str) -> Result<Option<Value>, AppError> {
+            Ok(None)
+        }
+
+        async fn fetch_company_modules(&self, cnpj: &str) -> Result<CompanyModules, AppError> {
+            Err(AppError::NotFound(cnpj.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_result_instead_of_failure() {
+        let cpfs = vec![EMAIL_CPF.to_string(), PHONE_CPF.to_string()];
+        let work = enrich_cpfs_with_work_api(&cpfs, &HalfWorkApi)
+            .await
+            .expect("partial result");
+
+        assert!(work.is_partial());
+        assert_eq!(work.cpfs, vec![PHONE_CPF.to_string()]);
+        let pieces: Vec<(&str, &str)> = work
+            .missing
+            .iter()
+            .map(|m| (m.cpf.as_str(), m.piece.as_str()))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![(EMAIL_CPF, "work_api"), (PHONE_CPF, "DadosEconomicos")]
+        );
+
+        // Positions follow the lookup order, the missing person is a gap
+        let aligned = work.aligned(&cpfs);
+        assert!(aligned[0].is_null());
+        assert_eq!(aligned[1]["DadosBasicos"]["cpf"], PHONE_CPF);
+
+        assert_eq!(work.completeness()["partial"], true);
+        let notice = format_partial_notice(&work.missing);
+        assert!(notice.contains("ENRIQUECIMENTO PARCIAL"));
+        assert!(notice.contains(EMAIL_CPF));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_documents_keeps_input_order() {
+        let documentos = vec![
+            "11222333000181".to_string(),
+            EMAIL_CPF.to_string(),
+            PHONE_CPF.to_string(),
+        ];
+        let results = HalfWorkApi.fetch_documents(&documentos).await;
+
+        assert_eq!(results.len(), 3);
+        // The CNPJ went through the company modules
+        assert!(matches!(results[0], Err(AppError::NotFound(_))));
+        assert!(matches!(results[1], Err(AppError::ExternalApiError(_))));
+        let Ok(DocumentModules::Person(data)) = &results[2] else {
+            panic!("CPF modules expected");
+        };
+        assert_eq!(data["DadosBasicos"]["cpf"], PHONE_CPF);
+    }
+
+    #[tokio::test]
+    async fn test_no_document_enriched_is_an_error() {
+        let cpfs = vec![EMAIL_CPF.to_string()];
+        assert!(enrich_cpfs_with_work_api(&cpfs, &HalfWorkApi)
+            .await
+            .is_err());
+        assert!(format_partial_notice(&[]).is_empty());
+    }
 }
 
 #[cfg(test)]
@@ -261,6 +502,96 @@ mod error_handling_tests {
         assert!(display.contains("Not found"));
         assert!(display.contains("Lead not found"));
     }
+
+    #[test]
+    fn test_provider_unavailable_detection() {
+        use axum::response::IntoResponse;
+        use rust_c2s_api::errors::ResultExt;
+
+        let error = AppError::ProviderUnavailable("work_api circuit breaker open".to_string());
+        assert!(error.is_provider_unavailable());
+        assert_eq!(
+            error.clone().into_response().status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        // Still detected through a context chain
+        let wrapped = Err::<(), _>(error)
+            .context("Failed to enrich lead")
+            .unwrap_err();
+        assert!(wrapped.is_provider_unavailable());
+
+        let other = AppError::ExternalApiError("Work API returned 500".to_string());
+        assert!(!other.is_provider_unavailable());
+    }
+
+    #[test]
+    fn test_invalid_document_response() {
+        use axum::response::IntoResponse;
+        use rust_c2s_api::errors::ResultExt;
+
+        let error = AppError::InvalidDocument("Invalid CPF (checksum), Work API not called".into());
+        assert!(error.is_invalid_document());
+        assert!(!error.is_provider_unavailable());
+        assert_eq!(
+            error.clone().into_response().status(),
+            axum::http::StatusCode::BAD_REQUEST
+        );
+
+        let wrapped = Err::<(), _>(error)
+            .context("Failed to enrich lead")
+            .unwrap_err();
+        assert!(wrapped.is_invalid_document());
+        assert!(!AppError::BadRequest("x".into()).is_invalid_document());
+    }
+
+    #[test]
+    fn test_error_chain() {
+        use rust_c2s_api::errors::ResultExt;
+
+        let error = Err::<(), _>(AppError::ExternalApiError("timeout".to_string()))
+            .context("Work API call for CPF 123")
+            .context("Failed to enrich lead 42")
+            .unwrap_err();
+        assert_eq!(
+            error.chain(),
+            vec![
+                "Failed to enrich lead 42",
+                "Work API call for CPF 123",
+                "External API error: timeout"
+            ]
+        );
+        assert_eq!(
+            AppError::NotFound("CPF".to_string()).chain(),
+            vec!["Not found: CPF"]
+        );
+    }
+
+    #[test]
+    fn test_structured_error_context() {
+        use axum::response::IntoResponse;
+        use rust_c2s_api::errors::{split_chain, ErrorContext, ResultExt};
+
+        let error = Err::<(), _>(AppError::NotFound("lead 42".to_string()))
+            .context(
+                ErrorContext::new("c2s.fetch_lead")
+                    .provider("c2s")
+                    .id("lead_id", 42),
+            )
+            .context(ErrorContext::new("enrichment.enrich_and_send"))
+            .unwrap_err();
+        assert_eq!(
+            error.chain_message(),
+            "enrichment.enrich_and_send -> c2s.fetch_lead [provider=c2s lead_id=42] -> Not found: lead 42"
+        );
+        assert_eq!(split_chain(&error.chain_message()), error.chain());
+        assert!(matches!(error.root(), AppError::NotFound(_)));
+        // The response is the one of the root error
+        assert_eq!(
+            error.into_response().status(),
+            axum::http::StatusCode::NOT_FOUND
+        );
+    }
 }
 
 #[cfg(test)]
@@ -335,6 +666,28 @@ mod deduplication_tests {
 mod data_extraction_tests {
     use serde_json::json;
 
+    #[test]
+    fn test_company_modules_section_names() {
+        use rust_c2s_api::models::CompanyModules;
+
+        let modules = CompanyModules {
+            cnpj: "11222333000181".to_string(),
+            socios: Some(json!([{"nome": "MARIA SILVA", "qualificacao": "Sócio-Administrador"}])),
+            faturamento_presumido: None,
+            situacao_cadastral: Some(json!({"razaoSocial": "IMOBILIARIA TESTE LTDA"})),
+            failed_modules: vec![],
+        };
+
+        let payload = json!(modules);
+        assert_eq!(payload["Socios"][0]["nome"], "MARIA SILVA");
+        assert_eq!(
+            payload["SituacaoCadastral"]["razaoSocial"],
+            "IMOBILIARIA TESTE LTDA"
+        );
+        assert!(payload.get("FaturamentoPresumido").is_none());
+        assert!(payload.get("ModulosComFalha").is_none());
+    }
+
     #[test]
     fn test_extract_name_from_work_api() {
         let data = json!({
@@ -430,3 +783,127 @@ mod data_extraction_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod diretrix_person_merge_tests {
+    use rust_c2s_api::enrichment::{merge_diretrix_person, DIRETRIX_PERSON_CONFIDENCE};
+    use rust_c2s_api::services::{
+        DiretrixAddress, DiretrixEmail, DiretrixPersonData, DiretrixPhone,
+    };
+    use serde_json::json;
+
+    fn person() -> DiretrixPersonData {
+        DiretrixPersonData {
+            nome: "JOAO DA SILVA".to_string(),
+            cpf: "52998224725".to_string(),
+            rg: Some("123456789".to_string()),
+            rg_orgao_emissor: None,
+            data_nascimento: Some("02/02/1980".to_string()),
+            idade: None,
+            signo: None,
+            sexo: None,
+            mae: Some("Maria da Silva".to_string()),
+            telefones: vec![
+                DiretrixPhone {
+                    numero: "987654321".to_string(),
+                    ddd: "11".to_string(),
+                    operadora: Some("VIVO".to_string()),
+                    tipo: Some("CELULAR".to_string()),
+                    ranking: 1,
+                },
+                DiretrixPhone {
+                    numero: "33334444".to_string(),
+                    ddd: "11".to_string(),
+                    operadora: None,
+                    tipo: Some("FIXO".to_string()),
+                    ranking: 2,
+                },
+            ],
+            emails: vec![DiretrixEmail {
+                endereco: "Joao@Example.com".to_string(),
+                ranking: 1,
+            }],
+            enderecos: vec![DiretrixAddress {
+                logadouro: "Rua Augusta".to_string(),
+                numero: "500".to_string(),
+                bairro: "Consolação".to_string(),
+                cidade: "São Paulo".to_string(),
+                uf: "SP".to_string(),
+                cep: "01305-000".to_string(),
+                complemento: None,
+                ranking: 1,
+                logadouro_tipo: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_merge_adds_only_unknown_entries() {
+        let mut payload = json!({
+            "DadosBasicos": { "nome": "João da Silva", "dataNascimento": "01/01/1980" },
+            "telefones": [{ "telefone": "(11) 98765-4321", "whatsapp": "SIM" }],
+            "emails": [{ "email": "joao@example.com" }],
+        });
+
+        let added = merge_diretrix_person(&mut payload, &person());
+
+        // Landline, address, mother's name and RG; the rest is already known
+        assert_eq!(added, 4);
+        let phones = payload["telefones"].as_array().unwrap();
+        assert_eq!(phones.len(), 2);
+        assert_eq!(phones[1]["telefone"], "1133334444");
+        assert_eq!(phones[1]["fonte"], "diretrix");
+        assert_eq!(phones[1]["confianca"], DIRETRIX_PERSON_CONFIDENCE);
+        assert_eq!(payload["emails"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["enderecos"][0]["cep"], "01305-000");
+        assert_eq!(payload["DadosBasicos"]["dataNascimento"], "01/01/1980");
+        assert_eq!(payload["DadosBasicos"]["nomeMae"], "Maria da Silva");
+        assert_eq!(
+            payload["enrichment_sources"],
+            json!(["work_api", "diretrix"])
+        );
+    }
+
+    #[test]
+    fn test_merge_twice_adds_nothing() {
+        let mut payload = json!({});
+        assert!(merge_diretrix_person(&mut payload, &person()) > 0);
+        assert_eq!(merge_diretrix_person(&mut payload, &person()), 0);
+    }
+}
+
+#[cfg(test)]
+mod no_data_note_tests {
+    use rust_c2s_api::enrichment::{format_no_data_note, NoDataReason};
+
+    #[test]
+    fn test_no_cpf_note_lists_contact_status() {
+        let note = format_no_data_note(
+            Some("11987654321"),
+            Some("not-an-email"),
+            &NoDataReason::NoCpf {
+                retry_scheduled: true,
+            },
+        );
+        assert!(note.starts_with("🔍 ENRIQUECIMENTO SEM RESULTADO"));
+        assert!(note.contains("Telefone: +5511987654321 (válido, consultado)"));
+        assert!(note.contains("E-mail: not-an-email (inválido, não consultado)"));
+        assert!(note.contains("nenhum CPF encontrado"));
+        assert!(note.contains("Nova consulta automática agendada"));
+    }
+
+    #[test]
+    fn test_no_work_api_data_note() {
+        let note = format_no_data_note(
+            None,
+            Some("joao@example.com"),
+            &NoDataReason::NoWorkApiData {
+                cpfs: vec!["52998224725".to_string()],
+            },
+        );
+        assert!(note.contains("Telefone: não informado"));
+        assert!(note.contains("E-mail: joao@example.com (válido, consultado)"));
+        assert!(note.contains("CPF 52998224725 sem dados na Work API"));
+        assert!(!note.contains("Nova consulta"));
+    }
+}
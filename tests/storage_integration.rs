@@ -41,3 +41,338 @@ async fn store_enriched_person_smoke_test() -> anyhow::Result<()> {
     assert_ne!(party_id, Uuid::nil());
     Ok(())
 }
+
+#[test]
+fn customer_lookup_keys_match_stored_formats() {
+    use rust_c2s_api::services::{cpf_lookup_key, email_lookup_key, phone_lookup_key};
+
+    assert_eq!(cpf_lookup_key("12345678901"), "cpf:12345678901");
+    assert_eq!(
+        email_lookup_key("Maria@Example.com"),
+        "email:maria@example.com"
+    );
+    assert_eq!(phone_lookup_key("(11) 98765-4321"), "phone:11987654321");
+}
+
+#[test]
+fn normalized_name_matches_migration_backfill() {
+    use rust_c2s_api::services::normalize_name;
+
+    // Same output as the translate/upper/regexp_replace backfill in migration 021
+    assert_eq!(normalize_name("  José  da Conceição "), "JOSE DA CONCEICAO");
+    assert_eq!(normalize_name("ÂNGELA müller"), "ANGELA MULLER");
+    assert_eq!(normalize_name("   "), "");
+}
+
+/// Writes through `EnrichmentStorage` must evict cached identifier → party_id entries.
+#[tokio::test]
+#[ignore]
+async fn store_enriched_person_invalidates_lookup_cache() -> anyhow::Result<()> {
+    use moka::future::Cache;
+    use rust_c2s_api::services::{cpf_lookup_key, phone_lookup_key};
+
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let cache: Cache<String, Uuid> = Cache::new(100);
+    let storage = EnrichmentStorage::new(db.pool.clone()).with_lookup_cache(cache.clone());
+
+    let cpf = format!("999{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+    let phone = "11900000000";
+    cache.insert(cpf_lookup_key(&cpf), Uuid::nil()).await;
+    cache.insert(phone_lookup_key(phone), Uuid::nil()).await;
+
+    let payload: WorkApiCompleteResponse = serde_json::json!({
+        "DadosBasicos": { "nome": "Test Cache Party" },
+        "telefones": [{ "telefone": phone, "whatsapp": "NAO" }]
+    });
+    storage
+        .store_enriched_person_with_lead(&cpf, &payload, None)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store enriched person: {e}"))?;
+
+    assert!(cache.get(&cpf_lookup_key(&cpf)).await.is_none());
+    assert!(cache.get(&phone_lookup_key(phone)).await.is_none());
+    Ok(())
+}
+
+/// CNPJ documents are stored as company parties with a core.companies row.
+#[tokio::test]
+#[ignore]
+async fn store_enriched_company_smoke_test() -> anyhow::Result<()> {
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let storage = EnrichmentStorage::new(db.pool.clone());
+
+    let payload = serde_json::json!({
+        "DadosEmpresa": {
+            "razaoSocial": "Imobiliaria Teste Ltda",
+            "nomeFantasia": "Teste Imoveis",
+            "porte": "ME",
+            "dataAbertura": "15/03/2010",
+            "cnaePrincipal": "6821-8/01 - Corretagem na compra e venda e avaliação de imóveis"
+        }
+    });
+
+    let cnpj = format!("99{:012}", Uuid::new_v4().as_u128() % 1_000_000_000_000);
+
+    let party_id = storage
+        .store_enriched_party(&cnpj, &payload, Some("test-lead-id"))
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store enriched company: {e}"))?;
+
+    let (party_type, cnae, industry): (String, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT p.party_type, c.cnae, c.industry
+         FROM core.parties p JOIN core.companies c ON c.party_id = p.id
+         WHERE p.id = $1",
+    )
+    .bind(party_id)
+    .fetch_one(&db.pool)
+    .await?;
+
+    assert_eq!(party_type, "company");
+    assert_eq!(cnae.as_deref(), Some("6821-8/01"));
+    assert_eq!(
+        industry.as_deref(),
+        Some("Corretagem na compra e venda e avaliação de imóveis")
+    );
+    Ok(())
+}
+
+/// A write failing mid-way (address confidence outside the 0-1 CHECK) must
+/// not leave a party behind, nor its contacts.
+#[tokio::test]
+#[ignore]
+async fn store_enriched_person_rolls_back_new_party_on_failure() -> anyhow::Result<()> {
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let storage = EnrichmentStorage::new(db.pool.clone());
+
+    let cpf = format!("999{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+    let phone = format!("119{:08}", Uuid::new_v4().as_u128() % 100_000_000);
+    let payload: WorkApiCompleteResponse = serde_json::json!({
+        "DadosBasicos": { "nome": "Test Rollback Party" },
+        "telefones": [{ "telefone": phone, "whatsapp": "NAO" }],
+        "enderecos": [{ "logradouro": "Rua Teste", "cidade": "São Paulo", "uf": "SP", "confianca": 5.0 }]
+    });
+
+    let result = storage
+        .store_enriched_person_with_lead(&cpf, &payload, None)
+        .await;
+    assert!(result.is_err());
+
+    let (parties,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM core.parties WHERE cpf_cnpj = $1")
+            .bind(&cpf)
+            .fetch_one(&db.pool)
+            .await?;
+    let (contacts,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM core.party_contacts WHERE value = $1")
+            .bind(&phone)
+            .fetch_one(&db.pool)
+            .await?;
+    assert_eq!(parties, 0);
+    assert_eq!(contacts, 0);
+    Ok(())
+}
+
+/// A failed re-enrichment keeps the previously stored party intact: its
+/// contacts are not retired and its snapshot is not replaced.
+#[tokio::test]
+#[ignore]
+async fn store_enriched_person_failure_keeps_existing_party() -> anyhow::Result<()> {
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let storage = EnrichmentStorage::new(db.pool.clone());
+
+    let cpf = format!("999{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+    let old_phone = format!("119{:08}", Uuid::new_v4().as_u128() % 100_000_000);
+    let first: WorkApiCompleteResponse = serde_json::json!({
+        "DadosBasicos": { "nome": "Test Existing Party" },
+        "telefones": [{ "telefone": old_phone, "whatsapp": "NAO" }]
+    });
+    let party_id = storage
+        .store_enriched_person_with_lead(&cpf, &first, Some("first-lead"))
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store enriched person: {e}"))?;
+
+    let new_phone = format!("219{:08}", Uuid::new_v4().as_u128() % 100_000_000);
+    let failing: WorkApiCompleteResponse = serde_json::json!({
+        "DadosBasicos": { "nome": "Test Existing Party" },
+        "telefones": [{ "telefone": new_phone, "whatsapp": "NAO" }],
+        "enderecos": [{ "logradouro": "Rua Teste", "cidade": "Rio de Janeiro", "uf": "RJ", "confianca": 5.0 }]
+    });
+    let result = storage
+        .store_enriched_person_with_lead(&cpf, &failing, Some("second-lead"))
+        .await;
+    assert!(result.is_err());
+
+    let contacts: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT value, valid_to IS NULL FROM core.party_contacts WHERE party_id = $1 ORDER BY value",
+    )
+    .bind(party_id)
+    .fetch_all(&db.pool)
+    .await?;
+    assert_eq!(contacts, vec![(old_phone, true)]);
+
+    let (lead_id,): (Option<String>,) = sqlx::query_as(
+        "SELECT raw_payload->>'lead_id' FROM core.party_enrichments WHERE party_id = $1",
+    )
+    .bind(party_id)
+    .fetch_one(&db.pool)
+    .await?;
+    assert_eq!(lead_id.as_deref(), Some("first-lead"));
+    Ok(())
+}
+
+/// A party stored under a formatted CPF is merged into the digits-only one:
+/// contacts are combined or moved, the newer snapshot is kept and the merge
+/// is recorded.
+#[tokio::test]
+#[ignore]
+async fn merge_parties_consolidates_formatted_cpf_duplicate() -> anyhow::Result<()> {
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let storage = EnrichmentStorage::new(db.pool.clone());
+
+    let cpf = format!("999{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+    let formatted = format!("{}.{}.{}-{}", &cpf[..3], &cpf[3..6], &cpf[6..9], &cpf[9..]);
+    let shared_phone = format!("119{:08}", Uuid::new_v4().as_u128() % 100_000_000);
+    let other_phone = format!("219{:08}", Uuid::new_v4().as_u128() % 100_000_000);
+
+    let survivor_id = storage
+        .store_enriched_person_with_lead(
+            &cpf,
+            &serde_json::json!({
+                "DadosBasicos": { "nome": "Test Merge Party" },
+                "telefones": [{ "telefone": shared_phone, "whatsapp": "NAO" }]
+            }),
+            Some("older-lead"),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store survivor: {e}"))?;
+    let merged_id = storage
+        .store_enriched_person_with_lead(
+            &formatted,
+            &serde_json::json!({
+                "DadosBasicos": { "nome": "Test Merge Party" },
+                "telefones": [
+                    { "telefone": shared_phone, "whatsapp": "NAO" },
+                    { "telefone": other_phone, "whatsapp": "NAO" }
+                ]
+            }),
+            Some("newer-lead"),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store duplicate: {e}"))?;
+    assert_ne!(survivor_id, merged_id);
+
+    let merges = storage
+        .merge_parties(survivor_id, &[merged_id], Some("test"), "admin_token")
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to merge parties: {e}"))?;
+    assert_eq!(merges.len(), 1);
+    assert_eq!(merges[0].moved.contacts_combined, 1);
+    assert_eq!(merges[0].moved.contacts_moved, 1);
+    assert!(merges[0].moved.enrichment_replaced);
+
+    let phones: Vec<(String,)> =
+        sqlx::query_as("SELECT value FROM core.party_contacts WHERE party_id = $1 ORDER BY value")
+            .bind(survivor_id)
+            .fetch_all(&db.pool)
+            .await?;
+    let mut expected = vec![(shared_phone,), (other_phone,)];
+    expected.sort();
+    assert_eq!(phones, expected);
+
+    let (lead_id,): (Option<String>,) = sqlx::query_as(
+        "SELECT raw_payload->>'lead_id' FROM core.party_enrichments WHERE party_id = $1",
+    )
+    .bind(survivor_id)
+    .fetch_one(&db.pool)
+    .await?;
+    assert_eq!(lead_id.as_deref(), Some("newer-lead"));
+
+    let (remaining,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM core.parties WHERE id = $1")
+        .bind(merged_id)
+        .fetch_one(&db.pool)
+        .await?;
+    assert_eq!(remaining, 0);
+    let (recorded,): (Option<String>,) =
+        sqlx::query_as("SELECT merged_document FROM core.party_merges WHERE merged_id = $1")
+            .bind(merged_id)
+            .fetch_one(&db.pool)
+            .await?;
+    assert_eq!(recorded, Some(formatted));
+    Ok(())
+}
+
+/// The party is tagged with the UF of its address (case-insensitive) and
+/// listed under that UF only.
+#[tokio::test]
+#[ignore]
+async fn store_enriched_person_tags_uf_from_address() -> anyhow::Result<()> {
+    let db_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .context("Set TEST_DATABASE_URL or DATABASE_URL to run this test")?;
+
+    let db = Database::new(&db_url)
+        .await
+        .context("failed to create database pool")?;
+    let storage = EnrichmentStorage::new(db.pool.clone());
+
+    let cpf = format!("999{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+    let party_id = storage
+        .store_enriched_person_with_lead(
+            &cpf,
+            &serde_json::json!({
+                "DadosBasicos": { "nome": "Test Residency Party" },
+                "enderecos": [{ "logradouro": "Rua Teste", "cidade": "Curitiba", "uf": "pr" }]
+            }),
+            Some("residency-lead"),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to store enriched person: {e}"))?;
+
+    let (uf,): (Option<String>,) = sqlx::query_as("SELECT uf FROM core.parties WHERE id = $1")
+        .bind(party_id)
+        .fetch_one(&db.pool)
+        .await?;
+    assert_eq!(uf.as_deref(), Some("PR"));
+
+    // Page starting right before the party, so it comes first if listed
+    let after = Uuid::from_u128(party_id.as_u128().saturating_sub(1));
+    let listed = rust_c2s_api::residency::list_parties(&db.pool, &["PR"], Some(after), 1)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to list parties: {e}"))?;
+    assert_eq!(listed.first().map(|p| p.id), Some(party_id));
+    let listed = rust_c2s_api::residency::list_parties(&db.pool, &["SC"], Some(after), 1)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to list parties: {e}"))?;
+    assert_ne!(listed.first().map(|p| p.id), Some(party_id));
+    Ok(())
+}
@@ -0,0 +1,88 @@
+/// Full enrichment workflow against the testsupport provider fakes
+/// Diretrix lookup → Work API enrichment → C2S lead creation and message
+use rust_c2s_api::enrichment::find_cpf_via_diretrix;
+use rust_c2s_api::services::{C2SService, DiretrixService, WorkApiService};
+use rust_c2s_api::testsupport::{
+    c2s_webhook_event, work_api_person, FakeProviders, GoogleAdsPayloadBuilder,
+};
+
+const CPF: &str = "52998224725";
+const NAME: &str = "João da Silva";
+const EMAIL: &str = "joao.silva@example.com";
+
+#[tokio::test]
+async fn test_workflow_against_fake_providers() {
+    let fakes = FakeProviders::start().await;
+    fakes.diretrix.with_email_match(EMAIL, NAME, CPF).await;
+    fakes.diretrix.with_no_match().await;
+    fakes
+        .work_api
+        .with_person(CPF, work_api_person(CPF, NAME))
+        .await;
+    fakes.c2s.accepts_leads("c2s-lead-1").await;
+    fakes.c2s.accepts_messages().await;
+    let config = fakes.config();
+
+    let diretrix = DiretrixService::new(&config);
+    let lookup = find_cpf_via_diretrix(
+        Some(NAME),
+        None,
+        Some(EMAIL),
+        &diretrix,
+        config.identity_match_threshold,
+    )
+    .await
+    .expect("lookup");
+    assert_eq!(lookup.cpfs, vec![CPF.to_string()]);
+
+    let work_data = WorkApiService::new(&config)
+        .fetch_all_modules(CPF)
+        .await
+        .expect("work api");
+    assert_eq!(work_data["DadosBasicos"]["nome"], NAME);
+    assert_eq!(fakes.work_api.lookup_count().await, 1);
+
+    let c2s = C2SService::new(&config);
+    let lead_id = c2s
+        .create_lead(
+            NAME,
+            None,
+            Some(EMAIL),
+            "Lead enriquecido",
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("create lead");
+    assert_eq!(lead_id, "c2s-lead-1");
+    c2s.send_message(&lead_id, "Dados enriquecidos")
+        .await
+        .expect("send message");
+
+    let created = fakes.c2s.created_leads().await;
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0]["email"], EMAIL);
+    assert_eq!(
+        fakes.c2s.sent_messages().await,
+        vec![("c2s-lead-1".to_string(), "Dados enriquecidos".to_string())]
+    );
+}
+
+#[test]
+fn test_payload_fixtures() {
+    let payload = GoogleAdsPayloadBuilder::new("lead-1")
+        .full_name(NAME)
+        .email(EMAIL)
+        .cpf("529.982.247-25")
+        .build();
+    assert_eq!(payload.get_name().as_deref(), Some(NAME));
+    assert_eq!(payload.get_email().as_deref(), Some(EMAIL));
+    assert_eq!(payload.get_cpf().as_deref(), Some(CPF));
+    assert!(!payload.is_test);
+
+    let event = c2s_webhook_event("lead-2", NAME, Some(EMAIL), None);
+    let customer = event.attributes.customer.expect("customer");
+    assert_eq!(customer.name.as_deref(), Some(NAME));
+    assert_eq!(event.hook_action.as_deref(), Some("lead.created"));
+}
@@ -0,0 +1,96 @@
+//! Parser corpus: every Work API fixture under tests/fixtures/work_api
+//!
+//! Fixtures are anonymized real responses recorded by `work_api_fixtures`
+//! (see `GET /api/v1/admin/work-api-fixtures`). Each one must go through
+//! every parser of the Work API payload without panicking.
+
+use rust_c2s_api::consistency::PersonFields;
+use rust_c2s_api::enrichment::{format_enriched_message_body, EnrichedLeadSummary};
+use rust_c2s_api::work_api_fixtures::{anonymize, FIXTURE_CPF};
+use serde_json::Value;
+use std::path::Path;
+
+fn fixtures() -> Vec<(String, Value)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/work_api");
+    let mut fixtures: Vec<(String, Value)> = std::fs::read_dir(&dir)
+        .expect("fixture directory exists")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let body = std::fs::read_to_string(&path).unwrap();
+            let payload = serde_json::from_str(&body)
+                .unwrap_or_else(|e| panic!("{}: invalid JSON: {}", path.display(), e));
+            (
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                payload,
+            )
+        })
+        .collect();
+    fixtures.sort_by(|a, b| a.0.cmp(&b.0));
+    fixtures
+}
+
+#[test]
+fn test_corpus_is_not_empty() {
+    assert!(fixtures().len() >= 2);
+}
+
+#[test]
+fn test_fixtures_hold_no_personal_data() {
+    for (name, payload) in fixtures() {
+        assert_eq!(
+            anonymize(&payload, FIXTURE_CPF),
+            payload,
+            "{} is not anonymized",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_fixtures_format_into_messages() {
+    for (name, payload) in fixtures() {
+        let same = format_enriched_message_body(
+            "Cliente",
+            "11987654321",
+            "cliente@example.com",
+            std::slice::from_ref(&payload),
+            true,
+        );
+        assert!(!same.is_empty(), "{}: empty message", name);
+
+        let different = format_enriched_message_body(
+            "Cliente",
+            "11987654321",
+            "cliente@example.com",
+            &[payload.clone(), Value::Null],
+            false,
+        );
+        assert!(different.contains("PESSOA 1"), "{}", name);
+    }
+}
+
+#[test]
+fn test_fixtures_feed_summary_and_checks() {
+    for (name, payload) in fixtures() {
+        let summary = EnrichedLeadSummary::from_work_data(
+            "lead-fixture",
+            "Cliente",
+            Some("11987654321"),
+            None,
+            Some(FIXTURE_CPF),
+            &payload,
+        );
+        assert_eq!(summary.lead_id, "lead-fixture", "{}", name);
+
+        let fields = PersonFields::from_work_data(&payload);
+        let comparison = rust_c2s_api::shadow::compare(&payload, &payload);
+        assert!(comparison.mismatches.is_empty(), "{}", name);
+        if payload
+            .pointer("/DadosBasicos/nome")
+            .is_some_and(Value::is_string)
+        {
+            assert!(fields.name.is_some(), "{}", name);
+        }
+    }
+}